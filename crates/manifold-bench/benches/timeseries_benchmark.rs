@@ -1,19 +1,25 @@
 //! Time series domain benchmark for Manifold
 //!
 //! Tests manifold-timeseries performance characteristics:
-//! - Raw data ingestion rate (absolute vs delta encoding)
+//! - Raw data ingestion rate (absolute vs delta encoding, f32 vs f64 values)
 //! - Range query performance across different time windows
-//! - Downsampling throughput (raw → minute → hour → day)
+//! - Downsampling throughput (raw → minute → hour → day, f32 vs f64 values)
 //! - Multi-series concurrent writes
-//! - Retention policy execution speed
+//! - Retention policy execution speed, and range deletes against per-key removal
 //! - Sustained high-volume stress tests
+//! - Single-series bulk writes (write_batch vs write_series_batch)
+//! - Cold-cache range scans with and without a sequential read hint
+//! - Single-point commits, opening only the raw table or every internal table
 //!
 //! Domain optimization benchmarks - Phase 3: Time Series
 
 use manifold::column_family::ColumnFamilyDatabase;
+use manifold::{ReadHint, ReadableTable, TableDefinition};
 use manifold_timeseries::{
-    AbsoluteEncoding, DeltaEncoding, Granularity, TimeSeriesTable, TimeSeriesTableRead,
+    AbsoluteEncoding, DeltaEncoding, FixedWidthValue, Granularity, TimeSeriesTable,
+    TimeSeriesTableRead,
 };
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
@@ -86,23 +92,36 @@ fn generate_data_points(count: usize, base_time: u64, interval_ms: u64) -> Vec<(
     points
 }
 
-/// Benchmark: Raw data ingestion with AbsoluteEncoding
-fn benchmark_absolute_encoding_write(num_points: usize, batch_size: usize) -> Duration {
+/// Converts generated data points to the value type `V`
+fn convert_data_points<V: FixedWidthValue>(
+    points: Vec<(String, u64, f32)>,
+) -> Vec<(String, u64, V)> {
+    points
+        .into_iter()
+        .map(|(s, t, v)| (s, t, V::from_f64(f64::from(v))))
+        .collect()
+}
+
+/// Benchmark: Raw data ingestion with AbsoluteEncoding and values of type `V`
+fn benchmark_absolute_encoding_write<V: FixedWidthValue>(
+    num_points: usize,
+    batch_size: usize,
+) -> Duration {
     let tmpfile = NamedTempFile::new().unwrap();
     let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
     let cf = db.column_family_or_create("metrics").unwrap();
 
     let base_time = current_timestamp();
-    let points = generate_data_points(num_points, base_time, 1000);
+    let points = convert_data_points::<V>(generate_data_points(num_points, base_time, 1000));
 
     let start = Instant::now();
 
     for chunk in points.chunks(batch_size) {
         let txn = cf.begin_write().unwrap();
         {
-            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding, V>::open(&txn, "data").unwrap();
 
-            let batch: Vec<(&str, u64, f32)> =
+            let batch: Vec<(&str, u64, V)> =
                 chunk.iter().map(|(s, t, v)| (s.as_str(), *t, *v)).collect();
 
             ts.write_batch(&batch, false).unwrap();
@@ -202,22 +221,84 @@ fn benchmark_range_query(num_points: usize, range_hours: u64) -> Duration {
     elapsed
 }
 
-/// Benchmark: Downsampling performance
-fn benchmark_downsampling(num_raw_points: usize) -> Duration {
+/// Drops the database file from the OS page cache, so that the next scan reads from disk
+#[cfg(target_os = "linux")]
+fn drop_page_cache(path: &Path) {
+    use std::os::fd::AsRawFd;
+
+    let file = std::fs::File::open(path).unwrap();
+    file.sync_all().unwrap();
+    let result = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+    assert_eq!(result, 0);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn drop_page_cache(_path: &Path) {}
+
+/// Writes a week of per-minute points for `num_series` series, returning the time range
+fn populate_week(path: &Path, num_series: usize) -> (u64, u64) {
+    const WEEK_MINUTES: u64 = 7 * 24 * 60;
+
+    let base_time = current_timestamp();
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let cf = db.column_family_or_create("metrics").unwrap();
+    let series: Vec<String> = (0..num_series).map(|i| format!("series_{i}")).collect();
+
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+        for minute in (0..WEEK_MINUTES).step_by(60) {
+            let batch: Vec<(&str, u64, f32)> = (minute..minute + 60)
+                .flat_map(|m| {
+                    series
+                        .iter()
+                        .map(move |s| (s.as_str(), base_time + m * 60_000, m as f32))
+                })
+                .collect();
+            ts.write_batch(&batch, false).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    (base_time, base_time + WEEK_MINUTES * 60_000)
+}
+
+/// Benchmark: Scan one series over the whole range, starting from a cold cache
+fn benchmark_cold_range_scan(path: &Path, range: (u64, u64), hint: ReadHint) -> (Duration, usize) {
+    drop_page_cache(path);
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let cf = db.column_family("metrics").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+
+    let start = Instant::now();
+    let mut count = 0;
+    for result in ts
+        .range_with_hint("series_0", range.0, range.1, hint)
+        .unwrap()
+    {
+        let (_timestamp, _value) = result.unwrap();
+        count += 1;
+    }
+    (start.elapsed(), count)
+}
+
+/// Benchmark: Downsampling performance with values of type `V`
+fn benchmark_downsampling<V: FixedWidthValue>(num_raw_points: usize) -> Duration {
     let tmpfile = NamedTempFile::new().unwrap();
     let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
     let cf = db.column_family_or_create("metrics").unwrap();
 
     let base_time = current_timestamp();
-    let points = generate_data_points(num_raw_points, base_time, 1000);
+    let points = convert_data_points::<V>(generate_data_points(num_raw_points, base_time, 1000));
 
     // Populate raw data
     {
         let txn = cf.begin_write().unwrap();
         {
-            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding, V>::open(&txn, "data").unwrap();
 
-            let batch: Vec<(&str, u64, f32)> = points
+            let batch: Vec<(&str, u64, V)> = points
                 .iter()
                 .map(|(s, t, v)| (s.as_str(), *t, *v))
                 .collect();
@@ -232,7 +313,7 @@ fn benchmark_downsampling(num_raw_points: usize) -> Duration {
 
     let write_txn = cf.begin_write().unwrap();
     {
-        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "data").unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding, V>::open(&write_txn, "data").unwrap();
 
         // Downsample from raw to minute to hour to day
         ts.downsample_to_minute(
@@ -307,6 +388,80 @@ fn benchmark_multi_series_writes(
     elapsed
 }
 
+/// Benchmark: Single-series bulk ingestion, write_batch vs write_series_batch
+fn benchmark_single_series_write(num_points: usize, series_batch: bool) -> Duration {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("metrics").unwrap();
+
+    let base_time = current_timestamp();
+    let points: Vec<(u64, f32)> = (0..num_points)
+        .map(|i| (base_time + i as u64 * 1000, (i % 10000) as f32 / 100.0))
+        .collect();
+
+    let start = Instant::now();
+
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+
+        if series_batch {
+            ts.write_series_batch("series_0", &points, false).unwrap();
+        } else {
+            let batch: Vec<(&str, u64, f32)> =
+                points.iter().map(|(t, v)| ("series_0", *t, *v)).collect();
+            ts.write_batch(&batch, false).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let elapsed = start.elapsed();
+
+    drop(db);
+    std::thread::sleep(Duration::from_millis(50));
+    drop(tmpfile);
+
+    elapsed
+}
+
+/// Benchmark: One point per write transaction
+///
+/// With `open_all`, the handle also uses the aggregate, downsampling progress and label
+/// tables in every transaction, as when `TimeSeriesTable::open` opened them all up front.
+fn benchmark_single_point_commits(num_commits: usize, open_all: bool) -> Duration {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("metrics").unwrap();
+
+    let base_time = current_timestamp();
+    let start = Instant::now();
+
+    for i in 0..num_commits {
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+            if open_all {
+                ts.minute_table().unwrap();
+                ts.hour_table().unwrap();
+                ts.day_table().unwrap();
+                ts.watermark("series_0", Granularity::Minute).unwrap();
+                ts.labels("series_0").unwrap();
+            }
+            ts.write("series_0", base_time + i as u64 * 1000, i as f32)
+                .unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    let elapsed = start.elapsed();
+
+    drop(db);
+    std::thread::sleep(Duration::from_millis(50));
+    drop(tmpfile);
+
+    elapsed
+}
+
 /// Benchmark: Retention policy execution
 fn benchmark_retention_policy(total_points: usize, retention_hours: u64) -> Duration {
     let tmpfile = NamedTempFile::new().unwrap();
@@ -355,6 +510,66 @@ fn benchmark_retention_policy(total_points: usize, retention_hours: u64) -> Dura
     elapsed
 }
 
+/// Benchmark: Deleting every raw point in a time range, either through `delete_range` or by
+/// collecting the keys and removing them one by one
+fn benchmark_range_delete(total_points: usize, per_key: bool) -> Duration {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("metrics").unwrap();
+
+    let base_time = current_timestamp();
+    let points = generate_data_points(total_points, base_time, 1000);
+    let end_time = points.iter().map(|(_, t, _)| *t).max().unwrap() + 1;
+
+    // Populate data
+    {
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&txn, "data").unwrap();
+
+            let batch: Vec<(&str, u64, f32)> = points
+                .iter()
+                .map(|(s, t, v)| (s.as_str(), *t, *v))
+                .collect();
+
+            ts.write_batch(&batch, false).unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    let start = Instant::now();
+
+    let write_txn = cf.begin_write().unwrap();
+    let deleted = if per_key {
+        let def: TableDefinition<(u64, &str), f32> = TableDefinition::new("data_raw");
+        let mut raw = write_txn.open_table(def).unwrap();
+        let mut keys = Vec::new();
+        for item in raw.range((base_time, "")..(end_time, "")).unwrap() {
+            let (key_guard, _) = item.unwrap();
+            let (timestamp, series_id) = key_guard.value();
+            keys.push((timestamp, series_id.to_string()));
+        }
+        for (timestamp, series_id) in &keys {
+            raw.remove((*timestamp, series_id.as_str())).unwrap();
+        }
+        keys.len()
+    } else {
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "data").unwrap();
+        ts.delete_range(Granularity::Raw, base_time, end_time)
+            .unwrap()
+    };
+    write_txn.commit().unwrap();
+
+    let elapsed = start.elapsed();
+    assert_eq!(deleted, total_points);
+
+    drop(db);
+    std::thread::sleep(Duration::from_millis(50));
+    drop(tmpfile);
+
+    elapsed
+}
+
 /// Benchmark: Sustained write stress test
 fn benchmark_sustained_writes(
     duration_secs: u64,
@@ -558,7 +773,7 @@ fn main() {
         // Absolute encoding
         let mut abs_durations = Vec::new();
         for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
-            let duration = benchmark_absolute_encoding_write(count, 1000);
+            let duration = benchmark_absolute_encoding_write::<f32>(count, 1000);
             if i >= WARMUP_ITERATIONS {
                 abs_durations.push(duration);
             }
@@ -566,6 +781,17 @@ fn main() {
         let avg_abs = abs_durations.iter().sum::<Duration>() / abs_durations.len() as u32;
         print_result(&format!("{} points (Absolute)", count), avg_abs, count);
 
+        // Absolute encoding with f64 values
+        let mut f64_durations = Vec::new();
+        for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+            let duration = benchmark_absolute_encoding_write::<f64>(count, 1000);
+            if i >= WARMUP_ITERATIONS {
+                f64_durations.push(duration);
+            }
+        }
+        let avg_f64 = f64_durations.iter().sum::<Duration>() / f64_durations.len() as u32;
+        print_result(&format!("{} points (Absolute, f64)", count), avg_f64, count);
+
         // Delta encoding
         let mut delta_durations = Vec::new();
         for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
@@ -611,11 +837,14 @@ fn main() {
 
     for &count in &[1000, 5000, 10000] {
         let mut durations = Vec::new();
+        let mut f64_durations = Vec::new();
 
         for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
-            let duration = benchmark_downsampling(count);
+            let duration = benchmark_downsampling::<f32>(count);
+            let f64_duration = benchmark_downsampling::<f64>(count);
             if i >= WARMUP_ITERATIONS {
                 durations.push(duration);
+                f64_durations.push(f64_duration);
             }
         }
 
@@ -625,6 +854,12 @@ fn main() {
             avg_duration,
             count,
         );
+        let avg_f64 = f64_durations.iter().sum::<Duration>() / f64_durations.len() as u32;
+        print_result(
+            &format!("{} raw points (3 levels, f64)", count),
+            avg_f64,
+            count,
+        );
     }
 
     // 4. Multi-Series Concurrent Writes
@@ -679,6 +914,30 @@ fn main() {
         );
     }
 
+    for per_key in [true, false] {
+        let points = 100_000;
+        let mut durations = Vec::new();
+
+        for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+            let duration = benchmark_range_delete(points, per_key);
+            if i >= WARMUP_ITERATIONS {
+                durations.push(duration);
+            }
+        }
+
+        let avg_duration = durations.iter().sum::<Duration>() / durations.len() as u32;
+        let method = if per_key {
+            "per-key remove"
+        } else {
+            "delete_range"
+        };
+        print_result(
+            &format!("{} points, {}", points, method),
+            avg_duration,
+            points,
+        );
+    }
+
     // 6. Sustained Write Stress Test
     print_section("6. Sustained Write Stress Test (30 seconds)");
     println!(
@@ -726,6 +985,85 @@ fn main() {
         );
     }
 
+    // 8. Single-Series Bulk Writes
+    print_section("8. Single-Series Bulk Writes (100K points)");
+    println!("  {:<50} {:>12}  {:>15}", "Test", "Duration", "Throughput");
+    println!("  {}", "-".repeat(80));
+
+    let count = 100_000;
+    let mut averages = Vec::new();
+    for (label, series_batch) in [("write_batch", false), ("write_series_batch", true)] {
+        let mut durations = Vec::new();
+        for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+            let duration = benchmark_single_series_write(count, series_batch);
+            if i >= WARMUP_ITERATIONS {
+                durations.push(duration);
+            }
+        }
+        let avg_duration = durations.iter().sum::<Duration>() / durations.len() as u32;
+        print_result(label, avg_duration, count);
+        averages.push(avg_duration);
+    }
+    println!(
+        "  Speedup: {:.2}x",
+        averages[0].as_secs_f64() / averages[1].as_secs_f64()
+    );
+
+    // 9. Cold-Cache Range Scans
+    print_section("9. Cold-Cache Range Scan (100 series, 1 week per-minute)");
+    if cfg!(not(target_os = "linux")) {
+        println!("  Page cache is not dropped on this platform, so scans run warm");
+    }
+    println!("  {:<50} {:>12}  {:>15}", "Test", "Duration", "Throughput");
+    println!("  {}", "-".repeat(80));
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let range = populate_week(tmpfile.path(), 100);
+    for (label, hint) in [
+        ("ReadHint::Normal", ReadHint::Normal),
+        ("ReadHint::Sequential", ReadHint::Sequential),
+        (
+            "ReadHint::SequentialWindow(256 KiB)",
+            ReadHint::SequentialWindow(256 * 1024),
+        ),
+    ] {
+        let mut durations = Vec::new();
+        let mut count = 0;
+        for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+            let (duration, points) = benchmark_cold_range_scan(tmpfile.path(), range, hint);
+            count = points;
+            if i >= WARMUP_ITERATIONS {
+                durations.push(duration);
+            }
+        }
+        let avg_duration = durations.iter().sum::<Duration>() / durations.len() as u32;
+        print_result(label, avg_duration, count);
+    }
+
+    // 10. Single-Point Commits
+    print_section("10. Single-Point Commits (1K transactions)");
+    println!("  {:<50} {:>12}  {:>15}", "Test", "Duration", "Throughput");
+    println!("  {}", "-".repeat(80));
+
+    let commits = 1000;
+    let mut averages = Vec::new();
+    for (label, open_all) in [("Every table opened", true), ("Raw table only", false)] {
+        let mut durations = Vec::new();
+        for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+            let duration = benchmark_single_point_commits(commits, open_all);
+            if i >= WARMUP_ITERATIONS {
+                durations.push(duration);
+            }
+        }
+        let avg_duration = durations.iter().sum::<Duration>() / durations.len() as u32;
+        print_result(label, avg_duration, commits);
+        averages.push(avg_duration);
+    }
+    println!(
+        "  Speedup: {:.2}x",
+        averages[0].as_secs_f64() / averages[1].as_secs_f64()
+    );
+
     println!("\n{}", "=".repeat(80));
     println!("BENCHMARK COMPLETE");
     println!("{}", "=".repeat(80));
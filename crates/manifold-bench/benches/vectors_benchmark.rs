@@ -4,13 +4,16 @@
 //! - Dense vector write throughput (128/384/768 dimensions)
 //! - Zero-copy read performance (guard vs traditional deserialization)
 //! - Distance computation throughput (cosine, euclidean, dot product)
+//! - Accelerated distance functions against their scalar fallbacks (128/384/768 dimensions)
+//! - Bulk scoring of candidate keys against a manual get-and-score loop
+//! - Sharded parallel scans against a single-threaded full scan
 //! - Batch insert operations with varying sizes
 //! - Sustained high-volume stress tests
 //!
 //! Domain optimization benchmarks - Phase 1: Vectors
 
 use manifold::column_family::ColumnFamilyDatabase;
-use manifold_vectors::{VectorTable, VectorTableRead, distance};
+use manifold_vectors::{Metric, VectorTable, VectorTableRead, distance};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
@@ -264,6 +267,152 @@ fn benchmark_distance_computation<const DIM: usize>(
     start.elapsed()
 }
 
+/// Benchmark: Accelerated distance function against its scalar fallback
+///
+/// Returns the time taken by each for `num_comparisons` in-memory comparisons.
+fn benchmark_distance_speedup<const DIM: usize>(
+    num_comparisons: usize,
+    distance_fn: &str,
+) -> (Duration, Duration) {
+    let (accelerated, scalar): (fn(&[f32], &[f32]) -> f32, fn(&[f32], &[f32]) -> f32) =
+        match distance_fn {
+            "cosine" => (distance::cosine, distance::scalar::cosine),
+            "euclidean" => (distance::euclidean, distance::scalar::euclidean),
+            "dot" => (distance::dot_product, distance::scalar::dot_product),
+            "manhattan" => (distance::manhattan, distance::scalar::manhattan),
+            _ => panic!("Unknown distance function"),
+        };
+
+    let mut query = random_vector::<DIM>(99999);
+    normalize(&mut query);
+    let candidates: Vec<[f32; DIM]> = (0..1000)
+        .map(|i| {
+            let mut vector = random_vector::<DIM>(i);
+            normalize(&mut vector);
+            vector
+        })
+        .collect();
+
+    let time = |f: fn(&[f32], &[f32]) -> f32| {
+        let start = Instant::now();
+        for i in 0..num_comparisons {
+            std::hint::black_box(f(&query, &candidates[i % candidates.len()]));
+        }
+        start.elapsed()
+    };
+    (time(accelerated), time(scalar))
+}
+
+/// Benchmark: Bulk scoring of candidate keys against a manual get-and-score loop
+///
+/// Scores `num_candidates` keys, picked at random from a table of `num_vectors`, and returns
+/// the time taken by `score_keys` and by the manual loop.
+fn benchmark_score_keys<const DIM: usize>(
+    num_vectors: usize,
+    num_candidates: usize,
+    metric: Metric,
+) -> (Duration, Duration) {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let mut keys = Vec::new();
+    {
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut vectors = VectorTable::<DIM>::open(&txn, "embeddings").unwrap();
+            let items: Vec<_> = (0..num_vectors)
+                .map(|i| (Uuid::new_v4(), random_vector::<DIM>(i as u64)))
+                .collect();
+            vectors.insert_batch(&items, false).unwrap();
+            keys.extend(items.iter().map(|(key, _)| *key));
+        }
+        txn.commit().unwrap();
+    }
+
+    // Candidates in the arbitrary order an external index would return them
+    let candidates: Vec<Uuid> = (0..num_candidates)
+        .map(|i| keys[(i * 7919) % keys.len()])
+        .collect();
+    let query = random_vector::<DIM>(99999);
+
+    let txn = cf.begin_read().unwrap();
+    let vectors = VectorTableRead::<DIM>::open(&txn, "embeddings").unwrap();
+
+    let start = Instant::now();
+    let scores = vectors.score_keys(&candidates, &query, metric).unwrap();
+    std::hint::black_box(scores);
+    let bulk = start.elapsed();
+
+    let start = Instant::now();
+    let scores: Vec<Option<f32>> = candidates
+        .iter()
+        .map(|key| {
+            vectors
+                .get(key)
+                .unwrap()
+                .map(|guard| metric.score(&query, guard.value()))
+        })
+        .collect();
+    std::hint::black_box(scores);
+    let manual = start.elapsed();
+
+    (bulk, manual)
+}
+
+/// Benchmark: Full scan split into shards scanned on `threads` threads, against a
+/// single-threaded scan doing the same work per vector
+fn benchmark_parallel_scan<const DIM: usize>(
+    num_vectors: usize,
+    threads: usize,
+) -> (Duration, Duration) {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    {
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut vectors = VectorTable::<DIM>::open(&txn, "embeddings").unwrap();
+            let items: Vec<_> = (0..num_vectors)
+                .map(|i| (Uuid::new_v4(), random_vector::<DIM>(i as u64)))
+                .collect();
+            vectors.insert_batch(&items, false).unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    let txn = cf.begin_read().unwrap();
+    let vectors = VectorTableRead::<DIM>::open(&txn, "embeddings").unwrap();
+    let query = random_vector::<DIM>(99999);
+    let scan = |iter: manifold_vectors::dense::VectorIter<'_, DIM>| {
+        iter.map(|result| {
+            let (_key, guard) = result.unwrap();
+            distance::dot_product(&query, guard.value())
+        })
+        .sum::<f32>()
+    };
+
+    let start = Instant::now();
+    std::hint::black_box(scan(vectors.all_vectors().unwrap()));
+    let sequential = start.elapsed();
+
+    let start = Instant::now();
+    let shards = vectors.par_chunks(threads).unwrap();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = shards
+            .into_iter()
+            .map(|shard| scope.spawn(|| scan(shard)))
+            .collect();
+        for handle in handles {
+            std::hint::black_box(handle.join().unwrap());
+        }
+    });
+    let parallel = start.elapsed();
+
+    (sequential, parallel)
+}
+
 /// Benchmark: Sustained write stress test
 fn benchmark_sustained_writes<const DIM: usize>(
     duration_secs: u64,
@@ -602,8 +751,79 @@ fn main() {
         );
     }
 
-    // 6. Sustained Write Stress Test
-    print_section("6. Sustained Write Stress Test (30 seconds)");
+    // 6. Accelerated vs Scalar Distance Functions
+    print_section("6. Accelerated vs Scalar Distance Functions (1M comparisons)");
+    println!("  {:<50} {:>12}  {:>15}", "Test", "Accelerated", "Speedup");
+    println!("  {}", "-".repeat(80));
+
+    for &dim in &[128, 384, 768] {
+        for distance_fn in &["cosine", "euclidean", "dot", "manhattan"] {
+            let num_comparisons = 1_000_000;
+            let mut accelerated_durations = Vec::new();
+            let mut scalar_durations = Vec::new();
+
+            for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+                let (accelerated, scalar) = match dim {
+                    128 => benchmark_distance_speedup::<128>(num_comparisons, distance_fn),
+                    384 => benchmark_distance_speedup::<384>(num_comparisons, distance_fn),
+                    768 => benchmark_distance_speedup::<768>(num_comparisons, distance_fn),
+                    _ => unreachable!(),
+                };
+
+                if i >= WARMUP_ITERATIONS {
+                    accelerated_durations.push(accelerated);
+                    scalar_durations.push(scalar);
+                }
+            }
+
+            let accelerated = accelerated_durations.iter().sum::<Duration>();
+            let scalar = scalar_durations.iter().sum::<Duration>();
+            println!(
+                "  {:<50} {:>12}  {:>14.2}x",
+                format!("{}-dim {}", dim, distance_fn),
+                format_duration(accelerated / accelerated_durations.len() as u32),
+                scalar.as_secs_f64() / accelerated.as_secs_f64()
+            );
+        }
+    }
+
+    // 7. Bulk Scoring vs Manual Loop
+    print_section("7. Bulk Scoring vs Manual Loop (768-dim, 20K vectors)");
+    println!("  {:<50} {:>12}  {:>15}", "Test", "score_keys", "Speedup");
+    println!("  {}", "-".repeat(80));
+
+    for &num_candidates in &[100, 1000, 5000] {
+        for (name, metric) in [
+            ("cosine", Metric::Cosine),
+            ("euclidean", Metric::Euclidean),
+            ("dot", Metric::DotProduct),
+            ("manhattan", Metric::Manhattan),
+        ] {
+            let mut bulk_durations = Vec::new();
+            let mut manual_durations = Vec::new();
+
+            for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+                let (bulk, manual) = benchmark_score_keys::<768>(20_000, num_candidates, metric);
+
+                if i >= WARMUP_ITERATIONS {
+                    bulk_durations.push(bulk);
+                    manual_durations.push(manual);
+                }
+            }
+
+            let bulk = bulk_durations.iter().sum::<Duration>();
+            let manual = manual_durations.iter().sum::<Duration>();
+            println!(
+                "  {:<50} {:>12}  {:>14.2}x",
+                format!("{} × {} candidates", name, num_candidates),
+                format_duration(bulk / bulk_durations.len() as u32),
+                manual.as_secs_f64() / bulk.as_secs_f64()
+            );
+        }
+    }
+
+    // 8. Sustained Write Stress Test
+    print_section("8. Sustained Write Stress Test (30 seconds)");
     println!(
         "  {:<50} {:>12}  {:>15}",
         "Configuration", "Duration", "Throughput"
@@ -623,8 +843,8 @@ fn main() {
         );
     }
 
-    // 7. Mixed Read/Write Workload
-    print_section("7. Mixed Read/Write Workload (5 seconds, 768-dim)");
+    // 9. Mixed Read/Write Workload
+    print_section("9. Mixed Read/Write Workload (5 seconds, 768-dim)");
     println!(
         "  {:<50} {:>12}  {:>15}",
         "Configuration", "Total Ops", "Throughput"
@@ -651,6 +871,34 @@ fn main() {
         );
     }
 
+    // 10. Sharded Parallel Scan
+    print_section("10. Sharded Parallel Scan vs Full Scan (768-dim, 200K vectors)");
+    println!("  {:<50} {:>12}  {:>15}", "Test", "par_chunks", "Speedup");
+    println!("  {}", "-".repeat(80));
+
+    for &threads in &[2, 4, 8] {
+        let mut sequential_durations = Vec::new();
+        let mut parallel_durations = Vec::new();
+
+        for i in 0..WARMUP_ITERATIONS + BENCHMARK_ITERATIONS {
+            let (sequential, parallel) = benchmark_parallel_scan::<768>(200_000, threads);
+
+            if i >= WARMUP_ITERATIONS {
+                sequential_durations.push(sequential);
+                parallel_durations.push(parallel);
+            }
+        }
+
+        let sequential = sequential_durations.iter().sum::<Duration>();
+        let parallel = parallel_durations.iter().sum::<Duration>();
+        println!(
+            "  {:<50} {:>12}  {:>14.2}x",
+            format!("{} threads", threads),
+            format_duration(parallel / parallel_durations.len() as u32),
+            sequential.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+
     println!("\n{}", "=".repeat(80));
     println!("BENCHMARK COMPLETE");
     println!("{}", "=".repeat(80));
@@ -249,6 +249,38 @@ fn benchmark_wal_concurrency(num_threads: usize) -> Duration {
     start.elapsed()
 }
 
+/// Benchmark: Loading rows with the bulk loader, or with one WAL commit per batch
+fn benchmark_bulk_load(num_rows: u64, bulk: bool) -> Duration {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    db.create_column_family("data", Some(100 * 1024 * 1024))
+        .unwrap();
+
+    let cf = db.column_family("data").unwrap();
+    let data = vec![0u8; 100];
+
+    let start = Instant::now();
+
+    if bulk {
+        cf.bulk_loader(BENCHMARK_TABLE)
+            .load((0..num_rows).map(|key| (key, data.as_slice())))
+            .unwrap();
+    } else {
+        for batch_start in (0..num_rows).step_by(BATCH_SIZE) {
+            let txn = cf.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(BENCHMARK_TABLE).unwrap();
+                for key in batch_start..(batch_start + BATCH_SIZE as u64).min(num_rows) {
+                    table.insert(&key, data.as_slice()).unwrap();
+                }
+            }
+            txn.commit().unwrap();
+        }
+    }
+
+    start.elapsed()
+}
+
 fn main() {
     println!("\nManifold WAL-Specific Benchmark Suite");
     println!("Phase 1, Task 1.6");
@@ -405,10 +437,40 @@ fn main() {
         print_result(&format!("{} threads", num_threads), avg_time, total_ops);
     }
 
+    // ========================================================================
+    // Test 6: Bulk load vs per-batch commits
+    // ========================================================================
+    print_section("Bulk Load (1M rows, 100 byte values)");
+    println!(
+        "  {:<50} {:>12}  {:>15}",
+        "Method", "Duration", "Throughput"
+    );
+    println!("  {}", "-".repeat(80));
+
+    let num_rows = 1_000_000;
+    for (method_name, bulk) in [
+        ("WAL commit every 1000 rows", false),
+        ("BulkLoader (default chunking)", true),
+    ] {
+        // Warmup
+        for _ in 0..WARMUP_ITERATIONS {
+            let _ = benchmark_bulk_load(num_rows, bulk);
+        }
+
+        // Benchmark
+        let mut times = vec![];
+        for _ in 0..BENCHMARK_ITERATIONS {
+            times.push(benchmark_bulk_load(num_rows, bulk));
+        }
+        let avg_time: Duration = times.iter().sum::<Duration>() / times.len() as u32;
+        print_result(method_name, avg_time, num_rows as usize);
+    }
+
     print_section("Benchmark Complete");
     println!("\nKey Findings:");
     println!("- WAL provides significant throughput improvements through group commit");
     println!("- Write latency is consistent with tight percentile spreads");
     println!("- Recovery is fast and ensures data integrity");
-    println!("- Group commit scales well with concurrent writers\n");
+    println!("- Group commit scales well with concurrent writers");
+    println!("- Bulk loads skip the WAL and sync only every few chunks, saving fsyncs\n");
 }
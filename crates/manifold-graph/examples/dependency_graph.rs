@@ -127,13 +127,15 @@ fn topological_sort(
 
         for edge in graph.outgoing_edges(&node)? {
             let edge = edge?;
-            if edge.edge_type == "depends_on" && edge.is_active
-                && let Some(deg) = in_degree.get_mut(&edge.target) {
-                    *deg -= 1;
-                    if *deg == 0 {
-                        queue.push_back(edge.target);
-                    }
+            if edge.edge_type == "depends_on"
+                && edge.is_active
+                && let Some(deg) = in_degree.get_mut(&edge.target)
+            {
+                *deg -= 1;
+                if *deg == 0 {
+                    queue.push_back(edge.target);
                 }
+            }
         }
     }
 
@@ -156,10 +158,9 @@ fn find_dependents(
     while let Some(current) = queue.pop_front() {
         for edge in graph.incoming_edges(&current)? {
             let edge = edge?;
-            if edge.edge_type == "depends_on" && edge.is_active
-                && dependents.insert(edge.source) {
-                    queue.push_back(edge.source);
-                }
+            if edge.edge_type == "depends_on" && edge.is_active && dependents.insert(edge.source) {
+                queue.push_back(edge.source);
+            }
         }
     }
 
@@ -414,10 +415,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ) -> Result<(), Box<dyn std::error::Error>> {
         for edge in graph.outgoing_edges(&pkg_id)? {
             let edge = edge?;
-            if edge.edge_type == "depends_on" && edge.is_active
-                && visited.insert(edge.target) {
-                    collect_all_deps(edge.target, graph, visited)?;
-                }
+            if edge.edge_type == "depends_on" && edge.is_active && visited.insert(edge.target) {
+                collect_all_deps(edge.target, graph, visited)?;
+            }
         }
         Ok(())
     }
@@ -0,0 +1,250 @@
+//! Consistency checks between the forward and reverse tables of a graph.
+//!
+//! Every edge is stored twice, in the forward table keyed by `(source, edge_type, target)` and
+//! in the reverse table keyed by `(target, edge_type, source)`, with the same properties.
+//! [`GraphTable`](crate::GraphTable) always writes both in the same transaction, but tables
+//! written directly, or by a buggy writer, can disagree: traversals then see an edge in one
+//! direction only, or with different properties depending on the direction.
+//!
+//! [`GraphTableRead::verify_consistency`](crate::GraphTableRead::verify_consistency) and
+//! [`GraphTable::verify_consistency`](crate::GraphTable::verify_consistency) report such
+//! disagreements, and [`GraphTable::repair`](crate::GraphTable::repair) resolves them with a
+//! [`RepairStrategy`].
+
+use crate::error::GraphError;
+use crate::vertex::VertexId;
+use manifold::{ReadableTable, Table};
+use uuid::Uuid;
+
+type EdgeKey<'a, V> = (V, &'a str, V);
+type EdgeProperties = (bool, f32, u64, u64);
+
+/// Maximum number of inconsistencies kept in [`ConsistencyReport::samples`].
+pub const MAX_SAMPLES: usize = 100;
+
+/// How an edge differs between the forward and reverse tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InconsistencyKind {
+    /// The edge is in the forward table but not in the reverse table.
+    ForwardOnly,
+    /// The edge is in the reverse table but not in the forward table.
+    ReverseOnly,
+    /// The edge is in both tables, with different properties.
+    Mismatched,
+}
+
+/// An edge that differs between the forward and reverse tables.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Inconsistency<V = Uuid> {
+    /// How the edge differs.
+    pub kind: InconsistencyKind,
+    /// Source vertex of the edge.
+    pub source: V,
+    /// Type of the edge.
+    pub edge_type: String,
+    /// Target vertex of the edge.
+    pub target: V,
+}
+
+/// Result of comparing the forward and reverse tables of a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyReport<V = Uuid> {
+    /// Number of entries in the forward table.
+    pub forward_edges: u64,
+    /// Number of entries in the reverse table.
+    pub reverse_edges: u64,
+    /// Number of edges in the forward table only.
+    pub forward_only: u64,
+    /// Number of edges in the reverse table only.
+    pub reverse_only: u64,
+    /// Number of edges in both tables with different properties.
+    pub mismatched: u64,
+    /// The first [`MAX_SAMPLES`] inconsistencies found, forward table entries first.
+    pub samples: Vec<Inconsistency<V>>,
+}
+
+impl<V> Default for ConsistencyReport<V> {
+    fn default() -> Self {
+        Self {
+            forward_edges: 0,
+            reverse_edges: 0,
+            forward_only: 0,
+            reverse_only: 0,
+            mismatched: 0,
+            samples: Vec::new(),
+        }
+    }
+}
+
+impl<V: Clone> ConsistencyReport<V> {
+    /// Returns `true` if both tables hold the same edges with the same properties.
+    pub fn is_consistent(&self) -> bool {
+        self.forward_only == 0 && self.reverse_only == 0 && self.mismatched == 0
+    }
+
+    fn record(&mut self, inconsistency: &Inconsistency<V>) {
+        match inconsistency.kind {
+            InconsistencyKind::ForwardOnly => self.forward_only += 1,
+            InconsistencyKind::ReverseOnly => self.reverse_only += 1,
+            InconsistencyKind::Mismatched => self.mismatched += 1,
+        }
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(inconsistency.clone());
+        }
+    }
+}
+
+/// How [`GraphTable::repair`](crate::GraphTable::repair) resolves inconsistencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RepairStrategy {
+    /// Make the reverse table match the forward table: edges missing from the reverse table
+    /// are added to it, edges only in the reverse table are removed, and mismatched edges take
+    /// their forward properties.
+    PreferForward,
+    /// Make the forward table match the reverse table: edges missing from the forward table
+    /// are added to it, edges only in the forward table are removed, and mismatched edges take
+    /// their reverse properties.
+    PreferReverse,
+    /// Remove edges present in only one table. Mismatched edges take their forward properties,
+    /// as with [`PreferForward`](Self::PreferForward).
+    DropOrphans,
+}
+
+/// An inconsistency along with the properties stored on each side.
+struct Found<V> {
+    inconsistency: Inconsistency<V>,
+    forward: Option<EdgeProperties>,
+    reverse: Option<EdgeProperties>,
+}
+
+/// Compares two property tuples bit for bit, so that NaN weights compare equal to themselves.
+fn same_properties(a: EdgeProperties, b: EdgeProperties) -> bool {
+    (a.0, a.1.to_bits(), a.2, a.3) == (b.0, b.1.to_bits(), b.2, b.3)
+}
+
+/// Scans both tables, calling `on_found` for every inconsistency, and returns the report.
+fn scan<V: VertexId>(
+    forward: &impl ReadableTable<EdgeKey<'static, V>, EdgeProperties>,
+    reverse: &impl ReadableTable<EdgeKey<'static, V>, EdgeProperties>,
+    mut on_found: impl FnMut(Found<V>),
+) -> Result<ConsistencyReport<V>, GraphError<V>> {
+    let mut report = ConsistencyReport::default();
+
+    for entry in forward.iter()? {
+        let (key, value) = entry?;
+        report.forward_edges += 1;
+        let (source, edge_type, target) = key.value();
+        let properties = value.value();
+        let reverse_properties = reverse
+            .get(&(target, edge_type, source))?
+            .map(|guard| guard.value());
+        let kind = match reverse_properties {
+            None => InconsistencyKind::ForwardOnly,
+            Some(other) if !same_properties(properties, other) => InconsistencyKind::Mismatched,
+            Some(_) => continue,
+        };
+        let found = Found {
+            inconsistency: Inconsistency {
+                kind,
+                source,
+                edge_type: edge_type.to_string(),
+                target,
+            },
+            forward: Some(properties),
+            reverse: reverse_properties,
+        };
+        report.record(&found.inconsistency);
+        on_found(found);
+    }
+
+    for entry in reverse.iter()? {
+        let (key, value) = entry?;
+        report.reverse_edges += 1;
+        let (target, edge_type, source) = key.value();
+        if forward.get(&(source, edge_type, target))?.is_none() {
+            let found = Found {
+                inconsistency: Inconsistency {
+                    kind: InconsistencyKind::ReverseOnly,
+                    source,
+                    edge_type: edge_type.to_string(),
+                    target,
+                },
+                forward: None,
+                reverse: Some(value.value()),
+            };
+            report.record(&found.inconsistency);
+            on_found(found);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Compares the forward and reverse tables.
+pub(crate) fn verify<V: VertexId>(
+    forward: &impl ReadableTable<EdgeKey<'static, V>, EdgeProperties>,
+    reverse: &impl ReadableTable<EdgeKey<'static, V>, EdgeProperties>,
+) -> Result<ConsistencyReport<V>, GraphError<V>> {
+    scan(forward, reverse, |_| {})
+}
+
+/// Compares the forward and reverse tables and resolves every inconsistency with `strategy`,
+/// returning the report from before the repair.
+pub(crate) fn repair<V: VertexId>(
+    forward: &mut Table<'_, EdgeKey<'static, V>, EdgeProperties>,
+    reverse: &mut Table<'_, EdgeKey<'static, V>, EdgeProperties>,
+    strategy: RepairStrategy,
+) -> Result<ConsistencyReport<V>, GraphError<V>> {
+    let mut found = Vec::new();
+    let report = scan(forward, reverse, |inconsistency| found.push(inconsistency))?;
+
+    for Found {
+        inconsistency,
+        forward: forward_properties,
+        reverse: reverse_properties,
+    } in found
+    {
+        let Inconsistency {
+            kind,
+            source,
+            edge_type,
+            target,
+        } = inconsistency;
+        let forward_key = (source, edge_type.as_str(), target);
+        let reverse_key = (target, edge_type.as_str(), source);
+
+        match (strategy, kind) {
+            (
+                RepairStrategy::PreferForward | RepairStrategy::DropOrphans,
+                InconsistencyKind::Mismatched,
+            )
+            | (RepairStrategy::PreferForward, InconsistencyKind::ForwardOnly) => {
+                if let Some(properties) = forward_properties {
+                    reverse.insert(&reverse_key, &properties)?;
+                }
+            }
+            (
+                RepairStrategy::PreferForward | RepairStrategy::DropOrphans,
+                InconsistencyKind::ReverseOnly,
+            ) => {
+                reverse.remove(&reverse_key)?;
+            }
+            (
+                RepairStrategy::PreferReverse,
+                InconsistencyKind::Mismatched | InconsistencyKind::ReverseOnly,
+            ) => {
+                if let Some(properties) = reverse_properties {
+                    forward.insert(&forward_key, &properties)?;
+                }
+            }
+            (
+                RepairStrategy::PreferReverse | RepairStrategy::DropOrphans,
+                InconsistencyKind::ForwardOnly,
+            ) => {
+                forward.remove(&forward_key)?;
+            }
+        }
+    }
+
+    Ok(report)
+}
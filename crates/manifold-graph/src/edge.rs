@@ -1,16 +1,19 @@
 //! Edge types for graph storage.
 
+use crate::vertex::VertexId;
 use uuid::Uuid;
 
 /// An edge in the graph with properties and temporal tracking.
+///
+/// `V` is the type of the vertex ids, see [`VertexId`].
 #[derive(Debug, Clone, PartialEq)]
-pub struct Edge {
+pub struct Edge<V = Uuid> {
     /// Source vertex ID
-    pub source: Uuid,
+    pub source: V,
     /// Edge type (e.g., "follows", "knows", "contains")
     pub edge_type: String,
     /// Target vertex ID
-    pub target: Uuid,
+    pub target: V,
     /// Whether this edge is active (vs passive/hidden/deleted)
     pub is_active: bool,
     /// Edge weight or score
@@ -21,12 +24,12 @@ pub struct Edge {
     pub deleted_at: u64,
 }
 
-impl Edge {
+impl<V> Edge<V> {
     /// Creates a new edge with current timestamp
     pub fn new(
-        source: Uuid,
+        source: V,
         edge_type: impl Into<String>,
-        target: Uuid,
+        target: V,
         is_active: bool,
         weight: f32,
     ) -> Self {
@@ -43,9 +46,9 @@ impl Edge {
 
     /// Creates a new edge with explicit timestamps
     pub fn with_timestamps(
-        source: Uuid,
+        source: V,
         edge_type: impl Into<String>,
-        target: Uuid,
+        target: V,
         is_active: bool,
         weight: f32,
         created_at: u64,
@@ -68,6 +71,64 @@ impl Edge {
     }
 }
 
+/// Outcome of [`GraphTable::update_edge_if`](crate::GraphTable::update_edge_if).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CasResult {
+    /// The edge held the expected properties and was updated.
+    Updated,
+    /// The edge does not exist or has been soft-deleted.
+    Missing,
+    /// The edge holds other properties, and was left unchanged.
+    Conflict {
+        /// The `(is_active, weight)` the edge holds.
+        actual: (bool, f32),
+    },
+}
+
+/// Position in an edge listing, used to resume paginated reads.
+///
+/// A cursor records the key of the last edge examined by a page. It can be converted to and
+/// from bytes with [`to_bytes`](Self::to_bytes) and [`from_bytes`](Self::from_bytes), so it
+/// can be handed to a client and passed back with the next request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EdgeCursor<V = Uuid> {
+    pub(crate) source: V,
+    pub(crate) edge_type: String,
+    pub(crate) target: V,
+}
+
+/// A page of edges, with the cursor to resume after it if more edges may follow.
+pub type EdgePage<V = Uuid> = (Vec<Edge<V>>, Option<EdgeCursor<V>>);
+
+impl<V: VertexId> EdgeCursor<V> {
+    /// Serializes the cursor.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let source = V::as_bytes(&self.source);
+        let target = V::as_bytes(&self.target);
+        let (source, target) = (source.as_ref(), target.as_ref());
+        let mut bytes = Vec::with_capacity(source.len() + target.len() + self.edge_type.len());
+        bytes.extend_from_slice(source);
+        bytes.extend_from_slice(target);
+        bytes.extend_from_slice(self.edge_type.as_bytes());
+        bytes
+    }
+
+    /// Deserializes a cursor produced by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Returns `None` if `bytes` is not a valid cursor.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let width = V::fixed_width()?;
+        if bytes.len() < 2 * width {
+            return None;
+        }
+        Some(Self {
+            source: V::from_bytes(&bytes[..width]),
+            target: V::from_bytes(&bytes[width..2 * width]),
+            edge_type: String::from_utf8(bytes[2 * width..].to_vec()).ok()?,
+        })
+    }
+}
+
 /// Returns the current timestamp in nanoseconds since Unix epoch
 pub fn current_timestamp_nanos() -> u64 {
     std::time::SystemTime::now()
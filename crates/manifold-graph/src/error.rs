@@ -0,0 +1,102 @@
+//! Error type for graph table operations.
+
+use manifold::{StorageError, TableError};
+use std::fmt;
+use uuid::Uuid;
+
+/// Error returned by graph table operations.
+///
+/// `V` is the type of the vertex ids of the graph, see [`VertexId`](crate::VertexId).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GraphError<V = Uuid> {
+    /// An argument is outside the values the operation accepts.
+    InvalidArgument(&'static str),
+    /// An update produced a NaN or infinite weight. No edge was changed.
+    NonFiniteWeight {
+        /// Source vertex of the edge.
+        source: V,
+        /// Type of the edge.
+        edge_type: String,
+        /// Target vertex of the edge.
+        target: V,
+        /// Weight the update produced.
+        weight: f32,
+    },
+    /// An imported edge duplicates a stored edge or one read before it, and the
+    /// [`DuplicatePolicy`](crate::DuplicatePolicy) is `Error`.
+    DuplicateEdge {
+        /// Source vertex of the edge.
+        source: V,
+        /// Type of the edge.
+        edge_type: String,
+        /// Target vertex of the edge.
+        target: V,
+    },
+    /// An edge type is longer than the graph accepts, set with
+    /// [`GraphTable::with_max_edge_type_len`](crate::GraphTable::with_max_edge_type_len).
+    /// The edge was not written.
+    EdgeTypeTooLong {
+        /// Length of the edge type in bytes.
+        len: usize,
+        /// Longest accepted edge type in bytes.
+        max: usize,
+    },
+    /// Error opening the underlying table.
+    Table(TableError),
+    /// Error reading or writing the underlying storage.
+    Storage(StorageError),
+}
+
+impl<V: fmt::Debug> fmt::Display for GraphError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidArgument(msg) => write!(f, "Invalid argument: {msg}"),
+            Self::NonFiniteWeight {
+                source,
+                edge_type,
+                target,
+                weight,
+            } => write!(
+                f,
+                "Update of edge {source:?} -[{edge_type}]-> {target:?} produced weight {weight}"
+            ),
+            Self::DuplicateEdge {
+                source,
+                edge_type,
+                target,
+            } => write!(f, "Duplicate edge {source:?} -[{edge_type}]-> {target:?}"),
+            Self::EdgeTypeTooLong { len, max } => write!(
+                f,
+                "Edge type of {len} bytes exceeds the maximum of {max} bytes"
+            ),
+            Self::Table(err) => write!(f, "Table error: {err}"),
+            Self::Storage(err) => write!(f, "Storage error: {err}"),
+        }
+    }
+}
+
+impl<V: fmt::Debug> std::error::Error for GraphError<V> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidArgument(_)
+            | Self::NonFiniteWeight { .. }
+            | Self::DuplicateEdge { .. }
+            | Self::EdgeTypeTooLong { .. } => None,
+            Self::Table(err) => Some(err),
+            Self::Storage(err) => Some(err),
+        }
+    }
+}
+
+impl<V> From<TableError> for GraphError<V> {
+    fn from(err: TableError) -> Self {
+        Self::Table(err)
+    }
+}
+
+impl<V> From<StorageError> for GraphError<V> {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
@@ -1,12 +1,70 @@
 //! Graph table implementation with bidirectional edge storage.
 
-use crate::edge::{current_timestamp_nanos, Edge};
+use crate::consistency::{self, ConsistencyReport, RepairStrategy};
+use crate::edge::{CasResult, Edge, EdgeCursor, EdgePage, current_timestamp_nanos};
+use crate::error::GraphError;
+use crate::import::{self, EdgeInput, ImportOptions, ImportStats};
+use crate::vertex::VertexId;
 use manifold::{
-    ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata, StorageError, Table,
-    TableDefinition, TableError, WriteTransaction,
+    ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata, Table, TableDefinition,
+    WriteTransaction,
 };
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::ops::{Bound, RangeBounds};
 use uuid::Uuid;
 
+/// Creation time range accepting every edge.
+const ANY_TIME: (Bound<u64>, Bound<u64>) = (Bound::Unbounded, Bound::Unbounded);
+
+type EdgeKey<V> = (V, &'static str, V);
+type EdgeProperties = (bool, f32, u64, u64);
+
+/// Longest edge type, in bytes, that a [`GraphTable`] writes unless set with
+/// [`GraphTable::with_max_edge_type_len`].
+pub const DEFAULT_MAX_EDGE_TYPE_LEN: usize = 4096;
+
+/// A graph table with [`Uuid`] vertex ids.
+pub type UuidGraphTable<'txn> = GraphTable<'txn, Uuid>;
+
+/// A read-only graph table with [`Uuid`] vertex ids.
+pub type UuidGraphTableRead = GraphTableRead<Uuid>;
+
+/// Fails with [`GraphError::EdgeTypeTooLong`] if `edge_type` is longer than `max` bytes.
+pub(crate) fn check_edge_type<V>(edge_type: &str, max: usize) -> Result<(), GraphError<V>> {
+    if edge_type.len() > max {
+        return Err(GraphError::EdgeTypeTooLong {
+            len: edge_type.len(),
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the definitions of the forward and reverse tables, checking that `V` has a fixed
+/// width.
+#[allow(clippy::type_complexity)]
+fn table_definitions<'a, V: VertexId>(
+    forward_name: &'a str,
+    reverse_name: &'a str,
+) -> Result<
+    (
+        TableDefinition<'a, EdgeKey<V>, EdgeProperties>,
+        TableDefinition<'a, EdgeKey<V>, EdgeProperties>,
+    ),
+    GraphError<V>,
+> {
+    if V::fixed_width().is_none() {
+        return Err(GraphError::InvalidArgument(
+            "vertex id type must have a fixed width",
+        ));
+    }
+    Ok((
+        TableDefinition::new(forward_name),
+        TableDefinition::new(reverse_name),
+    ))
+}
+
 /// A table storing graph edges with bidirectional indexes and temporal tracking.
 ///
 /// This table maintains two internal tables (forward and reverse) to enable
@@ -14,28 +72,59 @@ use uuid::Uuid;
 /// updated atomically within the same write transaction.
 ///
 /// Value tuple: (is_active, weight, created_at, deleted_at)
-pub struct GraphTable<'txn> {
-    forward: Table<'txn, (Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
-    reverse: Table<'txn, (Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
+///
+/// Vertices are identified by `V`, which defaults to [`Uuid`]; see [`VertexId`] for the
+/// supported types. [`GraphTable::open`] opens a graph of [`Uuid`] vertices and
+/// [`GraphTable::open_typed`] a graph of any other id type. A graph must always be opened with
+/// the id type it was created with.
+pub struct GraphTable<'txn, V: VertexId = Uuid> {
+    forward: Table<'txn, EdgeKey<V>, EdgeProperties>,
+    reverse: Table<'txn, EdgeKey<V>, EdgeProperties>,
+    max_edge_type_len: usize,
+}
+
+impl<'txn> GraphTable<'txn, Uuid> {
+    /// Opens a graph table of [`Uuid`] vertices for writing.
+    ///
+    /// Creates two internal tables: `{name}_forward` and `{name}_reverse`.
+    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, GraphError> {
+        Self::open_typed(txn, name)
+    }
 }
 
-impl<'txn> GraphTable<'txn> {
-    /// Opens a graph table for writing.
+impl<'txn, V: VertexId> GraphTable<'txn, V> {
+    /// Opens a graph table of `V` vertices for writing.
     ///
     /// Creates two internal tables: `{name}_forward` and `{name}_reverse`.
-    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TableError> {
+    pub fn open_typed(txn: &'txn WriteTransaction, name: &str) -> Result<Self, GraphError<V>> {
         let forward_name = format!("{name}_forward");
         let reverse_name = format!("{name}_reverse");
-
-        let forward_def: TableDefinition<(Uuid, &str, Uuid), (bool, f32, u64, u64)> =
-            TableDefinition::new(&forward_name);
-        let reverse_def: TableDefinition<(Uuid, &str, Uuid), (bool, f32, u64, u64)> =
-            TableDefinition::new(&reverse_name);
+        let (forward_def, reverse_def) = table_definitions(&forward_name, &reverse_name)?;
 
         let forward = txn.open_table(forward_def)?;
         let reverse = txn.open_table(reverse_def)?;
 
-        Ok(Self { forward, reverse })
+        Ok(Self {
+            forward,
+            reverse,
+            max_edge_type_len: DEFAULT_MAX_EDGE_TYPE_LEN,
+        })
+    }
+
+    /// Sets the longest edge type, in bytes, that writes through this handle accept.
+    ///
+    /// Edges with a longer type fail with [`GraphError::EdgeTypeTooLong`] before anything is
+    /// written. The limit is not stored in the database; it only lasts as long as this handle,
+    /// and defaults to [`DEFAULT_MAX_EDGE_TYPE_LEN`].
+    #[must_use]
+    pub fn with_max_edge_type_len(mut self, max: usize) -> Self {
+        self.max_edge_type_len = max;
+        self
+    }
+
+    /// Returns the longest edge type, in bytes, that writes through this handle accept.
+    pub fn max_edge_type_len(&self) -> usize {
+        self.max_edge_type_len
     }
 
     /// Adds an edge to the graph with optional timestamp.
@@ -50,15 +139,19 @@ impl<'txn> GraphTable<'txn> {
     /// * `is_active` - Whether the edge is active
     /// * `weight` - Edge weight/score
     /// * `created_at` - Optional creation timestamp (uses current time if None)
+    ///
+    /// If the edge already exists, it is replaced, including its creation timestamp. Use
+    /// [`update_edge`](Self::update_edge) to change an edge's properties but keep its timestamp.
     pub fn add_edge(
         &mut self,
-        source: &Uuid,
+        source: &V,
         edge_type: &str,
-        target: &Uuid,
+        target: &V,
         is_active: bool,
         weight: f32,
         created_at: Option<u64>,
-    ) -> Result<(), TableError> {
+    ) -> Result<(), GraphError<V>> {
+        check_edge_type(edge_type, self.max_edge_type_len)?;
         let timestamp = created_at.unwrap_or_else(current_timestamp_nanos);
         let properties = (is_active, weight, timestamp, 0);
 
@@ -79,10 +172,10 @@ impl<'txn> GraphTable<'txn> {
     /// Updates both forward and reverse indexes atomically.
     pub fn remove_edge(
         &mut self,
-        source: &Uuid,
+        source: &V,
         edge_type: &str,
-        target: &Uuid,
-    ) -> Result<(), StorageError> {
+        target: &V,
+    ) -> Result<(), GraphError<V>> {
         // Get existing edge to preserve created_at
         let key = (*source, edge_type, *target);
         let edge_data = if let Some(guard) = self.forward.get(&key)? {
@@ -113,10 +206,10 @@ impl<'txn> GraphTable<'txn> {
     /// soft delete that preserves temporal history.
     pub fn hard_delete_edge(
         &mut self,
-        source: &Uuid,
+        source: &V,
         edge_type: &str,
-        target: &Uuid,
-    ) -> Result<(), StorageError> {
+        target: &V,
+    ) -> Result<(), GraphError<V>> {
         self.forward.remove(&(*source, edge_type, *target))?;
         self.reverse.remove(&(*target, edge_type, *source))?;
         Ok(())
@@ -127,12 +220,12 @@ impl<'txn> GraphTable<'txn> {
     /// Updates both forward and reverse indexes atomically.
     pub fn update_edge(
         &mut self,
-        source: &Uuid,
+        source: &V,
         edge_type: &str,
-        target: &Uuid,
+        target: &V,
         is_active: bool,
         weight: f32,
-    ) -> Result<(), TableError> {
+    ) -> Result<(), GraphError<V>> {
         // Get existing edge to preserve created_at
         let key = (*source, edge_type, *target);
         let created_at = if let Some(guard) = self.forward.get(&key)? {
@@ -143,14 +236,118 @@ impl<'txn> GraphTable<'txn> {
         };
 
         // Use add_edge with preserved created_at
-        self.add_edge(source, edge_type, target, is_active, weight, Some(created_at))
+        self.add_edge(
+            source,
+            edge_type,
+            target,
+            is_active,
+            weight,
+            Some(created_at),
+        )
+    }
+
+    /// Updates an edge only if it still holds the `expected` active flag and weight.
+    ///
+    /// `expected` and `new` are `(is_active, weight)` pairs. This gives optimistic
+    /// concurrency across transactions: read an edge, compute its new properties, and write
+    /// them back only if no other transaction changed the edge in between. Weights are
+    /// compared by exact bit equality, so a NaN matches the same NaN and `0.0` does not match
+    /// `-0.0`. The creation timestamp is kept, as by [`update_edge`](Self::update_edge).
+    ///
+    /// Returns [`CasResult::Missing`] for an edge that does not exist or has been
+    /// soft-deleted, and [`CasResult::Conflict`] with the stored properties if they differ
+    /// from `expected`; in both cases nothing is written.
+    pub fn update_edge_if(
+        &mut self,
+        source: &V,
+        edge_type: &str,
+        target: &V,
+        expected: (bool, f32),
+        new: (bool, f32),
+    ) -> Result<CasResult, GraphError<V>> {
+        let Some((is_active, weight, created_at)) = self.live_edge(source, edge_type, target)?
+        else {
+            return Ok(CasResult::Missing);
+        };
+        if is_active != expected.0 || weight.to_bits() != expected.1.to_bits() {
+            return Ok(CasResult::Conflict {
+                actual: (is_active, weight),
+            });
+        }
+
+        let (is_active, weight) = new;
+        self.add_edge(
+            source,
+            edge_type,
+            target,
+            is_active,
+            weight,
+            Some(created_at),
+        )?;
+        Ok(CasResult::Updated)
+    }
+
+    /// Adds `delta` to the weight of an edge, keeping its active flag and timestamps.
+    ///
+    /// The read and the write happen in this write transaction, so no other writer can change
+    /// the edge in between. Returns the new weight, or `None` without writing anything if the
+    /// edge does not exist or has been soft-deleted.
+    ///
+    /// Fails with [`GraphError::NonFiniteWeight`] if the new weight is NaN or infinite, leaving
+    /// the edge unchanged.
+    pub fn adjust_weight(
+        &mut self,
+        source: &V,
+        edge_type: &str,
+        target: &V,
+        delta: f32,
+    ) -> Result<Option<f32>, GraphError<V>> {
+        let Some((is_active, weight, created_at)) = self.live_edge(source, edge_type, target)?
+        else {
+            return Ok(None);
+        };
+        let weight = weight + delta;
+        if !weight.is_finite() {
+            return Err(GraphError::NonFiniteWeight {
+                source: *source,
+                edge_type: edge_type.to_string(),
+                target: *target,
+                weight,
+            });
+        }
+
+        self.add_edge(
+            source,
+            edge_type,
+            target,
+            is_active,
+            weight,
+            Some(created_at),
+        )?;
+        Ok(Some(weight))
+    }
+
+    /// Returns the active flag, weight and creation timestamp of an edge that is not
+    /// soft-deleted.
+    fn live_edge(
+        &self,
+        source: &V,
+        edge_type: &str,
+        target: &V,
+    ) -> Result<Option<(bool, f32, u64)>, GraphError<V>> {
+        let Some(guard) = self.forward.get(&(*source, edge_type, *target))? else {
+            return Ok(None);
+        };
+        let (is_active, weight, created_at, deleted_at) = guard.value();
+        Ok((deleted_at == 0).then_some((is_active, weight, created_at)))
     }
 
     /// Adds multiple edges to the graph in a single batch operation.
     ///
     /// This method leverages Manifold's bulk insertion API for improved throughput,
     /// especially beneficial when loading large graphs. Both forward and reverse
-    /// indexes are updated atomically within the same transaction.
+    /// indexes are updated atomically within the same transaction. To load more edges than
+    /// fit in memory, stream them with [`add_edges_from`](Self::add_edges_from).
     ///
     /// # Arguments
     ///
@@ -198,29 +395,37 @@ impl<'txn> GraphTable<'txn> {
     #[allow(clippy::type_complexity)]
     pub fn add_edges_batch(
         &mut self,
-        edges: &[(Uuid, &str, Uuid, bool, f32, u64)],
+        edges: &[(V, &str, V, bool, f32, u64)],
         sorted: bool,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, GraphError<V>> {
+        for (_, edge_type, _, _, _, _) in edges {
+            check_edge_type(edge_type, self.max_edge_type_len)?;
+        }
+
         // Prepare forward table items: (source, edge_type, target) -> (is_active, weight, created_at, deleted_at)
-        let forward_items: Vec<((Uuid, &str, Uuid), (bool, f32, u64, u64))> = edges
+        let forward_items: Vec<((V, &str, V), EdgeProperties)> = edges
             .iter()
-            .map(|(source, edge_type, target, is_active, weight, created_at)| {
-                (
-                    (*source, *edge_type, *target),
-                    (*is_active, *weight, *created_at, 0),
-                )
-            })
+            .map(
+                |(source, edge_type, target, is_active, weight, created_at)| {
+                    (
+                        (*source, *edge_type, *target),
+                        (*is_active, *weight, *created_at, 0),
+                    )
+                },
+            )
             .collect();
 
         // Prepare reverse table items: (target, edge_type, source) -> (is_active, weight, created_at, deleted_at)
-        let reverse_items: Vec<((Uuid, &str, Uuid), (bool, f32, u64, u64))> = edges
+        let reverse_items: Vec<((V, &str, V), EdgeProperties)> = edges
             .iter()
-            .map(|(source, edge_type, target, is_active, weight, created_at)| {
-                (
-                    (*target, *edge_type, *source),
-                    (*is_active, *weight, *created_at, 0),
-                )
-            })
+            .map(
+                |(source, edge_type, target, is_active, weight, created_at)| {
+                    (
+                        (*target, *edge_type, *source),
+                        (*is_active, *weight, *created_at, 0),
+                    )
+                },
+            )
             .collect();
 
         // Note: reverse items are NOT sorted even if forward items are,
@@ -232,43 +437,240 @@ impl<'txn> GraphTable<'txn> {
         Ok(count)
     }
 
+    /// Adds the edges read from `edges`, without holding them all in memory.
+    ///
+    /// Edges are buffered into chunks of [`ImportOptions::chunk_size`], and each chunk is
+    /// sorted and written to both the forward and reverse tables before the next edge is
+    /// read. Edges that duplicate a stored edge or one read before them are handled by the
+    /// [`DuplicatePolicy`](crate::DuplicatePolicy) of `options`. Returns counts of what happened
+    /// to the edges read.
+    ///
+    /// If this fails, the transaction may hold part of the import and should be dropped
+    /// without committing; see the [module documentation](crate::import) for what is written
+    /// when the iterator or the import fails partway.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use manifold::column_family::ColumnFamilyDatabase;
+    /// # use manifold_graph::{DuplicatePolicy, EdgeInput, GraphTable, ImportOptions};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = ColumnFamilyDatabase::open("test.db")?;
+    /// # let cf = db.column_family_or_create("graph")?;
+    /// # let write_txn = cf.begin_write()?;
+    /// # let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges")?;
+    /// let edges = (0..10_000_000u64).map(|i| EdgeInput::new(i / 10, "links", i, true, 1.0));
+    /// let options = ImportOptions::new()
+    ///     .duplicate_policy(DuplicatePolicy::Ignore)
+    ///     .on_progress(1_000_000, |stats| println!("{} edges read", stats.read));
+    ///
+    /// let stats = graph.add_edges_from(edges, options)?;
+    /// assert_eq!(stats.inserted, 10_000_000);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_edges_from(
+        &mut self,
+        edges: impl IntoIterator<Item = EdgeInput<V>>,
+        options: ImportOptions<'_>,
+    ) -> Result<ImportStats, GraphError<V>> {
+        import::import(
+            &mut self.forward,
+            &mut self.reverse,
+            edges,
+            options,
+            self.max_edge_type_len,
+        )
+    }
+
+    /// Multiplies the weight of every edge of `edge_type` by `factor`.
+    ///
+    /// Soft-deleted edges keep the weight they had when they were deleted. Returns the number
+    /// of edges whose weight changed.
+    ///
+    /// Fails with [`GraphError::InvalidArgument`] if `factor` is not finite, and with
+    /// [`GraphError::NonFiniteWeight`] if a weight overflows; in either case no edge is changed.
+    pub fn rescale_weights(
+        &mut self,
+        edge_type: &str,
+        factor: f32,
+    ) -> Result<usize, GraphError<V>> {
+        if !factor.is_finite() {
+            return Err(GraphError::InvalidArgument("scale factor must be finite"));
+        }
+        self.for_each_edge_mut(
+            |edge| edge.edge_type == edge_type && edge.deleted_at == 0,
+            |_, weight| *weight *= factor,
+        )
+    }
+
+    /// Updates the active flag and weight of every edge accepted by `filter`.
+    ///
+    /// `filter` sees each edge in the graph, including soft-deleted ones, in forward table
+    /// order, and `f` is called with the active flag and weight of each accepted edge. Changes
+    /// are written to both the forward and reverse tables once every edge has been visited,
+    /// keeping timestamps. Returns the number of edges whose properties changed.
+    ///
+    /// A NaN or infinite weight is not clamped: if `f` changes a weight to one, the update
+    /// fails with [`GraphError::NonFiniteWeight`] before any edge is written.
+    pub fn for_each_edge_mut(
+        &mut self,
+        mut filter: impl FnMut(&Edge<V>) -> bool,
+        mut f: impl FnMut(&mut bool, &mut f32),
+    ) -> Result<usize, GraphError<V>> {
+        let mut updated = Vec::new();
+        for edge in self.forward_edges()? {
+            let mut edge = edge?;
+            if !filter(&edge) {
+                continue;
+            }
+
+            let (is_active, weight) = (edge.is_active, edge.weight);
+            f(&mut edge.is_active, &mut edge.weight);
+            let weight_changed = edge.weight.to_bits() != weight.to_bits();
+            if weight_changed && !edge.weight.is_finite() {
+                return Err(GraphError::NonFiniteWeight {
+                    source: edge.source,
+                    edge_type: edge.edge_type,
+                    target: edge.target,
+                    weight: edge.weight,
+                });
+            }
+            if weight_changed || edge.is_active != is_active {
+                updated.push(edge);
+            }
+        }
+
+        for edge in &updated {
+            self.write_edge(edge)?;
+        }
+        Ok(updated.len())
+    }
+
+    /// Reverses the direction of every edge accepted by `filter`.
+    ///
+    /// Each accepted edge from `source` to `target` is replaced by an edge from `target` to
+    /// `source` with the same type, properties and timestamps, in both the forward and reverse
+    /// tables. `filter` sees each edge in the graph, including soft-deleted ones. Two accepted
+    /// edges in opposite directions swap places. Returns the number of edges reversed.
+    ///
+    /// Fails with [`GraphError::InvalidArgument`], without changing any edge, if a reversed
+    /// edge would replace an existing edge that is not itself reversed.
+    pub fn reverse_edges(
+        &mut self,
+        mut filter: impl FnMut(&Edge<V>) -> bool,
+    ) -> Result<usize, GraphError<V>> {
+        let mut reversed = Vec::new();
+        for edge in self.forward_edges()? {
+            let edge = edge?;
+            if filter(&edge) {
+                reversed.push(edge);
+            }
+        }
+
+        let moved: HashSet<(V, &str, V)> = reversed
+            .iter()
+            .map(|edge| (edge.source, edge.edge_type.as_str(), edge.target))
+            .collect();
+        for edge in &reversed {
+            let flipped = (edge.target, edge.edge_type.as_str(), edge.source);
+            if !moved.contains(&flipped) && self.forward.get(&flipped)?.is_some() {
+                return Err(GraphError::InvalidArgument(
+                    "reversing an edge would replace an existing edge",
+                ));
+            }
+        }
+
+        for edge in &reversed {
+            self.hard_delete_edge(&edge.source, &edge.edge_type, &edge.target)?;
+        }
+        for edge in &mut reversed {
+            std::mem::swap(&mut edge.source, &mut edge.target);
+            self.write_edge(edge)?;
+        }
+        Ok(reversed.len())
+    }
+
+    /// Iterates over every edge in the forward table, including soft-deleted ones.
+    fn forward_edges(&self) -> Result<AllEdgesIter<'_, V>, GraphError<V>> {
+        Ok(AllEdgesIter {
+            inner: self.forward.iter()?,
+            include_deleted: true,
+            created: ANY_TIME,
+        })
+    }
+
+    /// Writes `edge` with all of its properties to both tables.
+    fn write_edge(&mut self, edge: &Edge<V>) -> Result<(), GraphError<V>> {
+        let properties = (
+            edge.is_active,
+            edge.weight,
+            edge.created_at,
+            edge.deleted_at,
+        );
+        let edge_type = edge.edge_type.as_str();
+        self.forward
+            .insert(&(edge.source, edge_type, edge.target), &properties)?;
+        self.reverse
+            .insert(&(edge.target, edge_type, edge.source), &properties)?;
+        Ok(())
+    }
+
+    /// Compares the forward and reverse tables, reporting edges that are missing from one of
+    /// them or stored with different properties.
+    ///
+    /// This scans both tables. See [`GraphTableRead::verify_consistency`] to check a graph
+    /// without a write transaction.
+    pub fn verify_consistency(&self) -> Result<ConsistencyReport<V>, GraphError<V>> {
+        consistency::verify(&self.forward, &self.reverse)
+    }
+
+    /// Resolves every difference between the forward and reverse tables with `strategy`.
+    ///
+    /// Returns the report of the differences found before the repair; once the transaction
+    /// commits, both tables hold the same edges.
+    pub fn repair(
+        &mut self,
+        strategy: RepairStrategy,
+    ) -> Result<ConsistencyReport<V>, GraphError<V>> {
+        consistency::repair(&mut self.forward, &mut self.reverse, strategy)
+    }
+
     /// Returns the number of edges in the forward table.
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.forward.len()
+    pub fn len(&self) -> Result<u64, GraphError<V>> {
+        Ok(self.forward.len()?)
     }
 
     /// Returns `true` if the table contains no edges.
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, GraphError<V>> {
         Ok(self.len()? == 0)
     }
 }
 
 /// Read-only graph table providing efficient edge traversal with temporal support.
-pub struct GraphTableRead {
-    forward: ReadOnlyTable<(Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
-    reverse: ReadOnlyTable<(Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
+///
+/// Vertices are identified by `V`, as in [`GraphTable`].
+pub struct GraphTableRead<V: VertexId = Uuid> {
+    forward: ReadOnlyTable<EdgeKey<V>, EdgeProperties>,
+    reverse: ReadOnlyTable<EdgeKey<V>, EdgeProperties>,
 }
 
-impl GraphTableRead {
-    /// Opens a graph table for reading.
-    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, StorageError> {
+impl GraphTableRead<Uuid> {
+    /// Opens a graph table of [`Uuid`] vertices for reading.
+    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, GraphError> {
+        Self::open_typed(txn, name)
+    }
+}
+
+impl<V: VertexId> GraphTableRead<V> {
+    /// Opens a graph table of `V` vertices for reading.
+    pub fn open_typed(txn: &ReadTransaction, name: &str) -> Result<Self, GraphError<V>> {
         let forward_name = format!("{name}_forward");
         let reverse_name = format!("{name}_reverse");
+        let (forward_def, reverse_def) = table_definitions(&forward_name, &reverse_name)?;
 
-        let forward_def: TableDefinition<(Uuid, &str, Uuid), (bool, f32, u64, u64)> =
-            TableDefinition::new(&forward_name);
-        let reverse_def: TableDefinition<(Uuid, &str, Uuid), (bool, f32, u64, u64)> =
-            TableDefinition::new(&reverse_name);
-
-        let forward = txn.open_table(forward_def).map_err(|e| match e {
-            TableError::Storage(s) => s,
-            _ => StorageError::Io(std::io::Error::other(e)),
-        })?;
-
-        let reverse = txn.open_table(reverse_def).map_err(|e| match e {
-            TableError::Storage(s) => s,
-            _ => StorageError::Io(std::io::Error::other(e)),
-        })?;
+        let forward = txn.open_table(forward_def)?;
+        let reverse = txn.open_table(reverse_def)?;
 
         Ok(Self { forward, reverse })
     }
@@ -279,10 +681,10 @@ impl GraphTableRead {
     /// Use get_edge_at() for temporal queries.
     pub fn get_edge(
         &self,
-        source: &Uuid,
+        source: &V,
         edge_type: &str,
-        target: &Uuid,
-    ) -> Result<Option<Edge>, StorageError> {
+        target: &V,
+    ) -> Result<Option<Edge<V>>, GraphError<V>> {
         Ok(self
             .forward
             .get(&(*source, edge_type, *target))?
@@ -291,13 +693,7 @@ impl GraphTableRead {
                 // Only return if not deleted
                 if deleted_at == 0 {
                     Some(Edge::with_timestamps(
-                        *source,
-                        edge_type,
-                        *target,
-                        is_active,
-                        weight,
-                        created_at,
-                        deleted_at,
+                        *source, edge_type, *target, is_active, weight, created_at, deleted_at,
                     ))
                 } else {
                     None
@@ -311,24 +707,18 @@ impl GraphTableRead {
     /// and either not deleted or deleted_at > timestamp).
     pub fn get_edge_at(
         &self,
-        source: &Uuid,
+        source: &V,
         edge_type: &str,
-        target: &Uuid,
+        target: &V,
         timestamp: u64,
-    ) -> Result<Option<Edge>, StorageError> {
+    ) -> Result<Option<Edge<V>>, GraphError<V>> {
         Ok(self
             .forward
             .get(&(*source, edge_type, *target))?
             .and_then(|guard| {
                 let (is_active, weight, created_at, deleted_at) = guard.value();
                 let edge = Edge::with_timestamps(
-                    *source,
-                    edge_type,
-                    *target,
-                    is_active,
-                    weight,
-                    created_at,
-                    deleted_at,
+                    *source, edge_type, *target, is_active, weight, created_at, deleted_at,
                 );
 
                 if edge.is_active_at(timestamp) {
@@ -342,38 +732,40 @@ impl GraphTableRead {
     /// Returns an iterator over all outgoing edges from the given source vertex.
     ///
     /// By default, excludes soft-deleted edges. Use outgoing_edges_with_deleted() to include them.
-    pub fn outgoing_edges(&self, source: &Uuid) -> Result<OutgoingEdgeIter<'_>, StorageError> {
+    pub fn outgoing_edges(&self, source: &V) -> Result<OutgoingEdgeIter<'_, V>, GraphError<V>> {
         // Range from (source, "", nil_uuid) to (source, max_str, max_uuid)
-        let start = (*source, "", Uuid::nil());
-        let end = (*source, "\u{FFFF}", Uuid::max());
+        let start = (*source, "", V::MIN);
+        let end = (*source, "\u{FFFF}", V::MAX);
 
         Ok(OutgoingEdgeIter {
             inner: self.forward.range(start..end)?,
             include_deleted: false,
+            created: ANY_TIME,
         })
     }
 
     /// Returns an iterator over all outgoing edges including soft-deleted ones.
     pub fn outgoing_edges_with_deleted(
         &self,
-        source: &Uuid,
-    ) -> Result<OutgoingEdgeIter<'_>, StorageError> {
-        let start = (*source, "", Uuid::nil());
-        let end = (*source, "\u{FFFF}", Uuid::max());
+        source: &V,
+    ) -> Result<OutgoingEdgeIter<'_, V>, GraphError<V>> {
+        let start = (*source, "", V::MIN);
+        let end = (*source, "\u{FFFF}", V::MAX);
 
         Ok(OutgoingEdgeIter {
             inner: self.forward.range(start..end)?,
             include_deleted: true,
+            created: ANY_TIME,
         })
     }
 
     /// Returns an iterator over all incoming edges to the given target vertex.
     ///
     /// By default, excludes soft-deleted edges. Use incoming_edges_with_deleted() to include them.
-    pub fn incoming_edges(&self, target: &Uuid) -> Result<IncomingEdgeIter<'_>, StorageError> {
+    pub fn incoming_edges(&self, target: &V) -> Result<IncomingEdgeIter<'_, V>, GraphError<V>> {
         // Range from (target, "", nil_uuid) to (target, max_str, max_uuid)
-        let start = (*target, "", Uuid::nil());
-        let end = (*target, "\u{FFFF}", Uuid::max());
+        let start = (*target, "", V::MIN);
+        let end = (*target, "\u{FFFF}", V::MAX);
 
         Ok(IncomingEdgeIter {
             inner: self.reverse.range(start..end)?,
@@ -384,10 +776,10 @@ impl GraphTableRead {
     /// Returns an iterator over all incoming edges including soft-deleted ones.
     pub fn incoming_edges_with_deleted(
         &self,
-        target: &Uuid,
-    ) -> Result<IncomingEdgeIter<'_>, StorageError> {
-        let start = (*target, "", Uuid::nil());
-        let end = (*target, "\u{FFFF}", Uuid::max());
+        target: &V,
+    ) -> Result<IncomingEdgeIter<'_, V>, GraphError<V>> {
+        let start = (*target, "", V::MIN);
+        let end = (*target, "\u{FFFF}", V::MAX);
 
         Ok(IncomingEdgeIter {
             inner: self.reverse.range(start..end)?,
@@ -398,42 +790,286 @@ impl GraphTableRead {
     /// Returns an iterator over all edges in the graph.
     ///
     /// By default, excludes soft-deleted edges. Use all_edges_with_deleted() to include them.
-    pub fn all_edges(&self) -> Result<AllEdgesIter<'_>, StorageError> {
+    pub fn all_edges(&self) -> Result<AllEdgesIter<'_, V>, GraphError<V>> {
         Ok(AllEdgesIter {
             inner: self.forward.iter()?,
             include_deleted: false,
+            created: ANY_TIME,
         })
     }
 
     /// Returns an iterator over all edges including soft-deleted ones.
-    pub fn all_edges_with_deleted(&self) -> Result<AllEdgesIter<'_>, StorageError> {
+    pub fn all_edges_with_deleted(&self) -> Result<AllEdgesIter<'_, V>, GraphError<V>> {
         Ok(AllEdgesIter {
             inner: self.forward.iter()?,
             include_deleted: true,
+            created: ANY_TIME,
         })
     }
 
+    /// Returns an iterator over the outgoing edges of `source` created at or after
+    /// `min_timestamp`.
+    ///
+    /// Edges are matched on [`Edge::created_at`], which [`GraphTable::update_edge`] and the bulk
+    /// updates keep, so updating an edge does not make it newer. Adding an edge that already
+    /// exists with [`GraphTable::add_edge`] replaces its creation time. Soft-deleted edges are
+    /// skipped, as in [`outgoing_edges`](Self::outgoing_edges).
+    ///
+    /// Edges are not indexed by time: this scans every outgoing edge of `source`.
+    pub fn outgoing_edges_since(
+        &self,
+        source: &V,
+        min_timestamp: u64,
+    ) -> Result<OutgoingEdgeIter<'_, V>, GraphError<V>> {
+        let start = (*source, "", V::MIN);
+        let end = (*source, "\u{FFFF}", V::MAX);
+
+        Ok(OutgoingEdgeIter {
+            inner: self.forward.range(start..end)?,
+            include_deleted: false,
+            created: (Bound::Included(min_timestamp), Bound::Unbounded),
+        })
+    }
+
+    /// Returns an iterator over the edges created from `min_timestamp` (inclusive) to
+    /// `max_timestamp` (exclusive), in forward table order.
+    ///
+    /// Edges are matched on [`Edge::created_at`] as in
+    /// [`outgoing_edges_since`](Self::outgoing_edges_since), and soft-deleted edges are skipped.
+    /// Fails with [`GraphError::InvalidArgument`] if `min_timestamp` is after `max_timestamp`.
+    ///
+    /// Edges are not indexed by time: this scans every edge in the graph.
+    pub fn all_edges_in_range(
+        &self,
+        min_timestamp: u64,
+        max_timestamp: u64,
+    ) -> Result<AllEdgesIter<'_, V>, GraphError<V>> {
+        if min_timestamp > max_timestamp {
+            return Err(GraphError::InvalidArgument(
+                "minimum timestamp is after maximum timestamp",
+            ));
+        }
+
+        Ok(AllEdgesIter {
+            inner: self.forward.iter()?,
+            include_deleted: false,
+            created: (
+                Bound::Included(min_timestamp),
+                Bound::Excluded(max_timestamp),
+            ),
+        })
+    }
+
+    /// Returns up to `limit` edges following `after`, and a cursor for the next page.
+    ///
+    /// Pass `None` to start from the first edge. The returned cursor is `None` once every edge
+    /// has been returned; a full page may be followed by a final empty page. Soft-deleted edges
+    /// are skipped, as in [`all_edges`](Self::all_edges).
+    ///
+    /// Pages can be read from different transactions, so a long export does not have to hold
+    /// one read transaction open. Each page then reflects the snapshot of its own transaction:
+    /// an edge present for the whole export is returned exactly once, but edges added or
+    /// removed between pages may or may not be returned.
+    pub fn all_edges_page(
+        &self,
+        after: Option<&EdgeCursor<V>>,
+        limit: usize,
+    ) -> Result<EdgePage<V>, GraphError<V>> {
+        let start = match after {
+            Some(cursor) => {
+                Bound::Excluded((cursor.source, cursor.edge_type.as_str(), cursor.target))
+            }
+            None => Bound::Unbounded,
+        };
+        let range = self
+            .forward
+            .range::<(V, &str, V)>((start, Bound::Unbounded))?;
+        read_page(
+            AllEdgesIter {
+                inner: range,
+                include_deleted: false,
+                created: ANY_TIME,
+            },
+            limit,
+        )
+    }
+
+    /// Returns up to `limit` outgoing edges of `source` following `after`, and a cursor for
+    /// the next page.
+    ///
+    /// This pages through the edges of a single vertex with the same semantics and snapshot
+    /// caveats as [`all_edges_page`](Self::all_edges_page). `after` must be a cursor returned
+    /// for the same `source`.
+    pub fn outgoing_edges_page(
+        &self,
+        source: &V,
+        after: Option<&EdgeCursor<V>>,
+        limit: usize,
+    ) -> Result<EdgePage<V>, GraphError<V>> {
+        let start = match after {
+            Some(cursor) if cursor.source != *source => {
+                return Err(GraphError::InvalidArgument(
+                    "cursor belongs to a different source vertex",
+                ));
+            }
+            Some(cursor) => {
+                Bound::Excluded((cursor.source, cursor.edge_type.as_str(), cursor.target))
+            }
+            None => Bound::Included((*source, "", V::MIN)),
+        };
+        let end = Bound::Excluded((*source, "\u{FFFF}", V::MAX));
+        let range = self.forward.range::<(V, &str, V)>((start, end))?;
+        read_page(
+            OutgoingEdgeIter {
+                inner: range,
+                include_deleted: false,
+                created: ANY_TIME,
+            },
+            limit,
+        )
+    }
+
+    /// Returns the `n` outgoing edges of `source` with the highest weights, strongest first.
+    ///
+    /// With `edge_type`, only edges of that type are considered. Soft-deleted edges are
+    /// skipped, as in [`outgoing_edges`](Self::outgoing_edges). NaN weights rank below every
+    /// other weight, and edges of equal weight keep their forward table order, so the result
+    /// is deterministic.
+    ///
+    /// Edges are not indexed by weight: this scans every outgoing edge of `source`, or of that
+    /// type, keeping only the `n` strongest in memory.
+    pub fn top_n_by_weight(
+        &self,
+        source: &V,
+        n: usize,
+        edge_type: Option<&str>,
+    ) -> Result<Vec<Edge<V>>, GraphError<V>> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        let range = match edge_type {
+            Some(edge_type) => self
+                .forward
+                .range((*source, edge_type, V::MIN)..=(*source, edge_type, V::MAX))?,
+            None => self
+                .forward
+                .range((*source, "", V::MIN)..(*source, "\u{FFFF}", V::MAX))?,
+        };
+        let edges = OutgoingEdgeIter {
+            inner: range,
+            include_deleted: false,
+            created: ANY_TIME,
+        };
+
+        // Min-heap of the strongest edges so far, whose top is the weakest of them
+        let mut strongest = BinaryHeap::new();
+        for (position, edge) in edges.enumerate() {
+            strongest.push(Reverse(RankedEdge {
+                edge: edge?,
+                position,
+            }));
+            if strongest.len() > n {
+                strongest.pop();
+            }
+        }
+        Ok(strongest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ranked)| ranked.edge)
+            .collect())
+    }
+
+    /// Compares the forward and reverse tables, reporting edges that are missing from one of
+    /// them or stored with different properties.
+    ///
+    /// This scans both tables. Inconsistencies can be fixed with [`GraphTable::repair`].
+    pub fn verify_consistency(&self) -> Result<ConsistencyReport<V>, GraphError<V>> {
+        consistency::verify(&self.forward, &self.reverse)
+    }
+
     /// Returns the number of edges stored in this table.
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.forward.len()
+    pub fn len(&self) -> Result<u64, GraphError<V>> {
+        Ok(self.forward.len()?)
     }
 
     /// Returns `true` if the table contains no edges.
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, GraphError<V>> {
         Ok(self.len()? == 0)
     }
 }
 
+/// An edge ranked by [`GraphTableRead::top_n_by_weight`]: a greater `RankedEdge` is stronger.
+struct RankedEdge<V> {
+    edge: Edge<V>,
+    // Position in forward table order, breaking ties in favour of the earlier edge
+    position: usize,
+}
+
+impl<V> Ord for RankedEdge<V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.edge.weight, other.edge.weight);
+        let by_weight = match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => a.total_cmp(&b),
+        };
+        by_weight.then_with(|| other.position.cmp(&self.position))
+    }
+}
+
+impl<V> PartialOrd for RankedEdge<V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<V> PartialEq for RankedEdge<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<V> Eq for RankedEdge<V> {}
+
+/// Collects up to `limit` edges from `edges`, returning a cursor if more may follow.
+fn read_page<V: VertexId>(
+    mut edges: impl Iterator<Item = Result<Edge<V>, GraphError<V>>>,
+    limit: usize,
+) -> Result<EdgePage<V>, GraphError<V>> {
+    if limit == 0 {
+        return Err(GraphError::InvalidArgument("page limit must be non-zero"));
+    }
+
+    let mut page = Vec::with_capacity(limit);
+    while page.len() < limit {
+        match edges.next() {
+            Some(edge) => page.push(edge?),
+            None => return Ok((page, None)),
+        }
+    }
+
+    let last = &page[page.len() - 1];
+    let cursor = EdgeCursor {
+        source: last.source,
+        edge_type: last.edge_type.clone(),
+        target: last.target,
+    };
+    Ok((page, Some(cursor)))
+}
+
 /// Iterator over outgoing edges from a source vertex.
 ///
 /// By default, only returns non-deleted edges. Use all_edges_with_deleted() to include soft-deleted edges.
-pub struct OutgoingEdgeIter<'a> {
-    inner: manifold::Range<'a, (Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
+pub struct OutgoingEdgeIter<'a, V: VertexId = Uuid> {
+    inner: manifold::Range<'a, EdgeKey<V>, EdgeProperties>,
     include_deleted: bool,
+    // Creation times of the edges returned
+    created: (Bound<u64>, Bound<u64>),
 }
 
-impl Iterator for OutgoingEdgeIter<'_> {
-    type Item = Result<Edge, StorageError>;
+impl<V: VertexId> Iterator for OutgoingEdgeIter<'_, V> {
+    type Item = Result<Edge<V>, GraphError<V>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
@@ -448,18 +1084,15 @@ impl Iterator for OutgoingEdgeIter<'_> {
                     if !self.include_deleted && deleted_at != 0 {
                         continue;
                     }
+                    if !self.created.contains(&created_at) {
+                        continue;
+                    }
 
                     return Some(Ok(Edge::with_timestamps(
-                        source,
-                        edge_type,
-                        target,
-                        is_active,
-                        weight,
-                        created_at,
-                        deleted_at,
+                        source, edge_type, target, is_active, weight, created_at, deleted_at,
                     )));
                 }
-                Err(e) => return Some(Err(e)),
+                Err(e) => return Some(Err(e.into())),
             }
         }
     }
@@ -468,13 +1101,15 @@ impl Iterator for OutgoingEdgeIter<'_> {
 /// Iterator over all edges in the graph.
 ///
 /// By default, only returns non-deleted edges.
-pub struct AllEdgesIter<'a> {
-    inner: manifold::Range<'a, (Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
+pub struct AllEdgesIter<'a, V: VertexId = Uuid> {
+    inner: manifold::Range<'a, EdgeKey<V>, EdgeProperties>,
     include_deleted: bool,
+    // Creation times of the edges returned
+    created: (Bound<u64>, Bound<u64>),
 }
 
-impl Iterator for AllEdgesIter<'_> {
-    type Item = Result<Edge, StorageError>;
+impl<V: VertexId> Iterator for AllEdgesIter<'_, V> {
+    type Item = Result<Edge<V>, GraphError<V>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
@@ -489,18 +1124,15 @@ impl Iterator for AllEdgesIter<'_> {
                     if !self.include_deleted && deleted_at != 0 {
                         continue;
                     }
+                    if !self.created.contains(&created_at) {
+                        continue;
+                    }
 
                     return Some(Ok(Edge::with_timestamps(
-                        source,
-                        edge_type,
-                        target,
-                        is_active,
-                        weight,
-                        created_at,
-                        deleted_at,
+                        source, edge_type, target, is_active, weight, created_at, deleted_at,
                     )));
                 }
-                Err(e) => return Some(Err(e)),
+                Err(e) => return Some(Err(e.into())),
             }
         }
     }
@@ -509,13 +1141,13 @@ impl Iterator for AllEdgesIter<'_> {
 /// Iterator over incoming edges to a target vertex.
 ///
 /// By default, only returns non-deleted edges.
-pub struct IncomingEdgeIter<'a> {
-    inner: manifold::Range<'a, (Uuid, &'static str, Uuid), (bool, f32, u64, u64)>,
+pub struct IncomingEdgeIter<'a, V: VertexId = Uuid> {
+    inner: manifold::Range<'a, EdgeKey<V>, EdgeProperties>,
     include_deleted: bool,
 }
 
-impl Iterator for IncomingEdgeIter<'_> {
-    type Item = Result<Edge, StorageError>;
+impl<V: VertexId> Iterator for IncomingEdgeIter<'_, V> {
+    type Item = Result<Edge<V>, GraphError<V>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
@@ -533,16 +1165,10 @@ impl Iterator for IncomingEdgeIter<'_> {
 
                     // Note: In reverse table, first UUID is target, third is source
                     return Some(Ok(Edge::with_timestamps(
-                        source,
-                        edge_type,
-                        target,
-                        is_active,
-                        weight,
-                        created_at,
-                        deleted_at,
+                        source, edge_type, target, is_active, weight, created_at, deleted_at,
                     )));
                 }
-                Err(e) => return Some(Err(e)),
+                Err(e) => return Some(Err(e.into())),
             }
         }
     }
@@ -0,0 +1,298 @@
+//! Streaming import of edges.
+//!
+//! [`GraphTable::add_edges_from`](crate::GraphTable::add_edges_from) reads edges from an
+//! iterator instead of a slice, so a graph far larger than memory can be loaded in one write
+//! transaction. Edges are buffered into chunks of [`ImportOptions::chunk_size`], and each full
+//! chunk is sorted, checked for duplicates and written to the forward and reverse tables
+//! before the next edge is read.
+//!
+//! # Duplicates
+//!
+//! An edge is a duplicate if an edge with the same source, type and target is already stored,
+//! including a soft-deleted one, or was read earlier in the same import. [`DuplicatePolicy`]
+//! decides what happens to it, and [`ImportStats`] counts the outcome of every edge read.
+//!
+//! # Failures
+//!
+//! The iterator is only advanced between chunks, never while a chunk is being written, so the
+//! forward and reverse tables agree whenever it runs: if it panics, or is stopped early, the
+//! tables hold every chunk filled before and none of the edges buffered since. When the
+//! import itself fails, chunks written before the failure remain in the transaction, and the
+//! chunk that failed may be written to one table only if the failure came from storage. A
+//! failed import therefore leaves the transaction in an unspecified state: drop it without
+//! committing. An import is all-or-nothing only as part of the transaction that commits it.
+
+use crate::edge::current_timestamp_nanos;
+use crate::error::GraphError;
+use crate::graph::check_edge_type;
+use crate::vertex::VertexId;
+use manifold::{ReadableTable, Table};
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+type EdgeKey<'a, V> = (V, &'a str, V);
+type EdgeProperties = (bool, f32, u64, u64);
+type ProgressCallback<'a> = Box<dyn FnMut(&ImportStats) + 'a>;
+
+/// Number of edges sorted and written at a time unless set with
+/// [`ImportOptions::chunk_size`].
+pub const DEFAULT_CHUNK_SIZE: usize = 100_000;
+
+/// An edge read by [`GraphTable::add_edges_from`](crate::GraphTable::add_edges_from).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeInput<V = Uuid> {
+    /// Source vertex ID
+    pub source: V,
+    /// Edge type (e.g., "follows", "knows", "contains")
+    pub edge_type: String,
+    /// Target vertex ID
+    pub target: V,
+    /// Whether this edge is active
+    pub is_active: bool,
+    /// Edge weight or score
+    pub weight: f32,
+    /// Creation timestamp in nanoseconds since Unix epoch, or `None` for the time the chunk
+    /// of the edge is written
+    pub created_at: Option<u64>,
+}
+
+impl<V> EdgeInput<V> {
+    /// Creates an edge stamped with the time it is written.
+    pub fn new(
+        source: V,
+        edge_type: impl Into<String>,
+        target: V,
+        is_active: bool,
+        weight: f32,
+    ) -> Self {
+        Self {
+            source,
+            edge_type: edge_type.into(),
+            target,
+            is_active,
+            weight,
+            created_at: None,
+        }
+    }
+
+    /// Sets the creation timestamp of the edge.
+    #[must_use]
+    pub fn with_created_at(mut self, created_at: u64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+}
+
+/// What an import does with an edge that duplicates a stored edge or one read before it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// The new edge replaces the stored one, including its creation timestamp. Within an
+    /// import, the last edge read wins.
+    #[default]
+    Overwrite,
+    /// The stored edge is kept and the new one is dropped. Within an import, the first edge
+    /// read wins.
+    Ignore,
+    /// The import fails with [`GraphError::DuplicateEdge`].
+    Error,
+}
+
+/// Counts of the edges handled by an import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    /// Edges read from the iterator.
+    pub read: u64,
+    /// Edges written that were not stored before.
+    pub inserted: u64,
+    /// Duplicates that replaced an edge, under [`DuplicatePolicy::Overwrite`].
+    pub replaced: u64,
+    /// Duplicates dropped under [`DuplicatePolicy::Ignore`].
+    pub ignored: u64,
+}
+
+/// Options for [`GraphTable::add_edges_from`](crate::GraphTable::add_edges_from).
+///
+/// # Example
+///
+/// ```rust
+/// use manifold_graph::{DuplicatePolicy, ImportOptions};
+///
+/// let options = ImportOptions::new()
+///     .chunk_size(10_000)
+///     .duplicate_policy(DuplicatePolicy::Ignore)
+///     .on_progress(1_000_000, |stats| println!("{} edges read", stats.read));
+/// ```
+pub struct ImportOptions<'a> {
+    chunk_size: usize,
+    duplicate_policy: DuplicatePolicy,
+    progress: Option<(u64, ProgressCallback<'a>)>,
+}
+
+impl Default for ImportOptions<'_> {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            duplicate_policy: DuplicatePolicy::default(),
+            progress: None,
+        }
+    }
+}
+
+impl<'a> ImportOptions<'a> {
+    /// Creates options with the [default chunk size](DEFAULT_CHUNK_SIZE), overwriting
+    /// duplicates and reporting no progress.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of edges buffered, sorted and written at a time, which must be
+    /// non-zero.
+    ///
+    /// Larger chunks write more edges in key order, at the cost of holding them in memory.
+    #[must_use]
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets what happens to duplicate edges.
+    #[must_use]
+    pub fn duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self
+    }
+
+    /// Calls `f` every `every` edges read, which must be non-zero.
+    ///
+    /// [`ImportStats::read`] is exact; the other counts cover the chunks written so far, so
+    /// they lag behind unless `every` is a multiple of the chunk size.
+    #[must_use]
+    pub fn on_progress(mut self, every: u64, f: impl FnMut(&ImportStats) + 'a) -> Self {
+        self.progress = Some((every, Box::new(f)));
+        self
+    }
+}
+
+/// Writes the edges of `edges` to both tables chunk by chunk.
+pub(crate) fn import<V: VertexId>(
+    forward: &mut Table<'_, EdgeKey<'static, V>, EdgeProperties>,
+    reverse: &mut Table<'_, EdgeKey<'static, V>, EdgeProperties>,
+    edges: impl IntoIterator<Item = EdgeInput<V>>,
+    mut options: ImportOptions<'_>,
+    max_edge_type_len: usize,
+) -> Result<ImportStats, GraphError<V>> {
+    if options.chunk_size == 0 {
+        return Err(GraphError::InvalidArgument("chunk size must be non-zero"));
+    }
+    if matches!(options.progress, Some((0, _))) {
+        return Err(GraphError::InvalidArgument(
+            "progress interval must be non-zero",
+        ));
+    }
+
+    let policy = options.duplicate_policy;
+    let mut stats = ImportStats::default();
+    let mut chunk = Vec::new();
+    for edge in edges {
+        check_edge_type(&edge.edge_type, max_edge_type_len)?;
+        chunk.push(edge);
+        stats.read += 1;
+        if chunk.len() == options.chunk_size {
+            write_chunk(forward, reverse, &mut chunk, policy, &mut stats)?;
+        }
+        match &mut options.progress {
+            Some((every, f)) if stats.read % *every == 0 => f(&stats),
+            _ => {}
+        }
+    }
+    write_chunk(forward, reverse, &mut chunk, policy, &mut stats)?;
+    Ok(stats)
+}
+
+/// Sorts `chunk`, resolves its duplicates and writes what remains to both tables, leaving
+/// `chunk` empty.
+///
+/// Duplicates are resolved before anything is written, so a duplicate under
+/// [`DuplicatePolicy::Error`] fails without writing any edge of the chunk.
+fn write_chunk<V: VertexId>(
+    forward: &mut Table<'_, EdgeKey<'static, V>, EdgeProperties>,
+    reverse: &mut Table<'_, EdgeKey<'static, V>, EdgeProperties>,
+    chunk: &mut Vec<EdgeInput<V>>,
+    policy: DuplicatePolicy,
+    stats: &mut ImportStats,
+) -> Result<(), GraphError<V>> {
+    // The sort is stable, so duplicates within the chunk stay in the order they were read
+    chunk.sort_by(compare_edges);
+    let mut unique: Vec<EdgeInput<V>> = Vec::with_capacity(chunk.len());
+    for edge in chunk.drain(..) {
+        match unique.last_mut() {
+            Some(last) if same_edge(last, &edge) => match policy {
+                DuplicatePolicy::Overwrite => {
+                    *last = edge;
+                    stats.replaced += 1;
+                }
+                DuplicatePolicy::Ignore => stats.ignored += 1,
+                DuplicatePolicy::Error => return Err(duplicate_edge(edge)),
+            },
+            _ => unique.push(edge),
+        }
+    }
+
+    let now = current_timestamp_nanos();
+    let mut forward_items = Vec::with_capacity(unique.len());
+    for edge in &unique {
+        let key = (edge.source, edge.edge_type.as_str(), edge.target);
+        if forward.get(&key)?.is_some() {
+            match policy {
+                DuplicatePolicy::Overwrite => stats.replaced += 1,
+                DuplicatePolicy::Ignore => {
+                    stats.ignored += 1;
+                    continue;
+                }
+                DuplicatePolicy::Error => return Err(duplicate_edge(edge.clone())),
+            }
+        } else {
+            stats.inserted += 1;
+        }
+        let properties = (
+            edge.is_active,
+            edge.weight,
+            edge.created_at.unwrap_or(now),
+            0,
+        );
+        forward_items.push((key, properties));
+    }
+
+    let reverse_items: Vec<_> = forward_items
+        .iter()
+        .map(|((source, edge_type, target), properties)| {
+            ((*target, *edge_type, *source), *properties)
+        })
+        .collect();
+    forward.insert_bulk(forward_items, true)?;
+    reverse.insert_bulk(reverse_items, false)?;
+    Ok(())
+}
+
+/// Orders edges as the forward table orders their keys.
+fn compare_edges<V: VertexId>(a: &EdgeInput<V>, b: &EdgeInput<V>) -> Ordering {
+    compare_ids(&a.source, &b.source)
+        .then_with(|| a.edge_type.cmp(&b.edge_type))
+        .then_with(|| compare_ids(&a.target, &b.target))
+}
+
+fn compare_ids<V: VertexId>(a: &V, b: &V) -> Ordering {
+    V::compare(V::as_bytes(a).as_ref(), V::as_bytes(b).as_ref())
+}
+
+fn same_edge<V: VertexId>(a: &EdgeInput<V>, b: &EdgeInput<V>) -> bool {
+    a.source == b.source && a.edge_type == b.edge_type && a.target == b.target
+}
+
+fn duplicate_edge<V: VertexId>(edge: EdgeInput<V>) -> GraphError<V> {
+    GraphError::DuplicateEdge {
+        source: edge.source,
+        edge_type: edge.edge_type,
+        target: edge.target,
+    }
+}
@@ -1,43 +1,136 @@
 //! Integration traits for external graph algorithm libraries.
 
-use crate::{AllEdgesIter, Edge, GraphTableRead};
-use manifold::StorageError;
+use crate::{AllEdgesIter, Edge, GraphError, GraphTableRead, VertexId};
+#[cfg(feature = "petgraph")]
+use petgraph::graph::{Graph, NodeIndex};
+#[cfg(feature = "petgraph")]
+use std::collections::HashMap;
+use uuid::Uuid;
 
 /// Trait for edge sources consumable by graph algorithm libraries.
 ///
 /// This trait enables external graph algorithm libraries (BFS, DFS, `PageRank`, etc.)
-/// to efficiently iterate over all edges in the graph.
-pub trait EdgeSource {
+/// to efficiently iterate over all edges in the graph. `V` is the type of the vertex ids.
+pub trait EdgeSource<V: VertexId = Uuid> {
     /// Iterator type over edges
-    type Iter<'a>: Iterator<Item = Result<Edge, StorageError>>
+    type Iter<'a>: Iterator<Item = Result<Edge<V>, GraphError<V>>>
     where
         Self: 'a;
 
     /// Returns an iterator over all edges in the graph.
     ///
     /// The iterator provides access to all edges with their properties.
-    fn all_edges(&self) -> Result<Self::Iter<'_>, StorageError>;
+    fn all_edges(&self) -> Result<Self::Iter<'_>, GraphError<V>>;
 
     /// Returns the number of edges.
-    fn edge_count(&self) -> Result<u64, StorageError>;
+    fn edge_count(&self) -> Result<u64, GraphError<V>>;
 
     /// Returns true if empty.
-    fn is_empty(&self) -> Result<bool, StorageError> {
+    fn is_empty(&self) -> Result<bool, GraphError<V>> {
         Ok(self.edge_count()? == 0)
     }
 }
 
-impl EdgeSource for GraphTableRead {
+impl<V: VertexId> EdgeSource<V> for GraphTableRead<V> {
     type Iter<'a>
-        = AllEdgesIter<'a>
+        = AllEdgesIter<'a, V>
     where
         Self: 'a;
 
-    fn all_edges(&self) -> Result<Self::Iter<'_>, StorageError> {
+    fn all_edges(&self) -> Result<Self::Iter<'_>, GraphError<V>> {
         GraphTableRead::all_edges(self)
     }
 
-    fn edge_count(&self) -> Result<u64, StorageError> {
+    fn edge_count(&self) -> Result<u64, GraphError<V>> {
         self.len()
     }
 }
+
+/// Selects which edges are exported from an [`EdgeSource`].
+///
+/// The default filter selects all active edges of every type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EdgeFilter {
+    /// Only select edges of this type, if set.
+    pub edge_type: Option<String>,
+    /// Also select edges whose `is_active` flag is false.
+    pub include_inactive: bool,
+}
+
+impl EdgeFilter {
+    /// Selects all active edges of every type.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Selects active edges of a single type.
+    pub fn edge_type(edge_type: impl Into<String>) -> Self {
+        Self {
+            edge_type: Some(edge_type.into()),
+            include_inactive: false,
+        }
+    }
+
+    /// Returns `true` if `edge` is selected by this filter.
+    pub fn matches<V>(&self, edge: &Edge<V>) -> bool {
+        (self.include_inactive || edge.is_active)
+            && self
+                .edge_type
+                .as_ref()
+                .is_none_or(|edge_type| *edge_type == edge.edge_type)
+    }
+}
+
+/// Builds a petgraph directed graph from the edges selected by `filter`.
+///
+/// Each vertex that appears as the source or target of a selected edge becomes a node
+/// weighted by its id, and each selected edge becomes a petgraph edge weighted by its
+/// `weight`. Vertices without selected edges are not included.
+///
+/// Returns the graph together with the mapping from vertex ids to node indices.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use manifold_graph::integration::{to_petgraph, EdgeFilter};
+/// use manifold_graph::GraphTableRead;
+/// use petgraph::algo::dijkstra;
+/// # use manifold::column_family::ColumnFamilyDatabase;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let db = ColumnFamilyDatabase::open("my.db")?;
+/// # let cf = db.column_family_or_create("graph")?;
+/// # let start = uuid::Uuid::nil();
+///
+/// let read_txn = cf.begin_read()?;
+/// let graph = GraphTableRead::open(&read_txn, "roads")?;
+/// let (pg_graph, nodes) = to_petgraph(&graph, &EdgeFilter::edge_type("road"))?;
+///
+/// let distances = dijkstra(&pg_graph, nodes[&start], None, |edge| *edge.weight());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "petgraph")]
+#[allow(clippy::type_complexity)]
+pub fn to_petgraph<V: VertexId, S: EdgeSource<V>>(
+    source: &S,
+    filter: &EdgeFilter,
+) -> Result<(Graph<V, f32>, HashMap<V, NodeIndex>), GraphError<V>> {
+    let mut graph = Graph::new();
+    let mut nodes = HashMap::new();
+
+    for edge in source.all_edges()? {
+        let edge = edge?;
+        if !filter.matches(&edge) {
+            continue;
+        }
+        let source = *nodes
+            .entry(edge.source)
+            .or_insert_with(|| graph.add_node(edge.source));
+        let target = *nodes
+            .entry(edge.target)
+            .or_insert_with(|| graph.add_node(edge.target));
+        graph.add_edge(source, target, edge.weight);
+    }
+
+    Ok((graph, nodes))
+}
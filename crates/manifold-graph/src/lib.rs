@@ -8,10 +8,14 @@
 //! ## Features
 //!
 //! - **Automatic bidirectional indexes**: Efficient queries for both outgoing and incoming edges
-//! - **UUID-based vertices**: Fixed-width 16-byte vertex IDs with proper ordering
-//! - **Type-safe edge properties**: Fixed-width tuple `(bool, f32, u64, Option<u64>)` for `is_active`, `weight`, and temporal tracking
+//! - **Fixed-width vertex IDs**: 16-byte UUIDs by default, or `u64`, `u128` and `[u8; N]` ids
+//!   for smaller keys, all with proper ordering
+//! - **Type-safe edge properties**: Fixed-width tuple `(bool, f32, u64, u64)` for `is_active`, `weight`, and temporal tracking
 //! - **Atomic updates**: Both forward and reverse indexes updated in same transaction
 //! - **Efficient traversal**: Range scans leverage tuple key ordering for fast queries
+//! - **Streaming import**: Load edges from an iterator in sorted chunks, with a duplicate policy
+//!   and progress reporting
+//! - **Consistency checks**: Detect and repair disagreements between the two indexes
 //!
 //! ## Quick Start
 //!
@@ -68,10 +72,11 @@
 //! - `is_active: bool` - For active/passive edges, soft deletes, hidden edges
 //! - `weight: f32` - General-purpose edge weight or score
 //! - `created_at: u64` - Creation timestamp in nanoseconds since Unix epoch
-//! - `deleted_at: Option<u64>` - Deletion timestamp (None if not deleted)
+//! - `deleted_at: u64` - Deletion timestamp (0 if not deleted)
 //!
-//! These properties are stored as a fixed-width tuple `(bool, f32, u64, Option<u64>)` for
-//! efficient serialization (21 bytes total).
+//! These properties are stored as a fixed-width tuple `(bool, f32, u64, u64)` for
+//! efficient serialization (21 bytes total). Edges can be queried by creation time with
+//! [`GraphTableRead::outgoing_edges_since`] and [`GraphTableRead::all_edges_in_range`].
 
 #![deny(missing_docs)]
 #![deny(clippy::all, clippy::pedantic)]
@@ -82,10 +87,21 @@
     clippy::missing_panics_doc
 )]
 
+pub mod consistency;
 pub mod edge;
+pub mod error;
 pub mod graph;
+pub mod import;
 pub mod integration;
+pub mod vertex;
 
-pub use edge::Edge;
-pub use graph::{AllEdgesIter, GraphTable, GraphTableRead, IncomingEdgeIter, OutgoingEdgeIter};
-pub use integration::EdgeSource;
+pub use consistency::{ConsistencyReport, Inconsistency, InconsistencyKind, RepairStrategy};
+pub use edge::{CasResult, Edge, EdgeCursor, EdgePage};
+pub use error::GraphError;
+pub use graph::{
+    AllEdgesIter, GraphTable, GraphTableRead, IncomingEdgeIter, OutgoingEdgeIter, UuidGraphTable,
+    UuidGraphTableRead,
+};
+pub use import::{DuplicatePolicy, EdgeInput, ImportOptions, ImportStats};
+pub use integration::{EdgeFilter, EdgeSource};
+pub use vertex::VertexId;
@@ -0,0 +1,47 @@
+//! Vertex id types.
+//!
+//! A graph is generic over the type of its vertex ids, which defaults to [`Uuid`]. Any
+//! fixed-width [`Key`] whose stored value is itself can be used: [`VertexId`] is implemented
+//! for [`Uuid`], `u64`, `u128` and `[u8; N]`. Smaller ids shrink both the forward and the
+//! reverse key of every edge, and integer ids let vertices be scanned in numeric order.
+//!
+//! Edges are ordered by the [`Key`] ordering of their ids: bytewise for [`Uuid`] and byte
+//! arrays, numeric for integers.
+
+use manifold::{Key, Value};
+use std::fmt::Debug;
+use std::hash::Hash;
+use uuid::Uuid;
+
+/// A fixed-width type usable as the vertex id of a graph.
+///
+/// Implementations must have a [`Value::fixed_width`]; opening a graph with a variable-width
+/// id type fails with [`GraphError::InvalidArgument`](crate::GraphError::InvalidArgument).
+pub trait VertexId:
+    Key + for<'a> Value<SelfType<'a> = Self> + Copy + Eq + Hash + Debug + 'static
+{
+    /// The smallest id in [`Key`] order.
+    const MIN: Self;
+    /// The largest id in [`Key`] order.
+    const MAX: Self;
+}
+
+impl VertexId for Uuid {
+    const MIN: Self = Uuid::nil();
+    const MAX: Self = Uuid::max();
+}
+
+impl VertexId for u64 {
+    const MIN: Self = u64::MIN;
+    const MAX: Self = u64::MAX;
+}
+
+impl VertexId for u128 {
+    const MIN: Self = u128::MIN;
+    const MAX: Self = u128::MAX;
+}
+
+impl<const N: usize> VertexId for [u8; N] {
+    const MIN: Self = [u8::MIN; N];
+    const MAX: Self = [u8::MAX; N];
+}
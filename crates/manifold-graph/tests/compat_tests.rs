@@ -0,0 +1,153 @@
+//! Read compatibility with graphs written by earlier versions
+//!
+//! `tests/golden/` holds a database written by `write_golden` at each released version, named
+//! after that version. Every golden file must pass `check_golden` with the current code. See
+//! `docs/on_disk_format.md` at the root of the repository for the table formats and for when
+//! to add a golden file.
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_graph::{Edge, GraphTable, GraphTableRead};
+use std::path::Path;
+use uuid::Uuid;
+
+#[path = "../../../tests/support/golden.rs"]
+mod golden;
+
+const GOLDEN_PREFIX: &str = "graph-v";
+
+const A: Uuid = Uuid::from_u128(1);
+const B: Uuid = Uuid::from_u128(2);
+const C: Uuid = Uuid::from_u128(3);
+
+fn edge<V>(
+    source: V,
+    edge_type: &str,
+    target: V,
+    is_active: bool,
+    weight: f32,
+    created_at: u64,
+) -> Edge<V> {
+    Edge {
+        source,
+        edge_type: edge_type.to_string(),
+        target,
+        is_active,
+        weight,
+        created_at,
+        deleted_at: 0,
+    }
+}
+
+/// Writes the fixture to `path`: a `Uuid` graph with an inactive and a soft-deleted edge, and
+/// a `u64` graph using the extremes of its ids and weights
+///
+/// Must not change once a golden file has been written with it.
+fn write_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    // Grows from 64 KB as it is written, so the golden file stays small
+    let cf = db.create_column_family("social", Some(64 * 1024)).unwrap();
+
+    let write_txn = cf.begin_write().unwrap();
+    {
+        let mut graph = GraphTable::<Uuid>::open(&write_txn, "people").unwrap();
+        graph
+            .add_edge(&A, "follows", &B, true, 1.0, Some(100))
+            .unwrap();
+        graph
+            .add_edge(&A, "follows", &C, true, 0.5, Some(200))
+            .unwrap();
+        graph
+            .add_edge(&B, "knows", &C, false, -2.25, Some(300))
+            .unwrap();
+        graph
+            .add_edge(&C, "follows", &A, true, 0.0, Some(400))
+            .unwrap();
+        graph.remove_edge(&C, "follows", &A).unwrap();
+    }
+    {
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "numbers").unwrap();
+        graph
+            .add_edge(&0, "next", &u64::MAX, true, f32::MAX, Some(1))
+            .unwrap();
+        graph
+            .add_edge(
+                &u64::MAX,
+                "prev",
+                &0,
+                true,
+                f32::MIN_POSITIVE,
+                Some(u64::MAX),
+            )
+            .unwrap();
+    }
+    write_txn.commit().unwrap();
+}
+
+/// Asserts that the database at `path` holds exactly what `write_golden` wrote
+fn check_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let cf = db.column_family("social").unwrap();
+    let read_txn = cf.begin_read().unwrap();
+
+    let graph = GraphTableRead::<Uuid>::open(&read_txn, "people").unwrap();
+    assert_eq!(graph.len().unwrap(), 4);
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+    let edges: Vec<Edge> = graph
+        .all_edges_with_deleted()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        edges[..3],
+        [
+            edge(A, "follows", B, true, 1.0, 100),
+            edge(A, "follows", C, true, 0.5, 200),
+            edge(B, "knows", C, false, -2.25, 300),
+        ]
+    );
+    let deleted = &edges[3];
+    assert_eq!((deleted.source, deleted.target), (C, A));
+    assert_eq!(deleted.created_at, 400);
+    assert!(deleted.deleted_at > 0);
+
+    assert!(graph.get_edge(&C, "follows", &A).unwrap().is_none());
+    let incoming: Vec<Uuid> = graph
+        .incoming_edges(&C)
+        .unwrap()
+        .map(|edge| edge.unwrap().source)
+        .collect();
+    assert_eq!(incoming, [A, B]);
+    assert_eq!(graph.outgoing_edges(&C).unwrap().count(), 0);
+
+    let graph = GraphTableRead::<u64>::open_typed(&read_txn, "numbers").unwrap();
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+    let edges: Vec<Edge<u64>> = graph
+        .all_edges_with_deleted()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            edge(0, "next", u64::MAX, true, f32::MAX, 1),
+            edge(u64::MAX, "prev", 0, true, f32::MIN_POSITIVE, u64::MAX),
+        ]
+    );
+}
+
+#[test]
+fn test_golden_files_read_back() {
+    golden::check_golden_files(GOLDEN_PREFIX, check_golden);
+}
+
+#[test]
+fn test_fixture_round_trips() {
+    golden::check_fixture_round_trips(write_golden, check_golden);
+}
+
+/// Writes the golden file of the current version
+#[test]
+#[ignore = "run once per release to add its golden file"]
+fn bless_golden_file() {
+    golden::bless_golden_file(GOLDEN_PREFIX, write_golden);
+}
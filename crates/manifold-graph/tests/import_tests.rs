@@ -0,0 +1,229 @@
+//! Tests for streaming edge imports
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_graph::{
+    DuplicatePolicy, EdgeInput, GraphError, GraphTable, GraphTableRead, ImportOptions, ImportStats,
+};
+use std::panic::{self, AssertUnwindSafe};
+
+const EDGES: u64 = 1_000_000;
+
+/// Number of distinct edges in [`synthetic_edges`]
+const UNIQUE_EDGES: u64 = 900_000;
+
+/// Chunk size that is not a multiple of 10, so that some duplicates fall in the chunk after
+/// the edge they repeat
+const CHUNK_SIZE: usize = 65_536;
+
+/// Streams `EDGES` edges in which every tenth repeats the edge read five before it, with the
+/// position of each edge in the stream as its weight
+fn synthetic_edges() -> impl Iterator<Item = EdgeInput<u64>> {
+    (0..EDGES).map(|i| {
+        let target = if i % 10 == 9 { i - 5 } else { i };
+        EdgeInput::new(target / 8, "links", target, true, i as f32).with_created_at(100)
+    })
+}
+
+/// Streams the edges of [`synthetic_edges`] that do not repeat an earlier one
+fn unique_edges() -> impl Iterator<Item = EdgeInput<u64>> {
+    synthetic_edges().filter(|edge| edge.weight == edge.target as f32)
+}
+
+#[test]
+fn test_import_overwrite_keeps_last_duplicate() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let mut progress = Vec::new();
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let options = ImportOptions::new()
+            .chunk_size(CHUNK_SIZE)
+            .on_progress(100_000, |stats| progress.push(stats.read));
+        let stats = graph.add_edges_from(synthetic_edges(), options).unwrap();
+        assert_eq!(
+            stats,
+            ImportStats {
+                read: EDGES,
+                inserted: UNIQUE_EDGES,
+                replaced: EDGES - UNIQUE_EDGES,
+                ignored: 0,
+            }
+        );
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+    assert_eq!(progress, (1..=10).map(|i| i * 100_000).collect::<Vec<_>>());
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::<u64>::open_typed(&read_txn, "edges").unwrap();
+    assert_eq!(graph.len().unwrap(), UNIQUE_EDGES);
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+
+    // Edge 65,534 is repeated by the 65,539th edge, in the next chunk
+    for target in [4, 65_534] {
+        let edge = graph
+            .get_edge(&(target / 8), "links", &target)
+            .unwrap()
+            .unwrap();
+        assert_eq!(edge.weight, (target + 5) as f32);
+        assert_eq!(edge.created_at, 100);
+    }
+    let incoming: Vec<_> = graph
+        .incoming_edges(&65_534)
+        .unwrap()
+        .map(|edge| edge.unwrap().weight)
+        .collect();
+    assert_eq!(incoming, [65_539.0]);
+}
+
+#[test]
+fn test_import_ignore_keeps_first_duplicate() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let options = || {
+        ImportOptions::new()
+            .chunk_size(CHUNK_SIZE)
+            .duplicate_policy(DuplicatePolicy::Ignore)
+    };
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let stats = graph.add_edges_from(synthetic_edges(), options()).unwrap();
+        assert_eq!(
+            stats,
+            ImportStats {
+                read: EDGES,
+                inserted: UNIQUE_EDGES,
+                replaced: 0,
+                ignored: EDGES - UNIQUE_EDGES,
+            }
+        );
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    // Importing the same edges again finds every one of them stored
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let stats = graph.add_edges_from(synthetic_edges(), options()).unwrap();
+        assert_eq!(stats.inserted, 0);
+        assert_eq!(stats.ignored, EDGES);
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::<u64>::open_typed(&read_txn, "edges").unwrap();
+    assert_eq!(graph.len().unwrap(), UNIQUE_EDGES);
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+    for target in [4, 65_534] {
+        let edge = graph
+            .get_edge(&(target / 8), "links", &target)
+            .unwrap()
+            .unwrap();
+        assert_eq!(edge.weight, target as f32);
+    }
+}
+
+#[test]
+fn test_import_error_policy_rejects_duplicates() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let options = || {
+        ImportOptions::new()
+            .chunk_size(CHUNK_SIZE)
+            .duplicate_policy(DuplicatePolicy::Error)
+    };
+
+    // A duplicate within the stream fails the import
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let err = graph
+            .add_edges_from(synthetic_edges(), options())
+            .unwrap_err();
+        let GraphError::DuplicateEdge {
+            source,
+            edge_type,
+            target,
+        } = err
+        else {
+            panic!("expected a duplicate edge");
+        };
+        assert_eq!((source, edge_type.as_str(), target), (0, "links", 4));
+    }
+
+    // Without duplicates every edge is inserted
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let stats = graph.add_edges_from(unique_edges(), options()).unwrap();
+        assert_eq!(stats.read, UNIQUE_EDGES);
+        assert_eq!(stats.inserted, UNIQUE_EDGES);
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    // An edge that is already stored is a duplicate too
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let edges = [EdgeInput::new(u64::MAX, "links", 1, true, 1.0)]
+            .into_iter()
+            .chain(unique_edges().skip(3));
+        let err = graph.add_edges_from(edges, options()).unwrap_err();
+        assert!(matches!(
+            err,
+            GraphError::DuplicateEdge {
+                source: 0,
+                target: 3,
+                ..
+            }
+        ));
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::<u64>::open_typed(&read_txn, "edges").unwrap();
+    assert_eq!(graph.len().unwrap(), UNIQUE_EDGES);
+}
+
+#[test]
+fn test_import_iterator_panic_leaves_tables_consistent() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let write_txn = cf.begin_write().unwrap();
+    let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+    let edges = (0..2500u64)
+        .map(|i| EdgeInput::new(i, "links", i + 1, true, 1.0))
+        .chain(std::iter::from_fn(|| panic!("source failed")));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        graph.add_edges_from(edges, ImportOptions::new().chunk_size(1000))
+    }));
+    assert!(result.is_err());
+
+    // Both full chunks were written to both tables, the half-filled one to neither
+    assert_eq!(graph.len().unwrap(), 2000);
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+
+    assert!(matches!(
+        graph.add_edges_from(std::iter::empty(), ImportOptions::new().chunk_size(0)),
+        Err(GraphError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        graph.add_edges_from(
+            std::iter::empty(),
+            ImportOptions::new().on_progress(0, |_| {})
+        ),
+        Err(GraphError::InvalidArgument(_))
+    ));
+}
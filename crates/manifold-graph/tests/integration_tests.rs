@@ -1,9 +1,17 @@
 //! Integration tests for manifold-graph
 
 use manifold::column_family::ColumnFamilyDatabase;
-use manifold_graph::{Edge, GraphTable, GraphTableRead};
+use manifold::{TableDefinition, TableError};
+use manifold_graph::graph::DEFAULT_MAX_EDGE_TYPE_LEN;
+use manifold_graph::{
+    CasResult, Edge, EdgeCursor, EdgeInput, GraphError, GraphTable, GraphTableRead, ImportOptions,
+};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// The raw forward or reverse table behind a `GraphTable<Uuid>`.
+type RawEdgeTable<'a> = TableDefinition<'a, (Uuid, &'static str, Uuid), (bool, f32, u64, u64)>;
+
 #[test]
 fn test_basic_edge_operations() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -424,18 +432,26 @@ fn test_full_graph_iteration() {
     assert_eq!(all_edges.len(), 4);
 
     // Verify we can find specific edges
-    assert!(all_edges
-        .iter()
-        .any(|e| e.source == u1 && e.target == u2 && e.edge_type == "follows"));
-    assert!(all_edges
-        .iter()
-        .any(|e| e.source == u1 && e.target == u3 && e.edge_type == "follows"));
-    assert!(all_edges
-        .iter()
-        .any(|e| e.source == u2 && e.target == u3 && e.edge_type == "follows"));
-    assert!(all_edges
-        .iter()
-        .any(|e| e.source == u3 && e.target == u1 && e.edge_type == "knows"));
+    assert!(
+        all_edges
+            .iter()
+            .any(|e| e.source == u1 && e.target == u2 && e.edge_type == "follows")
+    );
+    assert!(
+        all_edges
+            .iter()
+            .any(|e| e.source == u1 && e.target == u3 && e.edge_type == "follows")
+    );
+    assert!(
+        all_edges
+            .iter()
+            .any(|e| e.source == u2 && e.target == u3 && e.edge_type == "follows")
+    );
+    assert!(
+        all_edges
+            .iter()
+            .any(|e| e.source == u3 && e.target == u1 && e.edge_type == "knows")
+    );
 }
 
 #[test]
@@ -663,3 +679,861 @@ fn test_hard_delete_edge() {
         .collect();
     assert_eq!(edges_with_deleted.len(), 0);
 }
+
+#[test]
+fn test_all_edges_paginated_with_concurrent_writes() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("graph.db");
+    let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let vertices: Vec<Uuid> = (0..1000).map(|_| Uuid::new_v4()).collect();
+    let mut expected = HashSet::new();
+    {
+        let mut edges = Vec::with_capacity(100_000);
+        for i in 0..100_000 {
+            let source = vertices[i % 1000];
+            let target = vertices[(i / 1000 + i % 1000 + 1) % 1000];
+            let edge_type = if i % 2 == 0 { "follows" } else { "knows" };
+            edges.push((source, edge_type, target, true, 1.0, 0));
+            expected.insert((source, edge_type.to_string(), target));
+        }
+        assert_eq!(expected.len(), 100_000);
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        graph.add_edges_batch(&edges, false).unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let mut seen = HashSet::new();
+    let mut cursor: Option<EdgeCursor> = None;
+    let mut pages = 0;
+    loop {
+        let read_txn = cf.begin_read().unwrap();
+        let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+        let (edges, next) = graph.all_edges_page(cursor.as_ref(), 1000).unwrap();
+        drop(graph);
+        drop(read_txn);
+        assert!(edges.len() <= 1000);
+        pages += 1;
+
+        for edge in edges {
+            assert!(
+                seen.insert((edge.source, edge.edge_type, edge.target)),
+                "edge returned twice"
+            );
+        }
+
+        // Writes between pages land both before and after the cursor
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        for _ in 0..10 {
+            graph
+                .add_edge(&Uuid::new_v4(), "follows", &Uuid::new_v4(), true, 1.0, None)
+                .unwrap();
+        }
+        drop(graph);
+        write_txn.commit().unwrap();
+
+        // Cursors survive a round trip through their serialized form
+        match next {
+            Some(next) => cursor = Some(EdgeCursor::from_bytes(&next.to_bytes()).unwrap()),
+            None => break,
+        }
+    }
+
+    assert!(pages > 100);
+    assert!(expected.is_subset(&seen));
+    assert!(EdgeCursor::<Uuid>::from_bytes(&[0; 10]).is_none());
+}
+
+#[test]
+fn test_outgoing_edges_paginated() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("graph.db");
+    let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let hub = Uuid::new_v4();
+    let other = Uuid::new_v4();
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        for _ in 0..250 {
+            graph
+                .add_edge(&hub, "follows", &Uuid::new_v4(), true, 1.0, None)
+                .unwrap();
+            graph
+                .add_edge(&other, "follows", &Uuid::new_v4(), true, 1.0, None)
+                .unwrap();
+        }
+        let removed = Uuid::new_v4();
+        graph
+            .add_edge(&hub, "follows", &removed, true, 1.0, None)
+            .unwrap();
+        graph.remove_edge(&hub, "follows", &removed).unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+
+    let mut targets = HashSet::new();
+    let mut cursor = None;
+    loop {
+        let (edges, next) = graph
+            .outgoing_edges_page(&hub, cursor.as_ref(), 100)
+            .unwrap();
+        for edge in edges {
+            assert_eq!(edge.source, hub);
+            assert!(targets.insert(edge.target));
+        }
+        match next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(targets.len(), 250);
+
+    let (_, cursor) = graph.outgoing_edges_page(&other, None, 10).unwrap();
+    assert!(matches!(
+        graph.outgoing_edges_page(&hub, cursor.as_ref(), 10),
+        Err(GraphError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        graph.all_edges_page(None, 0),
+        Err(GraphError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_open_missing_graph_reports_table_error() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("graph").unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    assert!(matches!(
+        GraphTableRead::open(&read_txn, "missing"),
+        Err(GraphError::Table(TableError::TableDoesNotExist(_)))
+    ));
+}
+
+type EdgeKey = (Uuid, String, Uuid);
+type EdgeProperties = (bool, u32, u64, u64);
+
+fn forward_view(graph: &GraphTableRead) -> HashMap<EdgeKey, EdgeProperties> {
+    graph
+        .all_edges_with_deleted()
+        .unwrap()
+        .map(|edge| edge_entry(&edge.unwrap()))
+        .collect()
+}
+
+fn reverse_view(graph: &GraphTableRead, vertices: &[Uuid]) -> HashMap<EdgeKey, EdgeProperties> {
+    let mut view = HashMap::new();
+    for vertex in vertices {
+        for edge in graph.incoming_edges_with_deleted(vertex).unwrap() {
+            let (key, properties) = edge_entry(&edge.unwrap());
+            assert!(view.insert(key, properties).is_none());
+        }
+    }
+    view
+}
+
+fn edge_entry(edge: &Edge) -> (EdgeKey, EdgeProperties) {
+    (
+        (edge.source, edge.edge_type.clone(), edge.target),
+        (
+            edge.is_active,
+            edge.weight.to_bits(),
+            edge.created_at,
+            edge.deleted_at,
+        ),
+    )
+}
+
+#[test]
+fn test_bulk_edge_updates_keep_tables_in_sync() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("graph.db");
+    let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    // 500 vertices, each with 100 outgoing edges alternating between two types
+    let vertices: Vec<Uuid> = (1..=500).map(Uuid::from_u128).collect();
+    let edges: Vec<(Uuid, &str, Uuid, bool, f32, u64)> = (0..50_000)
+        .map(|i| {
+            let (source, step) = (i % 500, i / 500);
+            let edge_type = if step % 2 == 0 { "likes" } else { "follows" };
+            #[allow(clippy::cast_precision_loss)]
+            let weight = (step + 1) as f32;
+            let target = vertices[(source + step + 1) % 500];
+            (vertices[source], edge_type, target, true, weight, 1_000)
+        })
+        .collect();
+    let deleted = (vertices[0], "likes", vertices[1]);
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        graph.add_edges_batch(&edges, false).unwrap();
+        graph
+            .remove_edge(&deleted.0, deleted.1, &deleted.2)
+            .unwrap();
+
+        assert_eq!(graph.rescale_weights("likes", 0.5).unwrap(), 24_999);
+        assert!(matches!(
+            graph.rescale_weights("likes", f32::NAN),
+            Err(GraphError::InvalidArgument(_))
+        ));
+
+        // Deactivate the heaviest "follows" edges
+        let changed = graph
+            .for_each_edge_mut(
+                |edge| edge.edge_type == "follows",
+                |active, weight| *active = *weight <= 50.0,
+            )
+            .unwrap();
+        assert_eq!(changed, 12_500);
+
+        // A non-finite weight aborts the whole update
+        let err = graph
+            .for_each_edge_mut(|_| true, |_, weight| *weight *= f32::MAX)
+            .unwrap_err();
+        assert!(matches!(err, GraphError::NonFiniteWeight { .. }));
+
+        // Reversing an edge onto an existing one is rejected
+        graph
+            .add_edge(&vertices[1], "likes", &vertices[0], true, 1.0, None)
+            .unwrap();
+        let err = graph
+            .reverse_edges(|edge| edge.source == vertices[0] && edge.edge_type == "likes")
+            .unwrap_err();
+        assert!(matches!(err, GraphError::InvalidArgument(_)));
+        graph
+            .hard_delete_edge(&vertices[1], "likes", &vertices[0])
+            .unwrap();
+
+        assert_eq!(
+            graph
+                .reverse_edges(|edge| edge.edge_type == "follows")
+                .unwrap(),
+            25_000
+        );
+
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    let forward = forward_view(&graph);
+    assert_eq!(forward.len(), 50_000);
+    assert_eq!(forward, reverse_view(&graph, &vertices));
+
+    // The soft-deleted edge kept its weight
+    let (_, (_, weight, created_at, deleted_at)) = forward
+        .iter()
+        .find(|((source, edge_type, target), _)| (*source, edge_type.as_str(), *target) == deleted)
+        .unwrap();
+    assert_eq!(f32::from_bits(*weight), 1.0);
+    assert_eq!(*created_at, 1_000);
+    assert_ne!(*deleted_at, 0);
+
+    for (source, edge_type, target, _, weight, _) in &edges {
+        if (*source, *edge_type, *target) == deleted {
+            continue;
+        }
+        if *edge_type == "likes" {
+            let key = (*source, (*edge_type).to_string(), *target);
+            assert_eq!(f32::from_bits(forward[&key].1), weight * 0.5);
+        } else {
+            assert!(!forward.contains_key(&(*source, (*edge_type).to_string(), *target)));
+            let key = (*target, (*edge_type).to_string(), *source);
+            let (active, stored, _, _) = forward[&key];
+            assert_eq!(f32::from_bits(stored), *weight);
+            assert_eq!(active, *weight <= 50.0);
+        }
+    }
+}
+
+#[test]
+fn test_time_ranged_edge_queries() {
+    const DAY: u64 = 86_400_000_000_000;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let alice = Uuid::from_u128(1);
+    let bob = Uuid::from_u128(2);
+    let carol = Uuid::from_u128(3);
+    let dave = Uuid::from_u128(4);
+    let now = 30 * DAY;
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        graph
+            .add_edge(&alice, "follows", &bob, true, 1.0, Some(now - 20 * DAY))
+            .unwrap();
+        graph
+            .add_edge(&alice, "follows", &carol, true, 1.0, Some(now - 7 * DAY))
+            .unwrap();
+        graph
+            .add_edge(&alice, "likes", &dave, true, 1.0, Some(now - DAY))
+            .unwrap();
+        graph
+            .add_edge(&alice, "blocks", &dave, true, 1.0, Some(now - DAY))
+            .unwrap();
+        graph
+            .add_edge(&bob, "follows", &carol, true, 1.0, Some(now - 3 * DAY))
+            .unwrap();
+        graph
+            .add_edge(&carol, "follows", &alice, true, 1.0, Some(now))
+            .unwrap();
+
+        // Updating an old edge keeps its creation time, re-adding one replaces it
+        graph
+            .update_edge(&alice, "follows", &bob, false, 2.0)
+            .unwrap();
+        graph
+            .add_edge(&bob, "follows", &alice, true, 1.0, Some(now - 10 * DAY))
+            .unwrap();
+        graph
+            .add_edge(&bob, "follows", &alice, true, 1.0, Some(now - 2 * DAY))
+            .unwrap();
+
+        // Soft-deleted edges are not returned
+        graph.remove_edge(&alice, "blocks", &dave).unwrap();
+
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    let since = |source: &Uuid, min| -> Vec<(String, Uuid)> {
+        graph
+            .outgoing_edges_since(source, min)
+            .unwrap()
+            .map(|edge| {
+                let edge = edge.unwrap();
+                (edge.edge_type, edge.target)
+            })
+            .collect()
+    };
+
+    // Edges created in the last 7 days, with an inclusive lower bound
+    assert_eq!(
+        since(&alice, now - 7 * DAY),
+        [("follows".to_string(), carol), ("likes".to_string(), dave)]
+    );
+    let edge = graph
+        .outgoing_edges_since(&alice, now - DAY)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(edge.created_at, now - DAY);
+    assert_eq!(
+        since(&alice, 0).len(),
+        3,
+        "updated edge kept its creation time"
+    );
+    assert!(since(&alice, now + 1).is_empty());
+    assert_eq!(since(&bob, now - 2 * DAY), [("follows".to_string(), alice)]);
+
+    let in_range = |min, max| -> Vec<u64> {
+        graph
+            .all_edges_in_range(min, max)
+            .unwrap()
+            .map(|edge| edge.unwrap().created_at)
+            .collect()
+    };
+    assert_eq!(
+        in_range(now - 7 * DAY, now),
+        [now - 7 * DAY, now - DAY, now - 2 * DAY, now - 3 * DAY]
+    );
+    assert_eq!(in_range(now - 21 * DAY, now - 7 * DAY), [now - 20 * DAY]);
+    assert_eq!(in_range(now, now + 1), [now]);
+    assert!(in_range(now, now).is_empty());
+    assert!(matches!(
+        graph.all_edges_in_range(now, now - 1),
+        Err(GraphError::InvalidArgument(_))
+    ));
+}
+
+/// Writes a graph whose forward and reverse tables disagree on three edges
+fn write_inconsistent_graph(cf: &manifold::column_family::ColumnFamily, vertices: &[Uuid]) {
+    let (a, b, c) = (vertices[0], vertices[1], vertices[2]);
+    let write_txn = cf.begin_write().unwrap();
+    {
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        graph
+            .add_edge(&a, "follows", &b, true, 1.0, Some(10))
+            .unwrap();
+        graph
+            .add_edge(&b, "follows", &c, true, 1.0, Some(20))
+            .unwrap();
+    }
+    {
+        let forward_def: RawEdgeTable = TableDefinition::new("edges_forward");
+        let reverse_def: RawEdgeTable = TableDefinition::new("edges_reverse");
+        let mut forward = write_txn.open_table(forward_def).unwrap();
+        let mut reverse = write_txn.open_table(reverse_def).unwrap();
+
+        // a -likes-> c only in the forward table
+        forward
+            .insert(&(a, "likes", c), &(true, 0.5, 30, 0))
+            .unwrap();
+        // c -knows-> a only in the reverse table
+        reverse
+            .insert(&(a, "knows", c), &(true, 0.25, 40, 0))
+            .unwrap();
+        // b -follows-> c has a different weight in the reverse table
+        reverse
+            .insert(&(c, "follows", b), &(true, 5.0, 20, 0))
+            .unwrap();
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn test_consistency_check_and_repair() {
+    use manifold_graph::{Inconsistency, InconsistencyKind, RepairStrategy};
+
+    let vertices: Vec<Uuid> = (1..=3).map(Uuid::from_u128).collect();
+    let (a, b, c) = (vertices[0], vertices[1], vertices[2]);
+    let inconsistency = |kind, source, edge_type: &str, target| Inconsistency {
+        kind,
+        source,
+        edge_type: edge_type.to_string(),
+        target,
+    };
+
+    for (strategy, expected) in [
+        (
+            RepairStrategy::PreferForward,
+            vec![
+                (a, "follows", b, 1.0),
+                (a, "likes", c, 0.5),
+                (b, "follows", c, 1.0),
+            ],
+        ),
+        (
+            RepairStrategy::PreferReverse,
+            vec![
+                (a, "follows", b, 1.0),
+                (b, "follows", c, 5.0),
+                (c, "knows", a, 0.25),
+            ],
+        ),
+        (
+            RepairStrategy::DropOrphans,
+            vec![(a, "follows", b, 1.0), (b, "follows", c, 1.0)],
+        ),
+    ] {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+        let cf = db.column_family_or_create("test").unwrap();
+        write_inconsistent_graph(&cf, &vertices);
+
+        // Detected from a read transaction
+        {
+            let read_txn = cf.begin_read().unwrap();
+            let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+            let report = graph.verify_consistency().unwrap();
+            assert!(!report.is_consistent());
+            assert_eq!((report.forward_edges, report.reverse_edges), (3, 3));
+            assert_eq!(
+                (report.forward_only, report.reverse_only, report.mismatched),
+                (1, 1, 1)
+            );
+            assert_eq!(
+                report.samples,
+                [
+                    inconsistency(InconsistencyKind::ForwardOnly, a, "likes", c),
+                    inconsistency(InconsistencyKind::Mismatched, b, "follows", c),
+                    inconsistency(InconsistencyKind::ReverseOnly, c, "knows", a),
+                ]
+            );
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+            let before = graph.verify_consistency().unwrap();
+            assert_eq!(graph.repair(strategy).unwrap(), before);
+            assert!(
+                graph.verify_consistency().unwrap().is_consistent(),
+                "{strategy:?}"
+            );
+            drop(graph);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+        let report = graph.verify_consistency().unwrap();
+        assert!(report.is_consistent());
+        assert!(report.samples.is_empty());
+
+        let forward = forward_view(&graph);
+        assert_eq!(forward, reverse_view(&graph, &vertices), "{strategy:?}");
+        let mut edges: Vec<(Uuid, String, Uuid, f32)> = forward
+            .into_iter()
+            .map(|((source, edge_type, target), (_, weight, _, _))| {
+                (source, edge_type, target, f32::from_bits(weight))
+            })
+            .collect();
+        edges.sort_by(|x, y| (x.0, &x.1, x.2).cmp(&(y.0, &y.1, y.2)));
+        let expected: Vec<(Uuid, String, Uuid, f32)> = expected
+            .into_iter()
+            .map(|(source, edge_type, target, weight)| {
+                (source, edge_type.to_string(), target, weight)
+            })
+            .collect();
+        assert_eq!(edges, expected, "{strategy:?}");
+    }
+}
+
+#[test]
+fn test_consistency_report_samples_are_bounded() {
+    use manifold_graph::consistency::MAX_SAMPLES;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let orphans = MAX_SAMPLES as u128 + 50;
+    {
+        let write_txn = cf.begin_write().unwrap();
+        {
+            // Creates both tables
+            let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+            graph
+                .add_edge(&Uuid::nil(), "follows", &Uuid::max(), true, 1.0, None)
+                .unwrap();
+        }
+        let forward_def: RawEdgeTable = TableDefinition::new("edges_forward");
+        let mut forward = write_txn.open_table(forward_def).unwrap();
+        for i in 0..orphans {
+            forward
+                .insert(
+                    &(Uuid::from_u128(i + 1), "follows", Uuid::nil()),
+                    &(true, 1.0, 0, 0),
+                )
+                .unwrap();
+        }
+        drop(forward);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    let report = graph.verify_consistency().unwrap();
+    assert_eq!(report.forward_only, orphans as u64);
+    assert_eq!(report.samples.len(), MAX_SAMPLES);
+    assert_eq!(report.samples[0].source, Uuid::from_u128(1));
+}
+
+#[test]
+fn test_conditional_edge_updates() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let (user1, user2, user3) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        graph
+            .add_edge(&user1, "follows", &user2, true, 0.5, Some(100))
+            .unwrap();
+        graph
+            .add_edge(&user1, "follows", &user3, true, 0.5, Some(100))
+            .unwrap();
+        graph.remove_edge(&user1, "follows", &user3).unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    // Another job writes a different weight after the edge was read
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        graph
+            .update_edge(&user1, "follows", &user2, true, 0.7)
+            .unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+        let result = graph
+            .update_edge_if(&user1, "follows", &user2, (true, 0.5), (true, 0.9))
+            .unwrap();
+        assert_eq!(
+            result,
+            CasResult::Conflict {
+                actual: (true, 0.7)
+            }
+        );
+
+        // Weights must match bit for bit
+        let result = graph
+            .update_edge_if(
+                &user1,
+                "follows",
+                &user2,
+                (true, 0.7 + f32::EPSILON),
+                (true, 0.9),
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            CasResult::Conflict {
+                actual: (true, 0.7)
+            }
+        );
+        let result = graph
+            .update_edge_if(&user1, "follows", &user2, (false, 0.7), (true, 0.9))
+            .unwrap();
+        assert_eq!(
+            result,
+            CasResult::Conflict {
+                actual: (true, 0.7)
+            }
+        );
+
+        // Absent and soft-deleted edges are missing
+        let result = graph
+            .update_edge_if(&user2, "follows", &user1, (true, 0.7), (true, 0.9))
+            .unwrap();
+        assert_eq!(result, CasResult::Missing);
+        let result = graph
+            .update_edge_if(&user1, "follows", &user3, (true, 0.5), (true, 0.9))
+            .unwrap();
+        assert_eq!(result, CasResult::Missing);
+
+        let result = graph
+            .update_edge_if(&user1, "follows", &user2, (true, 0.7), (false, 0.25))
+            .unwrap();
+        assert_eq!(result, CasResult::Updated);
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    let edge = graph.get_edge(&user1, "follows", &user2).unwrap().unwrap();
+    assert_eq!(
+        (edge.is_active, edge.weight, edge.created_at),
+        (false, 0.25, 100)
+    );
+    let incoming = graph
+        .incoming_edges(&user2)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!((incoming.is_active, incoming.weight), (false, 0.25));
+    assert!(graph.get_edge(&user1, "follows", &user3).unwrap().is_none());
+}
+
+#[test]
+fn test_adjust_weight() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let (user1, user2) = (Uuid::from_u128(1), Uuid::from_u128(2));
+    let write_txn = cf.begin_write().unwrap();
+    let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+    graph
+        .add_edge(&user1, "follows", &user2, false, 1.0, Some(100))
+        .unwrap();
+
+    assert_eq!(
+        graph.adjust_weight(&user1, "follows", &user2, 0.5).unwrap(),
+        Some(1.5)
+    );
+    assert_eq!(
+        graph
+            .adjust_weight(&user1, "follows", &user2, -2.0)
+            .unwrap(),
+        Some(-0.5)
+    );
+    assert_eq!(
+        graph.adjust_weight(&user2, "follows", &user1, 1.0).unwrap(),
+        None
+    );
+    assert!(matches!(
+        graph.adjust_weight(&user1, "follows", &user2, f32::INFINITY),
+        Err(GraphError::NonFiniteWeight { weight, .. }) if weight.is_infinite()
+    ));
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+    drop(graph);
+    write_txn.commit().unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    let edge = graph.get_edge(&user1, "follows", &user2).unwrap().unwrap();
+    assert_eq!(
+        (edge.is_active, edge.weight, edge.created_at),
+        (false, -0.5, 100)
+    );
+}
+
+#[test]
+fn test_edge_type_length_limit() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let (user1, user2) = (Uuid::from_u128(1), Uuid::from_u128(2));
+    let at_limit = "t".repeat(DEFAULT_MAX_EDGE_TYPE_LEN);
+    let over_limit = "t".repeat(DEFAULT_MAX_EDGE_TYPE_LEN + 1);
+    let too_long = |result: Result<(), GraphError>| {
+        assert!(matches!(
+            result,
+            Err(GraphError::EdgeTypeTooLong { len, max: DEFAULT_MAX_EDGE_TYPE_LEN })
+                if len == DEFAULT_MAX_EDGE_TYPE_LEN + 1
+        ));
+    };
+
+    let write_txn = cf.begin_write().unwrap();
+    let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+    assert_eq!(graph.max_edge_type_len(), DEFAULT_MAX_EDGE_TYPE_LEN);
+    graph
+        .add_edge(&user1, &at_limit, &user2, true, 1.0, Some(100))
+        .unwrap();
+    graph
+        .add_edges_batch(&[(user2, at_limit.as_str(), user1, true, 1.0, 100)], false)
+        .unwrap();
+
+    too_long(graph.add_edge(&user1, &over_limit, &user2, true, 1.0, None));
+    too_long(graph.update_edge(&user1, &over_limit, &user2, true, 1.0));
+    too_long(
+        graph
+            .add_edges_batch(
+                &[
+                    (user1, "follows", user2, true, 1.0, 100),
+                    (user1, over_limit.as_str(), user2, true, 1.0, 100),
+                ],
+                false,
+            )
+            .map(|_| ()),
+    );
+    let edges = [
+        EdgeInput::new(user1, "follows", user2, true, 1.0),
+        EdgeInput::new(user1, over_limit.as_str(), user2, true, 1.0),
+    ];
+    too_long(
+        graph
+            .add_edges_from(edges, ImportOptions::new())
+            .map(|_| ()),
+    );
+    drop(graph);
+    write_txn.commit().unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    assert_eq!(graph.len().unwrap(), 2);
+    assert!(graph.get_edge(&user1, &at_limit, &user2).unwrap().is_some());
+
+    let write_txn = cf.begin_write().unwrap();
+    let mut graph = GraphTable::open(&write_txn, "edges")
+        .unwrap()
+        .with_max_edge_type_len(7);
+    graph
+        .add_edge(&user1, "follows", &user2, true, 1.0, None)
+        .unwrap();
+    assert!(matches!(
+        graph.add_edge(&user1, "follows!", &user2, true, 1.0, None),
+        Err(GraphError::EdgeTypeTooLong { len: 8, max: 7 })
+    ));
+}
+
+/// Identifies an edge and its weight bits, so NaN weights compare equal
+fn ranked_key(edge: &Edge) -> (String, Uuid, u32) {
+    (edge.edge_type.clone(), edge.target, edge.weight.to_bits())
+}
+
+#[test]
+fn test_top_n_by_weight_matches_full_sort() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    // A hub with many repeated, negative and NaN weights, and a few soft-deleted edges
+    let hub = Uuid::from_u128(u128::MAX);
+    let edges: Vec<_> = (0..20_000u32)
+        .map(|i| {
+            let edge_type = if i % 3 == 0 { "likes" } else { "follows" };
+            let weight = if i % 997 == 0 {
+                f32::NAN
+            } else {
+                ((i * 7919) % 2003) as f32 / 10.0 - 100.0
+            };
+            (
+                hub,
+                edge_type,
+                Uuid::from_u128(u128::from(i)),
+                true,
+                weight,
+                100,
+            )
+        })
+        .collect();
+
+    let write_txn = cf.begin_write().unwrap();
+    let mut graph = GraphTable::open(&write_txn, "edges").unwrap();
+    graph.add_edges_batch(&edges, false).unwrap();
+    graph
+        .add_edge(&Uuid::from_u128(1), "likes", &hub, true, 1000.0, Some(100))
+        .unwrap();
+    for i in [0u128, 5, 42, 1234] {
+        graph
+            .remove_edge(&hub, edges[i as usize].1, &Uuid::from_u128(i))
+            .unwrap();
+    }
+    drop(graph);
+    write_txn.commit().unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "edges").unwrap();
+    for edge_type in [None, Some("likes"), Some("missing")] {
+        let mut expected: Vec<Edge> = graph
+            .outgoing_edges(&hub)
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|edge| edge_type.is_none_or(|edge_type| edge.edge_type == edge_type))
+            .collect();
+        // Stable sort, so equal weights keep their table order
+        expected.sort_by(|a, b| match (a.weight.is_nan(), b.weight.is_nan()) {
+            (true, true) => std::cmp::Ordering::Equal,
+            (true, false) => std::cmp::Ordering::Greater,
+            (false, true) => std::cmp::Ordering::Less,
+            (false, false) => b.weight.total_cmp(&a.weight),
+        });
+
+        for n in [0, 1, 20, 500, 30_000] {
+            let top = graph.top_n_by_weight(&hub, n, edge_type).unwrap();
+            let expected_keys: Vec<_> = expected.iter().take(n).map(ranked_key).collect();
+            let top_keys: Vec<_> = top.iter().map(ranked_key).collect();
+            assert_eq!(top_keys, expected_keys, "n = {n}, edge type {edge_type:?}");
+        }
+    }
+
+    // NaN weights come last, after every negative weight
+    let all = graph.top_n_by_weight(&hub, usize::MAX, None).unwrap();
+    assert_eq!(all.len(), 20_000 - 4);
+    assert!(all.last().unwrap().weight.is_nan());
+    assert_eq!(all.iter().filter(|edge| edge.weight.is_nan()).count(), 20);
+}
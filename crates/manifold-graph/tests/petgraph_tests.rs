@@ -0,0 +1,77 @@
+//! Tests for exporting edge sources to petgraph
+
+#![cfg(feature = "petgraph")]
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_graph::integration::to_petgraph;
+use manifold_graph::{EdgeFilter, GraphTable, GraphTableRead};
+use petgraph::algo::dijkstra;
+use uuid::Uuid;
+
+#[test]
+fn test_to_petgraph_dijkstra() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    let [a, b, c, d, e] = [(); 5].map(|()| Uuid::new_v4());
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::open(&write_txn, "roads").unwrap();
+
+        graph.add_edge(&a, "road", &b, true, 4.0, None).unwrap();
+        graph.add_edge(&a, "road", &c, true, 1.0, None).unwrap();
+        graph.add_edge(&c, "road", &b, true, 2.0, None).unwrap();
+        graph.add_edge(&b, "road", &d, true, 5.0, None).unwrap();
+        graph.add_edge(&c, "road", &d, true, 8.0, None).unwrap();
+        // A closed road and a ferry, both shorter than any road route to d
+        graph.add_edge(&a, "road", &d, false, 1.0, None).unwrap();
+        graph.add_edge(&c, "ferry", &d, true, 1.0, None).unwrap();
+        // A soft-deleted road to an otherwise unreachable vertex
+        graph.add_edge(&d, "road", &e, true, 1.0, None).unwrap();
+        graph.remove_edge(&d, "road", &e).unwrap();
+
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::open(&read_txn, "roads").unwrap();
+
+    // Only active, non-deleted roads: a -> c -> b -> d is 1 + 2 + 5
+    let (roads, nodes) = to_petgraph(&graph, &EdgeFilter::edge_type("road")).unwrap();
+    assert_eq!(roads.node_count(), 4);
+    assert_eq!(roads.edge_count(), 5);
+    assert!(!nodes.contains_key(&e));
+    for (id, &index) in &nodes {
+        assert_eq!(roads[index], *id);
+    }
+
+    let distances = dijkstra(&roads, nodes[&a], None, |edge| *edge.weight());
+    assert_eq!(distances[&nodes[&a]], 0.0);
+    assert_eq!(distances[&nodes[&c]], 1.0);
+    assert_eq!(distances[&nodes[&b]], 3.0);
+    assert_eq!(distances[&nodes[&d]], 8.0);
+
+    // All edge types: the ferry gives a -> c -> d as 1 + 1
+    let (all, nodes) = to_petgraph(&graph, &EdgeFilter::all()).unwrap();
+    assert_eq!(all.edge_count(), 6);
+    let distances = dijkstra(&all, nodes[&a], None, |edge| *edge.weight());
+    assert_eq!(distances[&nodes[&d]], 2.0);
+
+    // Including inactive edges adds the closed road a -> d
+    let filter = EdgeFilter {
+        edge_type: Some("road".to_string()),
+        include_inactive: true,
+    };
+    let (with_closed, nodes) = to_petgraph(&graph, &filter).unwrap();
+    assert_eq!(with_closed.edge_count(), 6);
+    let distances = dijkstra(&with_closed, nodes[&a], None, |edge| *edge.weight());
+    assert_eq!(distances[&nodes[&d]], 1.0);
+
+    // No matching edges gives an empty graph
+    let (empty, nodes) = to_petgraph(&graph, &EdgeFilter::edge_type("rail")).unwrap();
+    assert_eq!(empty.node_count(), 0);
+    assert!(nodes.is_empty());
+}
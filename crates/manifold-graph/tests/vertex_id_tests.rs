@@ -0,0 +1,212 @@
+//! Tests for graphs with non-UUID vertex ids
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold::{ReadableTableMetadata, TableDefinition};
+use manifold_graph::{Edge, EdgeCursor, GraphError, GraphTable, GraphTableRead, UuidGraphTable};
+use uuid::Uuid;
+
+/// The raw forward or reverse table behind a `GraphTable<V>`.
+type RawEdgeTable<'a, V> = TableDefinition<'a, (V, &'static str, V), (bool, f32, u64, u64)>;
+
+/// Collects `(edge_type, target)` of each edge
+fn targets(edges: impl Iterator<Item = Result<Edge<u64>, GraphError<u64>>>) -> Vec<(String, u64)> {
+    edges
+        .map(|edge| {
+            let edge = edge.unwrap();
+            (edge.edge_type, edge.target)
+        })
+        .collect()
+}
+
+#[test]
+fn test_u64_vertex_traversal_and_deletion() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        graph
+            .add_edge(&1, "follows", &3, true, 1.0, Some(100))
+            .unwrap();
+        graph
+            .add_edge(&1, "follows", &2, true, 0.5, Some(100))
+            .unwrap();
+        graph
+            .add_edge(&1, "likes", &4, true, 1.0, Some(100))
+            .unwrap();
+        graph
+            .add_edge(&2, "follows", &3, true, 1.0, Some(100))
+            .unwrap();
+        // Little-endian bytes of 256 sort before those of 1, numeric order does not
+        graph
+            .add_edge(&10, "follows", &256, true, 1.0, None)
+            .unwrap();
+        graph.add_edge(&10, "follows", &1, true, 1.0, None).unwrap();
+        // Ids at both ends of the range are inside the scan bounds
+        graph
+            .add_edge(&u64::MAX, "follows", &0, true, 1.0, None)
+            .unwrap();
+        graph
+            .add_edge(&0, "follows", &u64::MAX, true, 1.0, None)
+            .unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let graph = GraphTableRead::<u64>::open_typed(&read_txn, "edges").unwrap();
+        assert_eq!(graph.len().unwrap(), 8);
+
+        let follows = |target| ("follows".to_string(), target);
+        assert_eq!(
+            targets(graph.outgoing_edges(&1).unwrap()),
+            [follows(2), follows(3), ("likes".to_string(), 4)]
+        );
+        assert_eq!(
+            targets(graph.outgoing_edges(&10).unwrap()),
+            [follows(1), follows(256)]
+        );
+        assert_eq!(
+            targets(graph.outgoing_edges(&u64::MAX).unwrap()),
+            [follows(0)]
+        );
+        assert_eq!(
+            targets(graph.outgoing_edges(&0).unwrap()),
+            [follows(u64::MAX)]
+        );
+
+        let sources: Vec<u64> = graph
+            .incoming_edges(&3)
+            .unwrap()
+            .map(|edge| edge.unwrap().source)
+            .collect();
+        assert_eq!(sources, [1, 2]);
+
+        let sources: Vec<u64> = graph
+            .all_edges()
+            .unwrap()
+            .map(|edge| edge.unwrap().source)
+            .collect();
+        assert_eq!(sources, [0, 1, 1, 1, 2, 10, 10, u64::MAX]);
+
+        let edge = graph.get_edge(&1, "follows", &2).unwrap().unwrap();
+        assert_eq!((edge.source, edge.target, edge.weight), (1, 2, 0.5));
+    }
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        graph.remove_edge(&1, "follows", &2).unwrap();
+        graph.hard_delete_edge(&2, "follows", &3).unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::<u64>::open_typed(&read_txn, "edges").unwrap();
+    assert_eq!(graph.len().unwrap(), 7);
+
+    // The soft-deleted edge is only visible to temporal and deleted-inclusive queries
+    assert!(graph.get_edge(&1, "follows", &2).unwrap().is_none());
+    assert!(graph.get_edge_at(&1, "follows", &2, 150).unwrap().is_some());
+    assert_eq!(graph.outgoing_edges(&1).unwrap().count(), 2);
+    assert_eq!(graph.outgoing_edges_with_deleted(&1).unwrap().count(), 3);
+
+    // The hard-deleted edge is gone from both directions
+    assert!(graph.get_edge(&2, "follows", &3).unwrap().is_none());
+    let sources: Vec<u64> = graph
+        .incoming_edges_with_deleted(&3)
+        .unwrap()
+        .map(|edge| edge.unwrap().source)
+        .collect();
+    assert_eq!(sources, [1]);
+    assert!(graph.verify_consistency().unwrap().is_consistent());
+}
+
+#[test]
+fn test_u64_vertex_pagination() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut graph = GraphTable::<u64>::open_typed(&write_txn, "edges").unwrap();
+        let edges: Vec<(u64, &str, u64, bool, f32, u64)> = (0..25)
+            .map(|i| (i % 5, "follows", i, true, 1.0, 100))
+            .collect();
+        graph.add_edges_batch(&edges, false).unwrap();
+        drop(graph);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let graph = GraphTableRead::<u64>::open_typed(&read_txn, "edges").unwrap();
+    let mut cursor: Option<EdgeCursor<u64>> = None;
+    let mut seen = vec![];
+    loop {
+        let (page, next) = graph.all_edges_page(cursor.as_ref(), 7).unwrap();
+        seen.extend(page.iter().map(|edge| (edge.source, edge.target)));
+        let Some(next) = next else { break };
+        // Cursors of u64 graphs hold two 8-byte ids
+        let bytes = next.to_bytes();
+        assert_eq!(bytes.len(), 16 + "follows".len());
+        cursor = Some(EdgeCursor::from_bytes(&bytes).unwrap());
+    }
+    let mut expected: Vec<(u64, u64)> = (0..25).map(|i| (i % 5, i)).collect();
+    expected.sort_unstable();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn test_u64_vertex_keys_are_smaller_than_uuid_keys() {
+    const EDGES: u64 = 500;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("graph.db")).unwrap();
+    let cf = db.column_family_or_create("test").unwrap();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut uuid_graph = UuidGraphTable::open(&write_txn, "uuid_edges").unwrap();
+        let mut u64_graph = GraphTable::<u64>::open_typed(&write_txn, "u64_edges").unwrap();
+        for i in 0..EDGES {
+            let (source, target) = (i / 10, i);
+            uuid_graph
+                .add_edge(
+                    &Uuid::from_u128(source.into()),
+                    "follows",
+                    &Uuid::from_u128(target.into()),
+                    true,
+                    1.0,
+                    Some(100),
+                )
+                .unwrap();
+            u64_graph
+                .add_edge(&source, "follows", &target, true, 1.0, Some(100))
+                .unwrap();
+        }
+        drop((uuid_graph, u64_graph));
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    for direction in ["forward", "reverse"] {
+        let uuid_name = format!("uuid_edges_{direction}");
+        let uuid_def: RawEdgeTable<Uuid> = TableDefinition::new(&uuid_name);
+        let u64_name = format!("u64_edges_{direction}");
+        let u64_def: RawEdgeTable<u64> = TableDefinition::new(&u64_name);
+        let uuid_table = read_txn.open_table(uuid_def).unwrap();
+        let u64_table = read_txn.open_table(u64_def).unwrap();
+        assert_eq!(uuid_table.len().unwrap(), EDGES);
+        assert_eq!(u64_table.len().unwrap(), EDGES);
+
+        // Each key holds two ids, 8 bytes each instead of 16
+        let uuid_bytes = uuid_table.stats().unwrap().stored_bytes();
+        let u64_bytes = u64_table.stats().unwrap().stored_bytes();
+        assert_eq!(uuid_bytes - u64_bytes, EDGES * 16);
+    }
+}
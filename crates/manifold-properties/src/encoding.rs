@@ -17,27 +17,35 @@ pub enum PropertyValueRef<'a> {
         value: i64,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// 64-bit floating point value
     Float {
         value: f64,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// Boolean value
     Boolean {
         value: bool,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// String value (borrowed from underlying storage)
     String {
         value: &'a str,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// Null value
-    Null { updated_at: u64, valid_from: u64 },
+    Null {
+        updated_at: u64,
+        valid_from: u64,
+        expires_at: Option<u64>,
+    },
 }
 
 impl<'a> PropertyValueRef<'a> {
@@ -111,6 +119,23 @@ impl<'a> PropertyValueRef<'a> {
         }
     }
 
+    /// Returns the expires_at timestamp for this property, if it has one.
+    pub fn expires_at(&self) -> Option<u64> {
+        match self {
+            Self::Integer { expires_at, .. } => *expires_at,
+            Self::Float { expires_at, .. } => *expires_at,
+            Self::Boolean { expires_at, .. } => *expires_at,
+            Self::String { expires_at, .. } => *expires_at,
+            Self::Null { expires_at, .. } => *expires_at,
+        }
+    }
+
+    /// Returns true if this property has expired at the given timestamp.
+    pub fn is_expired_at(&self, timestamp: u64) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at <= timestamp)
+    }
+
     /// Converts this reference to an owned PropertyValue.
     pub fn to_owned(&self) -> PropertyValue {
         match self {
@@ -118,44 +143,54 @@ impl<'a> PropertyValueRef<'a> {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValue::Integer {
                 value: *value,
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::Float {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValue::Float {
                 value: *value,
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::Boolean {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValue::Boolean {
                 value: *value,
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::String {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValue::String {
                 value: value.to_string(),
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::Null {
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValue::Null {
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
         }
     }
@@ -168,6 +203,28 @@ const DISCRIMINANT_BOOLEAN: u8 = 2;
 const DISCRIMINANT_STRING: u8 = 3;
 const DISCRIMINANT_NULL: u8 = 4;
 
+/// Set on the discriminant when an expires_at timestamp (8 bytes) follows it.
+///
+/// Values without an expiry are encoded exactly as before expiry was supported.
+const EXPIRES_AT_FLAG: u8 = 0x80;
+
+/// Starts an encoded value with its discriminant and, if present, its expiry.
+fn encode_header(discriminant: u8, expires_at: Option<u64>, payload_len: usize) -> Vec<u8> {
+    match expires_at {
+        Some(expires_at) => {
+            let mut bytes = Vec::with_capacity(9 + payload_len);
+            bytes.push(discriminant | EXPIRES_AT_FLAG);
+            bytes.extend_from_slice(&expires_at.to_le_bytes());
+            bytes
+        }
+        None => {
+            let mut bytes = Vec::with_capacity(1 + payload_len);
+            bytes.push(discriminant);
+            bytes
+        }
+    }
+}
+
 impl Value for PropertyValue {
     type SelfType<'a> = PropertyValueRef<'a>;
     type AsBytes<'a> = Vec<u8>;
@@ -185,8 +242,15 @@ impl Value for PropertyValue {
             panic!("Cannot deserialize PropertyValue from empty data");
         }
 
-        let discriminant = data[0];
-        let payload = &data[1..];
+        let (discriminant, expires_at, payload) = if data[0] & EXPIRES_AT_FLAG != 0 {
+            if data.len() < 9 {
+                panic!("Invalid expires_at length");
+            }
+            let expires_at = u64::from_le_bytes(data[1..9].try_into().unwrap());
+            (data[0] & !EXPIRES_AT_FLAG, Some(expires_at), &data[9..])
+        } else {
+            (data[0], None, &data[1..])
+        };
 
         match discriminant {
             DISCRIMINANT_INTEGER => {
@@ -200,6 +264,7 @@ impl Value for PropertyValue {
                     value,
                     updated_at,
                     valid_from,
+                    expires_at,
                 }
             }
             DISCRIMINANT_FLOAT => {
@@ -213,6 +278,7 @@ impl Value for PropertyValue {
                     value,
                     updated_at,
                     valid_from,
+                    expires_at,
                 }
             }
             DISCRIMINANT_BOOLEAN => {
@@ -226,6 +292,7 @@ impl Value for PropertyValue {
                     value,
                     updated_at,
                     valid_from,
+                    expires_at,
                 }
             }
             DISCRIMINANT_STRING => {
@@ -241,6 +308,7 @@ impl Value for PropertyValue {
                     value,
                     updated_at,
                     valid_from,
+                    expires_at,
                 }
             }
             DISCRIMINANT_NULL => {
@@ -252,6 +320,7 @@ impl Value for PropertyValue {
                 PropertyValueRef::Null {
                     updated_at,
                     valid_from,
+                    expires_at,
                 }
             }
             _ => panic!("Invalid PropertyValue discriminant: {}", discriminant),
@@ -267,9 +336,9 @@ impl Value for PropertyValue {
                 value: v,
                 updated_at,
                 valid_from,
+                expires_at,
             } => {
-                let mut bytes = Vec::with_capacity(25);
-                bytes.push(DISCRIMINANT_INTEGER);
+                let mut bytes = encode_header(DISCRIMINANT_INTEGER, *expires_at, 24);
                 bytes.extend_from_slice(&v.to_le_bytes());
                 bytes.extend_from_slice(&updated_at.to_le_bytes());
                 bytes.extend_from_slice(&valid_from.to_le_bytes());
@@ -279,9 +348,9 @@ impl Value for PropertyValue {
                 value: v,
                 updated_at,
                 valid_from,
+                expires_at,
             } => {
-                let mut bytes = Vec::with_capacity(25);
-                bytes.push(DISCRIMINANT_FLOAT);
+                let mut bytes = encode_header(DISCRIMINANT_FLOAT, *expires_at, 24);
                 bytes.extend_from_slice(&v.to_le_bytes());
                 bytes.extend_from_slice(&updated_at.to_le_bytes());
                 bytes.extend_from_slice(&valid_from.to_le_bytes());
@@ -291,9 +360,9 @@ impl Value for PropertyValue {
                 value: v,
                 updated_at,
                 valid_from,
+                expires_at,
             } => {
-                let mut bytes = Vec::with_capacity(18);
-                bytes.push(DISCRIMINANT_BOOLEAN);
+                let mut bytes = encode_header(DISCRIMINANT_BOOLEAN, *expires_at, 17);
                 bytes.push(if *v { 1 } else { 0 });
                 bytes.extend_from_slice(&updated_at.to_le_bytes());
                 bytes.extend_from_slice(&valid_from.to_le_bytes());
@@ -303,10 +372,11 @@ impl Value for PropertyValue {
                 value: v,
                 updated_at,
                 valid_from,
+                expires_at,
             } => {
                 let str_bytes = v.as_bytes();
-                let mut bytes = Vec::with_capacity(1 + 16 + str_bytes.len());
-                bytes.push(DISCRIMINANT_STRING);
+                let mut bytes =
+                    encode_header(DISCRIMINANT_STRING, *expires_at, 16 + str_bytes.len());
                 bytes.extend_from_slice(&updated_at.to_le_bytes());
                 bytes.extend_from_slice(&valid_from.to_le_bytes());
                 bytes.extend_from_slice(str_bytes);
@@ -315,9 +385,9 @@ impl Value for PropertyValue {
             PropertyValueRef::Null {
                 updated_at,
                 valid_from,
+                expires_at,
             } => {
-                let mut bytes = Vec::with_capacity(17);
-                bytes.push(DISCRIMINANT_NULL);
+                let mut bytes = encode_header(DISCRIMINANT_NULL, *expires_at, 16);
                 bytes.extend_from_slice(&updated_at.to_le_bytes());
                 bytes.extend_from_slice(&valid_from.to_le_bytes());
                 bytes
@@ -339,44 +409,54 @@ impl PropertyValue {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValueRef::Integer {
                 value: *value,
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::Float {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValueRef::Float {
                 value: *value,
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::Boolean {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValueRef::Boolean {
                 value: *value,
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::String {
                 value,
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValueRef::String {
                 value: value.as_str(),
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
             Self::Null {
                 updated_at,
                 valid_from,
+                expires_at,
             } => PropertyValueRef::Null {
                 updated_at: *updated_at,
                 valid_from: *valid_from,
+                expires_at: *expires_at,
             },
         }
     }
@@ -452,6 +532,7 @@ mod tests {
             value: 42,
             updated_at: 1000,
             valid_from: 2000,
+            expires_at: None,
         });
         let ref_val = PropertyValue::from_bytes(&bytes);
         let owned = ref_val.to_owned();
@@ -484,4 +565,32 @@ mod tests {
         // 1 (discriminant) + 8 (updated_at) + 8 (valid_from) = 17 bytes
         assert_eq!(bytes.len(), 17);
     }
+
+    #[test]
+    fn test_expires_at_roundtrip() {
+        let values = [
+            PropertyValue::new_integer_with_timestamps(42, 1000, 2000),
+            PropertyValue::new_float_with_timestamps(2.5, 1000, 2000),
+            PropertyValue::new_boolean_with_timestamps(true, 1000, 2000),
+            PropertyValue::new_string_with_timestamps("hello", 1000, 2000),
+            PropertyValue::new_null_with_timestamps(1000, 2000),
+        ];
+
+        for original in values {
+            let plain = PropertyValue::as_bytes(&original.as_ref());
+            let expiring = original.clone().with_expires_at(Some(3000));
+            let bytes = PropertyValue::as_bytes(&expiring.as_ref());
+
+            // The expiry adds 8 bytes and leaves the rest of the encoding as it was
+            assert_eq!(bytes.len(), plain.len() + 8);
+            assert_eq!(bytes[0], plain[0] | EXPIRES_AT_FLAG);
+            assert_eq!(&bytes[9..], &plain[1..]);
+
+            let decoded = PropertyValue::from_bytes(&bytes);
+            assert_eq!(decoded.expires_at(), Some(3000));
+            assert!(decoded.is_expired_at(3000));
+            assert_eq!(decoded.to_owned(), expiring);
+            assert_eq!(PropertyValue::from_bytes(&plain).to_owned(), original);
+        }
+    }
 }
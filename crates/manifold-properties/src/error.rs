@@ -0,0 +1,71 @@
+//! Error type for property writes.
+
+use manifold::{StorageError, TableError};
+use std::fmt;
+
+/// Error returned by operations that write properties.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PropertyError {
+    /// A property key is longer than the table accepts, set with
+    /// [`PropertyTable::with_max_key_len`](crate::PropertyTable::with_max_key_len). Nothing
+    /// was written.
+    KeyTooLong {
+        /// Length of the property key in bytes.
+        len: usize,
+        /// Longest accepted property key in bytes.
+        max: usize,
+    },
+    /// A string value is longer than the table accepts, set with
+    /// [`PropertyTable::with_max_value_len`](crate::PropertyTable::with_max_value_len).
+    /// Nothing was written.
+    ValueTooLarge {
+        /// Length of the value in bytes.
+        len: usize,
+        /// Longest accepted value in bytes.
+        max: usize,
+    },
+    /// Error opening the underlying table.
+    Table(TableError),
+    /// Error reading or writing the underlying storage.
+    Storage(StorageError),
+}
+
+impl fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KeyTooLong { len, max } => write!(
+                f,
+                "Property key of {len} bytes exceeds the maximum of {max} bytes"
+            ),
+            Self::ValueTooLarge { len, max } => write!(
+                f,
+                "Property value of {len} bytes exceeds the maximum of {max} bytes"
+            ),
+            Self::Table(err) => write!(f, "Table error: {err}"),
+            Self::Storage(err) => write!(f, "Storage error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PropertyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::KeyTooLong { .. } | Self::ValueTooLarge { .. } => None,
+            Self::Table(err) => Some(err),
+            Self::Storage(err) => Some(err),
+        }
+    }
+}
+
+impl From<TableError> for PropertyError {
+    fn from(err: TableError) -> Self {
+        Self::Table(err)
+    }
+}
+
+impl From<StorageError> for PropertyError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
@@ -0,0 +1,142 @@
+//! Secondary index on property values for equality queries.
+//!
+//! A table opened with [`PropertyTable::open_indexed`](crate::PropertyTable::open_indexed)
+//! keeps a posting `(property_name, encoded_value, entity_id)` for every stored value of
+//! the properties named in its [`IndexSpec`], in a companion table written in the same
+//! transaction as the properties themselves.
+//! [`find_by_value`](crate::PropertyTableRead::find_by_value) then reads the postings of one
+//! value instead of scanning every property.
+//!
+//! The indexed property names are stored with the table, so every later write maintains the
+//! index, including writes through [`PropertyTable::open`](crate::PropertyTable::open).
+//!
+//! # Encoding
+//!
+//! Values are encoded so that byte order matches value order within each type: integers and
+//! floats sort numerically, booleans false before true and strings by their bytes. Each
+//! encoding starts with a tag for the type, so values of different types never compare equal.
+
+use crate::property_value::PropertyValue;
+
+const TAG_NULL: u8 = 0;
+const TAG_BOOLEAN: u8 = 1;
+const TAG_INTEGER: u8 = 2;
+const TAG_FLOAT: u8 = 3;
+const TAG_STRING: u8 = 4;
+
+/// The properties of a table to index by value.
+///
+/// # Example
+///
+/// ```rust
+/// use manifold_properties::IndexSpec;
+///
+/// let spec = IndexSpec::new().property("status").property("tier");
+/// assert_eq!(spec.properties(), ["status", "tier"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexSpec {
+    properties: Vec<String>,
+}
+
+impl IndexSpec {
+    /// Creates a spec indexing no properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a property to index.
+    pub fn property(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        if !self.properties.contains(&name) {
+            self.properties.push(name);
+        }
+        self
+    }
+
+    /// Returns the names of the indexed properties.
+    pub fn properties(&self) -> &[String] {
+        &self.properties
+    }
+}
+
+/// Encodes a property value for the value index.
+///
+/// Two values encode the same exactly when they are equal by [`PropertyValue::value_eq`],
+/// so floats are compared by their bits. Timestamps and expiry are not part of the encoding.
+#[allow(clippy::big_endian_bytes)] // Big-endian so that byte order matches numeric order
+pub(crate) fn encode_index_value(value: &PropertyValue) -> Vec<u8> {
+    match value {
+        PropertyValue::Null { .. } => vec![TAG_NULL],
+        PropertyValue::Boolean { value, .. } => vec![TAG_BOOLEAN, u8::from(*value)],
+        PropertyValue::Integer { value, .. } => {
+            let mut bytes = vec![TAG_INTEGER];
+            // Flipping the sign bit orders negative values before positive ones
+            bytes.extend_from_slice(&(value.cast_unsigned() ^ (1 << 63)).to_be_bytes());
+            bytes
+        }
+        PropertyValue::Float { value, .. } => {
+            let bits = value.to_bits();
+            // Negative floats order in reverse of their bits, so all their bits are flipped
+            let ordered = if bits >> 63 == 1 {
+                !bits
+            } else {
+                bits ^ (1 << 63)
+            };
+            let mut bytes = vec![TAG_FLOAT];
+            bytes.extend_from_slice(&ordered.to_be_bytes());
+            bytes
+        }
+        PropertyValue::String { value, .. } => {
+            let mut bytes = Vec::with_capacity(1 + value.len());
+            bytes.push(TAG_STRING);
+            bytes.extend_from_slice(value.as_bytes());
+            bytes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_preserves_order() {
+        let integers = [i64::MIN, -7, -1, 0, 1, 42, i64::MAX];
+        let encoded: Vec<_> = integers
+            .iter()
+            .map(|value| encode_index_value(&PropertyValue::new_integer(*value)))
+            .collect();
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let floats = [f64::NEG_INFINITY, -2.5, -0.0, 0.0, 1e-9, 3.5, f64::INFINITY];
+        let encoded: Vec<_> = floats
+            .iter()
+            .map(|value| encode_index_value(&PropertyValue::new_float(*value)))
+            .collect();
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let strings = ["", "a", "ab", "b"];
+        let encoded: Vec<_> = strings
+            .iter()
+            .map(|value| encode_index_value(&PropertyValue::new_string(*value)))
+            .collect();
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_encoding_ignores_metadata_but_not_type() {
+        let a = PropertyValue::new_integer_with_timestamps(1, 10, 10);
+        let b = PropertyValue::new_integer_with_timestamps(1, 20, 20).with_expires_at(Some(30));
+        assert_eq!(encode_index_value(&a), encode_index_value(&b));
+
+        assert_ne!(
+            encode_index_value(&PropertyValue::new_integer(1)),
+            encode_index_value(&PropertyValue::new_float(1.0))
+        );
+        assert_ne!(
+            encode_index_value(&PropertyValue::new_boolean(false)),
+            encode_index_value(&PropertyValue::new_null())
+        );
+    }
+}
@@ -9,6 +9,10 @@
 //! - **Native Types**: Integer, Float, Boolean, String, and Null variants
 //! - **Efficient Deserialization**: Fixed-width types use direct byte copying without parsing
 //! - **Temporal Tracking**: Built-in timestamps for version history
+//! - **Expiry**: Per-property TTLs, with expired properties hidden from reads and purged on demand
+//! - **Bulk Sync**: `set_many` writes only the properties whose value changed
+//! - **Value Index**: `open_indexed` maintains per-value postings for `find_by_value` lookups
+//! - **Size Limits**: Oversized keys and string values fail with `PropertyError`, not in storage
 //! - **Type Safety**: Compile-time guarantees prevent type mismatches
 //! - **Efficient Storage**: 50-60% smaller than string-based encoding for numeric properties
 //!
@@ -41,6 +45,8 @@
 //! ```
 
 pub mod encoding;
+pub mod error;
+pub mod index;
 pub mod property_value;
 pub mod table;
 
@@ -50,5 +56,10 @@ pub mod temporal;
 
 // Re-export main types for convenience
 pub use encoding::PropertyValueRef;
+pub use error::PropertyError;
+pub use index::IndexSpec;
+pub use operations::SetManyReport;
 pub use property_value::PropertyValue;
-pub use table::{PropertyGuard, PropertyIter, PropertyTable, PropertyTableRead};
+pub use table::{
+    ExpiryPolicy, PropertyGuard, PropertyIter, PropertyTable, PropertyTableRead, ValueMatches,
+};
@@ -4,6 +4,7 @@
 //! bulk insert/delete APIs for significant performance improvements over individual
 //! operations.
 
+use crate::error::PropertyError;
 use crate::property_value::PropertyValue;
 use crate::table::{PropertyGuard, PropertyTable, PropertyTableRead};
 use manifold::StorageError;
@@ -13,6 +14,18 @@ use uuid::Uuid;
 type EntityPropertiesResult<'a> =
     Result<Vec<(Uuid, Vec<(String, PropertyGuard<'a>)>)>, StorageError>;
 
+/// How many properties a call to [`PropertyTable::set_many`] created, updated and left
+/// unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SetManyReport {
+    /// Properties that did not exist before.
+    pub created: usize,
+    /// Properties whose value or expiry changed.
+    pub updated: usize,
+    /// Properties that already held the value, which were not written.
+    pub unchanged: usize,
+}
+
 /// Sets multiple properties using Manifold's bulk insert API.
 ///
 /// This is significantly more efficient than calling `set()` multiple times as it:
@@ -56,7 +69,7 @@ pub fn batch_set_properties(
     table: &mut PropertyTable,
     properties: &[(Uuid, String, PropertyValue)],
     sorted: bool,
-) -> Result<usize, StorageError> {
+) -> Result<usize, PropertyError> {
     // Convert to the format needed for insert_bulk
     let items: Vec<_> = properties
         .iter()
@@ -68,10 +81,7 @@ pub fn batch_set_properties(
         .collect();
 
     // Use Manifold's bulk insert API
-    table.insert_bulk(&items, sorted).map_err(|e| match e {
-        manifold::TableError::Storage(s) => s,
-        _ => StorageError::Io(std::io::Error::other(e)),
-    })
+    table.insert_bulk(&items, sorted)
 }
 
 /// Gets multiple properties using Manifold's bulk get API.
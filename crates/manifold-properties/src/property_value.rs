@@ -9,7 +9,8 @@ use std::fmt;
 /// A property value with native type variants and temporal tracking.
 ///
 /// Each variant stores the actual value along with temporal metadata for
-/// version tracking and point-in-time queries.
+/// version tracking and point-in-time queries, and optionally the time at which
+/// the property expires.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PropertyValue {
     /// 64-bit signed integer value
@@ -17,27 +18,35 @@ pub enum PropertyValue {
         value: i64,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// 64-bit floating point value
     Float {
         value: f64,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// Boolean value
     Boolean {
         value: bool,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// String value (variable length)
     String {
         value: String,
         updated_at: u64,
         valid_from: u64,
+        expires_at: Option<u64>,
     },
     /// Null value (property exists but has no value)
-    Null { updated_at: u64, valid_from: u64 },
+    Null {
+        updated_at: u64,
+        valid_from: u64,
+        expires_at: Option<u64>,
+    },
 }
 
 impl PropertyValue {
@@ -48,6 +57,7 @@ impl PropertyValue {
             value,
             updated_at: now,
             valid_from: now,
+            expires_at: None,
         }
     }
 
@@ -57,6 +67,7 @@ impl PropertyValue {
             value,
             updated_at,
             valid_from,
+            expires_at: None,
         }
     }
 
@@ -67,6 +78,7 @@ impl PropertyValue {
             value,
             updated_at: now,
             valid_from: now,
+            expires_at: None,
         }
     }
 
@@ -76,6 +88,7 @@ impl PropertyValue {
             value,
             updated_at,
             valid_from,
+            expires_at: None,
         }
     }
 
@@ -86,6 +99,7 @@ impl PropertyValue {
             value,
             updated_at: now,
             valid_from: now,
+            expires_at: None,
         }
     }
 
@@ -95,6 +109,7 @@ impl PropertyValue {
             value,
             updated_at,
             valid_from,
+            expires_at: None,
         }
     }
 
@@ -105,6 +120,7 @@ impl PropertyValue {
             value: value.into(),
             updated_at: now,
             valid_from: now,
+            expires_at: None,
         }
     }
 
@@ -118,6 +134,7 @@ impl PropertyValue {
             value: value.into(),
             updated_at,
             valid_from,
+            expires_at: None,
         }
     }
 
@@ -127,6 +144,7 @@ impl PropertyValue {
         Self::Null {
             updated_at: now,
             valid_from: now,
+            expires_at: None,
         }
     }
 
@@ -135,6 +153,7 @@ impl PropertyValue {
         Self::Null {
             updated_at,
             valid_from,
+            expires_at: None,
         }
     }
 
@@ -208,6 +227,57 @@ impl PropertyValue {
         }
     }
 
+    /// Returns the expires_at timestamp for this property, if it has one.
+    pub fn expires_at(&self) -> Option<u64> {
+        match self {
+            Self::Integer { expires_at, .. } => *expires_at,
+            Self::Float { expires_at, .. } => *expires_at,
+            Self::Boolean { expires_at, .. } => *expires_at,
+            Self::String { expires_at, .. } => *expires_at,
+            Self::Null { expires_at, .. } => *expires_at,
+        }
+    }
+
+    /// Returns true if `other` holds the same value as this property, ignoring the
+    /// updated_at, valid_from and expires_at timestamps.
+    ///
+    /// Floats are compared bitwise, so a NaN equals itself and `0.0` differs from `-0.0`.
+    pub fn value_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer { value: a, .. }, Self::Integer { value: b, .. }) => a == b,
+            (Self::Float { value: a, .. }, Self::Float { value: b, .. }) => {
+                a.to_bits() == b.to_bits()
+            }
+            (Self::Boolean { value: a, .. }, Self::Boolean { value: b, .. }) => a == b,
+            (Self::String { value: a, .. }, Self::String { value: b, .. }) => a == b,
+            (Self::Null { .. }, Self::Null { .. }) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if this property has expired at the given timestamp.
+    ///
+    /// A property expires at its expires_at timestamp, so a property whose
+    /// expires_at equals its updated_at is expired as soon as it is written.
+    pub fn is_expired_at(&self, timestamp: u64) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| expires_at <= timestamp)
+    }
+
+    /// Sets the expires_at timestamp while preserving the value.
+    ///
+    /// Passing `None` makes the property persist until it is deleted.
+    pub fn with_expires_at(mut self, expires_at: Option<u64>) -> Self {
+        match &mut self {
+            Self::Integer { expires_at: e, .. }
+            | Self::Float { expires_at: e, .. }
+            | Self::Boolean { expires_at: e, .. }
+            | Self::String { expires_at: e, .. }
+            | Self::Null { expires_at: e, .. } => *e = expires_at,
+        }
+        self
+    }
+
     /// Updates the temporal metadata while preserving the value.
     pub fn with_timestamps(mut self, updated_at: u64, valid_from: u64) -> Self {
         match &mut self {
@@ -246,6 +316,7 @@ impl PropertyValue {
             Self::Null {
                 updated_at: u,
                 valid_from: v,
+                ..
             } => {
                 *u = updated_at;
                 *v = valid_from;
@@ -268,7 +339,7 @@ impl fmt::Display for PropertyValue {
 }
 
 /// Returns the current timestamp in nanoseconds since Unix epoch.
-fn current_timestamp_nanos() -> u64 {
+pub(crate) fn current_timestamp_nanos() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .expect("System time before Unix epoch")
@@ -341,6 +412,24 @@ mod tests {
         assert_eq!(prop.valid_from(), 6000);
     }
 
+    #[test]
+    fn test_expires_at() {
+        let prop = PropertyValue::new_integer_with_timestamps(42, 1000, 1000);
+        assert_eq!(prop.expires_at(), None);
+        assert!(!prop.is_expired_at(u64::MAX));
+
+        let prop = prop.with_expires_at(Some(1500));
+        assert_eq!(prop.as_integer(), Some(42));
+        assert_eq!(prop.expires_at(), Some(1500));
+        assert!(!prop.is_expired_at(1499));
+        assert!(prop.is_expired_at(1500));
+
+        // Updating the timestamps keeps the expiry
+        let prop = prop.with_timestamps(2000, 2000);
+        assert_eq!(prop.expires_at(), Some(1500));
+        assert_eq!(prop.with_expires_at(None).expires_at(), None);
+    }
+
     #[test]
     fn test_display() {
         assert_eq!(PropertyValue::new_integer(42).to_string(), "42");
@@ -366,4 +455,17 @@ mod tests {
         assert_eq!(prop1, prop2);
         assert_ne!(prop1, prop3);
     }
+
+    #[test]
+    fn test_value_eq_ignores_timestamps() {
+        let prop = PropertyValue::new_integer_with_timestamps(42, 1000, 1000);
+        assert!(prop.value_eq(&PropertyValue::new_integer_with_timestamps(42, 2000, 1500)));
+        assert!(prop.value_eq(&prop.clone().with_expires_at(Some(3000))));
+        assert!(!prop.value_eq(&PropertyValue::new_integer(43)));
+        assert!(!prop.value_eq(&PropertyValue::new_float(42.0)));
+
+        assert!(PropertyValue::new_float(f64::NAN).value_eq(&PropertyValue::new_float(f64::NAN)));
+        assert!(!PropertyValue::new_float(0.0).value_eq(&PropertyValue::new_float(-0.0)));
+        assert!(PropertyValue::new_null().value_eq(&PropertyValue::new_null()));
+    }
 }
@@ -1,32 +1,234 @@
 //! Property table implementation with typed storage and efficient access.
 
-use crate::property_value::PropertyValue;
+use crate::error::PropertyError;
+use crate::index::{IndexSpec, encode_index_value};
+use crate::operations::SetManyReport;
+use crate::property_value::{PropertyValue, current_timestamp_nanos};
 use manifold::{
     AccessGuard, ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata,
-    StorageError, Table, TableDefinition, TableError, WriteTransaction,
+    StorageError, Table, TableDefinition, TableError, TableHandle, WriteTransaction,
 };
-use std::ops::Deref;
+use std::ops::{Bound, Deref, RangeBounds};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Longest property key, in bytes, that a [`PropertyTable`] writes unless set with
+/// [`PropertyTable::with_max_key_len`].
+pub const DEFAULT_MAX_KEY_LEN: usize = 4096;
+
+/// Longest string value, in bytes, that a [`PropertyTable`] writes unless set with
+/// [`PropertyTable::with_max_value_len`].
+pub const DEFAULT_MAX_VALUE_LEN: usize = 1024 * 1024;
+
+/// How reads treat properties whose expiry has passed.
+///
+/// Expired properties stay in the table until [`PropertyTable::purge_expired`] removes
+/// them; the policy only decides whether reads return them in the meantime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpiryPolicy {
+    /// Expired properties are absent, judged against the system clock at read time.
+    #[default]
+    HideExpired,
+    /// Expired properties are absent, judged against the given timestamp
+    /// (nanoseconds since Unix epoch).
+    HideExpiredAt(u64),
+    /// Expired properties are returned like any other.
+    IncludeExpired,
+}
+
+impl ExpiryPolicy {
+    /// Returns the timestamp to judge expiry against, or None if nothing is hidden.
+    fn now(self) -> Option<u64> {
+        match self {
+            Self::HideExpired => Some(current_timestamp_nanos()),
+            Self::HideExpiredAt(timestamp) => Some(timestamp),
+            Self::IncludeExpired => None,
+        }
+    }
+}
+
+/// Returns the name of the table indexing the expiring properties of table `name`.
+///
+/// Entries are keyed by (expires_at, entity_id, property_name) so that purging reads
+/// only the entries that are due. Overwriting or deleting a property leaves its entry
+/// behind; purging drops such stale entries once they are due.
+fn expiry_index_name(name: &str) -> String {
+    format!("{name}_expiry")
+}
+
+/// Returns the name of the table recording which properties of table `name` are indexed.
+fn indexed_properties_name(name: &str) -> String {
+    format!("{name}_indexed")
+}
+
+/// Returns the name of the table indexing the values of properties of table `name`.
+///
+/// Entries are keyed by (property_name, encoded_value, entity_id), with the expiry of the
+/// property as their value so that queries can skip expired properties without reading them.
+fn value_index_name(name: &str) -> String {
+    format!("{name}_value_index")
+}
+
+type ValueIndexKey = (&'static str, &'static [u8], Uuid);
+
+fn value_index_definition(index_name: &str) -> TableDefinition<'_, ValueIndexKey, Option<u64>> {
+    TableDefinition::new(index_name)
+}
+
+/// Converts the error of opening a table into a storage error.
+fn into_storage_error(e: TableError) -> StorageError {
+    match e {
+        TableError::Storage(s) => s,
+        _ => StorageError::Io(std::io::Error::other(e)),
+    }
+}
+
 /// A table storing properties with composite keys (entity_id, property_name).
 ///
 /// Properties are stored with native types (Integer, Float, Boolean, String, Null)
 /// instead of string-based serialization, providing efficient storage and direct
 /// deserialization without parsing overhead.
 pub struct PropertyTable<'txn> {
+    txn: &'txn WriteTransaction,
+    name: String,
     table: Table<'txn, (Uuid, &'static str), PropertyValue>,
+    expiry_policy: ExpiryPolicy,
+    indexed: Vec<String>,
+    max_key_len: usize,
+    max_value_len: usize,
 }
 
 impl<'txn> PropertyTable<'txn> {
     /// Opens a property table for writing.
+    ///
+    /// Properties indexed by an earlier [`open_indexed`](Self::open_indexed) stay indexed:
+    /// writes through the returned table keep their postings up to date.
     pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TableError> {
+        let indexed_name = indexed_properties_name(name);
+        let mut indexed = Vec::new();
+        if txn
+            .list_tables()?
+            .any(|handle| handle.name() == indexed_name)
+        {
+            let def: TableDefinition<&str, ()> = TableDefinition::new(&indexed_name);
+            for result in txn.open_table(def)?.iter()? {
+                let (key_guard, _) = result?;
+                indexed.push(key_guard.value().to_string());
+            }
+        }
+
         let def: TableDefinition<(Uuid, &str), PropertyValue> = TableDefinition::new(name);
         let table = txn.open_table(def)?;
-        Ok(Self { table })
+        Ok(Self {
+            txn,
+            name: name.to_string(),
+            table,
+            expiry_policy: ExpiryPolicy::default(),
+            indexed,
+            max_key_len: DEFAULT_MAX_KEY_LEN,
+            max_value_len: DEFAULT_MAX_VALUE_LEN,
+        })
+    }
+
+    /// Opens a property table for writing, indexing the values of the properties in `spec`.
+    ///
+    /// The indexed properties are recorded with the table, so they stay indexed however the
+    /// table is opened later, and their postings are written in the same transaction as the
+    /// properties. Properties new to the index are indexed from the values already stored.
+    /// See the [`index`](crate::index) module for how values are indexed and
+    /// [`PropertyTableRead::find_by_value`] for querying them.
+    pub fn open_indexed(
+        txn: &'txn WriteTransaction,
+        name: &str,
+        spec: IndexSpec,
+    ) -> Result<Self, TableError> {
+        let mut table = Self::open(txn, name)?;
+        let added: Vec<String> = spec
+            .properties()
+            .iter()
+            .filter(|property| !table.indexed.contains(property))
+            .cloned()
+            .collect();
+        if added.is_empty() {
+            return Ok(table);
+        }
+
+        let indexed_name = indexed_properties_name(name);
+        let def: TableDefinition<&str, ()> = TableDefinition::new(&indexed_name);
+        let mut recorded = txn.open_table(def)?;
+        for property in &added {
+            recorded.insert(property.as_str(), ())?;
+        }
+
+        let index_name = value_index_name(name);
+        let mut index = txn.open_table(value_index_definition(&index_name))?;
+        for result in table.table.iter()? {
+            let (key_guard, value_guard) = result?;
+            let (entity_id, property_key) = key_guard.value();
+            if added.iter().any(|property| property == property_key) {
+                let value = value_guard.value().to_owned();
+                index.insert(
+                    &(
+                        property_key,
+                        encode_index_value(&value).as_slice(),
+                        entity_id,
+                    ),
+                    value.expires_at(),
+                )?;
+            }
+        }
+        drop(index);
+
+        table.indexed.extend(added);
+        Ok(table)
+    }
+
+    /// Sets how reads through this table treat expired properties.
+    pub fn with_expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
+    /// Sets the longest property key, in bytes, that writes through this table accept.
+    ///
+    /// Longer keys fail with [`PropertyError::KeyTooLong`] before anything is written. The
+    /// limit only lasts as long as this table, and defaults to [`DEFAULT_MAX_KEY_LEN`].
+    pub fn with_max_key_len(mut self, max: usize) -> Self {
+        self.max_key_len = max;
+        self
+    }
+
+    /// Sets the longest string value, in bytes, that writes through this table accept.
+    ///
+    /// Longer values fail with [`PropertyError::ValueTooLarge`] before anything is written.
+    /// Other value types have a fixed size and are always accepted. The limit only lasts as
+    /// long as this table, and defaults to [`DEFAULT_MAX_VALUE_LEN`].
+    pub fn with_max_value_len(mut self, max: usize) -> Self {
+        self.max_value_len = max;
+        self
+    }
+
+    /// Checks the size of a property key and of its value, if it is a string.
+    fn check_size(&self, property_key: &str, value: Option<&str>) -> Result<(), PropertyError> {
+        if property_key.len() > self.max_key_len {
+            return Err(PropertyError::KeyTooLong {
+                len: property_key.len(),
+                max: self.max_key_len,
+            });
+        }
+        match value {
+            Some(value) if value.len() > self.max_value_len => Err(PropertyError::ValueTooLarge {
+                len: value.len(),
+                max: self.max_value_len,
+            }),
+            _ => Ok(()),
+        }
     }
 
     /// Sets a property value for an entity.
     ///
+    /// The value replaces any previous value of the property, including its expiry.
+    ///
     /// # Arguments
     ///
     /// * `entity_id` - The UUID of the entity
@@ -37,15 +239,206 @@ impl<'txn> PropertyTable<'txn> {
         entity_id: &Uuid,
         property_key: &str,
         value: PropertyValue,
-    ) -> Result<(), TableError> {
+    ) -> Result<(), PropertyError> {
+        self.check_size(property_key, value.as_string())?;
+        let indexed = self.is_indexed(property_key);
         let value_ref = value.as_ref();
-        self.table.insert(&(*entity_id, property_key), &value_ref)?;
+        let previous = self
+            .table
+            .insert(&(*entity_id, property_key), &value_ref)?
+            .filter(|_| indexed)
+            .map(|guard| guard.value().to_owned());
+        if let Some(expires_at) = value.expires_at() {
+            self.index_expiry(&[(expires_at, *entity_id, property_key)])?;
+        }
+        self.reindex(*entity_id, property_key, previous.as_ref(), Some(&value))?;
+        Ok(())
+    }
+
+    /// Sets a property value that expires `ttl` after the value's updated_at timestamp.
+    ///
+    /// Once expired, the property is absent from reads unless the table's
+    /// [`ExpiryPolicy`] includes expired properties, and is removed by
+    /// [`purge_expired`](Self::purge_expired). A zero TTL makes the property expire
+    /// immediately. Setting the property again replaces its expiry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The UUID of the entity
+    /// * `property_key` - The property name
+    /// * `value` - The property value
+    /// * `ttl` - How long the property lives after its updated_at timestamp
+    pub fn insert_with_ttl(
+        &mut self,
+        entity_id: &Uuid,
+        property_key: &str,
+        value: PropertyValue,
+        ttl: Duration,
+    ) -> Result<(), PropertyError> {
+        let ttl_nanos = u64::try_from(ttl.as_nanos()).unwrap_or(u64::MAX);
+        let expires_at = value.updated_at().saturating_add(ttl_nanos);
+        self.set(
+            entity_id,
+            property_key,
+            value.with_expires_at(Some(expires_at)),
+        )
+    }
+
+    /// Removes every property that has expired at `now` (nanoseconds since Unix epoch).
+    ///
+    /// Only the expiry index is scanned, so this is cheap when nothing is due and does
+    /// nothing at all for a table that never held an expiring property.
+    ///
+    /// # Returns
+    ///
+    /// The number of properties removed.
+    pub fn purge_expired(&mut self, now: u64) -> Result<usize, TableError> {
+        let index_name = expiry_index_name(&self.name);
+        if !self
+            .txn
+            .list_tables()?
+            .any(|handle| handle.name() == index_name)
+        {
+            return Ok(0);
+        }
+
+        let def: TableDefinition<(u64, Uuid, &str), ()> = TableDefinition::new(&index_name);
+        let mut index = self.txn.open_table(def)?;
+
+        let mut due = Vec::new();
+        for result in index.iter()? {
+            let (key_guard, _) = result?;
+            let (expires_at, entity_id, property_key) = key_guard.value();
+            if expires_at > now {
+                break;
+            }
+            due.push((expires_at, entity_id, property_key.to_string()));
+        }
+
+        let mut purged = 0;
+        for (expires_at, entity_id, property_key) in &due {
+            index.remove(&(*expires_at, *entity_id, property_key.as_str()))?;
+
+            // The entry is stale if the property was deleted or set again since
+            let current = self
+                .table
+                .get(&(*entity_id, property_key.as_str()))?
+                .map(|guard| guard.value().expires_at());
+            if current == Some(Some(*expires_at)) {
+                let removed = self
+                    .table
+                    .remove(&(*entity_id, property_key.as_str()))?
+                    .map(|guard| guard.value().to_owned());
+                self.reindex(*entity_id, property_key, removed.as_ref(), None)?;
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+
+    /// Records expiring properties in the expiry index.
+    fn index_expiry(&self, entries: &[(u64, Uuid, &str)]) -> Result<(), TableError> {
+        let index_name = expiry_index_name(&self.name);
+        let def: TableDefinition<(u64, Uuid, &str), ()> = TableDefinition::new(&index_name);
+        let mut index = self.txn.open_table(def)?;
+        for entry in entries {
+            index.insert(entry, ())?;
+        }
         Ok(())
     }
 
+    /// Returns true if the values of `property_key` are indexed.
+    fn is_indexed(&self, property_key: &str) -> bool {
+        self.indexed.iter().any(|property| property == property_key)
+    }
+
+    /// Moves the value index posting of a property from its previous value to its new one.
+    ///
+    /// Does nothing for properties that are not indexed.
+    fn reindex(
+        &self,
+        entity_id: Uuid,
+        property_key: &str,
+        previous: Option<&PropertyValue>,
+        value: Option<&PropertyValue>,
+    ) -> Result<(), TableError> {
+        if !self.is_indexed(property_key) {
+            return Ok(());
+        }
+        let index_name = value_index_name(&self.name);
+        let mut index = self.txn.open_table(value_index_definition(&index_name))?;
+        if let Some(previous) = previous {
+            index.remove(&(
+                property_key,
+                encode_index_value(previous).as_slice(),
+                entity_id,
+            ))?;
+        }
+        if let Some(value) = value {
+            index.insert(
+                &(
+                    property_key,
+                    encode_index_value(value).as_slice(),
+                    entity_id,
+                ),
+                value.expires_at(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Sets many properties, writing only those whose value changed.
+    ///
+    /// Each value is compared with the stored one by [`PropertyValue::value_eq`], so
+    /// re-applying a batch from an upstream system leaves the updated_at timestamps of
+    /// unchanged properties alone. A property whose expiry differs counts as updated, as
+    /// does one with a different type. Expired properties that have not been purged yet are
+    /// compared like any other. Entries are applied in order, so when a key appears twice
+    /// the later entry is compared with the earlier one.
+    ///
+    /// Every entry is checked against the size limits of the table before any is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Slice of ((entity_id, property_key), value) tuples
+    ///
+    /// # Returns
+    ///
+    /// How many properties were created, updated and left unchanged.
+    pub fn set_many(
+        &mut self,
+        entries: &[((Uuid, &str), PropertyValue)],
+    ) -> Result<SetManyReport, PropertyError> {
+        for ((_, property_key), value) in entries {
+            self.check_size(property_key, value.as_string())?;
+        }
+
+        let mut report = SetManyReport::default();
+        for ((entity_id, property_key), value) in entries {
+            let stored = self
+                .table
+                .get(&(*entity_id, *property_key))?
+                .map(|guard| guard.value().to_owned());
+            match stored {
+                None => report.created += 1,
+                Some(stored)
+                    if stored.value_eq(value) && stored.expires_at() == value.expires_at() =>
+                {
+                    report.unchanged += 1;
+                    continue;
+                }
+                Some(_) => report.updated += 1,
+            }
+            self.set(entity_id, property_key, value.clone())?;
+        }
+        Ok(report)
+    }
+
     /// Bulk insert multiple properties using Manifold's optimized bulk API.
     ///
-    /// This is significantly more efficient than calling `set()` multiple times.
+    /// This is significantly more efficient than calling `set()` multiple times. Every item is
+    /// checked against the size limits of the table before any is written.
     ///
     /// # Arguments
     ///
@@ -59,8 +452,47 @@ impl<'txn> PropertyTable<'txn> {
         &mut self,
         items: &[((Uuid, &'a str), crate::encoding::PropertyValueRef<'a>)],
         sorted: bool,
-    ) -> Result<usize, TableError> {
-        Ok(self.table.insert_bulk(items.iter().cloned(), sorted)?)
+    ) -> Result<usize, PropertyError> {
+        for ((_, property_key), value) in items {
+            self.check_size(property_key, value.as_string())?;
+        }
+
+        let mut previous = Vec::new();
+        for ((entity_id, property_key), _) in items
+            .iter()
+            .filter(|((_, property_key), _)| self.is_indexed(property_key))
+        {
+            let stored = self
+                .table
+                .get(&(*entity_id, *property_key))?
+                .map(|guard| guard.value().to_owned());
+            previous.push((*entity_id, *property_key, stored));
+        }
+
+        let inserted = self.table.insert_bulk(items.iter().cloned(), sorted)?;
+
+        // Postings follow the value each key ends up with, even if `items` set it twice
+        for (entity_id, property_key, stored) in &previous {
+            let current = self
+                .table
+                .get(&(*entity_id, *property_key))?
+                .map(|guard| guard.value().to_owned());
+            self.reindex(*entity_id, property_key, stored.as_ref(), current.as_ref())?;
+        }
+
+        let expiring: Vec<(u64, Uuid, &str)> = items
+            .iter()
+            .filter_map(|((entity_id, property_key), value)| {
+                value
+                    .expires_at()
+                    .map(|expires_at| (expires_at, *entity_id, *property_key))
+            })
+            .collect();
+        if !expiring.is_empty() {
+            self.index_expiry(&expiring)?;
+        }
+
+        Ok(inserted)
     }
 
     /// Bulk remove multiple properties using Manifold's optimized bulk API.
@@ -75,7 +507,23 @@ impl<'txn> PropertyTable<'txn> {
     ///
     /// The number of properties actually deleted.
     pub fn remove_bulk(&mut self, keys: &[(Uuid, &str)]) -> Result<usize, StorageError> {
-        self.table.remove_bulk(keys.iter().cloned())
+        let mut previous = Vec::new();
+        for (entity_id, property_key) in keys
+            .iter()
+            .filter(|(_, property_key)| self.is_indexed(property_key))
+        {
+            if let Some(guard) = self.table.get(&(*entity_id, *property_key))? {
+                previous.push((*entity_id, *property_key, guard.value().to_owned()));
+            }
+        }
+
+        let removed = self.table.remove_bulk(keys.iter().cloned())?;
+
+        for (entity_id, property_key, value) in &previous {
+            self.reindex(*entity_id, property_key, Some(value), None)
+                .map_err(into_storage_error)?;
+        }
+        Ok(removed)
     }
 
     /// Gets a property value for an entity.
@@ -86,20 +534,83 @@ impl<'txn> PropertyTable<'txn> {
         entity_id: &Uuid,
         property_key: &str,
     ) -> Result<Option<PropertyGuard<'_>>, StorageError> {
+        let now = self.expiry_policy.now();
         Ok(self
             .table
             .get(&(*entity_id, property_key))?
-            .map(PropertyGuard::new))
+            .map(PropertyGuard::new)
+            .filter(|guard| !is_hidden(guard, now)))
     }
 
     /// Deletes a property for an entity.
     ///
     /// Returns true if the property existed and was deleted, false otherwise.
     pub fn delete(&mut self, entity_id: &Uuid, property_key: &str) -> Result<bool, StorageError> {
-        Ok(self.table.remove(&(*entity_id, property_key))?.is_some())
+        let indexed = self.is_indexed(property_key);
+        let Some(guard) = self.table.remove(&(*entity_id, property_key))? else {
+            return Ok(false);
+        };
+        if indexed {
+            let previous = guard.value().to_owned();
+            drop(guard);
+            self.reindex(*entity_id, property_key, Some(&previous), None)
+                .map_err(into_storage_error)?;
+        }
+        Ok(true)
+    }
+
+    /// Deletes every property of the entities whose ids fall in `entities`.
+    ///
+    /// The properties are removed by a range scan of the table, without collecting their
+    /// keys first. Expired properties that have not been purged yet are deleted and counted
+    /// like any other.
+    ///
+    /// # Returns
+    ///
+    /// The number of properties deleted.
+    pub fn delete_range(
+        &mut self,
+        entities: impl RangeBounds<Uuid>,
+    ) -> Result<usize, StorageError> {
+        let Some(keys) = entity_keys(&entities) else {
+            return Ok(0);
+        };
+        let index_name = value_index_name(&self.name);
+        let mut index = if self.indexed.is_empty() {
+            None
+        } else {
+            Some(
+                self.txn
+                    .open_table(value_index_definition(&index_name))
+                    .map_err(into_storage_error)?,
+            )
+        };
+
+        let mut deleted = 0;
+        for item in self
+            .table
+            .extract_from_if::<(Uuid, &str), _>(keys, |_, _| true)?
+        {
+            let (key_guard, value_guard) = item?;
+            let (entity_id, property_key) = key_guard.value();
+            if let Some(index) = &mut index
+                && self.indexed.iter().any(|property| property == property_key)
+            {
+                let value = value_guard.value().to_owned();
+                index.remove(&(
+                    property_key,
+                    encode_index_value(&value).as_slice(),
+                    entity_id,
+                ))?;
+            }
+            deleted += 1;
+        }
+        Ok(deleted)
     }
 
     /// Returns the total number of properties in the table.
+    ///
+    /// Expired properties are counted until they are purged.
     pub fn len(&self) -> Result<u64, StorageError> {
         self.table.len()
     }
@@ -117,6 +628,7 @@ impl<'txn> PropertyTable<'txn> {
         &self,
         entity_id: &Uuid,
     ) -> Result<Vec<(String, PropertyGuard<'_>)>, StorageError> {
+        let now = self.expiry_policy.now();
         let mut results = Vec::new();
 
         // Range query: all keys starting with (entity_id, *)
@@ -135,7 +647,10 @@ impl<'txn> PropertyTable<'txn> {
                 break;
             }
 
-            results.push((prop_key.to_string(), PropertyGuard::new(value_guard)));
+            let guard = PropertyGuard::new(value_guard);
+            if !is_hidden(&guard, now) {
+                results.push((prop_key.to_string(), guard));
+            }
         }
 
         Ok(results)
@@ -144,18 +659,51 @@ impl<'txn> PropertyTable<'txn> {
 
 /// Read-only property table providing efficient access without write capabilities.
 pub struct PropertyTableRead {
+    name: String,
     table: ReadOnlyTable<(Uuid, &'static str), PropertyValue>,
+    expiry_policy: ExpiryPolicy,
+    indexed: Vec<String>,
+    value_index: Option<ReadOnlyTable<ValueIndexKey, Option<u64>>>,
 }
 
 impl PropertyTableRead {
     /// Opens a property table for reading.
     pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, StorageError> {
         let def: TableDefinition<(Uuid, &str), PropertyValue> = TableDefinition::new(name);
-        let table = txn.open_table(def).map_err(|e| match e {
-            TableError::Storage(s) => s,
-            _ => StorageError::Io(std::io::Error::other(e)),
-        })?;
-        Ok(Self { table })
+        let table = txn.open_table(def).map_err(into_storage_error)?;
+
+        let indexed_name = indexed_properties_name(name);
+        let mut indexed = Vec::new();
+        let mut value_index = None;
+        if txn
+            .list_tables()?
+            .any(|handle| handle.name() == indexed_name)
+        {
+            let def: TableDefinition<&str, ()> = TableDefinition::new(&indexed_name);
+            for result in txn.open_table(def).map_err(into_storage_error)?.iter()? {
+                let (key_guard, _) = result?;
+                indexed.push(key_guard.value().to_string());
+            }
+            let index_name = value_index_name(name);
+            value_index = Some(
+                txn.open_table(value_index_definition(&index_name))
+                    .map_err(into_storage_error)?,
+            );
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            table,
+            expiry_policy: ExpiryPolicy::default(),
+            indexed,
+            value_index,
+        })
+    }
+
+    /// Sets how reads through this table treat expired properties.
+    pub fn with_expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
     }
 
     /// Gets a property value for an entity.
@@ -166,13 +714,17 @@ impl PropertyTableRead {
         entity_id: &Uuid,
         property_key: &str,
     ) -> Result<Option<PropertyGuard<'_>>, StorageError> {
+        let now = self.expiry_policy.now();
         Ok(self
             .table
             .get(&(*entity_id, property_key))?
-            .map(PropertyGuard::new))
+            .map(PropertyGuard::new)
+            .filter(|guard| !is_hidden(guard, now)))
     }
 
     /// Returns the total number of properties in the table.
+    ///
+    /// Expired properties are counted until they are purged.
     pub fn len(&self) -> Result<u64, StorageError> {
         self.table.len()
     }
@@ -189,6 +741,7 @@ impl PropertyTableRead {
         &self,
         entity_id: &Uuid,
     ) -> Result<Vec<(String, PropertyGuard<'_>)>, StorageError> {
+        let now = self.expiry_policy.now();
         let mut results = Vec::new();
 
         let start_key = (*entity_id, "");
@@ -202,19 +755,98 @@ impl PropertyTableRead {
                 break;
             }
 
-            results.push((prop_key.to_string(), PropertyGuard::new(value_guard)));
+            let guard = PropertyGuard::new(value_guard);
+            if !is_hidden(&guard, now) {
+                results.push((prop_key.to_string(), guard));
+            }
         }
 
         Ok(results)
     }
 
+    /// Finds the entities whose property `property_name` equals `value`.
+    ///
+    /// Values match by [`PropertyValue::value_eq`], so a value only matches values of the
+    /// same type, and timestamps are ignored. Entities are yielded in id order, without the
+    /// ones whose property is hidden by the table's [`ExpiryPolicy`].
+    ///
+    /// Only the postings of the value are read, so the property must have been indexed by
+    /// [`PropertyTable::open_indexed`]; querying any other property is an error.
+    pub fn find_by_value(
+        &self,
+        property_name: &str,
+        value: &PropertyValue,
+    ) -> Result<ValueMatches<'_>, StorageError> {
+        let index = self
+            .value_index
+            .as_ref()
+            .filter(|_| {
+                self.indexed
+                    .iter()
+                    .any(|property| property == property_name)
+            })
+            .ok_or_else(|| {
+                StorageError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "property {property_name:?} of table {:?} is not indexed",
+                        self.name
+                    ),
+                ))
+            })?;
+
+        let encoded = encode_index_value(value);
+        let first = (property_name, encoded.as_slice(), Uuid::nil());
+        let last = (
+            property_name,
+            encoded.as_slice(),
+            Uuid::from_u128(u128::MAX),
+        );
+        Ok(ValueMatches {
+            inner: index.range(first..=last)?,
+            now: self.expiry_policy.now(),
+        })
+    }
+
     /// Iterates over all properties in the table.
     pub fn iter(&self) -> Result<PropertyIter<'_>, StorageError> {
         Ok(PropertyIter {
             inner: self.table.iter()?,
+            now: self.expiry_policy.now(),
+        })
+    }
+
+    /// Iterates over the properties of the entities whose ids fall in `entities`.
+    ///
+    /// Properties are yielded in (entity_id, property_name) order, so each entity's
+    /// properties are contiguous.
+    pub fn entity_range(
+        &self,
+        entities: impl RangeBounds<Uuid>,
+    ) -> Result<PropertyIter<'_>, StorageError> {
+        let Some(keys) = entity_keys(&entities) else {
+            return Ok(PropertyIter {
+                inner: self.table.range((Uuid::nil(), "")..(Uuid::nil(), ""))?,
+                now: None,
+            });
+        };
+        Ok(PropertyIter {
+            inner: self.table.range::<(Uuid, &str)>(keys)?,
+            now: self.expiry_policy.now(),
         })
     }
 
+    /// Gets many properties, returning them in the order of `keys`.
+    ///
+    /// Missing and hidden expired properties are `None`. This is the counterpart of
+    /// [`PropertyTable::set_many`], and reads through [`get_bulk`](Self::get_bulk).
+    pub fn get_many(
+        &self,
+        keys: &[(Uuid, &str)],
+    ) -> Result<Vec<Option<PropertyGuard<'_>>>, StorageError> {
+        self.get_bulk(keys)
+    }
+
     /// Gets multiple properties in a single bulk operation.
     ///
     /// This uses Manifold's bulk get API for better performance than individual gets.
@@ -230,14 +862,44 @@ impl PropertyTableRead {
             .collect();
 
         let guards = self.table.get_bulk(composite_keys.into_iter())?;
+        let now = self.expiry_policy.now();
 
         Ok(guards
             .into_iter()
-            .map(|opt_guard| opt_guard.map(PropertyGuard::new))
+            .map(|opt_guard| {
+                opt_guard
+                    .map(PropertyGuard::new)
+                    .filter(|guard| !is_hidden(guard, now))
+            })
             .collect())
     }
 }
 
+type PropertyKeyBounds = (Bound<(Uuid, &'static str)>, Bound<(Uuid, &'static str)>);
+
+/// Maps a range of entity ids to the range of keys holding their properties.
+///
+/// Returns None if the range holds no entity.
+fn entity_keys(entities: &impl RangeBounds<Uuid>) -> Option<PropertyKeyBounds> {
+    // Every key of an entity sorts at or after (entity_id, ""), so entity bounds map to the
+    // first key of the entity or of the one after it
+    let next = |id: &Uuid| id.as_u128().checked_add(1).map(Uuid::from_u128);
+    let start = match entities.start_bound() {
+        Bound::Included(id) => Bound::Included((*id, "")),
+        // Nothing follows the largest id
+        Bound::Excluded(id) => Bound::Included((next(id)?, "")),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    let end = match entities.end_bound() {
+        Bound::Included(id) => {
+            next(id).map_or(Bound::Unbounded, |next| Bound::Excluded((next, "")))
+        }
+        Bound::Excluded(id) => Bound::Excluded((*id, "")),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    Some((start, end))
+}
+
 /// A guard providing access to a stored property value.
 ///
 /// This guard provides both generic and type-specific accessors for the property value.
@@ -301,6 +963,11 @@ impl<'a> PropertyGuard<'a> {
         self.value.valid_from()
     }
 
+    /// Returns the expires_at timestamp for this property, if it has one.
+    pub fn expires_at(&self) -> Option<u64> {
+        self.value.expires_at()
+    }
+
     /// Converts this guard to an owned PropertyValue (clones the internal value).
     pub fn to_owned(&self) -> PropertyValue {
         self.value.clone()
@@ -318,24 +985,63 @@ impl<'a> Deref for PropertyGuard<'a> {
 /// Iterator over properties in a PropertyTableRead.
 pub struct PropertyIter<'a> {
     inner: manifold::Range<'a, (Uuid, &'static str), PropertyValue>,
+    now: Option<u64>,
 }
 
 impl<'a> Iterator for PropertyIter<'a> {
     type Item = Result<((Uuid, String), PropertyGuard<'a>), StorageError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|result| {
-            result.map(|(key_guard, value_guard)| {
+        loop {
+            let item = self.inner.next()?.map(|(key_guard, value_guard)| {
                 let (entity_id, prop_key) = key_guard.value();
                 (
                     (entity_id, prop_key.to_string()),
                     PropertyGuard::new(value_guard),
                 )
-            })
-        })
+            });
+
+            match item {
+                Ok((_, ref guard)) if is_hidden(guard, self.now) => continue,
+                item => return Some(item),
+            }
+        }
     }
 }
 
+/// Iterator over the entities matching a value, returned by
+/// [`PropertyTableRead::find_by_value`].
+pub struct ValueMatches<'a> {
+    inner: manifold::Range<'a, ValueIndexKey, Option<u64>>,
+    now: Option<u64>,
+}
+
+impl Iterator for ValueMatches<'_> {
+    type Item = Result<Uuid, StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key_guard, value_guard) = match self.inner.next()? {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+            let expired = self.now.is_some_and(|now| {
+                value_guard
+                    .value()
+                    .is_some_and(|expires_at| expires_at <= now)
+            });
+            if !expired {
+                return Some(Ok(key_guard.value().2));
+            }
+        }
+    }
+}
+
+/// Returns true if the property has expired at `now`, where None hides nothing.
+fn is_hidden(guard: &PropertyGuard<'_>, now: Option<u64>) -> bool {
+    now.is_some_and(|now| guard.value().is_expired_at(now))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -566,6 +1272,89 @@ mod tests {
         assert_eq!(guard2.as_i64(), Some(40));
     }
 
+    #[test]
+    fn test_entity_range() {
+        use Bound::{Excluded, Included, Unbounded};
+
+        let (_temp, db) = setup_test_db();
+        let entities: Vec<Uuid> = [0, 1, 2, u128::MAX].map(Uuid::from_u128).to_vec();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            for (i, entity_id) in entities.iter().enumerate() {
+                for name in ["a", "b"] {
+                    table
+                        .set(entity_id, name, PropertyValue::new_integer(i as i64))
+                        .unwrap();
+                }
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "properties").unwrap();
+        let ids = |range: (Bound<Uuid>, Bound<Uuid>)| -> Vec<(u128, String)> {
+            table
+                .entity_range(range)
+                .unwrap()
+                .map(|result| {
+                    let ((id, name), _) = result.unwrap();
+                    (id.as_u128(), name)
+                })
+                .collect()
+        };
+        let pairs = |ids: &[u128]| -> Vec<(u128, String)> {
+            ids.iter()
+                .flat_map(|&id| [(id, "a".to_string()), (id, "b".to_string())])
+                .collect()
+        };
+
+        let (one, two, max) = (entities[1], entities[2], entities[3]);
+        assert_eq!(ids((Included(one), Included(two))), pairs(&[1, 2]));
+        assert_eq!(ids((Excluded(one), Excluded(two))), pairs(&[]));
+        assert_eq!(ids((Excluded(entities[0]), Excluded(two))), pairs(&[1]));
+        assert_eq!(ids((Included(two), Unbounded)), pairs(&[2, u128::MAX]));
+        assert_eq!(ids((Included(max), Included(max))), pairs(&[u128::MAX]));
+        assert_eq!(ids((Excluded(max), Unbounded)), pairs(&[]));
+        assert_eq!(ids((Unbounded, Excluded(one))), pairs(&[0]));
+    }
+
+    #[test]
+    fn test_delete_range() {
+        let (_temp, db) = setup_test_db();
+        let entities: Vec<Uuid> = [0, 1, 2, 3, u128::MAX].map(Uuid::from_u128).to_vec();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            for entity_id in &entities {
+                for name in ["", "a", "b"] {
+                    table
+                        .set(entity_id, name, PropertyValue::new_integer(1))
+                        .unwrap();
+                }
+            }
+
+            // The start is inclusive and the end exclusive, including the empty property name
+            assert_eq!(table.delete_range(entities[1]..entities[3]).unwrap(), 6);
+            assert_eq!(table.delete_range(entities[1]..entities[3]).unwrap(), 0);
+            assert_eq!(table.delete_range(entities[3]..entities[3]).unwrap(), 0);
+            assert_eq!(table.delete_range(entities[4]..).unwrap(), 3);
+            assert_eq!(table.len().unwrap(), 6);
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "properties").unwrap();
+        let remaining: Vec<u128> = table
+            .iter()
+            .unwrap()
+            .map(|result| result.unwrap().0.0.as_u128())
+            .collect();
+        assert_eq!(remaining, vec![0, 0, 0, 3, 3, 3]);
+    }
+
     #[test]
     fn test_type_safety() {
         let (_temp, db) = setup_test_db();
@@ -591,4 +1380,631 @@ mod tests {
         assert_eq!(guard.as_str(), None);
         assert!(!guard.is_null());
     }
+
+    #[test]
+    fn test_insert_with_ttl_hides_expired() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::new_v4();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            let session = PropertyValue::new_string_with_timestamps("token", 1000, 1000);
+            table
+                .insert_with_ttl(&entity_id, "session", session, Duration::from_nanos(500))
+                .unwrap();
+            table
+                .set(
+                    &entity_id,
+                    "name",
+                    PropertyValue::new_string_with_timestamps("Alice", 1000, 1000),
+                )
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let open = |policy| {
+            PropertyTableRead::open(&read_txn, "properties")
+                .unwrap()
+                .with_expiry_policy(policy)
+        };
+
+        let table = open(ExpiryPolicy::HideExpiredAt(1499));
+        let guard = table.get(&entity_id, "session").unwrap().unwrap();
+        assert_eq!(guard.as_str(), Some("token"));
+        assert_eq!(guard.expires_at(), Some(1500));
+        assert_eq!(table.get_all(&entity_id).unwrap().len(), 2);
+
+        let table = open(ExpiryPolicy::HideExpiredAt(1500));
+        assert!(table.get(&entity_id, "session").unwrap().is_none());
+        let properties = table.get_all(&entity_id).unwrap();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].0, "name");
+        assert_eq!(table.iter().unwrap().count(), 1);
+        let bulk = table
+            .get_bulk(&[(entity_id, "session"), (entity_id, "name")])
+            .unwrap();
+        assert!(bulk[0].is_none());
+        assert!(bulk[1].is_some());
+        // Expired properties still take up space until they are purged
+        assert_eq!(table.len().unwrap(), 2);
+
+        let table = open(ExpiryPolicy::IncludeExpired);
+        assert!(table.get(&entity_id, "session").unwrap().is_some());
+        assert_eq!(table.iter().unwrap().count(), 2);
+
+        // The system clock is long past the expiry
+        let table = open(ExpiryPolicy::HideExpired);
+        assert!(table.get(&entity_id, "session").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_zero_ttl_expires_immediately() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::new_v4();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties")
+                .unwrap()
+                .with_expiry_policy(ExpiryPolicy::HideExpiredAt(1000));
+            let value = PropertyValue::new_integer_with_timestamps(1, 1000, 1000);
+            table
+                .insert_with_ttl(&entity_id, "cached", value, Duration::ZERO)
+                .unwrap();
+
+            assert!(table.get(&entity_id, "cached").unwrap().is_none());
+            assert!(table.get_all(&entity_id).unwrap().is_empty());
+            assert_eq!(table.purge_expired(1000).unwrap(), 1);
+            assert!(table.is_empty().unwrap());
+        }
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_update_resets_expiry() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::new_v4();
+        let ttl = Duration::from_nanos(500);
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            let value = PropertyValue::new_integer_with_timestamps(1, 1000, 1000);
+            table
+                .insert_with_ttl(&entity_id, "enrichment", value, ttl)
+                .unwrap();
+            let value = PropertyValue::new_integer_with_timestamps(2, 1400, 1400);
+            table
+                .insert_with_ttl(&entity_id, "enrichment", value, ttl)
+                .unwrap();
+
+            let value = PropertyValue::new_integer_with_timestamps(3, 1000, 1000);
+            table
+                .insert_with_ttl(&entity_id, "persisted", value, ttl)
+                .unwrap();
+            let value = PropertyValue::new_integer_with_timestamps(4, 1100, 1100);
+            table.set(&entity_id, "persisted", value).unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties")
+                .unwrap()
+                .with_expiry_policy(ExpiryPolicy::IncludeExpired);
+
+            // The first expiry has passed, but neither property still carries it
+            assert_eq!(table.purge_expired(1600).unwrap(), 0);
+            let enrichment = table.get(&entity_id, "enrichment").unwrap().unwrap();
+            assert_eq!(enrichment.as_i64(), Some(2));
+            assert_eq!(enrichment.expires_at(), Some(1900));
+            let persisted = table.get(&entity_id, "persisted").unwrap().unwrap();
+            assert_eq!(persisted.expires_at(), None);
+            drop((enrichment, persisted));
+
+            assert_eq!(table.purge_expired(1900).unwrap(), 1);
+            assert!(table.get(&entity_id, "enrichment").unwrap().is_none());
+            assert_eq!(table.len().unwrap(), 1);
+        }
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let (_temp, db) = setup_test_db();
+        let entities: Vec<Uuid> = (0..10).map(|_| Uuid::new_v4()).collect();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            for (i, entity_id) in entities.iter().enumerate() {
+                let value = PropertyValue::new_integer_with_timestamps(i as i64, 1000, 1000);
+                let ttl = Duration::from_nanos(100 * i as u64);
+                table
+                    .insert_with_ttl(entity_id, "session", value, ttl)
+                    .unwrap();
+                table
+                    .set(entity_id, "name", PropertyValue::new_string("entity"))
+                    .unwrap();
+            }
+            // A deleted property leaves a stale index entry behind
+            table.delete(&entities[9], "session").unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            assert_eq!(table.purge_expired(1450).unwrap(), 5);
+            assert_eq!(table.purge_expired(1450).unwrap(), 0);
+            assert_eq!(table.len().unwrap(), 14);
+            assert_eq!(table.purge_expired(u64::MAX).unwrap(), 4);
+            assert_eq!(table.len().unwrap(), 10);
+        }
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_purge_expired_without_ttl_entries() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::new_v4();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            table
+                .set(&entity_id, "age", PropertyValue::new_integer(42))
+                .unwrap();
+            assert_eq!(table.purge_expired(u64::MAX).unwrap(), 0);
+            assert_eq!(table.len().unwrap(), 1);
+        }
+        // No expiry index is created for a table without expiring properties
+        assert_eq!(write_txn.list_tables().unwrap().count(), 1);
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_set_many_skips_unchanged_values() {
+        let (_temp, db) = setup_test_db();
+        let alice = Uuid::from_u128(1);
+        let bob = Uuid::from_u128(2);
+        let batch = |t: u64| {
+            vec![
+                (
+                    (alice, "age"),
+                    PropertyValue::new_integer_with_timestamps(30, t, t),
+                ),
+                (
+                    (alice, "name"),
+                    PropertyValue::new_string_with_timestamps("Alice", t, t),
+                ),
+                (
+                    (bob, "score"),
+                    PropertyValue::new_float_with_timestamps(9.5, t, t),
+                ),
+                ((bob, "note"), PropertyValue::new_null_with_timestamps(t, t)),
+            ]
+        };
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            let report = table.set_many(&batch(1000)).unwrap();
+            assert_eq!(
+                (report.created, report.updated, report.unchanged),
+                (4, 0, 0)
+            );
+        }
+        write_txn.commit().unwrap();
+
+        // The same values with newer timestamps are not written again
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            let report = table.set_many(&batch(2000)).unwrap();
+            assert_eq!(
+                (report.created, report.updated, report.unchanged),
+                (0, 0, 4)
+            );
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "properties").unwrap();
+        let keys: Vec<(Uuid, &str)> = batch(0).iter().map(|(key, _)| *key).collect();
+        for guard in table.get_many(&keys).unwrap() {
+            assert_eq!(guard.unwrap().updated_at(), 1000);
+        }
+        drop((table, read_txn));
+
+        // Changed values, types and expiries are written; new keys are created
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            let report = table
+                .set_many(&[
+                    (
+                        (alice, "age"),
+                        PropertyValue::new_integer_with_timestamps(31, 3000, 3000),
+                    ),
+                    (
+                        (alice, "name"),
+                        PropertyValue::new_string_with_timestamps("Alice", 3000, 3000),
+                    ),
+                    (
+                        (bob, "score"),
+                        PropertyValue::new_integer_with_timestamps(9, 3000, 3000),
+                    ),
+                    (
+                        (bob, "note"),
+                        PropertyValue::new_null_with_timestamps(3000, 3000)
+                            .with_expires_at(Some(u64::MAX)),
+                    ),
+                    (
+                        (bob, "age"),
+                        PropertyValue::new_integer_with_timestamps(40, 3000, 3000),
+                    ),
+                ])
+                .unwrap();
+            assert_eq!(
+                (report.created, report.updated, report.unchanged),
+                (1, 3, 1)
+            );
+            assert_eq!(
+                table.get(&alice, "age").unwrap().unwrap().as_i64(),
+                Some(31)
+            );
+            assert_eq!(
+                table.get(&alice, "name").unwrap().unwrap().updated_at(),
+                1000
+            );
+            assert_eq!(table.get(&bob, "score").unwrap().unwrap().as_i64(), Some(9));
+        }
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_get_many_preserves_order() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::new_v4();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            table
+                .set_many(&[
+                    ((entity_id, "a"), PropertyValue::new_integer(1)),
+                    ((entity_id, "b"), PropertyValue::new_integer(2)),
+                ])
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "properties").unwrap();
+        let values: Vec<Option<i64>> = table
+            .get_many(&[(entity_id, "b"), (entity_id, "missing"), (entity_id, "a")])
+            .unwrap()
+            .iter()
+            .map(|guard| guard.as_ref().and_then(PropertyGuard::as_i64))
+            .collect();
+        assert_eq!(values, [Some(2), None, Some(1)]);
+    }
+
+    fn find(table: &PropertyTableRead, property_name: &str, value: PropertyValue) -> Vec<Uuid> {
+        table
+            .find_by_value(property_name, &value)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_find_by_value_each_type() {
+        let (_temp, db) = setup_test_db();
+        let [a, b, c] = [1, 2, 3].map(Uuid::from_u128);
+        let spec = IndexSpec::new()
+            .property("age")
+            .property("score")
+            .property("active")
+            .property("name")
+            .property("nickname");
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open_indexed(&write_txn, "people", spec).unwrap();
+            for (entity_id, age, score, active, name) in [
+                (a, 30, 1.5, true, "Alice"),
+                (b, 30, -1.5, false, "Bob"),
+                (c, -30, 1.5, true, "Alice"),
+            ] {
+                table
+                    .set(&entity_id, "age", PropertyValue::new_integer(age))
+                    .unwrap();
+                table
+                    .set(&entity_id, "score", PropertyValue::new_float(score))
+                    .unwrap();
+                table
+                    .set(&entity_id, "active", PropertyValue::new_boolean(active))
+                    .unwrap();
+                table
+                    .set(&entity_id, "name", PropertyValue::new_string(name))
+                    .unwrap();
+            }
+            table
+                .set(&b, "nickname", PropertyValue::new_null())
+                .unwrap();
+            table
+                .set(&c, "nickname", PropertyValue::new_string(""))
+                .unwrap();
+            table
+                .set(&a, "city", PropertyValue::new_string("Paris"))
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "people").unwrap();
+
+        assert_eq!(find(&table, "age", PropertyValue::new_integer(30)), [a, b]);
+        assert_eq!(find(&table, "age", PropertyValue::new_integer(-30)), [c]);
+        assert_eq!(find(&table, "score", PropertyValue::new_float(1.5)), [a, c]);
+        assert_eq!(find(&table, "score", PropertyValue::new_float(-1.5)), [b]);
+        assert_eq!(
+            find(&table, "active", PropertyValue::new_boolean(false)),
+            [b]
+        );
+        assert_eq!(
+            find(&table, "name", PropertyValue::new_string("Alice")),
+            [a, c]
+        );
+        assert_eq!(find(&table, "nickname", PropertyValue::new_null()), [b]);
+        assert_eq!(find(&table, "nickname", PropertyValue::new_string("")), [c]);
+
+        // Values only match values of the same type and of the queried property
+        assert!(find(&table, "age", PropertyValue::new_float(30.0)).is_empty());
+        assert!(find(&table, "age", PropertyValue::new_null()).is_empty());
+        assert!(find(&table, "name", PropertyValue::new_string("Alic")).is_empty());
+        assert!(find(&table, "score", PropertyValue::new_integer(30)).is_empty());
+
+        assert!(
+            table
+                .find_by_value("city", &PropertyValue::new_string("Paris"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_value_index_follows_writes() {
+        let (_temp, db) = setup_test_db();
+        let [a, b, c, d] = [1, 2, 3, 4].map(Uuid::from_u128);
+        let active = || PropertyValue::new_string("active");
+        let inactive = || PropertyValue::new_string("inactive");
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let spec = IndexSpec::new().property("status");
+            let mut table = PropertyTable::open_indexed(&write_txn, "users", spec).unwrap();
+            for entity_id in [a, b, c, d] {
+                table.set(&entity_id, "status", active()).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        // Writes through a table opened without a spec keep the index up to date
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "users").unwrap();
+            table.set(&a, "status", inactive()).unwrap();
+            assert!(table.delete(&b, "status").unwrap());
+            table
+                .insert_bulk(
+                    &[
+                        ((c, "status"), PropertyValue::new_null().as_ref()),
+                        ((c, "status"), inactive().as_ref()),
+                    ],
+                    false,
+                )
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "users").unwrap();
+        assert_eq!(find(&table, "status", active()), [d]);
+        assert_eq!(find(&table, "status", inactive()), [a, c]);
+        assert!(find(&table, "status", PropertyValue::new_null()).is_empty());
+        drop(table);
+        drop(read_txn);
+
+        // Postings are written with the properties, so an aborted write leaves both alone
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "users").unwrap();
+            table.set(&d, "status", inactive()).unwrap();
+        }
+        write_txn.abort().unwrap();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "users").unwrap();
+            assert_eq!(table.remove_bulk(&[(a, "status")]).unwrap(), 1);
+            assert_eq!(table.delete_range(c..=c).unwrap(), 1);
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "users").unwrap();
+        assert_eq!(find(&table, "status", active()), [d]);
+        assert!(find(&table, "status", inactive()).is_empty());
+    }
+
+    #[test]
+    fn test_open_indexed_indexes_stored_values() {
+        let (_temp, db) = setup_test_db();
+        let [a, b] = [1, 2].map(Uuid::from_u128);
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "users").unwrap();
+            table
+                .set(&a, "tier", PropertyValue::new_integer(1))
+                .unwrap();
+            table
+                .set(&b, "tier", PropertyValue::new_integer(2))
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "users").unwrap();
+        assert!(
+            table
+                .find_by_value("tier", &PropertyValue::new_integer(1))
+                .is_err()
+        );
+        drop(table);
+        drop(read_txn);
+
+        let write_txn = db.begin_write().unwrap();
+        PropertyTable::open_indexed(&write_txn, "users", IndexSpec::new().property("tier"))
+            .unwrap();
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "users").unwrap();
+        assert_eq!(find(&table, "tier", PropertyValue::new_integer(1)), [a]);
+        assert_eq!(find(&table, "tier", PropertyValue::new_integer(2)), [b]);
+    }
+
+    #[test]
+    fn test_find_by_value_hides_expired() {
+        let (_temp, db) = setup_test_db();
+        let [a, b] = [1, 2].map(Uuid::from_u128);
+        let value = PropertyValue::new_string_with_timestamps("pending", 1000, 1000);
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let spec = IndexSpec::new().property("state");
+            let mut table = PropertyTable::open_indexed(&write_txn, "jobs", spec).unwrap();
+            table.set(&a, "state", value.clone()).unwrap();
+            table
+                .insert_with_ttl(&b, "state", value.clone(), Duration::from_nanos(500))
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "jobs")
+            .unwrap()
+            .with_expiry_policy(ExpiryPolicy::HideExpiredAt(1499));
+        assert_eq!(find(&table, "state", value.clone()), [a, b]);
+        let table = table.with_expiry_policy(ExpiryPolicy::HideExpiredAt(1500));
+        assert_eq!(find(&table, "state", value.clone()), [a]);
+        drop(table);
+        drop(read_txn);
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "jobs").unwrap();
+            assert_eq!(table.purge_expired(1500).unwrap(), 1);
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "jobs")
+            .unwrap()
+            .with_expiry_policy(ExpiryPolicy::IncludeExpired);
+        assert_eq!(find(&table, "state", value), [a]);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::from_u128(1);
+        let long_key = "k".repeat(DEFAULT_MAX_KEY_LEN);
+        let long_value = "v".repeat(DEFAULT_MAX_VALUE_LEN);
+        let too_long_key = "k".repeat(DEFAULT_MAX_KEY_LEN + 1);
+        let too_long_value = "v".repeat(DEFAULT_MAX_VALUE_LEN + 1);
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            table
+                .set(&entity_id, &long_key, PropertyValue::new_integer(1))
+                .unwrap();
+            table
+                .set(
+                    &entity_id,
+                    "bio",
+                    PropertyValue::new_string(long_value.as_str()),
+                )
+                .unwrap();
+
+            let err = table
+                .set(&entity_id, &too_long_key, PropertyValue::new_integer(1))
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                PropertyError::KeyTooLong { len, max: DEFAULT_MAX_KEY_LEN }
+                    if len == DEFAULT_MAX_KEY_LEN + 1
+            ));
+            let err = table
+                .set(
+                    &entity_id,
+                    "bio",
+                    PropertyValue::new_string(too_long_value.as_str()),
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                PropertyError::ValueTooLarge { len, max: DEFAULT_MAX_VALUE_LEN }
+                    if len == DEFAULT_MAX_VALUE_LEN + 1
+            ));
+
+            // A batch with one entry over the limit writes none of its entries
+            let entries = [
+                ((entity_id, "first"), PropertyValue::new_integer(1)),
+                (
+                    (entity_id, too_long_key.as_str()),
+                    PropertyValue::new_integer(2),
+                ),
+            ];
+            assert!(matches!(
+                table.set_many(&entries),
+                Err(PropertyError::KeyTooLong { .. })
+            ));
+            let first = PropertyValue::new_integer(1);
+            let second = PropertyValue::new_string(too_long_value.as_str());
+            let items = [
+                ((entity_id, "first"), first.as_ref()),
+                ((entity_id, "second"), second.as_ref()),
+            ];
+            assert!(matches!(
+                table.insert_bulk(&items, true),
+                Err(PropertyError::ValueTooLarge { .. })
+            ));
+            assert_eq!(table.len().unwrap(), 2);
+        }
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties")
+                .unwrap()
+                .with_max_key_len(3)
+                .with_max_value_len(5);
+            table
+                .set(&entity_id, "abc", PropertyValue::new_string("12345"))
+                .unwrap();
+            assert!(matches!(
+                table.set(&entity_id, "abcd", PropertyValue::new_integer(1)),
+                Err(PropertyError::KeyTooLong { len: 4, max: 3 })
+            ));
+            assert!(matches!(
+                table.set(&entity_id, "abc", PropertyValue::new_string("123456")),
+                Err(PropertyError::ValueTooLarge { len: 6, max: 5 })
+            ));
+        }
+        write_txn.commit().unwrap();
+    }
 }
@@ -25,6 +25,7 @@ use uuid::Uuid;
 /// The property value that was valid at the given timestamp, or None if:
 /// - The property didn't exist at that time
 /// - The property's valid_from is after the requested timestamp
+/// - The property had expired by the requested timestamp
 ///
 /// # Example
 ///
@@ -52,7 +53,7 @@ pub fn get_property_at<'a>(
     // Get the current property
     if let Some(guard) = table.get(entity_id, property_key)? {
         // Check if this version was valid at the requested timestamp
-        if guard.valid_from() <= timestamp {
+        if guard.valid_from() <= timestamp && !guard.is_expired_at(timestamp) {
             return Ok(Some(guard));
         }
     }
@@ -179,7 +180,7 @@ pub fn get_all_properties_at<'a>(
 
     // Filter to only those valid at the requested timestamp
     for (key, guard) in all_properties {
-        if guard.valid_from() <= timestamp {
+        if guard.valid_from() <= timestamp && !guard.is_expired_at(timestamp) {
             results.push((key, guard));
         }
     }
@@ -229,8 +230,9 @@ pub fn property_existed_at(
 mod tests {
     use super::*;
     use crate::PropertyValue;
-    use crate::table::PropertyTable;
+    use crate::table::{ExpiryPolicy, PropertyTable};
     use manifold::{Database, ReadableDatabase};
+    use std::time::Duration;
     use tempfile::TempDir;
 
     fn setup_test_db() -> (TempDir, Database) {
@@ -456,4 +458,33 @@ mod tests {
         // Should exist at timestamp
         assert!(property_existed_at(&table, &entity_id, "optional", 2000).unwrap());
     }
+
+    #[test]
+    fn test_get_property_at_respects_expiry() {
+        let (_temp, db) = setup_test_db();
+        let entity_id = Uuid::new_v4();
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = PropertyTable::open(&write_txn, "properties").unwrap();
+            let prop = PropertyValue::new_integer_with_timestamps(42, 1000, 1000);
+            table
+                .insert_with_ttl(&entity_id, "age", prop, Duration::from_nanos(1000))
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = PropertyTableRead::open(&read_txn, "properties")
+            .unwrap()
+            .with_expiry_policy(ExpiryPolicy::IncludeExpired);
+
+        assert!(property_existed_at(&table, &entity_id, "age", 1999).unwrap());
+        assert!(!property_existed_at(&table, &entity_id, "age", 2000).unwrap());
+        assert!(
+            get_all_properties_at(&table, &entity_id, 2000)
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
@@ -0,0 +1,136 @@
+//! Read compatibility with property tables written by earlier versions
+//!
+//! `tests/golden/` holds a database written by `write_golden` at each released version, named
+//! after that version. Every golden file must pass `check_golden` with the current code. See
+//! `docs/on_disk_format.md` at the root of the repository for the table formats and for when
+//! to add a golden file.
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_properties::{
+    ExpiryPolicy, IndexSpec, PropertyTable, PropertyTableRead, PropertyValue,
+};
+use std::path::Path;
+use uuid::Uuid;
+
+#[path = "../../../tests/support/golden.rs"]
+mod golden;
+
+const GOLDEN_PREFIX: &str = "properties-v";
+
+const A: Uuid = Uuid::from_u128(1);
+const B: Uuid = Uuid::from_u128(2);
+
+/// Every property of the fixture, in table order
+fn properties() -> Vec<((Uuid, &'static str), PropertyValue)> {
+    vec![
+        (
+            (A, "active"),
+            PropertyValue::new_boolean_with_timestamps(true, 30, 3),
+        ),
+        (
+            (A, "age"),
+            PropertyValue::new_integer_with_timestamps(i64::MIN, 10, 1),
+        ),
+        (
+            (A, "name"),
+            PropertyValue::new_string_with_timestamps("Ada", 40, 4),
+        ),
+        ((A, "note"), PropertyValue::new_null_with_timestamps(50, 5)),
+        (
+            (A, "score"),
+            PropertyValue::new_float_with_timestamps(-0.25, 20, 2),
+        ),
+        (
+            (A, "token"),
+            PropertyValue::new_string_with_timestamps("secret", 60, 6).with_expires_at(Some(1000)),
+        ),
+        (
+            (B, "age"),
+            PropertyValue::new_integer_with_timestamps(42, 70, 7),
+        ),
+        (
+            (B, "name"),
+            PropertyValue::new_string_with_timestamps("", 80, 8),
+        ),
+    ]
+}
+
+/// Writes the fixture to `path`: a property of every value type, one that has expired, and a
+/// value index over two of the properties
+///
+/// Must not change once a golden file has been written with it.
+fn write_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    // Grows from 64 KB as it is written, so the golden file stays small
+    let cf = db
+        .create_column_family("entities", Some(64 * 1024))
+        .unwrap();
+
+    let write_txn = cf.begin_write().unwrap();
+    {
+        let spec = IndexSpec::new().property("age").property("name");
+        let mut table = PropertyTable::open_indexed(&write_txn, "people", spec).unwrap();
+        for ((entity_id, key), value) in properties() {
+            table.set(&entity_id, key, value).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+}
+
+/// Asserts that the database at `path` holds exactly what `write_golden` wrote
+fn check_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let cf = db.column_family("entities").unwrap();
+    let read_txn = cf.begin_read().unwrap();
+
+    let table = PropertyTableRead::open(&read_txn, "people").unwrap();
+    assert!(table.get(&A, "token").unwrap().is_none());
+    assert_eq!(
+        table.get(&B, "age").unwrap().unwrap().to_owned(),
+        PropertyValue::new_integer_with_timestamps(42, 70, 7)
+    );
+    let matches: Vec<Uuid> = table
+        .find_by_value("name", &PropertyValue::new_string("Ada"))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(matches, [A]);
+    let matches: Vec<Uuid> = table
+        .find_by_value("age", &PropertyValue::new_integer(i64::MIN))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(matches, [A]);
+
+    let table = table.with_expiry_policy(ExpiryPolicy::IncludeExpired);
+    let stored: Vec<((Uuid, String), PropertyValue)> = table
+        .iter()
+        .unwrap()
+        .map(|entry| {
+            let (key, guard) = entry.unwrap();
+            (key, guard.to_owned())
+        })
+        .collect();
+    let expected: Vec<((Uuid, String), PropertyValue)> = properties()
+        .into_iter()
+        .map(|((entity_id, key), value)| ((entity_id, key.to_string()), value))
+        .collect();
+    assert_eq!(stored, expected);
+}
+
+#[test]
+fn test_golden_files_read_back() {
+    golden::check_golden_files(GOLDEN_PREFIX, check_golden);
+}
+
+#[test]
+fn test_fixture_round_trips() {
+    golden::check_fixture_round_trips(write_golden, check_golden);
+}
+
+/// Writes the golden file of the current version
+#[test]
+#[ignore = "run once per release to add its golden file"]
+fn bless_golden_file() {
+    golden::bless_golden_file(GOLDEN_PREFIX, write_golden);
+}
@@ -0,0 +1,89 @@
+//! Weekly averages computed by an external engine from the day aggregates.
+//!
+//! This example demonstrates:
+//! - Writing a month of raw data and downsampling it to day aggregates
+//! - An analytics function that only knows the `TimeSeriesSource` trait
+//! - Combining day aggregates into weekly averages without reading raw points
+//!
+//! Run with: cargo run --example weekly_average
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_timeseries::{
+    AbsoluteEncoding, Granularity, TimeSeriesError, TimeSeriesSource, TimeSeriesTable,
+    TimeSeriesTableRead,
+};
+use tempfile::tempdir;
+
+const HOUR: u64 = 3_600_000;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Computes the average of each week in `start_ms..end_ms` from the day aggregates of a series.
+///
+/// This is what a downstream crate would write: it depends only on the trait, and weighs each
+/// day by its number of points so that the result equals the average of the raw points.
+fn weekly_averages<'a, S: TimeSeriesSource<'a>>(
+    source: &'a S,
+    series_id: &str,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<Vec<(u64, f64)>, TimeSeriesError> {
+    let mut weeks: Vec<(u64, f64, u64)> = vec![];
+    for day in source.iter_aggregates(Granularity::Day, series_id, start_ms, end_ms)? {
+        let (day_start, aggregate) = day?;
+        let week_start = day_start - day_start % WEEK;
+        match weeks.last_mut() {
+            Some((start, sum, count)) if *start == week_start => {
+                *sum += aggregate.sum;
+                *count += aggregate.count;
+            }
+            _ => weeks.push((week_start, aggregate.sum, aggregate.count)),
+        }
+    }
+    Ok(weeks
+        .into_iter()
+        .map(|(start, sum, count)| (start, sum / count as f64))
+        .collect())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("=== Weekly Averages from Day Aggregates ===\n");
+
+    let dir = tempdir()?;
+    let db = ColumnFamilyDatabase::open(dir.path().join("weekly.db"))?;
+    let cf = db.column_family_or_create("metrics")?;
+
+    // Four weeks of hourly readings, rising by one every day
+    let days = 28;
+    {
+        let write_txn = cf.begin_write()?;
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "power")?;
+        for hour in 0..days * 24 {
+            ts.write("meter1", hour * HOUR, (hour / 24) as f32)?;
+        }
+
+        // Downsample through every level up to days
+        let end = days * DAY;
+        ts.downsample_to_minute("meter1", 0, end)?;
+        ts.downsample_minute_to_hour("meter1", 0, end)?;
+        let written = ts.downsample_hour_to_day("meter1", 0, end)?;
+        println!(
+            "Wrote {} hourly points and {written} day aggregates\n",
+            days * 24
+        );
+
+        drop(ts);
+        write_txn.commit()?;
+    }
+
+    let read_txn = cf.begin_read()?;
+    let ts_read = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "power")?;
+    for (week_start, average) in weekly_averages(&ts_read, "meter1", 0, days * DAY)? {
+        println!(
+            "Week starting day {:>2}: average {average:.1}",
+            week_start / DAY
+        );
+    }
+
+    Ok(())
+}
@@ -1,11 +1,25 @@
 //! Aggregate types and granularity levels for time series downsampling.
 
+use crate::value::FixedWidthValue;
 use manifold::{TypeName, Value};
 
+/// Encoding version of the current aggregate format.
+const AGGREGATE_VERSION: u8 = 2;
+
+/// Width of an encoded [`Aggregate`].
+const AGGREGATE_WIDTH: usize = 73;
+
+/// Encoding version of the [`AggregateV1`] format.
+const AGGREGATE_V1_VERSION: u8 = 1;
+
+/// Width of an encoded [`AggregateV1`].
+const AGGREGATE_V1_WIDTH: usize = 53;
+
 /// Time series aggregate containing statistical summaries.
 ///
-/// This is a fixed-width struct (24 bytes) that can be efficiently stored
-/// and retrieved from Manifold without serialization overhead.
+/// This is a fixed-width struct (73 bytes) that can be efficiently stored
+/// and retrieved from Manifold without serialization overhead. Values are accumulated in
+/// `f64` whatever the value type of the time series.
 ///
 /// # Fields
 ///
@@ -13,58 +27,103 @@ use manifold::{TypeName, Value};
 /// - `max`: Maximum value in the time window
 /// - `sum`: Sum of all values (used to compute average)
 /// - `count`: Number of data points aggregated
-/// - `last`: Last value seen in the time window
+/// - `first`: Earliest value seen in the time window
+/// - `last`: Latest value seen in the time window
+/// - `sum_sq`: Sum of squared values (used to compute standard deviation)
+/// - `first_ts`: Timestamp of `first`
+/// - `last_ts`: Timestamp of `last`
+///
+/// Aggregates written in an earlier format are upgraded when their table is opened for
+/// writing. Those written before `first`, `sum_sq` and the timestamps were added have an
+/// unknown `sum_sq`, stored as NaN, so [`stddev`](Self::stddev) of any aggregate that includes
+/// them is NaN. Those written before values were accumulated in `f64` keep the `f32` rounding
+/// they were accumulated with.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Aggregate {
     /// Minimum value in the aggregation window.
-    pub min: f32,
+    pub min: f64,
     /// Maximum value in the aggregation window.
-    pub max: f32,
+    pub max: f64,
     /// Sum of all values in the aggregation window.
-    pub sum: f32,
+    pub sum: f64,
     /// Number of data points in the aggregation window.
     pub count: u64,
-    /// Last value in the aggregation window.
-    pub last: f32,
+    /// Earliest value in the aggregation window.
+    pub first: f64,
+    /// Latest value in the aggregation window.
+    pub last: f64,
+    /// Sum of squared values in the aggregation window.
+    pub sum_sq: f64,
+    /// Timestamp of the earliest value, in milliseconds.
+    pub first_ts: u64,
+    /// Timestamp of the latest value, in milliseconds.
+    pub last_ts: u64,
 }
 
 impl Aggregate {
     /// Creates a new aggregate from a single value.
-    pub fn from_value(value: f32) -> Self {
+    ///
+    /// The value is recorded at timestamp 0; use [`from_point`](Self::from_point) when the
+    /// timestamp is known.
+    pub fn from_value(value: f64) -> Self {
+        Self::from_point(0, value)
+    }
+
+    /// Creates a new aggregate from a single data point.
+    pub fn from_point(timestamp_ms: u64, value: f64) -> Self {
         Self {
             min: value,
             max: value,
             sum: value,
             count: 1,
+            first: value,
             last: value,
+            sum_sq: value * value,
+            first_ts: timestamp_ms,
+            last_ts: timestamp_ms,
         }
     }
 
     /// Creates an empty aggregate (used as a starting point for accumulation).
     pub fn empty() -> Self {
         Self {
-            min: f32::INFINITY,
-            max: f32::NEG_INFINITY,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
             sum: 0.0,
             count: 0,
+            first: 0.0,
             last: 0.0,
+            sum_sq: 0.0,
+            first_ts: 0,
+            last_ts: 0,
         }
     }
 
     /// Accumulates a value into this aggregate.
-    pub fn accumulate(&mut self, value: f32) {
+    ///
+    /// The value is treated as following every value already accumulated, so it becomes
+    /// `last`. Use [`accumulate_point`](Self::accumulate_point) when the timestamp is known.
+    pub fn accumulate(&mut self, value: f64) {
         if self.count == 0 {
             *self = Self::from_value(value);
         } else {
-            self.min = self.min.min(value);
-            self.max = self.max.max(value);
-            self.sum += value;
-            self.count += 1;
-            self.last = value;
+            self.accumulate_point(self.last_ts, value);
         }
     }
 
+    /// Accumulates a data point into this aggregate.
+    ///
+    /// Points may arrive in any order; `first` and `last` follow their timestamps. A point
+    /// with the same timestamp as `last` replaces it.
+    pub fn accumulate_point(&mut self, timestamp_ms: u64, value: f64) {
+        self.merge(&Self::from_point(timestamp_ms, value));
+    }
+
     /// Merges another aggregate into this one.
+    ///
+    /// The two aggregates may cover overlapping or disjoint time ranges, in any order. This
+    /// combines partial aggregates of the same window, such as partially filled hours when
+    /// downsampling to days.
     pub fn merge(&mut self, other: &Self) {
         if other.count == 0 {
             return;
@@ -77,29 +136,249 @@ impl Aggregate {
         self.max = self.max.max(other.max);
         self.sum += other.sum;
         self.count += other.count;
-        self.last = other.last;
+        self.sum_sq += other.sum_sq;
+        if other.first_ts < self.first_ts {
+            self.first = other.first;
+            self.first_ts = other.first_ts;
+        }
+        if other.last_ts >= self.last_ts {
+            self.last = other.last;
+            self.last_ts = other.last_ts;
+        }
     }
 
     /// Computes the average value.
     ///
     /// Returns `0.0` if count is zero.
     #[allow(clippy::cast_precision_loss)]
-    pub fn average(&self) -> f32 {
+    pub fn average(&self) -> f64 {
         if self.count == 0 {
             0.0
         } else {
-            self.sum / (self.count as f32)
+            self.sum / (self.count as f64)
         }
     }
 
+    /// Computes the population variance.
+    ///
+    /// Returns `0.0` if count is zero. The result is derived from `sum` and `sum_sq`, so
+    /// its absolute error grows with the mean square of the values: series whose spread is
+    /// small relative to their magnitude lose precision.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let count = self.count as f64;
+        let mean = self.sum / count;
+        let variance = self.sum_sq / count - mean * mean;
+        // Rounding can push the difference slightly below zero for near-constant values. A
+        // NaN `sum_sq` from an upgraded legacy aggregate is passed through
+        if variance < 0.0 { 0.0 } else { variance }
+    }
+
+    /// Computes the population standard deviation.
+    ///
+    /// Returns `0.0` if count is zero.
+    pub fn stddev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
     /// Returns true if this aggregate contains no data points.
     pub fn is_empty(&self) -> bool {
         self.count == 0
     }
 }
 
+/// Folds partial aggregates into one, as if by repeated [`Aggregate::merge`].
+impl FromIterator<Aggregate> for Aggregate {
+    fn from_iter<I: IntoIterator<Item = Aggregate>>(iter: I) -> Self {
+        let mut aggregate = Self::empty();
+        for other in iter {
+            aggregate.merge(&other);
+        }
+        aggregate
+    }
+}
+
+/// Folds `(timestamp_ms, value)` data points into an aggregate.
+impl<V: FixedWidthValue> FromIterator<(u64, V)> for Aggregate {
+    fn from_iter<I: IntoIterator<Item = (u64, V)>>(iter: I) -> Self {
+        let mut aggregate = Self::empty();
+        for (timestamp_ms, value) in iter {
+            aggregate.accumulate_point(timestamp_ms, value.to_f64());
+        }
+        aggregate
+    }
+}
+
 /// Implements Value trait for efficient Manifold storage.
+///
+/// The encoding starts with a version byte, followed by the fields in declaration order.
 impl Value for Aggregate {
+    type SelfType<'a> = Self;
+    type AsBytes<'a> = [u8; AGGREGATE_WIDTH];
+
+    fn fixed_width() -> Option<usize> {
+        Some(AGGREGATE_WIDTH)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        assert_eq!(
+            data.len(),
+            AGGREGATE_WIDTH,
+            "Aggregate must be exactly {AGGREGATE_WIDTH} bytes"
+        );
+        assert_eq!(data[0], AGGREGATE_VERSION, "Unknown aggregate version");
+
+        let u64_at = |i: usize| u64::from_be_bytes(data[i..i + 8].try_into().unwrap());
+        let f64_at = |i: usize| f64::from_bits(u64_at(i));
+
+        Self {
+            min: f64_at(1),
+            max: f64_at(9),
+            sum: f64_at(17),
+            count: u64_at(25),
+            first: f64_at(33),
+            last: f64_at(41),
+            sum_sq: f64_at(49),
+            first_ts: u64_at(57),
+            last_ts: u64_at(65),
+        }
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'a + 'b,
+    {
+        let mut bytes = [0u8; AGGREGATE_WIDTH];
+        bytes[0] = AGGREGATE_VERSION;
+        bytes[1..9].copy_from_slice(&value.min.to_be_bytes());
+        bytes[9..17].copy_from_slice(&value.max.to_be_bytes());
+        bytes[17..25].copy_from_slice(&value.sum.to_be_bytes());
+        bytes[25..33].copy_from_slice(&value.count.to_be_bytes());
+        bytes[33..41].copy_from_slice(&value.first.to_be_bytes());
+        bytes[41..49].copy_from_slice(&value.last.to_be_bytes());
+        bytes[49..57].copy_from_slice(&value.sum_sq.to_be_bytes());
+        bytes[57..65].copy_from_slice(&value.first_ts.to_be_bytes());
+        bytes[65..73].copy_from_slice(&value.last_ts.to_be_bytes());
+        bytes
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("manifold_timeseries::Aggregate")
+    }
+}
+
+/// An aggregate format of an earlier version, read when upgrading old tables.
+pub(crate) trait AggregateFormat: for<'a> Value<SelfType<'a> = Self> + 'static {
+    /// Converts to the current format for the window starting at `bucket_ts`.
+    fn upgrade(self, bucket_ts: u64) -> Aggregate;
+}
+
+/// The 53-byte aggregate format with `f32` values, read when upgrading old tables.
+///
+/// Layout: a version byte, then `min`, `max` and `sum` as `f32`, `count`, `first` and `last`
+/// as `f32`, `sum_sq`, `first_ts` and `last_ts`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AggregateV1(Aggregate);
+
+impl AggregateFormat for AggregateV1 {
+    /// Widens the values to `f64`, which is exact.
+    fn upgrade(self, _bucket_ts: u64) -> Aggregate {
+        self.0
+    }
+}
+
+impl Value for AggregateV1 {
+    type SelfType<'a> = Self;
+    type AsBytes<'a> = [u8; AGGREGATE_V1_WIDTH];
+
+    fn fixed_width() -> Option<usize> {
+        Some(AGGREGATE_V1_WIDTH)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        assert_eq!(data[0], AGGREGATE_V1_VERSION, "Unknown aggregate version");
+
+        let f32_at = |i: usize| f32::from_be_bytes(data[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_be_bytes(data[i..i + 8].try_into().unwrap());
+
+        Self(Aggregate {
+            min: f64::from(f32_at(1)),
+            max: f64::from(f32_at(5)),
+            sum: f64::from(f32_at(9)),
+            count: u64_at(13),
+            first: f64::from(f32_at(21)),
+            last: f64::from(f32_at(25)),
+            sum_sq: f64::from_bits(u64_at(29)),
+            first_ts: u64_at(37),
+            last_ts: u64_at(45),
+        })
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'a + 'b,
+    {
+        let value = &value.0;
+        let mut bytes = [0u8; AGGREGATE_V1_WIDTH];
+        bytes[0] = AGGREGATE_V1_VERSION;
+        bytes[1..5].copy_from_slice(&(value.min as f32).to_be_bytes());
+        bytes[5..9].copy_from_slice(&(value.max as f32).to_be_bytes());
+        bytes[9..13].copy_from_slice(&(value.sum as f32).to_be_bytes());
+        bytes[13..21].copy_from_slice(&value.count.to_be_bytes());
+        bytes[21..25].copy_from_slice(&(value.first as f32).to_be_bytes());
+        bytes[25..29].copy_from_slice(&(value.last as f32).to_be_bytes());
+        bytes[29..37].copy_from_slice(&value.sum_sq.to_be_bytes());
+        bytes[37..45].copy_from_slice(&value.first_ts.to_be_bytes());
+        bytes[45..53].copy_from_slice(&value.last_ts.to_be_bytes());
+        bytes
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("manifold_timeseries::Aggregate")
+    }
+}
+
+/// The original 24-byte aggregate format, read when upgrading old tables.
+///
+/// Layout: `[min: f32][max: f32][sum: f32][count: u64][last: f32]`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LegacyAggregate {
+    min: f32,
+    max: f32,
+    sum: f32,
+    count: u64,
+    last: f32,
+}
+
+impl AggregateFormat for LegacyAggregate {
+    /// The legacy format has no sum of squares and no first value or timestamps, so `sum_sq`
+    /// is NaN, `first` is `last`, and both timestamps are the window start.
+    fn upgrade(self, bucket_ts: u64) -> Aggregate {
+        Aggregate {
+            min: f64::from(self.min),
+            max: f64::from(self.max),
+            sum: f64::from(self.sum),
+            count: self.count,
+            first: f64::from(self.last),
+            last: f64::from(self.last),
+            sum_sq: f64::NAN,
+            first_ts: bucket_ts,
+            last_ts: bucket_ts,
+        }
+    }
+}
+
+impl Value for LegacyAggregate {
     type SelfType<'a> = Self;
     type AsBytes<'a> = [u8; 24];
 
@@ -111,22 +390,14 @@ impl Value for Aggregate {
     where
         Self: 'a,
     {
-        assert_eq!(data.len(), 24, "Aggregate must be exactly 24 bytes");
-
-        let min = f32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-        let max = f32::from_be_bytes([data[4], data[5], data[6], data[7]]);
-        let sum = f32::from_be_bytes([data[8], data[9], data[10], data[11]]);
-        let count = u64::from_be_bytes([
-            data[12], data[13], data[14], data[15], data[16], data[17], data[18], data[19],
-        ]);
-        let last = f32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+        let f32_at = |i: usize| f32::from_be_bytes(data[i..i + 4].try_into().unwrap());
 
         Self {
-            min,
-            max,
-            sum,
-            count,
-            last,
+            min: f32_at(0),
+            max: f32_at(4),
+            sum: f32_at(8),
+            count: u64::from_be_bytes(data[12..20].try_into().unwrap()),
+            last: f32_at(20),
         }
     }
 
@@ -182,6 +453,21 @@ impl Granularity {
         }
     }
 
+    /// Selects the coarsest granularity that divides a window into at least `max_points`
+    /// buckets.
+    ///
+    /// Returns [`Granularity::Raw`] if even minute buckets would be too few.
+    ///
+    /// For example, a one day window with `max_points` of 500 selects
+    /// [`Granularity::Minute`] (1440 buckets), since hours would give only 24.
+    pub fn for_window(window_ms: u64, max_points: usize) -> Self {
+        let max_points = u64::try_from(max_points).unwrap_or(u64::MAX);
+        [Self::Day, Self::Hour, Self::Minute]
+            .into_iter()
+            .find(|granularity| window_ms / granularity.duration_ms() >= max_points)
+            .unwrap_or(Self::Raw)
+    }
+
     /// Rounds a timestamp down to the start of the granularity window.
     ///
     /// For example, with `Granularity::Minute`, timestamp `1609459261500` (01:01:01.500)
@@ -256,6 +542,72 @@ mod tests {
         assert_eq!(agg1.last, 25.0);
     }
 
+    #[test]
+    fn test_aggregate_merge_partials() {
+        let points: Vec<(u64, f32)> = (0..100u16)
+            .map(|i| (u64::from(i) * 1_000, f32::from(i % 17) - 4.0))
+            .collect();
+        let whole: Aggregate = points.iter().copied().collect();
+
+        // Partial aggregates merged in any order give the same result
+        let partials: Vec<Aggregate> = points
+            .chunks(30)
+            .rev()
+            .map(|chunk| chunk.iter().copied().collect())
+            .collect();
+        let merged: Aggregate = partials.into_iter().collect();
+
+        assert_eq!(merged, whole);
+        assert_eq!((merged.first_ts, merged.first), (0, -4.0));
+        assert_eq!((merged.last_ts, merged.last), (99_000, 10.0));
+        assert!(merged.stddev() > 0.0);
+
+        let constant: Aggregate = [(0, 3.0), (1, 3.0), (2, 3.0)].into_iter().collect();
+        assert!(constant.variance().abs() < f64::EPSILON);
+        assert!(Aggregate::empty().stddev().abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_legacy_aggregate_upgrade() {
+        let legacy = LegacyAggregate {
+            min: 1.0,
+            max: 9.0,
+            sum: 20.0,
+            count: 4,
+            last: 6.0,
+        };
+        let decoded = LegacyAggregate::from_bytes(&LegacyAggregate::as_bytes(&legacy));
+        let agg = decoded.upgrade(60_000);
+
+        assert_eq!((agg.min, agg.max, agg.sum, agg.count), (1.0, 9.0, 20.0, 4));
+        assert_eq!((agg.first, agg.last), (6.0, 6.0));
+        assert_eq!((agg.first_ts, agg.last_ts), (60_000, 60_000));
+        assert!(agg.stddev().is_nan());
+        assert!((agg.average() - 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_aggregate_v1_upgrade() {
+        let v1 = Aggregate {
+            min: 1.5,
+            max: 10.5,
+            sum: 42.0,
+            count: 7,
+            first: 2.5,
+            last: 8.5,
+            sum_sq: 301.25,
+            first_ts: 1_000,
+            last_ts: 7_000,
+        };
+        let bytes = AggregateV1::as_bytes(&AggregateV1(v1));
+        assert_eq!(bytes.len(), 53);
+        assert_eq!(bytes[0], AGGREGATE_V1_VERSION);
+
+        // Values representable as f32 survive the upgrade unchanged
+        let upgraded = AggregateV1::from_bytes(&bytes).upgrade(0);
+        assert_eq!(upgraded, v1);
+    }
+
     #[test]
     fn test_aggregate_value_trait() {
         let agg = Aggregate {
@@ -263,11 +615,16 @@ mod tests {
             max: 10.5,
             sum: 42.0,
             count: 7,
+            first: 2.5,
             last: 8.5,
+            sum_sq: 301.25,
+            first_ts: 1_000,
+            last_ts: 7_000,
         };
 
         let bytes = Aggregate::as_bytes(&agg);
-        assert_eq!(bytes.len(), 24);
+        assert_eq!(bytes.len(), 73);
+        assert_eq!(bytes[0], AGGREGATE_VERSION);
 
         let decoded = Aggregate::from_bytes(&bytes);
         assert_eq!(decoded, agg);
@@ -301,6 +658,24 @@ mod tests {
         assert_eq!(Granularity::Day.round_up(ts), 1_609_545_600_000); // Next day 00:00:00.000
     }
 
+    #[test]
+    fn test_granularity_for_window() {
+        assert_eq!(Granularity::for_window(3_600_000, 500), Granularity::Raw);
+        assert_eq!(
+            Granularity::for_window(86_400_000, 500),
+            Granularity::Minute
+        );
+        assert_eq!(
+            Granularity::for_window(30 * 86_400_000, 500),
+            Granularity::Hour
+        );
+        assert_eq!(
+            Granularity::for_window(30 * 86_400_000, 30),
+            Granularity::Day
+        );
+        assert_eq!(Granularity::for_window(0, 1), Granularity::Raw);
+    }
+
     #[test]
     fn test_aggregate_empty() {
         let agg = Aggregate::empty();
@@ -0,0 +1,298 @@
+//! Evenly spaced reads of raw data points.
+//!
+//! [`TimeSeriesTableRead::range_aligned`] divides a time range into buckets of a fixed step and
+//! yields one point per bucket, filling empty buckets according to a [`FillPolicy`], so charts
+//! can plot series with irregular spacing and gaps without resampling them first.
+//!
+//! # Buckets
+//!
+//! Bucket `i` covers `start + i * step` (inclusive) to `start + (i + 1) * step` (exclusive),
+//! and its point has the bucket start as timestamp. A range of `end - start` milliseconds has
+//! exactly `(end - start) / step` buckets; when the range is not a multiple of the step, the
+//! points after the last full bucket are not read.
+//!
+//! A bucket with several points takes the value of the last one, the point with the latest
+//! timestamp. The range is read once, in order, and only the buckets on either side of the
+//! current one are kept in memory.
+
+use crate::encoding::TimestampEncoding;
+use crate::error::TimeSeriesError;
+use crate::timeseries::{RangeIter, TimeSeriesTableRead, check_range};
+use crate::value::FixedWidthValue;
+
+/// How buckets without any data point are filled by
+/// [`TimeSeriesTableRead::range_aligned`].
+///
+/// Only points inside the requested range are considered. A fill that needs a neighbouring
+/// bucket with data, such as [`Previous`](Self::Previous) for leading gaps, leaves the bucket
+/// as a miss when there is none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillPolicy {
+    /// Leave empty buckets as misses, with no value.
+    #[default]
+    None,
+    /// Repeat the value of the closest earlier bucket with data.
+    Previous,
+    /// Interpolate linearly between the closest buckets with data on either side, by bucket
+    /// position. Interpolated `i64` values are rounded to the nearest integer.
+    Linear,
+    /// Fill empty buckets with zero.
+    Zero,
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTableRead<E, V> {
+    /// Returns the raw data points of a series resampled to evenly spaced buckets.
+    ///
+    /// Yields `(bucket_start, value)` for every bucket, in order, with `value` set to `None` for
+    /// an empty bucket that `fill` leaves as a miss. See the [module documentation](self) for
+    /// how buckets are laid out and which point a bucket with several points takes.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier
+    /// * `start_ms` - Start timestamp (inclusive), the start of the first bucket
+    /// * `end_ms` - End timestamp (exclusive), not before `start_ms`
+    /// * `step_ms` - Size of each bucket, must be non-zero
+    /// * `fill` - How empty buckets are filled
+    pub fn range_aligned(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        step_ms: u64,
+        fill: FillPolicy,
+    ) -> Result<AlignedIter<'_, V>, TimeSeriesError> {
+        if step_ms == 0 {
+            return Err(TimeSeriesError::InvalidArgument("step must be non-zero"));
+        }
+        check_range(start_ms, end_ms)?;
+
+        let buckets = (end_ms - start_ms) / step_ms;
+        Ok(AlignedIter {
+            points: self.range(series_id, start_ms, start_ms + buckets * step_ms)?,
+            start: start_ms,
+            step: step_ms,
+            buckets,
+            fill,
+            index: 0,
+            previous: None,
+            next: None,
+            pending: None,
+            exhausted: false,
+        })
+    }
+}
+
+/// Iterator over evenly spaced points, created by [`TimeSeriesTableRead::range_aligned`].
+pub struct AlignedIter<'a, V: FixedWidthValue = f32> {
+    points: RangeIter<'a, V>,
+    start: u64,
+    step: u64,
+    buckets: u64,
+    fill: FillPolicy,
+    // Index of the next bucket to yield
+    index: u64,
+    // Index and value of the last bucket yielded with data
+    previous: Option<(u64, V)>,
+    // Index and value of the first bucket with data at or after `index`, once read
+    next: Option<(u64, V)>,
+    // A point read past the end of the bucket in `next`
+    pending: Option<(u64, V)>,
+    // Set when `points` has no more points
+    exhausted: bool,
+}
+
+impl<V: FixedWidthValue> AlignedIter<'_, V> {
+    /// Reads the next bucket with data, returning its index and the value of its last point.
+    fn read_bucket(&mut self) -> Result<Option<(u64, V)>, TimeSeriesError> {
+        let Some(first) = self.pending.take().map(Ok).or_else(|| self.points.next()) else {
+            self.exhausted = true;
+            return Ok(None);
+        };
+        let first = first?;
+
+        let (start, step) = (self.start, self.step);
+        let bucket_of = |timestamp: u64| (timestamp - start) / step;
+        let bucket = bucket_of(first.0);
+        let mut value = first.1;
+        for point in self.points.by_ref() {
+            let (timestamp, next_value) = point?;
+            if bucket_of(timestamp) != bucket {
+                self.pending = Some((timestamp, next_value));
+                return Ok(Some((bucket, value)));
+            }
+            value = next_value;
+        }
+        self.exhausted = true;
+        Ok(Some((bucket, value)))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn fill_gap(&self) -> Option<V> {
+        match self.fill {
+            FillPolicy::None => None,
+            FillPolicy::Zero => Some(V::ZERO),
+            FillPolicy::Previous => self.previous.map(|(_, value)| value),
+            FillPolicy::Linear => {
+                let (before, from) = self.previous?;
+                let (after, to) = self.next?;
+                let fraction = (self.index - before) as f64 / (after - before) as f64;
+                let (from, to) = (from.to_f64(), to.to_f64());
+                Some(V::from_f64(from + (to - from) * fraction))
+            }
+        }
+    }
+}
+
+impl<V: FixedWidthValue> Iterator for AlignedIter<'_, V> {
+    type Item = Result<(u64, Option<V>), TimeSeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.buckets {
+            return None;
+        }
+
+        if self.next.is_none() && !self.exhausted {
+            match self.read_bucket() {
+                Ok(next) => self.next = next,
+                Err(e) => {
+                    // Stop after a failed read
+                    self.index = self.buckets;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let timestamp = self.start + self.index * self.step;
+        let value = match self.next {
+            Some((bucket, value)) if bucket == self.index => {
+                self.previous = self.next.take();
+                Some(value)
+            }
+            _ => self.fill_gap(),
+        };
+        self.index += 1;
+
+        Some(Ok((timestamp, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use crate::timeseries::TimeSeriesTable;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    // Renders aligned values compactly, with `-` for a miss
+    fn render(values: &[Option<f32>]) -> String {
+        let values: Vec<String> = values
+            .iter()
+            .map(|value| value.map_or("-".to_string(), |value| value.to_string()))
+            .collect();
+        values.join(" ")
+    }
+
+    fn aligned(
+        ts: &TimeSeriesTableRead<AbsoluteEncoding>,
+        start_ms: u64,
+        end_ms: u64,
+        step_ms: u64,
+        fill: FillPolicy,
+    ) -> (Vec<u64>, Vec<Option<f32>>) {
+        ts.range_aligned("s", start_ms, end_ms, step_ms, fill)
+            .unwrap()
+            .map(Result::unwrap)
+            .unzip()
+    }
+
+    #[test]
+    fn test_range_aligned_fill_policies() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            // Leading gap in buckets 0-1, two points in bucket 3 (one on its start), a gap in
+            // buckets 4-5, and a trailing gap after bucket 6
+            for (timestamp, value) in [(25, 2.0), (30, 3.5), (38, 4.0), (62, 10.0), (101, 99.0)] {
+                ts.write("s", timestamp, value).unwrap();
+            }
+            // Points of another series in the gaps are not seen
+            for timestamp in [0, 45, 70, 99] {
+                ts.write("other", timestamp, -1.0).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+
+        // The partial bucket at 100..105 is left out, along with its point
+        let (timestamps, values) = aligned(&ts, 0, 105, 10, FillPolicy::None);
+        assert_eq!(timestamps, (0..100).step_by(10).collect::<Vec<u64>>());
+        assert_eq!(render(&values), "- - 2 4 - - 10 - - -");
+
+        let (_, values) = aligned(&ts, 0, 100, 10, FillPolicy::Previous);
+        assert_eq!(render(&values), "- - 2 4 4 4 10 10 10 10");
+
+        let (_, values) = aligned(&ts, 0, 100, 10, FillPolicy::Linear);
+        assert_eq!(render(&values), "- - 2 4 6 8 10 - - -");
+
+        let (_, values) = aligned(&ts, 0, 100, 10, FillPolicy::Zero);
+        assert_eq!(render(&values), "0 0 2 4 0 0 10 0 0 0");
+
+        // Buckets start at the range start, not at multiples of the step
+        let (timestamps, values) = aligned(&ts, 5, 65, 10, FillPolicy::None);
+        assert_eq!(timestamps, [5, 15, 25, 35, 45, 55]);
+        assert_eq!(render(&values), "- - 3.5 4 - 10");
+
+        // A single bucket covering the whole series takes its last point
+        let (timestamps, values) = aligned(&ts, 0, 200, 200, FillPolicy::None);
+        assert_eq!(timestamps, [0]);
+        assert_eq!(render(&values), "99");
+    }
+
+    #[test]
+    fn test_range_aligned_arguments() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.write("s", 5, 1.0).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+
+        assert!(matches!(
+            ts.range_aligned("s", 0, 100, 0, FillPolicy::None),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ts.range_aligned("s", 100, 0, 10, FillPolicy::None),
+            Err(TimeSeriesError::InvalidRange { .. })
+        ));
+
+        // Ranges shorter than a step have no buckets
+        assert_eq!(aligned(&ts, 10, 10, 10, FillPolicy::Zero).0.len(), 0);
+        assert_eq!(aligned(&ts, 0, 9, 10, FillPolicy::Zero).0.len(), 0);
+
+        // Series without points are all misses
+        let values: Vec<Option<f32>> = ts
+            .range_aligned("missing", 0, 30, 10, FillPolicy::Previous)
+            .unwrap()
+            .map(|point| point.unwrap().1)
+            .collect();
+        assert_eq!(render(&values), "- - -");
+    }
+}
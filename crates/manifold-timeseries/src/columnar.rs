@@ -0,0 +1,233 @@
+//! Columnar export of raw data points.
+//!
+//! [`TimeSeriesTableRead::to_columnar_chunks`] reads a window of one or more series in a single
+//! ordered scan and returns it as [`ColumnarChunk`]s, so analytics tools can load it a batch at
+//! a time instead of point by point.
+//!
+//! # Memory layout
+//!
+//! Each column of a chunk is a separate `Vec`, and row `i` of the chunk is
+//! `(series[i], timestamps[i], values[i])`. `timestamps` and `values` are contiguous arrays of
+//! native-endian `u64` and of the value type of the time series, so a caller holding the chunk
+//! can expose their pointers and lengths as, for `f32` values, `uint64` and `float32` arrays
+//! (for example with `numpy.frombuffer`) without copying. `series` holds one owned string per
+//! row and has to be converted.
+
+use crate::encoding::TimestampEncoding;
+use crate::error::TimeSeriesError;
+use crate::timeseries::{TimeSeriesTableRead, check_range};
+use crate::value::FixedWidthValue;
+use std::collections::HashSet;
+
+/// A batch of raw data points in columnar form.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnarChunk<V = f32> {
+    /// Series identifier of each row.
+    pub series: Vec<String>,
+    /// Timestamp of each row, in milliseconds.
+    pub timestamps: Vec<u64>,
+    /// Value of each row.
+    pub values: Vec<V>,
+}
+
+impl<V> ColumnarChunk<V> {
+    /// Creates an empty chunk with room for `rows` rows.
+    pub fn with_capacity(rows: usize) -> Self {
+        Self {
+            series: Vec::with_capacity(rows),
+            timestamps: Vec::with_capacity(rows),
+            values: Vec::with_capacity(rows),
+        }
+    }
+
+    /// Returns the number of rows in the chunk.
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// Returns `true` if the chunk has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    fn push(&mut self, series_id: &str, timestamp: u64, value: V) {
+        self.series.push(series_id.to_string());
+        self.timestamps.push(timestamp);
+        self.values.push(value);
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTableRead<E, V> {
+    /// Returns the raw data points of several series in a time range as columnar chunks.
+    ///
+    /// Rows are in storage order: by timestamp, then by series identifier. Every chunk except
+    /// the last holds exactly `batch_rows` rows, and no chunk is empty, so a range without data
+    /// yields no chunks.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_ids` - Series identifiers to export
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive), not before `start_ms`
+    /// * `batch_rows` - Maximum number of rows per chunk, must be non-zero
+    pub fn to_columnar_chunks(
+        &self,
+        series_ids: &[&str],
+        start_ms: u64,
+        end_ms: u64,
+        batch_rows: usize,
+    ) -> Result<ColumnarIter<'_, V>, TimeSeriesError> {
+        if batch_rows == 0 {
+            return Err(TimeSeriesError::InvalidArgument(
+                "batch size must be non-zero",
+            ));
+        }
+        check_range(start_ms, end_ms)?;
+
+        Ok(ColumnarIter {
+            inner: self.raw_table().range((start_ms, "")..(end_ms, ""))?,
+            series_ids: series_ids.iter().map(ToString::to_string).collect(),
+            batch_rows,
+            done: false,
+        })
+    }
+}
+
+/// Iterator over columnar chunks of raw data points.
+pub struct ColumnarIter<'a, V: FixedWidthValue = f32> {
+    inner: manifold::Range<'a, (u64, &'static str), V>,
+    series_ids: HashSet<String>,
+    batch_rows: usize,
+    done: bool,
+}
+
+impl<V: FixedWidthValue> Iterator for ColumnarIter<'_, V> {
+    type Item = Result<ColumnarChunk<V>, TimeSeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.series_ids.is_empty() {
+            return None;
+        }
+
+        let mut chunk = ColumnarChunk::with_capacity(self.batch_rows);
+        while chunk.len() < self.batch_rows {
+            match self.inner.next() {
+                Some(Ok((key_guard, value_guard))) => {
+                    let (timestamp, series_id) = key_guard.value();
+                    if self.series_ids.contains(series_id) {
+                        chunk.push(series_id, timestamp, value_guard.value());
+                    }
+                }
+                Some(Err(e)) => {
+                    // Stop after a failed read
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(Ok(chunk))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use crate::timeseries::TimeSeriesTable;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    fn rows(chunks: &[ColumnarChunk]) -> Vec<(u64, String, f32)> {
+        let mut rows = vec![];
+        for chunk in chunks {
+            assert_eq!(chunk.series.len(), chunk.len());
+            assert_eq!(chunk.values.len(), chunk.len());
+            for i in 0..chunk.len() {
+                rows.push((
+                    chunk.timestamps[i],
+                    chunk.series[i].clone(),
+                    chunk.values[i],
+                ));
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_columnar_chunks_match_range() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for i in 0..1000u16 {
+                let timestamp = u64::from(i) * 1000;
+                ts.write("a", timestamp, f32::from(i)).unwrap();
+                ts.write("c", timestamp + 500, -f32::from(i)).unwrap();
+                if i % 3 == 0 {
+                    ts.write("b", timestamp, 0.5).unwrap();
+                }
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let (start, end) = (10_000, 900_000);
+
+        let chunks: Vec<ColumnarChunk> = ts
+            .to_columnar_chunks(&["a", "b"], start, end, 64)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let (last, full) = chunks.split_last().unwrap();
+        assert!(full.iter().all(|chunk| chunk.len() == 64));
+        assert!(!last.is_empty() && last.len() <= 64);
+
+        let mut expected = vec![];
+        for series_id in ["a", "b"] {
+            for point in ts.range(series_id, start, end).unwrap() {
+                let (timestamp, value) = point.unwrap();
+                expected.push((timestamp, series_id.to_string(), value));
+            }
+        }
+        expected.sort_by(|x, y| (x.0, &x.1).cmp(&(y.0, &y.1)));
+        assert_eq!(rows(&chunks), expected);
+
+        // Batch boundaries neither drop nor duplicate rows
+        for batch_rows in [1, 7, 1000, expected.len(), 10_000] {
+            let chunks: Vec<ColumnarChunk> = ts
+                .to_columnar_chunks(&["a", "b"], start, end, batch_rows)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(rows(&chunks), expected);
+        }
+
+        assert_eq!(ts.to_columnar_chunks(&["a"], 5, 5, 10).unwrap().count(), 0);
+        assert_eq!(
+            ts.to_columnar_chunks(&[], start, end, 10).unwrap().count(),
+            0
+        );
+        assert!(matches!(
+            ts.to_columnar_chunks(&["a"], start, end, 0),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ts.to_columnar_chunks(&["a"], end, start, 10),
+            Err(TimeSeriesError::InvalidRange { .. })
+        ));
+    }
+}
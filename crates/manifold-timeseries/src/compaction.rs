@@ -0,0 +1,422 @@
+//! Packing of raw points into chunks.
+//!
+//! Every raw point is a row of its own, so scanning a long range of old data reads one key per
+//! point. [`TimeSeriesTable::compact_range`] moves the raw points of a series in a time range
+//! into the `{name}_chunks` table, where each row is a chunk of consecutive points keyed by
+//! `(series_id, chunk_start_ms)`, the timestamp of its first point.
+//!
+//! A chunk holds the number of its points as a varint, followed by each point in timestamp
+//! order: the varint delta of its timestamp from the previous point, starting from the chunk
+//! key, and the bytes of its value.
+//!
+//! The chunks of a series never overlap: compacting a range that reaches into existing chunks
+//! unpacks them and packs their points again with the new ones. Points written into a
+//! compacted window afterwards are stored as raw rows like any other, and
+//! [`TimeSeriesTableRead::range`](crate::TimeSeriesTableRead::range) and
+//! [`TimeSeriesTableRead::get`](crate::TimeSeriesTableRead::get) merge them with the chunks; a
+//! raw point replaces a packed point with the same timestamp.
+
+use crate::encoding::{DeltaEncoding, EncodingError, TimestampEncoding};
+use crate::error::TimeSeriesError;
+use crate::timeseries::{TimeSeriesTable, check_range};
+use crate::value::FixedWidthValue;
+use manifold::{ReadOnlyTable, ReadableTable, StorageError};
+use std::collections::BTreeMap;
+
+/// Key of a chunk: its series and the timestamp of its first point.
+pub(crate) type ChunkKey = (&'static str, u64);
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTable<'_, E, V> {
+    /// Packs the raw points of a series in `start_ms..end_ms` into chunks of at most
+    /// `chunk_points` points each, and deletes the raw rows.
+    ///
+    /// Chunks the points reach into are unpacked and packed again together with them, so the
+    /// chunks of a series never overlap. Where a raw point and a packed point share a
+    /// timestamp, the raw point is kept. Compacting a range without raw points of the series
+    /// does nothing.
+    ///
+    /// Packed points are read by [`TimeSeriesTableRead::range`](crate::TimeSeriesTableRead::range)
+    /// and [`TimeSeriesTableRead::get`](crate::TimeSeriesTableRead::get), and removed by
+    /// [`delete_series`](Self::delete_series). Downsampling, retention,
+    /// [`delete_series_range`](Self::delete_series_range), duplicate policies and
+    /// [`len`](Self::len) only see raw rows, so only compact windows that are already
+    /// downsampled and will not be written again.
+    ///
+    /// Returns the number of raw points packed.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier to compact
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive)
+    /// * `chunk_points` - Largest number of points in a chunk; must be non-zero
+    pub fn compact_range(
+        &mut self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        chunk_points: usize,
+    ) -> Result<usize, TimeSeriesError> {
+        check_range(start_ms, end_ms)?;
+        if chunk_points == 0 {
+            return Err(TimeSeriesError::InvalidArgument(
+                "chunk_points must be non-zero",
+            ));
+        }
+
+        let mut loose = Vec::new();
+        for item in self.raw.range((start_ms, series_id)..(end_ms, series_id))? {
+            let (key_guard, value_guard) = item?;
+            let (timestamp, sid) = key_guard.value();
+            if sid == series_id {
+                loose.push((timestamp, value_guard.value()));
+            }
+        }
+        let (Some(&(first_ms, _)), Some(&(last_ms, _))) = (loose.first(), loose.last()) else {
+            return Ok(0);
+        };
+
+        let mut points = BTreeMap::new();
+        let mut unpacked = Vec::new();
+        // Below the exclusive end, so this cannot overflow
+        for item in chunks_from(self.chunks.table()?, series_id, first_ms, last_ms + 1)? {
+            let (key_guard, value_guard) = item?;
+            let (_, chunk_ms) = key_guard.value();
+            let chunk = decode_chunk::<V>(chunk_ms, value_guard.value())?;
+            // The chunk starting before the points may end before them too
+            if chunk
+                .last()
+                .is_some_and(|&(timestamp, _)| timestamp >= first_ms)
+            {
+                unpacked.push(chunk_ms);
+                points.extend(chunk);
+            }
+        }
+        for chunk_ms in unpacked {
+            self.chunks.table_mut()?.remove((series_id, chunk_ms))?;
+        }
+        for &(timestamp, _) in &loose {
+            self.raw.remove((timestamp, series_id))?;
+        }
+
+        let count = loose.len();
+        points.extend(loose);
+        let points: Vec<(u64, V)> = points.into_iter().collect();
+        for chunk in points.chunks(chunk_points) {
+            self.chunks
+                .table_mut()?
+                .insert((series_id, chunk[0].0), encode_chunk(chunk).as_slice())?;
+        }
+        Ok(count)
+    }
+
+    /// Removes every chunk of `series_id` and returns how many points they held.
+    pub(crate) fn remove_chunks(&mut self, series_id: &str) -> Result<usize, TimeSeriesError> {
+        let mut chunks = Vec::new();
+        for item in self
+            .chunks
+            .table()?
+            .range((series_id, 0)..=(series_id, u64::MAX))?
+        {
+            let (key_guard, value_guard) = item?;
+            let (count, _) = DeltaEncoding::decode_varint(value_guard.value())?;
+            chunks.push((key_guard.value().1, count));
+        }
+
+        let mut points = 0;
+        for (chunk_ms, count) in chunks {
+            self.chunks.table_mut()?.remove((series_id, chunk_ms))?;
+            points += usize::try_from(count).unwrap_or(usize::MAX);
+        }
+        Ok(points)
+    }
+}
+
+/// Returns the width of the stored values of `V`.
+fn value_width<V: FixedWidthValue>() -> usize {
+    V::fixed_width().expect("FixedWidthValue types have a fixed width")
+}
+
+/// Packs points, in timestamp order, into the bytes of a chunk.
+fn encode_chunk<V: FixedWidthValue>(points: &[(u64, V)]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(1 + points.len() * (2 + value_width::<V>()));
+    DeltaEncoding::encode_varint(points.len() as u64, &mut bytes);
+    let mut previous = points.first().map_or(0, |&(timestamp, _)| timestamp);
+    for (timestamp, value) in points {
+        DeltaEncoding::encode_varint(timestamp - previous, &mut bytes);
+        bytes.extend_from_slice(V::as_bytes(value).as_ref());
+        previous = *timestamp;
+    }
+    bytes
+}
+
+/// Unpacks the points of the chunk starting at `start_ms`.
+fn decode_chunk<V: FixedWidthValue>(
+    start_ms: u64,
+    bytes: &[u8],
+) -> Result<Vec<(u64, V)>, EncodingError> {
+    let width = value_width::<V>();
+    let (count, mut offset) = DeltaEncoding::decode_varint(bytes)?;
+    let mut points = Vec::with_capacity(usize::try_from(count).map_or(0, |n| n.min(bytes.len())));
+    let mut timestamp = start_ms;
+    for _ in 0..count {
+        let (delta, used) = DeltaEncoding::decode_varint(&bytes[offset..])?;
+        offset += used;
+        let value = bytes
+            .get(offset..offset + width)
+            .ok_or_else(|| EncodingError::InvalidData("Chunk ends inside a point".to_string()))?;
+        offset += width;
+        timestamp = timestamp
+            .checked_add(delta)
+            .ok_or_else(|| EncodingError::InvalidData("Chunk timestamp overflow".to_string()))?;
+        points.push((timestamp, V::from_bytes(value)));
+    }
+    Ok(points)
+}
+
+/// Returns the chunks of `series_id` that can hold points in `start_ms..end_ms`: the last one
+/// starting at or before `start_ms`, which may reach into the range, and every one starting in
+/// it.
+fn chunks_from<'a>(
+    table: &'a impl ReadableTable<ChunkKey, &'static [u8]>,
+    series_id: &str,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<manifold::Range<'a, ChunkKey, &'static [u8]>, StorageError> {
+    let first_ms = match table
+        .range((series_id, 0)..=(series_id, start_ms))?
+        .next_back()
+    {
+        Some(item) => item?.0.value().1,
+        None => start_ms,
+    };
+    table.range((series_id, first_ms)..(series_id, end_ms))
+}
+
+/// Looks up the packed point of a series at `timestamp_ms`.
+pub(crate) fn packed_point<V: FixedWidthValue>(
+    table: &ReadOnlyTable<ChunkKey, &'static [u8]>,
+    series_id: &str,
+    timestamp_ms: u64,
+) -> Result<Option<V>, TimeSeriesError> {
+    let Some(item) = table
+        .range((series_id, 0)..=(series_id, timestamp_ms))?
+        .next_back()
+    else {
+        return Ok(None);
+    };
+    let (key_guard, value_guard) = item?;
+    let points = decode_chunk::<V>(key_guard.value().1, value_guard.value())?;
+    Ok(points
+        .binary_search_by_key(&timestamp_ms, |&(timestamp, _)| timestamp)
+        .ok()
+        .map(|index| points[index].1))
+}
+
+/// The packed points of one series in a time range, unpacked one chunk at a time.
+pub(crate) struct PackedRange<'a, V: FixedWidthValue> {
+    table: &'a ReadOnlyTable<ChunkKey, &'static [u8]>,
+    chunks: manifold::Range<'a, ChunkKey, &'static [u8]>,
+    points: std::vec::IntoIter<(u64, V)>,
+    series_id: String,
+    end_ms: u64,
+    /// Smallest timestamp the next point can have.
+    next_ms: u64,
+}
+
+impl<'a, V: FixedWidthValue> PackedRange<'a, V> {
+    pub(crate) fn new(
+        table: &'a ReadOnlyTable<ChunkKey, &'static [u8]>,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<Self, TimeSeriesError> {
+        Ok(Self {
+            table,
+            chunks: chunks_from(table, series_id, start_ms, end_ms)?,
+            points: Vec::new().into_iter(),
+            series_id: series_id.to_string(),
+            end_ms,
+            next_ms: start_ms,
+        })
+    }
+
+    /// Restarts the scan at `timestamp_ms`, unless the scan is already past it.
+    pub(crate) fn seek(&mut self, timestamp_ms: u64) -> Result<(), TimeSeriesError> {
+        if timestamp_ms <= self.next_ms {
+            return Ok(());
+        }
+        let start_ms = timestamp_ms.min(self.end_ms);
+        self.chunks = chunks_from(self.table, &self.series_id, start_ms, self.end_ms)?;
+        self.points = Vec::new().into_iter();
+        self.next_ms = timestamp_ms;
+        Ok(())
+    }
+}
+
+impl<V: FixedWidthValue> Iterator for PackedRange<'_, V> {
+    type Item = Result<(u64, V), TimeSeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((timestamp, value)) = self.points.next() {
+                if timestamp < self.next_ms {
+                    continue;
+                }
+                // Later chunks start after this one ends, so the range is done
+                if timestamp >= self.end_ms {
+                    return None;
+                }
+                self.next_ms = timestamp + 1;
+                return Some(Ok((timestamp, value)));
+            }
+
+            let (key_guard, value_guard) = match self.chunks.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err.into())),
+            };
+            match decode_chunk(key_guard.value().1, value_guard.value()) {
+                Ok(points) => self.points = points.into_iter(),
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use crate::timeseries::TimeSeriesTableRead;
+    use manifold::ReadableTableMetadata;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    fn read_range(
+        ts: &TimeSeriesTableRead<AbsoluteEncoding>,
+        start: u64,
+        end: u64,
+    ) -> Vec<(u64, f32)> {
+        ts.range("a", start, end)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_compact_range_round_trip() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Irregular gaps, each too large for a one-byte varint
+        let points: Vec<(u64, f32)> = (0..100u16)
+            .map(|i| (u64::from(i) * 1000 + u64::from(i % 7) * 100, f32::from(i)))
+            .collect();
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.write_series_batch("a", &points, true).unwrap();
+            ts.write_series_batch("b", &points, true).unwrap();
+
+            assert_eq!(ts.compact_range("a", 0, 60_000, 16).unwrap(), 60);
+            assert_eq!(ts.compact_range("a", 0, 60_000, 16).unwrap(), 0);
+            assert!(matches!(
+                ts.compact_range("a", 0, 60_000, 0),
+                Err(TimeSeriesError::InvalidArgument(_))
+            ));
+            assert_eq!(ts.len().unwrap(), 140);
+            assert_eq!(ts.chunks.table().unwrap().len().unwrap(), 4);
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        assert_eq!(read_range(&ts, 0, u64::MAX), points);
+        // Ranges starting and ending inside chunks, and spanning packed and raw points
+        assert_eq!(read_range(&ts, 20_700, 50_000), points[21..50]);
+        assert_eq!(read_range(&ts, 55_000, 70_000), points[55..70]);
+        assert_eq!(read_range(&ts, 33_000, 33_000), []);
+        assert_eq!(ts.get("a", points[17].0).unwrap(), Some(17.0));
+        assert_eq!(ts.get("a", points[17].0 + 1).unwrap(), None);
+        assert_eq!(ts.get("a", points[80].0).unwrap(), Some(80.0));
+
+        let mut iter = ts.range("a", 0, u64::MAX).unwrap();
+        iter.seek(points[40].0).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), points[40]);
+        iter.seek(points[75].0).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), points[75]);
+        iter.seek(0).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), points[76]);
+
+        let other: Vec<(u64, f32)> = ts
+            .range("b", 0, u64::MAX)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(other, points);
+    }
+
+    #[test]
+    fn test_writes_into_compacted_window() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for i in 0..10 {
+                ts.write("a", i * 10, 1.0).unwrap();
+            }
+            assert_eq!(ts.compact_range("a", 0, 100, 4).unwrap(), 10);
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        // Late data between packed points, and over a packed point
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.write("a", 35, 2.0).unwrap();
+            ts.write("a", 50, 3.0).unwrap();
+            ts.write("a", 95, 4.0).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let mut expected: Vec<(u64, f32)> = (0..10).map(|i| (i * 10, 1.0)).collect();
+        expected.insert(4, (35, 2.0));
+        expected[6] = (50, 3.0);
+        expected.push((95, 4.0));
+        {
+            let read_txn = cf.begin_read().unwrap();
+            let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+            assert_eq!(read_range(&ts, 0, 100), expected);
+            assert_eq!(read_range(&ts, 40, 60), [(40, 1.0), (50, 3.0)]);
+            assert_eq!(ts.get("a", 50).unwrap(), Some(3.0));
+            assert_eq!(ts.len().unwrap(), 3);
+        }
+
+        // Compacting part of the window again repacks the chunks the late points fall into
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            assert_eq!(ts.compact_range("a", 30, 60, 4).unwrap(), 2);
+            assert_eq!(ts.len().unwrap(), 1);
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+        {
+            let read_txn = cf.begin_read().unwrap();
+            let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+            assert_eq!(read_range(&ts, 0, 100), expected);
+        }
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        assert_eq!(ts.delete_series("a").unwrap().raw, 12);
+        assert!(ts.chunks.table().unwrap().is_empty().unwrap());
+    }
+}
@@ -0,0 +1,265 @@
+//! Increase and rate of monotonic counters, such as bytes sent or request totals.
+//!
+//! [`TimeSeriesTableRead::increase`] and [`TimeSeriesTableRead::rate`] read the raw points of a
+//! counter once, in order, and add up how much it grew between consecutive points.
+//!
+//! # Resets
+//!
+//! A counter whose value drops, as when the process that owns it restarts, is taken to have
+//! been reset to zero and counted up again. The increase between the point before the drop and
+//! the point after it is the value after the drop, so the pre-reset total is kept and the
+//! increase over a range with resets is the sum of what each segment between resets counted.
+//!
+//! # Window edges
+//!
+//! Only points inside the requested range are read, and nothing is extrapolated: the counter is
+//! not assumed to have grown between the start of the range and its first point, or between its
+//! last point and the end. The first point of a range is only a baseline, so a range with fewer
+//! than two points has an increase of zero.
+
+use crate::encoding::TimestampEncoding;
+use crate::error::TimeSeriesError;
+use crate::timeseries::{TimeSeriesTableRead, check_range};
+use crate::value::FixedWidthValue;
+
+/// Maximum number of windows [`rate`](TimeSeriesTableRead::rate) returns.
+///
+/// The result holds one entry per window whether or not it has data, so the limit keeps a wide
+/// range with a small window from allocating without bound.
+pub const MAX_RATE_WINDOWS: u64 = 1 << 20;
+
+/// Returns how much a counter grew from `previous` to `value`, treating a drop as a reset.
+fn counter_increase(previous: f64, value: f64) -> f64 {
+    if value < previous {
+        value
+    } else {
+        value - previous
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTableRead<E, V> {
+    /// Returns how much a counter grew over a time range, compensating for resets.
+    ///
+    /// See the [module documentation](crate::counter) for how resets are detected and how the
+    /// edges of the range are treated.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive), not before `start_ms`
+    pub fn increase(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<f64, TimeSeriesError> {
+        let mut total = 0.0;
+        self.for_each_increase(series_id, start_ms, end_ms, |_, increase| total += increase)?;
+        Ok(total)
+    }
+
+    /// Returns the per-second rate of a counter over evenly spaced windows, compensating for
+    /// resets.
+    ///
+    /// Windows are laid out as the buckets of
+    /// [`range_aligned`](TimeSeriesTableRead::range_aligned): window `i` covers
+    /// `start + i * window` (inclusive) to `start + (i + 1) * window` (exclusive), and the
+    /// points after the last full window are not read. The growth between two consecutive
+    /// points counts towards the window of the later point, so the increases of all windows add
+    /// up to [`increase`](Self::increase) over the windows, including across their edges.
+    ///
+    /// Returns `(window_start, rate)` for every window, with `rate` set to `None` for a window
+    /// without a point that follows another one in the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier
+    /// * `start_ms` - Start timestamp (inclusive), the start of the first window
+    /// * `end_ms` - End timestamp (exclusive), not before `start_ms`
+    /// * `window_ms` - Size of each window, must be non-zero
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimeSeriesError::InvalidArgument`] if `window_ms` is zero or the range holds
+    /// more than [`MAX_RATE_WINDOWS`] windows.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn rate(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        window_ms: u64,
+    ) -> Result<Vec<(u64, Option<f64>)>, TimeSeriesError> {
+        if window_ms == 0 {
+            return Err(TimeSeriesError::InvalidArgument("window must be non-zero"));
+        }
+        check_range(start_ms, end_ms)?;
+
+        let windows = (end_ms - start_ms) / window_ms;
+        if windows > MAX_RATE_WINDOWS {
+            return Err(TimeSeriesError::InvalidArgument(
+                "range holds more than MAX_RATE_WINDOWS windows",
+            ));
+        }
+        // Under the limit, the number of windows fits in a usize on every target
+        let mut increases = vec![None; usize::try_from(windows).unwrap()];
+        let windows_end = start_ms + windows * window_ms;
+        self.for_each_increase(series_id, start_ms, windows_end, |timestamp, increase| {
+            let window = usize::try_from((timestamp - start_ms) / window_ms).unwrap();
+            let window_increase = increases[window].get_or_insert(0.0);
+            *window_increase += increase;
+        })?;
+
+        let seconds = window_ms as f64 / 1000.0;
+        Ok((0..windows)
+            .zip(increases)
+            .map(|(window, increase)| {
+                (
+                    start_ms + window * window_ms,
+                    increase.map(|increase| increase / seconds),
+                )
+            })
+            .collect())
+    }
+
+    /// Calls `f` with the timestamp of every point of a range after the first, and how much the
+    /// counter grew since the point before it.
+    fn for_each_increase(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        mut f: impl FnMut(u64, f64),
+    ) -> Result<(), TimeSeriesError> {
+        let mut previous = None;
+        for point in self.range(series_id, start_ms, end_ms)? {
+            let (timestamp, value) = point?;
+            let value = value.to_f64();
+            if let Some(previous) = previous {
+                f(timestamp, counter_increase(previous, value));
+            }
+            previous = Some(value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use crate::timeseries::TimeSeriesTable;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    // One point per second, with resets after 3s and 5s: the segments between resets count
+    // 40, 15 and 13
+    const COUNTER: [(u64, f32); 8] = [
+        (0, 0.0),
+        (1000, 10.0),
+        (2000, 25.0),
+        (3000, 40.0),
+        (4000, 5.0),
+        (5000, 15.0),
+        (6000, 3.0),
+        (7000, 13.0),
+    ];
+
+    #[test]
+    fn test_increase_compensates_resets() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "bytes").unwrap();
+            for (timestamp, value) in COUNTER {
+                ts.write("sent", timestamp, value).unwrap();
+            }
+            ts.write("other", 3500, 1000.0).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "bytes").unwrap();
+
+        // The whole counter adds up its three segments
+        assert_eq!(ts.increase("sent", 0, 10_000).unwrap(), 40.0 + 15.0 + 13.0);
+
+        // A range starting mid-segment uses its first point as the baseline, with no
+        // extrapolation to the range edges
+        assert_eq!(ts.increase("sent", 1500, 5500).unwrap(), 15.0 + 5.0 + 10.0);
+
+        // Ranges with fewer than two points did not see the counter grow
+        assert_eq!(ts.increase("sent", 1000, 2000).unwrap(), 0.0);
+        assert_eq!(ts.increase("missing", 0, 10_000).unwrap(), 0.0);
+
+        assert!(matches!(
+            ts.increase("sent", 10, 0),
+            Err(TimeSeriesError::InvalidRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rate_windows_straddle_resets() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "bytes").unwrap();
+            for (timestamp, value) in COUNTER {
+                ts.write("sent", timestamp, value).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "bytes").unwrap();
+
+        // The second window holds the first reset and the third window the second one; the
+        // last window has no points
+        let rates = ts.rate("sent", 0, 12_000, 3000).unwrap();
+        assert_eq!(
+            rates,
+            [
+                (0, Some(25.0 / 3.0)),
+                (3000, Some((15.0 + 5.0 + 10.0) / 3.0)),
+                (6000, Some((3.0 + 10.0) / 3.0)),
+                (9000, None),
+            ]
+        );
+
+        // Growth between windows counts towards the window of the later point, so the rates
+        // add up to the increase over the windows
+        let rates = ts.rate("sent", 0, 8000, 2000).unwrap();
+        let total: f64 = rates.iter().map(|(_, rate)| rate.unwrap() * 2.0).sum();
+        assert_eq!(total, ts.increase("sent", 0, 8000).unwrap());
+        assert_eq!(rates[2], (4000, Some(7.5)));
+
+        // The first point of the range is only a baseline for the window it falls in
+        let rates = ts.rate("sent", 3000, 5000, 1000).unwrap();
+        assert_eq!(rates, [(3000, None), (4000, Some(5.0))]);
+
+        assert!(matches!(
+            ts.rate("sent", 0, 8000, 0),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+        assert!(ts.rate("sent", 0, 999, 1000).unwrap().is_empty());
+
+        // The windows are allocated up front, so their number is bounded
+        assert_eq!(
+            ts.rate("sent", 0, MAX_RATE_WINDOWS, 1).unwrap().len(),
+            usize::try_from(MAX_RATE_WINDOWS).unwrap()
+        );
+        assert!(matches!(
+            ts.rate("sent", 0, u64::MAX, 1),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+    }
+}
@@ -5,11 +5,33 @@
 
 use crate::aggregate::{Aggregate, Granularity};
 use crate::encoding::TimestampEncoding;
-use crate::timeseries::TimeSeriesTable;
-use manifold::{ReadableTable, StorageError};
+use crate::error::TimeSeriesError;
+use crate::maintenance::now_ms;
+use crate::timeseries::{TimeSeriesTable, check_range};
+use crate::value::FixedWidthValue;
+use manifold::ReadableTable;
 use std::collections::HashMap;
 
-impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
+/// The series [`TimeSeriesTable::rollup`] reads from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollupSource<'a> {
+    /// The listed series.
+    Series(&'a [&'a str]),
+    /// Every series whose identifier starts with the prefix, except the target series.
+    Prefix(&'a str),
+}
+
+impl RollupSource<'_> {
+    fn contains(&self, series_id: &str, target_series: &str) -> bool {
+        series_id != target_series
+            && match self {
+                Self::Series(series) => series.contains(&series_id),
+                Self::Prefix(prefix) => series_id.starts_with(prefix),
+            }
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTable<'_, E, V> {
     /// Downsamples raw data to minute-level aggregates.
     ///
     /// Reads raw data points for the specified series within the time range,
@@ -29,7 +51,7 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         self.downsample_range(series_id, start_ms, end_ms, Granularity::Minute)
     }
 
@@ -49,7 +71,7 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         self.downsample_aggregates(
             series_id,
             start_ms,
@@ -75,7 +97,7 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         self.downsample_aggregates(
             series_id,
             start_ms,
@@ -85,20 +107,223 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         )
     }
 
+    /// Downsamples the windows of a series completed since the previous call.
+    ///
+    /// Each series and granularity has a high-water mark, stored in the `{name}_watermarks`
+    /// table, holding the end of the last window downsampled by this method. Only the windows
+    /// between the mark and the last complete window are processed, and the mark is advanced
+    /// in the same transaction as the aggregates are written, so calling this again before
+    /// another window completes writes nothing.
+    ///
+    /// Minute windows are complete once the current time has passed their end. Hour and day
+    /// windows are complete once the level below has been downsampled through their end by
+    /// this method, so call it for [`Granularity::Minute`], then [`Granularity::Hour`], then
+    /// [`Granularity::Day`].
+    ///
+    /// Points written into a window behind the mark are not aggregated until
+    /// [`invalidate_after`](Self::invalidate_after) moves the mark back.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier to downsample
+    /// * `granularity` - Granularity of the aggregates to write
+    ///
+    /// # Returns
+    ///
+    /// Number of aggregates written
+    pub fn downsample_incremental(
+        &mut self,
+        series_id: &str,
+        granularity: Granularity,
+    ) -> Result<usize, TimeSeriesError> {
+        // Data at the source level is complete up to this point
+        let complete_ms = match granularity {
+            Granularity::Raw => {
+                return Err(TimeSeriesError::InvalidArgument(
+                    "cannot downsample to Raw granularity",
+                ));
+            }
+            Granularity::Minute => now_ms()?,
+            Granularity::Hour => self.watermark(series_id, Granularity::Minute)?.unwrap_or(0),
+            Granularity::Day => self.watermark(series_id, Granularity::Hour)?.unwrap_or(0),
+        };
+
+        let start_ms = self.watermark(series_id, granularity)?.unwrap_or(0);
+        let end_ms = granularity.round_down(complete_ms);
+        if end_ms <= start_ms {
+            return Ok(0);
+        }
+
+        let written = match granularity {
+            Granularity::Raw => unreachable!(),
+            Granularity::Minute => self.downsample_to_minute(series_id, start_ms, end_ms)?,
+            Granularity::Hour => self.downsample_minute_to_hour(series_id, start_ms, end_ms)?,
+            Granularity::Day => self.downsample_hour_to_day(series_id, start_ms, end_ms)?,
+        };
+        self.watermarks
+            .table_mut()?
+            .insert((series_id, granularity.table_suffix()), &end_ms)?;
+        Ok(written)
+    }
+
+    /// Returns the end of the last window of a series downsampled to `granularity` by
+    /// [`downsample_incremental`](Self::downsample_incremental), if any.
+    pub fn watermark(
+        &self,
+        series_id: &str,
+        granularity: Granularity,
+    ) -> Result<Option<u64>, TimeSeriesError> {
+        Ok(self
+            .watermarks
+            .table()?
+            .get((series_id, granularity.table_suffix()))?
+            .map(|guard| guard.value()))
+    }
+
+    /// Marks the windows of a series from `timestamp_ms` on to be downsampled again, after
+    /// late points were written into windows that
+    /// [`downsample_incremental`](Self::downsample_incremental) already processed.
+    ///
+    /// Moves the high-water mark of `granularity`, and of every coarser granularity, back to
+    /// the start of its window containing `timestamp_ms`. Marks already before that are left
+    /// alone. The next calls to `downsample_incremental` recompute those windows from the
+    /// level below and overwrite their aggregates.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier to invalidate
+    /// * `granularity` - Finest granularity whose aggregates are out of date
+    /// * `timestamp_ms` - Timestamp of the earliest late point
+    pub fn invalidate_after(
+        &mut self,
+        series_id: &str,
+        granularity: Granularity,
+        timestamp_ms: u64,
+    ) -> Result<(), TimeSeriesError> {
+        let levels: &[Granularity] = match granularity {
+            Granularity::Raw => {
+                return Err(TimeSeriesError::InvalidArgument(
+                    "Raw granularity has no high-water mark",
+                ));
+            }
+            Granularity::Minute => &[Granularity::Minute, Granularity::Hour, Granularity::Day],
+            Granularity::Hour => &[Granularity::Hour, Granularity::Day],
+            Granularity::Day => &[Granularity::Day],
+        };
+
+        for &level in levels {
+            let window_ms = level.round_down(timestamp_ms);
+            if self
+                .watermark(series_id, level)?
+                .is_some_and(|mark| mark > window_ms)
+            {
+                self.watermarks
+                    .table_mut()?
+                    .insert((series_id, level.table_suffix()), &window_ms)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolls several series up into one, such as the hosts of a service into the service.
+    ///
+    /// Groups the data of every source series in the time range by `granularity` window, and
+    /// writes one aggregate per window under `target_series`, holding the min, max, sum, count
+    /// and other statistics of all the sources together. Minute aggregates are computed from
+    /// the raw points of the sources, and hour and day aggregates from their minute
+    /// aggregates, so downsample the sources to minutes first for those.
+    ///
+    /// The aggregates of the target overwrite those already in the same windows, so rolling up
+    /// the same range again does not count any point twice. As with the other downsampling
+    /// methods, a window only partly inside the range is overwritten with the part inside it.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Series to roll up
+    /// * `target_series` - Series identifier to write the aggregates under
+    /// * `granularity` - Granularity of the aggregates to write
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive)
+    ///
+    /// # Returns
+    ///
+    /// Number of aggregates written
+    pub fn rollup(
+        &mut self,
+        source: RollupSource<'_>,
+        target_series: &str,
+        granularity: Granularity,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<usize, TimeSeriesError> {
+        if granularity == Granularity::Raw {
+            return Err(TimeSeriesError::InvalidArgument(
+                "cannot roll up to Raw granularity",
+            ));
+        }
+        check_range(start_ms, end_ms)?;
+        self.check_series_ids(std::iter::once(target_series))?;
+
+        // Group the points or minute aggregates of every source by target window
+        let mut buckets: HashMap<u64, Aggregate> = HashMap::new();
+        let start_key = (start_ms, "");
+        let end_key = (end_ms, "");
+
+        if granularity == Granularity::Minute {
+            for item in self.raw.range(start_key..end_key)? {
+                let (key_guard, value_guard) = item?;
+                let (timestamp, sid) = key_guard.value();
+                if !source.contains(sid, target_series) {
+                    continue;
+                }
+                buckets
+                    .entry(granularity.round_down(timestamp))
+                    .or_insert_with(Aggregate::empty)
+                    .accumulate_point(timestamp, value_guard.value().to_f64());
+            }
+        } else {
+            for item in self.minute.table()?.range(start_key..end_key)? {
+                let (key_guard, agg_guard) = item?;
+                let (timestamp, sid) = key_guard.value();
+                if !source.contains(sid, target_series) {
+                    continue;
+                }
+                buckets
+                    .entry(granularity.round_down(timestamp))
+                    .or_insert_with(Aggregate::empty)
+                    .merge(&agg_guard.value());
+            }
+        }
+
+        let target_table = match granularity {
+            Granularity::Raw => unreachable!(),
+            Granularity::Minute => self.minute.table_mut()?,
+            Granularity::Hour => self.hour.table_mut()?,
+            Granularity::Day => self.day.table_mut()?,
+        };
+
+        let count = buckets.len();
+        for (bucket_ts, aggregate) in buckets {
+            target_table.insert((bucket_ts, target_series), &aggregate)?;
+        }
+
+        Ok(count)
+    }
+
     /// Internal helper: Downsamples raw data to a target granularity.
-    fn downsample_range(
+    pub(crate) fn downsample_range(
         &mut self,
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
         target: Granularity,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         if target == Granularity::Raw {
-            return Err(StorageError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Cannot downsample to Raw granularity",
-            )));
+            return Err(TimeSeriesError::InvalidArgument(
+                "cannot downsample to Raw granularity",
+            ));
         }
+        check_range(start_ms, end_ms)?;
 
         // Group raw data points by target granularity windows
         let mut buckets: HashMap<u64, Aggregate> = HashMap::new();
@@ -121,15 +346,15 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
             buckets
                 .entry(bucket_ts)
                 .or_insert_with(Aggregate::empty)
-                .accumulate(value);
+                .accumulate_point(timestamp, value.to_f64());
         }
 
         // Write aggregates to the target table
         let target_table = match target {
             Granularity::Raw => unreachable!(),
-            Granularity::Minute => &mut self.minute,
-            Granularity::Hour => &mut self.hour,
-            Granularity::Day => &mut self.day,
+            Granularity::Minute => self.minute.table_mut()?,
+            Granularity::Hour => self.hour.table_mut()?,
+            Granularity::Day => self.day.table_mut()?,
         };
 
         let count = buckets.len();
@@ -141,43 +366,41 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
     }
 
     /// Internal helper: Downsamples from one aggregate granularity to a coarser one.
-    fn downsample_aggregates(
+    pub(crate) fn downsample_aggregates(
         &mut self,
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
         source: Granularity,
         target: Granularity,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         if source == Granularity::Raw {
-            return Err(StorageError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Use downsample_to_minute for raw data",
-            )));
+            return Err(TimeSeriesError::InvalidArgument(
+                "use downsample_to_minute for raw data",
+            ));
         }
 
         if target == Granularity::Raw {
-            return Err(StorageError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Cannot downsample to Raw granularity",
-            )));
+            return Err(TimeSeriesError::InvalidArgument(
+                "cannot downsample to Raw granularity",
+            ));
         }
 
         if target.duration_ms() <= source.duration_ms() {
-            return Err(StorageError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Target granularity must be coarser than source",
-            )));
+            return Err(TimeSeriesError::InvalidArgument(
+                "target granularity must be coarser than source",
+            ));
         }
+        check_range(start_ms, end_ms)?;
 
         // Group source aggregates by target granularity windows
         let mut buckets: HashMap<u64, Aggregate> = HashMap::new();
 
         let source_table = match source {
             Granularity::Raw => unreachable!(),
-            Granularity::Minute => &self.minute,
-            Granularity::Hour => &self.hour,
-            Granularity::Day => &self.day,
+            Granularity::Minute => self.minute.table()?,
+            Granularity::Hour => self.hour.table()?,
+            Granularity::Day => self.day.table()?,
         };
 
         let start_key = (start_ms, series_id);
@@ -204,9 +427,9 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         // Write aggregates to the target table
         let target_table = match target {
             Granularity::Raw => unreachable!(),
-            Granularity::Minute => &mut self.minute,
-            Granularity::Hour => &mut self.hour,
-            Granularity::Day => &mut self.day,
+            Granularity::Minute => self.minute.table_mut()?,
+            Granularity::Hour => self.hour.table_mut()?,
+            Granularity::Day => self.day.table_mut()?,
         };
 
         let count = buckets.len();
@@ -223,6 +446,8 @@ mod tests {
     use super::*;
     use crate::encoding::AbsoluteEncoding;
     use manifold::column_family::ColumnFamilyDatabase;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
     use tempfile::tempdir;
 
     #[test]
@@ -305,6 +530,7 @@ mod tests {
 
             // Hour 1: 3 minute aggregates
             ts.minute_table_mut()
+                .unwrap()
                 .insert(
                     (60_000, "server1"),
                     &Aggregate {
@@ -312,12 +538,17 @@ mod tests {
                         max: 20.0,
                         sum: 100.0,
                         count: 10,
+                        first: 12.0,
                         last: 15.0,
+                        sum_sq: 1_050.0,
+                        first_ts: 60_000,
+                        last_ts: 119_000,
                     },
                 )
                 .unwrap();
 
             ts.minute_table_mut()
+                .unwrap()
                 .insert(
                     (120_000, "server1"),
                     &Aggregate {
@@ -325,7 +556,11 @@ mod tests {
                         max: 25.0,
                         sum: 150.0,
                         count: 15,
+                        first: 18.0,
                         last: 20.0,
+                        sum_sq: 1_550.0,
+                        first_ts: 120_000,
+                        last_ts: 179_000,
                     },
                 )
                 .unwrap();
@@ -359,11 +594,294 @@ mod tests {
                 .get_aggregate(Granularity::Hour, "server1", 120_000)
                 .unwrap()
                 .unwrap();
-            assert!((agg.min - 10.0).abs() < f32::EPSILON); // min of mins
-            assert!((agg.max - 25.0).abs() < f32::EPSILON); // max of maxes
-            assert!((agg.sum - 250.0).abs() < f32::EPSILON); // sum of sums
+            assert!((agg.min - 10.0).abs() < f64::EPSILON); // min of mins
+            assert!((agg.max - 25.0).abs() < f64::EPSILON); // max of maxes
+            assert!((agg.sum - 250.0).abs() < f64::EPSILON); // sum of sums
             assert_eq!(agg.count, 25); // sum of counts
-            assert!((agg.last - 20.0).abs() < f32::EPSILON); // last of lasts
+            assert!((agg.last - 20.0).abs() < f64::EPSILON); // last of lasts
+            assert!((agg.first - 12.0).abs() < f64::EPSILON); // first of firsts
+            assert!((agg.sum_sq - 2_600.0).abs() < f64::EPSILON); // sum of sums of squares
+            assert_eq!((agg.first_ts, agg.last_ts), (60_000, 179_000));
         }
     }
+
+    #[test]
+    fn test_downsample_incremental() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Three minutes of data for two series, long since complete
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for minute in 1..=3u16 {
+                let timestamp = u64::from(minute) * 60_000;
+                ts.write("server1", timestamp, f32::from(minute)).unwrap();
+                ts.write("server2", timestamp, f32::from(minute)).unwrap();
+            }
+
+            // Hours wait for their minutes
+            assert_eq!(
+                ts.downsample_incremental("server1", Granularity::Hour)
+                    .unwrap(),
+                0
+            );
+            assert_eq!(
+                ts.downsample_incremental("server1", Granularity::Minute)
+                    .unwrap(),
+                3
+            );
+            assert_eq!(
+                ts.downsample_incremental("server1", Granularity::Hour)
+                    .unwrap(),
+                1
+            );
+            assert!(matches!(
+                ts.downsample_incremental("server1", Granularity::Raw),
+                Err(TimeSeriesError::InvalidArgument(_))
+            ));
+
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        // The marks survive the transaction, so a second pass is a no-op
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for granularity in [Granularity::Minute, Granularity::Hour] {
+                assert_eq!(
+                    ts.downsample_incremental("server1", granularity).unwrap(),
+                    0
+                );
+            }
+            assert!(
+                ts.watermark("server2", Granularity::Minute)
+                    .unwrap()
+                    .is_none()
+            );
+
+            // A late point is skipped until its window is invalidated
+            ts.write("server1", 90_000, 10.0).unwrap();
+            assert_eq!(
+                ts.downsample_incremental("server1", Granularity::Minute)
+                    .unwrap(),
+                0
+            );
+            ts.invalidate_after("server1", Granularity::Minute, 90_000)
+                .unwrap();
+            assert_eq!(
+                ts.watermark("server1", Granularity::Minute).unwrap(),
+                Some(60_000)
+            );
+            assert_eq!(ts.watermark("server1", Granularity::Hour).unwrap(), Some(0));
+
+            assert_eq!(
+                ts.downsample_incremental("server1", Granularity::Minute)
+                    .unwrap(),
+                3
+            );
+            assert_eq!(
+                ts.downsample_incremental("server1", Granularity::Hour)
+                    .unwrap(),
+                1
+            );
+
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        let minute = ts_read
+            .get_aggregate(Granularity::Minute, "server1", 60_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(minute.count, 2);
+        assert!((minute.sum - 11.0).abs() < f64::EPSILON);
+        let hour = ts_read
+            .get_aggregate(Granularity::Hour, "server1", 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hour.count, 4);
+        assert!((hour.sum - 16.0).abs() < f64::EPSILON);
+        assert!((hour.max - 10.0).abs() < f64::EPSILON);
+        assert!(
+            ts_read
+                .get_aggregate(Granularity::Minute, "server2", 60_000)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_downsampled_stats_match_raw() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Irregular points over three days, starting mid-hour
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut points = Vec::new();
+        let mut timestamp = 1_800_000;
+        while timestamp < 3 * 86_400_000 {
+            points.push((timestamp, rng.random_range(-50.0f32..150.0)));
+            timestamp += rng.random_range(1_000..90_000);
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for &(timestamp, value) in &points {
+                ts.write("server1", timestamp, value).unwrap();
+                ts.write("server2", timestamp, value * 2.0).unwrap();
+            }
+
+            // Downsample minutes in two passes split mid-hour, so that hour is assembled from
+            // minute aggregates written by both passes
+            let split = 43_260_000;
+            ts.downsample_to_minute("server1", 0, split).unwrap();
+            ts.downsample_to_minute("server1", split, u64::MAX).unwrap();
+            ts.downsample_minute_to_hour("server1", 0, u64::MAX)
+                .unwrap();
+            ts.downsample_hour_to_day("server1", 0, u64::MAX).unwrap();
+
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+
+        for granularity in [Granularity::Minute, Granularity::Hour, Granularity::Day] {
+            let mut windows = 0;
+            for entry in ts_read
+                .range_aggregates(granularity, "server1", 0, u64::MAX)
+                .unwrap()
+            {
+                let (window, agg) = entry.unwrap();
+                let end = window + granularity.duration_ms();
+                let raw: Vec<(u64, f32)> = points
+                    .iter()
+                    .copied()
+                    .filter(|(timestamp, _)| (window..end).contains(timestamp))
+                    .collect();
+
+                let count = f64::from(u32::try_from(raw.len()).unwrap());
+                let mean = raw.iter().map(|(_, v)| f64::from(*v)).sum::<f64>() / count;
+                let mean_sq = raw.iter().map(|(_, v)| f64::from(*v).powi(2)).sum::<f64>() / count;
+                let variance = raw
+                    .iter()
+                    .map(|(_, v)| (f64::from(*v) - mean).powi(2))
+                    .sum::<f64>()
+                    / count;
+
+                assert_eq!(agg.count, raw.len() as u64);
+                let (first_ts, first) = raw[0];
+                let (last_ts, last) = raw[raw.len() - 1];
+                assert_eq!((agg.first_ts, agg.first), (first_ts, f64::from(first)));
+                assert_eq!((agg.last_ts, agg.last), (last_ts, f64::from(last)));
+                // Deriving the variance from the sums limits precision relative to the mean
+                // square, not the variance
+                assert!(
+                    (agg.variance() - variance).abs() <= 1e-5 * mean_sq,
+                    "{granularity:?} window {window}: {} vs {}",
+                    agg.variance(),
+                    variance
+                );
+                assert!((agg.stddev() - agg.variance().sqrt()).abs() < f64::EPSILON);
+                windows += 1;
+            }
+            assert!(windows > 0);
+        }
+    }
+
+    #[test]
+    fn test_rollup_merges_series_and_reruns_idempotently() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        // Three hosts over two minutes, and a host of another service
+        ts.write("api.host1", 60_000, 1.0).unwrap();
+        ts.write("api.host1", 61_000, 5.0).unwrap();
+        ts.write("api.host2", 62_000, -2.0).unwrap();
+        ts.write("api.host3", 63_000, 8.0).unwrap();
+        ts.write("api.host2", 120_000, 4.0).unwrap();
+        ts.write("api.host3", 121_000, 6.0).unwrap();
+        ts.write("db.host1", 60_000, 100.0).unwrap();
+
+        let hosts = ["api.host1", "api.host2", "api.host3"];
+        for _ in 0..2 {
+            let count = ts
+                .rollup(
+                    RollupSource::Series(&hosts),
+                    "api",
+                    Granularity::Minute,
+                    0,
+                    u64::MAX,
+                )
+                .unwrap();
+            assert_eq!(count, 2);
+        }
+        // Hour rollups read the minute aggregates of the hosts. The prefix matches the same
+        // hosts, and skips the minute aggregates of the target itself
+        for host in hosts {
+            ts.downsample_to_minute(host, 0, u64::MAX).unwrap();
+        }
+        for _ in 0..2 {
+            let count = ts
+                .rollup(
+                    RollupSource::Prefix("api"),
+                    "api",
+                    Granularity::Hour,
+                    0,
+                    u64::MAX,
+                )
+                .unwrap();
+            assert_eq!(count, 1);
+        }
+        drop(ts);
+        write_txn.commit().unwrap();
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        let minutes: Vec<(u64, Aggregate)> = ts_read
+            .range_aggregates(Granularity::Minute, "api", 0, u64::MAX)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(minutes.len(), 2);
+        let (window, first) = minutes[0];
+        assert_eq!(window, 60_000);
+        assert_eq!(
+            (first.min, first.max, first.sum, first.count),
+            (-2.0, 8.0, 12.0, 4)
+        );
+        let (window, second) = minutes[1];
+        assert_eq!(window, 120_000);
+        assert_eq!(
+            (second.min, second.max, second.sum, second.count),
+            (4.0, 6.0, 10.0, 2)
+        );
+
+        let hour = ts_read
+            .get_aggregate(Granularity::Hour, "api", 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (hour.min, hour.max, hour.sum, hour.count),
+            (-2.0, 8.0, 22.0, 6)
+        );
+        assert_eq!((hour.first_ts, hour.last_ts), (60_000, 121_000));
+    }
 }
@@ -8,6 +8,17 @@ use std::io;
 pub enum EncodingError {
     /// Invalid encoded data.
     InvalidData(String),
+    /// A point of a series was written with an earlier timestamp than one already stored or
+    /// written, with an encoding that requires each series in timestamp order. Nothing was
+    /// written.
+    NonMonotonicTimestamp {
+        /// Series the point belongs to.
+        series: String,
+        /// Latest timestamp of the series before the point.
+        last: u64,
+        /// Timestamp of the rejected point.
+        attempted: u64,
+    },
     /// IO error during encoding/decoding.
     Io(io::Error),
 }
@@ -16,6 +27,14 @@ impl fmt::Display for EncodingError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::InvalidData(msg) => write!(f, "Invalid encoded data: {msg}"),
+            Self::NonMonotonicTimestamp {
+                series,
+                last,
+                attempted,
+            } => write!(
+                f,
+                "Timestamp {attempted} of series '{series}' is before its latest timestamp {last}"
+            ),
             Self::Io(err) => write!(f, "IO error: {err}"),
         }
     }
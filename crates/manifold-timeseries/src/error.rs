@@ -0,0 +1,180 @@
+//! Error type for time series tables.
+
+use crate::encoding::EncodingError;
+use manifold::{CommitError, StorageError, TableError, TransactionError};
+use std::fmt;
+
+/// Error returned by time series table operations.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TimeSeriesError {
+    /// Some points have timestamps outside the table's valid range. Nothing was written.
+    OutOfRange {
+        /// Index in the written slice and timestamp of each rejected point.
+        points: Vec<(usize, u64)>,
+        /// Smallest accepted timestamp.
+        min_ts: u64,
+        /// Largest accepted timestamp.
+        max_ts: u64,
+    },
+    /// A point was written with the series and timestamp of an existing point, and the table's
+    /// [`DuplicatePolicy`](crate::DuplicatePolicy) is `Error`. Nothing was written.
+    DuplicateTimestamp {
+        /// Series the point belongs to.
+        series: String,
+        /// Timestamp shared by the points.
+        timestamp: u64,
+    },
+    /// A point was written to a series that is not registered, through a table opened with
+    /// [`RegistryPolicy::Strict`](crate::RegistryPolicy::Strict). Nothing was written.
+    UnregisteredSeries {
+        /// Series the point belongs to.
+        series: String,
+    },
+    /// A series id is longer than the table accepts, set with
+    /// [`TimeSeriesOptions::max_series_id_len`](crate::TimeSeriesOptions::max_series_id_len).
+    /// Nothing was written.
+    SeriesIdTooLong {
+        /// Length of the series id in bytes.
+        len: usize,
+        /// Longest accepted series id in bytes.
+        max: usize,
+    },
+    /// The start of a time range is after its end.
+    InvalidRange {
+        /// Start of the range (inclusive).
+        start: u64,
+        /// End of the range (exclusive).
+        end: u64,
+    },
+    /// An argument is outside the values the operation accepts.
+    InvalidArgument(&'static str),
+    /// The time series was opened with another value type than it was created with.
+    ValueTypeMismatch {
+        /// Name of the time series.
+        table: String,
+        /// Value type the time series was created with.
+        stored: String,
+        /// Value type the time series was opened with.
+        requested: String,
+    },
+    /// Error encoding or decoding a timestamp, or a series written out of order with an
+    /// encoding that requires timestamps in order.
+    Encoding(EncodingError),
+    /// Error opening the underlying table.
+    Table(TableError),
+    /// Error beginning a write transaction.
+    Transaction(TransactionError),
+    /// Error committing a write transaction.
+    Commit(CommitError),
+    /// Error reading or writing the underlying storage.
+    Storage(StorageError),
+}
+
+impl fmt::Display for TimeSeriesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange {
+                points,
+                min_ts,
+                max_ts,
+            } => {
+                write!(
+                    f,
+                    "{} point(s) outside the valid timestamp range {min_ts}..={max_ts}:",
+                    points.len()
+                )?;
+                for (index, timestamp) in points.iter().take(10) {
+                    write!(f, " #{index} at {timestamp}")?;
+                }
+                if points.len() > 10 {
+                    write!(f, " and {} more", points.len() - 10)?;
+                }
+                Ok(())
+            }
+            Self::DuplicateTimestamp { series, timestamp } => {
+                write!(
+                    f,
+                    "Series '{series}' already has a point at timestamp {timestamp}"
+                )
+            }
+            Self::UnregisteredSeries { series } => {
+                write!(f, "Series '{series}' is not registered")
+            }
+            Self::SeriesIdTooLong { len, max } => {
+                write!(
+                    f,
+                    "Series id of {len} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+            Self::InvalidRange { start, end } => {
+                write!(f, "Invalid time range: start {start} is after end {end}")
+            }
+            Self::InvalidArgument(msg) => write!(f, "Invalid argument: {msg}"),
+            Self::ValueTypeMismatch {
+                table,
+                stored,
+                requested,
+            } => {
+                write!(
+                    f,
+                    "Time series '{table}' stores {stored} values, not {requested}"
+                )
+            }
+            Self::Encoding(err) => write!(f, "Encoding error: {err}"),
+            Self::Table(err) => write!(f, "Table error: {err}"),
+            Self::Transaction(err) => write!(f, "Transaction error: {err}"),
+            Self::Commit(err) => write!(f, "Commit error: {err}"),
+            Self::Storage(err) => write!(f, "Storage error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TimeSeriesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::OutOfRange { .. }
+            | Self::DuplicateTimestamp { .. }
+            | Self::UnregisteredSeries { .. }
+            | Self::SeriesIdTooLong { .. }
+            | Self::InvalidRange { .. }
+            | Self::InvalidArgument(_)
+            | Self::ValueTypeMismatch { .. } => None,
+            Self::Encoding(err) => Some(err),
+            Self::Table(err) => Some(err),
+            Self::Transaction(err) => Some(err),
+            Self::Commit(err) => Some(err),
+            Self::Storage(err) => Some(err),
+        }
+    }
+}
+
+impl From<EncodingError> for TimeSeriesError {
+    fn from(err: EncodingError) -> Self {
+        Self::Encoding(err)
+    }
+}
+
+impl From<TableError> for TimeSeriesError {
+    fn from(err: TableError) -> Self {
+        Self::Table(err)
+    }
+}
+
+impl From<TransactionError> for TimeSeriesError {
+    fn from(err: TransactionError) -> Self {
+        Self::Transaction(err)
+    }
+}
+
+impl From<CommitError> for TimeSeriesError {
+    fn from(err: CommitError) -> Self {
+        Self::Commit(err)
+    }
+}
+
+impl From<StorageError> for TimeSeriesError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
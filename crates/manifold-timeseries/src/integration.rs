@@ -2,8 +2,9 @@
 
 use crate::aggregate::{Aggregate, Granularity};
 use crate::encoding::TimestampEncoding;
-use crate::timeseries::TimeSeriesTableRead;
-use manifold::StorageError;
+use crate::error::TimeSeriesError;
+use crate::timeseries::{AggregateRangeIter, RangeIter, TimeSeriesTableRead, check_range};
+use std::iter::Peekable;
 
 /// Trait for consuming time series data from external analytics libraries.
 ///
@@ -26,10 +27,10 @@ use manifold::StorageError;
 /// ```
 pub trait TimeSeriesSource<'a> {
     /// Iterator over raw time series data points.
-    type RawIter: Iterator<Item = Result<(u64, f32), StorageError>>;
+    type RawIter: Iterator<Item = Result<(u64, f32), TimeSeriesError>>;
 
     /// Iterator over aggregate data points.
-    type AggregateIter: Iterator<Item = Result<(u64, Aggregate), StorageError>>;
+    type AggregateIter: Iterator<Item = Result<(u64, Aggregate), TimeSeriesError>>;
 
     /// Iterates over raw data points for a series within a time range.
     ///
@@ -43,9 +44,15 @@ pub trait TimeSeriesSource<'a> {
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<Self::RawIter, StorageError>;
+    ) -> Result<Self::RawIter, TimeSeriesError>;
 
-    /// Iterates over aggregate data points for a series within a time range.
+    /// Iterates over the aggregates of a series within a time range, keyed by the start of
+    /// their window.
+    ///
+    /// The aggregates are read from the table of `granularity` as written by downsampling, so
+    /// a granularity that has not been downsampled in the range yields no aggregates. Fails
+    /// with [`TimeSeriesError::InvalidArgument`] for [`Granularity::Raw`], which has no
+    /// aggregates.
     ///
     /// # Arguments
     ///
@@ -59,88 +66,28 @@ pub trait TimeSeriesSource<'a> {
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<Self::AggregateIter, StorageError>;
+    ) -> Result<Self::AggregateIter, TimeSeriesError>;
 
     /// Returns the number of raw data points in a time range.
-    fn count_raw(&self, series_id: &str, start_ms: u64, end_ms: u64)
-        -> Result<usize, StorageError>;
-}
-
-/// Iterator over raw time series data points.
-pub struct RawPointsIter<'a, E: TimestampEncoding> {
-    #[allow(dead_code)]
-    table_read: &'a TimeSeriesTableRead<E>,
-    #[allow(dead_code)]
-    series_id: String,
-    #[allow(dead_code)]
-    start_ms: u64,
-    end_ms: u64,
-    current_pos: u64,
-}
-
-impl<E: TimestampEncoding> Iterator for RawPointsIter<'_, E> {
-    type Item = Result<(u64, f32), StorageError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_pos >= self.end_ms {
-            return None;
-        }
-
-        // Note: This is a placeholder iterator implementation.
-        // In production, you'd want to wrap Manifold's actual range iterator.
-        // For now, return None to satisfy the trait but mark fields as allowed dead_code
-        // since they'll be used when we implement proper iteration.
-        None
-    }
-}
-
-/// Iterator over aggregate time series data points.
-pub struct AggregatePointsIter<'a, E: TimestampEncoding> {
-    #[allow(dead_code)]
-    table_read: &'a TimeSeriesTableRead<E>,
-    #[allow(dead_code)]
-    granularity: Granularity,
-    #[allow(dead_code)]
-    series_id: String,
-    #[allow(dead_code)]
-    start_ms: u64,
-    end_ms: u64,
-    current_pos: u64,
-}
-
-impl<E: TimestampEncoding> Iterator for AggregatePointsIter<'_, E> {
-    type Item = Result<(u64, Aggregate), StorageError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_pos >= self.end_ms {
-            return None;
-        }
-
-        // Note: This is a placeholder iterator implementation.
-        // In production, you'd want to wrap Manifold's actual range iterator.
-        // For now, return None to satisfy the trait but mark fields as allowed dead_code
-        // since they'll be used when we implement proper iteration.
-        None
-    }
+    fn count_raw(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<usize, TimeSeriesError>;
 }
 
 impl<'a, E: TimestampEncoding + 'a> TimeSeriesSource<'a> for TimeSeriesTableRead<E> {
-    type RawIter = RawPointsIter<'a, E>;
-    type AggregateIter = AggregatePointsIter<'a, E>;
+    type RawIter = RangeIter<'a>;
+    type AggregateIter = AggregateRangeIter<'a>;
 
     fn iter_raw(
         &'a self,
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<Self::RawIter, StorageError> {
-        Ok(RawPointsIter {
-            table_read: self,
-            series_id: series_id.to_string(),
-            start_ms,
-            end_ms,
-            current_pos: start_ms,
-        })
+    ) -> Result<Self::RawIter, TimeSeriesError> {
+        self.range(series_id, start_ms, end_ms)
     }
 
     fn iter_aggregates(
@@ -149,15 +96,8 @@ impl<'a, E: TimestampEncoding + 'a> TimeSeriesSource<'a> for TimeSeriesTableRead
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<Self::AggregateIter, StorageError> {
-        Ok(AggregatePointsIter {
-            table_read: self,
-            granularity,
-            series_id: series_id.to_string(),
-            start_ms,
-            end_ms,
-            current_pos: start_ms,
-        })
+    ) -> Result<Self::AggregateIter, TimeSeriesError> {
+        self.range_aggregates(granularity, series_id, start_ms, end_ms)
     }
 
     fn count_raw(
@@ -165,7 +105,8 @@ impl<'a, E: TimestampEncoding + 'a> TimeSeriesSource<'a> for TimeSeriesTableRead
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
+        check_range(start_ms, end_ms)?;
         let start_key = (start_ms, series_id);
         let end_key = (end_ms, series_id);
 
@@ -178,10 +119,214 @@ impl<'a, E: TimestampEncoding + 'a> TimeSeriesSource<'a> for TimeSeriesTableRead
     }
 }
 
+/// A fixed-size window of raw data points, as yielded by [`ChunkedTimeSeriesSource`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Start of the window (inclusive), a multiple of the window size.
+    pub start: u64,
+    /// End of the window (exclusive).
+    pub end: u64,
+    /// Timestamps of the points in the window, in ascending order.
+    pub timestamps: Vec<u64>,
+    /// Values of the points in the window, one per timestamp.
+    pub values: Vec<f32>,
+    /// Whether any expected interval in the window had no data point.
+    ///
+    /// This is set even when the gaps were filled with [`GapFill::Value`].
+    pub has_gaps: bool,
+}
+
+/// How gaps in a chunk are handled.
+///
+/// The window is divided into consecutive slots of the expected interval, starting at the
+/// window start. A slot that contains no data point is a gap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GapFill {
+    /// Leave gaps empty and only set [`Chunk::has_gaps`].
+    Flag,
+    /// Insert a point with this value at the start of each empty slot.
+    ///
+    /// Windows without any data are never filled, so they stay distinguishable from windows
+    /// with sparse data.
+    Value(f32),
+}
+
+/// Trait for consuming time series data as fixed-size, wall-clock aligned chunks.
+///
+/// Unlike [`TimeSeriesSource`], which hands out arbitrary ranges, this yields one
+/// [`Chunk`] per window, including empty windows, so consumers such as forecasting
+/// libraries can work with contiguous blocks and explicit gap information.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use manifold_timeseries::integration::{ChunkedTimeSeriesSource, GapFill};
+///
+/// fn hourly_blocks<S: ChunkedTimeSeriesSource>(source: &S, series_id: &str, day_ms: u64) {
+///     // One chunk per hour of the day, expecting a point every minute
+///     let chunks = source
+///         .chunks(series_id, day_ms, day_ms + 86_400_000, 3_600_000, 60_000, GapFill::Flag)
+///         .unwrap();
+///     for chunk in chunks {
+///         let chunk = chunk.unwrap();
+///         if !chunk.has_gaps {
+///             // Process a complete hour...
+///         }
+///     }
+/// }
+/// ```
+pub trait ChunkedTimeSeriesSource {
+    /// Iterator over chunks.
+    type ChunkIter<'a>: Iterator<Item = Result<Chunk, TimeSeriesError>>
+    where
+        Self: 'a;
+
+    /// Iterates over the raw data points of a series in windows of `window_ms`.
+    ///
+    /// Windows are aligned to multiples of `window_ms`, and every window that overlaps
+    /// `start_ms..end_ms` is yielded in full. A point exactly on a window boundary belongs to
+    /// the window that starts there. A window without any points is yielded with empty
+    /// vectors and `has_gaps` set.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive)
+    /// * `window_ms` - Size of each window, must be non-zero
+    /// * `expected_interval_ms` - Expected spacing of points, must be non-zero
+    /// * `gap_fill` - How gaps are handled
+    fn chunks(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        window_ms: u64,
+        expected_interval_ms: u64,
+        gap_fill: GapFill,
+    ) -> Result<Self::ChunkIter<'_>, TimeSeriesError>;
+}
+
+/// Iterator over chunks of raw time series data points.
+pub struct ChunkIter<'a> {
+    points: Peekable<RangeIter<'a>>,
+    next_start: u64,
+    end: u64,
+    window_ms: u64,
+    expected_interval_ms: u64,
+    gap_fill: GapFill,
+}
+
+impl ChunkIter<'_> {
+    fn build_chunk(&mut self, start: u64, end: u64) -> Result<Chunk, TimeSeriesError> {
+        let mut points = vec![];
+        while let Some(point) = self.points.next_if(|point| match point {
+            Ok((timestamp, _)) => *timestamp < end,
+            Err(_) => true,
+        }) {
+            points.push(point?);
+        }
+
+        let mut chunk = Chunk {
+            start,
+            end,
+            timestamps: Vec::with_capacity(points.len()),
+            values: Vec::with_capacity(points.len()),
+            has_gaps: points.is_empty(),
+        };
+        // Windows without data are left empty, even when filling
+        if points.is_empty() {
+            return Ok(chunk);
+        }
+
+        let mut points = points.into_iter().peekable();
+        let mut slot = start;
+        while slot < end {
+            let slot_end = slot.saturating_add(self.expected_interval_ms).min(end);
+            let mut found = false;
+            while let Some((timestamp, value)) = points.next_if(|(t, _)| *t < slot_end) {
+                chunk.timestamps.push(timestamp);
+                chunk.values.push(value);
+                found = true;
+            }
+            if !found {
+                chunk.has_gaps = true;
+                if let GapFill::Value(value) = self.gap_fill {
+                    chunk.timestamps.push(slot);
+                    chunk.values.push(value);
+                }
+            }
+            slot = slot_end;
+        }
+
+        Ok(chunk)
+    }
+}
+
+impl Iterator for ChunkIter<'_> {
+    type Item = Result<Chunk, TimeSeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_start >= self.end {
+            return None;
+        }
+
+        let start = self.next_start;
+        let end = start.saturating_add(self.window_ms).min(self.end);
+        self.next_start = end;
+        let chunk = self.build_chunk(start, end);
+        // Stop after a failed read
+        if chunk.is_err() {
+            self.next_start = self.end;
+        }
+        Some(chunk)
+    }
+}
+
+impl<E: TimestampEncoding> ChunkedTimeSeriesSource for TimeSeriesTableRead<E> {
+    type ChunkIter<'a>
+        = ChunkIter<'a>
+    where
+        Self: 'a;
+
+    fn chunks(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        window_ms: u64,
+        expected_interval_ms: u64,
+        gap_fill: GapFill,
+    ) -> Result<Self::ChunkIter<'_>, TimeSeriesError> {
+        if window_ms == 0 || expected_interval_ms == 0 {
+            return Err(TimeSeriesError::InvalidArgument(
+                "window size and expected interval must be non-zero",
+            ));
+        }
+
+        let start = start_ms / window_ms * window_ms;
+        let end = if start_ms >= end_ms {
+            start
+        } else {
+            end_ms.div_ceil(window_ms).saturating_mul(window_ms)
+        };
+
+        Ok(ChunkIter {
+            points: self.range(series_id, start, end)?.peekable(),
+            next_start: start,
+            end,
+            window_ms,
+            expected_interval_ms,
+            gap_fill,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::encoding::AbsoluteEncoding;
+    use manifold::TableDefinition;
     use manifold::column_family::ColumnFamilyDatabase;
     use tempfile::tempdir;
 
@@ -216,4 +361,230 @@ mod tests {
             assert_eq!(count, 3);
         }
     }
+
+    #[test]
+    fn test_timeseries_source_matches_tables() {
+        const DAY: u64 = 86_400_000;
+
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // A point every two hours for ten days, for two series
+        let mut points = vec![];
+        for i in 0..120u16 {
+            let timestamp = u64::from(i) * 7_200_000;
+            points.push(("server1", timestamp, f32::from(i)));
+            points.push(("server2", timestamp, -1.0));
+        }
+        write_points(&cf, &points);
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts =
+                crate::timeseries::TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu")
+                    .unwrap();
+            ts.downsample_to_minute("server1", 0, 10 * DAY).unwrap();
+            ts.downsample_minute_to_hour("server1", 0, 10 * DAY)
+                .unwrap();
+            ts.downsample_hour_to_day("server1", 0, 10 * DAY).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+
+        let raw: Vec<(u64, f32)> = ts_read
+            .iter_raw("server1", DAY, 3 * DAY)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let mut expected = vec![];
+        for item in ts_read.raw_table().range((DAY, "")..(3 * DAY, "")).unwrap() {
+            let (key, value) = item.unwrap();
+            let (timestamp, series_id) = key.value();
+            if series_id == "server1" {
+                expected.push((timestamp, value.value()));
+            }
+        }
+        assert_eq!(raw.len(), 24);
+        assert_eq!(raw, expected);
+
+        let day_def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new("cpu_day");
+        let day_table = read_txn.open_table(day_def).unwrap();
+        for (start, end) in [(0, 10 * DAY), (2 * DAY, 5 * DAY), (DAY + 1, DAY + 2)] {
+            let days: Vec<(u64, Aggregate)> = ts_read
+                .iter_aggregates(Granularity::Day, "server1", start, end)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            let mut expected = vec![];
+            for item in day_table.range((start, "")..(end, "")).unwrap() {
+                let (key, value) = item.unwrap();
+                let (timestamp, series_id) = key.value();
+                if series_id == "server1" {
+                    expected.push((timestamp, value.value()));
+                }
+            }
+            assert_eq!(days, expected);
+        }
+        let days = ts_read
+            .iter_aggregates(Granularity::Day, "server1", 0, 10 * DAY)
+            .unwrap()
+            .count();
+        assert_eq!(days, 10);
+
+        // A series or granularity without aggregates yields nothing
+        assert_eq!(
+            ts_read
+                .iter_aggregates(Granularity::Day, "server2", 0, 10 * DAY)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert!(matches!(
+            ts_read.iter_aggregates(Granularity::Raw, "server1", 0, DAY),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+    }
+
+    fn write_points(cf: &manifold::column_family::ColumnFamily, points: &[(&str, u64, f32)]) {
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts =
+            crate::timeseries::TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu")
+                .unwrap();
+        for &(series_id, timestamp, value) in points {
+            ts.write(series_id, timestamp, value).unwrap();
+        }
+        drop(ts);
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_chunks_with_gaps() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Expected interval 10, windows of 50:
+        // [100, 150) is complete, [150, 200) misses 170, [200, 250) is empty,
+        // [250, 300) only has a point at 290
+        let mut points = vec![];
+        for t in [100u16, 110, 120, 130, 140, 150, 160, 180, 190, 290] {
+            points.push(("server1", u64::from(t), f32::from(t)));
+        }
+        // Another series interleaved in the same table is ignored
+        points.push(("server0", 170, -1.0));
+        points.push(("server2", 200, -1.0));
+        write_points(&cf, &points);
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+
+        let chunks: Vec<Chunk> = ts_read
+            .chunks("server1", 120, 260, 50, 10, GapFill::Flag)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk {
+                    start: 100,
+                    end: 150,
+                    timestamps: vec![100, 110, 120, 130, 140],
+                    values: vec![100.0, 110.0, 120.0, 130.0, 140.0],
+                    has_gaps: false,
+                },
+                Chunk {
+                    start: 150,
+                    end: 200,
+                    timestamps: vec![150, 160, 180, 190],
+                    values: vec![150.0, 160.0, 180.0, 190.0],
+                    has_gaps: true,
+                },
+                Chunk {
+                    start: 200,
+                    end: 250,
+                    timestamps: vec![],
+                    values: vec![],
+                    has_gaps: true,
+                },
+                Chunk {
+                    start: 250,
+                    end: 300,
+                    timestamps: vec![290],
+                    values: vec![290.0],
+                    has_gaps: true,
+                },
+            ]
+        );
+
+        let filled: Vec<Chunk> = ts_read
+            .chunks("server1", 150, 300, 50, 10, GapFill::Value(f32::NAN))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(filled.len(), 3);
+        assert_eq!(filled[0].timestamps, vec![150, 160, 170, 180, 190]);
+        assert!(filled[0].values[2].is_nan());
+        assert!(filled[0].has_gaps);
+        // Empty windows are not filled
+        assert!(filled[1].timestamps.is_empty() && filled[1].has_gaps);
+        assert_eq!(filled[2].timestamps, vec![250, 260, 270, 280, 290]);
+        assert_eq!(filled[2].values[4..], [290.0]);
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Points exactly on window boundaries belong to the later window
+        write_points(
+            &cf,
+            &[
+                ("server1", 60_000, 1.0),
+                ("server1", 119_999, 2.0),
+                ("server1", 120_000, 3.0),
+            ],
+        );
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+
+        let chunks: Vec<Chunk> = ts_read
+            .chunks("server1", 0, 180_000, 60_000, 60_000, GapFill::Flag)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].timestamps, Vec::<u64>::new());
+        assert!(chunks[0].has_gaps);
+        assert_eq!(chunks[1].timestamps, vec![60_000, 119_999]);
+        assert!(!chunks[1].has_gaps);
+        assert_eq!(chunks[2].timestamps, vec![120_000]);
+        assert_eq!(chunks[2].values, vec![3.0]);
+        assert!(!chunks[2].has_gaps);
+
+        // An empty range yields no chunks, and zero sizes are rejected
+        assert_eq!(
+            ts_read
+                .chunks("server1", 60_000, 60_000, 60_000, 1000, GapFill::Flag)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert!(
+            ts_read
+                .chunks("server1", 0, 180_000, 0, 1000, GapFill::Flag)
+                .is_err()
+        );
+        assert!(
+            ts_read
+                .chunks("server1", 0, 180_000, 60_000, 0, GapFill::Flag)
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,357 @@
+//! Labels on series and selection of series by label.
+//!
+//! [`TimeSeriesTable::set_labels`] attaches `key=value` labels to a series, so queries can
+//! select series by what they measure instead of by identifier, as in Prometheus selectors
+//! such as `{host="server1", metric="cpu"}`.
+//!
+//! # Storage
+//!
+//! The labels of each series are stored in `{name}_labels`, keyed by `(series_id, key)`, and
+//! indexed in `{name}_label_index`, keyed by `(key, value, series_id)`. A matcher reads the
+//! index entries of its label only, so selecting series does not scan the series that do not
+//! match.
+
+use crate::columnar::ColumnarIter;
+use crate::encoding::TimestampEncoding;
+use crate::error::TimeSeriesError;
+use crate::timeseries::{TimeSeriesTable, TimeSeriesTableRead};
+use crate::value::FixedWidthValue;
+use manifold::ReadableTable;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A condition on one label of a series, for
+/// [`TimeSeriesTableRead::select_series`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelMatcher {
+    /// Matches series whose label `key` is exactly `value`.
+    Equal {
+        /// Label key.
+        key: String,
+        /// Label value.
+        value: String,
+    },
+}
+
+impl LabelMatcher {
+    /// Creates a matcher for series whose label `key` is exactly `value`.
+    pub fn equal(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self::Equal {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTable<'_, E, V> {
+    /// Replaces the labels of a series.
+    ///
+    /// Labels the series had before and that are not in `labels` are removed, along with
+    /// their index entries. If `labels` has the same key more than once, the last value wins.
+    /// An empty slice removes every label, like [`remove_labels`](Self::remove_labels).
+    ///
+    /// Labels are independent of the series' data points: a series can be labelled before
+    /// any point is written to it, and keeps its labels when its points are deleted, unless
+    /// the whole series is deleted with [`delete_series`](Self::delete_series).
+    pub fn set_labels(
+        &mut self,
+        series_id: &str,
+        labels: &[(&str, &str)],
+    ) -> Result<(), TimeSeriesError> {
+        self.check_series_ids(std::iter::once(series_id))?;
+        let labels: BTreeMap<&str, &str> = labels.iter().copied().collect();
+        let old = self.labels(series_id)?;
+
+        for (key, value) in &old {
+            if labels.get(key.as_str()) != Some(&value.as_str()) {
+                self.labels.table_mut()?.remove((series_id, key.as_str()))?;
+                self.label_index
+                    .table_mut()?
+                    .remove((key.as_str(), value.as_str(), series_id))?;
+            }
+        }
+        for (key, value) in labels {
+            self.labels.table_mut()?.insert((series_id, key), value)?;
+            self.label_index
+                .table_mut()?
+                .insert((key, value, series_id), ())?;
+        }
+        Ok(())
+    }
+
+    /// Removes every label of a series, returning whether it had any.
+    pub fn remove_labels(&mut self, series_id: &str) -> Result<bool, TimeSeriesError> {
+        let old = self.labels(series_id)?;
+        for (key, value) in &old {
+            self.labels.table_mut()?.remove((series_id, key.as_str()))?;
+            self.label_index
+                .table_mut()?
+                .remove((key.as_str(), value.as_str(), series_id))?;
+        }
+        Ok(!old.is_empty())
+    }
+
+    /// Returns the labels of a series as `(key, value)` pairs, ordered by key.
+    pub fn labels(&self, series_id: &str) -> Result<Vec<(String, String)>, TimeSeriesError> {
+        series_labels(self.labels.table()?, series_id)
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTableRead<E, V> {
+    /// Returns the labels of a series as `(key, value)` pairs, ordered by key.
+    pub fn labels(&self, series_id: &str) -> Result<Vec<(String, String)>, TimeSeriesError> {
+        match &self.labels {
+            Some(table) => series_labels(table, series_id),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the identifiers of the series matching every matcher, in order.
+    ///
+    /// Each matcher reads the index entries of its label, and the series are intersected
+    /// across matchers, stopping early once no series is left.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimeSeriesError::InvalidArgument`] if `matchers` is empty.
+    pub fn select_series(&self, matchers: &[LabelMatcher]) -> Result<Vec<String>, TimeSeriesError> {
+        if matchers.is_empty() {
+            return Err(TimeSeriesError::InvalidArgument(
+                "at least one label matcher is required",
+            ));
+        }
+        let Some(index) = &self.label_index else {
+            return Ok(Vec::new());
+        };
+
+        let mut selected = matching_series(index, &matchers[0])?;
+        for matcher in &matchers[1..] {
+            if selected.is_empty() {
+                break;
+            }
+            let matching = matching_series(index, matcher)?;
+            selected.retain(|series_id| matching.contains(series_id));
+        }
+        Ok(selected.into_iter().collect())
+    }
+
+    /// Returns the raw data points of the series matching every matcher in a time range, as
+    /// columnar chunks.
+    ///
+    /// Combines [`select_series`](Self::select_series) with
+    /// [`to_columnar_chunks`](Self::to_columnar_chunks), to answer a selector query in one
+    /// call. The points of every selected series are read in a single ordered scan.
+    pub fn select_columnar_chunks(
+        &self,
+        matchers: &[LabelMatcher],
+        start_ms: u64,
+        end_ms: u64,
+        batch_rows: usize,
+    ) -> Result<ColumnarIter<'_, V>, TimeSeriesError> {
+        let series = self.select_series(matchers)?;
+        let series_ids: Vec<&str> = series.iter().map(String::as_str).collect();
+        self.to_columnar_chunks(&series_ids, start_ms, end_ms, batch_rows)
+    }
+}
+
+/// Reads the labels of a series from the labels table.
+fn series_labels(
+    table: &impl ReadableTable<(&'static str, &'static str), &'static str>,
+    series_id: &str,
+) -> Result<Vec<(String, String)>, TimeSeriesError> {
+    let mut labels = Vec::new();
+    for entry in table.range((series_id, "")..)? {
+        let (key_guard, value_guard) = entry?;
+        let (sid, key) = key_guard.value();
+        if sid != series_id {
+            break;
+        }
+        labels.push((key.to_string(), value_guard.value().to_string()));
+    }
+    Ok(labels)
+}
+
+/// Reads the series matching one matcher from the label index.
+fn matching_series(
+    index: &impl ReadableTable<(&'static str, &'static str, &'static str), ()>,
+    matcher: &LabelMatcher,
+) -> Result<BTreeSet<String>, TimeSeriesError> {
+    let LabelMatcher::Equal { key, value } = matcher;
+    let mut series = BTreeSet::new();
+    for entry in index.range((key.as_str(), value.as_str(), "")..)? {
+        let (key_guard, _) = entry?;
+        let (k, v, series_id) = key_guard.value();
+        if k != key.as_str() || v != value.as_str() {
+            break;
+        }
+        series.insert(series_id.to_string());
+    }
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use manifold::ReadableTableMetadata;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use std::collections::HashSet;
+    use tempfile::tempdir;
+
+    const HOSTS: usize = 10;
+    const METRICS: [&str; 3] = ["cpu", "memory", "disk"];
+
+    /// Labels of series `i` of the selection test.
+    fn labels_of(i: usize) -> [(&'static str, String); 3] {
+        [
+            ("host", format!("server{}", i % HOSTS)),
+            ("metric", METRICS[i % METRICS.len()].to_string()),
+            (
+                "env",
+                if i % 4 == 0 { "staging" } else { "prod" }.to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_select_series_intersects_matchers() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "ts").unwrap();
+            for i in 0..100 {
+                let series_id = format!("series{i}");
+                let labels = labels_of(i);
+                let labels: Vec<(&str, &str)> =
+                    labels.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                ts.set_labels(&series_id, &labels).unwrap();
+                ts.write(&series_id, 1_000, 1.0).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "ts").unwrap();
+
+        let selectors = [
+            vec![LabelMatcher::equal("host", "server1")],
+            vec![LabelMatcher::equal("metric", "cpu")],
+            vec![
+                LabelMatcher::equal("host", "server1"),
+                LabelMatcher::equal("metric", "cpu"),
+            ],
+            vec![
+                LabelMatcher::equal("env", "prod"),
+                LabelMatcher::equal("metric", "disk"),
+                LabelMatcher::equal("host", "server5"),
+            ],
+            // Keys and values must both match; a value of another key does not
+            vec![LabelMatcher::equal("host", "cpu")],
+            vec![LabelMatcher::equal("region", "eu")],
+            vec![
+                LabelMatcher::equal("host", "server2"),
+                LabelMatcher::equal("host", "server3"),
+            ],
+        ];
+        for matchers in &selectors {
+            let mut expected: Vec<String> = (0..100)
+                .filter(|&i| {
+                    let labels = labels_of(i);
+                    matchers.iter().all(|LabelMatcher::Equal { key, value }| {
+                        labels.iter().any(|(k, v)| *k == key.as_str() && v == value)
+                    })
+                })
+                .map(|i| format!("series{i}"))
+                .collect();
+            expected.sort();
+            assert_eq!(
+                ts.select_series(matchers).unwrap(),
+                expected,
+                "{matchers:?}"
+            );
+        }
+
+        // Points of the selected series, in one scan
+        let matchers = [
+            LabelMatcher::equal("host", "server1"),
+            LabelMatcher::equal("metric", "cpu"),
+        ];
+        let selected: HashSet<String> = ts.select_series(&matchers).unwrap().into_iter().collect();
+        let mut rows = 0;
+        for chunk in ts.select_columnar_chunks(&matchers, 0, 2_000, 2).unwrap() {
+            let chunk = chunk.unwrap();
+            assert!(
+                chunk
+                    .series
+                    .iter()
+                    .all(|series_id| selected.contains(series_id))
+            );
+            rows += chunk.len();
+        }
+        assert_eq!(rows, selected.len());
+
+        assert_eq!(
+            ts.labels("series13").unwrap(),
+            [
+                ("env".to_string(), "prod".to_string()),
+                ("host".to_string(), "server3".to_string()),
+                ("metric".to_string(), "memory".to_string()),
+            ]
+        );
+        assert!(matches!(
+            ts.select_series(&[]),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_label_updates_remove_old_postings() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "ts").unwrap();
+            ts.set_labels("a", &[("host", "server1"), ("metric", "cpu")])
+                .unwrap();
+            ts.set_labels("b", &[("host", "server1"), ("metric", "cpu")])
+                .unwrap();
+            ts.set_labels("c", &[("host", "server2")]).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "ts").unwrap();
+            // Changes one value, drops one label and adds another
+            ts.set_labels("a", &[("host", "server2"), ("dc", "east")])
+                .unwrap();
+            // The last value of a repeated key wins
+            ts.set_labels("b", &[("metric", "memory"), ("metric", "cpu")])
+                .unwrap();
+            assert!(ts.remove_labels("c").unwrap());
+            assert!(!ts.remove_labels("c").unwrap());
+            assert_eq!(ts.label_index.table().unwrap().len().unwrap(), 3);
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "ts").unwrap();
+        let select = |key: &str, value: &str| {
+            ts.select_series(&[LabelMatcher::equal(key, value)])
+                .unwrap()
+        };
+        assert_eq!(select("host", "server1"), Vec::<String>::new());
+        assert_eq!(select("host", "server2"), ["a"]);
+        assert_eq!(select("dc", "east"), ["a"]);
+        assert_eq!(select("metric", "cpu"), ["b"]);
+        assert_eq!(select("metric", "memory"), Vec::<String>::new());
+        assert!(ts.labels("c").unwrap().is_empty());
+    }
+}
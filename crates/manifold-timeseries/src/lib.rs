@@ -9,9 +9,19 @@
 //! ## Features
 //!
 //! - **Dual encoding strategies**: Absolute (default) or delta encoding for timestamps
+//! - **Value types**: `f32` (default), `f64` or `i64` raw values, aggregated in `f64`
 //! - **Multi-granularity tables**: Raw, minute, hour, and day aggregates
-//! - **Manual downsampling**: Compute aggregates (min, max, avg, sum, count)
+//! - **Manual downsampling**: Compute aggregates (min, max, avg, sum, count, stddev, first, last)
 //! - **Retention policies**: Time-based cleanup of old data
+//! - **Background maintenance**: Scheduled downsampling and retention on a dedicated thread
+//! - **Columnar export**: Batches of several series for loading into dataframe libraries
+//! - **Aligned reads**: Evenly spaced points with gap filling, for charts
+//! - **Series labels**: Selection of series by `key=value` labels through an inverted index
+//! - **Chunk compaction**: Old raw points packed into delta-encoded chunks, merged on read
+//! - **Series registry**: Optional rejection or registration of unknown series, with units
+//! - **Counters**: Increase and windowed rates of monotonic counters, with reset detection
+//! - **Rollups**: Aggregates of several series merged into one, such as hosts into a service
+//! - **Paged reads**: Ranges read in fixed-size pages, and as typed arrays from WASM
 //! - **High performance**: Leverages Manifold's WAL group commit and ordered key-value storage
 //!
 //! ## Quick Start
@@ -66,14 +76,38 @@
 )]
 
 pub mod aggregate;
-pub mod encoding;
-pub mod timeseries;
+pub mod alignment;
+pub mod columnar;
+pub mod compaction;
+pub mod counter;
 pub mod downsampling;
-pub mod retention;
+pub mod encoding;
+pub mod error;
 pub mod integration;
+pub mod labels;
+pub mod maintenance;
+pub mod registry;
+pub mod retention;
+pub mod timeseries;
+pub mod value;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+mod wasm_ranges;
 
 pub use aggregate::{Aggregate, Granularity};
+pub use alignment::{AlignedIter, FillPolicy};
+pub use columnar::{ColumnarChunk, ColumnarIter};
+pub use downsampling::RollupSource;
 pub use encoding::{AbsoluteEncoding, DeltaEncoding, EncodingError, TimestampEncoding};
-pub use timeseries::{TimeSeriesTable, TimeSeriesTableRead};
-pub use integration::TimeSeriesSource;
-
+pub use error::TimeSeriesError;
+pub use integration::{Chunk, ChunkedTimeSeriesSource, GapFill, TimeSeriesSource};
+pub use labels::LabelMatcher;
+pub use maintenance::{DownsamplePolicy, MaintenanceReport, MaintenanceScheduler, RetentionPolicy};
+pub use registry::{RegistryPolicy, SeriesMeta};
+pub use retention::{ChunkedRetentionReport, DeleteReport};
+pub use timeseries::{
+    DuplicatePolicy, QueryPoint, RangeBatches, TimeSeriesOptions, TimeSeriesTable,
+    TimeSeriesTableRead,
+};
+pub use value::FixedWidthValue;
@@ -0,0 +1,534 @@
+//! Background downsampling and retention.
+//!
+//! A [`MaintenanceScheduler`] owns a thread that periodically downsamples newly completed
+//! windows of a time series table and then applies a retention policy, so that applications
+//! do not have to call [`downsample_to_minute`](TimeSeriesTable::downsample_to_minute) and
+//! [`apply_all_retentions`](TimeSeriesTable::apply_all_retentions) themselves.
+//!
+//! Progress is recorded in a `{name}_maintenance` table in the same transaction as the
+//! aggregates it covers, so each window is downsampled exactly once, even across restarts.
+
+use crate::aggregate::Granularity;
+use crate::encoding::TimestampEncoding;
+use crate::error::TimeSeriesError;
+use crate::timeseries::TimeSeriesTable;
+use crate::value::FixedWidthValue;
+use manifold::column_family::ColumnFamily;
+use manifold::{Error, ReadableTable, StorageError, TableDefinition, Value, WriteTransaction};
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Which aggregate levels a [`MaintenanceScheduler`] builds.
+///
+/// Each enabled level is built from the level below it: minute aggregates from raw points,
+/// hour aggregates from minute aggregates, and day aggregates from hour aggregates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownsamplePolicy {
+    /// Build minute aggregates from raw points.
+    pub minute: bool,
+    /// Build hour aggregates from minute aggregates.
+    pub hour: bool,
+    /// Build day aggregates from hour aggregates.
+    pub day: bool,
+    /// How long to wait after a window closes before downsampling it, to allow late points
+    /// to arrive. Points written into a window after it was downsampled are not aggregated.
+    pub delay: Duration,
+}
+
+impl Default for DownsamplePolicy {
+    /// Builds all levels as soon as their windows close.
+    fn default() -> Self {
+        Self {
+            minute: true,
+            hour: true,
+            day: true,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+/// How long a [`MaintenanceScheduler`] keeps data at each granularity.
+///
+/// `None` keeps data forever. The default keeps everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// How long to keep raw points.
+    pub raw: Option<Duration>,
+    /// How long to keep minute aggregates.
+    pub minute: Option<Duration>,
+    /// How long to keep hour aggregates.
+    pub hour: Option<Duration>,
+    /// How long to keep day aggregates.
+    pub day: Option<Duration>,
+}
+
+/// Summary of one maintenance cycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Minute aggregates written.
+    pub minute_aggregates: usize,
+    /// Hour aggregates written.
+    pub hour_aggregates: usize,
+    /// Day aggregates written.
+    pub day_aggregates: usize,
+    /// Raw points and aggregates deleted by the retention policy.
+    pub deleted: usize,
+}
+
+/// Runs downsampling and retention for one time series table on a background thread.
+///
+/// Every `interval` the scheduler opens a write transaction on its column family and:
+///
+/// 1. Downsamples every window that closed (plus [`DownsamplePolicy::delay`]) since the
+///    previous cycle, for every series with data in it
+/// 2. Applies the [`RetentionPolicy`]
+/// 3. Commits
+///
+/// Downsampling runs before retention, so raw points are aggregated before they are deleted.
+/// A cycle that fails, or cannot begin a write transaction, is skipped and retried on the
+/// next interval. With the `logging` feature, each cycle logs a summary.
+///
+/// Dropping the scheduler stops the thread, waiting for a running cycle to finish.
+///
+/// `V` is the value type of the table, `f32` by default (see [`FixedWidthValue`]).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use manifold::column_family::ColumnFamilyDatabase;
+/// use manifold_timeseries::{AbsoluteEncoding, DownsamplePolicy, MaintenanceScheduler, RetentionPolicy};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = ColumnFamilyDatabase::open("my.db")?;
+/// let cf = db.column_family_or_create("metrics")?;
+///
+/// let retention = RetentionPolicy {
+///     raw: Some(Duration::from_secs(24 * 60 * 60)),
+///     ..RetentionPolicy::default()
+/// };
+/// let scheduler = MaintenanceScheduler::<AbsoluteEncoding>::start(
+///     cf,
+///     "cpu",
+///     DownsamplePolicy::default(),
+///     retention,
+///     Duration::from_secs(60),
+/// );
+/// # drop(scheduler);
+/// # Ok(())
+/// # }
+/// ```
+pub struct MaintenanceScheduler<E: TimestampEncoding + 'static, V: FixedWidthValue = f32> {
+    maintenance: Arc<Maintenance<E, V>>,
+    shutdown_signal: Arc<AtomicBool>,
+    maintenance_thread: Option<JoinHandle<()>>,
+}
+
+impl<E: TimestampEncoding + 'static, V: FixedWidthValue> MaintenanceScheduler<E, V> {
+    /// Starts maintaining the table `name` in `cf`, running a cycle every `interval`.
+    ///
+    /// The first cycle runs one `interval` after the scheduler starts.
+    pub fn start(
+        cf: ColumnFamily,
+        name: &str,
+        downsample: DownsamplePolicy,
+        retention: RetentionPolicy,
+        interval: Duration,
+    ) -> Self {
+        let maintenance = Arc::new(Maintenance {
+            cf,
+            name: name.to_string(),
+            downsample,
+            retention,
+            _types: PhantomData,
+        });
+        let shutdown_signal = Arc::new(AtomicBool::new(false));
+
+        let thread_maintenance = Arc::clone(&maintenance);
+        let thread_shutdown = Arc::clone(&shutdown_signal);
+        let maintenance_thread = thread::spawn(move || {
+            Self::maintenance_loop(&thread_maintenance, interval, &thread_shutdown);
+        });
+
+        Self {
+            maintenance,
+            shutdown_signal,
+            maintenance_thread: Some(maintenance_thread),
+        }
+    }
+
+    /// Runs one maintenance cycle on the calling thread.
+    ///
+    /// Blocks while another write transaction, including a background cycle, is in progress
+    /// on the column family. Returns `Ok(None)` if a write transaction could not be begun.
+    pub fn run_once(&self) -> Result<Option<MaintenanceReport>, Error> {
+        self.maintenance.run_cycle()
+    }
+
+    fn maintenance_loop(
+        maintenance: &Maintenance<E, V>,
+        interval: Duration,
+        shutdown: &AtomicBool,
+    ) {
+        // Sleep in small increments to check the shutdown signal
+        let sleep_duration = interval.clamp(Duration::from_millis(1), Duration::from_millis(100));
+        let mut last_cycle = Instant::now();
+
+        loop {
+            thread::sleep(sleep_duration);
+
+            if shutdown.load(Ordering::Acquire) {
+                break;
+            }
+
+            if last_cycle.elapsed() >= interval {
+                if let Err(e) = maintenance.run_cycle() {
+                    #[cfg(feature = "logging")]
+                    log::error!(
+                        "Time series maintenance of '{}' failed (will retry): {e}",
+                        maintenance.name
+                    );
+                    #[cfg(not(feature = "logging"))]
+                    let _ = e;
+                }
+                last_cycle = Instant::now();
+            }
+        }
+    }
+}
+
+impl<E: TimestampEncoding + 'static, V: FixedWidthValue> Drop for MaintenanceScheduler<E, V> {
+    fn drop(&mut self) {
+        self.shutdown_signal.store(true, Ordering::Release);
+
+        if let Some(handle) = self.maintenance_thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// State shared between a scheduler and its thread.
+struct Maintenance<E, V> {
+    cf: ColumnFamily,
+    name: String,
+    downsample: DownsamplePolicy,
+    retention: RetentionPolicy,
+    _types: PhantomData<fn() -> (E, V)>,
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> Maintenance<E, V> {
+    fn run_cycle(&self) -> Result<Option<MaintenanceReport>, Error> {
+        let txn = match self.cf.begin_write() {
+            Ok(txn) => txn,
+            Err(e) => {
+                #[cfg(feature = "logging")]
+                log::warn!("Skipping time series maintenance of '{}': {e}", self.name);
+                #[cfg(not(feature = "logging"))]
+                let _ = e;
+                return Ok(None);
+            }
+        };
+
+        let report = self.run_in(&txn)?;
+        txn.commit()?;
+
+        #[cfg(feature = "logging")]
+        log::info!(
+            "Time series maintenance of '{}': {} minute, {} hour and {} day aggregates written, {} entries deleted",
+            self.name,
+            report.minute_aggregates,
+            report.hour_aggregates,
+            report.day_aggregates,
+            report.deleted
+        );
+
+        Ok(Some(report))
+    }
+
+    fn run_in(&self, txn: &WriteTransaction) -> Result<MaintenanceReport, Error> {
+        let now_ms = now_ms()?;
+        let closed_ms = now_ms.saturating_sub(duration_ms(self.downsample.delay));
+
+        let watermark_name = format!("{}_maintenance", self.name);
+        let watermark_def: TableDefinition<&str, u64> = TableDefinition::new(&watermark_name);
+        let mut watermarks = txn.open_table(watermark_def)?;
+        let mut ts = TimeSeriesTable::<E, V>::open(txn, &self.name).map_err(into_error)?;
+        let mut report = MaintenanceReport::default();
+
+        // Data at the source level is complete up to this point
+        let mut complete_ms = closed_ms;
+        for (target, enabled) in [
+            (Granularity::Minute, self.downsample.minute),
+            (Granularity::Hour, self.downsample.hour),
+            (Granularity::Day, self.downsample.day),
+        ] {
+            if !enabled {
+                continue;
+            }
+
+            let key = target.table_suffix();
+            let start_ms = watermarks.get(key)?.map_or(0, |guard| guard.value());
+            let end_ms = target.round_down(complete_ms);
+            if end_ms > start_ms {
+                let written =
+                    downsample_level(&mut ts, target, start_ms, end_ms).map_err(into_error)?;
+                match target {
+                    Granularity::Raw => unreachable!(),
+                    Granularity::Minute => report.minute_aggregates = written,
+                    Granularity::Hour => report.hour_aggregates = written,
+                    Granularity::Day => report.day_aggregates = written,
+                }
+                watermarks.insert(key, end_ms)?;
+            }
+
+            // The next level may only read windows this level has finished
+            complete_ms = complete_ms.min(end_ms.max(start_ms));
+        }
+
+        report.deleted = ts
+            .apply_all_retentions(
+                self.retention.raw,
+                self.retention.minute,
+                self.retention.hour,
+                self.retention.day,
+            )
+            .map_err(into_error)?;
+
+        Ok(report)
+    }
+}
+
+/// Downsamples `[start_ms, end_ms)` of every series into `target` from the level below it.
+fn downsample_level<E: TimestampEncoding, V: FixedWidthValue>(
+    ts: &mut TimeSeriesTable<'_, E, V>,
+    target: Granularity,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<usize, TimeSeriesError> {
+    let series = match target {
+        Granularity::Raw => unreachable!(),
+        Granularity::Minute => distinct_series(&ts.raw, start_ms, end_ms)?,
+        Granularity::Hour => distinct_series(ts.minute.table()?, start_ms, end_ms)?,
+        Granularity::Day => distinct_series(ts.hour.table()?, start_ms, end_ms)?,
+    };
+
+    let mut written = 0;
+    for series_id in &series {
+        written += match target {
+            Granularity::Raw => unreachable!(),
+            Granularity::Minute => ts.downsample_to_minute(series_id, start_ms, end_ms)?,
+            Granularity::Hour => ts.downsample_minute_to_hour(series_id, start_ms, end_ms)?,
+            Granularity::Day => ts.downsample_hour_to_day(series_id, start_ms, end_ms)?,
+        };
+    }
+    Ok(written)
+}
+
+/// Returns the series with at least one entry in `[start_ms, end_ms)`.
+fn distinct_series<V: Value + 'static>(
+    table: &impl ReadableTable<(u64, &'static str), V>,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<BTreeSet<String>, StorageError> {
+    let mut series = BTreeSet::new();
+    for item in table.range((start_ms, "")..(end_ms, ""))? {
+        let (key_guard, _) = item?;
+        let (_, series_id) = key_guard.value();
+        if !series.contains(series_id) {
+            series.insert(series_id.to_string());
+        }
+    }
+    Ok(series)
+}
+
+/// Converts an error of a time series table into the error type of a maintenance cycle.
+fn into_error(err: TimeSeriesError) -> Error {
+    match err {
+        TimeSeriesError::Storage(err) => err.into(),
+        TimeSeriesError::Table(err) => err.into(),
+        TimeSeriesError::Transaction(err) => err.into(),
+        TimeSeriesError::Commit(err) => err.into(),
+        err => Error::Io(std::io::Error::other(err)),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().min(u128::from(u64::MAX)) as u64
+}
+
+pub(crate) fn now_ms() -> Result<u64, StorageError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| StorageError::Io(std::io::Error::other(format!("System time error: {e}"))))?;
+    Ok(duration_ms(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use crate::timeseries::TimeSeriesTableRead;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    const MINUTE_MS: u64 = 60_000;
+    const HOUR_MS: u64 = 3_600_000;
+
+    /// Writes one point every 10 seconds for two series, from 3 hours to 90 minutes ago.
+    fn write_history(cf: &ColumnFamily) -> u64 {
+        let start = Granularity::Minute.round_down(now_ms().unwrap() - 3 * HOUR_MS);
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        let mut points = vec![];
+        for i in 0..540u16 {
+            let timestamp = start + u64::from(i) * 10_000;
+            points.push(("server1", timestamp, f32::from(i)));
+            points.push(("server2", timestamp, -f32::from(i)));
+        }
+        ts.write_batch(&points, false).unwrap();
+        drop(ts);
+        write_txn.commit().unwrap();
+        start
+    }
+
+    fn raw_retention() -> RetentionPolicy {
+        RetentionPolicy {
+            raw: Some(Duration::from_secs(2 * 60 * 60)),
+            ..RetentionPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_run_once() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+        let start = write_history(&cf);
+
+        let scheduler = MaintenanceScheduler::<AbsoluteEncoding>::start(
+            cf.clone(),
+            "cpu",
+            DownsamplePolicy::default(),
+            raw_retention(),
+            Duration::from_secs(3600),
+        );
+        let report = scheduler.run_once().unwrap().unwrap();
+
+        // 90 minutes of data per series, spanning two or three hours
+        assert_eq!(report.minute_aggregates, 2 * 90);
+        let hours = (Granularity::Hour.round_up(start + 90 * MINUTE_MS)
+            - Granularity::Hour.round_down(start))
+            / HOUR_MS;
+        assert_eq!(report.hour_aggregates, 2 * usize::try_from(hours).unwrap());
+        assert!(report.deleted >= 2 * 6 * 60);
+
+        {
+            let read_txn = cf.begin_read().unwrap();
+            let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+            let minute = ts
+                .get_aggregate(Granularity::Minute, "server1", start)
+                .unwrap()
+                .unwrap();
+            assert_eq!(minute.count, 6);
+            assert!((minute.min - 0.0).abs() < f64::EPSILON);
+            assert!((minute.max - 5.0).abs() < f64::EPSILON);
+            assert_eq!(minute.first_ts, start);
+            let minute = ts
+                .get_aggregate(Granularity::Minute, "server2", start + 89 * MINUTE_MS)
+                .unwrap()
+                .unwrap();
+            assert!((minute.min + 539.0).abs() < f64::EPSILON);
+        }
+
+        // Windows are only downsampled once, including by a new scheduler
+        let report = scheduler.run_once().unwrap().unwrap();
+        assert_eq!(report, MaintenanceReport::default());
+        drop(scheduler);
+
+        let scheduler = MaintenanceScheduler::<AbsoluteEncoding>::start(
+            cf,
+            "cpu",
+            DownsamplePolicy::default(),
+            raw_retention(),
+            Duration::from_secs(3600),
+        );
+        let report = scheduler.run_once().unwrap().unwrap();
+        assert_eq!(report.minute_aggregates, 0);
+        assert_eq!(report.hour_aggregates, 0);
+    }
+
+    #[test]
+    fn test_downsample_delay() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+        write_history(&cf);
+
+        // Nothing has been closed for four hours yet
+        let downsample = DownsamplePolicy {
+            delay: Duration::from_secs(4 * 60 * 60),
+            ..DownsamplePolicy::default()
+        };
+        let scheduler = MaintenanceScheduler::<AbsoluteEncoding>::start(
+            cf,
+            "cpu",
+            downsample,
+            RetentionPolicy::default(),
+            Duration::from_secs(3600),
+        );
+        let report = scheduler.run_once().unwrap().unwrap();
+        assert_eq!(report, MaintenanceReport::default());
+    }
+
+    #[test]
+    fn test_scheduler_runs_in_background() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+        let start = write_history(&cf);
+
+        let scheduler = MaintenanceScheduler::<AbsoluteEncoding>::start(
+            cf.clone(),
+            "cpu",
+            DownsamplePolicy::default(),
+            raw_retention(),
+            Duration::from_millis(20),
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let read_txn = cf.begin_read().unwrap();
+            let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+            let old_raw = ts
+                .range("server1", start, start + 30 * MINUTE_MS)
+                .unwrap()
+                .count();
+            let aggregate = ts
+                .get_aggregate(
+                    Granularity::Hour,
+                    "server2",
+                    Granularity::Hour.round_down(start),
+                )
+                .unwrap();
+            if old_raw == 0 && aggregate.is_some() {
+                break;
+            }
+            assert!(Instant::now() < deadline, "maintenance did not run");
+            drop(ts);
+            drop(read_txn);
+            thread::sleep(Duration::from_millis(20));
+        }
+        drop(scheduler);
+
+        // The most recent raw points are kept
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let recent = start + 89 * MINUTE_MS;
+        assert!(ts.get("server1", recent).unwrap().is_some());
+    }
+}
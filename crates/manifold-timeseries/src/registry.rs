@@ -0,0 +1,345 @@
+//! Registration of series before points are written to them.
+//!
+//! A time series accepts points for any series id by default, so a typo in a collector
+//! creates a new series silently. A table opened with
+//! [`TimeSeriesTable::open_with_registry`] checks every series written through it against a
+//! registry of known series, each with its creation time and an optional unit and
+//! description:
+//!
+//! - [`RegistryPolicy::Strict`] rejects points of unregistered series with
+//!   [`TimeSeriesError::UnregisteredSeries`], so series must be registered with
+//!   [`TimeSeriesTable::register_series`] first.
+//! - [`RegistryPolicy::AutoCreate`] registers a series, without unit or description, the first
+//!   time a point is written to it, which records when each series appeared.
+//!
+//! The registry is stored with the time series, but the policy is not: handles opened with
+//! [`TimeSeriesTable::open`] write to any series and leave the registry as it is. Registrations
+//! are kept when the points of a series are deleted.
+//!
+//! # Storage
+//!
+//! Registered series are stored in `{name}_series`, keyed by series id, with their creation
+//! time in milliseconds since the Unix epoch, unit and description.
+
+use crate::encoding::TimestampEncoding;
+use crate::error::TimeSeriesError;
+use crate::maintenance::now_ms;
+use crate::timeseries::{TimeSeriesTable, TimeSeriesTableRead};
+use crate::value::FixedWidthValue;
+use manifold::ReadableTable;
+use std::collections::BTreeSet;
+
+/// Value of a registered series: creation time, unit and description.
+pub(crate) type SeriesMetaValue = (u64, Option<&'static str>, Option<&'static str>);
+
+/// How a table opened with [`TimeSeriesTable::open_with_registry`] treats writes to series
+/// that are not registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryPolicy {
+    /// Writes to unregistered series fail with [`TimeSeriesError::UnregisteredSeries`] and
+    /// write nothing.
+    Strict,
+    /// Series are registered the first time a point is written to them.
+    AutoCreate,
+}
+
+/// What the registry records about a series.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeriesMeta {
+    /// When the series was registered, in milliseconds since the Unix epoch.
+    pub created_at: u64,
+    /// Unit of the values, such as `"bytes"` or `"celsius"`.
+    pub unit: Option<String>,
+    /// Description of what the series measures.
+    pub description: Option<String>,
+}
+
+impl SeriesMeta {
+    fn from_value((created_at, unit, description): (u64, Option<&str>, Option<&str>)) -> Self {
+        Self {
+            created_at,
+            unit: unit.map(str::to_string),
+            description: description.map(str::to_string),
+        }
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTable<'_, E, V> {
+    /// Registers a series, or updates the unit and description of a registered one.
+    ///
+    /// A series that is already registered keeps its creation time. Returns `true` if the
+    /// series was not registered before.
+    pub fn register_series(
+        &mut self,
+        series_id: &str,
+        unit: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<bool, TimeSeriesError> {
+        self.check_series_ids(std::iter::once(series_id))?;
+        let table = self.registry.table_mut()?;
+        let stored = table.get(series_id)?.map(|guard| guard.value().0);
+        let created_at = match stored {
+            Some(created_at) => created_at,
+            None => now_ms()?,
+        };
+        table.insert(series_id, (created_at, unit, description))?;
+        self.registered.insert(series_id.to_string());
+        Ok(stored.is_none())
+    }
+
+    /// Returns what the registry records about a series, or `None` if it is not registered.
+    pub fn series_meta(&self, series_id: &str) -> Result<Option<SeriesMeta>, TimeSeriesError> {
+        series_meta(self.registry.table()?, series_id)
+    }
+
+    /// Returns every registered series with what the registry records about it, ordered by
+    /// series id.
+    pub fn list_registered(&self) -> Result<Vec<(String, SeriesMeta)>, TimeSeriesError> {
+        list_registered(self.registry.table()?)
+    }
+
+    /// Returns the series among `series` that are not registered, in order, if the handle
+    /// checks the registry.
+    ///
+    /// Fails with [`TimeSeriesError::UnregisteredSeries`] under [`RegistryPolicy::Strict`].
+    /// Runs before anything is written, and the series returned are registered with
+    /// [`register_new_series`](Self::register_new_series) once every other check has passed.
+    pub(crate) fn unregistered_series<'a>(
+        &mut self,
+        series: impl Iterator<Item = &'a str>,
+    ) -> Result<BTreeSet<&'a str>, TimeSeriesError> {
+        let mut unregistered = BTreeSet::new();
+        let Some(policy) = self.registry_policy() else {
+            return Ok(unregistered);
+        };
+
+        for series_id in series {
+            if self.registered.contains(series_id) || unregistered.contains(series_id) {
+                continue;
+            }
+            if self.registry.table()?.get(series_id)?.is_some() {
+                self.registered.insert(series_id.to_string());
+                continue;
+            }
+            if policy == RegistryPolicy::Strict {
+                return Err(TimeSeriesError::UnregisteredSeries {
+                    series: series_id.to_string(),
+                });
+            }
+            unregistered.insert(series_id);
+        }
+        Ok(unregistered)
+    }
+
+    /// Registers series found by [`unregistered_series`](Self::unregistered_series), without
+    /// unit or description.
+    pub(crate) fn register_new_series(
+        &mut self,
+        series: BTreeSet<&str>,
+    ) -> Result<(), TimeSeriesError> {
+        if series.is_empty() {
+            return Ok(());
+        }
+        let created_at = now_ms()?;
+        let table = self.registry.table_mut()?;
+        for series_id in series {
+            table.insert(series_id, (created_at, None, None))?;
+            self.registered.insert(series_id.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTableRead<E, V> {
+    /// Returns what the registry records about a series, or `None` if it is not registered.
+    pub fn series_meta(&self, series_id: &str) -> Result<Option<SeriesMeta>, TimeSeriesError> {
+        match &self.registry {
+            Some(table) => series_meta(table, series_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns every registered series with what the registry records about it, ordered by
+    /// series id.
+    pub fn list_registered(&self) -> Result<Vec<(String, SeriesMeta)>, TimeSeriesError> {
+        match &self.registry {
+            Some(table) => list_registered(table),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Reads the registration of a series from the registry table.
+fn series_meta(
+    table: &impl ReadableTable<&'static str, SeriesMetaValue>,
+    series_id: &str,
+) -> Result<Option<SeriesMeta>, TimeSeriesError> {
+    Ok(table
+        .get(series_id)?
+        .map(|guard| SeriesMeta::from_value(guard.value())))
+}
+
+/// Reads every registration from the registry table.
+fn list_registered(
+    table: &impl ReadableTable<&'static str, SeriesMetaValue>,
+) -> Result<Vec<(String, SeriesMeta)>, TimeSeriesError> {
+    let mut series = Vec::new();
+    for entry in table.iter()? {
+        let (key_guard, value_guard) = entry?;
+        series.push((
+            key_guard.value().to_string(),
+            SeriesMeta::from_value(value_guard.value()),
+        ));
+    }
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::AbsoluteEncoding;
+    use manifold::ReadableTableMetadata;
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_strict_registry_rejects_unregistered_series() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open_with_registry(
+            &write_txn,
+            "cpu",
+            RegistryPolicy::Strict,
+        )
+        .unwrap();
+        assert_eq!(ts.registry_policy(), Some(RegistryPolicy::Strict));
+        assert!(
+            ts.register_series("server1.cpu", Some("percent"), None)
+                .unwrap()
+        );
+
+        ts.write("server1.cpu", 1000, 1.0).unwrap();
+        assert!(matches!(
+            ts.write("sever1.cpu", 1000, 1.0),
+            Err(TimeSeriesError::UnregisteredSeries { ref series }) if series == "sever1.cpu"
+        ));
+
+        // A batch with one unregistered series writes nothing
+        let points = [("server1.cpu", 2000, 2.0), ("server2.cpu", 2000, 2.0)];
+        assert!(matches!(
+            ts.write_batch(&points, false),
+            Err(TimeSeriesError::UnregisteredSeries { ref series }) if series == "server2.cpu"
+        ));
+        assert!(matches!(
+            ts.write_series_batch("server2.cpu", &[(3000, 3.0)], true),
+            Err(TimeSeriesError::UnregisteredSeries { .. })
+        ));
+        assert_eq!(ts.len().unwrap(), 1);
+
+        ts.register_series("server2.cpu", Some("percent"), None)
+            .unwrap();
+        ts.write_batch(&points, false).unwrap();
+        assert_eq!(ts.len().unwrap(), 3);
+        assert!(ts.series_meta("sever1.cpu").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_auto_create_registers_on_first_write() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let before = now_ms().unwrap();
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open_with_registry(
+            &write_txn,
+            "cpu",
+            RegistryPolicy::AutoCreate,
+        )
+        .unwrap();
+        ts.write("b", 1000, 1.0).unwrap();
+        ts.write_batch(&[("a", 1000, 1.0), ("b", 2000, 2.0)], false)
+            .unwrap();
+        let after = now_ms().unwrap();
+
+        let registered = ts.list_registered().unwrap();
+        let ids: Vec<&str> = registered.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        for (_, meta) in &registered {
+            assert!((before..=after).contains(&meta.created_at));
+            assert_eq!(meta.unit, None);
+            assert_eq!(meta.description, None);
+        }
+
+        // A series registered later keeps its creation time when described
+        let created_at = registered[1].1.created_at;
+        assert!(
+            !ts.register_series("b", Some("ms"), Some("Latency"))
+                .unwrap()
+        );
+        assert_eq!(ts.series_meta("b").unwrap().unwrap().created_at, created_at);
+        drop(ts);
+
+        // Without a policy, writes neither check nor touch the registry
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        assert_eq!(ts.registry_policy(), None);
+        ts.write("c", 1000, 1.0).unwrap();
+        assert!(ts.series_meta("c").unwrap().is_none());
+        assert_eq!(ts.registry.table().unwrap().len().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_series_meta_round_trips_after_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let registered = {
+            let db = ColumnFamilyDatabase::open(&path).unwrap();
+            let cf = db.column_family_or_create("metrics").unwrap();
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open_with_registry(
+                &write_txn,
+                "net",
+                RegistryPolicy::Strict,
+            )
+            .unwrap();
+            ts.register_series("eth0.rx", Some("bytes"), Some("Bytes received"))
+                .unwrap();
+            ts.register_series("eth0.tx", None, None).unwrap();
+            ts.write("eth0.rx", 1000, 10.0).unwrap();
+            let registered = ts.list_registered().unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+            registered
+        };
+
+        let db = ColumnFamilyDatabase::open(&path).unwrap();
+        let cf = db.column_family("metrics").unwrap();
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "net").unwrap();
+
+        let meta = ts.series_meta("eth0.rx").unwrap().unwrap();
+        assert_eq!(meta.unit.as_deref(), Some("bytes"));
+        assert_eq!(meta.description.as_deref(), Some("Bytes received"));
+        assert!(meta.created_at > 0);
+        assert_eq!(ts.series_meta("eth0.tx").unwrap().unwrap().unit, None);
+        assert!(ts.series_meta("eth1.rx").unwrap().is_none());
+        assert_eq!(ts.list_registered().unwrap(), registered);
+        drop(ts);
+        drop(read_txn);
+
+        // A time series that never used the registry has none
+        let write_txn = cf.begin_write().unwrap();
+        TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "disk")
+            .unwrap()
+            .write("sda", 1000, 1.0)
+            .unwrap();
+        write_txn.commit().unwrap();
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "disk").unwrap();
+        assert!(ts.list_registered().unwrap().is_empty());
+    }
+}
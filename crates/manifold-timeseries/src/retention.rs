@@ -2,11 +2,44 @@
 
 use crate::aggregate::Granularity;
 use crate::encoding::TimestampEncoding;
-use crate::timeseries::TimeSeriesTable;
-use manifold::{ReadableTable, StorageError};
+use crate::error::TimeSeriesError;
+use crate::timeseries::{TimeSeriesTable, check_range};
+use crate::value::FixedWidthValue;
+use manifold::column_family::ColumnFamily;
+use manifold::{ReadableTable, StorageError, Table, Value};
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::thread;
 use std::time::Duration;
 
-impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
+/// Outcome of [`TimeSeriesTable::apply_retention_chunked`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkedRetentionReport {
+    /// Number of data points deleted.
+    pub deleted: usize,
+    /// Number of write transactions committed.
+    pub transactions: usize,
+}
+
+/// Outcome of [`TimeSeriesTable::delete_series`] and
+/// [`TimeSeriesTable::delete_series_range`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeleteReport {
+    /// Number of raw data points deleted.
+    pub raw: usize,
+    /// Number of minute aggregates deleted.
+    pub minute: usize,
+    /// Number of hour aggregates deleted.
+    pub hour: usize,
+    /// Number of day aggregates deleted.
+    pub day: usize,
+    /// Number of aggregates recomputed from the remaining data.
+    pub recomputed: usize,
+    /// Number of labels deleted.
+    pub labels: usize,
+}
+
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTable<'_, E, V> {
     /// Applies a retention policy to delete data older than the specified duration.
     ///
     /// # Arguments
@@ -44,7 +77,7 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         &mut self,
         granularity: Granularity,
         keep_duration: Duration,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         let now_ms = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map_err(|e| {
@@ -55,10 +88,9 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
 
         let keep_duration_ms = keep_duration.as_millis().min(u128::from(u64::MAX)) as u64;
         if keep_duration_ms == 0 {
-            return Err(StorageError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Retention duration must be positive",
-            )));
+            return Err(TimeSeriesError::InvalidArgument(
+                "retention duration must be positive",
+            ));
         }
 
         let cutoff_ms = now_ms.saturating_sub(keep_duration_ms);
@@ -80,71 +112,137 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         &mut self,
         granularity: Granularity,
         cutoff_ms: u64,
-    ) -> Result<usize, StorageError> {
-        let mut keys_to_delete = Vec::new();
-        let start_key = (0u64, "");
-        let end_key = (cutoff_ms, "\u{10FFFF}");
-
-        // Collect keys based on granularity
-        match granularity {
-            Granularity::Raw => {
-                for item in self.raw.range(start_key..end_key)? {
-                    let (key_guard, _) = item?;
-                    let (timestamp, series_id) = key_guard.value();
-                    if timestamp < cutoff_ms {
-                        keys_to_delete.push((timestamp, series_id.to_string()));
-                    }
-                }
-            }
-            Granularity::Minute => {
-                for item in self.minute.range(start_key..end_key)? {
-                    let (key_guard, _) = item?;
-                    let (timestamp, series_id) = key_guard.value();
-                    if timestamp < cutoff_ms {
-                        keys_to_delete.push((timestamp, series_id.to_string()));
-                    }
-                }
-            }
-            Granularity::Hour => {
-                for item in self.hour.range(start_key..end_key)? {
-                    let (key_guard, _) = item?;
-                    let (timestamp, series_id) = key_guard.value();
-                    if timestamp < cutoff_ms {
-                        keys_to_delete.push((timestamp, series_id.to_string()));
-                    }
-                }
-            }
-            Granularity::Day => {
-                for item in self.day.range(start_key..end_key)? {
-                    let (key_guard, _) = item?;
-                    let (timestamp, series_id) = key_guard.value();
-                    if timestamp < cutoff_ms {
-                        keys_to_delete.push((timestamp, series_id.to_string()));
-                    }
-                }
-            }
+    ) -> Result<usize, TimeSeriesError> {
+        self.delete_before_limited(granularity, cutoff_ms, usize::MAX)
+    }
+
+    /// Deletes the data points of every series with a timestamp in `start_ms..end_ms` for a
+    /// given granularity.
+    ///
+    /// The rows are removed by a range scan of the table, without collecting their keys first.
+    /// Only raw rows are deleted from the raw table; points packed by
+    /// [`compact_range`](Self::compact_range) are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `granularity` - Which table to delete from
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive)
+    ///
+    /// # Returns
+    ///
+    /// Number of data points deleted
+    pub fn delete_range(
+        &mut self,
+        granularity: Granularity,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<usize, TimeSeriesError> {
+        check_range(start_ms, end_ms)?;
+        self.extract_range(granularity, start_ms, end_ms, usize::MAX)
+    }
+
+    /// Deletes up to `limit` of the oldest data points before `cutoff_ms` and returns how many
+    /// were deleted.
+    fn delete_before_limited(
+        &mut self,
+        granularity: Granularity,
+        cutoff_ms: u64,
+        limit: usize,
+    ) -> Result<usize, TimeSeriesError> {
+        self.extract_range(granularity, 0, cutoff_ms, limit)
+    }
+
+    /// Deletes up to `limit` of the oldest data points in `start_ms..end_ms` and returns how
+    /// many were deleted.
+    fn extract_range(
+        &mut self,
+        granularity: Granularity,
+        start_ms: u64,
+        end_ms: u64,
+        limit: usize,
+    ) -> Result<usize, TimeSeriesError> {
+        let range = (start_ms, "")..(end_ms, "");
+        let count = match granularity {
+            Granularity::Raw => extract_range(&mut self.raw, range, limit)?,
+            Granularity::Minute => extract_range(self.minute.table_mut()?, range, limit)?,
+            Granularity::Hour => extract_range(self.hour.table_mut()?, range, limit)?,
+            Granularity::Day => extract_range(self.day.table_mut()?, range, limit)?,
+        };
+        Ok(count)
+    }
+
+    /// Deletes all data points before `cutoff_ms` in repeated small write transactions.
+    ///
+    /// Deleting millions of points in one transaction holds the column family's write lock
+    /// for as long as the delete takes, stalling every other writer. This method instead
+    /// deletes at most `max_points_per_txn` of the oldest points per transaction, commits,
+    /// and yields before the next one, so that ingest writers interleave with the deletion.
+    ///
+    /// Each committed chunk stays deleted if a later chunk fails or the process stops, and
+    /// calling this method again continues where it left off.
+    ///
+    /// # Arguments
+    ///
+    /// * `cf` - Column family holding the table
+    /// * `name` - Name the table was opened with
+    /// * `granularity` - Which table to delete from
+    /// * `cutoff_ms` - Delete all data with timestamps before this value
+    /// * `max_points_per_txn` - Largest number of points deleted in one transaction
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use manifold_timeseries::{AbsoluteEncoding, Granularity, TimeSeriesTable};
+    /// # use manifold::column_family::ColumnFamilyDatabase;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = ColumnFamilyDatabase::open("test.db")?;
+    /// let cf = db.column_family_or_create("metrics")?;
+    ///
+    /// let report = TimeSeriesTable::<AbsoluteEncoding>::apply_retention_chunked(
+    ///     &cf,
+    ///     "cpu",
+    ///     Granularity::Raw,
+    ///     1_700_000_000_000,
+    ///     10_000,
+    /// )?;
+    /// println!("Deleted {} points in {} transactions", report.deleted, report.transactions);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_retention_chunked(
+        cf: &ColumnFamily,
+        name: &str,
+        granularity: Granularity,
+        cutoff_ms: u64,
+        max_points_per_txn: usize,
+    ) -> Result<ChunkedRetentionReport, TimeSeriesError> {
+        if max_points_per_txn == 0 {
+            return Err(TimeSeriesError::InvalidArgument(
+                "max_points_per_txn must be positive",
+            ));
         }
 
-        // Delete collected keys
-        let count = keys_to_delete.len();
-        for (timestamp, series_id) in keys_to_delete {
-            match granularity {
-                Granularity::Raw => {
-                    self.raw.remove((timestamp, series_id.as_str()))?;
-                }
-                Granularity::Minute => {
-                    self.minute.remove((timestamp, series_id.as_str()))?;
-                }
-                Granularity::Hour => {
-                    self.hour.remove((timestamp, series_id.as_str()))?;
-                }
-                Granularity::Day => {
-                    self.day.remove((timestamp, series_id.as_str()))?;
-                }
+        let mut report = ChunkedRetentionReport::default();
+        loop {
+            let txn = cf.begin_write()?;
+            let mut ts = TimeSeriesTable::<E, V>::open(&txn, name)?;
+            let deleted = ts.delete_before_limited(granularity, cutoff_ms, max_points_per_txn)?;
+            drop(ts);
+            if deleted == 0 {
+                txn.abort()?;
+                break;
             }
-        }
+            txn.commit()?;
 
-        Ok(count)
+            report.deleted += deleted;
+            report.transactions += 1;
+            if deleted < max_points_per_txn {
+                break;
+            }
+            thread::yield_now();
+        }
+        Ok(report)
     }
 
     /// Applies retention policies to all granularities at once.
@@ -191,7 +289,7 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
         minute_duration: Option<Duration>,
         hour_duration: Option<Duration>,
         day_duration: Option<Duration>,
-    ) -> Result<usize, StorageError> {
+    ) -> Result<usize, TimeSeriesError> {
         let mut total = 0;
 
         if let Some(duration) = raw_duration {
@@ -212,6 +310,146 @@ impl<E: TimestampEncoding> TimeSeriesTable<'_, E> {
 
         Ok(total)
     }
+
+    /// Deletes a series from every granularity.
+    ///
+    /// Removes the raw points, including those packed by
+    /// [`compact_range`](Self::compact_range), and the minute, hour and day aggregates of the
+    /// series, along with its labels and the progress of
+    /// [`downsample_incremental`](Self::downsample_incremental), as part of the write
+    /// transaction the table was opened in. Other series are left alone. Each table is scanned
+    /// in full, since keys are ordered by timestamp first.
+    pub fn delete_series(&mut self, series_id: &str) -> Result<DeleteReport, TimeSeriesError> {
+        let labels = self.labels(series_id)?.len();
+        self.remove_labels(series_id)?;
+        for level in [Granularity::Minute, Granularity::Hour, Granularity::Day] {
+            self.watermarks
+                .table_mut()?
+                .remove((series_id, level.table_suffix()))?;
+        }
+        self.latest.remove(series_id);
+
+        let packed = self.remove_chunks(series_id)?;
+        Ok(DeleteReport {
+            raw: remove_series(&mut self.raw, series_id)? + packed,
+            minute: remove_series(self.minute.table_mut()?, series_id)?,
+            hour: remove_series(self.hour.table_mut()?, series_id)?,
+            day: remove_series(self.day.table_mut()?, series_id)?,
+            recomputed: 0,
+            labels,
+        })
+    }
+
+    /// Deletes the raw points of a series in `start_ms..end_ms` and corrects its aggregates.
+    ///
+    /// Every aggregate whose window held a deleted point is recomputed from the level below, in
+    /// the same transaction: minute aggregates from the remaining raw points, hour aggregates
+    /// from minute aggregates, and day aggregates from hour aggregates. An aggregate with
+    /// nothing left in its window is deleted. Windows that have not been downsampled yet are
+    /// not created, and a coarser aggregate is only recomputed if an aggregate below it
+    /// changed.
+    ///
+    /// Labels and downsampling progress are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier to delete from
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive)
+    pub fn delete_series_range(
+        &mut self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<DeleteReport, TimeSeriesError> {
+        check_range(start_ms, end_ms)?;
+
+        let mut timestamps = Vec::new();
+        for item in self.raw.range((start_ms, "")..(end_ms, ""))? {
+            let (key_guard, _) = item?;
+            let (timestamp, sid) = key_guard.value();
+            if sid == series_id {
+                timestamps.push(timestamp);
+            }
+        }
+        for &timestamp in &timestamps {
+            self.raw.remove((timestamp, series_id))?;
+        }
+        self.latest.remove(series_id);
+
+        let mut report = DeleteReport {
+            raw: timestamps.len(),
+            ..DeleteReport::default()
+        };
+        let mut source = Granularity::Raw;
+        let mut changed: BTreeSet<u64> = timestamps.into_iter().collect();
+        for level in [Granularity::Minute, Granularity::Hour, Granularity::Day] {
+            let windows: BTreeSet<u64> = changed.iter().map(|&ts| level.round_down(ts)).collect();
+            changed.clear();
+            for window_ms in windows {
+                let existed = match level {
+                    Granularity::Raw => unreachable!(),
+                    Granularity::Minute => self.minute.table_mut()?,
+                    Granularity::Hour => self.hour.table_mut()?,
+                    Granularity::Day => self.day.table_mut()?,
+                }
+                .remove((window_ms, series_id))?
+                .is_some();
+                if !existed {
+                    continue;
+                }
+
+                let window_end = window_ms.saturating_add(level.duration_ms());
+                let written = if source == Granularity::Raw {
+                    self.downsample_range(series_id, window_ms, window_end, level)?
+                } else {
+                    self.downsample_aggregates(series_id, window_ms, window_end, source, level)?
+                };
+                if written > 0 {
+                    report.recomputed += written;
+                } else {
+                    match level {
+                        Granularity::Raw => unreachable!(),
+                        Granularity::Minute => report.minute += 1,
+                        Granularity::Hour => report.hour += 1,
+                        Granularity::Day => report.day += 1,
+                    }
+                }
+                changed.insert(window_ms);
+            }
+            source = level;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Removes every row of `series_id` from `table` and returns how many were removed.
+fn remove_series<V: Value + 'static>(
+    table: &mut Table<'_, (u64, &'static str), V>,
+    series_id: &str,
+) -> Result<usize, StorageError> {
+    let mut count = 0;
+    for item in table.extract_if(|(_, sid), _| sid == series_id)? {
+        item?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Removes up to `limit` of the first rows of `table` in `range` and returns how many were
+/// removed.
+fn extract_range<V: Value + 'static>(
+    table: &mut Table<'_, (u64, &'static str), V>,
+    range: Range<(u64, &str)>,
+    limit: usize,
+) -> Result<usize, StorageError> {
+    let mut count = 0;
+    for item in table.extract_from_if(range, |_, _| true)?.take(limit) {
+        item?;
+        count += 1;
+    }
+    Ok(count)
 }
 
 #[cfg(test)]
@@ -324,4 +562,379 @@ mod tests {
             assert_eq!(count, 2); // Only 2 points remain
         }
     }
+
+    #[test]
+    fn test_delete_range_bounds() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for timestamp in [100_000, 200_000, 300_000, 400_000] {
+                ts.write("server1", timestamp, 1.0).unwrap();
+                ts.write("server2", timestamp, 2.0).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+
+            // The start is inclusive and the end exclusive, for every series
+            let deleted = ts.delete_range(Granularity::Raw, 200_000, 400_000).unwrap();
+            assert_eq!(deleted, 4);
+            assert_eq!(
+                ts.delete_range(Granularity::Raw, 200_000, 400_000).unwrap(),
+                0
+            );
+            assert_eq!(
+                ts.delete_range(Granularity::Raw, 400_000, 400_000).unwrap(),
+                0
+            );
+            assert!(matches!(
+                ts.delete_range(Granularity::Raw, 400_000, 300_000),
+                Err(TimeSeriesError::InvalidRange { .. })
+            ));
+
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        for series_id in ["server1", "server2"] {
+            let timestamps: Vec<u64> = ts_read
+                .range(series_id, 0, u64::MAX)
+                .unwrap()
+                .map(|point| point.unwrap().0)
+                .collect();
+            assert_eq!(timestamps, vec![100_000, 400_000]);
+        }
+    }
+
+    /// Counts the raw points of a series
+    fn raw_points(
+        ts: &crate::timeseries::TimeSeriesTableRead<AbsoluteEncoding>,
+        series_id: &str,
+    ) -> usize {
+        ts.range(series_id, 0, u64::MAX).unwrap().count()
+    }
+
+    #[test]
+    fn test_apply_retention_chunked_with_concurrent_writes() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // 200k old points across four series
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for series in ["a", "b", "c", "d"] {
+                let points: Vec<(u64, f32)> = (0..50_000u64).map(|i| (i, 1.0)).collect();
+                ts.write_series_batch(series, &points, true).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let done = std::sync::atomic::AtomicBool::new(false);
+        let (report, written) = std::thread::scope(|s| {
+            let writer = s.spawn(|| {
+                let mut written = 0u64;
+                while !done.load(std::sync::atomic::Ordering::Acquire) || written < 10 {
+                    let write_txn = cf.begin_write().unwrap();
+                    let mut ts =
+                        TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+                    ts.write("live", 1_000_000 + written, 2.0).unwrap();
+                    drop(ts);
+                    write_txn.commit().unwrap();
+                    written += 1;
+                }
+                written
+            });
+
+            let report = TimeSeriesTable::<AbsoluteEncoding>::apply_retention_chunked(
+                &cf,
+                "cpu",
+                Granularity::Raw,
+                100_000,
+                10_000,
+            )
+            .unwrap();
+            done.store(true, std::sync::atomic::Ordering::Release);
+            (report, writer.join().unwrap())
+        });
+
+        assert_eq!(report.deleted, 200_000);
+        assert_eq!(report.transactions, 20);
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        for series in ["a", "b", "c", "d"] {
+            assert_eq!(raw_points(&ts_read, series), 0);
+        }
+        assert_eq!(
+            raw_points(&ts_read, "live"),
+            usize::try_from(written).unwrap()
+        );
+
+        // Re-running with nothing left to delete uses no transactions
+        drop(ts_read);
+        drop(read_txn);
+        let report = TimeSeriesTable::<AbsoluteEncoding>::apply_retention_chunked(
+            &cf,
+            "cpu",
+            Granularity::Raw,
+            100_000,
+            10_000,
+        )
+        .unwrap();
+        assert_eq!(report, ChunkedRetentionReport::default());
+    }
+
+    #[test]
+    fn test_apply_retention_chunked_resumes() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            let points: Vec<(u64, f32)> = (0..25u64).map(|i| (i * 1000, 1.0)).collect();
+            ts.write_series_batch("server1", &points, true).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        // A partial run, as if interrupted after one chunk
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            assert_eq!(
+                ts.delete_before_limited(Granularity::Raw, 20_000, 7)
+                    .unwrap(),
+                7
+            );
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let report = TimeSeriesTable::<AbsoluteEncoding>::apply_retention_chunked(
+            &cf,
+            "cpu",
+            Granularity::Raw,
+            20_000,
+            5,
+        )
+        .unwrap();
+        assert_eq!(
+            report,
+            ChunkedRetentionReport {
+                deleted: 13,
+                transactions: 3
+            }
+        );
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        assert_eq!(ts_read.count_raw("server1", 0, u64::MAX).unwrap(), 5);
+        assert!(matches!(
+            TimeSeriesTable::<AbsoluteEncoding>::apply_retention_chunked(
+                &cf,
+                "cpu",
+                Granularity::Raw,
+                20_000,
+                0
+            ),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+    }
+
+    /// Counts the aggregates of a series at `granularity`
+    fn aggregates(
+        ts: &crate::timeseries::TimeSeriesTableRead<AbsoluteEncoding>,
+        granularity: Granularity,
+        series_id: &str,
+    ) -> usize {
+        ts.range_aggregates(granularity, series_id, 0, u64::MAX)
+            .unwrap()
+            .count()
+    }
+
+    #[test]
+    fn test_delete_series() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Two days of points every 10 minutes, downsampled to every granularity
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            let points: Vec<(u64, f32)> = (0..288u64).map(|i| (i * 600_000, 1.0)).collect();
+            for series in ["a", "b"] {
+                ts.write_series_batch(series, &points, true).unwrap();
+                ts.set_labels(series, &[("host", series), ("metric", "cpu")])
+                    .unwrap();
+                for level in [Granularity::Minute, Granularity::Hour, Granularity::Day] {
+                    ts.downsample_incremental(series, level).unwrap();
+                }
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            let report = ts.delete_series("a").unwrap();
+            assert_eq!(
+                report,
+                DeleteReport {
+                    raw: 288,
+                    minute: 288,
+                    hour: 48,
+                    day: 2,
+                    recomputed: 0,
+                    labels: 2,
+                }
+            );
+            assert_eq!(ts.watermark("a", Granularity::Minute).unwrap(), None);
+            assert!(ts.watermark("b", Granularity::Day).unwrap().is_some());
+
+            // Deleting it again finds nothing
+            assert_eq!(ts.delete_series("a").unwrap(), DeleteReport::default());
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        assert_eq!(raw_points(&ts_read, "a"), 0);
+        assert_eq!(raw_points(&ts_read, "b"), 288);
+        for (level, count) in [
+            (Granularity::Minute, 288),
+            (Granularity::Hour, 48),
+            (Granularity::Day, 2),
+        ] {
+            assert_eq!(aggregates(&ts_read, level, "a"), 0);
+            assert_eq!(aggregates(&ts_read, level, "b"), count);
+        }
+        assert!(ts_read.labels("a").unwrap().is_empty());
+        assert_eq!(
+            ts_read
+                .select_series(&[crate::labels::LabelMatcher::equal("metric", "cpu")])
+                .unwrap(),
+            ["b"]
+        );
+    }
+
+    #[test]
+    fn test_delete_series_range_recomputes_aggregates() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Two hours of points every 10 seconds, valued by their index, downsampled, and one
+        // later point that is not downsampled yet
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            let mut points: Vec<(u64, f32)> = (0..720u16)
+                .map(|i| (u64::from(i) * 10_000, f32::from(i)))
+                .collect();
+            points.push((8_000_000, 1000.0));
+            for series in ["a", "b"] {
+                ts.write_series_batch(series, &points, true).unwrap();
+                ts.downsample_to_minute(series, 0, 7_200_000).unwrap();
+                ts.downsample_minute_to_hour(series, 0, 7_200_000).unwrap();
+                ts.downsample_hour_to_day(series, 0, 86_400_000).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+
+            // Empties the minute at 120s and leaves three points in the minute at 60s
+            let report = ts.delete_series_range("a", 90_000, 180_000).unwrap();
+            assert_eq!(
+                report,
+                DeleteReport {
+                    raw: 9,
+                    minute: 1,
+                    recomputed: 3,
+                    ..DeleteReport::default()
+                }
+            );
+
+            // Windows that were never downsampled are not created
+            let report = ts.delete_series_range("a", 7_200_000, 9_000_000).unwrap();
+            assert_eq!(
+                report,
+                DeleteReport {
+                    raw: 1,
+                    ..DeleteReport::default()
+                }
+            );
+            assert!(matches!(
+                ts.delete_series_range("a", 2, 1),
+                Err(TimeSeriesError::InvalidRange { start: 2, end: 1 })
+            ));
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts_read =
+            crate::timeseries::TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu")
+                .unwrap();
+        assert_eq!(raw_points(&ts_read, "a"), 711);
+        assert_eq!(raw_points(&ts_read, "b"), 721);
+
+        let aggregate = |granularity, series_id, timestamp_ms| {
+            ts_read
+                .get_aggregate(granularity, series_id, timestamp_ms)
+                .unwrap()
+        };
+        let minute = aggregate(Granularity::Minute, "a", 60_000).unwrap();
+        assert_eq!((minute.count, minute.min, minute.max), (3, 6.0, 8.0));
+        assert!(aggregate(Granularity::Minute, "a", 120_000).is_none());
+        assert!(aggregate(Granularity::Minute, "a", 8_000_000).is_none());
+
+        // Coarser windows match the remaining raw points
+        let hour = aggregate(Granularity::Hour, "a", 0).unwrap();
+        let expected_sum: f64 = (0..360)
+            .filter(|i| !(9..18).contains(i))
+            .map(f64::from)
+            .sum();
+        assert_eq!((hour.count, hour.sum), (351, expected_sum));
+        assert_eq!(
+            aggregate(Granularity::Hour, "a", 3_600_000).unwrap().count,
+            360
+        );
+        assert_eq!(aggregate(Granularity::Day, "a", 0).unwrap().count, 711);
+
+        // The other series is untouched
+        assert_eq!(
+            aggregate(Granularity::Minute, "b", 120_000).unwrap().count,
+            6
+        );
+        assert_eq!(aggregate(Granularity::Day, "b", 0).unwrap().count, 720);
+    }
 }
@@ -1,12 +1,82 @@
 //! Time series table implementation with multi-granularity support.
 
-use crate::aggregate::{Aggregate, Granularity};
-use crate::encoding::TimestampEncoding;
+use crate::aggregate::{Aggregate, AggregateFormat, AggregateV1, Granularity, LegacyAggregate};
+use crate::compaction::{ChunkKey, PackedRange, packed_point};
+use crate::encoding::{EncodingError, TimestampEncoding};
+use crate::error::TimeSeriesError;
+use crate::registry::{RegistryPolicy, SeriesMetaValue};
+use crate::value::FixedWidthValue;
 use manifold::{
-    ReadOnlyTable, ReadTransaction, ReadableTableMetadata, StorageError, Table, TableDefinition,
-    TableError, WriteTransaction,
+    AccessGuard, Key, ReadHint, ReadOnlyTable, ReadTransaction, ReadableTable,
+    ReadableTableMetadata, Table, TableDefinition, TableError, TableHandle, Value,
+    WriteTransaction,
 };
+use std::cell::OnceCell;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
+/// A key and value of the raw table, ready to insert.
+type RawEntry<'a, V> = ((u64, &'a str), V);
+
+/// What a write does with a point whose series and timestamp already have a value.
+///
+/// Applies both to points already stored and to several points with the same series and
+/// timestamp within one batch, which are handled in the order they appear in the batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// The new value replaces the stored one. Within a batch, the last value wins.
+    #[default]
+    Overwrite,
+    /// The stored value is kept and the new one is dropped. Within a batch, the first value
+    /// wins.
+    Ignore,
+    /// The write fails with [`TimeSeriesError::DuplicateTimestamp`] and no point of it is
+    /// written.
+    Error,
+    /// The new value is added to the stored one, for counters that are sharded across
+    /// writers.
+    Sum,
+}
+
+/// Longest series id, in bytes, that writes accept unless set with
+/// [`TimeSeriesOptions::max_series_id_len`].
+pub const DEFAULT_MAX_SERIES_ID_LEN: usize = 4096;
+
+/// Options for opening a [`TimeSeriesTable`] with
+/// [`open_with_options`](TimeSeriesTable::open_with_options).
+///
+/// Options are not stored in the database; they only last as long as the opened handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSeriesOptions {
+    /// How writes handle points with a series and timestamp that already have a value.
+    pub duplicate_policy: DuplicatePolicy,
+    /// Whether writes accept points older than the latest point of their series with an
+    /// encoding that requires timestamps in order, such as
+    /// [`DeltaEncoding`](crate::DeltaEncoding).
+    ///
+    /// Keys hold absolute timestamps for every encoding, so such a point is stored at its
+    /// absolute timestamp like any other, instead of failing the write.
+    pub allow_out_of_order: bool,
+    /// Whether writes check their series against the registry, and what they do with
+    /// unregistered ones. `None` writes to any series without touching the registry.
+    pub registry: Option<RegistryPolicy>,
+    /// Longest series id, in bytes, that writes, labels and registrations accept. Longer ones
+    /// fail with [`TimeSeriesError::SeriesIdTooLong`] before anything is written.
+    pub max_series_id_len: usize,
+}
+
+impl Default for TimeSeriesOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_policy: DuplicatePolicy::default(),
+            allow_out_of_order: false,
+            registry: None,
+            max_series_id_len: DEFAULT_MAX_SERIES_ID_LEN,
+        }
+    }
+}
 
 /// A table storing time series data with multi-granularity support.
 ///
@@ -14,49 +84,380 @@ use std::marker::PhantomData;
 /// efficient queries at different time scales. All tables are updated within
 /// the same write transaction.
 ///
+/// Only the raw table is opened with the handle. The aggregate tables, and the tables of
+/// downsampling progress, labels and compacted chunks, are opened the first time the handle
+/// uses them, so a transaction that only writes points neither opens nor creates them.
+///
+/// Writes can be restricted to a range of timestamps with
+/// [`set_valid_range`](Self::set_valid_range), to catch clients sending timestamps in the
+/// wrong unit before the points reach downsampling and retention.
+///
+/// With an encoding that does not support random access, such as
+/// [`DeltaEncoding`](crate::DeltaEncoding), each series must be written in timestamp order,
+/// including the points of a series within one batch: a point before the latest timestamp of
+/// its series, whether stored or written earlier in the transaction, fails the write with
+/// [`EncodingError::NonMonotonicTimestamp`] unless
+/// [`allow_out_of_order`](TimeSeriesOptions::allow_out_of_order) is set.
+///
+/// Writing a point with the same series and timestamp as an existing one replaces its value,
+/// unless another [`DuplicatePolicy`] is chosen with
+/// [`open_with_options`](Self::open_with_options).
+///
 /// # Type Parameters
 ///
 /// - `E`: The timestamp encoding strategy (`AbsoluteEncoding` or `DeltaEncoding`)
-pub struct TimeSeriesTable<'txn, E: TimestampEncoding> {
-    pub(crate) raw: Table<'txn, (u64, &'static str), f32>,
-    pub(crate) minute: Table<'txn, (u64, &'static str), Aggregate>,
-    pub(crate) hour: Table<'txn, (u64, &'static str), Aggregate>,
-    pub(crate) day: Table<'txn, (u64, &'static str), Aggregate>,
+/// - `V`: The type of raw values, `f32` by default (see [`FixedWidthValue`])
+pub struct TimeSeriesTable<'txn, E: TimestampEncoding, V: FixedWidthValue = f32> {
+    pub(crate) raw: Table<'txn, (u64, &'static str), V>,
+    pub(crate) minute: LazyTable<'txn, (u64, &'static str), Aggregate>,
+    pub(crate) hour: LazyTable<'txn, (u64, &'static str), Aggregate>,
+    pub(crate) day: LazyTable<'txn, (u64, &'static str), Aggregate>,
+    pub(crate) watermarks: LazyTable<'txn, (&'static str, &'static str), u64>,
+    pub(crate) labels: LazyTable<'txn, (&'static str, &'static str), &'static str>,
+    pub(crate) label_index: LazyTable<'txn, (&'static str, &'static str, &'static str), ()>,
+    pub(crate) chunks: LazyTable<'txn, ChunkKey, &'static [u8]>,
+    pub(crate) registry: LazyTable<'txn, &'static str, SeriesMetaValue>,
+    pub(crate) registered: HashSet<String>,
+    valid_range: Option<RangeInclusive<u64>>,
+    pub(crate) latest: HashMap<String, u64>,
+    duplicate_policy: DuplicatePolicy,
+    allow_out_of_order: bool,
+    registry_policy: Option<RegistryPolicy>,
+    max_series_id_len: usize,
     _encoding: PhantomData<E>,
 }
 
-impl<'txn, E: TimestampEncoding> TimeSeriesTable<'txn, E> {
+impl<'txn, E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTable<'txn, E, V> {
     /// Opens a time series table for writing.
     ///
-    /// Creates four internal tables: `{name}_raw`, `{name}_minute`, `{name}_hour`, `{name}_day`.
-    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TableError> {
-        let raw_name = format!("{name}_raw");
-        let minute_name = format!("{name}_minute");
-        let hour_name = format!("{name}_hour");
-        let day_name = format!("{name}_day");
+    /// Opens, and creates if needed, the `{name}_raw` table of raw points. The other internal
+    /// tables are opened on first use: `{name}_minute`, `{name}_hour` and `{name}_day`, a
+    /// `{name}_watermarks` table recording the progress of
+    /// [`downsample_incremental`](Self::downsample_incremental), the `{name}_labels` and
+    /// `{name}_label_index` tables of [`set_labels`](Self::set_labels), the `{name}_chunks`
+    /// table of [`compact_range`](Self::compact_range), and the `{name}_series` table of
+    /// [`register_series`](Self::register_series).
+    ///
+    /// Aggregate tables written in an earlier [`Aggregate`] format are upgraded to the current
+    /// format as part of `txn` when the handle first uses them.
+    ///
+    /// Fails with [`TimeSeriesError::ValueTypeMismatch`] if the time series was created with
+    /// another value type than `V`.
+    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TimeSeriesError> {
+        Self::open_with_options(txn, name, TimeSeriesOptions::default())
+    }
 
-        let raw_def: TableDefinition<(u64, &str), f32> = TableDefinition::new(&raw_name);
-        let minute_def: TableDefinition<(u64, &str), Aggregate> =
-            TableDefinition::new(&minute_name);
-        let hour_def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(&hour_name);
-        let day_def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(&day_name);
+    /// Opens a time series table for writing that checks the series of every write against
+    /// the registry, as described in the [`registry`](crate::registry) module.
+    ///
+    /// See [`open`](Self::open) for the tables that are opened.
+    pub fn open_with_registry(
+        txn: &'txn WriteTransaction,
+        name: &str,
+        policy: RegistryPolicy,
+    ) -> Result<Self, TimeSeriesError> {
+        let options = TimeSeriesOptions {
+            registry: Some(policy),
+            ..TimeSeriesOptions::default()
+        };
+        Self::open_with_options(txn, name, options)
+    }
 
-        let raw = txn.open_table(raw_def)?;
-        let minute = txn.open_table(minute_def)?;
-        let hour = txn.open_table(hour_def)?;
-        let day = txn.open_table(day_def)?;
+    /// Opens a time series table for writing with the given options.
+    ///
+    /// See [`open`](Self::open) for the tables that are opened.
+    pub fn open_with_options(
+        txn: &'txn WriteTransaction,
+        name: &str,
+        options: TimeSeriesOptions,
+    ) -> Result<Self, TimeSeriesError> {
+        let raw_name = format!("{name}_raw");
+        let raw_def: TableDefinition<(u64, &str), V> = TableDefinition::new(&raw_name);
+
+        let raw = check_value_type::<V, _>(txn.open_table(raw_def), name)?;
 
         Ok(Self {
             raw,
-            minute,
-            hour,
-            day,
+            minute: LazyTable::new(txn, format!("{name}_minute"), open_aggregate_table),
+            hour: LazyTable::new(txn, format!("{name}_hour"), open_aggregate_table),
+            day: LazyTable::new(txn, format!("{name}_day"), open_aggregate_table),
+            watermarks: LazyTable::new(txn, format!("{name}_watermarks"), open_table),
+            labels: LazyTable::new(txn, format!("{name}_labels"), open_table),
+            label_index: LazyTable::new(txn, format!("{name}_label_index"), open_table),
+            chunks: LazyTable::new(txn, format!("{name}_chunks"), open_table),
+            registry: LazyTable::new(txn, format!("{name}_series"), open_table),
+            registered: HashSet::new(),
+            valid_range: None,
+            latest: HashMap::new(),
+            duplicate_policy: options.duplicate_policy,
+            allow_out_of_order: options.allow_out_of_order,
+            registry_policy: options.registry,
+            max_series_id_len: options.max_series_id_len,
             _encoding: PhantomData,
         })
     }
 
+    /// Deletes the aggregate table of `granularity` from the time series `name`.
+    ///
+    /// The downsampling progress recorded for that granularity is cleared too, so
+    /// [`downsample_incremental`](Self::downsample_incremental) and
+    /// [`MaintenanceScheduler`](crate::MaintenanceScheduler) rebuild it from the raw data. The table
+    /// is recreated empty the next time a handle opened with [`open`](Self::open) uses it, and
+    /// reads through [`TimeSeriesTableRead`] find no aggregates of that granularity until then.
+    /// Must not be called while the time series is open in `txn`.
+    ///
+    /// Returns whether the table existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimeSeriesError::InvalidArgument`] for [`Granularity::Raw`].
+    pub fn drop_granularity(
+        txn: &WriteTransaction,
+        name: &str,
+        granularity: Granularity,
+    ) -> Result<bool, TimeSeriesError> {
+        if granularity == Granularity::Raw {
+            return Err(TimeSeriesError::InvalidArgument(
+                "raw data cannot be dropped as a granularity",
+            ));
+        }
+
+        let suffix = granularity.table_suffix();
+        let table_name = format!("{name}_{suffix}");
+        let def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(&table_name);
+        if !txn.delete_table(def)? {
+            return Ok(false);
+        }
+
+        // Check before opening, to not create the progress tables of a series that has none
+        let tables: HashSet<String> = txn
+            .list_tables()?
+            .map(|handle| handle.name().to_string())
+            .collect();
+
+        let watermarks_name = format!("{name}_watermarks");
+        if tables.contains(&watermarks_name) {
+            let def: TableDefinition<(&str, &str), u64> = TableDefinition::new(&watermarks_name);
+            txn.open_table(def)?
+                .retain(|(_, level), _| level != suffix)?;
+        }
+        let maintenance_name = format!("{name}_maintenance");
+        if tables.contains(&maintenance_name) {
+            let def: TableDefinition<&str, u64> = TableDefinition::new(&maintenance_name);
+            txn.open_table(def)?.remove(suffix)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Rejects writes of points with timestamps outside `min_ts..=max_ts`.
+    ///
+    /// Applies to [`write`](Self::write), [`write_batch`](Self::write_batch) and
+    /// [`write_series_batch`](Self::write_series_batch) on this handle, which fail with
+    /// [`TimeSeriesError::OutOfRange`] without writing any point of the call. The range is
+    /// not stored in the database; it only lasts as long as this handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_ts` is greater than `max_ts`.
+    pub fn set_valid_range(&mut self, min_ts: u64, max_ts: u64) {
+        assert!(min_ts <= max_ts, "min_ts must not be greater than max_ts");
+        self.valid_range = Some(min_ts..=max_ts);
+    }
+
+    /// Accepts points with any timestamp again, undoing
+    /// [`set_valid_range`](Self::set_valid_range).
+    pub fn clear_valid_range(&mut self) {
+        self.valid_range = None;
+    }
+
+    /// Returns the range of accepted timestamps, if writes are restricted.
+    pub fn valid_range(&self) -> Option<RangeInclusive<u64>> {
+        self.valid_range.clone()
+    }
+
+    /// Returns how writes through this handle treat duplicate timestamps.
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicate_policy
+    }
+
+    /// Returns how writes through this handle treat unregistered series, or `None` if they do
+    /// not check the registry.
+    pub fn registry_policy(&self) -> Option<RegistryPolicy> {
+        self.registry_policy
+    }
+
+    /// Returns the longest series id, in bytes, that writes through this handle accept.
+    pub fn max_series_id_len(&self) -> usize {
+        self.max_series_id_len
+    }
+
+    /// Checks the length of every series id before anything is written.
+    pub(crate) fn check_series_ids<'a>(
+        &self,
+        series_ids: impl Iterator<Item = &'a str>,
+    ) -> Result<(), TimeSeriesError> {
+        for series_id in series_ids {
+            if series_id.len() > self.max_series_id_len {
+                return Err(TimeSeriesError::SeriesIdTooLong {
+                    len: series_id.len(),
+                    max: self.max_series_id_len,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every timestamp against the valid range before anything is written.
+    fn validate(&self, timestamps: impl Iterator<Item = u64>) -> Result<(), TimeSeriesError> {
+        let Some(range) = &self.valid_range else {
+            return Ok(());
+        };
+
+        let points: Vec<(usize, u64)> = timestamps
+            .enumerate()
+            .filter(|(_, timestamp)| !range.contains(timestamp))
+            .collect();
+        if points.is_empty() {
+            Ok(())
+        } else {
+            Err(TimeSeriesError::OutOfRange {
+                points,
+                min_ts: *range.start(),
+                max_ts: *range.end(),
+            })
+        }
+    }
+
+    /// Checks that no series goes back in time, for encodings that require timestamps in
+    /// order, and records the latest timestamp of each series.
+    ///
+    /// Points are checked in the order they are given, each against the latest timestamp of
+    /// its series so far. A point at the latest timestamp is accepted, and left to the
+    /// duplicate policy.
+    ///
+    /// Runs after every other check, so the recorded timestamps belong to points that are
+    /// about to be written.
+    fn check_order<'a>(
+        &mut self,
+        points: impl Iterator<Item = (&'a str, u64)>,
+    ) -> Result<(), TimeSeriesError> {
+        if E::supports_random_access() || self.allow_out_of_order {
+            return Ok(());
+        }
+
+        let mut latest: HashMap<&str, u64> = HashMap::new();
+        for (series_id, timestamp) in points {
+            let last = match latest.get(series_id) {
+                Some(&last) => Some(last),
+                None => self.latest_timestamp(series_id, timestamp)?,
+            };
+            if let Some(last) = last
+                && timestamp < last
+            {
+                return Err(EncodingError::NonMonotonicTimestamp {
+                    series: series_id.to_string(),
+                    last,
+                    attempted: timestamp,
+                }
+                .into());
+            }
+            latest.insert(series_id, timestamp);
+        }
+
+        for (series_id, timestamp) in latest {
+            self.latest.insert(series_id.to_string(), timestamp);
+        }
+        Ok(())
+    }
+
+    /// Returns the latest timestamp of a series before a point at `timestamp` is written, if
+    /// it may be after `timestamp`.
+    ///
+    /// The first time a series is written through this handle, its latest stored point is
+    /// looked up in the raw table. Only points after `timestamp` are read, so the lookup is
+    /// cheap when series are written in order.
+    fn latest_timestamp(
+        &self,
+        series_id: &str,
+        timestamp: u64,
+    ) -> Result<Option<u64>, TimeSeriesError> {
+        if let Some(&latest) = self.latest.get(series_id) {
+            return Ok(Some(latest));
+        }
+        let Some(after) = timestamp.checked_add(1) else {
+            return Ok(None);
+        };
+
+        for entry in self.raw.range((after, "")..)?.rev() {
+            let (key, _) = entry?;
+            let (stored_ms, stored_series) = key.value();
+            if stored_series == series_id {
+                return Ok(Some(stored_ms));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Applies the duplicate policy to points about to be written.
+    ///
+    /// Returns the points to write in key order, with duplicates resolved against each other
+    /// and against stored values, or `None` under [`DuplicatePolicy::Overwrite`], where points
+    /// are written as they are.
+    fn resolve_duplicates<'a>(
+        &self,
+        points: impl Iterator<Item = (&'a str, u64, V)>,
+    ) -> Result<Option<Vec<RawEntry<'a, V>>>, TimeSeriesError> {
+        let policy = self.duplicate_policy;
+        if policy == DuplicatePolicy::Overwrite {
+            return Ok(None);
+        }
+        let duplicate = |series_id: &str, timestamp| TimeSeriesError::DuplicateTimestamp {
+            series: series_id.to_string(),
+            timestamp,
+        };
+
+        let mut resolved: BTreeMap<(u64, &str), V> = BTreeMap::new();
+        for (series_id, timestamp, value) in points {
+            match resolved.entry((timestamp, series_id)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                Entry::Occupied(mut entry) => match policy {
+                    DuplicatePolicy::Overwrite => unreachable!(),
+                    DuplicatePolicy::Ignore => {}
+                    DuplicatePolicy::Error => return Err(duplicate(series_id, timestamp)),
+                    DuplicatePolicy::Sum => {
+                        let sum = *entry.get() + value;
+                        entry.insert(sum);
+                    }
+                },
+            }
+        }
+
+        let mut items = Vec::with_capacity(resolved.len());
+        for ((timestamp, series_id), value) in resolved {
+            let stored = self
+                .raw
+                .get((timestamp, series_id))?
+                .map(|guard| guard.value());
+            let value = match (stored, policy) {
+                (None, _) => value,
+                (Some(_), DuplicatePolicy::Overwrite) => unreachable!(),
+                (Some(_), DuplicatePolicy::Ignore) => continue,
+                (Some(_), DuplicatePolicy::Error) => return Err(duplicate(series_id, timestamp)),
+                (Some(stored), DuplicatePolicy::Sum) => stored + value,
+            };
+            items.push(((timestamp, series_id), value));
+        }
+        Ok(Some(items))
+    }
+
     /// Writes a single data point to the raw table.
     ///
+    /// A point with the same series and timestamp as a stored one is handled according to the
+    /// table's [`DuplicatePolicy`].
+    ///
     /// # Arguments
     ///
     /// * `series_id` - Series identifier (e.g., `"cpu.usage"`, `"sensor_42.temp"`)
@@ -66,24 +467,59 @@ impl<'txn, E: TimestampEncoding> TimeSeriesTable<'txn, E> {
         &mut self,
         series_id: &str,
         timestamp_ms: u64,
-        value: f32,
-    ) -> Result<(), TableError> {
-        self.raw.insert((timestamp_ms, series_id), &value)?;
+        value: V,
+    ) -> Result<(), TimeSeriesError> {
+        self.check_series_ids(std::iter::once(series_id))?;
+        self.validate(std::iter::once(timestamp_ms))?;
+        let unregistered = self.unregistered_series(std::iter::once(series_id))?;
+        let resolved =
+            self.resolve_duplicates(std::iter::once((series_id, timestamp_ms, value)))?;
+        self.check_order(std::iter::once((series_id, timestamp_ms)))?;
+        self.register_new_series(unregistered)?;
+        match resolved {
+            Some(items) => {
+                self.raw.insert_bulk(items, true)?;
+            }
+            None => {
+                self.raw.insert((timestamp_ms, series_id), &value)?;
+            }
+        }
         Ok(())
     }
 
     /// Writes multiple data points in a batch operation.
     ///
+    /// If a valid range is set and any point is outside it, no point is written. Points with
+    /// the same series and timestamp as a stored point or another point in the batch are
+    /// handled according to the table's [`DuplicatePolicy`].
+    ///
     /// # Arguments
     ///
     /// * `points` - Slice of (`series_id`, `timestamp_ms`, `value`) tuples
     /// * `sorted` - Whether the points are pre-sorted by (`timestamp`, `series_id`)
     pub fn write_batch(
         &mut self,
-        points: &[(&str, u64, f32)],
+        points: &[(&str, u64, V)],
         sorted: bool,
-    ) -> Result<(), StorageError> {
-        let items: Vec<((u64, &str), f32)> = points
+    ) -> Result<(), TimeSeriesError> {
+        self.check_series_ids(points.iter().map(|&(series_id, _, _)| series_id))?;
+        self.validate(points.iter().map(|&(_, timestamp_ms, _)| timestamp_ms))?;
+        let unregistered =
+            self.unregistered_series(points.iter().map(|&(series_id, _, _)| series_id))?;
+        let resolved = self.resolve_duplicates(points.iter().copied())?;
+        self.check_order(
+            points
+                .iter()
+                .map(|&(series_id, timestamp_ms, _)| (series_id, timestamp_ms)),
+        )?;
+        self.register_new_series(unregistered)?;
+
+        if let Some(items) = resolved {
+            self.raw.insert_bulk(items, true)?;
+            return Ok(());
+        }
+
+        let items: Vec<RawEntry<'_, V>> = points
             .iter()
             .map(|(series_id, timestamp_ms, value)| ((*timestamp_ms, *series_id), *value))
             .collect();
@@ -92,107 +528,390 @@ impl<'txn, E: TimestampEncoding> TimeSeriesTable<'txn, E> {
         Ok(())
     }
 
+    /// Writes multiple data points of a single series in a batch operation.
+    ///
+    /// This is faster than [`write_batch`](Self::write_batch) when every point belongs to the
+    /// same series: unsorted points are ordered by timestamp alone, and keys are built as they
+    /// are inserted instead of being collected and encoded up front.
+    ///
+    /// Points sharing a timestamp with each other or with a stored point are handled
+    /// according to the table's [`DuplicatePolicy`], as with repeated calls to
+    /// [`write`](Self::write). Keys have the same layout for every [`TimestampEncoding`], so
+    /// all encodings share this path.
+    ///
+    /// If a valid range is set and any point is outside it, no point is written. Indexes in
+    /// the error refer to `points` as passed in.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier of every point
+    /// * `points` - Slice of (`timestamp_ms`, `value`) tuples
+    /// * `sorted` - Whether the points are pre-sorted by timestamp. If `false`, the order is
+    ///   checked and the points are sorted only when needed
+    pub fn write_series_batch(
+        &mut self,
+        series_id: &str,
+        points: &[(u64, V)],
+        sorted: bool,
+    ) -> Result<(), TimeSeriesError> {
+        self.check_series_ids(std::iter::once(series_id))?;
+        self.validate(points.iter().map(|&(timestamp_ms, _)| timestamp_ms))?;
+        let unregistered = if points.is_empty() {
+            BTreeSet::new()
+        } else {
+            self.unregistered_series(std::iter::once(series_id))?
+        };
+        let resolved = self.resolve_duplicates(
+            points
+                .iter()
+                .map(|&(timestamp_ms, value)| (series_id, timestamp_ms, value)),
+        )?;
+        self.check_order(
+            points
+                .iter()
+                .map(|&(timestamp_ms, _)| (series_id, timestamp_ms)),
+        )?;
+        self.register_new_series(unregistered)?;
+
+        if let Some(items) = resolved {
+            self.raw.insert_bulk(items, true)?;
+            return Ok(());
+        }
+
+        let by_timestamp = |&(timestamp, _): &(u64, V)| timestamp;
+        debug_assert!(!sorted || points.is_sorted_by_key(by_timestamp));
+
+        let mut sorted_points = Vec::new();
+        let points = if sorted || points.is_sorted_by_key(by_timestamp) {
+            points
+        } else {
+            sorted_points.extend_from_slice(points);
+            // A stable sort keeps duplicate timestamps in their original order
+            sorted_points.sort_by_key(by_timestamp);
+            &sorted_points
+        };
+
+        self.raw.insert_bulk(
+            points
+                .iter()
+                .map(|&(timestamp, value)| ((timestamp, series_id), value)),
+            true,
+        )?;
+        Ok(())
+    }
+
     /// Returns the number of raw data points stored.
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.raw.len()
+    pub fn len(&self) -> Result<u64, TimeSeriesError> {
+        Ok(self.raw.len()?)
     }
 
     /// Returns `true` if the raw table contains no data points.
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, TimeSeriesError> {
         Ok(self.len()? == 0)
     }
 
     /// Returns a reference to the raw data table.
-    pub fn raw_table(&self) -> &Table<'txn, (u64, &'static str), f32> {
+    pub fn raw_table(&self) -> &Table<'txn, (u64, &'static str), V> {
         &self.raw
     }
 
-    /// Returns a reference to the minute aggregate table.
-    pub fn minute_table(&self) -> &Table<'txn, (u64, &'static str), Aggregate> {
-        &self.minute
+    /// Returns a reference to the minute aggregate table, opening it if this is its first use.
+    pub fn minute_table(&self) -> Result<&Table<'txn, (u64, &'static str), Aggregate>, TableError> {
+        self.minute.table()
+    }
+
+    /// Returns a mutable reference to the minute aggregate table, opening it if this is its
+    /// first use.
+    pub fn minute_table_mut(
+        &mut self,
+    ) -> Result<&mut Table<'txn, (u64, &'static str), Aggregate>, TableError> {
+        self.minute.table_mut()
+    }
+
+    /// Returns a reference to the hour aggregate table, opening it if this is its first use.
+    pub fn hour_table(&self) -> Result<&Table<'txn, (u64, &'static str), Aggregate>, TableError> {
+        self.hour.table()
+    }
+
+    /// Returns a reference to the day aggregate table, opening it if this is its first use.
+    pub fn day_table(&self) -> Result<&Table<'txn, (u64, &'static str), Aggregate>, TableError> {
+        self.day.table()
+    }
+}
+
+/// Opens a table of a time series, creating it if needed.
+type OpenTable<'txn, K, V> =
+    fn(&'txn WriteTransaction, &str) -> Result<Table<'txn, K, V>, TableError>;
+
+/// A table of a [`TimeSeriesTable`] that is opened, and created if needed, on first use.
+pub(crate) struct LazyTable<'txn, K: Key + 'static, V: Value + 'static> {
+    txn: &'txn WriteTransaction,
+    name: String,
+    open: OpenTable<'txn, K, V>,
+    table: OnceCell<Table<'txn, K, V>>,
+}
+
+impl<'txn, K: Key + 'static, V: Value + 'static> LazyTable<'txn, K, V> {
+    fn new(txn: &'txn WriteTransaction, name: String, open: OpenTable<'txn, K, V>) -> Self {
+        Self {
+            txn,
+            name,
+            open,
+            table: OnceCell::new(),
+        }
+    }
+
+    /// Returns the table, opening it if this is its first use.
+    pub(crate) fn table(&self) -> Result<&Table<'txn, K, V>, TableError> {
+        if let Some(table) = self.table.get() {
+            return Ok(table);
+        }
+        let table = (self.open)(self.txn, &self.name)?;
+        Ok(self.table.get_or_init(|| table))
     }
 
-    /// Returns a mutable reference to the minute aggregate table.
-    pub fn minute_table_mut(&mut self) -> &mut Table<'txn, (u64, &'static str), Aggregate> {
-        &mut self.minute
+    /// Returns the table for writing, opening it if this is its first use.
+    pub(crate) fn table_mut(&mut self) -> Result<&mut Table<'txn, K, V>, TableError> {
+        self.table()?;
+        Ok(self.table.get_mut().expect("table was opened above"))
     }
+}
+
+/// Opens a table with its current definition.
+fn open_table<'txn, K: Key + 'static, V: Value + 'static>(
+    txn: &'txn WriteTransaction,
+    name: &str,
+) -> Result<Table<'txn, K, V>, TableError> {
+    txn.open_table(TableDefinition::new(name))
+}
 
-    /// Returns a reference to the hour aggregate table.
-    pub fn hour_table(&self) -> &Table<'txn, (u64, &'static str), Aggregate> {
-        &self.hour
+/// Reports a raw table created with another value type than `V` as
+/// [`TimeSeriesError::ValueTypeMismatch`].
+fn check_value_type<V: FixedWidthValue, T>(
+    result: Result<T, TableError>,
+    name: &str,
+) -> Result<T, TimeSeriesError> {
+    match result {
+        Err(TableError::TableTypeMismatch { value, .. }) => {
+            Err(TimeSeriesError::ValueTypeMismatch {
+                table: name.to_string(),
+                stored: value.name().to_string(),
+                requested: V::type_name().name().to_string(),
+            })
+        }
+        result => Ok(result?),
     }
+}
 
-    /// Returns a reference to the day aggregate table.
-    pub fn day_table(&self) -> &Table<'txn, (u64, &'static str), Aggregate> {
-        &self.day
+/// Opens an aggregate table, upgrading it first if it uses an earlier format.
+///
+/// Every format shares the type name of [`Aggregate`], so earlier formats are recognized by
+/// their width.
+fn open_aggregate_table<'txn>(
+    txn: &'txn WriteTransaction,
+    name: &str,
+) -> Result<Table<'txn, (u64, &'static str), Aggregate>, TableError> {
+    let def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(name);
+    match txn.open_table(def) {
+        Err(TableError::TypeDefinitionChanged { width, .. })
+            if width == LegacyAggregate::fixed_width() =>
+        {
+            upgrade_aggregates::<LegacyAggregate>(txn, name)?;
+            txn.open_table(def)
+        }
+        Err(TableError::TypeDefinitionChanged { width, .. })
+            if width == AggregateV1::fixed_width() =>
+        {
+            upgrade_aggregates::<AggregateV1>(txn, name)?;
+            txn.open_table(def)
+        }
+        result => result,
     }
 }
 
+/// Rewrites a table of aggregates in the earlier format `F` in the current format.
+///
+/// Aggregate tables are small relative to raw data, so the rows are buffered in memory while
+/// the table is recreated.
+fn upgrade_aggregates<F: AggregateFormat>(
+    txn: &WriteTransaction,
+    name: &str,
+) -> Result<(), TableError> {
+    let legacy_def: TableDefinition<(u64, &str), F> = TableDefinition::new(name);
+    let mut rows = Vec::new();
+    {
+        let legacy = txn.open_table(legacy_def)?;
+        for item in legacy.iter()? {
+            let (key_guard, value_guard) = item?;
+            let (timestamp, series_id) = key_guard.value();
+            rows.push((
+                timestamp,
+                series_id.to_string(),
+                value_guard.value().upgrade(timestamp),
+            ));
+        }
+    }
+    txn.delete_table(legacy_def)?;
+
+    let def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(name);
+    let mut table = txn.open_table(def)?;
+    for (timestamp, series_id, aggregate) in &rows {
+        table.insert((*timestamp, series_id.as_str()), aggregate)?;
+    }
+    Ok(())
+}
+
 /// Read-only time series table providing efficient access.
-pub struct TimeSeriesTableRead<E: TimestampEncoding> {
-    raw: ReadOnlyTable<(u64, &'static str), f32>,
-    minute: ReadOnlyTable<(u64, &'static str), Aggregate>,
-    hour: ReadOnlyTable<(u64, &'static str), Aggregate>,
-    day: ReadOnlyTable<(u64, &'static str), Aggregate>,
+///
+/// `V` is the type of raw values, as for [`TimeSeriesTable`].
+pub struct TimeSeriesTableRead<E: TimestampEncoding, V: FixedWidthValue = f32> {
+    raw: ReadOnlyTable<(u64, &'static str), V>,
+    minute: Option<ReadOnlyTable<(u64, &'static str), Aggregate>>,
+    hour: Option<ReadOnlyTable<(u64, &'static str), Aggregate>>,
+    day: Option<ReadOnlyTable<(u64, &'static str), Aggregate>>,
+    pub(crate) labels: Option<ReadOnlyTable<(&'static str, &'static str), &'static str>>,
+    pub(crate) label_index: Option<ReadOnlyTable<(&'static str, &'static str, &'static str), ()>>,
+    chunks: Option<ReadOnlyTable<ChunkKey, &'static [u8]>>,
+    pub(crate) registry: Option<ReadOnlyTable<&'static str, SeriesMetaValue>>,
     _encoding: PhantomData<E>,
 }
 
-impl<E: TimestampEncoding> TimeSeriesTableRead<E> {
+impl<E: TimestampEncoding, V: FixedWidthValue> TimeSeriesTableRead<E, V> {
     /// Opens a time series table for reading.
-    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, TableError> {
+    ///
+    /// Fails with [`TableError::TypeDefinitionChanged`], wrapped in
+    /// [`TimeSeriesError::Table`], if the aggregate tables still use an earlier format; using
+    /// them once through [`TimeSeriesTable::open`], for instance to downsample, upgrades them.
+    /// Fails with [`TimeSeriesError::ValueTypeMismatch`] if the time series was created with
+    /// another value type than `V`.
+    ///
+    /// Aggregate, label and registry tables that were never written, such as those of a time series
+    /// that only ever received raw points, are treated as empty.
+    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, TimeSeriesError> {
         let raw_name = format!("{name}_raw");
         let minute_name = format!("{name}_minute");
         let hour_name = format!("{name}_hour");
         let day_name = format!("{name}_day");
 
-        let raw_def: TableDefinition<(u64, &str), f32> = TableDefinition::new(&raw_name);
+        let raw_def: TableDefinition<(u64, &str), V> = TableDefinition::new(&raw_name);
         let minute_def: TableDefinition<(u64, &str), Aggregate> =
             TableDefinition::new(&minute_name);
         let hour_def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(&hour_name);
         let day_def: TableDefinition<(u64, &str), Aggregate> = TableDefinition::new(&day_name);
 
-        let raw = txn.open_table(raw_def)?;
-        let minute = txn.open_table(minute_def)?;
-        let hour = txn.open_table(hour_def)?;
-        let day = txn.open_table(day_def)?;
+        let raw = check_value_type::<V, _>(txn.open_table(raw_def), name)?;
+        let minute = open_optional(txn.open_table(minute_def))?;
+        let hour = open_optional(txn.open_table(hour_def))?;
+        let day = open_optional(txn.open_table(day_def))?;
+
+        let labels_name = format!("{name}_labels");
+        let labels_def: TableDefinition<(&str, &str), &str> = TableDefinition::new(&labels_name);
+        let labels = open_optional(txn.open_table(labels_def))?;
+        let label_index_name = format!("{name}_label_index");
+        let label_index_def: TableDefinition<(&str, &str, &str), ()> =
+            TableDefinition::new(&label_index_name);
+        let label_index = open_optional(txn.open_table(label_index_def))?;
+        let chunks_name = format!("{name}_chunks");
+        let chunks_def: TableDefinition<ChunkKey, &[u8]> = TableDefinition::new(&chunks_name);
+        let chunks = open_optional(txn.open_table(chunks_def))?;
+        let registry_name = format!("{name}_series");
+        let registry_def: TableDefinition<&str, SeriesMetaValue> =
+            TableDefinition::new(&registry_name);
+        let registry = open_optional(txn.open_table(registry_def))?;
 
         Ok(Self {
             raw,
             minute,
             hour,
             day,
+            labels,
+            label_index,
+            chunks,
+            registry,
             _encoding: PhantomData,
         })
     }
 
-    /// Gets a single data point from the raw table.
-    pub fn get(&self, series_id: &str, timestamp_ms: u64) -> Result<Option<f32>, StorageError> {
-        self.raw
-            .get((timestamp_ms, series_id))
-            .map(|opt| opt.map(|guard| guard.value()))
+    /// Gets a single data point, from the raw table or from a chunk packed by
+    /// [`TimeSeriesTable::compact_range`].
+    pub fn get(&self, series_id: &str, timestamp_ms: u64) -> Result<Option<V>, TimeSeriesError> {
+        if let Some(guard) = self.raw.get((timestamp_ms, series_id))? {
+            return Ok(Some(guard.value()));
+        }
+        match &self.chunks {
+            Some(chunks) => packed_point(chunks, series_id, timestamp_ms),
+            None => Ok(None),
+        }
     }
 
     /// Returns an iterator over raw data points in a time range.
     ///
+    /// Points packed into chunks by [`TimeSeriesTable::compact_range`] are merged with the
+    /// rows of the raw table.
+    ///
     /// # Arguments
     ///
     /// * `series_id` - Series identifier to query
     /// * `start_ms` - Start timestamp (inclusive)
-    /// * `end_ms` - End timestamp (exclusive)
+    /// * `end_ms` - End timestamp (exclusive), not before `start_ms`
     pub fn range(
         &self,
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<RangeIter<'_>, StorageError> {
-        let start_key = (start_ms, series_id);
-        let end_key = (end_ms, series_id);
-
-        let iter = self.raw.range(start_key..end_key)?;
+    ) -> Result<RangeIter<'_, V>, TimeSeriesError> {
+        self.range_with_hint(series_id, start_ms, end_ms, ReadHint::Normal)
+    }
 
+    /// Like [`range`](Self::range), with a hint for how the points will be read.
+    ///
+    /// Large scans read in order should pass [`ReadHint::Sequential`], so that the raw table
+    /// is prefetched from disk ahead of the iterator, or [`ReadHint::SequentialWindow`] to
+    /// bound how far ahead. The points returned are the same with any hint.
+    pub fn range_with_hint(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        hint: ReadHint,
+    ) -> Result<RangeIter<'_, V>, TimeSeriesError> {
+        let inner = SeriesRange::new(&self.raw, series_id, start_ms, end_ms, hint)?;
+        let packed = match &self.chunks {
+            Some(chunks) => Some(PackedRange::new(chunks, series_id, start_ms, end_ms)?),
+            None => None,
+        };
         Ok(RangeIter {
-            inner: iter,
-            series_id: series_id.to_string(),
+            inner,
+            packed,
+            next_loose: None,
+            next_packed: None,
+        })
+    }
+
+    /// Like [`range`](Self::range), returning the points in pages of up to `batch_size`.
+    ///
+    /// Every page but the last holds exactly `batch_size` points, so bindings that pay per
+    /// call across a boundary, such as WASM, can copy a page at a time instead of a point at a
+    /// time. Together the pages hold the points of `range`, in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimeSeriesError::InvalidArgument`] if `batch_size` is 0.
+    pub fn range_batched(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        batch_size: usize,
+    ) -> Result<RangeBatches<'_, V>, TimeSeriesError> {
+        if batch_size == 0 {
+            return Err(TimeSeriesError::InvalidArgument(
+                "batch size must be non-zero",
+            ));
+        }
+        Ok(RangeBatches {
+            inner: self.range(series_id, start_ms, end_ms)?,
+            batch_size,
         })
     }
 
@@ -202,24 +921,26 @@ impl<E: TimestampEncoding> TimeSeriesTableRead<E> {
         granularity: Granularity,
         series_id: &str,
         timestamp_ms: u64,
-    ) -> Result<Option<Aggregate>, StorageError> {
+    ) -> Result<Option<Aggregate>, TimeSeriesError> {
         let rounded_ts = granularity.round_down(timestamp_ms);
 
         let table = match granularity {
             Granularity::Raw => {
-                return Err(StorageError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "Cannot get aggregate for Raw granularity",
-                )))
+                return Err(TimeSeriesError::InvalidArgument(
+                    "cannot get aggregate for Raw granularity",
+                ));
             }
             Granularity::Minute => &self.minute,
             Granularity::Hour => &self.hour,
             Granularity::Day => &self.day,
         };
+        let Some(table) = table else {
+            return Ok(None);
+        };
 
-        table
-            .get((rounded_ts, series_id))
-            .map(|opt| opt.map(|guard| guard.value()))
+        Ok(table
+            .get((rounded_ts, series_id))?
+            .map(|guard| guard.value()))
     }
 
     /// Returns an iterator over aggregates in a time range.
@@ -229,37 +950,116 @@ impl<E: TimestampEncoding> TimeSeriesTableRead<E> {
         series_id: &str,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<AggregateRangeIter<'_>, StorageError> {
+    ) -> Result<AggregateRangeIter<'_>, TimeSeriesError> {
         let table = match granularity {
             Granularity::Raw => {
-                return Err(StorageError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "Cannot iterate aggregates for Raw granularity",
-                )))
+                return Err(TimeSeriesError::InvalidArgument(
+                    "cannot iterate aggregates for Raw granularity",
+                ));
             }
             Granularity::Minute => &self.minute,
             Granularity::Hour => &self.hour,
             Granularity::Day => &self.day,
         };
+        check_range(start_ms, end_ms)?;
+        let inner = match table {
+            Some(table) => Some(SeriesRange::new(
+                table,
+                series_id,
+                start_ms,
+                end_ms,
+                ReadHint::Normal,
+            )?),
+            None => None,
+        };
+        Ok(AggregateRangeIter { inner })
+    }
 
-        let start_key = (start_ms, series_id);
-        let end_key = (end_ms, series_id);
+    /// Queries a time range at a resolution suited to displaying about `max_points` points.
+    ///
+    /// The granularity is chosen with [`Granularity::for_window`]. Aggregates are returned for
+    /// every window of that granularity starting in the range, including a window that starts
+    /// before `start_ms` and overlaps it. Parts of the range not covered by an aggregate, such
+    /// as recent data that has not been downsampled yet, are filled in with raw points, so the
+    /// result has no gaps where raw data exists.
+    ///
+    /// Points are returned in timestamp order.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_id` - Series identifier to query
+    /// * `start_ms` - Start timestamp (inclusive)
+    /// * `end_ms` - End timestamp (exclusive), not before `start_ms`
+    /// * `max_points` - Target number of points; must be non-zero
+    pub fn query(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        max_points: usize,
+    ) -> Result<Vec<QueryPoint<V>>, TimeSeriesError> {
+        if max_points == 0 {
+            return Err(TimeSeriesError::InvalidArgument(
+                "max_points must be non-zero",
+            ));
+        }
+        check_range(start_ms, end_ms)?;
 
-        let iter = table.range(start_key..end_key)?;
+        let mut points = Vec::new();
+        let granularity = Granularity::for_window(end_ms.saturating_sub(start_ms), max_points);
+        if granularity == Granularity::Raw {
+            self.push_raw(series_id, start_ms, end_ms, &mut points)?;
+            return Ok(points);
+        }
 
-        Ok(AggregateRangeIter {
-            inner: iter,
-            series_id: series_id.to_string(),
-        })
+        // Raw data is needed from `covered_until` up to the next aggregate window
+        let mut covered_until = start_ms;
+        for entry in self.range_aggregates(
+            granularity,
+            series_id,
+            granularity.round_down(start_ms),
+            end_ms,
+        )? {
+            let (timestamp, aggregate) = entry?;
+            if timestamp > covered_until {
+                self.push_raw(series_id, covered_until, timestamp, &mut points)?;
+            }
+            points.push(QueryPoint::Aggregate {
+                timestamp,
+                granularity,
+                aggregate,
+            });
+            covered_until = covered_until.max(timestamp.saturating_add(granularity.duration_ms()));
+        }
+        if covered_until < end_ms {
+            self.push_raw(series_id, covered_until, end_ms, &mut points)?;
+        }
+
+        Ok(points)
+    }
+
+    /// Appends the raw points of a time range to `points`.
+    fn push_raw(
+        &self,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        points: &mut Vec<QueryPoint<V>>,
+    ) -> Result<(), TimeSeriesError> {
+        for point in self.range(series_id, start_ms, end_ms)? {
+            let (timestamp, value) = point?;
+            points.push(QueryPoint::Raw { timestamp, value });
+        }
+        Ok(())
     }
 
     /// Returns the number of raw data points stored.
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.raw.len()
+    pub fn len(&self) -> Result<u64, TimeSeriesError> {
+        Ok(self.raw.len()?)
     }
 
     /// Returns `true` if the raw table contains no data points.
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, TimeSeriesError> {
         Ok(self.len()? == 0)
     }
 
@@ -267,59 +1067,1397 @@ impl<E: TimestampEncoding> TimeSeriesTableRead<E> {
     ///
     /// This is useful for integration with external libraries that need
     /// direct access to the underlying table.
-    pub fn raw_table(&self) -> &ReadOnlyTable<(u64, &'static str), f32> {
+    pub fn raw_table(&self) -> &ReadOnlyTable<(u64, &'static str), V> {
         &self.raw
     }
 }
 
-/// Iterator over raw time series data points in a range.
-pub struct RangeIter<'a> {
-    inner: manifold::Range<'a, (u64, &'static str), f32>,
+/// Treats a table that does not exist as absent.
+fn open_optional<T>(result: Result<T, TableError>) -> Result<Option<T>, TableError> {
+    match result {
+        Ok(table) => Ok(Some(table)),
+        Err(TableError::TableDoesNotExist(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Rejects a time range whose start is after its end.
+pub(crate) fn check_range(start_ms: u64, end_ms: u64) -> Result<(), TimeSeriesError> {
+    if start_ms > end_ms {
+        return Err(TimeSeriesError::InvalidRange {
+            start: start_ms,
+            end: end_ms,
+        });
+    }
+    Ok(())
+}
+
+/// A point returned by [`TimeSeriesTableRead::query`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryPoint<V = f32> {
+    /// A raw data point.
+    Raw {
+        /// Timestamp in milliseconds.
+        timestamp: u64,
+        /// Recorded value.
+        value: V,
+    },
+    /// An aggregate over one window.
+    Aggregate {
+        /// Start of the window in milliseconds.
+        timestamp: u64,
+        /// Granularity of the window.
+        granularity: Granularity,
+        /// Aggregate of the raw points in the window.
+        aggregate: Aggregate,
+    },
+}
+
+impl<V: FixedWidthValue> QueryPoint<V> {
+    /// Returns the timestamp of the point, or the start of its window.
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            Self::Raw { timestamp, .. } | Self::Aggregate { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Returns the value of a raw point, or the average of an aggregate.
+    pub fn value(&self) -> f64 {
+        match self {
+            Self::Raw { value, .. } => value.to_f64(),
+            Self::Aggregate { aggregate, .. } => aggregate.average(),
+        }
+    }
+
+    /// Returns the granularity of the point.
+    pub fn granularity(&self) -> Granularity {
+        match self {
+            Self::Raw { .. } => Granularity::Raw,
+            Self::Aggregate { granularity, .. } => *granularity,
+        }
+    }
+}
+
+/// The rows of one series in a time range of a table, which can skip ahead.
+struct SeriesRange<'a, V: Value + 'static> {
+    table: &'a ReadOnlyTable<(u64, &'static str), V>,
+    inner: manifold::Range<'a, (u64, &'static str), V>,
     series_id: String,
+    end_ms: u64,
+    hint: ReadHint,
+    /// Smallest timestamp the next row can have.
+    next_ms: u64,
 }
 
-impl Iterator for RangeIter<'_> {
-    type Item = Result<(u64, f32), StorageError>;
+impl<'a, V: Value + 'static> SeriesRange<'a, V> {
+    fn new(
+        table: &'a ReadOnlyTable<(u64, &'static str), V>,
+        series_id: &str,
+        start_ms: u64,
+        end_ms: u64,
+        hint: ReadHint,
+    ) -> Result<Self, TimeSeriesError> {
+        check_range(start_ms, end_ms)?;
+        let inner = table
+            .range((start_ms, series_id)..(end_ms, series_id))?
+            .with_hint(hint);
+        Ok(Self {
+            table,
+            inner,
+            series_id: series_id.to_string(),
+            end_ms,
+            hint,
+            next_ms: start_ms,
+        })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Restarts the scan at `timestamp_ms`, unless the scan is already past it.
+    fn seek(&mut self, timestamp_ms: u64) -> Result<(), TimeSeriesError> {
+        if timestamp_ms <= self.next_ms {
+            return Ok(());
+        }
+        // Past the end, the scan restarts on an empty range
+        let start_ms = timestamp_ms.min(self.end_ms);
+        let series_id = self.series_id.as_str();
+        self.inner = self
+            .table
+            .range((start_ms, series_id)..(self.end_ms, series_id))?
+            .with_hint(self.hint);
+        self.next_ms = timestamp_ms;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Option<Result<(u64, AccessGuard<'a, V>), TimeSeriesError>> {
         loop {
             match self.inner.next()? {
                 Ok((key_guard, value_guard)) => {
                     let (timestamp, sid) = key_guard.value();
                     // Filter to only the requested series_id
                     if sid == self.series_id {
-                        return Some(Ok((timestamp, value_guard.value())));
+                        // Below the exclusive end, so this cannot overflow
+                        self.next_ms = timestamp + 1;
+                        return Some(Ok((timestamp, value_guard)));
                     }
                     // Continue if different series_id
                 }
-                Err(e) => return Some(Err(e)),
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+/// Iterator over raw time series data points in a range.
+///
+/// Points are returned in strictly ascending timestamp order. A series has at most one point
+/// per timestamp, since its points are keyed by timestamp, so no two points share one. Where a
+/// raw row and a packed chunk both hold a point at a timestamp, the raw point is returned.
+///
+/// The number of points is not known up front: the raw table holds every series in timestamp
+/// order, and points of other series in the range are skipped as they are read.
+pub struct RangeIter<'a, V: FixedWidthValue = f32> {
+    inner: SeriesRange<'a, V>,
+    packed: Option<PackedRange<'a, V>>,
+    /// Points read ahead from the raw table and from the chunks, to merge them in order.
+    next_loose: Option<(u64, V)>,
+    next_packed: Option<(u64, V)>,
+}
+
+impl<V: FixedWidthValue> RangeIter<'_, V> {
+    /// Skips forward to the first point at or after `timestamp_ms`.
+    ///
+    /// Does nothing if the iterator has already returned a point at or after `timestamp_ms`,
+    /// so the iterator never goes back. Seeking to or past the end of the range ends the
+    /// iteration. Resuming from a pagination token this way reuses the open table instead of
+    /// starting a new range.
+    pub fn seek(&mut self, timestamp_ms: u64) -> Result<(), TimeSeriesError> {
+        let before = |point: Option<(u64, V)>| point.is_some_and(|(ts, _)| ts < timestamp_ms);
+        if before(self.next_loose) {
+            self.next_loose = None;
+        }
+        if before(self.next_packed) {
+            self.next_packed = None;
+        }
+        self.inner.seek(timestamp_ms)?;
+        if let Some(packed) = &mut self.packed {
+            packed.seek(timestamp_ms)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: FixedWidthValue> Iterator for RangeIter<'_, V> {
+    type Item = Result<(u64, V), TimeSeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_loose.is_none() {
+            self.next_loose = match self.inner.next() {
+                Some(Ok((timestamp, value_guard))) => Some((timestamp, value_guard.value())),
+                Some(Err(err)) => return Some(Err(err)),
+                None => None,
+            };
+        }
+        if self.next_packed.is_none()
+            && let Some(packed) = &mut self.packed
+        {
+            self.next_packed = match packed.next() {
+                Some(Ok(point)) => Some(point),
+                Some(Err(err)) => return Some(Err(err)),
+                None => None,
+            };
+        }
+
+        let take_packed = match (self.next_loose, self.next_packed) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some((loose_ms, _)), Some((packed_ms, _))) => {
+                // A raw point replaces the packed point with the same timestamp
+                if packed_ms == loose_ms {
+                    self.next_packed = None;
+                }
+                packed_ms < loose_ms
             }
+        };
+        if take_packed {
+            self.next_packed.take().map(Ok)
+        } else {
+            self.next_loose.take().map(Ok)
         }
     }
 }
 
+/// Iterator over the raw data points in a range in pages, returned by
+/// [`TimeSeriesTableRead::range_batched`].
+pub struct RangeBatches<'a, V: FixedWidthValue = f32> {
+    inner: RangeIter<'a, V>,
+    batch_size: usize,
+}
+
+impl<V: FixedWidthValue> Iterator for RangeBatches<'_, V> {
+    type Item = Result<Vec<(u64, V)>, TimeSeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only preallocate up to a bound, as the batch size may be far larger than the range
+        let mut page = Vec::with_capacity(self.batch_size.min(4096));
+        while page.len() < self.batch_size {
+            match self.inner.next() {
+                Some(Ok(point)) => page.push(point),
+                Some(Err(err)) => return Some(Err(err)),
+                None => break,
+            }
+        }
+        (!page.is_empty()).then_some(Ok(page))
+    }
+}
+
 /// Iterator over aggregate data points in a range.
+///
+/// Aggregates are returned in strictly ascending timestamp order, like the points of a
+/// [`RangeIter`].
 pub struct AggregateRangeIter<'a> {
-    inner: manifold::Range<'a, (u64, &'static str), Aggregate>,
-    series_id: String,
+    /// None if the granularity has no aggregate table yet.
+    inner: Option<SeriesRange<'a, Aggregate>>,
+}
+
+impl AggregateRangeIter<'_> {
+    /// Skips forward to the first aggregate at or after `timestamp_ms`, as
+    /// [`RangeIter::seek`] does for points.
+    pub fn seek(&mut self, timestamp_ms: u64) -> Result<(), TimeSeriesError> {
+        match &mut self.inner {
+            Some(inner) => inner.seek(timestamp_ms),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Iterator for AggregateRangeIter<'_> {
-    type Item = Result<(u64, Aggregate), StorageError>;
+    type Item = Result<(u64, Aggregate), TimeSeriesError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.inner.next()? {
-                Ok((key_guard, value_guard)) => {
-                    let (timestamp, sid) = key_guard.value();
-                    // Filter to only the requested series_id
-                    if sid == self.series_id {
-                        return Some(Ok((timestamp, value_guard.value())));
-                    }
-                    // Continue if different series_id
+        Some(
+            self.inner
+                .as_mut()?
+                .next()?
+                .map(|(timestamp, value_guard)| (timestamp, value_guard.value())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{AbsoluteEncoding, DeltaEncoding};
+    use manifold::column_family::ColumnFamilyDatabase;
+    use tempfile::tempdir;
+
+    fn legacy_bytes(min: f32, max: f32, sum: f32, count: u64, last: f32) -> [u8; 24] {
+        let mut bytes = [0u8; 24];
+        bytes[0..4].copy_from_slice(&min.to_be_bytes());
+        bytes[4..8].copy_from_slice(&max.to_be_bytes());
+        bytes[8..12].copy_from_slice(&sum.to_be_bytes());
+        bytes[12..20].copy_from_slice(&count.to_be_bytes());
+        bytes[20..24].copy_from_slice(&last.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_open_upgrades_legacy_aggregates() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Tables as written before the aggregate format was extended, and minute aggregates
+        // as written before values were accumulated in f64
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let raw_def: TableDefinition<(u64, &str), f32> = TableDefinition::new("cpu_raw");
+            write_txn.open_table(raw_def).unwrap();
+            let def: TableDefinition<(u64, &str), LegacyAggregate> =
+                TableDefinition::new("cpu_day");
+            write_txn.open_table(def).unwrap();
+            let def: TableDefinition<(u64, &str), AggregateV1> = TableDefinition::new("cpu_minute");
+            let mut bytes = [0u8; 53];
+            bytes[0] = 1;
+            bytes[1..5].copy_from_slice(&0.25f32.to_be_bytes());
+            bytes[5..9].copy_from_slice(&0.75f32.to_be_bytes());
+            bytes[9..13].copy_from_slice(&1.0f32.to_be_bytes());
+            bytes[13..21].copy_from_slice(&2u64.to_be_bytes());
+            write_txn
+                .open_table(def)
+                .unwrap()
+                .insert((60_000, "server1"), AggregateV1::from_bytes(&bytes))
+                .unwrap();
+            let def: TableDefinition<(u64, &str), LegacyAggregate> =
+                TableDefinition::new("cpu_hour");
+            let mut table = write_txn.open_table(def).unwrap();
+            for (hour, last) in [(0, 4.0), (3_600_000, 7.0)] {
+                let bytes = legacy_bytes(1.0, 9.0, 20.0, 4, last);
+                table
+                    .insert((hour, "server1"), LegacyAggregate::from_bytes(&bytes))
+                    .unwrap();
+            }
+            drop(table);
+            write_txn.commit().unwrap();
+        }
+
+        // Reading requires the upgrade
+        {
+            let read_txn = cf.begin_read().unwrap();
+            assert!(matches!(
+                TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu"),
+                Err(TimeSeriesError::Table(
+                    TableError::TypeDefinitionChanged { .. }
+                ))
+            ));
+        }
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            // Each aggregate table is upgraded the first time the handle uses it
+            assert_eq!(ts.minute_table().unwrap().len().unwrap(), 1);
+            assert_eq!(ts.hour_table().unwrap().len().unwrap(), 2);
+            ts.downsample_hour_to_day("server1", 0, 86_400_000).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let hour = ts
+            .get_aggregate(Granularity::Hour, "server1", 3_600_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (hour.min, hour.max, hour.sum, hour.count),
+            (1.0, 9.0, 20.0, 4)
+        );
+        assert_eq!(
+            (hour.first_ts, hour.last_ts, hour.last),
+            (3_600_000, 3_600_000, 7.0)
+        );
+
+        let day = ts
+            .get_aggregate(Granularity::Day, "server1", 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(day.count, 8);
+        assert_eq!((day.first, day.last), (4.0, 7.0));
+        assert!(day.stddev().is_nan());
+
+        let minute = ts
+            .get_aggregate(Granularity::Minute, "server1", 60_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (minute.min, minute.max, minute.sum, minute.count),
+            (0.25, 0.75, 1.0, 2)
+        );
+    }
+
+    #[test]
+    fn test_query_selects_granularity_and_stitches_raw() {
+        const DAY: u64 = 86_400_000;
+
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Two days of raw points every 10 seconds, minute aggregates for both days and hour
+        // aggregates for the first day only
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for i in 0..2 * DAY / 10_000 {
+                ts.write(
+                    "server1",
+                    i * 10_000,
+                    f32::from(u16::try_from(i % 120).unwrap()),
+                )
+                .unwrap();
+            }
+            ts.downsample_to_minute("server1", 0, 2 * DAY).unwrap();
+            ts.downsample_minute_to_hour("server1", 0, DAY).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+
+        // One hour at 500 points is finer than minutes
+        let points = ts.query("server1", 0, 3_600_000, 500).unwrap();
+        assert_eq!(points.len(), 360);
+        assert!(points.iter().all(|p| p.granularity() == Granularity::Raw));
+
+        // One day at 500 points uses minutes, starting with the window overlapping the start
+        let points = ts.query("server1", 30_000, DAY, 500).unwrap();
+        assert_eq!(points.len(), 1440);
+        assert!(
+            points
+                .iter()
+                .all(|p| p.granularity() == Granularity::Minute)
+        );
+        assert_eq!(points[0].timestamp(), 0);
+
+        // Two days at 24 points use hours, which stop after the first day; the second day is
+        // filled in from raw data
+        let points = ts.query("server1", 0, 2 * DAY, 24).unwrap();
+        let (hours, raw): (Vec<&QueryPoint>, Vec<&QueryPoint>) = points
+            .iter()
+            .partition(|p| p.granularity() == Granularity::Hour);
+        assert_eq!(hours.len(), 24);
+        assert_eq!(raw.len(), 8640);
+        assert!(raw.iter().all(|p| p.granularity() == Granularity::Raw));
+        assert_eq!(points[23].timestamp(), DAY - 3_600_000);
+        assert_eq!(points[24].timestamp(), DAY);
+        assert!(
+            points
+                .windows(2)
+                .all(|w| w[0].timestamp() < w[1].timestamp())
+        );
+
+        // Each hour aggregate summarizes the raw points it replaces
+        let QueryPoint::Aggregate { aggregate, .. } = points[0] else {
+            panic!("expected an aggregate");
+        };
+        assert_eq!(aggregate.count, 360);
+        assert!((points[0].value() - 59.5).abs() < 1e-3);
+
+        assert!(ts.query("server1", 0, DAY, 0).is_err());
+    }
+
+    #[test]
+    fn test_write_series_batch_matches_write() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Batches out of order, overlapping each other and single writes, including duplicate
+        // timestamps within a batch
+        let groups: Vec<Vec<(u64, f32)>> = vec![
+            (0..100).map(|i| (i * 1_000, 1.0)).collect(),
+            (0..100).rev().map(|i| (i * 1_500, 2.0)).collect(),
+            vec![(500, 3.0), (250, 4.0), (500, 5.0), (99_000, 6.0)],
+        ];
+        let singles = [(1_000, 7.0), (300_000, 8.0), (50, 9.0)];
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut batched =
+                TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "batched").unwrap();
+            batched
+                .write_series_batch("server1", &groups[0], true)
+                .unwrap();
+            batched
+                .write("server1", singles[0].0, singles[0].1)
+                .unwrap();
+            batched
+                .write_series_batch("server1", &groups[1], false)
+                .unwrap();
+            batched
+                .write("server1", singles[1].0, singles[1].1)
+                .unwrap();
+            batched.write("server2", 1_000, -1.0).unwrap();
+            batched
+                .write_series_batch("server1", &groups[2], false)
+                .unwrap();
+            batched
+                .write("server1", singles[2].0, singles[2].1)
+                .unwrap();
+            drop(batched);
+
+            let mut single =
+                TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "single").unwrap();
+            let sequence = [
+                &groups[0][..],
+                &singles[..1],
+                &groups[1][..],
+                &singles[1..2],
+                &groups[2][..],
+                &singles[2..],
+            ];
+            for points in sequence {
+                for &(timestamp, value) in points {
+                    single.write("server1", timestamp, value).unwrap();
+                }
+            }
+            drop(single);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let read = |name: &str| -> Vec<(u64, f32)> {
+            TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, name)
+                .unwrap()
+                .range("server1", 0, u64::MAX)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect()
+        };
+        let batched = read("batched");
+        assert_eq!(batched, read("single"));
+        assert!(batched.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(batched[0], (0, 2.0));
+        assert_eq!(batched[1], (50, 9.0));
+        assert!(batched.contains(&(500, 5.0)));
+        assert!(batched.contains(&(1_000, 7.0)));
+        assert!(batched.contains(&(1_500, 2.0)));
+    }
+
+    #[test]
+    fn test_range_with_hint_matches_range() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Enough points for the raw table to span many leaf pages and several branches
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for series in ["server1", "server2", "server3"] {
+                let points: Vec<(u64, f32)> = (0..20_000u16)
+                    .map(|i| (u64::from(i) * 1_000, f32::from(i)))
+                    .collect();
+                ts.write_series_batch(series, &points, true).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let read = |start_ms: u64, end_ms: u64, hint: ReadHint| -> Vec<(u64, f32)> {
+            ts.range_with_hint("server2", start_ms, end_ms, hint)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect()
+        };
+        for (start_ms, end_ms) in [(0, u64::MAX), (1_234_000, 15_000_000), (5_000, 5_000)] {
+            let expected = read(start_ms, end_ms, ReadHint::Normal);
+            for hint in [
+                ReadHint::Sequential,
+                ReadHint::SequentialWindow(0),
+                ReadHint::SequentialWindow(16 * 1024),
+            ] {
+                assert_eq!(read(start_ms, end_ms, hint), expected, "{hint:?}");
+            }
+        }
+        assert_eq!(read(0, u64::MAX, ReadHint::Sequential).len(), 20_000);
+    }
+
+    #[test]
+    fn test_range_batched_pages_cover_range() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for series in ["server1", "server2"] {
+                let points: Vec<(u64, f32)> = (0..100u8)
+                    .map(|i| (u64::from(i) * 1_000, f32::from(i)))
+                    .collect();
+                ts.write_series_batch(series, &points, true).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        for (start_ms, end_ms) in [(0, u64::MAX), (10_500, 60_000), (5_000, 5_001)] {
+            let expected: Vec<(u64, f32)> = ts
+                .range("server1", start_ms, end_ms)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            for batch_size in [1, 3, 50, expected.len().max(1), usize::MAX] {
+                let pages: Vec<Vec<(u64, f32)>> = ts
+                    .range_batched("server1", start_ms, end_ms, batch_size)
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .collect();
+                let (last, full) = pages.split_last().unwrap();
+                assert!(full.iter().all(|page| page.len() == batch_size));
+                assert!(!last.is_empty() && last.len() <= batch_size);
+                assert_eq!(pages.concat(), expected, "{batch_size}");
+            }
+        }
+
+        // An empty range has no pages rather than one empty page
+        assert_eq!(
+            ts.range_batched("server1", 5_000, 5_000, 10)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(
+            ts.range_batched("server3", 0, u64::MAX, 10)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert!(matches!(
+            ts.range_batched("server1", 0, u64::MAX, 0),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_range_seek() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for series in ["server1", "server2"] {
+                let points: Vec<(u64, f32)> = (0..100u8)
+                    .map(|i| (u64::from(i) * 1_000, f32::from(i)))
+                    .collect();
+                ts.write_series_batch(series, &points, true).unwrap();
+            }
+            ts.downsample_to_minute("server1", 0, 100_000).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let mut iter = ts.range("server1", 10_000, 90_000).unwrap();
+        let next = |iter: &mut RangeIter<'_>| iter.next().unwrap().unwrap().0;
+        assert_eq!(next(&mut iter), 10_000);
+        assert_eq!(next(&mut iter), 11_000);
+
+        // Forward, between points
+        iter.seek(40_500).unwrap();
+        assert_eq!(next(&mut iter), 41_000);
+
+        // Backward, or to the point just returned, does not move
+        iter.seek(20_000).unwrap();
+        iter.seek(41_000).unwrap();
+        assert_eq!(next(&mut iter), 42_000);
+        iter.seek(43_000).unwrap();
+        assert_eq!(next(&mut iter), 43_000);
+
+        // Timestamps are strictly ascending after a seek
+        let rest: Vec<u64> = iter.by_ref().map(|point| point.unwrap().0).collect();
+        assert_eq!(rest, (44..90).map(|i| i * 1_000).collect::<Vec<u64>>());
+
+        // Past the end of the range, the iterator stays finished
+        let mut iter = ts.range("server1", 0, 50_000).unwrap();
+        iter.seek(60_000).unwrap();
+        assert!(iter.next().is_none());
+        iter.seek(1_000).unwrap();
+        assert!(iter.next().is_none());
+
+        let mut aggregates = ts
+            .range_aggregates(Granularity::Minute, "server1", 0, u64::MAX)
+            .unwrap();
+        aggregates.seek(1).unwrap();
+        let (timestamp, aggregate) = aggregates.next().unwrap().unwrap();
+        assert_eq!((timestamp, aggregate.count), (60_000, 40));
+        assert!(aggregates.next().is_none());
+    }
+
+    #[test]
+    fn test_valid_range_boundaries() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        assert_eq!(ts.valid_range(), None);
+        ts.write("s", 0, 1.0).unwrap();
+
+        ts.set_valid_range(1_000, 2_000);
+        assert_eq!(ts.valid_range(), Some(1_000..=2_000));
+        ts.write("s", 1_000, 2.0).unwrap();
+        ts.write("s", 2_000, 3.0).unwrap();
+        for timestamp in [0, 999, 2_001, u64::MAX] {
+            match ts.write("s", timestamp, 4.0) {
+                Err(TimeSeriesError::OutOfRange {
+                    points,
+                    min_ts,
+                    max_ts,
+                }) => {
+                    assert_eq!(points, vec![(0, timestamp)]);
+                    assert_eq!((min_ts, max_ts), (1_000, 2_000));
                 }
-                Err(e) => return Some(Err(e)),
+                other => panic!("expected OutOfRange, got {other:?}"),
             }
         }
+
+        ts.clear_valid_range();
+        ts.write("s", 5_000, 5.0).unwrap();
+        drop(ts);
+        write_txn.commit().unwrap();
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let points: Vec<_> = ts
+            .range("s", 0, u64::MAX)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            points,
+            vec![(0, 1.0), (1_000, 2.0), (2_000, 3.0), (5_000, 5.0)]
+        );
+    }
+
+    #[test]
+    fn test_valid_range_rejects_whole_batch() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Timestamps sent in seconds instead of milliseconds
+        let now_ms = 1_700_000_000_000;
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        ts.set_valid_range(now_ms - 86_400_000, now_ms + 3_600_000);
+
+        let batch = [
+            ("a", now_ms, 1.0),
+            ("b", now_ms + 1_000, 2.0),
+            ("a", now_ms / 1_000, 3.0),
+            ("b", now_ms + 2_000, 4.0),
+        ];
+        let err = ts.write_batch(&batch, false).unwrap_err();
+        assert!(matches!(
+            &err,
+            TimeSeriesError::OutOfRange { points, .. } if points == &[(2, now_ms / 1_000)]
+        ));
+        assert!(err.to_string().contains("#2 at 1700000000"), "{err}");
+
+        let series = [(now_ms + 5_000, 1.0), (now_ms / 1_000, 2.0), (0, 3.0)];
+        let err = ts.write_series_batch("a", &series, false).unwrap_err();
+        assert!(matches!(
+            err,
+            TimeSeriesError::OutOfRange { points, .. } if points == [(1, now_ms / 1_000), (2, 0)]
+        ));
+        assert!(ts.is_empty().unwrap());
+
+        ts.write_batch(&[batch[0], batch[1], batch[3]], false)
+            .unwrap();
+        ts.write_series_batch("a", &series[..1], false).unwrap();
+        assert_eq!(ts.len().unwrap(), 4);
+        drop(ts);
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_series_id_length_limit() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let at_limit = "s".repeat(DEFAULT_MAX_SERIES_ID_LEN);
+        let over_limit = "s".repeat(DEFAULT_MAX_SERIES_ID_LEN + 1);
+        let too_long = |result: Result<_, TimeSeriesError>| {
+            assert!(matches!(
+                result,
+                Err(TimeSeriesError::SeriesIdTooLong {
+                    len,
+                    max: DEFAULT_MAX_SERIES_ID_LEN,
+                }) if len == DEFAULT_MAX_SERIES_ID_LEN + 1
+            ));
+        };
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        assert_eq!(ts.max_series_id_len(), DEFAULT_MAX_SERIES_ID_LEN);
+        ts.write(&at_limit, 1_000, 1.0).unwrap();
+        ts.write_batch(&[(at_limit.as_str(), 2_000, 2.0)], true)
+            .unwrap();
+        ts.write_series_batch(&at_limit, &[(3_000, 3.0)], true)
+            .unwrap();
+        ts.set_labels(&at_limit, &[("host", "a")]).unwrap();
+        ts.register_series(&at_limit, None, None).unwrap();
+
+        too_long(ts.write(&over_limit, 1_000, 1.0));
+        too_long(ts.write_batch(
+            &[("a", 1_000, 1.0), (over_limit.as_str(), 2_000, 2.0)],
+            true,
+        ));
+        too_long(ts.write_series_batch(&over_limit, &[(1_000, 1.0)], true));
+        too_long(ts.set_labels(&over_limit, &[("host", "a")]));
+        too_long(ts.register_series(&over_limit, None, None).map(|_| ()));
+        assert_eq!(ts.len().unwrap(), 3);
+        drop(ts);
+
+        let options = TimeSeriesOptions {
+            max_series_id_len: 3,
+            ..TimeSeriesOptions::default()
+        };
+        let mut ts =
+            TimeSeriesTable::<AbsoluteEncoding>::open_with_options(&write_txn, "cpu", options)
+                .unwrap();
+        ts.write("abc", 1_000, 1.0).unwrap();
+        assert!(matches!(
+            ts.write("abcd", 1_000, 1.0),
+            Err(TimeSeriesError::SeriesIdTooLong { len: 4, max: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_policies() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // Stored value of "a" at 1000 and 2000, "b" at 2000 and "c" at 1 for each policy
+        let expected = [
+            (DuplicatePolicy::Overwrite, [2.0, 2.0, 5.0, 2.0]),
+            (DuplicatePolicy::Ignore, [1.0, 1.0, 5.0, 1.0]),
+            (DuplicatePolicy::Sum, [3.0, 3.0, 5.0, 3.0]),
+        ];
+        for (policy, values) in expected {
+            let write_txn = cf.begin_write().unwrap();
+            let options = TimeSeriesOptions {
+                duplicate_policy: policy,
+                ..TimeSeriesOptions::default()
+            };
+            let mut ts =
+                TimeSeriesTable::<AbsoluteEncoding>::open_with_options(&write_txn, "cpu", options)
+                    .unwrap();
+            assert_eq!(ts.duplicate_policy(), policy);
+
+            ts.write("a", 1_000, 1.0).unwrap();
+            // Includes a duplicate within the batch and one of a stored point
+            ts.write_batch(
+                &[("a", 2_000, 1.0), ("b", 2_000, 5.0), ("a", 2_000, 2.0)],
+                false,
+            )
+            .unwrap();
+            ts.write("a", 1_000, 2.0).unwrap();
+            ts.write_series_batch("c", &[(1, 1.0), (1, 2.0)], false)
+                .unwrap();
+
+            let stored: Vec<f32> = [(1_000, "a"), (2_000, "a"), (2_000, "b"), (1, "c")]
+                .into_iter()
+                .map(|key| ts.raw.get(key).unwrap().unwrap().value())
+                .collect();
+            assert_eq!(stored, values, "{policy:?}");
+            assert_eq!(ts.len().unwrap(), 4);
+            drop(ts);
+            // Each policy starts from an empty table
+            drop(write_txn);
+        }
+
+        let write_txn = cf.begin_write().unwrap();
+        let options = TimeSeriesOptions {
+            duplicate_policy: DuplicatePolicy::Error,
+            ..TimeSeriesOptions::default()
+        };
+        let mut ts =
+            TimeSeriesTable::<AbsoluteEncoding>::open_with_options(&write_txn, "cpu", options)
+                .unwrap();
+        ts.write("a", 1_000, 1.0).unwrap();
+
+        let err = ts.write("a", 1_000, 2.0).unwrap_err();
+        assert!(matches!(
+            &err,
+            TimeSeriesError::DuplicateTimestamp { series, timestamp: 1_000 } if series == "a"
+        ));
+        assert!(err.to_string().contains("'a'"), "{err}");
+
+        // A duplicate within the batch rejects the whole batch
+        assert!(matches!(
+            ts.write_batch(&[("b", 2_000, 1.0), ("a", 2_000, 2.0), ("b", 2_000, 3.0)], false),
+            Err(TimeSeriesError::DuplicateTimestamp { series, timestamp: 2_000 }) if series == "b"
+        ));
+        assert!(matches!(
+            ts.write_series_batch("c", &[(5, 1.0), (1, 1.0), (5, 2.0)], false),
+            Err(TimeSeriesError::DuplicateTimestamp { series, timestamp: 5 }) if series == "c"
+        ));
+        assert!(matches!(
+            ts.write_batch(&[("b", 2_000, 1.0), ("a", 1_000, 2.0)], true),
+            Err(TimeSeriesError::DuplicateTimestamp { series, timestamp: 1_000 }) if series == "a"
+        ));
+        assert_eq!(ts.len().unwrap(), 1);
+        assert!((ts.raw.get((1_000, "a")).unwrap().unwrap().value() - 1.0).abs() < f32::EPSILON);
+
+        ts.write_batch(&[("b", 2_000, 1.0), ("a", 2_000, 2.0)], false)
+            .unwrap();
+        assert_eq!(ts.len().unwrap(), 3);
+    }
+
+    fn non_monotonic(err: TimeSeriesError) -> (String, u64, u64) {
+        match err {
+            TimeSeriesError::Encoding(EncodingError::NonMonotonicTimestamp {
+                series,
+                last,
+                attempted,
+            }) => (series, last, attempted),
+            other => panic!("expected NonMonotonicTimestamp, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_delta_encoding_rejects_out_of_order_writes() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<DeltaEncoding>::open(&write_txn, "cpu").unwrap();
+        ts.write("a", 2_000, 1.0).unwrap();
+        // Same timestamp overwrites, and other series are independent
+        ts.write("a", 2_000, 2.0).unwrap();
+        ts.write("b", 1_000, 3.0).unwrap();
+
+        let err = ts.write("a", 1_999, 4.0).unwrap_err();
+        assert_eq!(non_monotonic(err), ("a".to_string(), 2_000, 1_999));
+
+        // Each series must also be in order within a batch
+        ts.write_batch(
+            &[("a", 3_000, 5.0), ("b", 1_500, 6.0), ("a", 4_000, 7.0)],
+            false,
+        )
+        .unwrap();
+        let err = ts
+            .write_batch(
+                &[("b", 1_600, 8.0), ("a", 5_000, 9.0), ("b", 1_550, 10.0)],
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(non_monotonic(err), ("b".to_string(), 1_600, 1_550));
+        let err = ts
+            .write_series_batch("b", &[(1_700, 11.0), (1_400, 12.0)], false)
+            .unwrap_err();
+        assert_eq!(non_monotonic(err), ("b".to_string(), 1_700, 1_400));
+        let err = ts
+            .write_series_batch("b", &[(1_450, 13.0)], true)
+            .unwrap_err();
+        assert_eq!(non_monotonic(err), ("b".to_string(), 1_500, 1_450));
+        assert_eq!(ts.len().unwrap(), 5);
+
+        // Encodings with random access accept any order
+        let mut absolute = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "mem").unwrap();
+        absolute.write("a", 2_000, 1.0).unwrap();
+        absolute.write("a", 1_000, 2.0).unwrap();
+    }
+
+    #[test]
+    fn test_delta_encoding_checks_stored_points() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<DeltaEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.write_batch(
+                &[("a", 1_000, 1.0), ("b", 5_000, 2.0), ("a", 2_000, 3.0)],
+                false,
+            )
+            .unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<DeltaEncoding>::open(&write_txn, "cpu").unwrap();
+        // The first point of the batch is older than the latest stored point of its series,
+        // even though a later point of another series is stored
+        let err = ts
+            .write_batch(&[("a", 1_500, 4.0), ("a", 3_000, 5.0)], false)
+            .unwrap_err();
+        assert_eq!(non_monotonic(err), ("a".to_string(), 2_000, 1_500));
+        ts.write("a", 2_500, 6.0).unwrap();
+        ts.write("c", 100, 7.0).unwrap();
+        assert_eq!(ts.len().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_delta_encoding_allow_out_of_order() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        let write_txn = cf.begin_write().unwrap();
+        let options = TimeSeriesOptions {
+            allow_out_of_order: true,
+            ..TimeSeriesOptions::default()
+        };
+        let mut ts =
+            TimeSeriesTable::<DeltaEncoding>::open_with_options(&write_txn, "cpu", options)
+                .unwrap();
+        ts.write("a", 2_000, 1.0).unwrap();
+        ts.write("a", 1_000, 2.0).unwrap();
+        ts.write_batch(&[("a", 3_000, 3.0), ("a", 1_500, 4.0)], false)
+            .unwrap();
+        drop(ts);
+        write_txn.commit().unwrap();
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<DeltaEncoding>::open(&read_txn, "cpu").unwrap();
+        let timestamps: Vec<u64> = ts
+            .range("a", 0, 10_000)
+            .unwrap()
+            .map(|point| point.unwrap().0)
+            .collect();
+        assert_eq!(timestamps, [1_000, 1_500, 2_000, 3_000]);
+    }
+
+    #[test]
+    fn test_errors_identify_failure() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let read_txn = cf.begin_read().unwrap();
+            assert!(matches!(
+                TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "missing"),
+                Err(TimeSeriesError::Table(TableError::TableDoesNotExist(_)))
+            ));
+        }
+
+        let write_txn = cf.begin_write().unwrap();
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        ts.write("a", 1_000, 1.0).unwrap();
+        assert!(matches!(
+            ts.downsample_to_minute("a", 2_000, 1_000),
+            Err(TimeSeriesError::InvalidRange {
+                start: 2_000,
+                end: 1_000
+            })
+        ));
+        drop(ts);
+        write_txn.commit().unwrap();
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        assert!(matches!(
+            ts.range("a", 2_000, 1_000),
+            Err(TimeSeriesError::InvalidRange {
+                start: 2_000,
+                end: 1_000
+            })
+        ));
+        assert!(matches!(
+            ts.query("a", 2_000, 1_000, 10),
+            Err(TimeSeriesError::InvalidRange { .. })
+        ));
+        assert!(matches!(
+            ts.range_aggregates(Granularity::Minute, "a", 2_000, 1_000),
+            Err(TimeSeriesError::InvalidRange { .. })
+        ));
+        assert!(matches!(
+            ts.get_aggregate(Granularity::Raw, "a", 1_000),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            ts.query("a", 0, 1_000, 0),
+            Err(TimeSeriesError::InvalidArgument(_))
+        ));
+        assert_eq!(ts.range("a", 1_000, 1_000).unwrap().count(), 0);
+
+        let err: TimeSeriesError = AbsoluteEncoding::decode(&[0; 3]).unwrap_err().into();
+        assert!(matches!(err, TimeSeriesError::Encoding(_)));
+    }
+
+    #[test]
+    fn test_drop_granularity() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+        let table_names = || -> Vec<String> {
+            let read_txn = cf.begin_read().unwrap();
+            read_txn
+                .list_tables()
+                .unwrap()
+                .map(|handle| handle.name().to_string())
+                .collect()
+        };
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for i in 0..180u16 {
+                ts.write("a", u64::from(i) * 1_000, f32::from(i)).unwrap();
+            }
+            ts.downsample_incremental("a", Granularity::Minute).unwrap();
+            ts.downsample_incremental("a", Granularity::Hour).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+        // Tables are only created once used, so there is no day table yet
+        let mut names = table_names();
+        names.sort();
+        assert_eq!(
+            names,
+            ["cpu_hour", "cpu_minute", "cpu_raw", "cpu_watermarks"]
+        );
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            assert!(
+                TimeSeriesTable::<AbsoluteEncoding>::drop_granularity(
+                    &write_txn,
+                    "cpu",
+                    Granularity::Minute
+                )
+                .unwrap()
+            );
+            assert!(
+                !TimeSeriesTable::<AbsoluteEncoding>::drop_granularity(
+                    &write_txn,
+                    "cpu",
+                    Granularity::Minute
+                )
+                .unwrap()
+            );
+            assert!(matches!(
+                TimeSeriesTable::<AbsoluteEncoding>::drop_granularity(
+                    &write_txn,
+                    "cpu",
+                    Granularity::Raw
+                ),
+                Err(TimeSeriesError::InvalidArgument(_))
+            ));
+            write_txn.commit().unwrap();
+        }
+        assert!(!table_names().contains(&"cpu_minute".to_string()));
+
+        // The raw data is untouched, and the dropped granularity reads as empty until it is
+        // written again
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            assert_eq!(ts.watermark("a", Granularity::Minute).unwrap(), None);
+            assert!(ts.watermark("a", Granularity::Hour).unwrap().is_some());
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        assert_eq!(ts.range("a", 0, u64::MAX).unwrap().count(), 180);
+        assert_eq!(
+            ts.range_aggregates(Granularity::Minute, "a", 0, u64::MAX)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(
+            ts.range_aggregates(Granularity::Hour, "a", 0, u64::MAX)
+                .unwrap()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_raw_writes_only_create_raw_table() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        for i in 0..3u16 {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.write("a", u64::from(i) * 30_000, f32::from(i)).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let names: Vec<String> = read_txn
+            .list_tables()
+            .unwrap()
+            .map(|handle| handle.name().to_string())
+            .collect();
+        assert_eq!(names, ["cpu_raw"]);
+
+        // Tables that were never created read as empty
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        assert_eq!(ts.range("a", 0, u64::MAX).unwrap().count(), 3);
+        assert_eq!(ts.get_aggregate(Granularity::Minute, "a", 0).unwrap(), None);
+        assert_eq!(
+            ts.range_aggregates(Granularity::Hour, "a", 0, u64::MAX)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(ts.query("a", 0, 90_000, 1).unwrap().len(), 3);
+        assert!(ts.labels("a").unwrap().is_empty());
+        drop(ts);
+        drop(read_txn);
+
+        // Downsampling creates the tables it uses
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.downsample_to_minute("a", 0, 90_000).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+        let read_txn = cf.begin_read().unwrap();
+        let mut names: Vec<String> = read_txn
+            .list_tables()
+            .unwrap()
+            .map(|handle| handle.name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["cpu_minute", "cpu_raw"]);
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        assert_eq!(
+            ts.get_aggregate(Granularity::Minute, "a", 0)
+                .unwrap()
+                .unwrap()
+                .count,
+            2
+        );
+    }
+
+    #[test]
+    fn test_f64_and_i64_values_keep_precision_near_2_pow_53() {
+        // Consecutive integers just below 2^53, where f64 still represents every integer
+        const BASE: i64 = (1 << 53) - 8;
+
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+        let points: Vec<(u64, i64)> = (0..6u16)
+            .map(|i| (u64::from(i) * 1_000, BASE + 1 + i64::from(i)))
+            .collect();
+        let as_f64 = |value: i64| -> f64 { FixedWidthValue::to_f64(value) };
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut counters =
+                TimeSeriesTable::<AbsoluteEncoding, i64>::open(&write_txn, "counters").unwrap();
+            counters.write_series_batch("a", &points, true).unwrap();
+            counters.downsample_to_minute("a", 0, 60_000).unwrap();
+            drop(counters);
+
+            let mut gauges =
+                TimeSeriesTable::<AbsoluteEncoding, f64>::open(&write_txn, "gauges").unwrap();
+            for &(timestamp, value) in &points {
+                gauges.write("a", timestamp, as_f64(value)).unwrap();
+            }
+            gauges.downsample_to_minute("a", 0, 60_000).unwrap();
+            drop(gauges);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let counters =
+            TimeSeriesTableRead::<AbsoluteEncoding, i64>::open(&read_txn, "counters").unwrap();
+        let read: Vec<(u64, i64)> = counters
+            .range("a", 0, 60_000)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(read, points);
+
+        let gauges =
+            TimeSeriesTableRead::<AbsoluteEncoding, f64>::open(&read_txn, "gauges").unwrap();
+        let read: Vec<(u64, f64)> = gauges
+            .range("a", 0, 60_000)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let expected: Vec<(u64, f64)> = points.iter().map(|&(t, v)| (t, as_f64(v))).collect();
+        assert_eq!(read, expected);
+
+        // Aggregates keep every value exact, whatever the value type
+        for ts_aggregate in [
+            counters.get_aggregate(Granularity::Minute, "a", 0),
+            gauges.get_aggregate(Granularity::Minute, "a", 0),
+        ] {
+            let aggregate = ts_aggregate.unwrap().unwrap();
+            assert_eq!(aggregate.count, 6);
+            assert_eq!(
+                (aggregate.min, aggregate.max),
+                (as_f64(BASE + 1), as_f64(BASE + 6))
+            );
+            assert_eq!(
+                (aggregate.first, aggregate.last),
+                (as_f64(BASE + 1), as_f64(BASE + 6))
+            );
+        }
+    }
+
+    #[test]
+    fn test_f32_values_lose_precision_near_2_pow_53() {
+        const BASE: i64 = (1 << 53) - 8;
+
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for i in 0..6 {
+                let value = f32::from_f64(FixedWidthValue::to_f64(BASE + 1 + i));
+                ts.write("a", u64::try_from(i).unwrap() * 1_000, value)
+                    .unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        // f32 has a 24-bit significand, so every value rounds to 2^53
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        let values: HashSet<u64> = ts
+            .range("a", 0, 60_000)
+            .unwrap()
+            .map(|point| f64::from(point.unwrap().1).to_bits())
+            .collect();
+        assert_eq!(values, HashSet::from([9_007_199_254_740_992f64.to_bits()]));
+    }
+
+    #[test]
+    fn test_open_rejects_other_value_type() {
+        let dir = tempdir().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            ts.write("a", 1_000, 1.0).unwrap();
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let mismatch = |err| match err {
+            TimeSeriesError::ValueTypeMismatch {
+                table,
+                stored,
+                requested,
+            } => (table, stored, requested),
+            err => panic!("unexpected error: {err}"),
+        };
+        let expected = ("cpu".to_string(), "f32".to_string(), "f64".to_string());
+
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let Err(err) = TimeSeriesTable::<AbsoluteEncoding, f64>::open(&write_txn, "cpu") else {
+                panic!("opened f32 time series as f64");
+            };
+            assert_eq!(mismatch(err), expected);
+            // The value type is not changed by the failed open
+            TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let Err(err) = TimeSeriesTableRead::<AbsoluteEncoding, f64>::open(&read_txn, "cpu") else {
+            panic!("opened f32 time series as f64");
+        };
+        assert_eq!(mismatch(err), expected);
+        assert!(matches!(
+            TimeSeriesTableRead::<AbsoluteEncoding, i64>::open(&read_txn, "cpu"),
+            Err(TimeSeriesError::ValueTypeMismatch { .. })
+        ));
     }
 }
@@ -0,0 +1,72 @@
+//! Value types of raw data points.
+//!
+//! A time series is generic over the type of the values in its raw table, which defaults to
+//! `f32`. [`FixedWidthValue`] is implemented for `f32`, `f64` and `i64`. Aggregates are
+//! accumulated in `f64` whatever the value type, so counters and other integers up to 2^53
+//! stay exact through downsampling with `f64` or `i64` values, where `f32` rounds every value
+//! above 2^24.
+//!
+//! The value type is recorded with the raw table when the time series is created, and opening
+//! it with another value type fails with
+//! [`TimeSeriesError::ValueTypeMismatch`](crate::TimeSeriesError::ValueTypeMismatch).
+
+use manifold::Value;
+use std::fmt::Debug;
+use std::ops::Add;
+
+/// A fixed-width number usable as the value of raw data points.
+///
+/// Values are added with [`Add`] for [`DuplicatePolicy::Sum`](crate::DuplicatePolicy::Sum),
+/// and converted to `f64` for aggregation.
+pub trait FixedWidthValue:
+    for<'a> Value<SelfType<'a> = Self> + Copy + PartialEq + Add<Output = Self> + Debug + 'static
+{
+    /// The value zero.
+    const ZERO: Self;
+
+    /// Converts the value to `f64`.
+    fn to_f64(self) -> f64;
+
+    /// Converts an `f64`, such as an interpolated value, to the nearest value of this type.
+    fn from_f64(value: f64) -> Self;
+}
+
+impl FixedWidthValue for f32 {
+    const ZERO: Self = 0.0;
+
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl FixedWidthValue for f64 {
+    const ZERO: Self = 0.0;
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+impl FixedWidthValue for i64 {
+    const ZERO: Self = 0;
+
+    #[allow(clippy::cast_precision_loss)]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    /// Rounds to the nearest integer, saturating at the bounds of `i64`.
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_f64(value: f64) -> Self {
+        value.round() as i64
+    }
+}
@@ -0,0 +1,66 @@
+//! WASM bindings for reading time series tables.
+//!
+//! [`WasmTimeSeries`] reads a time series table in a column family opened through
+//! [`WasmDatabase`](manifold::wasm::WasmDatabase), and returns each range as one typed array
+//! instead of one JS object per point.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // In a Web Worker, with `cf` a WasmColumnFamily
+//! const ts = new WasmTimeSeries(cf, "cpu");
+//! const points = ts.readRange("server1", start, end);
+//! for (let i = 0; i < points.length; i += 2) {
+//!     console.log(points[i], points[i + 1]);
+//! }
+//! ```
+
+use manifold::column_family::ColumnFamily;
+use manifold::wasm::WasmColumnFamily;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn error(s: &str);
+}
+
+/// WASM-specific wrapper for reading a time series table of `f32` values
+#[wasm_bindgen]
+pub struct WasmTimeSeries {
+    cf: ColumnFamily,
+    name: String,
+}
+
+#[wasm_bindgen]
+impl WasmTimeSeries {
+    /// Reads the table `name` in the column family `cf`
+    #[wasm_bindgen(constructor)]
+    pub fn new(cf: &WasmColumnFamily, name: String) -> WasmTimeSeries {
+        WasmTimeSeries {
+            cf: cf.column_family().clone(),
+            name,
+        }
+    }
+
+    /// Reads the raw points of a series from `start_ms` (inclusive) to `end_ms` (exclusive)
+    ///
+    /// Returns a Float64Array of interleaved `[timestamp, value, timestamp, value, ...]`
+    /// pairs in timestamp order. Timestamps above 2^53 lose precision.
+    #[wasm_bindgen(js_name = readRange)]
+    pub fn read_range(
+        &self,
+        series_id: String,
+        start_ms: u64,
+        end_ms: u64,
+    ) -> Result<js_sys::Float64Array, JsValue> {
+        let interleaved = crate::wasm_ranges::read_range_interleaved(
+            &self.cf, &self.name, &series_id, start_ms, end_ms,
+        )
+        .map_err(|e| {
+            error(&format!("readRange error: {}", e));
+            JsValue::from_str(&format!("Failed to read range: {}", e))
+        })?;
+        Ok(js_sys::Float64Array::from(interleaved.as_slice()))
+    }
+}
@@ -0,0 +1,88 @@
+//! Reads behind the methods of [`WasmTimeSeries`](crate::wasm::WasmTimeSeries).
+//!
+//! These functions are plain Rust and build on every target, which lets native tests cover the
+//! same code the WASM bindings call.
+
+use crate::encoding::AbsoluteEncoding;
+use crate::error::TimeSeriesError;
+use crate::timeseries::TimeSeriesTableRead;
+use manifold::column_family::ColumnFamily;
+
+/// Number of points read from the table per page.
+const PAGE_SIZE: usize = 4096;
+
+/// Reads the raw points of `series_id` in `start_ms..end_ms` from the table `name`, as
+/// interleaved `[timestamp, value, timestamp, value, ...]` pairs.
+///
+/// Timestamps above 2^53 lose precision as `f64`, which is the precision of a JS number.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn read_range_interleaved(
+    cf: &ColumnFamily,
+    name: &str,
+    series_id: &str,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<Vec<f64>, TimeSeriesError> {
+    let txn = cf.begin_read()?;
+    // The timestamp encoding only affects writes, so any encoding reads the table
+    let table = TimeSeriesTableRead::<AbsoluteEncoding>::open(&txn, name)?;
+    let mut interleaved = Vec::new();
+    for page in table.range_batched(series_id, start_ms, end_ms, PAGE_SIZE)? {
+        let page = page?;
+        interleaved.reserve(2 * page.len());
+        for (timestamp, value) in page {
+            interleaved.push(timestamp as f64);
+            interleaved.push(f64::from(value));
+        }
+    }
+    Ok(interleaved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeseries::TimeSeriesTable;
+    use manifold::column_family::ColumnFamilyDatabase;
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_read_range_interleaved_matches_range() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("metrics").unwrap();
+
+        // More points than fit in one page, in two series
+        {
+            let write_txn = cf.begin_write().unwrap();
+            let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open(&write_txn, "cpu").unwrap();
+            for series in ["server1", "server2"] {
+                let points: Vec<(u64, f32)> = (0..10_000u16)
+                    .map(|i| (u64::from(i) * 1_000, f32::from(i) * 0.5))
+                    .collect();
+                ts.write_series_batch(series, &points, true).unwrap();
+            }
+            drop(ts);
+            write_txn.commit().unwrap();
+        }
+
+        let read_txn = cf.begin_read().unwrap();
+        let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "cpu").unwrap();
+        for (start_ms, end_ms) in [(0, u64::MAX), (1_500, 5_000_000), (7_000, 7_000)] {
+            let expected: Vec<f64> = ts
+                .range("server1", start_ms, end_ms)
+                .unwrap()
+                .flat_map(|point| {
+                    let (timestamp, value) = point.unwrap();
+                    [timestamp as f64, f64::from(value)]
+                })
+                .collect();
+            let interleaved =
+                read_range_interleaved(&cf, "cpu", "server1", start_ms, end_ms).unwrap();
+            assert_eq!(interleaved, expected);
+        }
+        let interleaved = read_range_interleaved(&cf, "cpu", "server1", 0, u64::MAX).unwrap();
+        assert_eq!(interleaved.len(), 20_000);
+        assert_eq!(interleaved[2 * 9_999..], [9_999_000.0, 4_999.5]);
+
+        assert!(read_range_interleaved(&cf, "missing", "server1", 0, u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Read compatibility with time series written by earlier versions
+//!
+//! `tests/golden/` holds a database written by `write_golden` at each released version, named
+//! after that version. Every golden file must pass `check_golden` with the current code. See
+//! `docs/on_disk_format.md` at the root of the repository for the table formats and for when
+//! to add a golden file.
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_timeseries::{
+    AbsoluteEncoding, Aggregate, DeltaEncoding, Granularity, LabelMatcher, RegistryPolicy,
+    TimeSeriesTable, TimeSeriesTableRead,
+};
+use std::path::Path;
+
+#[path = "../../../tests/support/golden.rs"]
+mod golden;
+
+const GOLDEN_PREFIX: &str = "timeseries-v";
+
+/// Start of the fixture, on a minute boundary
+const T0: u64 = 1_700_000_040_000;
+
+/// Writes the fixture to `path`: `f32` series with aggregates, labels, packed chunks and
+/// registered series, an `f64` series with delta encoding, and `i64` extremes
+///
+/// Must not change once a golden file has been written with it.
+fn write_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    // Grows from 64 KB as it is written, so the golden file stays small
+    let cf = db.create_column_family("metrics", Some(64 * 1024)).unwrap();
+
+    let write_txn = cf.begin_write().unwrap();
+    {
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding>::open_with_registry(
+            &write_txn,
+            "abs",
+            RegistryPolicy::Strict,
+        )
+        .unwrap();
+        ts.register_series("cpu", Some("percent"), Some("CPU usage"))
+            .unwrap();
+        ts.register_series("mem", None, None).unwrap();
+
+        let points: Vec<(&str, u64, f32)> = (0..12u16)
+            .map(|i| ("cpu", T0 + u64::from(i) * 10_000, f32::from(i)))
+            .collect();
+        ts.write_batch(&points, true).unwrap();
+        for i in 0..3u16 {
+            ts.write("mem", T0 + u64::from(i) * 1000, f32::from(i) + 0.5)
+                .unwrap();
+        }
+
+        ts.downsample_to_minute("cpu", T0, T0 + 120_000).unwrap();
+        ts.set_labels("cpu", &[("host", "a"), ("metric", "cpu")])
+            .unwrap();
+        ts.set_labels("mem", &[("host", "a"), ("metric", "mem")])
+            .unwrap();
+        assert_eq!(ts.compact_range("mem", T0, T0 + 60_000, 2).unwrap(), 3);
+    }
+    {
+        let mut ts = TimeSeriesTable::<DeltaEncoding, f64>::open(&write_txn, "delta").unwrap();
+        for i in 0..5u32 {
+            ts.write("temp", T0 + u64::from(i) * 1000, -1.5 + f64::from(i) * 0.25)
+                .unwrap();
+        }
+    }
+    {
+        let mut ts = TimeSeriesTable::<AbsoluteEncoding, i64>::open(&write_txn, "counts").unwrap();
+        ts.write("requests", T0, i64::MIN).unwrap();
+        ts.write("requests", T0 + 1, 0).unwrap();
+        ts.write("requests", T0 + 2, i64::MAX).unwrap();
+    }
+    write_txn.commit().unwrap();
+}
+
+/// Asserts that the database at `path` holds exactly what `write_golden` wrote
+fn check_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let cf = db.column_family("metrics").unwrap();
+    let read_txn = cf.begin_read().unwrap();
+
+    let ts = TimeSeriesTableRead::<AbsoluteEncoding>::open(&read_txn, "abs").unwrap();
+    assert_eq!(ts.len().unwrap(), 12);
+    let cpu: Vec<(u64, f32)> = ts
+        .range("cpu", T0, T0 + 120_000)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let expected: Vec<(u64, f32)> = (0..12u16)
+        .map(|i| (T0 + u64::from(i) * 10_000, f32::from(i)))
+        .collect();
+    assert_eq!(cpu, expected);
+
+    // Packed into two chunks, so only readable through the chunks table
+    let mem: Vec<(u64, f32)> = ts
+        .range("mem", T0, T0 + 60_000)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(mem, [(T0, 0.5), (T0 + 1000, 1.5), (T0 + 2000, 2.5)]);
+    assert_eq!(ts.get("mem", T0 + 1000).unwrap(), Some(1.5));
+
+    assert_eq!(
+        ts.get_aggregate(Granularity::Minute, "cpu", T0).unwrap(),
+        Some(Aggregate {
+            min: 0.0,
+            max: 5.0,
+            sum: 15.0,
+            count: 6,
+            first: 0.0,
+            last: 5.0,
+            sum_sq: 55.0,
+            first_ts: T0,
+            last_ts: T0 + 50_000,
+        })
+    );
+    assert_eq!(
+        ts.get_aggregate(Granularity::Minute, "cpu", T0 + 60_000)
+            .unwrap(),
+        Some(Aggregate {
+            min: 6.0,
+            max: 11.0,
+            sum: 51.0,
+            count: 6,
+            first: 6.0,
+            last: 11.0,
+            sum_sq: 451.0,
+            first_ts: T0 + 60_000,
+            last_ts: T0 + 110_000,
+        })
+    );
+    assert!(
+        ts.get_aggregate(Granularity::Hour, "cpu", T0)
+            .unwrap()
+            .is_none()
+    );
+
+    assert_eq!(
+        ts.labels("cpu").unwrap(),
+        [
+            ("host".to_string(), "a".to_string()),
+            ("metric".to_string(), "cpu".to_string()),
+        ]
+    );
+    assert_eq!(
+        ts.select_series(&[LabelMatcher::equal("host", "a")])
+            .unwrap(),
+        ["cpu", "mem"]
+    );
+    assert_eq!(
+        ts.select_series(&[LabelMatcher::equal("metric", "mem")])
+            .unwrap(),
+        ["mem"]
+    );
+
+    let registered = ts.list_registered().unwrap();
+    assert_eq!(registered.len(), 2);
+    let (name, cpu_meta) = &registered[0];
+    assert_eq!(name, "cpu");
+    assert!(cpu_meta.created_at > 0);
+    assert_eq!(cpu_meta.unit.as_deref(), Some("percent"));
+    assert_eq!(cpu_meta.description.as_deref(), Some("CPU usage"));
+    let (name, mem_meta) = &registered[1];
+    assert_eq!(name, "mem");
+    assert_eq!(
+        (mem_meta.unit.as_deref(), mem_meta.description.as_deref()),
+        (None, None)
+    );
+
+    let ts = TimeSeriesTableRead::<DeltaEncoding, f64>::open(&read_txn, "delta").unwrap();
+    let temp: Vec<(u64, f64)> = ts
+        .range("temp", T0, T0 + 60_000)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        temp,
+        [
+            (T0, -1.5),
+            (T0 + 1000, -1.25),
+            (T0 + 2000, -1.0),
+            (T0 + 3000, -0.75),
+            (T0 + 4000, -0.5),
+        ]
+    );
+    assert!(ts.list_registered().unwrap().is_empty());
+
+    let ts = TimeSeriesTableRead::<AbsoluteEncoding, i64>::open(&read_txn, "counts").unwrap();
+    let requests: Vec<(u64, i64)> = ts
+        .range("requests", T0, T0 + 3)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(requests, [(T0, i64::MIN), (T0 + 1, 0), (T0 + 2, i64::MAX)]);
+}
+
+#[test]
+fn test_golden_files_read_back() {
+    golden::check_golden_files(GOLDEN_PREFIX, check_golden);
+}
+
+#[test]
+fn test_fixture_round_trips() {
+    golden::check_fixture_round_trips(write_golden, check_golden);
+}
+
+/// Writes the golden file of the current version
+#[test]
+#[ignore = "run once per release to add its golden file"]
+fn bless_golden_file() {
+    golden::bless_golden_file(GOLDEN_PREFIX, write_golden);
+}
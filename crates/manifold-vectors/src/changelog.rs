@@ -0,0 +1,128 @@
+//! Change log of a vector table, ordered by sequence number.
+//!
+//! A [`VectorTable`](crate::VectorTable) opened with
+//! [`open_with_changelog`](crate::VectorTable::open_with_changelog) appends an entry for every
+//! insert and removal to a sibling table, so that an external index can pick up only the
+//! vectors changed since its last build with
+//! [`VectorTableRead::changes_since`](crate::VectorTableRead::changes_since).
+
+use manifold::{
+    ReadOnlyTable, ReadTransaction, ReadableTable, StorageError, Table, TableDefinition,
+    TableError, WriteTransaction,
+};
+use uuid::Uuid;
+
+use crate::error::VectorError;
+
+/// Returns the name of the change log table of the vector table `name`.
+pub(crate) fn table_name(name: &str) -> String {
+    format!("manifold_vectors::changes::{name}")
+}
+
+/// A change made to a vector table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOp {
+    /// A vector was inserted or replaced.
+    Insert,
+    /// A vector was removed.
+    Remove,
+}
+
+impl ChangeOp {
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::Insert => 0,
+            Self::Remove => 1,
+        }
+    }
+
+    fn from_u8(op: u8) -> Result<Self, StorageError> {
+        match op {
+            0 => Ok(Self::Insert),
+            1 => Ok(Self::Remove),
+            _ => Err(StorageError::Corrupted(format!(
+                "Unknown vector change operation {op}"
+            ))),
+        }
+    }
+}
+
+/// Writer side of a change log.
+pub(crate) struct ChangeLog<'txn> {
+    table: Table<'txn, u64, (Uuid, u8)>,
+    last: u64,
+}
+
+impl<'txn> ChangeLog<'txn> {
+    /// Opens the change log of the vector table `name`, creating it if it does not exist.
+    pub(crate) fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        let log_name = table_name(name);
+        let def: TableDefinition<u64, (Uuid, u8)> = TableDefinition::new(&log_name);
+        let table = txn.open_table(def)?;
+        let last = table.last()?.map_or(0, |(seq, _)| seq.value());
+        Ok(Self { table, last })
+    }
+
+    /// Appends a change with the next sequence number.
+    pub(crate) fn record(&mut self, key: &Uuid, op: ChangeOp) -> Result<(), VectorError> {
+        self.last += 1;
+        self.table.insert(self.last, (*key, op.to_u8()))?;
+        Ok(())
+    }
+
+    /// Removes the changes with a sequence number below `seq`, except the most recent one.
+    pub(crate) fn truncate_before(&mut self, seq: u64) -> Result<u64, VectorError> {
+        let end = seq.min(self.last);
+        let mut removed = 0;
+        self.table.retain_in(..end, |_, _| {
+            removed += 1;
+            false
+        })?;
+        Ok(removed)
+    }
+}
+
+/// Reader side of a change log.
+pub(crate) struct ChangeLogRead {
+    table: ReadOnlyTable<u64, (Uuid, u8)>,
+}
+
+impl ChangeLogRead {
+    /// Opens the change log of the vector table `name`, if it has one.
+    pub(crate) fn open(txn: &ReadTransaction, name: &str) -> Result<Option<Self>, VectorError> {
+        let log_name = table_name(name);
+        let def: TableDefinition<u64, (Uuid, u8)> = TableDefinition::new(&log_name);
+        match txn.open_table(def) {
+            Ok(table) => Ok(Some(Self { table })),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub(crate) fn changes_since(&self, seq: u64) -> Result<ChangeIter<'_>, VectorError> {
+        Ok(ChangeIter {
+            inner: self.table.range(seq.saturating_add(1)..)?,
+        })
+    }
+
+    pub(crate) fn last_seq(&self) -> Result<Option<u64>, VectorError> {
+        Ok(self.table.last()?.map(|(seq, _)| seq.value()))
+    }
+}
+
+/// Iterator over the changes of a vector table, in sequence order.
+pub struct ChangeIter<'a> {
+    inner: manifold::Range<'a, u64, (Uuid, u8)>,
+}
+
+impl Iterator for ChangeIter<'_> {
+    type Item = Result<(u64, Uuid, ChangeOp), VectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|result| {
+            let (seq_guard, change_guard) = result?;
+            let (key, op) = change_guard.value();
+            Ok((seq_guard.value(), key, ChangeOp::from_u8(op)?))
+        })
+    }
+}
@@ -2,71 +2,558 @@
 
 use manifold::{
     AccessGuard, ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata,
-    StorageError, Table, TableDefinition, TableError, WriteTransaction,
+    StorageError, Table, TableDefinition, TableError, TypeName, Value, WriteTransaction,
 };
-use std::ops::Deref;
+use std::ops::{Bound, Deref, RangeBounds, RangeInclusive};
+use std::sync::OnceLock;
 use uuid::Uuid;
 
+use crate::changelog::{self, ChangeIter, ChangeLog, ChangeLogRead, ChangeOp};
+use crate::distance::{self, Metric};
+use crate::error::VectorError;
+use crate::index_blob::{IndexBlobs, IndexBlobsRead};
+use crate::{dimension, options};
+
+/// Options fixed when a vector table is created.
+///
+/// The options are recorded with the table, so every later open uses them. Passing different
+/// options to [`VectorTable::open_with_options`] for an existing table fails with
+/// [`VectorError::OptionsMismatch`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VectorOptions {
+    /// Scales every inserted vector to unit length, so that
+    /// [`distance::cosine_prenormalized`] gives the cosine similarity against a normalized
+    /// query. Inserting a zero vector fails with [`VectorError::ZeroVector`].
+    pub normalize_on_insert: bool,
+    /// Stores the L2 norm of every vector in a sibling table, 4 bytes per vector, and returns
+    /// it from [`VectorGuard::norm`] instead of recomputing it.
+    pub store_norm: bool,
+    /// Stores a payload of up to [`MAX_PAYLOAD_LEN`] bytes after each vector, in the same
+    /// row, written with [`VectorTable::insert_with_payload`] and returned by
+    /// [`VectorGuard::payload`]. The vector stays at the start of the row, so it is still read
+    /// in place.
+    pub store_payload: bool,
+}
+
+/// Maximum length of the payload stored with a vector.
+pub const MAX_PAYLOAD_LEN: usize = 64 * 1024;
+
+/// Stored value type of a vector table created with [`VectorOptions::store_payload`].
+///
+/// The value is the vector's `DIM` little-endian `f32`s, followed by the length of the
+/// payload as a little-endian `u32` and the payload itself.
+#[derive(Debug)]
+struct VectorRecord<const DIM: usize>;
+
+impl<const DIM: usize> VectorRecord<DIM> {
+    const VECTOR_LEN: usize = DIM * size_of::<f32>();
+
+    /// Encodes `vector` and `payload` into `out`.
+    fn encode(vector: &[f32; DIM], payload: &[u8], out: &mut Vec<u8>) {
+        out.clear();
+        out.reserve(Self::VECTOR_LEN + 4 + payload.len());
+        for x in vector {
+            out.extend_from_slice(&x.to_le_bytes());
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+    }
+
+    /// Returns the payload of an encoded record.
+    fn payload(record: &[u8]) -> &[u8] {
+        let len_bytes = &record[Self::VECTOR_LEN..Self::VECTOR_LEN + 4];
+        let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        &record[Self::VECTOR_LEN + 4..][..len]
+    }
+}
+
+impl<const DIM: usize> Value for VectorRecord<DIM> {
+    type SelfType<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> &'a [u8]
+    where
+        Self: 'a,
+    {
+        data
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a &'b [u8]) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new(&format!("manifold_vectors::record[{DIM}]"))
+    }
+}
+
+/// Rows of a vector table, in the layout chosen by [`VectorOptions::store_payload`] when the
+/// table was created. Used for the table itself and for the guards and ranges read from it.
+enum Layout<P, R> {
+    /// Each value is the vector alone.
+    Plain(P),
+    /// Each value is a [`VectorRecord`], the vector followed by its payload.
+    WithPayload(R),
+}
+
+type StoredVector<'a, const DIM: usize> =
+    Layout<AccessGuard<'a, [f32; DIM]>, AccessGuard<'a, VectorRecord<DIM>>>;
+
+type StoredRange<'a, const DIM: usize> =
+    Layout<manifold::Range<'a, Uuid, [f32; DIM]>, manifold::Range<'a, Uuid, VectorRecord<DIM>>>;
+
+impl<P: ReadableTableMetadata, R: ReadableTableMetadata> Layout<P, R> {
+    fn len(&self) -> Result<u64, StorageError> {
+        match self {
+            Self::Plain(table) => table.len(),
+            Self::WithPayload(table) => table.len(),
+        }
+    }
+
+    fn get<const DIM: usize>(
+        &self,
+        key: &Uuid,
+    ) -> Result<Option<StoredVector<'_, DIM>>, StorageError>
+    where
+        P: ReadableTable<Uuid, [f32; DIM]>,
+        R: ReadableTable<Uuid, VectorRecord<DIM>>,
+    {
+        Ok(match self {
+            Self::Plain(table) => table.get(key)?.map(Layout::Plain),
+            Self::WithPayload(table) => table.get(key)?.map(Layout::WithPayload),
+        })
+    }
+
+    fn key_bounds<const DIM: usize>(&self) -> Result<Option<RangeInclusive<Uuid>>, StorageError>
+    where
+        P: ReadableTable<Uuid, [f32; DIM]>,
+        R: ReadableTable<Uuid, VectorRecord<DIM>>,
+    {
+        let (first, last) = match self {
+            Self::Plain(table) => (
+                table.first()?.map(|(key, _)| key.value()),
+                table.last()?.map(|(key, _)| key.value()),
+            ),
+            Self::WithPayload(table) => (
+                table.first()?.map(|(key, _)| key.value()),
+                table.last()?.map(|(key, _)| key.value()),
+            ),
+        };
+        Ok(first.zip(last).map(|(first, last)| first..=last))
+    }
+
+    fn range<const DIM: usize>(
+        &self,
+        range: impl RangeBounds<Uuid>,
+    ) -> Result<StoredRange<'_, DIM>, StorageError>
+    where
+        P: ReadableTable<Uuid, [f32; DIM]>,
+        R: ReadableTable<Uuid, VectorRecord<DIM>>,
+    {
+        Ok(match self {
+            Self::Plain(table) => Layout::Plain(table.range(range)?),
+            Self::WithPayload(table) => Layout::WithPayload(table.range(range)?),
+        })
+    }
+}
+
+impl<'txn, const DIM: usize>
+    Layout<Table<'txn, Uuid, [f32; DIM]>, Table<'txn, Uuid, VectorRecord<DIM>>>
+{
+    fn remove(&mut self, key: &Uuid) -> Result<Option<StoredVector<'_, DIM>>, StorageError> {
+        Ok(match self {
+            Self::Plain(table) => table.remove(key)?.map(Layout::Plain),
+            Self::WithPayload(table) => table.remove(key)?.map(Layout::WithPayload),
+        })
+    }
+
+    fn remove_bulk(&mut self, keys: &[Uuid]) -> Result<usize, StorageError> {
+        match self {
+            Self::Plain(table) => table.remove_bulk(keys.iter().copied()),
+            Self::WithPayload(table) => table.remove_bulk(keys.iter().copied()),
+        }
+    }
+
+    fn remove_range(&mut self, range: RangeInclusive<Uuid>) -> Result<(), StorageError> {
+        match self {
+            Self::Plain(table) => table.retain_in(range, |_, _| false),
+            Self::WithPayload(table) => table.retain_in(range, |_, _| false),
+        }
+    }
+}
+
+impl<'a, const DIM: usize> StoredRange<'a, DIM> {
+    fn next_row(&mut self) -> Option<Result<(Uuid, StoredVector<'a, DIM>), StorageError>> {
+        Some(match self {
+            Self::Plain(range) => range
+                .next()?
+                .map(|(key, value)| (key.value(), Layout::Plain(value))),
+            Self::WithPayload(range) => range
+                .next()?
+                .map(|(key, value)| (key.value(), Layout::WithPayload(value))),
+        })
+    }
+}
+
 /// A table storing fixed-dimension dense vectors.
 pub struct VectorTable<'txn, const DIM: usize> {
-    table: Table<'txn, Uuid, [f32; DIM]>,
+    txn: &'txn WriteTransaction,
+    name: String,
+    table: Layout<Table<'txn, Uuid, [f32; DIM]>, Table<'txn, Uuid, VectorRecord<DIM>>>,
+    options: VectorOptions,
+    norms: Option<Table<'txn, Uuid, f32>>,
+    changes: Option<ChangeLog<'txn>>,
+    buffer: Vec<u8>,
 }
 
 impl<'txn, const DIM: usize> VectorTable<'txn, DIM> {
-    /// Opens a vector table for writing.
-    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TableError> {
-        let def: TableDefinition<Uuid, [f32; DIM]> = TableDefinition::new(name);
-        let table = txn.open_table(def)?;
-        Ok(Self { table })
+    /// Opens a vector table for writing, creating it if it does not exist.
+    ///
+    /// The dimension is recorded when the table is first opened, and later opens with a
+    /// different `DIM` fail with [`VectorError::DimensionMismatch`]. A table created with
+    /// [`open_with_options`](Self::open_with_options) keeps using its recorded options.
+    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        Self::open_inner(txn, name, None)
+    }
+
+    /// Opens a vector table for writing with the given options, creating it if it does not
+    /// exist.
+    ///
+    /// Fails with [`VectorError::OptionsMismatch`] if the table exists with other options,
+    /// including a table created with [`open`](Self::open), which has the default options.
+    pub fn open_with_options(
+        txn: &'txn WriteTransaction,
+        name: &str,
+        options: VectorOptions,
+    ) -> Result<Self, VectorError> {
+        Self::open_inner(txn, name, Some(options))
+    }
+
+    fn open_inner(
+        txn: &'txn WriteTransaction,
+        name: &str,
+        options: Option<VectorOptions>,
+    ) -> Result<Self, VectorError> {
+        let options = options::open_checked(txn, name, options)?;
+        let table = if options.store_payload {
+            let def: TableDefinition<Uuid, VectorRecord<DIM>> = TableDefinition::new(name);
+            Layout::WithPayload(dimension::open_checked(txn, name, DIM, || {
+                txn.open_table(def)
+            })?)
+        } else {
+            let def: TableDefinition<Uuid, [f32; DIM]> = TableDefinition::new(name);
+            Layout::Plain(dimension::open_checked(txn, name, DIM, || {
+                txn.open_table(def)
+            })?)
+        };
+        let norms = if options.store_norm {
+            let norms_name = options::norms_table_name(name);
+            Some(txn.open_table(TableDefinition::<Uuid, f32>::new(&norms_name))?)
+        } else {
+            None
+        };
+        Ok(Self {
+            txn,
+            name: name.to_string(),
+            table,
+            options,
+            norms,
+            changes: None,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Returns the options the table was created with.
+    pub fn options(&self) -> VectorOptions {
+        self.options
+    }
+
+    /// Opens a vector table for writing with its change log enabled.
+    ///
+    /// Every insert and removal through the returned table also appends an entry with the
+    /// next sequence number to the table's change log, in the same transaction, which can be
+    /// read back with [`VectorTableRead::changes_since`]. Changes made through a table opened
+    /// with [`open`](Self::open) are not logged, so every writer of a table whose log is read
+    /// should open it this way.
+    pub fn open_with_changelog(
+        txn: &'txn WriteTransaction,
+        name: &str,
+    ) -> Result<Self, VectorError> {
+        let mut table = Self::open(txn, name)?;
+        table.changes = Some(ChangeLog::open(txn, name)?);
+        Ok(table)
+    }
+
+    /// Appends a change to the change log, if it is enabled.
+    fn record(&mut self, key: &Uuid, op: ChangeOp) -> Result<(), VectorError> {
+        match &mut self.changes {
+            Some(changes) => changes.record(key, op),
+            None => Ok(()),
+        }
     }
 
     /// Inserts a vector with the given key.
-    pub fn insert(&mut self, key: &Uuid, vector: &[f32; DIM]) -> Result<(), TableError> {
-        self.table.insert(key, vector)?;
-        Ok(())
+    ///
+    /// In a table that stores payloads, the vector is stored with an empty payload, replacing
+    /// any payload stored for the key before.
+    ///
+    /// Fails with [`VectorError::ZeroVector`] if the table normalizes vectors on insert and
+    /// `vector` is all zeros.
+    pub fn insert(&mut self, key: &Uuid, vector: &[f32; DIM]) -> Result<(), VectorError> {
+        self.insert_with_payload(key, vector, &[])
+    }
+
+    /// Inserts a vector with the given key, together with a payload stored in the same row.
+    ///
+    /// The payload, such as the title of a document for re-ranking, is returned by
+    /// [`VectorGuard::payload`] along with the vector, without a second lookup.
+    ///
+    /// Fails with [`VectorError::PayloadTooLarge`] if `payload` is longer than
+    /// [`MAX_PAYLOAD_LEN`], with [`VectorError::PayloadNotStored`] if it is not empty and
+    /// the table was created without [`VectorOptions::store_payload`], and with
+    /// [`VectorError::ZeroVector`] if the table normalizes vectors on insert and `vector` is
+    /// all zeros.
+    pub fn insert_with_payload(
+        &mut self,
+        key: &Uuid,
+        vector: &[f32; DIM],
+        payload: &[u8],
+    ) -> Result<(), VectorError> {
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(VectorError::PayloadTooLarge {
+                len: payload.len(),
+                max: MAX_PAYLOAD_LEN,
+            });
+        }
+        let normalized;
+        let vector = if self.options.normalize_on_insert {
+            normalized = normalize(vector)?;
+            &normalized
+        } else {
+            vector
+        };
+        match &mut self.table {
+            Layout::Plain(_) if !payload.is_empty() => return Err(VectorError::PayloadNotStored),
+            Layout::Plain(table) => {
+                table.insert(key, vector)?;
+            }
+            Layout::WithPayload(table) => {
+                VectorRecord::encode(vector, payload, &mut self.buffer);
+                table.insert(key, self.buffer.as_slice())?;
+            }
+        }
+        if let Some(norms) = &mut self.norms {
+            norms.insert(key, norm(vector))?;
+        }
+        self.record(key, ChangeOp::Insert)
     }
 
     /// Inserts multiple vectors in a single batch operation.
+    ///
+    /// In a table that stores payloads, the vectors are stored with empty payloads.
+    ///
+    /// Fails with [`VectorError::ZeroVector`], before inserting anything, if the table
+    /// normalizes vectors on insert and any of the vectors is all zeros.
     pub fn insert_batch(
         &mut self,
         items: &[(Uuid, [f32; DIM])],
         sorted: bool,
-    ) -> Result<(), StorageError> {
-        self.table.insert_bulk(items.to_vec(), sorted)?;
+    ) -> Result<(), VectorError> {
+        let normalized;
+        let items = if self.options.normalize_on_insert {
+            normalized = items
+                .iter()
+                .map(|(key, vector)| Ok((*key, normalize(vector)?)))
+                .collect::<Result<Vec<_>, VectorError>>()?;
+            normalized.as_slice()
+        } else {
+            items
+        };
+        match &mut self.table {
+            Layout::Plain(table) => {
+                table.insert_bulk(items.to_vec(), sorted)?;
+            }
+            Layout::WithPayload(table) => {
+                let records: Vec<(Uuid, Vec<u8>)> = items
+                    .iter()
+                    .map(|(key, vector)| {
+                        let mut record = Vec::new();
+                        VectorRecord::encode(vector, &[], &mut record);
+                        (*key, record)
+                    })
+                    .collect();
+                table.insert_bulk(
+                    records
+                        .iter()
+                        .map(|(key, record)| (*key, record.as_slice())),
+                    sorted,
+                )?;
+            }
+        }
+        if let Some(norms) = &mut self.norms {
+            norms.insert_bulk(
+                items.iter().map(|(key, vector)| (*key, norm(vector))),
+                sorted,
+            )?;
+        }
+        for (key, _) in items {
+            self.record(key, ChangeOp::Insert)?;
+        }
         Ok(())
     }
 
+    /// Retrieves a vector by key, including changes made earlier in this transaction.
+    pub fn get(&self, key: &Uuid) -> Result<Option<VectorGuard<'_, DIM>>, VectorError> {
+        let norm = stored_norm(self.norms.as_ref(), key)?;
+        Ok(self
+            .table
+            .get(key)?
+            .map(|guard| VectorGuard::new(guard, norm)))
+    }
+
     /// Removes a vector by key.
     ///
-    /// Returns the removed vector if it existed, or None if the key was not found.
-    pub fn remove(&mut self, key: &Uuid) -> Result<Option<VectorGuard<'_, DIM>>, StorageError> {
-        match self.table.remove(key) {
-            Ok(Some(guard)) => {
-                let value_cached = guard.value();
-                Ok(Some(VectorGuard {
-                    value_cached,
-                    _guard: guard,
-                }))
+    /// Returns the removed vector if it existed, or None if the key was not found. Use
+    /// `is_some()` on the result when only the existence of the key matters.
+    pub fn remove(&mut self, key: &Uuid) -> Result<Option<VectorGuard<'_, DIM>>, VectorError> {
+        let norm = match &mut self.norms {
+            Some(norms) => norms.remove(key)?.map(|guard| guard.value()),
+            None => None,
+        };
+        let removed = self.table.remove(key)?;
+        if removed.is_some()
+            && let Some(changes) = &mut self.changes
+        {
+            changes.record(key, ChangeOp::Remove)?;
+        }
+        Ok(removed.map(|guard| VectorGuard::new(guard, norm)))
+    }
+
+    /// Removes multiple vectors in a single batch operation.
+    ///
+    /// Returns the number of vectors actually removed. Keys that are not present are skipped.
+    pub fn remove_batch(&mut self, keys: &[Uuid]) -> Result<usize, VectorError> {
+        if let Some(norms) = &mut self.norms {
+            norms.remove_bulk(keys.iter().copied())?;
+        }
+        if self.changes.is_none() {
+            return Ok(self.table.remove_bulk(keys)?);
+        }
+
+        let mut removed = 0;
+        for key in keys {
+            if self.table.remove(key)?.is_some() {
+                self.record(key, ChangeOp::Remove)?;
+                removed += 1;
             }
-            Ok(None) => Ok(None),
-            Err(e) => Err(e),
         }
+        Ok(removed)
     }
 
     /// Removes multiple vectors in a single batch operation.
+    #[deprecated(note = "renamed to `remove_batch`")]
+    pub fn remove_bulk(&mut self, keys: &[Uuid]) -> Result<usize, VectorError> {
+        self.remove_batch(keys)
+    }
+
+    /// Removes every vector from the table.
+    pub fn clear(&mut self) -> Result<(), VectorError> {
+        self.remove_range(Uuid::nil()..=Uuid::max())?;
+        Ok(())
+    }
+
+    /// Removes every vector whose key starts with `prefix`.
     ///
-    /// Returns the number of vectors actually removed.
-    pub fn remove_bulk(&mut self, keys: &[Uuid]) -> Result<usize, StorageError> {
-        self.table.remove_bulk(keys.iter().copied())
+    /// See [`VectorTableRead::prefix_iter`] for how keys are matched. Returns the number of
+    /// vectors removed.
+    pub fn remove_prefix(&mut self, prefix: &[u8]) -> Result<u64, VectorError> {
+        let Some(range) = prefix_range(prefix) else {
+            return Ok(0);
+        };
+        self.remove_range(range)
+    }
+
+    /// Removes every vector in `range`, logging each removal, and returns how many were
+    /// removed.
+    fn remove_range(&mut self, range: RangeInclusive<Uuid>) -> Result<u64, VectorError> {
+        if let Some(norms) = &mut self.norms {
+            norms.retain_in(range.clone(), |_, _| false)?;
+        }
+        let Some(changes) = &mut self.changes else {
+            let before = self.len()?;
+            self.table.remove_range(range)?;
+            return Ok(before - self.len()?);
+        };
+
+        let mut keys = Vec::new();
+        let mut rows = self.table.range(range.clone())?;
+        while let Some(row) = rows.next_row() {
+            keys.push(row?.0);
+        }
+        drop(rows);
+        self.table.remove_range(range)?;
+        for key in &keys {
+            changes.record(key, ChangeOp::Remove)?;
+        }
+        Ok(keys.len() as u64)
+    }
+
+    /// Removes the change log entries with a sequence number below `seq`.
+    ///
+    /// The most recent entry is always kept, so that sequence numbers keep increasing. Returns
+    /// the number of entries removed, or 0 if the change log is not enabled.
+    pub fn truncate_changes_before(&mut self, seq: u64) -> Result<u64, VectorError> {
+        match &mut self.changes {
+            Some(changes) => changes.truncate_before(seq),
+            None => Ok(0),
+        }
+    }
+
+    /// Stores an opaque blob under `name`, such as a serialized external index built over the
+    /// table, replacing any earlier version of it.
+    ///
+    /// The blob is written in chunks of [`INDEX_BLOB_CHUNK_LEN`](crate::INDEX_BLOB_CHUNK_LEN)
+    /// bytes in the same transaction as the vectors, and the chunks of the version it replaces
+    /// are removed. `version` is returned by [`VectorTableRead::get_index_blob`] and is not
+    /// interpreted, so it can record the index format or the
+    /// [change log](VectorTableRead::last_change_seq) position the index was built at.
+    pub fn put_index_blob(
+        &mut self,
+        name: &str,
+        version: u64,
+        bytes: &[u8],
+    ) -> Result<(), VectorError> {
+        IndexBlobs::open(self.txn, &self.name)?.put(name, version, bytes)
+    }
+
+    /// Removes the blob stored under `name` and its chunks.
+    ///
+    /// Returns `true` if the blob existed.
+    pub fn delete_index_blob(&mut self, name: &str) -> Result<bool, VectorError> {
+        IndexBlobs::open(self.txn, &self.name)?.delete(name)
     }
 
     /// Returns the number of vectors stored in this table.
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.table.len()
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.table.len()?)
     }
 
     /// Returns `true` if the table contains no vectors.
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 }
@@ -76,72 +563,336 @@ impl<'txn, const DIM: usize> VectorTable<'txn, DIM> {
 /// This table leverages Manifold's fixed-width Value trait for arrays,
 /// which deserializes directly from memory-mapped pages.
 pub struct VectorTableRead<const DIM: usize> {
-    table: ReadOnlyTable<Uuid, [f32; DIM]>,
+    table: Layout<ReadOnlyTable<Uuid, [f32; DIM]>, ReadOnlyTable<Uuid, VectorRecord<DIM>>>,
+    name: String,
+    options: VectorOptions,
+    norms: Option<ReadOnlyTable<Uuid, f32>>,
+    changes: Option<ChangeLogRead>,
+    index_blobs: Option<IndexBlobsRead>,
 }
 
 impl<const DIM: usize> VectorTableRead<DIM> {
     /// Opens a vector table for reading.
-    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, StorageError> {
-        let def: TableDefinition<Uuid, [f32; DIM]> = TableDefinition::new(name);
-        let table = txn.open_table(def).map_err(|e| match e {
-            TableError::Storage(s) => s,
-            _ => StorageError::Io(std::io::Error::other(e)),
-        })?;
-        Ok(Self { table })
+    ///
+    /// Fails with [`VectorError::DimensionMismatch`] if the table was created with a
+    /// different `DIM`.
+    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, VectorError> {
+        dimension::check_read(txn, name, DIM)?;
+        let options = options::read(txn, name)?;
+        let table = if options.store_payload {
+            let def: TableDefinition<Uuid, VectorRecord<DIM>> = TableDefinition::new(name);
+            Layout::WithPayload(txn.open_table(def)?)
+        } else {
+            let def: TableDefinition<Uuid, [f32; DIM]> = TableDefinition::new(name);
+            Layout::Plain(txn.open_table(def)?)
+        };
+        let norms = if options.store_norm {
+            let norms_name = options::norms_table_name(name);
+            Some(txn.open_table(TableDefinition::<Uuid, f32>::new(&norms_name))?)
+        } else {
+            None
+        };
+        let changes = ChangeLogRead::open(txn, name)?;
+        let index_blobs = IndexBlobsRead::open(txn, name)?;
+        Ok(Self {
+            table,
+            name: name.to_string(),
+            options,
+            norms,
+            changes,
+            index_blobs,
+        })
+    }
+
+    /// Returns the options the table was created with.
+    pub fn options(&self) -> VectorOptions {
+        self.options
     }
 
     /// Retrieves a vector by key.
     ///
-    /// Returns a guard that holds the vector data cached from deserialization.
-    /// The vector is deserialized once when the guard is created.
-    pub fn get(&self, key: &Uuid) -> Result<Option<VectorGuard<'_, DIM>>, StorageError> {
-        Ok(self.table.get(key)?.map(VectorGuard::new))
+    /// Returns a guard giving access to the vector and, in a table that stores payloads, its
+    /// payload.
+    pub fn get(&self, key: &Uuid) -> Result<Option<VectorGuard<'_, DIM>>, VectorError> {
+        let norm = stored_norm(self.norms.as_ref(), key)?;
+        Ok(self
+            .table
+            .get(key)?
+            .map(|guard| VectorGuard::new(guard, norm)))
+    }
+
+    /// Scores the vectors of `keys` against `query`, such as the candidates of an external
+    /// index for re-ranking.
+    ///
+    /// Returns one score per key, in the order of `keys`, with `None` for a key that is not in
+    /// the table. The keys are looked up in sorted order, so that neighbouring lookups share
+    /// pages, and a key given more than once is only looked up once. Each score is computed on
+    /// the stored vector in place, and equals `metric.score(query, guard.value())` for the
+    /// guard returned by [`get`](Self::get).
+    pub fn score_keys(
+        &self,
+        keys: &[Uuid],
+        query: &[f32; DIM],
+        metric: Metric,
+    ) -> Result<Vec<Option<f32>>, VectorError> {
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_unstable_by_key(|&i| keys[i]);
+
+        let mut scores = vec![None; keys.len()];
+        let mut previous: Option<(Uuid, Option<f32>)> = None;
+        for i in order {
+            let key = keys[i];
+            let score = match previous {
+                Some((previous_key, score)) if previous_key == key => score,
+                _ => self
+                    .table
+                    .get(&key)?
+                    .map(|guard| metric.score(query, VectorGuard::new(guard, None).value())),
+            };
+            scores[i] = score;
+            previous = Some((key, score));
+        }
+        Ok(scores)
     }
 
     /// Returns the number of vectors stored in this table.
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.table.len()
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.table.len()?)
     }
 
     /// Returns `true` if the table contains no vectors.
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 
-    /// Iterates over all vectors in the table.
-    pub fn all_vectors(&self) -> Result<VectorIter<'_, DIM>, StorageError> {
+    /// Iterates over all vectors in the table, in key order.
+    ///
+    /// Removed vectors are deleted from the table rather than marked, so the iterator only
+    /// yields vectors that were present when the read transaction began.
+    pub fn all_vectors(&self) -> Result<VectorIter<'_, DIM>, VectorError> {
+        self.range_iter(..)
+    }
+
+    /// Splits a scan of every vector into up to `num_shards` iterators over disjoint key
+    /// ranges, so that an external index can be built from several threads.
+    ///
+    /// Together the shards yield every vector of [`all_vectors`](Self::all_vectors) exactly
+    /// once, each in key order, and are returned in key order. They borrow the table, so they
+    /// can be driven from threads scoped to it, such as with [`std::thread::scope`], all
+    /// reading the snapshot of the same read transaction.
+    ///
+    /// The range from the smallest to the largest key is cut into shards of equal width, so
+    /// shards hold about as many vectors each when keys are spread evenly over that range, as
+    /// random UUIDs are, including behind a common prefix. Fewer shards are returned if the
+    /// range is narrower than `num_shards` keys, and a single one if the table is empty or
+    /// `num_shards` is 0.
+    pub fn par_chunks(&self, num_shards: usize) -> Result<Vec<VectorIter<'_, DIM>>, VectorError> {
+        let Some(bounds) = self.table.key_bounds::<DIM>()? else {
+            return Ok(vec![self.all_vectors()?]);
+        };
+        let first = bounds.start().as_u128();
+        let width = bounds.end().as_u128() - first;
+        // At most one shard per unit of width, so that every shard starts after the previous
+        let shards = (num_shards.max(1) as u128).min(width.max(1));
+
+        let mut iters = Vec::new();
+        let mut start = Bound::Unbounded;
+        for i in 1..shards {
+            // first + width * i / shards, without overflowing
+            let offset = width / shards * i + width % shards * i / shards;
+            let next = Uuid::from_u128(first + offset);
+            iters.push(self.range_iter((start, Bound::Excluded(next)))?);
+            start = Bound::Included(next);
+        }
+        iters.push(self.range_iter((start, Bound::Unbounded))?);
+        Ok(iters)
+    }
+
+    /// Iterates over the vectors whose key starts with `prefix`, in key order.
+    ///
+    /// Keys are matched on the bytes of [`Uuid::as_bytes`], so vectors can be namespaced by
+    /// tenant or collection by putting a fixed identifier in the leading bytes of their keys.
+    /// Only the matching range of the table is scanned. A prefix longer than 16 bytes matches
+    /// nothing, and an empty prefix matches every vector.
+    pub fn prefix_iter(&self, prefix: &[u8]) -> Result<VectorIter<'_, DIM>, VectorError> {
+        match prefix_range(prefix) {
+            Some(range) => self.range_iter(range),
+            None => self.range_iter(Uuid::nil()..Uuid::nil()),
+        }
+    }
+
+    /// Iterates over the changes logged after sequence number `seq`, in sequence order.
+    ///
+    /// Pass the sequence number of the last change already processed to resume from it, or 0
+    /// to read the whole log. A removal is only logged for a key that was present. Changes
+    /// before a point passed to [`VectorTable::truncate_changes_before`] are no longer
+    /// available.
+    ///
+    /// Fails with [`TableError::TableDoesNotExist`] if the table was never opened with
+    /// [`VectorTable::open_with_changelog`].
+    pub fn changes_since(&self, seq: u64) -> Result<ChangeIter<'_>, VectorError> {
+        self.change_log()?.changes_since(seq)
+    }
+
+    /// Returns the sequence number of the most recent change, or `None` if none was logged.
+    ///
+    /// Recording this alongside a full scan of the table in the same read transaction gives
+    /// the point to resume from with [`changes_since`](Self::changes_since).
+    pub fn last_change_seq(&self) -> Result<Option<u64>, VectorError> {
+        self.change_log()?.last_seq()
+    }
+
+    /// Reads the blob stored under `name` with [`VectorTable::put_index_blob`].
+    ///
+    /// Returns the version and bytes of the blob, or `None` if no blob is stored under `name`.
+    pub fn get_index_blob(&self, name: &str) -> Result<Option<(u64, Vec<u8>)>, VectorError> {
+        match &self.index_blobs {
+            Some(index_blobs) => index_blobs.get(name),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterates over the vectors in `range`, along with their stored norms.
+    pub(crate) fn range_iter(
+        &self,
+        range: impl RangeBounds<Uuid> + Clone,
+    ) -> Result<VectorIter<'_, DIM>, VectorError> {
+        let norms = match &self.norms {
+            Some(norms) => Some(norms.range(range.clone())?),
+            None => None,
+        };
         Ok(VectorIter {
-            inner: self.table.iter()?,
+            inner: self.table.range(range)?,
+            norms,
         })
     }
+
+    fn change_log(&self) -> Result<&ChangeLogRead, VectorError> {
+        self.changes
+            .as_ref()
+            .ok_or_else(|| TableError::TableDoesNotExist(changelog::table_name(&self.name)).into())
+    }
 }
 
-/// A guard providing access to a stored vector.
+/// Scales `vector` to unit length.
+fn normalize<const DIM: usize>(vector: &[f32; DIM]) -> Result<[f32; DIM], VectorError> {
+    let norm = norm(vector);
+    if norm == 0.0 {
+        return Err(VectorError::ZeroVector);
+    }
+    Ok(vector.map(|x| x / norm))
+}
+
+/// Computes the L2 norm of `vector`.
+fn norm(vector: &[f32]) -> f32 {
+    distance::dot_product(vector, vector).sqrt()
+}
+
+/// Looks up the stored norm of `key`, if the table stores norms.
+fn stored_norm(
+    norms: Option<&impl ReadableTable<Uuid, f32>>,
+    key: &Uuid,
+) -> Result<Option<f32>, VectorError> {
+    match norms {
+        Some(norms) => Ok(norms.get(key)?.map(|guard| guard.value())),
+        None => Ok(None),
+    }
+}
+
+/// Returns the range of keys that start with `prefix`, or `None` if no key can.
+pub(crate) fn prefix_range(prefix: &[u8]) -> Option<RangeInclusive<Uuid>> {
+    if prefix.len() > 16 {
+        return None;
+    }
+    let mut start = [0x00; 16];
+    let mut end = [0xFF; 16];
+    start[..prefix.len()].copy_from_slice(prefix);
+    end[..prefix.len()].copy_from_slice(prefix);
+    Some(Uuid::from_bytes(start)..=Uuid::from_bytes(end))
+}
+
+/// A guard providing access to a stored vector and its payload.
 ///
-/// The vector data is deserialized once when the guard is created,
-/// then cached for subsequent accesses.
+/// When the stored bytes are suitably aligned for `f32`, the vector is read in place. Otherwise
+/// it is decoded once, on first access, into a buffer held by the guard.
 pub struct VectorGuard<'a, const DIM: usize> {
-    value_cached: [f32; DIM],
-    _guard: AccessGuard<'a, [f32; DIM]>,
+    guard: StoredVector<'a, DIM>,
+    unaligned: OnceLock<[f32; DIM]>,
+    norm: Option<f32>,
 }
 
 impl<'a, const DIM: usize> VectorGuard<'a, DIM> {
-    fn new(guard: AccessGuard<'a, [f32; DIM]>) -> Self {
-        let value_cached = guard.value();
+    fn new(guard: StoredVector<'a, DIM>, norm: Option<f32>) -> Self {
         Self {
-            value_cached,
-            _guard: guard,
+            guard,
+            unaligned: OnceLock::new(),
+            norm,
         }
     }
 
     /// Returns a reference to the vector data.
     pub fn value(&self) -> &[f32; DIM] {
-        &self.value_cached
+        if let Some(value) = self.in_place() {
+            return value;
+        }
+        self.unaligned.get_or_init(|| {
+            let mut vector = [0.0; DIM];
+            for (x, bytes) in vector.iter_mut().zip(self.as_bytes().chunks_exact(4)) {
+                *x = f32::from_le_bytes(bytes.try_into().unwrap());
+            }
+            vector
+        })
+    }
+
+    /// Returns a reference to the vector data, like [`value`](Self::value).
+    pub fn vector(&self) -> &[f32; DIM] {
+        self.value()
+    }
+
+    /// Returns the payload stored with the vector.
+    ///
+    /// The payload is empty for a vector inserted without one, and for every vector of a
+    /// table created without [`VectorOptions::store_payload`].
+    pub fn payload(&self) -> &[u8] {
+        match &self.guard {
+            Layout::Plain(_) => &[],
+            Layout::WithPayload(guard) => VectorRecord::<DIM>::payload(guard.as_bytes()),
+        }
     }
 
     /// Returns the vector data as a slice.
     pub fn as_slice(&self) -> &[f32] {
-        &self.value_cached
+        self.value()
+    }
+
+    /// Returns the L2 norm of the vector.
+    ///
+    /// The norm is read from the table if it was created with
+    /// [`VectorOptions::store_norm`], and computed from the vector otherwise.
+    pub fn norm(&self) -> f32 {
+        self.norm.unwrap_or_else(|| norm(self.value()))
+    }
+
+    /// Returns the stored encoding of the vector: `DIM` little-endian `f32`s, with no
+    /// particular alignment.
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.guard {
+            Layout::Plain(guard) => guard.as_bytes(),
+            Layout::WithPayload(guard) => &guard.as_bytes()[..VectorRecord::<DIM>::VECTOR_LEN],
+        }
+    }
+
+    fn in_place(&self) -> Option<&[f32; DIM]> {
+        if cfg!(target_endian = "big") {
+            return None;
+        }
+        // SAFETY: every bit pattern is a valid f32, and align_to only returns the aligned part
+        let (prefix, floats, _) = unsafe { self.as_bytes().align_to::<f32>() };
+        if prefix.is_empty() {
+            floats.try_into().ok()
+        } else {
+            None
+        }
     }
 }
 
@@ -149,22 +900,81 @@ impl<const DIM: usize> Deref for VectorGuard<'_, DIM> {
     type Target = [f32; DIM];
 
     fn deref(&self) -> &Self::Target {
-        &self.value_cached
+        self.value()
     }
 }
 
 /// Iterator over vectors in a `VectorTableRead`.
 pub struct VectorIter<'a, const DIM: usize> {
-    inner: manifold::Range<'a, Uuid, [f32; DIM]>,
+    inner: StoredRange<'a, DIM>,
+    // Holds the same keys as `inner`, since norms are written and removed with the vectors
+    norms: Option<manifold::Range<'a, Uuid, f32>>,
 }
 
 impl<'a, const DIM: usize> Iterator for VectorIter<'a, DIM> {
-    type Item = Result<(Uuid, VectorGuard<'a, DIM>), StorageError>;
+    type Item = Result<(Uuid, VectorGuard<'a, DIM>), VectorError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|result| {
-            result
-                .map(|(key_guard, value_guard)| (key_guard.value(), VectorGuard::new(value_guard)))
-        })
+        let (key, value_guard) = match self.inner.next_row()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err.into())),
+        };
+        let norm = match self.norms.as_mut().and_then(Iterator::next) {
+            Some(Ok((_, norm_guard))) => Some(norm_guard.value()),
+            Some(Err(err)) => return Some(Err(err.into())),
+            None => None,
+        };
+        Some(Ok((key, VectorGuard::new(value_guard, norm))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, VectorGuard};
+    use manifold::backends::InMemoryBackend;
+    use manifold::{Database, ReadableDatabase, TableDefinition};
+
+    #[test]
+    fn test_guard_handles_unaligned_values() {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap();
+        let names = ["a", "bb", "ccc", "dddd"];
+        let vector = [1.5f32, -2.25, 3.0];
+
+        // Keys of different lengths shift where the value starts within the page
+        let write_txn = db.begin_write().unwrap();
+        for name in names {
+            let def: TableDefinition<&str, [f32; 3]> = TableDefinition::new(name);
+            let mut table = write_txn.open_table(def).unwrap();
+            table.insert(name, &vector).unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let mut unaligned = 0;
+        for name in names {
+            let def: TableDefinition<&str, [f32; 3]> = TableDefinition::new(name);
+            let table = read_txn.open_table(def).unwrap();
+            let guard = VectorGuard::new(Layout::Plain(table.get(name).unwrap().unwrap()), None);
+
+            let bytes = guard.as_bytes();
+            assert_eq!(bytes.len(), size_of::<[f32; 3]>());
+            let aligned = bytes.as_ptr().addr() % align_of::<f32>() == 0;
+            if !aligned {
+                unaligned += 1;
+            }
+            assert_eq!(
+                guard.in_place().is_some(),
+                aligned && cfg!(target_endian = "little")
+            );
+
+            let bits = vector.map(f32::to_bits);
+            assert!(guard.value().as_ptr().is_aligned());
+            assert_eq!(guard.value().map(f32::to_bits), bits);
+            assert_eq!(guard.as_slice().len(), 3);
+            assert_eq!(guard.map(f32::to_bits), bits);
+        }
+        assert!(unaligned > 0);
     }
 }
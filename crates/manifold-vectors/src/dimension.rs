@@ -0,0 +1,56 @@
+//! Records the dimension of each vector table so mismatched opens can be rejected.
+
+use crate::error::VectorError;
+use manifold::{ReadTransaction, ReadableTable, TableDefinition, TableError, WriteTransaction};
+
+// Maps vector table names to the dimension they were created with
+const DIMENSIONS: TableDefinition<&str, u64> = TableDefinition::new("manifold_vectors::dimensions");
+
+/// Returns an error if `name` was recorded with a dimension other than `requested`.
+fn check(stored: Option<u64>, requested: usize) -> Result<(), VectorError> {
+    match stored {
+        Some(stored) if stored != requested as u64 => Err(VectorError::DimensionMismatch {
+            stored: usize::try_from(stored).unwrap_or(usize::MAX),
+            requested,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Validates the recorded dimension of `name`, then opens the table with `open` and records
+/// `requested` if no dimension was recorded yet.
+pub(crate) fn open_checked<T>(
+    txn: &WriteTransaction,
+    name: &str,
+    requested: usize,
+    open: impl FnOnce() -> Result<T, TableError>,
+) -> Result<T, VectorError> {
+    let mut dimensions = txn.open_table(DIMENSIONS)?;
+    let stored = dimensions.get(name)?.map(|guard| guard.value());
+    check(stored, requested)?;
+
+    // Open before recording, so that a table created before dimensions were recorded is
+    // still rejected by its stored type if the dimension differs
+    let table = open()?;
+    if stored.is_none() {
+        dimensions.insert(name, requested as u64)?;
+    }
+    Ok(table)
+}
+
+/// Validates the recorded dimension of `name` for a read transaction.
+///
+/// Tables created before dimensions were recorded have no entry and are accepted here; their
+/// stored type still rejects a mismatched dimension when the table is opened.
+pub(crate) fn check_read(
+    txn: &ReadTransaction,
+    name: &str,
+    requested: usize,
+) -> Result<(), VectorError> {
+    let dimensions = match txn.open_table(DIMENSIONS) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    check(dimensions.get(name)?.map(|guard| guard.value()), requested)
+}
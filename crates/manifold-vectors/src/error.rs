@@ -0,0 +1,94 @@
+//! Error type for vector table operations.
+
+use manifold::{StorageError, TableError};
+use std::fmt;
+
+use crate::dense::VectorOptions;
+
+/// Error returned by vector table operations.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum VectorError {
+    /// The table was created with a different vector dimension than the one requested.
+    DimensionMismatch {
+        /// Dimension recorded when the table was created.
+        stored: usize,
+        /// Dimension the table was opened with.
+        requested: usize,
+    },
+    /// The table was created with different [`VectorOptions`] than the ones requested.
+    OptionsMismatch {
+        /// Options recorded when the table was created.
+        stored: VectorOptions,
+        /// Options the table was opened with.
+        requested: VectorOptions,
+    },
+    /// A zero vector was inserted into a table that normalizes vectors on insert.
+    ZeroVector,
+    /// A payload longer than [`MAX_PAYLOAD_LEN`](crate::dense::MAX_PAYLOAD_LEN) was inserted.
+    PayloadTooLarge {
+        /// Length of the payload.
+        len: usize,
+        /// Maximum length of a payload.
+        max: usize,
+    },
+    /// A payload was inserted into a table created without
+    /// [`VectorOptions::store_payload`].
+    PayloadNotStored,
+    /// Error opening the underlying table.
+    Table(TableError),
+    /// Error reading or writing the underlying storage.
+    Storage(StorageError),
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DimensionMismatch { stored, requested } => write!(
+                f,
+                "Vector dimension mismatch: table stores {stored} dimensions but {requested} were requested"
+            ),
+            Self::OptionsMismatch { stored, requested } => write!(
+                f,
+                "Vector options mismatch: table stores {stored:?} but {requested:?} were requested"
+            ),
+            Self::ZeroVector => write!(f, "Cannot normalize a zero vector"),
+            Self::PayloadTooLarge { len, max } => write!(
+                f,
+                "Vector payload of {len} bytes exceeds the maximum of {max} bytes"
+            ),
+            Self::PayloadNotStored => write!(
+                f,
+                "Cannot store a payload in a vector table created without payloads"
+            ),
+            Self::Table(err) => write!(f, "Table error: {err}"),
+            Self::Storage(err) => write!(f, "Storage error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for VectorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DimensionMismatch { .. }
+            | Self::OptionsMismatch { .. }
+            | Self::ZeroVector
+            | Self::PayloadTooLarge { .. }
+            | Self::PayloadNotStored => None,
+            Self::Table(err) => Some(err),
+            Self::Storage(err) => Some(err),
+        }
+    }
+}
+
+impl From<TableError> for VectorError {
+    fn from(err: TableError) -> Self {
+        Self::Table(err)
+    }
+}
+
+impl From<StorageError> for VectorError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
@@ -0,0 +1,134 @@
+//! Opaque index blobs stored next to a vector table.
+//!
+//! An external index, such as an HNSW graph built over the table, can persist its serialized
+//! form with [`VectorTable::put_index_blob`](crate::VectorTable::put_index_blob) and load it
+//! back on start with [`VectorTableRead::get_index_blob`](crate::VectorTableRead::get_index_blob)
+//! instead of rebuilding from every vector. Each blob is split into chunks of
+//! [`INDEX_BLOB_CHUNK_LEN`] bytes, so a blob of several gigabytes is never one value.
+
+use manifold::{
+    ReadOnlyTable, ReadTransaction, StorageError, Table, TableDefinition, TableError,
+    WriteTransaction,
+};
+
+use crate::error::VectorError;
+
+/// Length of the chunks an index blob is stored in, except the last one, which may be shorter.
+pub const INDEX_BLOB_CHUNK_LEN: u32 = 1024 * 1024;
+
+/// Returns the name of the table holding the version and length of each index blob of the
+/// vector table `name`.
+pub(crate) fn table_name(name: &str) -> String {
+    format!("manifold_vectors::index_blobs::{name}")
+}
+
+/// Returns the name of the table holding the chunks of each index blob of the vector table
+/// `name`.
+pub(crate) fn chunks_table_name(name: &str) -> String {
+    format!("manifold_vectors::index_blob_chunks::{name}")
+}
+
+/// Version, length in bytes and chunk length of a stored blob.
+type BlobMeta = (u64, u64, u32);
+
+/// Returns the number of chunks a blob of `len` bytes is stored in.
+fn chunk_count(len: u64, chunk_len: u32) -> u64 {
+    len.div_ceil(u64::from(chunk_len))
+}
+
+/// Writer side of the index blobs of a vector table.
+pub(crate) struct IndexBlobs<'txn> {
+    meta: Table<'txn, &'static str, BlobMeta>,
+    chunks: Table<'txn, (&'static str, u64), &'static [u8]>,
+}
+
+impl<'txn> IndexBlobs<'txn> {
+    /// Opens the index blobs of the vector table `name`, creating their tables if they do not
+    /// exist.
+    pub(crate) fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        let meta_name = table_name(name);
+        let chunks_name = chunks_table_name(name);
+        let meta_def: TableDefinition<&str, BlobMeta> = TableDefinition::new(&meta_name);
+        let chunks_def: TableDefinition<(&str, u64), &[u8]> = TableDefinition::new(&chunks_name);
+        Ok(Self {
+            meta: txn.open_table(meta_def)?,
+            chunks: txn.open_table(chunks_def)?,
+        })
+    }
+
+    /// Stores `bytes` as the blob `blob`, replacing any earlier version and its chunks.
+    pub(crate) fn put(
+        &mut self,
+        blob: &str,
+        version: u64,
+        bytes: &[u8],
+    ) -> Result<(), VectorError> {
+        self.delete(blob)?;
+        for (i, chunk) in bytes.chunks(INDEX_BLOB_CHUNK_LEN as usize).enumerate() {
+            self.chunks.insert((blob, i as u64), chunk)?;
+        }
+        self.meta
+            .insert(blob, (version, bytes.len() as u64, INDEX_BLOB_CHUNK_LEN))?;
+        Ok(())
+    }
+
+    /// Removes the blob `blob` and its chunks, returning whether it existed.
+    pub(crate) fn delete(&mut self, blob: &str) -> Result<bool, VectorError> {
+        let Some((_, len, chunk_len)) = self.meta.remove(blob)?.map(|guard| guard.value()) else {
+            return Ok(false);
+        };
+        for i in 0..chunk_count(len, chunk_len) {
+            self.chunks.remove((blob, i))?;
+        }
+        Ok(true)
+    }
+}
+
+/// Reader side of the index blobs of a vector table.
+pub(crate) struct IndexBlobsRead {
+    meta: ReadOnlyTable<&'static str, BlobMeta>,
+    chunks: ReadOnlyTable<(&'static str, u64), &'static [u8]>,
+}
+
+impl IndexBlobsRead {
+    /// Opens the index blobs of the vector table `name`, if it has any.
+    pub(crate) fn open(txn: &ReadTransaction, name: &str) -> Result<Option<Self>, VectorError> {
+        let meta_name = table_name(name);
+        let chunks_name = chunks_table_name(name);
+        let meta_def: TableDefinition<&str, BlobMeta> = TableDefinition::new(&meta_name);
+        let chunks_def: TableDefinition<(&str, u64), &[u8]> = TableDefinition::new(&chunks_name);
+        let meta = match txn.open_table(meta_def) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(Self {
+            meta,
+            chunks: txn.open_table(chunks_def)?,
+        }))
+    }
+
+    /// Reads the version and bytes of the blob `blob`.
+    pub(crate) fn get(&self, blob: &str) -> Result<Option<(u64, Vec<u8>)>, VectorError> {
+        let Some((version, len, chunk_len)) = self.meta.get(blob)?.map(|guard| guard.value())
+        else {
+            return Ok(None);
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let mut bytes = Vec::with_capacity(len as usize);
+        for i in 0..chunk_count(len, chunk_len) {
+            let chunk = self.chunks.get((blob, i))?.ok_or_else(|| {
+                StorageError::Corrupted(format!("Missing chunk {i} of index blob {blob}"))
+            })?;
+            bytes.extend_from_slice(chunk.value());
+        }
+        if bytes.len() as u64 != len {
+            return Err(StorageError::Corrupted(format!(
+                "Index blob {blob} is {} bytes, expected {len}",
+                bytes.len()
+            ))
+            .into());
+        }
+        Ok(Some((version, bytes)))
+    }
+}
@@ -1,7 +1,7 @@
 //! Integration traits for external vector index libraries.
 
 use crate::dense::{VectorGuard, VectorIter};
-use manifold::StorageError;
+use crate::error::VectorError;
 use uuid::Uuid;
 
 /// Trait for vector sources consumable by index builders
@@ -10,20 +10,20 @@ use uuid::Uuid;
 /// to efficiently iterate over vectors with zero-copy access.
 pub trait VectorSource<const DIM: usize> {
     /// Iterator type over vectors with zero-copy access
-    type Iter<'a>: Iterator<Item = Result<(Uuid, VectorGuard<'a, DIM>), StorageError>>
+    type Iter<'a>: Iterator<Item = Result<(Uuid, VectorGuard<'a, DIM>), VectorError>>
     where
         Self: 'a;
 
     /// Returns an iterator over all vectors
     ///
     /// The iterator provides zero-copy access to vector data through guards.
-    fn all_vectors(&self) -> Result<Self::Iter<'_>, StorageError>;
+    fn all_vectors(&self) -> Result<Self::Iter<'_>, VectorError>;
 
     /// Returns the number of vectors
-    fn len(&self) -> Result<u64, StorageError>;
+    fn len(&self) -> Result<u64, VectorError>;
 
     /// Returns true if empty
-    fn is_empty(&self) -> Result<bool, StorageError> {
+    fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 }
@@ -34,11 +34,11 @@ impl<const DIM: usize> VectorSource<DIM> for crate::dense::VectorTableRead<DIM>
     where
         Self: 'a;
 
-    fn all_vectors(&self) -> Result<Self::Iter<'_>, StorageError> {
+    fn all_vectors(&self) -> Result<Self::Iter<'_>, VectorError> {
         self.all_vectors()
     }
 
-    fn len(&self) -> Result<u64, StorageError> {
+    fn len(&self) -> Result<u64, VectorError> {
         self.len()
     }
 }
@@ -0,0 +1,122 @@
+//! Joined scans of vector and property tables.
+//!
+//! Retrieval usually reads the embedding of a document together with some of its
+//! properties. Vector tables and property tables are both ordered by entity id, so
+//! [`joined_scan`] advances the two scans in lockstep as a merge join, traversing each table
+//! once instead of looking every property up separately.
+//!
+//! Requires the `properties` feature.
+
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+
+use manifold_properties::{PropertyGuard, PropertyIter, PropertyTableRead};
+use uuid::Uuid;
+
+use crate::dense::{VectorGuard, VectorIter, VectorTableRead, prefix_range};
+use crate::error::VectorError;
+
+/// Scans the vectors with keys in `keys`, each joined with its `property` from `properties`.
+///
+/// Yields `(key, vector, property)` in key order, with `None` for a vector whose entity has
+/// no such property. Properties of entities without a vector are skipped. Expired
+/// properties are treated as missing unless the property table's
+/// [`ExpiryPolicy`](manifold_properties::ExpiryPolicy) includes them.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use manifold_properties::PropertyTableRead;
+/// use manifold_vectors::VectorTableRead;
+/// use manifold_vectors::join::joined_scan;
+/// # use manifold::column_family::ColumnFamilyDatabase;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let db = ColumnFamilyDatabase::open("test.db")?;
+/// # let cf = db.column_family_or_create("documents")?;
+/// let read_txn = cf.begin_read()?;
+/// let embeddings = VectorTableRead::<768>::open(&read_txn, "embeddings")?;
+/// let properties = PropertyTableRead::open(&read_txn, "properties")?;
+///
+/// for row in joined_scan(&embeddings, &properties, "title", ..)? {
+///     let (id, embedding, title) = row?;
+///     let title = title.as_ref().and_then(|guard| guard.as_str());
+///     println!("{id}: {title:?}, norm {}", embedding.norm());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn joined_scan<'a, const DIM: usize>(
+    vectors: &'a VectorTableRead<DIM>,
+    properties: &'a PropertyTableRead,
+    property: &str,
+    keys: impl RangeBounds<Uuid> + Clone,
+) -> Result<JoinedIter<'a, DIM>, VectorError> {
+    Ok(JoinedIter {
+        vectors: vectors.range_iter(keys.clone())?,
+        properties: properties.entity_range(keys)?,
+        property: property.to_string(),
+        pending: None,
+    })
+}
+
+/// Like [`joined_scan`], over the vectors whose key starts with `prefix`.
+///
+/// Keys are matched as by [`VectorTableRead::prefix_iter`].
+pub fn joined_prefix_scan<'a, const DIM: usize>(
+    vectors: &'a VectorTableRead<DIM>,
+    properties: &'a PropertyTableRead,
+    property: &str,
+    prefix: &[u8],
+) -> Result<JoinedIter<'a, DIM>, VectorError> {
+    match prefix_range(prefix) {
+        Some(range) => joined_scan(vectors, properties, property, range),
+        None => joined_scan(vectors, properties, property, Uuid::nil()..Uuid::nil()),
+    }
+}
+
+/// Iterator over vectors joined with a property, returned by [`joined_scan`].
+pub struct JoinedIter<'a, const DIM: usize> {
+    vectors: VectorIter<'a, DIM>,
+    properties: PropertyIter<'a>,
+    property: String,
+    // The next property row the vector scan has not passed yet
+    pending: Option<(Uuid, String, PropertyGuard<'a>)>,
+}
+
+impl<'a, const DIM: usize> JoinedIter<'a, DIM> {
+    /// Advances the property scan past every row ordered before (`key`, property), and
+    /// takes the row of `key` if it is next.
+    fn property_of(&mut self, key: Uuid) -> Result<Option<PropertyGuard<'a>>, VectorError> {
+        loop {
+            if self.pending.is_none() {
+                let Some(row) = self.properties.next() else {
+                    return Ok(None);
+                };
+                let ((id, name), guard) = row?;
+                self.pending = Some((id, name, guard));
+            }
+
+            let (id, name, _) = self.pending.as_ref().unwrap();
+            match (*id, name.as_str()).cmp(&(key, self.property.as_str())) {
+                Ordering::Less => self.pending = None,
+                Ordering::Equal => return Ok(self.pending.take().map(|(_, _, guard)| guard)),
+                Ordering::Greater => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<'a, const DIM: usize> Iterator for JoinedIter<'a, DIM> {
+    type Item = Result<(Uuid, VectorGuard<'a, DIM>, Option<PropertyGuard<'a>>), VectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, vector) = match self.vectors.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(
+            self.property_of(key)
+                .map(|property| (key, vector, property)),
+        )
+    }
+}
@@ -12,6 +12,12 @@
 //! - **Type safety**: Compile-time dimension checking via const generics
 //! - **High performance**: Bulk operations, efficient encoding, WAL group commit
 //! - **Multiple formats**: Dense, sparse (COO), and multi-vector (ColBERT-style) support
+//! - **Quantization**: f16 and int8 storage for large embedding collections
+//! - **Change log**: Optional per-table log of inserts and removals for incremental indexing
+//! - **Index blobs**: Versioned, chunked storage for serialized external indexes
+//! - **Parallel scans**: Full scans split into key range shards for multi-threaded index builds
+//! - **Payloads**: Optional per-vector bytes stored in the same row, for re-ranking
+//! - **Sparse retrieval**: Optional inverted index for top-k dot product queries over sparse vectors
 //! - **Integration-ready**: Traits for external index libraries (HNSW, FAISS, etc.)
 //!
 //! ## Quick Start
@@ -73,12 +79,26 @@
     clippy::missing_panics_doc
 )]
 
+pub mod changelog;
 pub mod dense;
+mod dimension;
 pub mod distance;
+pub mod error;
+pub mod index_blob;
 pub mod integration;
+#[cfg(feature = "properties")]
+pub mod join;
 pub mod multi;
+mod options;
+pub mod quantized;
 pub mod sparse;
+mod sparse_index;
 
-pub use dense::{VectorGuard, VectorTable, VectorTableRead};
+pub use changelog::{ChangeIter, ChangeOp};
+pub use dense::{VectorGuard, VectorOptions, VectorTable, VectorTableRead};
+pub use distance::Metric;
+pub use error::VectorError;
+pub use index_blob::INDEX_BLOB_CHUNK_LEN;
 pub use multi::{MultiVectorTable, MultiVectorTableRead};
+pub use quantized::{QuantizedGuard, QuantizedVectorTable, QuantizedVectorTableRead};
 pub use sparse::{SparseVector, SparseVectorTable, SparseVectorTableRead};
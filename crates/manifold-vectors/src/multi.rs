@@ -1,68 +1,244 @@
 //! Multi-vector storage for token-level embeddings.
+//!
+//! Each vector of a document is stored under its own key `(doc_id, token_index)`, so tokens
+//! can be appended or truncated without rewriting the rest of the document. The number of
+//! vectors of each document is kept in `manifold_vectors::token_counts::{name}`.
+//!
+//! Tables written before vectors were stored per token hold each document as one row. They
+//! are upgraded as part of the write transaction the first time they are opened with
+//! [`MultiVectorTable::open`]; until then, [`MultiVectorTableRead::open`] fails with
+//! [`VectorError::Table`].
 use manifold::{
-    ReadOnlyTable, ReadTransaction, ReadableTableMetadata, StorageError, Table, TableDefinition,
+    ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata, Table, TableDefinition,
     TableError, WriteTransaction,
 };
 use uuid::Uuid;
 
+use crate::dimension;
+use crate::distance;
+use crate::error::VectorError;
+
+/// Returns the name of the table holding the vector counts of the multi-vector table `name`.
+fn counts_table_name(name: &str) -> String {
+    format!("manifold_vectors::token_counts::{name}")
+}
+
+/// Converts a number of vectors to a token index.
+fn token_index(len: usize) -> u32 {
+    u32::try_from(len).expect("a document holds at most u32::MAX vectors")
+}
+
+/// Opens the token table of `name`, first upgrading it if it holds each document as one row.
+fn open_tokens<'txn, const DIM: usize>(
+    txn: &'txn WriteTransaction,
+    name: &str,
+) -> Result<Table<'txn, (Uuid, u32), [f32; DIM]>, TableError> {
+    let def: TableDefinition<(Uuid, u32), [f32; DIM]> = TableDefinition::new(name);
+    match txn.open_table(def) {
+        Err(TableError::TableTypeMismatch { .. }) => {
+            upgrade::<DIM>(txn, name)?;
+            txn.open_table(def)
+        }
+        result => result,
+    }
+}
+
+/// Rewrites a table holding each document as one row with a key per token.
+///
+/// The documents are buffered in memory while the table is recreated.
+fn upgrade<const DIM: usize>(txn: &WriteTransaction, name: &str) -> Result<(), TableError> {
+    let legacy_def: TableDefinition<Uuid, Vec<[f32; DIM]>> = TableDefinition::new(name);
+    let mut documents = Vec::new();
+    {
+        let legacy = txn.open_table(legacy_def)?;
+        for item in legacy.iter()? {
+            let (key_guard, value_guard) = item?;
+            documents.push((key_guard.value(), value_guard.value()));
+        }
+    }
+    txn.delete_table(legacy_def)?;
+
+    let def: TableDefinition<(Uuid, u32), [f32; DIM]> = TableDefinition::new(name);
+    let mut tokens = txn.open_table(def)?;
+    let counts_name = counts_table_name(name);
+    let counts_def: TableDefinition<Uuid, u32> = TableDefinition::new(&counts_name);
+    let mut counts = txn.open_table(counts_def)?;
+    for (key, vectors) in &documents {
+        for (index, vector) in vectors.iter().enumerate() {
+            tokens.insert((*key, token_index(index)), vector)?;
+        }
+        counts.insert(key, token_index(vectors.len()))?;
+    }
+    Ok(())
+}
+
 /// Table for storing multi-vectors (sequences of vectors)
 pub struct MultiVectorTable<'txn, const DIM: usize> {
-    table: Table<'txn, Uuid, Vec<[f32; DIM]>>,
+    tokens: Table<'txn, (Uuid, u32), [f32; DIM]>,
+    counts: Table<'txn, Uuid, u32>,
 }
 
 impl<'txn, const DIM: usize> MultiVectorTable<'txn, DIM> {
     /// Opens a multi-vector table for writing
-    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TableError> {
-        let def: TableDefinition<Uuid, Vec<[f32; DIM]>> = TableDefinition::new(name);
-        let table = txn.open_table(def)?;
-        Ok(Self { table })
+    ///
+    /// Fails with [`VectorError::DimensionMismatch`] if the table was created with a
+    /// different `DIM`. A table that holds each document as one row is upgraded to a row per
+    /// token as part of `txn`.
+    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        let tokens = dimension::open_checked(txn, name, DIM, || open_tokens(txn, name))?;
+        let counts_name = counts_table_name(name);
+        let counts_def: TableDefinition<Uuid, u32> = TableDefinition::new(&counts_name);
+        let counts = txn.open_table(counts_def)?;
+        Ok(Self { tokens, counts })
     }
 
-    /// Inserts a sequence of vectors
-    pub fn insert(&mut self, key: &Uuid, vectors: &[[f32; DIM]]) -> Result<(), TableError> {
-        self.table.insert(key, &vectors.to_vec())?;
+    /// Inserts a sequence of vectors, replacing the vectors the document had before
+    pub fn insert(&mut self, key: &Uuid, vectors: &[[f32; DIM]]) -> Result<(), VectorError> {
+        let old_len = self.count(key)?.unwrap_or(0);
+        let len = token_index(vectors.len());
+        for (index, vector) in vectors.iter().enumerate() {
+            self.tokens.insert((*key, token_index(index)), vector)?;
+        }
+        if old_len > len {
+            self.tokens
+                .retain_in((*key, len)..(*key, old_len), |_, _| false)?;
+        }
+        self.counts.insert(key, len)?;
         Ok(())
     }
 
+    /// Appends vectors after the ones the document already has, without rewriting them
+    ///
+    /// A document that is not stored yet is created. Returns the number of vectors of the
+    /// document afterwards.
+    pub fn append_vectors(
+        &mut self,
+        key: &Uuid,
+        vectors: &[[f32; DIM]],
+    ) -> Result<usize, VectorError> {
+        let start = self.count(key)?.unwrap_or(0);
+        let len = token_index(start as usize + vectors.len());
+        for (offset, vector) in vectors.iter().enumerate() {
+            self.tokens
+                .insert((*key, start + token_index(offset)), vector)?;
+        }
+        self.counts.insert(key, len)?;
+        Ok(len as usize)
+    }
+
+    /// Removes the vectors of a document from `new_len` on, keeping the first `new_len`
+    ///
+    /// Does nothing if the document has no more than `new_len` vectors, or is not stored.
+    /// Returns the number of vectors removed.
+    pub fn truncate_vectors(&mut self, key: &Uuid, new_len: usize) -> Result<usize, VectorError> {
+        let Some(len) = self.count(key)? else {
+            return Ok(0);
+        };
+        let Ok(new_len) = u32::try_from(new_len) else {
+            return Ok(0);
+        };
+        if new_len >= len {
+            return Ok(0);
+        }
+        self.tokens
+            .retain_in((*key, new_len)..(*key, len), |_, _| false)?;
+        self.counts.insert(key, new_len)?;
+        Ok((len - new_len) as usize)
+    }
+
+    /// Returns the number of vectors of a document, or `None` if it is not stored
+    pub fn vector_count(&self, key: &Uuid) -> Result<Option<usize>, VectorError> {
+        Ok(self.count(key)?.map(|count| count as usize))
+    }
+
+    fn count(&self, key: &Uuid) -> Result<Option<u32>, VectorError> {
+        Ok(self.counts.get(key)?.map(|guard| guard.value()))
+    }
+
     /// Returns the number of entries stored
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.table.len()
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.counts.len()?)
     }
 
     /// Returns true if the table is empty
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 }
 
 /// Read-only multi-vector table
 pub struct MultiVectorTableRead<const DIM: usize> {
-    table: ReadOnlyTable<Uuid, Vec<[f32; DIM]>>,
+    tokens: ReadOnlyTable<(Uuid, u32), [f32; DIM]>,
+    counts: ReadOnlyTable<Uuid, u32>,
 }
 
 impl<const DIM: usize> MultiVectorTableRead<DIM> {
     /// Opens a multi-vector table for reading
-    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, StorageError> {
-        let def: TableDefinition<Uuid, Vec<[f32; DIM]>> = TableDefinition::new(name);
-        let table = txn.open_table(def).map_err(|e| match e {
-            TableError::Storage(s) => s,
-            _ => StorageError::Io(std::io::Error::other(e)),
-        })?;
-        Ok(Self { table })
+    ///
+    /// Fails with [`VectorError::DimensionMismatch`] if the table was created with a
+    /// different `DIM`
+    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, VectorError> {
+        dimension::check_read(txn, name, DIM)?;
+        let def: TableDefinition<(Uuid, u32), [f32; DIM]> = TableDefinition::new(name);
+        let tokens = txn.open_table(def)?;
+        let counts_name = counts_table_name(name);
+        let counts_def: TableDefinition<Uuid, u32> = TableDefinition::new(&counts_name);
+        let counts = txn.open_table(counts_def)?;
+        Ok(Self { tokens, counts })
+    }
+
+    /// Retrieves a sequence of vectors by key, in token order
+    pub fn get(&self, key: &Uuid) -> Result<Option<Vec<[f32; DIM]>>, VectorError> {
+        let Some(len) = self.count(key)? else {
+            return Ok(None);
+        };
+        let mut vectors = Vec::with_capacity(len as usize);
+        for item in self.tokens.range((*key, 0)..(*key, len))? {
+            let (_, value_guard) = item?;
+            vectors.push(value_guard.value());
+        }
+        Ok(Some(vectors))
+    }
+
+    /// Returns the number of vectors of a document, or `None` if it is not stored
+    pub fn vector_count(&self, key: &Uuid) -> Result<Option<usize>, VectorError> {
+        Ok(self.count(key)?.map(|count| count as usize))
+    }
+
+    /// Scores a document against `query` by late interaction, as in `ColBERT`
+    ///
+    /// Sums, over the vectors of `query`, the largest dot product with any vector of the
+    /// document. The document's vectors are read once, in place. Returns `None` if the
+    /// document is not stored or has no vectors.
+    pub fn max_sim(&self, key: &Uuid, query: &[[f32; DIM]]) -> Result<Option<f32>, VectorError> {
+        let Some(len) = self.count(key)? else {
+            return Ok(None);
+        };
+        if len == 0 {
+            return Ok(None);
+        }
+        let mut best = vec![f32::NEG_INFINITY; query.len()];
+        for item in self.tokens.range((*key, 0)..(*key, len))? {
+            let (_, value_guard) = item?;
+            let vector = value_guard.value();
+            for (max, query_vector) in best.iter_mut().zip(query) {
+                *max = max.max(distance::dot_product(query_vector, &vector));
+            }
+        }
+        Ok(Some(best.iter().sum()))
     }
 
-    /// Retrieves a sequence of vectors by key
-    pub fn get(&self, key: &Uuid) -> Result<Option<Vec<[f32; DIM]>>, StorageError> {
-        Ok(self.table.get(key)?.map(|guard| guard.value().clone()))
+    fn count(&self, key: &Uuid) -> Result<Option<u32>, VectorError> {
+        Ok(self.counts.get(key)?.map(|guard| guard.value()))
     }
 
     /// Returns the number of entries stored
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.table.len()
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.counts.len()?)
     }
 
     /// Returns true if the table is empty
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 }
@@ -0,0 +1,92 @@
+//! Records the options of each vector table so every writer keeps its layout consistent.
+
+use crate::dense::VectorOptions;
+use crate::error::VectorError;
+use manifold::{
+    ReadTransaction, ReadableTable, TableDefinition, TableError, TableHandle, WriteTransaction,
+};
+
+// Maps vector table names to their option flags. Tables with default options have no entry,
+// so tables created before options existed read back as default
+const OPTIONS: TableDefinition<&str, u8> = TableDefinition::new("manifold_vectors::options");
+
+const NORMALIZE_ON_INSERT: u8 = 0b01;
+const STORE_NORM: u8 = 0b10;
+const STORE_PAYLOAD: u8 = 0b100;
+
+/// Returns the name of the table holding the stored norms of the vector table `name`.
+pub(crate) fn norms_table_name(name: &str) -> String {
+    format!("manifold_vectors::norms::{name}")
+}
+
+fn to_flags(options: VectorOptions) -> u8 {
+    let mut flags = 0;
+    if options.normalize_on_insert {
+        flags |= NORMALIZE_ON_INSERT;
+    }
+    if options.store_norm {
+        flags |= STORE_NORM;
+    }
+    if options.store_payload {
+        flags |= STORE_PAYLOAD;
+    }
+    flags
+}
+
+fn from_flags(flags: u8) -> VectorOptions {
+    VectorOptions {
+        normalize_on_insert: flags & NORMALIZE_ON_INSERT != 0,
+        store_norm: flags & STORE_NORM != 0,
+        store_payload: flags & STORE_PAYLOAD != 0,
+    }
+}
+
+/// Returns the options of `name` for a write transaction.
+///
+/// With `requested` set, fails with [`VectorError::OptionsMismatch`] if the table exists with
+/// other options, and records them if the table is new. Without it, returns the recorded
+/// options, so that a plain open keeps maintaining the table the way it was created.
+pub(crate) fn open_checked(
+    txn: &WriteTransaction,
+    name: &str,
+    requested: Option<VectorOptions>,
+) -> Result<VectorOptions, VectorError> {
+    let mut options = txn.open_table(OPTIONS)?;
+    let stored = options.get(name)?.map(|guard| from_flags(guard.value()));
+    let Some(requested) = requested else {
+        return Ok(stored.unwrap_or_default());
+    };
+
+    let stored = match stored {
+        Some(stored) => stored,
+        // A table without an entry was created with default options, unless it is new
+        None if table_exists(txn, name)? => VectorOptions::default(),
+        None => {
+            if requested != VectorOptions::default() {
+                options.insert(name, to_flags(requested))?;
+            }
+            return Ok(requested);
+        }
+    };
+    if stored != requested {
+        return Err(VectorError::OptionsMismatch { stored, requested });
+    }
+    Ok(stored)
+}
+
+/// Returns the recorded options of `name` for a read transaction.
+pub(crate) fn read(txn: &ReadTransaction, name: &str) -> Result<VectorOptions, VectorError> {
+    let options = match txn.open_table(OPTIONS) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(VectorOptions::default()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(options
+        .get(name)?
+        .map(|guard| from_flags(guard.value()))
+        .unwrap_or_default())
+}
+
+fn table_exists(txn: &WriteTransaction, name: &str) -> Result<bool, VectorError> {
+    Ok(txn.list_tables()?.any(|handle| handle.name() == name))
+}
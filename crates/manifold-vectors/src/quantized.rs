@@ -0,0 +1,315 @@
+//! Quantized fixed-dimension vector storage.
+//!
+//! Vectors are inserted as `f32` and stored in a compact encoding chosen by the
+//! [`Quantization`] type parameter:
+//!
+//! - [`F16`]: half precision floats, 2 bytes per dimension
+//! - [`Int8`]: symmetric 8-bit integers with a per-vector `f32` scale, 1 byte per dimension
+//!   plus 4 bytes
+//!
+//! Reads return a [`QuantizedGuard`] that exposes both the stored encoding, for cheap distance
+//! computations during candidate search, and a dequantized `[f32; DIM]` for re-ranking.
+
+use manifold::{
+    AccessGuard, ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata, Table,
+    TableDefinition, TypeName, Value, WriteTransaction,
+};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+use crate::dimension;
+use crate::error::VectorError;
+
+/// A storage encoding for quantized vectors.
+pub trait Quantization: Debug + 'static {
+    /// Name recorded in the table's value type.
+    const NAME: &'static str;
+
+    /// Returns the number of bytes used to store a vector of `dim` dimensions.
+    fn encoded_len(dim: usize) -> usize;
+
+    /// Appends the encoding of `vector` to `out`.
+    fn encode(vector: &[f32], out: &mut Vec<u8>);
+
+    /// Decodes `bytes` into `out`, which has one element per dimension.
+    fn decode(bytes: &[u8], out: &mut [f32]);
+}
+
+/// Half precision storage.
+///
+/// Values keep about three significant decimal digits. Values beyond the `f16` range of
+/// ±65504 saturate to infinity.
+#[derive(Debug)]
+pub struct F16;
+
+impl Quantization for F16 {
+    const NAME: &'static str = "f16";
+
+    fn encoded_len(dim: usize) -> usize {
+        dim * 2
+    }
+
+    fn encode(vector: &[f32], out: &mut Vec<u8>) {
+        for &x in vector {
+            out.extend_from_slice(&half::f16::from_f32(x).to_bits().to_le_bytes());
+        }
+    }
+
+    fn decode(bytes: &[u8], out: &mut [f32]) {
+        for (x, chunk) in out.iter_mut().zip(bytes.chunks_exact(2)) {
+            *x = half::f16::from_bits(u16::from_le_bytes([chunk[0], chunk[1]])).to_f32();
+        }
+    }
+}
+
+/// Symmetric 8-bit integer storage with a per-vector scale.
+///
+/// Each vector is stored as its scale, `max(|x|) / 127`, followed by one code per dimension
+/// in `-127..=127`. Dequantized values are within `scale / 2` of the inserted values.
+#[derive(Debug)]
+pub struct Int8;
+
+impl Int8 {
+    /// Quantizes `vector`, returning its codes and scale.
+    ///
+    /// Use this to quantize a query for [`distance::dot_product_i8`](crate::distance::dot_product_i8)
+    /// against stored vectors.
+    pub fn quantize(vector: &[f32]) -> (Vec<i8>, f32) {
+        let scale = Self::scale(vector);
+        let codes = vector
+            .iter()
+            .map(|&x| Self::quantize_one(x, scale))
+            .collect();
+        (codes, scale)
+    }
+
+    fn scale(vector: &[f32]) -> f32 {
+        vector.iter().fold(0.0f32, |max, x| max.max(x.abs())) / 127.0
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn quantize_one(x: f32, scale: f32) -> i8 {
+        if scale == 0.0 {
+            0
+        } else {
+            // The clamp guarantees the cast is lossless
+            (x / scale).round().clamp(-127.0, 127.0) as i8
+        }
+    }
+}
+
+impl Quantization for Int8 {
+    const NAME: &'static str = "int8";
+
+    fn encoded_len(dim: usize) -> usize {
+        4 + dim
+    }
+
+    fn encode(vector: &[f32], out: &mut Vec<u8>) {
+        let scale = Self::scale(vector);
+        out.extend_from_slice(&scale.to_le_bytes());
+        for &x in vector {
+            out.extend_from_slice(&Self::quantize_one(x, scale).to_le_bytes());
+        }
+    }
+
+    fn decode(bytes: &[u8], out: &mut [f32]) {
+        let scale = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        for (x, &code) in out.iter_mut().zip(&bytes[4..]) {
+            *x = f32::from(i8::from_le_bytes([code])) * scale;
+        }
+    }
+}
+
+/// Stored value type of a quantized vector table.
+///
+/// The value is the raw encoding of one vector. The type name records both the quantization
+/// and the dimension, so a table cannot be opened with a different encoding.
+#[derive(Debug)]
+pub struct QuantizedValue<const DIM: usize, Q: Quantization> {
+    _quantization: PhantomData<Q>,
+}
+
+impl<const DIM: usize, Q: Quantization> Value for QuantizedValue<DIM, Q> {
+    type SelfType<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(Q::encoded_len(DIM))
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> &'a [u8]
+    where
+        Self: 'a,
+    {
+        data
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a &'b [u8]) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new(&format!("manifold_vectors::{}[{DIM}]", Q::NAME))
+    }
+}
+
+/// A table storing quantized fixed-dimension vectors.
+pub struct QuantizedVectorTable<'txn, const DIM: usize, Q: Quantization> {
+    table: Table<'txn, Uuid, QuantizedValue<DIM, Q>>,
+    buffer: Vec<u8>,
+}
+
+impl<'txn, const DIM: usize, Q: Quantization> QuantizedVectorTable<'txn, DIM, Q> {
+    /// Opens a quantized vector table for writing, creating it if it does not exist.
+    ///
+    /// Fails with [`VectorError::DimensionMismatch`] if the table was created with a
+    /// different `DIM`, and with a table type mismatch if it uses a different quantization.
+    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        let def: TableDefinition<Uuid, QuantizedValue<DIM, Q>> = TableDefinition::new(name);
+        let table = dimension::open_checked(txn, name, DIM, || txn.open_table(def))?;
+        Ok(Self {
+            table,
+            buffer: Vec::with_capacity(Q::encoded_len(DIM)),
+        })
+    }
+
+    /// Quantizes `vector` and inserts it with the given key.
+    pub fn insert(&mut self, key: &Uuid, vector: &[f32; DIM]) -> Result<(), VectorError> {
+        self.buffer.clear();
+        Q::encode(vector, &mut self.buffer);
+        self.table.insert(key, self.buffer.as_slice())?;
+        Ok(())
+    }
+
+    /// Retrieves a vector by key, including changes made earlier in this transaction.
+    pub fn get(&self, key: &Uuid) -> Result<Option<QuantizedGuard<'_, DIM, Q>>, VectorError> {
+        Ok(self.table.get(key)?.map(QuantizedGuard::new))
+    }
+
+    /// Removes a vector by key.
+    ///
+    /// Returns `true` if the key was present.
+    pub fn remove(&mut self, key: &Uuid) -> Result<bool, VectorError> {
+        Ok(self.table.remove(key)?.is_some())
+    }
+
+    /// Returns the number of vectors stored in this table.
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.table.len()?)
+    }
+
+    /// Returns `true` if the table contains no vectors.
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
+        Ok(self.len()? == 0)
+    }
+}
+
+/// Read-only quantized vector table.
+pub struct QuantizedVectorTableRead<const DIM: usize, Q: Quantization> {
+    table: ReadOnlyTable<Uuid, QuantizedValue<DIM, Q>>,
+}
+
+impl<const DIM: usize, Q: Quantization> QuantizedVectorTableRead<DIM, Q> {
+    /// Opens a quantized vector table for reading.
+    ///
+    /// Fails with [`VectorError::DimensionMismatch`] if the table was created with a
+    /// different `DIM`.
+    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, VectorError> {
+        dimension::check_read(txn, name, DIM)?;
+        let def: TableDefinition<Uuid, QuantizedValue<DIM, Q>> = TableDefinition::new(name);
+        let table = txn.open_table(def)?;
+        Ok(Self { table })
+    }
+
+    /// Retrieves a vector by key.
+    pub fn get(&self, key: &Uuid) -> Result<Option<QuantizedGuard<'_, DIM, Q>>, VectorError> {
+        Ok(self.table.get(key)?.map(QuantizedGuard::new))
+    }
+
+    /// Returns the number of vectors stored in this table.
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.table.len()?)
+    }
+
+    /// Returns `true` if the table contains no vectors.
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Iterates over all vectors in the table, in key order.
+    pub fn all_vectors(&self) -> Result<QuantizedVectorIter<'_, DIM, Q>, VectorError> {
+        Ok(QuantizedVectorIter {
+            inner: self.table.iter()?,
+        })
+    }
+}
+
+/// A guard providing access to a stored quantized vector.
+///
+/// The stored encoding is read in place; [`dequantize`](Self::dequantize) decodes a copy.
+pub struct QuantizedGuard<'a, const DIM: usize, Q: Quantization> {
+    guard: AccessGuard<'a, QuantizedValue<DIM, Q>>,
+}
+
+impl<'a, const DIM: usize, Q: Quantization> QuantizedGuard<'a, DIM, Q> {
+    fn new(guard: AccessGuard<'a, QuantizedValue<DIM, Q>>) -> Self {
+        Self { guard }
+    }
+
+    /// Returns the stored encoding of the vector.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.guard.value()
+    }
+
+    /// Decodes the vector to `f32`.
+    pub fn dequantize(&self) -> [f32; DIM] {
+        let mut vector = [0.0; DIM];
+        Q::decode(self.as_bytes(), &mut vector);
+        vector
+    }
+}
+
+impl<const DIM: usize> QuantizedGuard<'_, DIM, Int8> {
+    /// Returns the scale that converts codes back to `f32` values.
+    pub fn scale(&self) -> f32 {
+        let bytes = self.as_bytes();
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    /// Returns the stored codes, one per dimension.
+    pub fn codes(&self) -> &[i8] {
+        let codes = &self.as_bytes()[4..];
+        // SAFETY: i8 and u8 have the same size and alignment, and every bit pattern is valid
+        unsafe { std::slice::from_raw_parts(codes.as_ptr().cast::<i8>(), codes.len()) }
+    }
+}
+
+/// Iterator over vectors in a `QuantizedVectorTableRead`.
+pub struct QuantizedVectorIter<'a, const DIM: usize, Q: Quantization> {
+    inner: manifold::Range<'a, Uuid, QuantizedValue<DIM, Q>>,
+}
+
+impl<'a, const DIM: usize, Q: Quantization> Iterator for QuantizedVectorIter<'a, DIM, Q> {
+    type Item = Result<(Uuid, QuantizedGuard<'a, DIM, Q>), VectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|result| {
+            result
+                .map(|(key_guard, value_guard)| {
+                    (key_guard.value(), QuantizedGuard::new(value_guard))
+                })
+                .map_err(VectorError::from)
+        })
+    }
+}
@@ -1,10 +1,14 @@
 //! Sparse vector storage using COO format.
 use manifold::{
-    ReadOnlyTable, ReadTransaction, ReadableTableMetadata, StorageError, Table, TableDefinition,
-    TableError, WriteTransaction,
+    ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata, Table, TableDefinition,
+    WriteTransaction,
 };
+use std::cmp::Ordering;
 use uuid::Uuid;
 
+use crate::error::VectorError;
+use crate::sparse_index::{SparseIndex, SparseIndexRead};
+
 /// A sparse vector represented as (index, value) pairs.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SparseVector {
@@ -31,52 +35,117 @@ impl SparseVector {
 
     /// Computes the dot product with another sparse vector
     pub fn dot(&self, other: &Self) -> f32 {
-        let mut result = 0.0;
-        let mut i = 0;
-        let mut j = 0;
-        while i < self.entries.len() && j < other.entries.len() {
-            let (idx_a, val_a) = self.entries[i];
-            let (idx_b, val_b) = other.entries[j];
-            match idx_a.cmp(&idx_b) {
-                std::cmp::Ordering::Equal => {
-                    result += val_a * val_b;
-                    i += 1;
-                    j += 1;
-                }
-                std::cmp::Ordering::Less => i += 1,
-                std::cmp::Ordering::Greater => j += 1,
+        overlapping_dot(&self.entries, &other.entries).unwrap_or(0.0)
+    }
+}
+
+/// Computes the dot product of two sparse vectors, or `None` if they have no index in common.
+fn overlapping_dot(a: &[(u32, f32)], b: &[(u32, f32)]) -> Option<f32> {
+    let mut result = None;
+    let mut i = 0;
+    let mut j = 0;
+    while i < a.len() && j < b.len() {
+        let (idx_a, val_a) = a[i];
+        let (idx_b, val_b) = b[j];
+        match idx_a.cmp(&idx_b) {
+            Ordering::Equal => {
+                *result.get_or_insert(0.0) += val_a * val_b;
+                i += 1;
+                j += 1;
             }
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
         }
-        result
     }
+    result
+}
+
+/// Returns the `k` highest scores, highest first, with ties broken by key.
+fn top_k(scores: impl IntoIterator<Item = (Uuid, f32)>, k: usize) -> Vec<(Uuid, f32)> {
+    let order = |a: &(Uuid, f32), b: &(Uuid, f32)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+
+    let mut scores: Vec<(Uuid, f32)> = scores.into_iter().collect();
+    if k == 0 {
+        return Vec::new();
+    }
+    if scores.len() > k {
+        scores.select_nth_unstable_by(k - 1, order);
+        scores.truncate(k);
+    }
+    scores.sort_unstable_by(order);
+    scores
 }
 
 /// Table for storing sparse vectors
 pub struct SparseVectorTable<'txn> {
     table: Table<'txn, Uuid, Vec<(u32, f32)>>,
+    index: Option<SparseIndex<'txn>>,
 }
 
 impl<'txn> SparseVectorTable<'txn> {
     /// Opens a sparse vector table for writing
-    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, TableError> {
+    pub fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
         let def: TableDefinition<Uuid, Vec<(u32, f32)>> = TableDefinition::new(name);
         let table = txn.open_table(def)?;
-        Ok(Self { table })
+        Ok(Self { table, index: None })
     }
 
-    /// Inserts a sparse vector
-    pub fn insert(&mut self, key: &Uuid, vector: &SparseVector) -> Result<(), TableError> {
-        self.table.insert(key, &vector.entries)?;
+    /// Opens a sparse vector table for writing with its inverted index enabled.
+    ///
+    /// Every insert and removal through the returned table also updates a posting per
+    /// non-zero entry, keyed by `(index, key)`, in the same transaction, so that
+    /// [`SparseVectorTableRead::top_k_dot`] only reads the postings of the query's dimensions.
+    /// The first time the index is enabled on a table that already holds vectors, they are
+    /// indexed before this returns. Changes made through a table opened with
+    /// [`open`](Self::open) do not update the index, so every writer of an indexed table
+    /// should open it this way.
+    ///
+    /// Vectors are expected to have distinct indices, as they do when built with
+    /// [`SparseVector::new`] from distinct entries.
+    pub fn open_with_index(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        let mut table = Self::open(txn, name)?;
+        let mut index = SparseIndex::open(txn, name)?;
+        if index.is_empty()? {
+            for item in table.table.iter()? {
+                let (key, entries) = item?;
+                index.add(&key.value(), &entries.value())?;
+            }
+        }
+        table.index = Some(index);
+        Ok(table)
+    }
+
+    /// Inserts a sparse vector, replacing any vector with the same key
+    pub fn insert(&mut self, key: &Uuid, vector: &SparseVector) -> Result<(), VectorError> {
+        let replaced = self.table.insert(key, &vector.entries)?;
+        if let Some(index) = &mut self.index {
+            if let Some(replaced) = replaced {
+                index.remove(key, &replaced.value())?;
+            }
+            index.add(key, &vector.entries)?;
+        }
         Ok(())
     }
 
+    /// Removes a sparse vector by key, returning it if it existed
+    pub fn remove(&mut self, key: &Uuid) -> Result<Option<SparseVector>, VectorError> {
+        let Some(removed) = self.table.remove(key)? else {
+            return Ok(None);
+        };
+        let entries = removed.value();
+        if let Some(index) = &mut self.index {
+            index.remove(key, &entries)?;
+        }
+        Ok(Some(SparseVector { entries }))
+    }
+
     /// Returns the number of vectors stored
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.table.len()
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.table.len()?)
     }
 
     /// Returns true if the table is empty
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 }
@@ -84,33 +153,66 @@ impl<'txn> SparseVectorTable<'txn> {
 /// Read-only sparse vector table
 pub struct SparseVectorTableRead {
     table: ReadOnlyTable<Uuid, Vec<(u32, f32)>>,
+    index: Option<SparseIndexRead>,
 }
 
 impl SparseVectorTableRead {
     /// Opens a sparse vector table for reading
-    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, StorageError> {
+    pub fn open(txn: &ReadTransaction, name: &str) -> Result<Self, VectorError> {
         let def: TableDefinition<Uuid, Vec<(u32, f32)>> = TableDefinition::new(name);
-        let table = txn.open_table(def).map_err(|e| match e {
-            TableError::Storage(s) => s,
-            _ => StorageError::Io(std::io::Error::other(e)),
-        })?;
-        Ok(Self { table })
+        let table = txn.open_table(def)?;
+        let index = SparseIndexRead::open(txn, name)?;
+        Ok(Self { table, index })
+    }
+
+    /// Returns `true` if the table has an inverted index, enabled by
+    /// [`SparseVectorTable::open_with_index`].
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Returns the `k` stored vectors with the highest dot product with `query`, highest first.
+    ///
+    /// Only vectors sharing at least one index with `query` are considered, so fewer than `k`
+    /// results may be returned. Ties are broken by key. `query` is expected to have distinct
+    /// indices.
+    ///
+    /// With an inverted index, only the postings of the query's indices are read, and the
+    /// scores held in memory are bounded by the number of vectors sharing an index with the
+    /// query. Without one, every stored vector is read.
+    pub fn top_k_dot(
+        &self,
+        query: &SparseVector,
+        k: usize,
+    ) -> Result<Vec<(Uuid, f32)>, VectorError> {
+        if let Some(index) = &self.index {
+            return Ok(top_k(index.scores(&query.entries)?, k));
+        }
+
+        let mut scores = Vec::new();
+        for item in self.table.iter()? {
+            let (key, entries) = item?;
+            if let Some(score) = overlapping_dot(&query.entries, &entries.value()) {
+                scores.push((key.value(), score));
+            }
+        }
+        Ok(top_k(scores, k))
     }
 
     /// Retrieves a sparse vector by key
-    pub fn get(&self, key: &Uuid) -> Result<Option<SparseVector>, StorageError> {
+    pub fn get(&self, key: &Uuid) -> Result<Option<SparseVector>, VectorError> {
         Ok(self.table.get(key)?.map(|guard| SparseVector {
             entries: guard.value().clone(),
         }))
     }
 
     /// Returns the number of vectors stored
-    pub fn len(&self) -> Result<u64, StorageError> {
-        self.table.len()
+    pub fn len(&self) -> Result<u64, VectorError> {
+        Ok(self.table.len()?)
     }
 
     /// Returns true if the table is empty
-    pub fn is_empty(&self) -> Result<bool, StorageError> {
+    pub fn is_empty(&self) -> Result<bool, VectorError> {
         Ok(self.len()? == 0)
     }
 }
@@ -0,0 +1,91 @@
+//! Inverted index of a sparse vector table.
+//!
+//! A [`SparseVectorTable`](crate::SparseVectorTable) opened with
+//! [`open_with_index`](crate::SparseVectorTable::open_with_index) keeps a sibling table of
+//! postings keyed by `(dimension, key)`, holding the weight of dimension `dimension` in the
+//! vector `key`. Scoring a query then only reads the postings of the query's dimensions.
+
+use manifold::{
+    ReadOnlyTable, ReadTransaction, ReadableTableMetadata, Table, TableDefinition, TableError,
+    WriteTransaction,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::VectorError;
+
+/// Returns the name of the postings table of the sparse vector table `name`.
+pub(crate) fn table_name(name: &str) -> String {
+    format!("manifold_vectors::postings::{name}")
+}
+
+/// Writer side of an inverted index.
+pub(crate) struct SparseIndex<'txn> {
+    table: Table<'txn, (u32, Uuid), f32>,
+}
+
+impl<'txn> SparseIndex<'txn> {
+    /// Opens the postings of the sparse vector table `name`, creating them if they do not
+    /// exist.
+    pub(crate) fn open(txn: &'txn WriteTransaction, name: &str) -> Result<Self, VectorError> {
+        let postings_name = table_name(name);
+        let def: TableDefinition<(u32, Uuid), f32> = TableDefinition::new(&postings_name);
+        Ok(Self {
+            table: txn.open_table(def)?,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> Result<bool, VectorError> {
+        Ok(self.table.is_empty()?)
+    }
+
+    /// Adds a posting for every entry of the vector `key`.
+    pub(crate) fn add(&mut self, key: &Uuid, entries: &[(u32, f32)]) -> Result<(), VectorError> {
+        for &(index, weight) in entries {
+            self.table.insert((index, *key), weight)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the postings of the entries of the vector `key`.
+    pub(crate) fn remove(&mut self, key: &Uuid, entries: &[(u32, f32)]) -> Result<(), VectorError> {
+        for &(index, _) in entries {
+            self.table.remove((index, *key))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reader side of an inverted index.
+pub(crate) struct SparseIndexRead {
+    table: ReadOnlyTable<(u32, Uuid), f32>,
+}
+
+impl SparseIndexRead {
+    /// Opens the postings of the sparse vector table `name`, if it has them.
+    pub(crate) fn open(txn: &ReadTransaction, name: &str) -> Result<Option<Self>, VectorError> {
+        let postings_name = table_name(name);
+        let def: TableDefinition<(u32, Uuid), f32> = TableDefinition::new(&postings_name);
+        match txn.open_table(def) {
+            Ok(table) => Ok(Some(Self { table })),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the dot product of `query` with every vector sharing a dimension with it.
+    pub(crate) fn scores(&self, query: &[(u32, f32)]) -> Result<HashMap<Uuid, f32>, VectorError> {
+        let mut scores = HashMap::new();
+        for &(index, query_weight) in query {
+            for posting in self
+                .table
+                .range((index, Uuid::nil())..=(index, Uuid::max()))?
+            {
+                let (key, weight) = posting?;
+                let (_, key) = key.value();
+                *scores.entry(key).or_insert(0.0) += query_weight * weight.value();
+            }
+        }
+        Ok(scores)
+    }
+}
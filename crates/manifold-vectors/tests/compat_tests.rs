@@ -0,0 +1,124 @@
+//! Read compatibility with vector tables written by earlier versions
+//!
+//! `tests/golden/` holds a database written by `write_golden` at each released version, named
+//! after that version. Every golden file must pass `check_golden` with the current code. See
+//! `docs/on_disk_format.md` at the root of the repository for the table formats and for when
+//! to add a golden file.
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_vectors::{
+    SparseVector, SparseVectorTable, SparseVectorTableRead, VectorOptions, VectorTable,
+    VectorTableRead,
+};
+use std::path::Path;
+use uuid::Uuid;
+
+#[path = "../../../tests/support/golden.rs"]
+mod golden;
+
+const GOLDEN_PREFIX: &str = "vectors-v";
+
+const A: Uuid = Uuid::from_u128(1);
+const B: Uuid = Uuid::from_u128(2);
+
+const PLAIN_A: [f32; 4] = [1.0, -2.0, 0.5, 0.0];
+const PLAIN_B: [f32; 4] = [f32::MAX, f32::MIN, f32::MIN_POSITIVE, -0.0];
+
+const RICH_OPTIONS: VectorOptions = VectorOptions {
+    normalize_on_insert: false,
+    store_norm: true,
+    store_payload: true,
+};
+
+/// Writes the fixture to `path`: a dense table with the default options, one storing norms
+/// and payloads, and a sparse table with an inverted index
+///
+/// Must not change once a golden file has been written with it.
+fn write_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    // Grows from 64 KB as it is written, so the golden file stays small
+    let cf = db
+        .create_column_family("embeddings", Some(64 * 1024))
+        .unwrap();
+
+    let write_txn = cf.begin_write().unwrap();
+    {
+        let mut table = VectorTable::<4>::open(&write_txn, "plain").unwrap();
+        table.insert(&A, &PLAIN_A).unwrap();
+        table.insert(&B, &PLAIN_B).unwrap();
+    }
+    {
+        let mut table =
+            VectorTable::<4>::open_with_options(&write_txn, "rich", RICH_OPTIONS).unwrap();
+        table
+            .insert_with_payload(&A, &[3.0, 4.0, 0.0, 0.0], b"first")
+            .unwrap();
+        table.insert(&B, &[0.0, 0.0, 0.0, 1.0]).unwrap();
+    }
+    {
+        let mut table = SparseVectorTable::open_with_index(&write_txn, "sparse").unwrap();
+        table
+            .insert(&A, &SparseVector::new(vec![(1, 0.5), (7, 2.0)]))
+            .unwrap();
+        table
+            .insert(&B, &SparseVector::new(vec![(u32::MAX, -1.0), (7, 1.0)]))
+            .unwrap();
+    }
+    write_txn.commit().unwrap();
+}
+
+/// Asserts that the database at `path` holds exactly what `write_golden` wrote
+fn check_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let cf = db.column_family("embeddings").unwrap();
+    let read_txn = cf.begin_read().unwrap();
+
+    let table = VectorTableRead::<4>::open(&read_txn, "plain").unwrap();
+    assert_eq!(table.options(), VectorOptions::default());
+    assert_eq!(table.len().unwrap(), 2);
+    assert_eq!(table.get(&A).unwrap().unwrap().value(), &PLAIN_A);
+    let b = table.get(&B).unwrap().unwrap();
+    assert_eq!(b.value().map(f32::to_bits), PLAIN_B.map(f32::to_bits));
+    assert!(b.payload().is_empty());
+
+    let table = VectorTableRead::<4>::open(&read_txn, "rich").unwrap();
+    assert_eq!(table.options(), RICH_OPTIONS);
+    let a = table.get(&A).unwrap().unwrap();
+    assert_eq!(a.value(), &[3.0, 4.0, 0.0, 0.0]);
+    assert_eq!(a.payload(), b"first");
+    assert_eq!(a.norm(), 5.0);
+    let b = table.get(&B).unwrap().unwrap();
+    assert_eq!(b.value(), &[0.0, 0.0, 0.0, 1.0]);
+    assert!(b.payload().is_empty());
+    assert_eq!(b.norm(), 1.0);
+
+    let table = SparseVectorTableRead::open(&read_txn, "sparse").unwrap();
+    assert!(table.is_indexed());
+    assert_eq!(
+        table.get(&A).unwrap(),
+        Some(SparseVector::new(vec![(1, 0.5), (7, 2.0)]))
+    );
+    assert_eq!(
+        table.get(&B).unwrap(),
+        Some(SparseVector::new(vec![(7, 1.0), (u32::MAX, -1.0)]))
+    );
+    let query = SparseVector::new(vec![(7, 1.0), (u32::MAX, 1.0)]);
+    assert_eq!(table.top_k_dot(&query, 2).unwrap(), [(A, 2.0), (B, 0.0)]);
+}
+
+#[test]
+fn test_golden_files_read_back() {
+    golden::check_golden_files(GOLDEN_PREFIX, check_golden);
+}
+
+#[test]
+fn test_fixture_round_trips() {
+    golden::check_fixture_round_trips(write_golden, check_golden);
+}
+
+/// Writes the golden file of the current version
+#[test]
+#[ignore = "run once per release to add its golden file"]
+fn bless_golden_file() {
+    golden::bless_golden_file(GOLDEN_PREFIX, write_golden);
+}
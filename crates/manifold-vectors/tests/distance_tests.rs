@@ -0,0 +1,102 @@
+use manifold_vectors::distance::{self, LANES, scalar};
+
+/// Deterministic values in `[-1, 1)`.
+fn random_vector(len: usize, seed: u64) -> Vec<f32> {
+    let mut rng = seed;
+    (0..len)
+        .map(|_| {
+            rng = rng
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            #[allow(clippy::cast_precision_loss)]
+            let unit = (rng >> 40) as f32 / (1u64 << 24) as f32;
+            unit * 2.0 - 1.0
+        })
+        .collect()
+}
+
+fn normalized(len: usize, seed: u64) -> Vec<f32> {
+    let mut vector = random_vector(len, seed);
+    let norm = scalar::dot_product(&vector, &vector).sqrt();
+    for x in &mut vector {
+        *x /= norm;
+    }
+    vector
+}
+
+/// Lengths around multiples of the lane width, including ones shorter than a lane.
+fn lengths() -> Vec<usize> {
+    let mut lengths = vec![
+        1,
+        2,
+        3,
+        LANES - 1,
+        LANES,
+        LANES + 1,
+        100,
+        128,
+        383,
+        384,
+        768,
+        1001,
+    ];
+    lengths.extend((2..5).map(|n| n * LANES + 3));
+    lengths
+}
+
+#[test]
+fn test_accelerated_matches_scalar() {
+    for len in lengths() {
+        for seed in 0..20 {
+            let a = normalized(len, seed);
+            let b = normalized(len, seed + 1_000);
+            let pairs = [
+                (distance::cosine(&a, &b), scalar::cosine(&a, &b)),
+                (distance::dot_product(&a, &b), scalar::dot_product(&a, &b)),
+                (distance::euclidean(&a, &b), scalar::euclidean(&a, &b)),
+                (
+                    distance::euclidean_squared(&a, &b),
+                    scalar::euclidean_squared(&a, &b),
+                ),
+            ];
+            for (accelerated, reference) in pairs {
+                assert!(
+                    (accelerated - reference).abs() <= 1e-5,
+                    "len {len}: {accelerated} vs {reference}"
+                );
+            }
+
+            // The L1 distance of unit vectors grows with the square root of the length
+            let accelerated = distance::manhattan(&a, &b);
+            let reference = scalar::manhattan(&a, &b);
+            assert!(
+                (accelerated - reference).abs() <= 1e-5 * reference.max(1.0),
+                "len {len}: {accelerated} vs {reference}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_accelerated_edge_cases() {
+    let zero = [0.0f32; 13];
+    let ones = [1.0f32; 13];
+    assert!(distance::cosine(&zero, &ones).abs() < f32::EPSILON);
+    assert!((distance::cosine(&ones, &ones) - 1.0).abs() < 1e-6);
+    assert!((distance::dot_product(&ones, &ones) - 13.0).abs() < f32::EPSILON);
+    assert!((distance::manhattan(&zero, &ones) - 13.0).abs() < f32::EPSILON);
+    assert!(distance::dot_product(&[], &[]).abs() < f32::EPSILON);
+
+    // Unnormalized inputs stay close in relative terms
+    let a = random_vector(777, 1);
+    let b: Vec<f32> = random_vector(777, 2).iter().map(|x| x * 100.0).collect();
+    let accelerated = distance::euclidean_squared(&a, &b);
+    let reference = scalar::euclidean_squared(&a, &b);
+    assert!((accelerated - reference).abs() <= 1e-5 * reference);
+}
+
+#[test]
+#[should_panic(expected = "assertion `left == right` failed")]
+fn test_accelerated_rejects_mismatched_lengths() {
+    distance::dot_product(&[1.0; 9], &[1.0; 8]);
+}
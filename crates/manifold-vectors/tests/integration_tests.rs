@@ -1,7 +1,13 @@
 use manifold::column_family::ColumnFamilyDatabase;
+use manifold::{TableDefinition, TableError};
+use manifold_vectors::dense::MAX_PAYLOAD_LEN;
 use manifold_vectors::multi::{MultiVectorTable, MultiVectorTableRead};
 use manifold_vectors::sparse::{SparseVector, SparseVectorTable, SparseVectorTableRead};
-use manifold_vectors::{VectorTable, VectorTableRead, distance};
+use manifold_vectors::{
+    ChangeOp, INDEX_BLOB_CHUNK_LEN, Metric, VectorError, VectorOptions, VectorTable,
+    VectorTableRead, distance,
+};
+use std::collections::BTreeSet;
 use tempfile::NamedTempFile;
 use uuid::Uuid;
 
@@ -38,6 +44,10 @@ fn test_dense_vector_zero_copy() {
 
     // And as_slice()
     assert_eq!(guard.as_slice().len(), 128);
+
+    // The stored encoding is available as raw bytes
+    assert_eq!(guard.as_bytes().len(), 128 * 4);
+    assert_eq!(guard.as_bytes()[..4], 1.0f32.to_le_bytes());
 }
 
 #[test]
@@ -154,6 +164,103 @@ fn test_multi_vector() {
     assert!((result[0][0] - 1.0).abs() < 1e-6);
 }
 
+#[test]
+fn test_multi_vector_append_and_truncate() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let id = Uuid::new_v4();
+    let other = Uuid::new_v4();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = MultiVectorTable::<2>::open(&write_txn, "multi").unwrap();
+        assert_eq!(
+            table
+                .append_vectors(&id, &[[1.0, 0.0], [2.0, 0.0]])
+                .unwrap(),
+            2
+        );
+        assert_eq!(table.append_vectors(&id, &[[3.0, 0.0]]).unwrap(), 3);
+        table.insert(&other, &[[0.0, 1.0]; 4]).unwrap();
+        assert_eq!(table.vector_count(&id).unwrap(), Some(3));
+        assert_eq!(table.len().unwrap(), 2);
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let table = MultiVectorTableRead::<2>::open(&read_txn, "multi").unwrap();
+        let tokens = table.get(&id).unwrap().unwrap();
+        assert_eq!(tokens, [[1.0, 0.0], [2.0, 0.0], [3.0, 0.0]]);
+        // Each query vector scores its best match: 3.0 + 0.0
+        let score = table.max_sim(&id, &[[1.0, 0.0], [0.0, 1.0]]).unwrap();
+        assert_eq!(score, Some(3.0));
+        assert_eq!(table.max_sim(&Uuid::new_v4(), &[[1.0, 0.0]]).unwrap(), None);
+    }
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = MultiVectorTable::<2>::open(&write_txn, "multi").unwrap();
+        assert_eq!(table.truncate_vectors(&id, 5).unwrap(), 0);
+        assert_eq!(table.truncate_vectors(&id, 1).unwrap(), 2);
+        assert_eq!(table.truncate_vectors(&Uuid::new_v4(), 0).unwrap(), 0);
+        // Inserting a shorter sequence drops the old tail
+        table.insert(&other, &[[0.0, 5.0]]).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = MultiVectorTableRead::<2>::open(&read_txn, "multi").unwrap();
+    assert_eq!(table.get(&id).unwrap().unwrap(), [[1.0, 0.0]]);
+    assert_eq!(table.max_sim(&id, &[[1.0, 0.0]]).unwrap(), Some(1.0));
+    assert_eq!(table.get(&other).unwrap().unwrap(), [[0.0, 5.0]]);
+    assert_eq!(table.vector_count(&other).unwrap(), Some(1));
+}
+
+#[test]
+fn test_multi_vector_upgrades_one_row_per_document() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let id = Uuid::new_v4();
+    let legacy: TableDefinition<Uuid, Vec<[f32; 2]>> = TableDefinition::new("legacy");
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = write_txn.open_table(legacy).unwrap();
+        table.insert(id, vec![[1.0, 2.0], [3.0, 4.0]]).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    {
+        let read_txn = cf.begin_read().unwrap();
+        assert!(matches!(
+            MultiVectorTableRead::<2>::open(&read_txn, "legacy"),
+            Err(VectorError::Table(_))
+        ));
+    }
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = MultiVectorTable::<2>::open(&write_txn, "legacy").unwrap();
+        assert_eq!(table.append_vectors(&id, &[[5.0, 6.0]]).unwrap(), 3);
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = MultiVectorTableRead::<2>::open(&read_txn, "legacy").unwrap();
+    assert_eq!(
+        table.get(&id).unwrap().unwrap(),
+        [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]
+    );
+}
+
 #[test]
 fn test_batch_insert() {
     let tmpfile = NamedTempFile::new().unwrap();
@@ -231,7 +338,7 @@ fn test_bulk_remove() {
         }
         assert_eq!(table.len().unwrap(), 10);
 
-        let removed_count = table.remove_bulk(&ids[0..5]).unwrap();
+        let removed_count = table.remove_batch(&ids[0..5]).unwrap();
         assert_eq!(removed_count, 5);
         assert_eq!(table.len().unwrap(), 5);
 
@@ -250,3 +357,931 @@ fn test_bulk_remove() {
         assert!(table.get(id).unwrap().is_some());
     }
 }
+
+#[test]
+fn test_remove_by_predicate() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let ids: Vec<Uuid> = (0..10_000).map(|_| Uuid::new_v4()).collect();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<4>::open(&write_txn, "gdpr").unwrap();
+        for (i, id) in ids.iter().enumerate() {
+            table.insert(id, &[i as f32; 4]).unwrap();
+        }
+
+        // Remove every vector whose first component is odd
+        let doomed: Vec<Uuid> = ids
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 1)
+            .map(|(_, id)| *id)
+            .collect();
+        assert!(table.remove(&doomed[0]).unwrap().is_some());
+        assert!(table.remove(&doomed[0]).unwrap().is_none());
+        assert_eq!(table.remove_batch(&doomed).unwrap(), doomed.len() - 1);
+        assert_eq!(table.remove_batch(&doomed).unwrap(), 0);
+        assert_eq!(table.len().unwrap(), 5_000);
+
+        // Guards for the remaining vectors still read correctly in the same transaction
+        for (i, id) in ids.iter().enumerate() {
+            let guard = table.get(id).unwrap();
+            if i % 2 == 1 {
+                assert!(guard.is_none());
+            } else {
+                assert_eq!(guard.unwrap().value(), &[i as f32; 4]);
+            }
+        }
+
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<4>::open(&read_txn, "gdpr").unwrap();
+    assert_eq!(table.len().unwrap(), 5_000);
+
+    let mut count = 0;
+    for result in table.all_vectors().unwrap() {
+        let (_key, guard) = result.unwrap();
+        assert_eq!(guard.value()[0] as usize % 2, 0);
+        count += 1;
+    }
+    assert_eq!(count, 5_000);
+}
+
+#[test]
+fn test_clear_vectors() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<3>::open(&write_txn, "clear").unwrap();
+        for _ in 0..100 {
+            table.insert(&Uuid::new_v4(), &[1.0, 2.0, 3.0]).unwrap();
+        }
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<3>::open(&write_txn, "clear").unwrap();
+        table.clear().unwrap();
+        assert!(table.is_empty().unwrap());
+
+        // The table is still usable after clearing
+        let id = Uuid::new_v4();
+        table.insert(&id, &[4.0, 5.0, 6.0]).unwrap();
+        assert_eq!(table.get(&id).unwrap().unwrap().value(), &[4.0, 5.0, 6.0]);
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<3>::open(&read_txn, "clear").unwrap();
+    assert_eq!(table.len().unwrap(), 1);
+    assert_eq!(table.all_vectors().unwrap().count(), 1);
+}
+
+#[test]
+fn test_dimension_mismatch() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let id = Uuid::new_v4();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<768>::open(&write_txn, "embeddings").unwrap();
+        table.insert(&id, &[0.5; 768]).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let write_txn = cf.begin_write().unwrap();
+    assert!(matches!(
+        VectorTable::<384>::open(&write_txn, "embeddings"),
+        Err(VectorError::DimensionMismatch {
+            stored: 768,
+            requested: 384
+        })
+    ));
+    assert!(VectorTable::<768>::open(&write_txn, "embeddings").is_ok());
+    drop(write_txn);
+
+    let read_txn = cf.begin_read().unwrap();
+    assert!(matches!(
+        VectorTableRead::<384>::open(&read_txn, "embeddings"),
+        Err(VectorError::DimensionMismatch {
+            stored: 768,
+            requested: 384
+        })
+    ));
+    let table = VectorTableRead::<768>::open(&read_txn, "embeddings").unwrap();
+    assert_eq!(table.get(&id).unwrap().unwrap().value(), &[0.5; 768]);
+}
+
+#[test]
+fn test_fresh_table_records_dimension() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        VectorTable::<3>::open(&write_txn, "small").unwrap();
+        MultiVectorTable::<16>::open(&write_txn, "tokens").unwrap();
+        write_txn.commit().unwrap();
+    }
+
+    let write_txn = cf.begin_write().unwrap();
+    assert!(matches!(
+        VectorTable::<4>::open(&write_txn, "small"),
+        Err(VectorError::DimensionMismatch {
+            stored: 3,
+            requested: 4
+        })
+    ));
+    assert!(matches!(
+        MultiVectorTable::<8>::open(&write_txn, "tokens"),
+        Err(VectorError::DimensionMismatch {
+            stored: 16,
+            requested: 8
+        })
+    ));
+    drop(write_txn);
+
+    let read_txn = cf.begin_read().unwrap();
+    assert!(matches!(
+        MultiVectorTableRead::<8>::open(&read_txn, "tokens"),
+        Err(VectorError::DimensionMismatch {
+            stored: 16,
+            requested: 8
+        })
+    ));
+    assert!(MultiVectorTableRead::<16>::open(&read_txn, "tokens").is_ok());
+    assert!(VectorTableRead::<3>::open(&read_txn, "small").is_ok());
+}
+
+#[test]
+fn test_open_missing_table_reports_table_error() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    assert!(matches!(
+        VectorTableRead::<3>::open(&read_txn, "missing"),
+        Err(VectorError::Table(TableError::TableDoesNotExist(_)))
+    ));
+    assert!(matches!(
+        MultiVectorTableRead::<3>::open(&read_txn, "missing"),
+        Err(VectorError::Table(TableError::TableDoesNotExist(_)))
+    ));
+    assert!(matches!(
+        SparseVectorTableRead::open(&read_txn, "missing"),
+        Err(VectorError::Table(TableError::TableDoesNotExist(_)))
+    ));
+}
+
+fn prefixed_id(prefix: &[u8], n: u8) -> Uuid {
+    let mut bytes = [0u8; 16];
+    bytes[..prefix.len()].copy_from_slice(prefix);
+    bytes[15] = n;
+    Uuid::from_bytes(bytes)
+}
+
+#[test]
+fn test_prefix_iter_and_remove() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    // "ten" is also a prefix of "tena" and "tenb", so it covers all three tenants
+    let tenants: [(&[u8], u8); 5] = [
+        (b"ten", 3),
+        (b"tena", 4),
+        (b"tenb", 5),
+        (b"\xff\xff", 2),
+        (b"\xff", 1),
+    ];
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<2>::open(&write_txn, "tenants").unwrap();
+        for (prefix, count) in tenants {
+            for n in 0..count {
+                table
+                    .insert(&prefixed_id(prefix, n), &[f32::from(count), f32::from(n)])
+                    .unwrap();
+            }
+        }
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let count_prefix = |table: &VectorTableRead<2>, prefix: &[u8]| {
+        let mut keys = vec![];
+        for entry in table.prefix_iter(prefix).unwrap() {
+            let (key, guard) = entry.unwrap();
+            assert!(key.as_bytes().starts_with(prefix));
+            assert_eq!(guard.value()[1], f32::from(key.as_bytes()[15]));
+            keys.push(key);
+        }
+        assert!(keys.is_sorted());
+        keys.len()
+    };
+
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let table = VectorTableRead::<2>::open(&read_txn, "tenants").unwrap();
+        assert_eq!(count_prefix(&table, b"tena"), 4);
+        assert_eq!(count_prefix(&table, b"tenb"), 5);
+        assert_eq!(count_prefix(&table, b"ten"), 12);
+        assert_eq!(count_prefix(&table, b"te"), 12);
+        assert_eq!(count_prefix(&table, b"\xff"), 3);
+        assert_eq!(count_prefix(&table, b"\xff\xff"), 2);
+        assert_eq!(count_prefix(&table, b""), 15);
+        assert_eq!(count_prefix(&table, b"tenc"), 0);
+        assert_eq!(count_prefix(&table, b"zzz"), 0);
+        assert_eq!(count_prefix(&table, &[b't'; 17]), 0);
+        assert_eq!(count_prefix(&table, prefixed_id(b"tena", 2).as_bytes()), 1);
+    }
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<2>::open(&write_txn, "tenants").unwrap();
+        assert_eq!(table.remove_prefix(b"tena").unwrap(), 4);
+        assert_eq!(table.remove_prefix(b"tena").unwrap(), 0);
+        assert_eq!(table.remove_prefix(b"nothing").unwrap(), 0);
+        assert_eq!(table.remove_prefix(b"\xff\xff").unwrap(), 2);
+        assert_eq!(table.len().unwrap(), 9);
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "tenants").unwrap();
+    assert_eq!(count_prefix(&table, b"tena"), 0);
+    assert_eq!(count_prefix(&table, b"tenb"), 5);
+    assert_eq!(count_prefix(&table, b"ten"), 8);
+    assert_eq!(count_prefix(&table, b"\xff"), 1);
+}
+
+/// Applies the changes after `seq` to `keys`, returning the last sequence number seen.
+fn replay_changes(table: &VectorTableRead<2>, seq: u64, keys: &mut BTreeSet<Uuid>) -> u64 {
+    let mut last = seq;
+    for change in table.changes_since(seq).unwrap() {
+        let (seq, key, op) = change.unwrap();
+        assert!(seq > last);
+        last = seq;
+        match op {
+            ChangeOp::Insert => keys.insert(key),
+            ChangeOp::Remove => keys.remove(&key),
+        };
+    }
+    last
+}
+
+fn stored_keys(table: &VectorTableRead<2>) -> BTreeSet<Uuid> {
+    table
+        .all_vectors()
+        .unwrap()
+        .map(|entry| entry.unwrap().0)
+        .collect()
+}
+
+/// Interleaves inserts, removals and re-inserts of `ids` across four steps.
+fn changelog_step(table: &mut VectorTable<'_, 2>, ids: &[Uuid], step: usize) {
+    match step {
+        0 => {
+            table.insert(&ids[0], &[0.0, 0.0]).unwrap();
+            table
+                .insert_batch(&[(ids[1], [1.0, 1.0]), (ids[2], [2.0, 2.0])], false)
+                .unwrap();
+        }
+        1 => {
+            assert!(table.remove(&ids[0]).unwrap().is_some());
+            assert!(table.remove(&ids[3]).unwrap().is_none());
+            table.insert(&ids[3], &[3.0, 3.0]).unwrap();
+            table.insert(&ids[0], &[0.5, 0.5]).unwrap();
+        }
+        2 => {
+            assert_eq!(table.remove_batch(&[ids[1], ids[5]]).unwrap(), 1);
+            table
+                .insert_batch(&[(ids[4], [4.0, 4.0]), (ids[5], [5.0, 5.0])], true)
+                .unwrap();
+            // Removes ids 0, 2 and 4
+            assert_eq!(table.remove_prefix(&[0]).unwrap(), 3);
+        }
+        _ => {
+            table.insert(&ids[6], &[6.0, 6.0]).unwrap();
+            table.clear().unwrap();
+            table.insert(&ids[7], &[7.0, 7.0]).unwrap();
+            table.insert(&ids[1], &[1.5, 1.5]).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_changelog_replay() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let ids: Vec<Uuid> = (0..8u8).map(|n| prefixed_id(&[n % 2], n)).collect();
+
+    let mut checkpoints = vec![(0, BTreeSet::new())];
+    // Each step is one transaction, after which a checkpoint is taken
+    for step in 0..4 {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<2>::open_with_changelog(&write_txn, "docs").unwrap();
+        changelog_step(&mut table, &ids, step);
+        drop(table);
+        write_txn.commit().unwrap();
+
+        let read_txn = cf.begin_read().unwrap();
+        let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+        let seq = table.last_change_seq().unwrap().unwrap();
+        checkpoints.push((seq, stored_keys(&table)));
+    }
+
+    // Replaying from any checkpoint reconstructs the final state
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+    let expected = stored_keys(&table);
+    assert_eq!(expected, BTreeSet::from([ids[1], ids[7]]));
+    let last = table.last_change_seq().unwrap().unwrap();
+    for (seq, keys) in &checkpoints {
+        let mut keys = keys.clone();
+        assert_eq!(replay_changes(&table, *seq, &mut keys), last);
+        assert_eq!(keys, expected, "replay from {seq}");
+    }
+    assert_eq!(table.changes_since(last).unwrap().count(), 0);
+    drop(table);
+    drop(read_txn);
+
+    // Truncation drops old entries but keeps the sequence increasing
+    let truncate_at = checkpoints[2].0;
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<2>::open_with_changelog(&write_txn, "docs").unwrap();
+        assert_eq!(
+            table.truncate_changes_before(truncate_at + 1).unwrap(),
+            truncate_at
+        );
+        assert_eq!(
+            table.truncate_changes_before(u64::MAX).unwrap(),
+            last - truncate_at - 1
+        );
+        table.insert(&ids[2], &[2.5, 2.5]).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+    let changes: Vec<_> = table
+        .changes_since(0)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (last, ids[1], ChangeOp::Insert),
+            (last + 1, ids[2], ChangeOp::Insert)
+        ]
+    );
+
+    // Tables written without the change log have none to read
+    let write_txn = cf.begin_write().unwrap();
+    VectorTable::<2>::open(&write_txn, "plain").unwrap();
+    write_txn.commit().unwrap();
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "plain").unwrap();
+    assert!(matches!(
+        table.changes_since(0),
+        Err(VectorError::Table(TableError::TableDoesNotExist(_)))
+    ));
+}
+
+fn sample_vector(i: usize) -> [f32; 16] {
+    std::array::from_fn(|j| ((i * 16 + j) as f32 * 0.37).sin() * (1.0 + i as f32))
+}
+
+#[test]
+fn test_normalized_storage_matches_cosine() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let ids: Vec<Uuid> = (0..100).map(|_| Uuid::new_v4()).collect();
+    let options = VectorOptions {
+        normalize_on_insert: true,
+        store_norm: true,
+        store_payload: false,
+    };
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<16>::open_with_options(&write_txn, "docs", options).unwrap();
+        table.insert(&ids[0], &sample_vector(0)).unwrap();
+        let items: Vec<_> = (1..100).map(|i| (ids[i], sample_vector(i))).collect();
+        table.insert_batch(&items, false).unwrap();
+
+        assert!(matches!(
+            table.insert(&Uuid::new_v4(), &[0.0; 16]),
+            Err(VectorError::ZeroVector)
+        ));
+        assert!(matches!(
+            table.insert_batch(
+                &[(Uuid::new_v4(), [1.0; 16]), (Uuid::new_v4(), [0.0; 16])],
+                false
+            ),
+            Err(VectorError::ZeroVector)
+        ));
+        assert_eq!(table.len().unwrap(), 100);
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let query = sample_vector(1_000);
+    let norm = distance::dot_product(&query, &query).sqrt();
+    let normalized_query = query.map(|x| x / norm);
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<16>::open(&read_txn, "docs").unwrap();
+    assert_eq!(table.options(), options);
+    for (i, id) in ids.iter().enumerate() {
+        let guard = table.get(id).unwrap().unwrap();
+        assert!((guard.norm() - 1.0).abs() < 1e-5);
+        let expected = distance::cosine(&query, &sample_vector(i));
+        let actual = distance::cosine_prenormalized(&normalized_query, guard.value());
+        assert!((expected - actual).abs() < 1e-5, "{expected} vs {actual}");
+    }
+}
+
+#[test]
+fn test_stored_norms() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let ids: Vec<Uuid> = (0..10u8).map(|n| prefixed_id(&[n % 2], n)).collect();
+    let options = VectorOptions {
+        normalize_on_insert: false,
+        store_norm: true,
+        store_payload: false,
+    };
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<16>::open_with_options(&write_txn, "docs", options).unwrap();
+        for (i, id) in ids.iter().enumerate() {
+            table.insert(id, &sample_vector(i)).unwrap();
+        }
+        let removed = table.remove(&ids[0]).unwrap().unwrap();
+        assert_eq!(removed.value(), &sample_vector(0));
+        assert!(
+            (removed.norm() - distance::dot_product(removed.as_slice(), removed.as_slice()).sqrt())
+                .abs()
+                < 1e-4
+        );
+        drop(removed);
+        assert_eq!(table.remove_batch(&ids[1..3]).unwrap(), 2);
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    // A plain open keeps maintaining the norms
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<16>::open(&write_txn, "docs").unwrap();
+        assert_eq!(table.options(), options);
+        table.insert(&ids[0], &sample_vector(0)).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<16>::open(&read_txn, "docs").unwrap();
+    let query = sample_vector(1_000);
+    let query_norm = distance::dot_product(&query, &query).sqrt();
+    let mut seen = 0;
+    for result in table.all_vectors().unwrap() {
+        let (key, guard) = result.unwrap();
+        let i = ids.iter().position(|id| *id == key).unwrap();
+        let vector = sample_vector(i);
+        assert_eq!(guard.value(), &vector);
+        assert!((guard.norm() - distance::dot_product(&vector, &vector).sqrt()).abs() < 1e-4);
+
+        let cosine = distance::dot_product(&query, guard.as_slice()) / (query_norm * guard.norm());
+        assert!((cosine - distance::cosine(&query, &vector)).abs() < 1e-5);
+        seen += 1;
+    }
+    assert_eq!(seen, 8);
+    for result in table.prefix_iter(&[1]).unwrap() {
+        let (key, guard) = result.unwrap();
+        assert_eq!(key.as_bytes()[0], 1);
+        assert!(
+            (guard.norm() - distance::dot_product(guard.as_slice(), guard.as_slice()).sqrt()).abs()
+                < 1e-4
+        );
+    }
+}
+
+#[test]
+fn test_options_are_checked_on_open() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let id = Uuid::new_v4();
+    let store_norm = VectorOptions {
+        normalize_on_insert: false,
+        store_norm: true,
+        store_payload: false,
+    };
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut plain = VectorTable::<4>::open(&write_txn, "plain").unwrap();
+        plain.insert(&id, &[3.0, 4.0, 0.0, 0.0]).unwrap();
+        drop(plain);
+        VectorTable::<4>::open_with_options(&write_txn, "normed", store_norm).unwrap();
+        write_txn.commit().unwrap();
+    }
+
+    // A table created without options cannot gain them later
+    let write_txn = cf.begin_write().unwrap();
+    assert!(matches!(
+        VectorTable::<4>::open_with_options(&write_txn, "plain", store_norm),
+        Err(VectorError::OptionsMismatch { stored, requested })
+            if stored == VectorOptions::default() && requested == store_norm
+    ));
+    assert!(
+        VectorTable::<4>::open_with_options(&write_txn, "plain", VectorOptions::default()).is_ok()
+    );
+    assert!(matches!(
+        VectorTable::<4>::open_with_options(&write_txn, "normed", VectorOptions::default()),
+        Err(VectorError::OptionsMismatch { .. })
+    ));
+    drop(write_txn);
+
+    // Tables without stored norms still compute them
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<4>::open(&read_txn, "plain").unwrap();
+    assert_eq!(table.options(), VectorOptions::default());
+    assert_eq!(table.get(&id).unwrap().unwrap().norm(), 5.0);
+    assert_eq!(table.all_vectors().unwrap().count(), 1);
+    assert_eq!(
+        VectorTableRead::<4>::open(&read_txn, "normed")
+            .unwrap()
+            .options(),
+        store_norm
+    );
+}
+
+#[test]
+fn test_vector_payloads() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let ids: Vec<Uuid> = (0..6).map(Uuid::from_u128).collect();
+    let options = VectorOptions {
+        store_norm: true,
+        store_payload: true,
+        ..VectorOptions::default()
+    };
+    let title = |i: usize| format!("document {i}").into_bytes();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<4>::open_with_options(&write_txn, "docs", options).unwrap();
+        // Even rows have a payload, odd rows are written without one
+        for (i, id) in ids.iter().enumerate() {
+            let vector = [i as f32, 1.0, 2.0, 3.0];
+            if i % 2 == 0 {
+                table.insert_with_payload(id, &vector, &title(i)).unwrap();
+            } else {
+                table.insert(id, &vector).unwrap();
+            }
+        }
+        // A batch insert replaces the payload with an empty one
+        table
+            .insert_batch(&[(ids[4], [4.0, 1.0, 2.0, 3.0])], false)
+            .unwrap();
+
+        let guard = table.get(&ids[0]).unwrap().unwrap();
+        assert_eq!(guard.payload(), title(0));
+        drop(guard);
+
+        assert!(matches!(
+            table.insert_with_payload(&ids[0], &[0.0; 4], &vec![0; MAX_PAYLOAD_LEN + 1]),
+            Err(VectorError::PayloadTooLarge { len, max })
+                if len == MAX_PAYLOAD_LEN + 1 && max == MAX_PAYLOAD_LEN
+        ));
+        table
+            .insert_with_payload(&ids[5], &[5.0, 1.0, 2.0, 3.0], &vec![7; MAX_PAYLOAD_LEN])
+            .unwrap();
+        drop(table);
+
+        let mut plain = VectorTable::<4>::open(&write_txn, "plain").unwrap();
+        plain.insert(&ids[0], &[1.0; 4]).unwrap();
+        plain.insert_with_payload(&ids[1], &[2.0; 4], &[]).unwrap();
+        assert!(matches!(
+            plain.insert_with_payload(&ids[2], &[3.0; 4], b"title"),
+            Err(VectorError::PayloadNotStored)
+        ));
+        drop(plain);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<4>::open(&read_txn, "docs").unwrap();
+    assert_eq!(table.options(), options);
+    let guard = table.get(&ids[2]).unwrap().unwrap();
+    assert_eq!(guard.vector(), &[2.0, 1.0, 2.0, 3.0]);
+    assert_eq!(guard.as_bytes().len(), 16);
+    assert_eq!(guard.payload(), title(2));
+    assert!(table.get(&ids[1]).unwrap().unwrap().payload().is_empty());
+    drop(guard);
+
+    let rows: Vec<(Uuid, f32, f32, Vec<u8>)> = table
+        .all_vectors()
+        .unwrap()
+        .map(|row| {
+            let (id, guard) = row.unwrap();
+            (id, guard[0], guard.norm(), guard.payload().to_vec())
+        })
+        .collect();
+    assert_eq!(rows.len(), 6);
+    for (i, (id, first, norm, payload)) in rows.into_iter().enumerate() {
+        assert_eq!(id, ids[i]);
+        assert_eq!(first, i as f32);
+        assert_eq!(
+            norm,
+            distance::dot_product(&[first, 1.0, 2.0, 3.0], &[first, 1.0, 2.0, 3.0]).sqrt()
+        );
+        let expected = match i {
+            0 | 2 => title(i),
+            5 => vec![7; MAX_PAYLOAD_LEN],
+            _ => vec![],
+        };
+        assert_eq!(payload, expected);
+    }
+
+    // Tables created without payloads read back with empty ones
+    let plain = VectorTableRead::<4>::open(&read_txn, "plain").unwrap();
+    let guard = plain.get(&ids[1]).unwrap().unwrap();
+    assert_eq!(guard.vector(), &[2.0; 4]);
+    assert!(guard.payload().is_empty());
+    assert!(plain.get(&ids[2]).unwrap().is_none());
+    drop(read_txn);
+
+    // Removing a vector removes its payload with it
+    let write_txn = cf.begin_write().unwrap();
+    let mut table = VectorTable::<4>::open(&write_txn, "docs").unwrap();
+    assert_eq!(table.remove(&ids[0]).unwrap().unwrap().payload(), title(0));
+    assert_eq!(table.remove_batch(&ids[1..3]).unwrap(), 2);
+    assert_eq!(table.len().unwrap(), 3);
+}
+
+#[test]
+fn test_score_keys_matches_manual_scoring() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let ids: Vec<Uuid> = (0..50).map(|_| Uuid::new_v4()).collect();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<16>::open(&write_txn, "docs").unwrap();
+        let items: Vec<_> = (0..50).map(|i| (ids[i], sample_vector(i))).collect();
+        table.insert_batch(&items, false).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    // Candidates in no particular order, with missing keys and duplicates among them
+    let missing = Uuid::new_v4();
+    let keys = vec![
+        ids[42], missing, ids[3], ids[17], ids[3], ids[0], missing, ids[49], ids[42],
+    ];
+    let query = sample_vector(1_000);
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<16>::open(&read_txn, "docs").unwrap();
+    for metric in [
+        Metric::Cosine,
+        Metric::DotProduct,
+        Metric::Euclidean,
+        Metric::Manhattan,
+    ] {
+        let scores = table.score_keys(&keys, &query, metric).unwrap();
+        let expected: Vec<Option<f32>> = keys
+            .iter()
+            .map(|key| {
+                table
+                    .get(key)
+                    .unwrap()
+                    .map(|guard| metric.score(&query, guard.value()))
+            })
+            .collect();
+        assert_eq!(scores, expected, "{metric:?}");
+        assert_eq!(scores[1], None);
+        assert_eq!(scores[6], None);
+        assert_eq!(scores[2], scores[4]);
+        assert_eq!(scores[0], scores[8]);
+    }
+
+    assert_eq!(
+        table
+            .score_keys(&keys[2..3], &query, Metric::Cosine)
+            .unwrap(),
+        [Some(distance::cosine(&query, &sample_vector(3)))]
+    );
+    assert!(
+        table
+            .score_keys(&[], &query, Metric::Euclidean)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+/// Returns the number of chunks stored for the index blobs of the vector table `name`
+fn index_blob_chunks(cf: &manifold::column_family::ColumnFamily, name: &str) -> u64 {
+    use manifold::ReadableTableMetadata;
+
+    let chunks_name = format!("manifold_vectors::index_blob_chunks::{name}");
+    let def: TableDefinition<(&str, u64), &[u8]> = TableDefinition::new(&chunks_name);
+    let read_txn = cf.begin_read().unwrap();
+    read_txn.open_table(def).unwrap().len().unwrap()
+}
+
+#[test]
+fn test_index_blobs() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let chunk_len = u64::from(INDEX_BLOB_CHUNK_LEN);
+
+    // No blobs before any is stored
+    let write_txn = cf.begin_write().unwrap();
+    VectorTable::<2>::open(&write_txn, "docs").unwrap();
+    write_txn.commit().unwrap();
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+    assert!(table.get_index_blob("hnsw").unwrap().is_none());
+    drop(table);
+    drop(read_txn);
+
+    let blob: Vec<u8> = (0..50_000_000u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+        .collect();
+    let write_txn = cf.begin_write().unwrap();
+    let mut table = VectorTable::<2>::open(&write_txn, "docs").unwrap();
+    table.insert(&Uuid::from_u128(1), &[1.0, 0.0]).unwrap();
+    table.put_index_blob("hnsw", 1, &blob).unwrap();
+    table.put_index_blob("empty", 7, &[]).unwrap();
+    drop(table);
+    write_txn.commit().unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+    let (version, bytes) = table.get_index_blob("hnsw").unwrap().unwrap();
+    assert_eq!(version, 1);
+    assert!(bytes == blob, "blob read back differs");
+    assert_eq!(
+        table.get_index_blob("empty").unwrap(),
+        Some((7, Vec::new()))
+    );
+    assert!(table.get_index_blob("ivf").unwrap().is_none());
+    drop(table);
+    drop(read_txn);
+    assert_eq!(
+        index_blob_chunks(&cf, "docs"),
+        (blob.len() as u64).div_ceil(chunk_len)
+    );
+
+    // Overwriting with a shorter blob removes the chunks past its end
+    let smaller = vec![0xAB; 3 * INDEX_BLOB_CHUNK_LEN as usize + 1];
+    let write_txn = cf.begin_write().unwrap();
+    let mut table = VectorTable::<2>::open(&write_txn, "docs").unwrap();
+    table.put_index_blob("hnsw", 2, &smaller).unwrap();
+    drop(table);
+    write_txn.commit().unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+    assert_eq!(table.get_index_blob("hnsw").unwrap(), Some((2, smaller)));
+    assert_eq!(table.len().unwrap(), 1);
+    drop(table);
+    drop(read_txn);
+    assert_eq!(index_blob_chunks(&cf, "docs"), 4);
+
+    let write_txn = cf.begin_write().unwrap();
+    let mut table = VectorTable::<2>::open(&write_txn, "docs").unwrap();
+    assert!(table.delete_index_blob("hnsw").unwrap());
+    assert!(!table.delete_index_blob("hnsw").unwrap());
+    drop(table);
+    write_txn.commit().unwrap();
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = VectorTableRead::<2>::open(&read_txn, "docs").unwrap();
+    assert!(table.get_index_blob("hnsw").unwrap().is_none());
+    assert_eq!(
+        table.get_index_blob("empty").unwrap(),
+        Some((7, Vec::new()))
+    );
+    drop(table);
+    drop(read_txn);
+    assert_eq!(index_blob_chunks(&cf, "docs"), 0);
+}
+
+#[test]
+fn test_par_chunks_cover_all_vectors_once() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let options = VectorOptions {
+        store_norm: true,
+        ..VectorOptions::default()
+    };
+
+    // Random keys, keys sharing a 15 byte prefix, the extremes of the key space, a single key
+    let random: Vec<Uuid> = (0..4000).map(|_| Uuid::new_v4()).collect();
+    let common_prefix: Vec<Uuid> = (0..=255).map(|n| prefixed_id(&[0xAB; 15], n)).collect();
+    let extremes = vec![Uuid::nil(), Uuid::from_u128(1 << 64), Uuid::max()];
+    let single = vec![Uuid::from_u128(42)];
+    let key_sets = [
+        ("random", random),
+        ("common_prefix", common_prefix),
+        ("extremes", extremes),
+        ("single", single),
+        ("empty", vec![]),
+    ];
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        for (name, keys) in &key_sets {
+            let mut table = VectorTable::<2>::open_with_options(&write_txn, name, options).unwrap();
+            for (i, key) in keys.iter().enumerate() {
+                table.insert(key, &[i as f32, 1.0]).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    for (name, _) in &key_sets {
+        let table = VectorTableRead::<2>::open(&read_txn, name).unwrap();
+        let expected: Vec<(Uuid, [f32; 2], f32)> = table
+            .all_vectors()
+            .unwrap()
+            .map(|entry| {
+                let (key, guard) = entry.unwrap();
+                (key, *guard.value(), guard.norm())
+            })
+            .collect();
+
+        for num_shards in [0, 1, 2, 3, 4, 7, 64, 1000] {
+            let shards = table.par_chunks(num_shards).unwrap();
+            assert!(!shards.is_empty() && shards.len() <= num_shards.max(1));
+
+            // Each shard scanned on its own thread, over the same read transaction
+            let scanned: Vec<Vec<(Uuid, [f32; 2], f32)>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = shards
+                    .into_iter()
+                    .map(|shard| {
+                        scope.spawn(move || {
+                            shard
+                                .map(|entry| {
+                                    let (key, guard) = entry.unwrap();
+                                    (key, *guard.value(), guard.norm())
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+
+            // Shards in order, concatenated, give the full scan with no key twice
+            let concatenated: Vec<_> = scanned.iter().flatten().copied().collect();
+            assert_eq!(concatenated, expected, "{name} in {num_shards} shards");
+            if *name == "random" && num_shards == 4 {
+                assert_eq!(scanned.len(), 4);
+                for shard in &scanned {
+                    assert!(shard.len() > 500, "unbalanced shard of {}", shard.len());
+                }
+            }
+            if *name == "common_prefix" && num_shards <= 64 {
+                assert_eq!(scanned.len(), num_shards.max(1));
+                assert!(scanned.iter().all(|shard| !shard.is_empty()));
+            }
+        }
+    }
+}
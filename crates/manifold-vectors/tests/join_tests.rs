@@ -0,0 +1,183 @@
+//! Tests for joined scans of vector and property tables
+
+#![cfg(feature = "properties")]
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold_properties::{PropertyTable, PropertyTableRead, PropertyValue};
+use manifold_vectors::join::{joined_prefix_scan, joined_scan};
+use manifold_vectors::{VectorTable, VectorTableRead};
+use tempfile::NamedTempFile;
+use uuid::Uuid;
+
+fn id(n: u128) -> Uuid {
+    Uuid::from_u128(n)
+}
+
+/// Collects `(key, vector[0], title)` from a joined scan
+fn collect(iter: manifold_vectors::join::JoinedIter<'_, 2>) -> Vec<(u128, f32, Option<String>)> {
+    iter.map(|row| {
+        let (key, vector, title) = row.unwrap();
+        let title = title.map(|guard| guard.as_str().unwrap().to_string());
+        (key.as_u128(), vector.value()[0], title)
+    })
+    .collect()
+}
+
+#[test]
+fn test_joined_scan_merges_both_tables() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("documents").unwrap();
+
+    // 1 and 6 only have properties, 2 and 5 only vectors, 3, 4 and u128::MAX both,
+    // and 7 neither. 4 has other properties but no title.
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut vectors = VectorTable::<2>::open(&write_txn, "embeddings").unwrap();
+        for n in [2, 3, 4, 5, u128::MAX] {
+            vectors.insert(&id(n), &[n as f32, 0.0]).unwrap();
+        }
+        drop(vectors);
+
+        let mut properties = PropertyTable::open(&write_txn, "properties").unwrap();
+        for n in [1, 3, 6, u128::MAX] {
+            properties
+                .set(
+                    &id(n),
+                    "title",
+                    PropertyValue::new_string(format!("doc {n}")),
+                )
+                .unwrap();
+        }
+        // Names sorting before and after "title" must not be mistaken for it
+        for n in [3, 4] {
+            properties
+                .set(&id(n), "author", PropertyValue::new_string("someone"))
+                .unwrap();
+            properties
+                .set(&id(n), "year", PropertyValue::new_integer(2024))
+                .unwrap();
+        }
+        drop(properties);
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let vectors = VectorTableRead::<2>::open(&read_txn, "embeddings").unwrap();
+    let properties = PropertyTableRead::open(&read_txn, "properties").unwrap();
+    let title = |n: u128| Some(format!("doc {n}"));
+
+    let all = collect(joined_scan(&vectors, &properties, "title", ..).unwrap());
+    assert_eq!(
+        all,
+        vec![
+            (2, 2.0, None),
+            (3, 3.0, title(3)),
+            (4, 4.0, None),
+            (5, 5.0, None),
+            (u128::MAX, u128::MAX as f32, title(u128::MAX)),
+        ]
+    );
+
+    // Range bounds apply to both tables
+    let middle = collect(joined_scan(&vectors, &properties, "title", id(3)..id(5)).unwrap());
+    assert_eq!(middle, vec![(3, 3.0, title(3)), (4, 4.0, None)]);
+    let tail = collect(joined_scan(&vectors, &properties, "title", id(5)..).unwrap());
+    assert_eq!(tail[0], (5, 5.0, None));
+    assert_eq!(tail[1].2, title(u128::MAX));
+    assert!(
+        collect(joined_scan(&vectors, &properties, "title", id(6)..=id(7)).unwrap()).is_empty()
+    );
+
+    // A property nobody has joins as None everywhere
+    let missing = collect(joined_scan(&vectors, &properties, "summary", ..).unwrap());
+    assert_eq!(missing.len(), 5);
+    assert!(missing.iter().all(|(_, _, title)| title.is_none()));
+
+    let other = joined_scan(&vectors, &properties, "year", ..)
+        .unwrap()
+        .map(|row| {
+            let (key, _, year) = row.unwrap();
+            (key.as_u128(), year.and_then(|guard| guard.as_i64()))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        other,
+        vec![
+            (2, None),
+            (3, Some(2024)),
+            (4, Some(2024)),
+            (5, None),
+            (u128::MAX, None)
+        ]
+    );
+}
+
+#[test]
+fn test_joined_prefix_scan() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("documents").unwrap();
+
+    let tenant = |tenant: u8, n: u8| {
+        let mut bytes = [0; 16];
+        bytes[0] = tenant;
+        bytes[15] = n;
+        Uuid::from_bytes(bytes)
+    };
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut vectors = VectorTable::<2>::open(&write_txn, "embeddings").unwrap();
+        let mut properties = PropertyTable::open(&write_txn, "properties").unwrap();
+        for t in [1, 2, 3] {
+            for n in 0..3 {
+                vectors
+                    .insert(&tenant(t, n), &[f32::from(t), f32::from(n)])
+                    .unwrap();
+                if n != 1 {
+                    properties
+                        .set(
+                            &tenant(t, n),
+                            "title",
+                            PropertyValue::new_string(format!("{t}/{n}")),
+                        )
+                        .unwrap();
+                }
+            }
+        }
+        drop((vectors, properties));
+        write_txn.commit().unwrap();
+    }
+
+    let read_txn = cf.begin_read().unwrap();
+    let vectors = VectorTableRead::<2>::open(&read_txn, "embeddings").unwrap();
+    let properties = PropertyTableRead::open(&read_txn, "properties").unwrap();
+
+    let rows = joined_prefix_scan(&vectors, &properties, "title", &[2])
+        .unwrap()
+        .map(|row| {
+            let (key, vector, title) = row.unwrap();
+            assert_eq!(key.as_bytes()[0], 2);
+            assert_eq!(vector.value()[0], 2.0);
+            title.map(|guard| guard.as_str().unwrap().to_string())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![Some("2/0".to_string()), None, Some("2/2".to_string())]
+    );
+
+    assert_eq!(
+        joined_prefix_scan(&vectors, &properties, "title", &[4])
+            .unwrap()
+            .count(),
+        0
+    );
+    assert_eq!(
+        joined_prefix_scan(&vectors, &properties, "title", &[0; 17])
+            .unwrap()
+            .count(),
+        0
+    );
+}
@@ -0,0 +1,234 @@
+use manifold::column_family::{ColumnFamily, ColumnFamilyDatabase};
+use manifold::{ReadableTableMetadata, TableDefinition, TableError, Value};
+use manifold_vectors::quantized::{F16, Int8, Quantization, QuantizedValue};
+use manifold_vectors::{
+    QuantizedVectorTable, QuantizedVectorTableRead, VectorError, VectorTable, distance,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tempfile::NamedTempFile;
+use uuid::Uuid;
+
+fn random_vectors<const DIM: usize>(rng: &mut StdRng, count: usize) -> Vec<(Uuid, [f32; DIM])> {
+    (0..count)
+        .map(|_| {
+            let mut vector = [0.0; DIM];
+            for x in &mut vector {
+                *x = rng.random_range(-1.0..1.0);
+            }
+            (Uuid::new_v4(), vector)
+        })
+        .collect()
+}
+
+fn insert_quantized<const DIM: usize, Q: Quantization>(
+    cf: &ColumnFamily,
+    name: &str,
+    items: &[(Uuid, [f32; DIM])],
+) {
+    let write_txn = cf.begin_write().unwrap();
+    let mut table = QuantizedVectorTable::<DIM, Q>::open(&write_txn, name).unwrap();
+    for (id, vector) in items {
+        table.insert(id, vector).unwrap();
+    }
+    drop(table);
+    write_txn.commit().unwrap();
+}
+
+fn stored_bytes<V: Value + 'static>(cf: &ColumnFamily, name: &str) -> u64 {
+    let read_txn = cf.begin_read().unwrap();
+    let def: TableDefinition<Uuid, V> = TableDefinition::new(name);
+    read_txn
+        .open_table(def)
+        .unwrap()
+        .stats()
+        .unwrap()
+        .stored_bytes()
+}
+
+fn top_k(scores: &[(Uuid, f32)], k: usize) -> Vec<Uuid> {
+    let mut scores = scores.to_vec();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.into_iter().take(k).map(|(id, _)| id).collect()
+}
+
+#[test]
+fn test_quantized_round_trip_accuracy() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut items = random_vectors::<128>(&mut rng, 500);
+    // Vectors with a wide dynamic range and an all-zero vector
+    items[0].1[0] = 1000.0;
+    items[1].1 = [0.0; 128];
+
+    insert_quantized::<128, F16>(&cf, "f16", &items);
+    insert_quantized::<128, Int8>(&cf, "int8", &items);
+
+    let read_txn = cf.begin_read().unwrap();
+    let f16_table = QuantizedVectorTableRead::<128, F16>::open(&read_txn, "f16").unwrap();
+    let int8_table = QuantizedVectorTableRead::<128, Int8>::open(&read_txn, "int8").unwrap();
+    assert_eq!(f16_table.len().unwrap(), 500);
+    assert_eq!(int8_table.len().unwrap(), 500);
+
+    for (id, vector) in &items {
+        let guard = f16_table.get(id).unwrap().unwrap();
+        assert_eq!(guard.as_bytes().len(), 256);
+        for (x, y) in vector.iter().zip(guard.dequantize()) {
+            // f16 keeps 11 significant bits
+            assert!((x - y).abs() <= x.abs() / 2048.0 + 1e-7, "{x} vs {y}");
+        }
+
+        let guard = int8_table.get(id).unwrap().unwrap();
+        assert_eq!(guard.as_bytes().len(), 132);
+        let max_abs = vector.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+        assert!((guard.scale() - max_abs / 127.0).abs() <= f32::EPSILON * max_abs);
+        for (x, y) in vector.iter().zip(guard.dequantize()) {
+            assert!((x - y).abs() <= guard.scale() / 2.0 + 1e-6, "{x} vs {y}");
+        }
+    }
+
+    let zero = int8_table.get(&items[1].0).unwrap().unwrap();
+    assert_eq!(zero.scale(), 0.0);
+    assert!(zero.codes().iter().all(|&code| code == 0));
+    assert_eq!(zero.dequantize(), [0.0; 128]);
+}
+
+#[test]
+fn test_quantized_storage_size() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let mut rng = StdRng::seed_from_u64(11);
+    let items = random_vectors::<256>(&mut rng, 1000);
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = VectorTable::<256>::open(&write_txn, "f32").unwrap();
+        for (id, vector) in &items {
+            table.insert(id, vector).unwrap();
+        }
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+    insert_quantized::<256, F16>(&cf, "f16", &items);
+    insert_quantized::<256, Int8>(&cf, "int8", &items);
+
+    let f32_bytes = stored_bytes::<[f32; 256]>(&cf, "f32");
+    let f16_bytes = stored_bytes::<QuantizedValue<256, F16>>(&cf, "f16");
+    let int8_bytes = stored_bytes::<QuantizedValue<256, Int8>>(&cf, "int8");
+
+    // Each entry also stores a 16 byte key
+    assert_eq!(f32_bytes, 1000 * (16 + 1024));
+    assert_eq!(f16_bytes, 1000 * (16 + 512));
+    assert_eq!(int8_bytes, 1000 * (16 + 4 + 256));
+    assert!(f16_bytes * 100 < f32_bytes * 55);
+    assert!(int8_bytes * 100 < f32_bytes * 30);
+}
+
+#[test]
+fn test_quantized_top_k_recall() {
+    const K: usize = 10;
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let mut rng = StdRng::seed_from_u64(23);
+    let items = random_vectors::<64>(&mut rng, 2000);
+    let queries = random_vectors::<64>(&mut rng, 20);
+
+    insert_quantized::<64, F16>(&cf, "f16", &items);
+    insert_quantized::<64, Int8>(&cf, "int8", &items);
+
+    let read_txn = cf.begin_read().unwrap();
+    let f16_table = QuantizedVectorTableRead::<64, F16>::open(&read_txn, "f16").unwrap();
+    let int8_table = QuantizedVectorTableRead::<64, Int8>::open(&read_txn, "int8").unwrap();
+
+    let mut f16_hits = 0;
+    let mut int8_hits = 0;
+    for (_, query) in &queries {
+        let exact: Vec<(Uuid, f32)> = items
+            .iter()
+            .map(|(id, vector)| (*id, distance::dot_product(query, vector)))
+            .collect();
+        let exact = top_k(&exact, K);
+
+        let f16_scores: Vec<(Uuid, f32)> = f16_table
+            .all_vectors()
+            .unwrap()
+            .map(|entry| {
+                let (id, guard) = entry.unwrap();
+                (id, distance::dot_product(query, &guard.dequantize()))
+            })
+            .collect();
+
+        // The int8 path scores codes directly, without dequantizing
+        let (query_codes, query_scale) = Int8::quantize(query);
+        let int8_scores: Vec<(Uuid, f32)> = int8_table
+            .all_vectors()
+            .unwrap()
+            .map(|entry| {
+                let (id, guard) = entry.unwrap();
+                let dot = distance::dot_product_i8(&query_codes, guard.codes());
+                (id, dot as f32 * query_scale * guard.scale())
+            })
+            .collect();
+
+        let f16_top = top_k(&f16_scores, K);
+        let int8_top = top_k(&int8_scores, K);
+        f16_hits += exact.iter().filter(|id| f16_top.contains(id)).count();
+        int8_hits += exact.iter().filter(|id| int8_top.contains(id)).count();
+    }
+
+    let total = (queries.len() * K) as f64;
+    assert!(
+        f16_hits as f64 / total >= 0.98,
+        "f16 recall {f16_hits}/{total}"
+    );
+    assert!(
+        int8_hits as f64 / total >= 0.9,
+        "int8 recall {int8_hits}/{total}"
+    );
+}
+
+#[test]
+fn test_quantized_table_type_checks() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+    let id = Uuid::new_v4();
+
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = QuantizedVectorTable::<8, Int8>::open(&write_txn, "codes").unwrap();
+        table.insert(&id, &[0.5; 8]).unwrap();
+        assert!(table.get(&id).unwrap().is_some());
+        assert!(table.remove(&id).unwrap());
+        assert!(!table.remove(&id).unwrap());
+        table.insert(&id, &[0.5; 8]).unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+
+    let write_txn = cf.begin_write().unwrap();
+    assert!(matches!(
+        QuantizedVectorTable::<16, Int8>::open(&write_txn, "codes"),
+        Err(VectorError::DimensionMismatch {
+            stored: 8,
+            requested: 16
+        })
+    ));
+    assert!(matches!(
+        QuantizedVectorTable::<8, F16>::open(&write_txn, "codes"),
+        Err(VectorError::Table(TableError::TableTypeMismatch { .. }))
+    ));
+    drop(write_txn);
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = QuantizedVectorTableRead::<8, Int8>::open(&read_txn, "codes").unwrap();
+    assert_eq!(table.get(&id).unwrap().unwrap().codes(), &[127; 8]);
+}
@@ -0,0 +1,179 @@
+use manifold::column_family::{ColumnFamily, ColumnFamilyDatabase};
+use manifold::{ReadableTableMetadata, TableDefinition};
+use manifold_vectors::{SparseVector, SparseVectorTable, SparseVectorTableRead};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use tempfile::NamedTempFile;
+use uuid::Uuid;
+
+/// Returns a vector with 1 to `max_entries` distinct indices below `dims` and positive weights
+fn random_sparse(rng: &mut StdRng, dims: u32, max_entries: usize) -> SparseVector {
+    let count = rng.random_range(1..=max_entries);
+    let mut indices = BTreeSet::new();
+    while indices.len() < count {
+        indices.insert(rng.random_range(0..dims));
+    }
+    SparseVector::new(
+        indices
+            .into_iter()
+            .map(|index| (index, rng.random_range(0.01..1.0)))
+            .collect(),
+    )
+}
+
+/// Scores every vector sharing an index with `query`, returning the `k` best
+fn brute_force_top_k(
+    vectors: &[(Uuid, SparseVector)],
+    query: &SparseVector,
+    k: usize,
+) -> Vec<(Uuid, f32)> {
+    let mut scores: Vec<(Uuid, f32)> = vectors
+        .iter()
+        .map(|(key, vector)| (*key, query.dot(vector)))
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scores.truncate(k);
+    scores
+}
+
+fn assert_same_results(actual: &[(Uuid, f32)], expected: &[(Uuid, f32)]) {
+    assert_eq!(actual.len(), expected.len());
+    for ((key, score), (expected_key, expected_score)) in actual.iter().zip(expected) {
+        assert_eq!(key, expected_key);
+        assert!(
+            (score - expected_score).abs() < 1e-5,
+            "{score} != {expected_score}"
+        );
+    }
+}
+
+fn postings(cf: &ColumnFamily, name: &str) -> u64 {
+    let read_txn = cf.begin_read().unwrap();
+    let postings_name = format!("manifold_vectors::postings::{name}");
+    let def: TableDefinition<(u32, Uuid), f32> = TableDefinition::new(&postings_name);
+    read_txn.open_table(def).unwrap().len().unwrap()
+}
+
+#[test]
+fn test_top_k_dot_matches_brute_force() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let vectors: Vec<(Uuid, SparseVector)> = (0..2000)
+        .map(|i| (Uuid::from_u128(i), random_sparse(&mut rng, 1000, 30)))
+        .collect();
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut indexed = SparseVectorTable::open_with_index(&write_txn, "indexed").unwrap();
+        let mut plain = SparseVectorTable::open(&write_txn, "plain").unwrap();
+        for (key, vector) in &vectors {
+            indexed.insert(key, vector).unwrap();
+            plain.insert(key, vector).unwrap();
+        }
+        drop(indexed);
+        drop(plain);
+        write_txn.commit().unwrap();
+    }
+    let entries: usize = vectors.iter().map(|(_, vector)| vector.len()).sum();
+    assert_eq!(postings(&cf, "indexed"), entries as u64);
+
+    let read_txn = cf.begin_read().unwrap();
+    let indexed = SparseVectorTableRead::open(&read_txn, "indexed").unwrap();
+    let plain = SparseVectorTableRead::open(&read_txn, "plain").unwrap();
+    assert!(indexed.is_indexed());
+    assert!(!plain.is_indexed());
+
+    for _ in 0..25 {
+        let query = random_sparse(&mut rng, 1000, 10);
+        for k in [1, 10, 100] {
+            let expected = brute_force_top_k(&vectors, &query, k);
+            assert_same_results(&indexed.top_k_dot(&query, k).unwrap(), &expected);
+            assert_same_results(&plain.top_k_dot(&query, k).unwrap(), &expected);
+        }
+    }
+
+    // Only vectors sharing an index with the query are returned
+    let query = SparseVector::new(vec![(5000, 1.0)]);
+    assert!(indexed.top_k_dot(&query, 10).unwrap().is_empty());
+    assert!(plain.top_k_dot(&query, 10).unwrap().is_empty());
+    assert!(
+        indexed
+            .top_k_dot(&random_sparse(&mut rng, 1000, 10), 0)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_sparse_index_maintenance() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family_or_create("vectors").unwrap();
+
+    let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+
+    // Vectors written before the index is enabled are indexed when it is
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = SparseVectorTable::open(&write_txn, "docs").unwrap();
+        table
+            .insert(&a, &SparseVector::new(vec![(1, 1.0), (2, 2.0)]))
+            .unwrap();
+        table
+            .insert(&b, &SparseVector::new(vec![(2, 1.0), (3, 4.0)]))
+            .unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = SparseVectorTable::open_with_index(&write_txn, "docs").unwrap();
+        table
+            .insert(&c, &SparseVector::new(vec![(3, 1.0)]))
+            .unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+    assert_eq!(postings(&cf, "docs"), 5);
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let table = SparseVectorTableRead::open(&read_txn, "docs").unwrap();
+        let query = SparseVector::new(vec![(2, 1.0), (3, 1.0)]);
+        assert_eq!(
+            table.top_k_dot(&query, 10).unwrap(),
+            [(b, 5.0), (a, 2.0), (c, 1.0)]
+        );
+    }
+
+    // Overwriting a vector drops the postings of its old indices, removing one drops them all
+    {
+        let write_txn = cf.begin_write().unwrap();
+        let mut table = SparseVectorTable::open_with_index(&write_txn, "docs").unwrap();
+        table
+            .insert(&a, &SparseVector::new(vec![(7, 3.0)]))
+            .unwrap();
+        let removed = table.remove(&b).unwrap().unwrap();
+        assert_eq!(removed, SparseVector::new(vec![(2, 1.0), (3, 4.0)]));
+        assert!(table.remove(&b).unwrap().is_none());
+        drop(table);
+        write_txn.commit().unwrap();
+    }
+    assert_eq!(postings(&cf, "docs"), 2);
+
+    let read_txn = cf.begin_read().unwrap();
+    let table = SparseVectorTableRead::open(&read_txn, "docs").unwrap();
+    assert_eq!(table.len().unwrap(), 2);
+    let query = SparseVector::new(vec![(2, 1.0), (3, 1.0), (7, 1.0)]);
+    assert_eq!(table.top_k_dot(&query, 10).unwrap(), [(a, 3.0), (c, 1.0)]);
+    assert_eq!(table.top_k_dot(&query, 1).unwrap(), [(a, 3.0)]);
+    assert!(
+        table
+            .top_k_dot(&SparseVector::new(vec![(1, 1.0), (2, 1.0)]), 10)
+            .unwrap()
+            .is_empty()
+    );
+}
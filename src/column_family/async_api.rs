@@ -0,0 +1,141 @@
+use crate::error::CommitError;
+use crate::{DatabaseError, StorageError, TransactionError, WriteTransaction};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::time::Instant;
+
+use super::database::{ColumnFamily, ColumnFamilyDatabase};
+use super::wal::CheckpointReport;
+
+/// Converts the failure of a blocking task into a storage error.
+fn join_error(error: tokio::task::JoinError) -> StorageError {
+    StorageError::from(io::Error::other(error))
+}
+
+/// A write transaction whose commit can be awaited.
+///
+/// Created by [`ColumnFamily::begin_write_async`]. It dereferences to the underlying
+/// [`WriteTransaction`], which is used to open tables and write to them as usual; only
+/// committing differs.
+///
+/// Dropping the transaction without committing it aborts it.
+pub struct AsyncWriteTransaction {
+    txn: WriteTransaction,
+}
+
+impl AsyncWriteTransaction {
+    /// Commits the transaction, resolving once it is durable.
+    ///
+    /// A transaction logged to the WAL is appended to it and made visible right away, like a
+    /// commit with [`Durability::Eventual`](crate::Durability::Eventual), and the future then
+    /// waits for the WAL group commit that syncs its entry. The wait does not block a thread:
+    /// the waiting task is woken when the sync completes, and if no sync is in progress the
+    /// task leads one on tokio's blocking thread pool. Concurrent async and sync commits are
+    /// batched into the same fsync.
+    ///
+    /// Other transactions may therefore see the data before the future resolves. If the
+    /// transaction's durability was set to `Eventual`, the future resolves without waiting
+    /// for the sync. Transactions that bypass the WAL are committed on the blocking thread
+    /// pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the commit fails. If the WAL sync fails, the transaction is
+    /// already visible but may not survive a crash.
+    pub async fn commit(self) -> Result<(), CommitError> {
+        let txn = self.txn;
+        if !txn.is_logged() {
+            return tokio::task::spawn_blocking(move || txn.commit())
+                .await
+                .map_err(join_error)?;
+        }
+
+        let commit = txn.commit_unsynced()?;
+        let mut wal_wait = commit.wal_wait;
+        if let Some((journal, sequence)) = commit.wal {
+            let wait_started = commit.started.map(|_| Instant::now());
+            journal
+                .wait_for_sync_async(sequence)
+                .await
+                .map_err(StorageError::from)?;
+            if let Some(wait_started) = wait_started {
+                wal_wait += wait_started.elapsed();
+            }
+        }
+
+        if let Some(checkpoint_mgr) = commit.checkpoint_manager
+            && checkpoint_mgr.needs_backpressure()
+        {
+            tokio::task::spawn_blocking(move || checkpoint_mgr.apply_backpressure())
+                .await
+                .map_err(join_error)?;
+        }
+
+        if let (Some((metrics, cf_name)), Some(started)) = (commit.metrics, commit.started) {
+            metrics.on_commit(&cf_name, wal_wait, started.elapsed());
+        }
+
+        Ok(())
+    }
+
+    /// Aborts the transaction. Any changes made in it are discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rolling back the transaction fails.
+    pub fn abort(self) -> Result<(), StorageError> {
+        self.txn.abort()
+    }
+}
+
+impl Deref for AsyncWriteTransaction {
+    type Target = WriteTransaction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.txn
+    }
+}
+
+impl DerefMut for AsyncWriteTransaction {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.txn
+    }
+}
+
+impl ColumnFamily {
+    /// Begins a write transaction whose commit can be awaited.
+    ///
+    /// Waiting for the column family's write lock happens on tokio's blocking thread pool,
+    /// so that a writer queued behind another does not hold up the runtime's worker threads.
+    /// Must be called from within a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction cannot be started.
+    pub async fn begin_write_async(&self) -> Result<AsyncWriteTransaction, TransactionError> {
+        let cf = self.clone();
+        let txn = tokio::task::spawn_blocking(move || cf.begin_write())
+            .await
+            .map_err(|e| TransactionError::Storage(join_error(e)))??;
+        Ok(AsyncWriteTransaction { txn })
+    }
+}
+
+impl ColumnFamilyDatabase {
+    /// Checkpoints the WAL like [`Self::checkpoint`], on tokio's blocking thread pool.
+    ///
+    /// Must be called from within a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checkpoint operation fails.
+    pub async fn checkpoint_async(&self) -> Result<CheckpointReport, DatabaseError> {
+        let Some(checkpoint_mgr) = self.checkpoint_manager().cloned() else {
+            return Ok(CheckpointReport::default());
+        };
+        tokio::task::spawn_blocking(move || checkpoint_mgr.checkpoint_now())
+            .await
+            .map_err(|e| DatabaseError::Storage(join_error(e)))?
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))
+    }
+}
@@ -1,8 +1,22 @@
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
-use super::database::ColumnFamilyDatabase;
+use super::database::{ColumnFamilyDatabase, ColumnFamilyDefaults, MIN_COLUMN_FAMILY_SIZE};
+#[cfg(not(target_arch = "wasm32"))]
+use super::encryption::Keyring;
+#[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+use super::encryption::{EncryptionKey, KeyProvider};
+#[cfg(not(target_arch = "wasm32"))]
+use super::metrics::DatabaseMetrics;
+#[cfg(not(target_arch = "wasm32"))]
+use super::wal::WALConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use super::wal::recovery::{RecoveryOptions, RecoveryProgress};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::DatabaseError;
 
@@ -28,6 +42,13 @@ const DEFAULT_POOL_SIZE: usize = 64;
 #[cfg(not(target_arch = "wasm32"))]
 pub struct ColumnFamilyDatabaseBuilder {
     pool_size: usize,
+    pool_size_auto: bool,
+    open_timeout: Option<Duration>,
+    wal_config: WALConfig,
+    cf_defaults: ColumnFamilyDefaults,
+    recovery: RecoveryOptions,
+    keyring: Keyring,
+    metrics: Option<Arc<dyn DatabaseMetrics>>,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -36,6 +57,13 @@ impl ColumnFamilyDatabaseBuilder {
     pub fn new() -> Self {
         Self {
             pool_size: DEFAULT_POOL_SIZE,
+            pool_size_auto: false,
+            open_timeout: None,
+            wal_config: WALConfig::default(),
+            cf_defaults: ColumnFamilyDefaults::default(),
+            recovery: RecoveryOptions::default(),
+            keyring: Keyring::default(),
+            metrics: None,
         }
     }
 
@@ -59,6 +87,21 @@ impl ColumnFamilyDatabaseBuilder {
         self
     }
 
+    /// Grows the file handle pool to one handle per column family.
+    ///
+    /// The pool is sized to the number of column families in the database when it is
+    /// opened, and grows as column families are created, so column families never evict
+    /// each other's handles. `pool_size` remains the minimum. Useful for databases with
+    /// more column families than the default pool size, at the cost of one file descriptor
+    /// per column family in use.
+    ///
+    /// Default: false
+    #[must_use]
+    pub fn pool_size_auto(mut self, auto: bool) -> Self {
+        self.pool_size_auto = auto;
+        self
+    }
+
     /// Disables the Write-Ahead Log (WAL) for this database.
     ///
     /// **Warning:** This significantly reduces write performance:
@@ -75,6 +118,176 @@ impl ColumnFamilyDatabaseBuilder {
         self
     }
 
+    /// Sets how long `open()` keeps retrying when the database file is locked.
+    ///
+    /// By default `open()` fails immediately with [`DatabaseError::FileLocked`] if another
+    /// handle (in this or another process) holds the file lock. With a timeout set, the lock
+    /// is retried until it is acquired or the timeout elapses.
+    ///
+    /// Default: `None` (no retry)
+    #[must_use]
+    pub fn open_timeout(mut self, timeout: Duration) -> Self {
+        self.open_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets checkpoint intervals and WAL size limits.
+    ///
+    /// Has no effect when the WAL is disabled. See [`WALConfig`] for the individual settings,
+    /// including the soft and hard limits at which commits are throttled.
+    ///
+    /// Default: [`WALConfig::default()`]
+    #[must_use]
+    pub fn wal_config(mut self, config: WALConfig) -> Self {
+        self.wal_config = config;
+        self
+    }
+
+    /// Sets the initial size of column families created without an explicit size.
+    ///
+    /// This applies to [`ColumnFamilyDatabase::column_family_or_create`] and to
+    /// [`ColumnFamilyDatabase::create_column_family`] with `None`. Column families grow past
+    /// their initial size through segment expansion, so databases with many small column
+    /// families can use a small size to avoid reserving space they may never use.
+    ///
+    /// Default: 1 GB (4 MB for an in-memory database)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is below the minimum column family size of 16 KB.
+    #[must_use]
+    pub fn default_cf_size(mut self, size: u64) -> Self {
+        assert!(
+            size >= MIN_COLUMN_FAMILY_SIZE,
+            "column family size must be at least {MIN_COLUMN_FAMILY_SIZE} bytes"
+        );
+        self.cf_defaults.size = Some(size);
+        self
+    }
+
+    /// Sets whether the file is extended to cover a new column family when it is created.
+    ///
+    /// Pre-allocation avoids file size changes during writes, but leaves the file sparse
+    /// until the space is used. Disable it on filesystems, or with backup tools, that handle
+    /// large sparse files poorly; the file then grows as data is written.
+    ///
+    /// Default: `true`
+    #[must_use]
+    pub fn preallocate(mut self, preallocate: bool) -> Self {
+        self.cf_defaults.preallocate = preallocate;
+        self
+    }
+
+    /// Sets how much memory the database of each column family uses to cache pages.
+    ///
+    /// Every column family is a separate database with its own cache, so a column family that
+    /// scans much more data than fits in its cache does not evict the pages of the others.
+    /// The size can be set for individual column families with
+    /// [`cf_cache_size`](Self::cf_cache_size); [`ColumnFamily::cache_stats`] reports how well
+    /// each cache works.
+    ///
+    /// Default: 1 GiB, allocated as pages are read
+    ///
+    /// [`ColumnFamily::cache_stats`]: super::ColumnFamily::cache_stats
+    #[must_use]
+    pub fn default_cf_cache_size(mut self, bytes: usize) -> Self {
+        self.cf_defaults.cache_size = Some(bytes);
+        self
+    }
+
+    /// Sets how much memory the database of the column family `name` uses to cache pages,
+    /// overriding [`default_cf_cache_size`](Self::default_cf_cache_size).
+    ///
+    /// A small, frequently read column family can be given a cache that holds all of it,
+    /// and a column family that is mostly scanned a small one.
+    ///
+    /// Default: the [`default_cf_cache_size`](Self::default_cf_cache_size)
+    #[must_use]
+    pub fn cf_cache_size(mut self, name: impl Into<String>, bytes: usize) -> Self {
+        self.cf_defaults.cf_cache_sizes.insert(name.into(), bytes);
+        self
+    }
+
+    /// Sets whether a damaged WAL makes `open()` fail instead of being repaired.
+    ///
+    /// A crash while a transaction is being appended can leave an incomplete entry at the
+    /// end of the WAL. By default, recovery replays every valid entry before it, discards the
+    /// damaged tail, and truncates the WAL so the database opens normally; the transaction
+    /// that was being appended was never acknowledged, so nothing committed is lost. Entries
+    /// whose roots lie outside their column family are skipped the same way. With strict
+    /// recovery, either case is reported as an error and the WAL is left as it was.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn strict_recovery(mut self, strict: bool) -> Self {
+        self.recovery.strict = strict;
+        self
+    }
+
+    /// Sets a callback that reports how far WAL recovery has got when the database is opened.
+    ///
+    /// Recovery reads the WAL once, from start to end. The callback is called every 10,000
+    /// entries or 64 MiB read, and once more when the whole WAL has been read, so progress
+    /// through a large WAL left by a crash can be shown while `open()` blocks. It is not
+    /// called when there is nothing to recover. Progress is also logged at info level when
+    /// the `logging` feature is enabled.
+    ///
+    /// Default: `None`
+    #[must_use]
+    pub fn on_recovery_progress(
+        mut self,
+        callback: impl FnMut(RecoveryProgress) + Send + 'static,
+    ) -> Self {
+        self.recovery.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Encrypts the column family `name` with `key`.
+    ///
+    /// A column family created while a key is set for it is encrypted at rest: its pages are
+    /// stored encrypted with XChaCha20-Poly1305, and so are the WAL entries written for it.
+    /// Encryption is recorded in the master header when the column family is created, so the
+    /// same key must be set every time the database is opened afterwards. Opening fails if it
+    /// is missing or wrong, and also if a key is set for an existing column family that is
+    /// not encrypted.
+    ///
+    /// A key set here has id 0 and takes precedence over the
+    /// [`key_provider`](Self::key_provider) for its column family.
+    ///
+    /// Default: no column family is encrypted
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn cf_key(mut self, name: impl Into<String>, key: impl Into<EncryptionKey>) -> Self {
+        self.keyring.insert(name.into(), key.into());
+        self
+    }
+
+    /// Sets the provider of keys for encrypted column families, which allows keys to be
+    /// rotated.
+    ///
+    /// Column families for which the provider returns a current key when they are created
+    /// are encrypted, as with [`cf_key`](Self::cf_key). See [`KeyProvider`] for how keys are
+    /// rotated.
+    ///
+    /// Default: `None`
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn key_provider(mut self, provider: Arc<dyn KeyProvider>) -> Self {
+        self.keyring.set_provider(provider);
+        self
+    }
+
+    /// Sets the hooks that receive commit, checkpoint and segment allocation events.
+    ///
+    /// See [`DatabaseMetrics`] for when each hook is called.
+    ///
+    /// Default: `None` (no instrumentation)
+    #[must_use]
+    pub fn metrics(mut self, metrics: Arc<dyn DatabaseMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Opens or creates a column family database at the specified path.
     ///
     /// If the file does not exist, it will be created with an empty master header.
@@ -86,10 +299,41 @@ impl ColumnFamilyDatabaseBuilder {
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be opened or the header is invalid.
+    /// Returns [`DatabaseError::FileLocked`] if the file is locked by another handle and
+    /// could not be acquired within the configured `open_timeout`, or another error if the
+    /// file cannot be opened or the header is invalid.
     pub fn open(self, path: impl AsRef<Path>) -> Result<ColumnFamilyDatabase, DatabaseError> {
         let path = path.as_ref().to_path_buf();
-        ColumnFamilyDatabase::open_with_builder(path, self.pool_size)
+        ColumnFamilyDatabase::open_with_builder(
+            path,
+            self.pool_size,
+            self.pool_size_auto,
+            self.open_timeout,
+            self.wal_config,
+            self.cf_defaults,
+            self.recovery,
+            self.keyring,
+            self.metrics,
+        )
+    }
+
+    /// Creates a column family database held entirely in memory.
+    ///
+    /// Nothing is read from or written to the filesystem, and all data is lost when the
+    /// database is dropped. There is no file handle pool; `pool_size` only controls whether
+    /// the WAL is enabled, in which case it is also kept in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the master header or WAL cannot be initialized.
+    pub fn open_in_memory(self) -> Result<ColumnFamilyDatabase, DatabaseError> {
+        ColumnFamilyDatabase::open_in_memory_with_builder(
+            self.pool_size > 0,
+            self.wal_config,
+            self.cf_defaults,
+            self.keyring,
+            self.metrics,
+        )
     }
 }
 
@@ -130,6 +374,67 @@ mod tests {
         assert!(builder.pool_size > 0); // WAL enabled
     }
 
+    #[test]
+    fn test_builder_open_timeout() {
+        let builder = ColumnFamilyDatabaseBuilder::new();
+        assert_eq!(builder.open_timeout, None);
+
+        let builder = builder.open_timeout(Duration::from_millis(250));
+        assert_eq!(builder.open_timeout, Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn test_builder_wal_config() {
+        let builder = ColumnFamilyDatabaseBuilder::new();
+        assert_eq!(
+            builder.wal_config.soft_wal_size_limit,
+            WALConfig::default().soft_wal_size_limit
+        );
+
+        let builder = builder.wal_config(WALConfig {
+            soft_wal_size_limit: 1024,
+            hard_wal_size_limit: 4096,
+            ..WALConfig::default()
+        });
+        assert_eq!(builder.wal_config.soft_wal_size_limit, 1024);
+        assert_eq!(builder.wal_config.hard_wal_size_limit, 4096);
+    }
+
+    #[test]
+    fn test_builder_cf_defaults() {
+        let builder = ColumnFamilyDatabaseBuilder::new();
+        assert_eq!(builder.cf_defaults.size, None);
+        assert!(builder.cf_defaults.preallocate);
+
+        let builder = builder.default_cf_size(16 * 1024 * 1024).preallocate(false);
+        assert_eq!(builder.cf_defaults.size, Some(16 * 1024 * 1024));
+        assert!(!builder.cf_defaults.preallocate);
+    }
+
+    #[test]
+    fn test_builder_cf_cache_size() {
+        let builder = ColumnFamilyDatabaseBuilder::new();
+        assert_eq!(builder.cf_defaults.cache_size_for("hot"), None);
+
+        let builder = builder
+            .default_cf_cache_size(1024 * 1024)
+            .cf_cache_size("hot", 64 * 1024 * 1024);
+        assert_eq!(
+            builder.cf_defaults.cache_size_for("hot"),
+            Some(64 * 1024 * 1024)
+        );
+        assert_eq!(
+            builder.cf_defaults.cache_size_for("scan"),
+            Some(1024 * 1024)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "column family size must be at least")]
+    fn test_builder_default_cf_size_too_small() {
+        let _ = ColumnFamilyDatabaseBuilder::new().default_cf_size(4096);
+    }
+
     #[test]
     fn test_builder_open() {
         let tmpfile = NamedTempFile::new().unwrap();
@@ -0,0 +1,181 @@
+use crate::types::{Key, Value};
+use crate::{Durability, Error, TableDefinition};
+use std::borrow::Borrow;
+use std::time::{Duration, Instant};
+
+use super::database::ColumnFamily;
+
+/// Default number of rows committed together by a [`BulkLoader`].
+const DEFAULT_CHUNK_ROWS: usize = 100_000;
+
+/// Default number of chunks committed between syncs by a [`BulkLoader`].
+const DEFAULT_SYNC_EVERY: usize = 10;
+
+/// Progress of a bulk load, as reported by [`BulkLoader::on_progress`] and returned by
+/// [`BulkLoader::load`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkLoadProgress {
+    /// Number of rows committed so far.
+    pub rows: u64,
+    /// Total serialized size of the keys and values committed so far, in bytes.
+    pub bytes: u64,
+    /// Number of chunks committed so far.
+    pub chunks: u64,
+    /// Time since the load started.
+    pub elapsed: Duration,
+}
+
+/// Loads a stream of rows into one table of a column family, bypassing the WAL.
+///
+/// Created by [`ColumnFamily::bulk_loader`]. Rows are inserted in chunks of
+/// [`chunk_rows`](Self::chunk_rows), and each chunk is committed as its own transaction, so
+/// memory use is bounded by the chunk size rather than the size of the load. Chunks are
+/// committed without the WAL, like [`ColumnFamily::begin_write_unlogged`], and only every
+/// [`sync_every`](Self::sync_every)-th chunk is synced to disk. The last chunk is always
+/// synced, so the whole load is durable once [`load`](Self::load) returns.
+///
+/// A crash during the load keeps every chunk up to the last synced one and loses the chunks
+/// after it. Each chunk is either fully present or fully absent; the load can be resumed
+/// after the last row that is present.
+///
+/// The column family's write lock is only held while a chunk is being written, so other
+/// writers may commit between chunks.
+pub struct BulkLoader<'a, K: Key + 'static, V: Value + 'static> {
+    cf: &'a ColumnFamily,
+    table: TableDefinition<'a, K, V>,
+    chunk_rows: usize,
+    sync_every: usize,
+    checkpoint: bool,
+    progress: Option<Box<dyn FnMut(BulkLoadProgress) + 'a>>,
+}
+
+impl<'a, K: Key + 'static, V: Value + 'static> BulkLoader<'a, K, V> {
+    pub(crate) fn new(cf: &'a ColumnFamily, table: TableDefinition<'a, K, V>) -> Self {
+        Self {
+            cf,
+            table,
+            chunk_rows: DEFAULT_CHUNK_ROWS,
+            sync_every: DEFAULT_SYNC_EVERY,
+            checkpoint: false,
+            progress: None,
+        }
+    }
+
+    /// Sets the number of rows committed together in one transaction.
+    ///
+    /// Larger chunks commit less often, at the cost of holding more dirty pages in memory.
+    ///
+    /// Default: 100,000
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows` is zero.
+    #[must_use]
+    pub fn chunk_rows(mut self, rows: usize) -> Self {
+        assert!(rows > 0, "chunk size must be non-zero");
+        self.chunk_rows = rows;
+        self
+    }
+
+    /// Sets how many chunks are committed between syncs to disk.
+    ///
+    /// A crash loses at most the chunks committed since the last sync. With `1`, every chunk
+    /// is synced.
+    ///
+    /// Default: 10
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunks` is zero.
+    #[must_use]
+    pub fn sync_every(mut self, chunks: usize) -> Self {
+        assert!(chunks > 0, "sync interval must be non-zero");
+        self.sync_every = chunks;
+        self
+    }
+
+    /// Sets whether the WAL is checkpointed once the load has finished.
+    ///
+    /// The load itself does not go through the WAL, but other column families may have
+    /// written to it in the meantime. Checkpointing leaves the WAL empty, so that a later
+    /// open has nothing to replay. Has no effect when the WAL is disabled.
+    ///
+    /// Default: `false`
+    #[must_use]
+    pub fn checkpoint_on_finish(mut self, checkpoint: bool) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Sets a callback that is called after every committed chunk.
+    #[must_use]
+    pub fn on_progress(mut self, callback: impl FnMut(BulkLoadProgress) + 'a) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Inserts every row of `rows` into the table, creating it if it does not exist.
+    ///
+    /// Rows with a key that is already present replace the existing value. Returns the
+    /// progress after the last chunk.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered. Chunks committed before it stay committed, and
+    /// are durable up to the last synced one.
+    pub fn load<'k, 'v, I, KB, VB>(mut self, rows: I) -> Result<BulkLoadProgress, Error>
+    where
+        I: IntoIterator<Item = (KB, VB)>,
+        KB: Borrow<K::SelfType<'k>>,
+        VB: Borrow<V::SelfType<'v>>,
+    {
+        let started = Instant::now();
+        let mut progress = BulkLoadProgress {
+            rows: 0,
+            bytes: 0,
+            chunks: 0,
+            elapsed: Duration::ZERO,
+        };
+
+        let mut rows = rows.into_iter().peekable();
+        while rows.peek().is_some() {
+            let mut txn = self.cf.begin_write_unlogged()?;
+            let mut chunk_bytes = 0;
+            let mut chunk_rows = 0;
+            {
+                let mut table = txn.open_table(self.table)?;
+                for (key, value) in rows.by_ref().take(self.chunk_rows) {
+                    chunk_bytes += K::as_bytes(key.borrow()).as_ref().len()
+                        + V::as_bytes(value.borrow()).as_ref().len();
+                    chunk_rows += 1;
+                    table.insert(key, value)?;
+                }
+            }
+
+            // Sync every few chunks, and always after the last one
+            let chunk = progress.chunks + 1;
+            let sync = chunk.is_multiple_of(self.sync_every as u64) || rows.peek().is_none();
+            if !sync {
+                txn.set_durability(Durability::None)?;
+            }
+            txn.commit()?;
+
+            progress.rows += chunk_rows;
+            progress.bytes += chunk_bytes as u64;
+            progress.chunks = chunk;
+            progress.elapsed = started.elapsed();
+            if let Some(callback) = &mut self.progress {
+                callback(progress);
+            }
+        }
+
+        if self.checkpoint
+            && let Some(checkpoint_mgr) = self.cf.checkpoint_manager()
+        {
+            checkpoint_mgr.checkpoint_now()?;
+        }
+
+        progress.elapsed = started.elapsed();
+        Ok(progress)
+    }
+}
@@ -1,37 +1,114 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
-use std::io;
+use std::io::{self, Read, Write};
 use std::mem::ManuallyDrop;
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 #[cfg(not(target_arch = "wasm32"))]
-use crate::backends::FileBackend;
+use crate::CompactionError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::TableDefinition;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backends::{FileBackend, InMemoryBackend};
 use crate::db::ReadableDatabase;
 use crate::transaction_tracker::TransactionId;
 #[cfg(not(target_arch = "wasm32"))]
-use crate::tree_store::BtreeHeader;
+use crate::tree_store::{BtreeHeader, TransactionalMemory};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::types::{Key, Value};
 use crate::{
-    Database, DatabaseError, ReadTransaction, StorageBackend, StorageError, TransactionError,
-    WriteTransaction,
+    CacheStats, Database, DatabaseError, Error, MultimapTableHandle, ReadTransaction,
+    ReadableTableMetadata, Snapshot, StorageBackend, StorageError, TableError, TableHandle,
+    TransactionError, UntypedMultimapTableHandle, UntypedTableHandle, WriteTransaction,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
 use super::builder::ColumnFamilyDatabaseBuilder;
 #[cfg(not(target_arch = "wasm32"))]
-use super::file_handle_pool::FileHandlePool;
-use super::header::{ColumnFamilyMetadata, FreeSegment, MasterHeader, Segment, PAGE_SIZE};
+use super::bulk_load::BulkLoader;
+use super::encryption::Keyring;
+use super::export;
+#[cfg(not(target_arch = "wasm32"))]
+use super::file_handle_pool::{BackendSource, FileHandlePool, FileHandlePoolStats};
+use super::header::{
+    ColumnFamilyMetadata, HEADER_SIZE, MAX_USER_METADATA_SIZE, MasterHeader, PAGE_SIZE, Segment,
+    round_up_to_page,
+};
+use super::metrics::DatabaseMetrics;
 use super::partitioned_backend::PartitionedStorageBackend;
-use super::state::ColumnFamilyState;
-use super::wal::checkpoint::CheckpointManager;
 #[cfg(not(target_arch = "wasm32"))]
+use super::redb_import::{self, CopyProgress, RedbImportOptions, RedbImportProgress, RedbSource};
+use super::state::ColumnFamilyState;
+use super::wal::checkpoint::{CheckpointManager, CheckpointTarget};
 use super::wal::config::CheckpointConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use super::wal::config::WALConfig;
+#[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+use super::wal::entry::PayloadCipher;
 use super::wal::journal::WALJournal;
+#[cfg(not(target_arch = "wasm32"))]
+use super::wal::recovery::{
+    PROGRESS_INTERVAL_BYTES, PROGRESS_INTERVAL_ENTRIES, RecoveryOptions, RecoveryProgress, Replay,
+    ReplayPlan,
+};
+use super::wal::stats::{CheckpointReport, WALStats};
+#[cfg(not(target_arch = "wasm32"))]
+use super::write_group::WriteGroup;
 
 /// Default size allocated to a new column family (1 GB).
 const DEFAULT_COLUMN_FAMILY_SIZE: u64 = 1024 * 1024 * 1024;
 
+/// Default size allocated to a new column family in an in-memory database (4 MB).
+///
+/// In memory, partitions are laid out in a single buffer that must be materialized up to
+/// the end of the last one in use, so they start small and grow through segment expansion.
+const DEFAULT_IN_MEMORY_COLUMN_FAMILY_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Smallest size a column family can be created with (four pages).
+///
+/// Smaller column families start here and grow through segment expansion.
+pub(crate) const MIN_COLUMN_FAMILY_SIZE: u64 = 4 * PAGE_SIZE as u64;
+
+/// How column families are created when no size is given, and how their databases are
+/// opened.
+#[derive(Debug, Clone)]
+pub(crate) struct ColumnFamilyDefaults {
+    /// Initial size of a new column family. `None` uses the 1 GB default (4 MB in memory).
+    pub(crate) size: Option<u64>,
+    /// Whether the file is extended to cover a new column family when it is created.
+    pub(crate) preallocate: bool,
+    /// Cache size of each column family's database. `None` uses the 1 GiB default of
+    /// [`Builder`](crate::Builder).
+    pub(crate) cache_size: Option<usize>,
+    /// Cache sizes of individual column families, which take precedence over `cache_size`.
+    pub(crate) cf_cache_sizes: HashMap<String, usize>,
+}
+
+impl ColumnFamilyDefaults {
+    /// Returns the cache size of the database of the column family `name`.
+    pub(crate) fn cache_size_for(&self, name: &str) -> Option<usize> {
+        self.cf_cache_sizes.get(name).copied().or(self.cache_size)
+    }
+}
+
+impl Default for ColumnFamilyDefaults {
+    fn default() -> Self {
+        Self {
+            size: None,
+            preallocate: true,
+            cache_size: None,
+            cf_cache_sizes: HashMap::new(),
+        }
+    }
+}
+
 /// Errors that can occur when working with column families.
 #[derive(Debug)]
 pub enum ColumnFamilyError {
@@ -82,6 +159,94 @@ impl From<io::Error> for ColumnFamilyError {
     }
 }
 
+/// Summary of a backup written by [`ColumnFamilyDatabase::backup_to`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupSummary {
+    /// Size of the backup file in bytes.
+    pub bytes_written: u64,
+    /// Names of the column families copied, in master header order.
+    pub column_families: Vec<String>,
+}
+
+/// Free space statistics returned by [`ColumnFamilyDatabase::fragmentation_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentationStats {
+    /// Total size of all free segments in bytes.
+    pub free_bytes: u64,
+    /// Size of the largest free segment in bytes, or 0 if there is none.
+    pub largest_free_block: u64,
+    /// Number of free segments.
+    pub fragments: usize,
+}
+
+/// How much a column family would gain from maintenance, as returned by
+/// [`ColumnFamily::maintenance_hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceHint {
+    /// Approximate bytes of free pages inside the column family's allocated space, left behind
+    /// by deleted or overwritten data. Compacting the column family would return them.
+    pub reclaimable_bytes: u64,
+    /// WAL entries of the column family pending checkpoint. Always 0 if WAL is disabled.
+    pub pending_wal_entries: u64,
+    /// Time since the column family was last checkpointed, by a full checkpoint or one of
+    /// the column family alone.
+    ///
+    /// `None` if it has not been checkpointed since the database was opened, if WAL is
+    /// disabled, and always on WASM, where no system clock is available.
+    pub since_checkpoint: Option<Duration>,
+}
+
+impl MaintenanceHint {
+    /// Returns `true` if any measure has reached its threshold.
+    ///
+    /// The time since the last checkpoint only counts while WAL entries are pending, since a
+    /// checkpoint has nothing to do otherwise.
+    pub fn needs_maintenance(&self, thresholds: &MaintenanceThresholds) -> bool {
+        self.reclaimable_bytes >= thresholds.reclaimable_bytes
+            || self.pending_wal_entries >= thresholds.pending_wal_entries
+            || (self.pending_wal_entries > 0
+                && self
+                    .since_checkpoint
+                    .is_some_and(|since| since >= thresholds.since_checkpoint))
+    }
+}
+
+/// Limits above which [`MaintenanceHint::needs_maintenance`] reports that a column family
+/// needs maintenance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceThresholds {
+    /// Reclaimable bytes at which compaction is worthwhile (default: 64 MiB).
+    pub reclaimable_bytes: u64,
+    /// Pending WAL entries at which a checkpoint is worthwhile (default: 10,000).
+    pub pending_wal_entries: u64,
+    /// Time since the last checkpoint after which pending WAL entries should be checkpointed
+    /// (default: 5 minutes).
+    pub since_checkpoint: Duration,
+}
+
+impl Default for MaintenanceThresholds {
+    fn default() -> Self {
+        Self {
+            reclaimable_bytes: 64 * 1024 * 1024,
+            pending_wal_entries: 10_000,
+            since_checkpoint: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// A table in a column family, as returned by [`ColumnFamily::list_tables`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInfo {
+    /// Name of the table.
+    pub name: String,
+    /// Number of entries in the table as of the latest commit. For a multimap table, this
+    /// counts every value of every key.
+    pub entries: u64,
+    /// Whether this is a multimap table.
+    pub multimap: bool,
+}
+
 /// A high-performance database that manages multiple independent column families within a single file.
 ///
 /// **This is the recommended interface for most use cases**, providing excellent concurrent
@@ -156,12 +321,9 @@ impl From<io::Error> for ColumnFamilyError {
 /// ```
 pub struct ColumnFamilyDatabase {
     #[cfg(not(target_arch = "wasm32"))]
-    path: PathBuf,
-    #[cfg(not(target_arch = "wasm32"))]
-    header_backend: Arc<FileBackend>,
+    path: Option<PathBuf>,
     #[cfg(not(target_arch = "wasm32"))]
-    handle_pool: Arc<FileHandlePool>,
-    #[cfg(target_arch = "wasm32")]
+    storage: BackendSource,
     header_backend: Arc<dyn StorageBackend>,
     #[cfg(target_arch = "wasm32")]
     file_name: String,
@@ -171,6 +333,10 @@ pub struct ColumnFamilyDatabase {
     header: Arc<RwLock<MasterHeader>>,
     wal_journal: Option<Arc<WALJournal>>,
     checkpoint_manager: Option<Arc<CheckpointManager>>,
+    cf_defaults: ColumnFamilyDefaults,
+    keyring: Arc<Keyring>,
+    metrics: Option<Arc<dyn DatabaseMetrics>>,
+    closed: bool,
 }
 
 impl ColumnFamilyDatabase {
@@ -221,22 +387,48 @@ impl ColumnFamilyDatabase {
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be opened or the header is invalid.
+    /// Returns [`DatabaseError::FileLocked`] if another handle holds the file lock, or another
+    /// error if the file cannot be opened or the header is invalid.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn open(path: impl AsRef<Path>) -> Result<Self, DatabaseError> {
         Self::builder().open(path)
     }
 
+    /// Creates a column family database held entirely in memory with optimal defaults.
+    ///
+    /// The master header, column family partitions and WAL all live in memory, so nothing
+    /// touches the filesystem and all data is lost when the database is dropped. Column
+    /// families behave exactly as they do for a file-backed database, which makes this
+    /// useful for tests.
+    ///
+    /// This is equivalent to `ColumnFamilyDatabase::builder().open_in_memory()`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let db = ColumnFamilyDatabase::open_in_memory()?;
+    /// let users = db.column_family_or_create("users")?;
+    /// assert!(db.path().is_none());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the master header or WAL cannot be initialized.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_in_memory() -> Result<Self, DatabaseError> {
+        Self::builder().open_in_memory()
+    }
+
     /// Opens or creates a column family database with a WASM backend.
     ///
     /// This is the WASM-specific initialization that accepts a `WasmStorageBackend`
-    /// instead of a file path.
+    /// instead of a file path. The database is opened without a WAL; call
+    /// [`enable_wal`](Self::enable_wal) afterwards to enable it.
     ///
     /// # Arguments
     ///
     /// * `file_name` - Name of the OPFS file (for identification in errors)
     /// * `backend` - The WASM storage backend to use
-    /// * `pool_size` - WAL pool size (0 to disable WAL)
     ///
     /// # Example
     ///
@@ -246,18 +438,17 @@ impl ColumnFamilyDatabase {
     ///
     /// // In a Web Worker context:
     /// let backend = WasmStorageBackend::new("my-database.db").await?;
-    /// let db = ColumnFamilyDatabase::open_with_backend(
-    ///     "my-database.db",
+    /// let mut db = ColumnFamilyDatabase::open_with_backend_internal(
+    ///     "my-database.db".to_string(),
     ///     Arc::new(backend),
-    ///     64, // WAL enabled
     /// )?;
+    /// let wal_backend = WasmStorageBackend::new("my-database.db.wal").await?;
+    /// db.enable_wal(Arc::new(wal_backend))?;
     /// ```
     #[cfg(target_arch = "wasm32")]
     pub(crate) fn open_with_backend_internal(
         file_name: String,
         backend: Arc<dyn StorageBackend>,
-        wal_journal: Option<Arc<WALJournal>>,
-        checkpoint_manager: Option<Arc<CheckpointManager>>,
     ) -> Result<Self, DatabaseError> {
         let file_name = file_name.into();
 
@@ -267,34 +458,27 @@ impl ColumnFamilyDatabase {
             == 0;
 
         let header = if is_new {
-            let header = MasterHeader::new();
-            let header_bytes = header
-                .to_bytes()
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
-            backend
-                .write(0, &header_bytes)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-            backend
-                .sync_data()
+            let mut header = MasterHeader::new();
+            header
+                .write_to(backend.as_ref())
                 .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
 
             header
         } else {
-            let mut header_bytes = vec![0u8; PAGE_SIZE];
-            backend
-                .read(0, &mut header_bytes)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
-            MasterHeader::from_bytes(&header_bytes)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?
+            Self::read_header(backend.as_ref())?
         };
 
+        let keyring = Arc::new(Keyring::default());
+        Self::check_encryption(&header, &keyring)?;
         let header = Arc::new(RwLock::new(header));
 
         let mut column_families = HashMap::new();
         for cf_meta in &header.read().unwrap().column_families {
-            let state = ColumnFamilyState::new(cf_meta.name.clone(), cf_meta.segments.clone());
+            let state = ColumnFamilyState::new(
+                cf_meta.name.clone(),
+                cf_meta.generation,
+                cf_meta.segments.clone(),
+            );
             column_families.insert(cf_meta.name.clone(), Arc::new(state));
         }
 
@@ -304,81 +488,175 @@ impl ColumnFamilyDatabase {
             file_growth_lock: Arc::new(std::sync::Mutex::new(())),
             column_families: Arc::new(RwLock::new(column_families)),
             header,
-            wal_journal,
-            checkpoint_manager,
+            wal_journal: None,
+            checkpoint_manager: None,
+            cf_defaults: ColumnFamilyDefaults::default(),
+            keyring,
+            metrics: None,
+            closed: false,
         })
     }
 
-    /// Performs WAL recovery without creating Database instances.
+    /// Replays the WAL into the column families without creating Database instances.
     /// Operates entirely at the `TransactionalMemory` layer to avoid Drop cleanup issues.
     ///
+    /// The journal is read once, one entry at a time, and folded into a [`ReplayPlan`] that
+    /// keeps only the entry each column family is recovered to, so memory use does not grow
+    /// with the size of the WAL. Progress is logged and passed to the callback in `options`
+    /// every [`PROGRESS_INTERVAL_ENTRIES`] entries or [`PROGRESS_INTERVAL_BYTES`] bytes.
+    ///
     /// # Arguments
     /// * `column_families` - Map of column family names to their states
-    /// * `handle_pool` - File handle pool for acquiring storage backends
-    /// * `journal` - WAL journal to read entries from
+    /// * `storage` - Source for acquiring storage backends
+    /// * `journal` - WAL journal to recover
+    /// * `options` - Strict mode, which fails instead of repairing a damaged journal or
+    ///   skipping entries whose roots lie outside their column family's segments, and the
+    ///   progress callback
     ///
     /// # Returns
     /// Ok(()) if recovery succeeded, Err otherwise
     #[cfg(not(target_arch = "wasm32"))]
     fn perform_wal_recovery(
         column_families: &HashMap<String, Arc<ColumnFamilyState>>,
-        handle_pool: &FileHandlePool,
+        storage: &BackendSource,
         journal: &WALJournal,
+        mut options: RecoveryOptions,
     ) -> Result<(), DatabaseError> {
-        // Read all WAL entries
-        let entries = journal
-            .read_from(0)
-            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
-        if entries.is_empty() {
-            return Ok(());
-        }
-
-        #[cfg(feature = "logging")]
-        log::info!("Performing WAL recovery for {} entries", entries.len());
-
-        // Group entries by column family
-        let mut cf_entries: HashMap<String, Vec<&super::wal::entry::WALEntry>> = HashMap::new();
-        for entry in &entries {
-            cf_entries
-                .entry(entry.cf_name.clone())
-                .or_default()
-                .push(entry);
-        }
+        let start = Instant::now();
+        let total_bytes = journal.file_size()?;
+        let mut progress = RecoveryProgress {
+            entries: 0,
+            bytes: 0,
+            total_bytes,
+            elapsed: Duration::ZERO,
+        };
+        let mut reported = progress;
+        let mut report = |progress: RecoveryProgress| {
+            #[cfg(feature = "logging")]
+            log::info!(
+                "WAL recovery: read {} entries, {} of {} bytes",
+                progress.entries,
+                progress.bytes,
+                progress.total_bytes
+            );
+            if let Some(callback) = options.progress.as_mut() {
+                callback(progress);
+            }
+        };
 
         // Create Database instances for recovery using ManuallyDrop to prevent Drop cleanup
         // This gives us proper initialization (allocator state, repair if needed)
-        // but prevents Database::drop from running cleanup that would corrupt recovery
+        // but prevents Database::drop from running cleanup that would corrupt recovery.
+        // They are created when the first entry for their column family is read.
         let mut recovery_dbs: HashMap<String, ManuallyDrop<Database>> = HashMap::new();
+        let mut plan = ReplayPlan::default();
+
+        journal.recover(options.strict, |entry, offset| {
+            progress.entries += 1;
+            progress.bytes = offset;
+
+            plan.push(entry, |entry| -> Result<Replay, DatabaseError> {
+                // Skip entries for column families that are no longer in the header (deleted
+                // after the entry was written). Their segments may already belong to another
+                // column family.
+                let Some(cf_state) = column_families.get(&entry.cf_name) else {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Skipping WAL entry {} for deleted column family '{}'",
+                        entry.sequence,
+                        entry.cf_name
+                    );
+                    return Ok(Replay::Skip);
+                };
+                // A column family that was deleted and recreated under the same name has a
+                // new generation; entries from the old incarnation must not be replayed into it.
+                if cf_state.generation != entry.cf_generation {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Discarding WAL entry {} for column family '{}': generation {} does not match current generation {}",
+                        entry.sequence,
+                        entry.cf_name,
+                        entry.cf_generation,
+                        cf_state.generation
+                    );
+                    return Ok(Replay::Skip);
+                }
 
-        for (cf_name, cf_state) in column_families {
-            if !cf_entries.contains_key(cf_name) {
-                continue; // Skip CFs not in WAL
-            }
+                let segments = cf_state.segments.read().unwrap().clone();
+                let capacity = cf_state.capacity(&segments);
+                let db = match recovery_dbs.entry(entry.cf_name.clone()) {
+                    std::collections::hash_map::Entry::Occupied(db) => db.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(vacant) => {
+                        let partition_backend = PartitionedStorageBackend::with_segments(
+                            storage.acquire(&entry.cf_name)?,
+                            segments,
+                            None, // No expansion callback during recovery
+                            storage.file_growth_lock(),
+                        )
+                        .with_label(&entry.cf_name);
+                        // Create Database with proper initialization (handles repair, allocator
+                        // state, etc.). Wrap in ManuallyDrop to prevent Database::drop cleanup
+                        // from running
+                        vacant.insert(ManuallyDrop::new(
+                            cf_state.create_database(partition_backend)?,
+                        ))
+                    }
+                };
+
+                // An entry whose roots lie outside the column family's segments cannot have
+                // been written by it. It and every later entry for the column family, which
+                // build on it, are skipped.
+                let mem = db.get_memory();
+                if [entry.user_root, entry.system_root]
+                    .into_iter()
+                    .flatten()
+                    .any(|(page_num, _, _)| !mem.page_within(page_num, capacity))
+                {
+                    let sequence = entry.sequence;
+                    let cf_name = &entry.cf_name;
+                    if options.strict {
+                        return Err(DatabaseError::Storage(StorageError::from(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "WAL entry {sequence} for column family '{cf_name}' has a root outside its segments"
+                            ),
+                        ))));
+                    }
 
-            // Get storage backend directly from ColumnFamilyState
-            let backend = handle_pool.acquire(cf_name)?;
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Skipping WAL entries for column family '{cf_name}' from entry {sequence}: root outside its segments"
+                    );
+                    return Ok(Replay::Stop);
+                }
 
-            // Create PartitionedStorageBackend
-            let segments = cf_state.segments.read().unwrap().clone();
-            let file_growth_lock = handle_pool.file_growth_lock();
+                Ok(Replay::Apply)
+            })?;
 
-            let partition_backend = PartitionedStorageBackend::with_segments(
-                backend,
-                segments,
-                None, // No expansion callback during recovery
-                file_growth_lock,
-            );
+            if progress.entries - reported.entries >= PROGRESS_INTERVAL_ENTRIES
+                || progress.bytes - reported.bytes >= PROGRESS_INTERVAL_BYTES
+            {
+                progress.elapsed = start.elapsed();
+                report(progress);
+                reported = progress;
+            }
+            Ok::<_, DatabaseError>(())
+        })?;
 
-            // Create Database with proper initialization (handles repair, allocator state, etc.)
-            // Wrap in ManuallyDrop to prevent Database::drop cleanup from running
-            let db = ManuallyDrop::new(Database::builder().create_with_backend(partition_backend)?);
+        // The WAL is truncated past every entry, including those that are discarded: the
+        // members of write groups whose commit entry was never written, and the entries
+        // already checkpointed on their own
+        let (latest, latest_seq) = plan.finish();
+        let Some(latest_seq) = latest_seq else {
+            return Ok(());
+        };
 
-            recovery_dbs.insert(cf_name.clone(), db);
-        }
+        progress.elapsed = start.elapsed();
+        report(progress);
 
-        // Apply WAL entries to each Database
-        for (cf_name, entries_for_cf) in &cf_entries {
+        // Apply the latest entry for each column family and commit the recovered state at
+        // the TransactionalMemory level. This promotes secondary → primary and fsyncs
+        for (cf_name, entry) in &latest {
             let db = recovery_dbs.get(cf_name).ok_or_else(|| {
                 DatabaseError::Storage(StorageError::from(io::Error::new(
                     io::ErrorKind::NotFound,
@@ -388,60 +666,26 @@ impl ColumnFamilyDatabase {
 
             let mem = db.get_memory();
 
-            for entry in entries_for_cf {
-                // Convert WAL payload to BtreeHeader format
-                let data_root =
-                    entry
-                        .payload
-                        .user_root
-                        .map(|(page_num, checksum, length)| BtreeHeader {
-                            root: page_num,
-                            checksum,
-                            length,
-                        });
-
-                let system_root = entry
-                    .payload
-                    .system_root
-                    .map(|(page_num, checksum, length)| BtreeHeader {
-                        root: page_num,
-                        checksum,
-                        length,
-                    });
-
-                // Apply WAL transaction (updates secondary slot)
-                mem.apply_wal_transaction(
-                    data_root,
-                    system_root,
-                    TransactionId::new(entry.transaction_id),
-                )?;
-            }
-        }
-
-        // Commit all recovered state at TransactionalMemory level
-        // This promotes secondary → primary and fsyncs
-        for (cf_name, db) in &recovery_dbs {
-            // Get the last WAL entry for this CF to use its transaction ID
-            let last_entry = cf_entries
-                .get(cf_name)
-                .and_then(|entries| entries.last())
-                .ok_or_else(|| {
-                    DatabaseError::Storage(StorageError::from(io::Error::new(
-                        io::ErrorKind::NotFound,
-                        format!("No entries for CF '{cf_name}'"),
-                    )))
-                })?;
+            // Convert WAL payload to BtreeHeader format
+            let to_header = |(root, checksum, length)| BtreeHeader {
+                root,
+                checksum,
+                length,
+            };
+            let txn_id = TransactionId::new(entry.transaction_id);
 
-            let mem = db.get_memory();
-            let data_root = mem.get_data_root();
-            let system_root = mem.get_system_root();
-            let txn_id = TransactionId::new(last_entry.transaction_id);
+            // Apply WAL transaction (updates secondary slot)
+            mem.apply_wal_transaction(
+                entry.user_root.map(to_header),
+                entry.system_root.map(to_header),
+                txn_id,
+            )?;
 
             // Directly commit: swap secondary to primary and fsync
             // Use two_phase=false and shrink_policy=Never for simplicity
             mem.commit(
-                data_root,
-                system_root,
+                mem.get_data_root(),
+                mem.get_system_root(),
                 txn_id,
                 false,
                 crate::tree_store::ShrinkPolicy::Never,
@@ -460,128 +704,320 @@ impl ColumnFamilyDatabase {
         }
 
         // Truncate WAL after successful recovery
-        let latest_seq = entries.last().unwrap().sequence;
         journal
             .truncate(latest_seq + 1)
             .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
 
         #[cfg(feature = "logging")]
-        log::info!("WAL recovery completed successfully");
+        log::info!(
+            "WAL recovery completed: {} entries in {:?}",
+            progress.entries,
+            progress.elapsed
+        );
 
         // All ManuallyDrop<Database> instances drop here
         // ManuallyDrop prevents Database::drop from running, so NO cleanup, NO corruption
         Ok(())
     }
 
+    /// Opens the database file and acquires its exclusive lock.
+    ///
+    /// If the lock is held by another handle, retries until `open_timeout` elapses (or fails
+    /// immediately when no timeout is set) and then returns [`DatabaseError::FileLocked`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn lock_database_file(
+        path: &Path,
+        open_timeout: Option<Duration>,
+    ) -> Result<FileBackend, DatabaseError> {
+        const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+        let deadline = open_timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)
+                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+
+            match FileBackend::new(file) {
+                Err(DatabaseError::DatabaseAlreadyOpen) => {
+                    let now = Instant::now();
+                    match deadline {
+                        Some(deadline) if now < deadline => {
+                            std::thread::sleep(RETRY_INTERVAL.min(deadline - now));
+                        }
+                        _ => {
+                            return Err(DatabaseError::FileLocked {
+                                path: path.to_path_buf(),
+                                holder_hint: lock_holder_hint(path),
+                            });
+                        }
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Internal implementation of open, called by the builder (native platforms).
     #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn open_with_builder(
         path: PathBuf,
         pool_size: usize,
+        pool_size_auto: bool,
+        open_timeout: Option<Duration>,
+        wal_config: WALConfig,
+        cf_defaults: ColumnFamilyDefaults,
+        recovery: RecoveryOptions,
+        keyring: Keyring,
+        metrics: Option<Arc<dyn DatabaseMetrics>>,
     ) -> Result<Self, DatabaseError> {
-        let file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(&path)
-            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+        let header_backend: Arc<dyn StorageBackend> =
+            Arc::new(Self::lock_database_file(&path, open_timeout)?);
+        let pool = FileHandlePool::new(path.clone(), pool_size);
+        let pool = if pool_size_auto {
+            pool.with_auto_size()
+        } else {
+            pool
+        };
+        let storage = BackendSource::Pool(Arc::new(pool));
+
+        let wal_journal = if pool_size > 0 {
+            let wal_path = path.with_extension("wal");
+            let journal = WALJournal::open(&wal_path, wal_config.segment_size)
+                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+            Some(journal)
+        } else {
+            None
+        };
+
+        Self::open_with_storage(
+            Some(path),
+            header_backend,
+            storage,
+            wal_journal,
+            wal_config,
+            cf_defaults,
+            recovery,
+            keyring,
+            metrics,
+        )
+    }
+
+    /// Internal implementation of `open_in_memory`, called by the builder (native platforms).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn open_in_memory_with_builder(
+        wal_enabled: bool,
+        wal_config: WALConfig,
+        cf_defaults: ColumnFamilyDefaults,
+        keyring: Keyring,
+        metrics: Option<Arc<dyn DatabaseMetrics>>,
+    ) -> Result<Self, DatabaseError> {
+        let backend: Arc<dyn StorageBackend> = Arc::new(InMemoryBackend::new());
+
+        let wal_journal = if wal_enabled {
+            let journal = WALJournal::new(Arc::new(InMemoryBackend::new()))
+                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+            Some(journal)
+        } else {
+            None
+        };
 
-        let header_backend = Arc::new(FileBackend::new(file)?);
+        // A new in-memory WAL has nothing to recover
+        Self::open_with_storage(
+            None,
+            Arc::clone(&backend),
+            BackendSource::shared(backend),
+            wal_journal,
+            wal_config,
+            cf_defaults,
+            RecoveryOptions::default(),
+            keyring,
+            metrics,
+        )
+    }
 
+    /// Reads or initializes the master header, replays the WAL and starts the checkpoint
+    /// manager (native platforms).
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
+    fn open_with_storage(
+        path: Option<PathBuf>,
+        header_backend: Arc<dyn StorageBackend>,
+        storage: BackendSource,
+        wal_journal: Option<WALJournal>,
+        wal_config: WALConfig,
+        cf_defaults: ColumnFamilyDefaults,
+        recovery: RecoveryOptions,
+        keyring: Keyring,
+        metrics: Option<Arc<dyn DatabaseMetrics>>,
+    ) -> Result<Self, DatabaseError> {
         let is_new = header_backend
             .len()
             .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?
             == 0;
 
         let header = if is_new {
-            let header = MasterHeader::new();
-            let header_bytes = header
-                .to_bytes()
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
+            let mut header = MasterHeader::new();
             header_backend
-                .write(0, &header_bytes)
+                .set_len(HEADER_SIZE)
                 .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-            header_backend
-                .sync_data()
+            header
+                .write_to(header_backend.as_ref())
                 .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
 
             header
         } else {
-            let mut header_bytes = vec![0u8; PAGE_SIZE];
-            header_backend
-                .read(0, &mut header_bytes)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
-            MasterHeader::from_bytes(&header_bytes)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?
+            Self::read_header(header_backend.as_ref())?
         };
 
-        let handle_pool = Arc::new(FileHandlePool::new(path.clone(), pool_size));
+        // Keys are checked before anything is read from an encrypted column family, so that
+        // a missing or wrong key fails here rather than on first access
+        let keyring = Arc::new(keyring);
+        Self::check_encryption(&header, &keyring)?;
+        storage.set_column_families(header.column_families.len());
         let header = Arc::new(RwLock::new(header));
 
         let mut column_families = HashMap::new();
         for cf_meta in &header.read().unwrap().column_families {
-            let state = ColumnFamilyState::new(cf_meta.name.clone(), cf_meta.segments.clone());
+            let state = ColumnFamilyState::new(
+                cf_meta.name.clone(),
+                cf_meta.generation,
+                cf_meta.segments.clone(),
+            )
+            .with_keys(cf_meta.encrypted.then(|| Arc::clone(&keyring)))
+            .with_cache_size(cf_defaults.cache_size_for(&cf_meta.name));
+            #[cfg(feature = "encryption")]
+            if cf_meta.encrypted {
+                state.verify_key(
+                    PartitionedStorageBackend::with_segments(
+                        storage.acquire(&cf_meta.name)?,
+                        cf_meta.segments.clone(),
+                        None,
+                        storage.file_growth_lock(),
+                    )
+                    .with_label(&cf_meta.name),
+                )?;
+            }
             column_families.insert(cf_meta.name.clone(), Arc::new(state));
         }
-
-        // Initialize WAL journal and perform recovery if needed
-        let wal_journal = if pool_size > 0 {
-            let wal_path = path.with_extension("wal");
-            let journal = WALJournal::open(&wal_path)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
+        let column_families = Arc::new(RwLock::new(column_families));
+
+        // Perform WAL recovery if needed
+        let wal_journal = if let Some(journal) = wal_journal {
+            // Entries for encrypted column families are sealed with their keys
+            #[cfg(feature = "encryption")]
+            let journal = if keyring.is_empty() {
+                journal
+            } else {
+                journal.with_cipher(Arc::clone(&keyring) as Arc<dyn PayloadCipher>)
+            };
             // Perform WAL recovery without creating Database instances
             // This operates entirely at the TransactionalMemory layer to avoid Drop cleanup issues
-            let entries = journal
-                .read_from(0)
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-
-            if !entries.is_empty() {
-                Self::perform_wal_recovery(&column_families, &handle_pool, &journal)?;
-            }
+            Self::perform_wal_recovery(
+                &column_families.read().unwrap(),
+                &storage,
+                &journal,
+                recovery,
+            )?;
 
             Some(Arc::new(journal))
         } else {
             None
         };
 
+        let mut db = Self {
+            path,
+            storage,
+            header_backend,
+            column_families,
+            header,
+            wal_journal: None,
+            checkpoint_manager: None,
+            cf_defaults,
+            keyring,
+            metrics,
+            closed: false,
+        };
+
         // Start checkpoint manager if WAL is enabled
-        let checkpoint_manager = if let Some(ref journal_arc) = wal_journal {
-            let config = CheckpointConfig {
-                interval: std::time::Duration::from_secs(60),
-                max_wal_size: 64 * 1024 * 1024,
-            };
+        if let Some(journal) = wal_journal {
+            db.start_checkpoint_manager(journal, CheckpointConfig::from(wal_config));
+        }
 
-            // Create database Arc for checkpoint manager (temporary, will be replaced by self)
-            let db_arc = Arc::new(Self {
-                path: path.clone(),
-                header_backend: Arc::clone(&header_backend),
-                handle_pool: Arc::clone(&handle_pool),
-                column_families: Arc::new(RwLock::new(column_families.clone())),
-                header: Arc::clone(&header),
-                wal_journal: Some(Arc::clone(journal_arc)),
-                checkpoint_manager: None, // Will be set after creation
-            });
+        Ok(db)
+    }
 
-            let manager = CheckpointManager::start(Arc::clone(journal_arc), db_arc, config);
+    /// Reads the master header of an existing database.
+    ///
+    /// A header from before format version 7 is upgraded right away, while no column family
+    /// is open, because the upgrade moves data out of the page that becomes the second header
+    /// slot.
+    fn read_header(backend: &dyn StorageBackend) -> Result<MasterHeader, DatabaseError> {
+        let mut header = MasterHeader::read_from(backend)
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+        if header.header_generation == 0 {
+            header
+                .write_to(backend)
+                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+        }
+        Ok(header)
+    }
 
-            Some(Arc::new(manager))
-        } else {
-            None
-        };
+    /// Checks that every encrypted column family in `header` has a key in `keyring`, and
+    /// that no unencrypted column family has a key set with `cf_key()`.
+    ///
+    /// Encrypted column families can only be opened with the `encryption` feature.
+    fn check_encryption(header: &MasterHeader, keyring: &Keyring) -> Result<(), DatabaseError> {
+        for cf in &header.column_families {
+            let message = match (cf.encrypted, keyring.has_key(&cf.name)) {
+                (true, _) if !cfg!(feature = "encryption") => format!(
+                    "column family '{}' is encrypted, which requires the 'encryption' feature",
+                    cf.name
+                ),
+                (true, false) => format!(
+                    "column family '{}' is encrypted, but no key was set for it",
+                    cf.name
+                ),
+                (false, true) if keyring.has_cf_key(&cf.name) => format!(
+                    "a key was set for column family '{}', but it is not encrypted",
+                    cf.name
+                ),
+                _ => continue,
+            };
+            return Err(StorageError::from(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                message,
+            ))
+            .into());
+        }
+        Ok(())
+    }
 
-        Ok(Self {
-            path,
-            header_backend,
-            handle_pool,
-            column_families: Arc::new(RwLock::new(column_families)),
-            header,
-            wal_journal,
-            checkpoint_manager,
-        })
+    /// Enables the WAL with `journal` and starts a checkpoint manager for it.
+    fn start_checkpoint_manager(&mut self, journal: Arc<WALJournal>, config: CheckpointConfig) {
+        let view = Arc::new(self.checkpoint_view());
+        let manager = CheckpointManager::start(Arc::clone(&journal), view, config);
+        self.wal_journal = Some(journal);
+        self.checkpoint_manager = Some(Arc::new(manager));
+    }
+
+    /// Returns the view of this database that a checkpoint manager works on.
+    pub(crate) fn checkpoint_view(&self) -> CheckpointView {
+        CheckpointView {
+            #[cfg(not(target_arch = "wasm32"))]
+            storage: self.storage.clone(),
+            header_backend: Arc::clone(&self.header_backend),
+            #[cfg(target_arch = "wasm32")]
+            file_growth_lock: Arc::clone(&self.file_growth_lock),
+            column_families: Arc::clone(&self.column_families),
+            header: Arc::clone(&self.header),
+            metrics: self.metrics.clone(),
+        }
     }
 
     /// Creates a new column family with the specified name and optional size.
@@ -589,22 +1025,43 @@ impl ColumnFamilyDatabase {
     /// The column family is created cheaply with no file descriptor allocated.
     /// The Database instance and file handle are lazily initialized on first write.
     ///
+    /// The size only sets the first segment; column families grow past it through segment
+    /// expansion, so small sizes are fine for column families that start small. The segment
+    /// is placed in space freed by deleted column families when it fits, and at the end of the
+    /// file otherwise.
+    ///
     /// # Arguments
     ///
     /// * `name` - Name of the column family
-    /// * `size` - Initial size in bytes. If None, defaults to 1GB.
+    /// * `size` - Initial size in bytes, at least 16 KB. If None, defaults to the size set
+    ///   with [`ColumnFamilyDatabaseBuilder::default_cf_size`], or 1GB (4MB for an in-memory
+    ///   database).
     ///
     /// # Errors
     ///
-    /// Returns an error if a column family with this name already exists or
-    /// the header cannot be updated.
+    /// Returns an error if a column family with this name already exists, the size is
+    /// below the minimum, or the header cannot be updated.
     pub fn create_column_family(
         &self,
         name: impl Into<String>,
         size: Option<u64>,
     ) -> Result<ColumnFamily, ColumnFamilyError> {
         let name = name.into();
-        let size = size.unwrap_or(DEFAULT_COLUMN_FAMILY_SIZE);
+        let size = size
+            .or(self.cf_defaults.size)
+            .unwrap_or(if self.is_in_memory() {
+                DEFAULT_IN_MEMORY_COLUMN_FAMILY_SIZE
+            } else {
+                DEFAULT_COLUMN_FAMILY_SIZE
+            });
+        if size < MIN_COLUMN_FAMILY_SIZE {
+            return Err(ColumnFamilyError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "column family size {size} is below the minimum of {MIN_COLUMN_FAMILY_SIZE} bytes"
+                ),
+            )));
+        }
 
         let mut cfs = self.column_families.write().unwrap();
 
@@ -612,16 +1069,21 @@ impl ColumnFamilyDatabase {
             return Err(ColumnFamilyError::AlreadyExists(name));
         }
 
-        let (segments, cf_name) = {
+        // The header lock is held across the whole read-modify-write, so concurrent creations
+        // and segment expansions each see the space allocated by the one before. The change is
+        // made on a copy and only published once it is durable, so a failed write leaves both
+        // the in-memory and the on-disk header as they were.
+        let (segments, cf_name, generation) = {
             let mut header = self.header.write().unwrap();
-            let offset = header.end_of_file();
-            let metadata = ColumnFamilyMetadata::new(name.clone(), offset, size);
+            let mut updated = header.clone();
+            let segment = updated.allocate_segment(size);
+            let generation = updated.allocate_generation();
+            let metadata = ColumnFamilyMetadata::with_segments(name.clone(), vec![segment.clone()])
+                .with_generation(generation)
+                .with_created_at(now_ms())
+                .with_encrypted(self.keyring.has_key(&name));
 
-            header.column_families.push(metadata.clone());
-
-            let header_bytes = header.to_bytes()?;
-            self.header_backend.write(0, &header_bytes)?;
-            self.header_backend.sync_data()?;
+            updated.column_families.push(metadata.clone());
 
             // PRE-ALLOCATE FILE SPACE for this partition
             // CRITICAL: This eliminates filesystem metadata update contention
@@ -629,10 +1091,18 @@ impl ColumnFamilyDatabase {
             // 1. File extension syscalls during Database writes
             // 2. Kernel-level serialization on file size changes
             // 3. Filesystem journal updates
-            let new_file_size = offset + size;
+            // In memory there is no file to extend, and reserving the space would hold the
+            // whole partition in memory, so the buffer is left to grow as data is written.
+            // The same applies when pre-allocation is disabled, for filesystems and backup
+            // tools that handle large sparse files poorly. The file is extended before the
+            // header is written, so a failure here leaves no trace beyond unused space.
+            let new_file_size = segment.end();
             let current_file_size = self.header_backend.len().map_err(ColumnFamilyError::Io)?;
 
-            if new_file_size > current_file_size {
+            if new_file_size > current_file_size
+                && self.cf_defaults.preallocate
+                && !self.is_in_memory()
+            {
                 // Extend file to reserve space for this partition
                 self.header_backend
                     .set_len(new_file_size)
@@ -642,23 +1112,40 @@ impl ColumnFamilyDatabase {
                 // This keeps create_column_family() fast
             }
 
-            (metadata.segments, metadata.name.clone())
+            updated.write_to(self.header_backend.as_ref())?;
+            *header = updated;
+
+            (metadata.segments, metadata.name.clone(), generation)
         };
 
-        let state = Arc::new(ColumnFamilyState::new(name.clone(), segments));
+        if let Some(metrics) = &self.metrics {
+            metrics.on_segment_allocated(&cf_name, size);
+        }
+
+        let keys = self
+            .keyring
+            .has_key(&name)
+            .then(|| Arc::clone(&self.keyring));
+        let state = Arc::new(
+            ColumnFamilyState::new(name.clone(), generation, segments)
+                .with_keys(keys)
+                .with_cache_size(self.cf_defaults.cache_size_for(&name)),
+        );
         cfs.insert(name.clone(), Arc::clone(&state));
+        #[cfg(not(target_arch = "wasm32"))]
+        self.storage.set_column_families(cfs.len());
 
         #[cfg(not(target_arch = "wasm32"))]
         {
             Ok(ColumnFamily {
                 name: cf_name,
                 state,
-                pool: self.handle_pool.clone(),
-                path: self.path.clone(),
+                pool: self.storage.clone(),
                 header: self.header.clone(),
                 header_backend: self.header_backend.clone(),
                 wal_journal: self.wal_journal.clone(),
                 checkpoint_manager: self.checkpoint_manager.clone(),
+                metrics: self.metrics.clone(),
             })
         }
         #[cfg(target_arch = "wasm32")]
@@ -672,6 +1159,7 @@ impl ColumnFamilyDatabase {
                 file_growth_lock: self.file_growth_lock.clone(),
                 wal_journal: self.wal_journal.clone(),
                 checkpoint_manager: self.checkpoint_manager.clone(),
+                metrics: self.metrics.clone(),
             })
         }
     }
@@ -693,12 +1181,12 @@ impl ColumnFamilyDatabase {
                     Ok(ColumnFamily {
                         name: name.to_string(),
                         state: state.clone(),
-                        pool: self.handle_pool.clone(),
-                        path: self.path.clone(),
+                        pool: self.storage.clone(),
                         header: self.header.clone(),
                         header_backend: self.header_backend.clone(),
                         wal_journal: self.wal_journal.clone(),
                         checkpoint_manager: self.checkpoint_manager.clone(),
+                        metrics: self.metrics.clone(),
                     })
                 }
                 #[cfg(target_arch = "wasm32")]
@@ -712,6 +1200,7 @@ impl ColumnFamilyDatabase {
                         file_growth_lock: self.file_growth_lock.clone(),
                         wal_journal: self.wal_journal.clone(),
                         checkpoint_manager: self.checkpoint_manager.clone(),
+                        metrics: self.metrics.clone(),
                     })
                 }
             }
@@ -723,7 +1212,8 @@ impl ColumnFamilyDatabase {
     ///
     /// This is a convenience method that combines `column_family()` and `create_column_family()`.
     /// If the column family exists, it returns a handle to it. Otherwise, it creates a new
-    /// column family with the default size (1GB) and returns a handle.
+    /// column family with the default size (1GB unless set with
+    /// [`ColumnFamilyDatabaseBuilder::default_cf_size`]) and returns a handle.
     ///
     /// This is the recommended way to access column families for most use cases.
     ///
@@ -743,40 +1233,52 @@ impl ColumnFamilyDatabase {
     ///
     /// Returns an error if the column family cannot be created (e.g., I/O error).
     pub fn column_family_or_create(&self, name: &str) -> Result<ColumnFamily, ColumnFamilyError> {
+        self.get_or_create_column_family(name, None)
+    }
+
+    /// Retrieves a handle to a column family, creating it with `size` bytes if it doesn't
+    /// exist.
+    ///
+    /// Like [`column_family_or_create`](Self::column_family_or_create), but with an explicit
+    /// initial size for a newly created column family. The size of an existing column family
+    /// is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Per-tenant column families start at 16MB and grow as needed
+    /// let tenant = db.column_family_or_create_with("tenant-42", 16 * 1024 * 1024)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `size` is below the minimum or the column family cannot be
+    /// created.
+    pub fn column_family_or_create_with(
+        &self,
+        name: &str,
+        size: u64,
+    ) -> Result<ColumnFamily, ColumnFamilyError> {
+        self.get_or_create_column_family(name, Some(size))
+    }
+
+    fn get_or_create_column_family(
+        &self,
+        name: &str,
+        size: Option<u64>,
+    ) -> Result<ColumnFamily, ColumnFamilyError> {
         // Try to get existing CF first (read lock only)
-        {
-            let cfs = self.column_families.read().unwrap();
-            if let Some(state) = cfs.get(name) {
-                #[cfg(not(target_arch = "wasm32"))]
-                {
-                    return Ok(ColumnFamily {
-                        name: name.to_string(),
-                        state: state.clone(),
-                        pool: self.handle_pool.clone(),
-                        path: self.path.clone(),
-                        header: self.header.clone(),
-                        header_backend: self.header_backend.clone(),
-                        wal_journal: self.wal_journal.clone(),
-                        checkpoint_manager: self.checkpoint_manager.clone(),
-                    });
-                }
-                #[cfg(target_arch = "wasm32")]
-                {
-                    return Ok(ColumnFamily {
-                        name: name.to_string(),
-                        state: state.clone(),
-                        backend: self.header_backend.clone(),
-                        header: self.header.clone(),
-                        header_backend: self.header_backend.clone(),
-                        wal_journal: self.wal_journal.clone(),
-                        checkpoint_manager: self.checkpoint_manager.clone(),
-                    });
-                }
-            }
+        match self.column_family(name) {
+            Err(ColumnFamilyError::NotFound(_)) => {}
+            result => return result,
         }
 
-        // Doesn't exist - create it with default size
-        self.create_column_family(name, None)
+        // Doesn't exist - create it. Another thread may create it first, in which case the
+        // existing column family is returned.
+        match self.create_column_family(name, size) {
+            Err(ColumnFamilyError::AlreadyExists(_)) => self.column_family(name),
+            result => result,
+        }
     }
 
     /// Returns a list of all column family names in the database.
@@ -789,65 +1291,90 @@ impl ColumnFamilyDatabase {
             .collect()
     }
 
-    /// Enable WAL with the given backend (WASM only).
+    /// Replaces the user metadata of a column family.
     ///
-    /// This must be called immediately after creation to initialize WAL support.
-    /// Returns the checkpoint manager for tracking.
-    #[cfg(target_arch = "wasm32")]
-    pub fn enable_wal(
-        &mut self,
-        wal_backend: Arc<dyn StorageBackend>,
-    ) -> Result<(), DatabaseError> {
-        use crate::column_family::wal::checkpoint::CheckpointManager;
-        use crate::column_family::wal::config::CheckpointConfig;
-        use crate::column_family::wal::journal::WALJournal;
-
-        // Create WAL journal with the provided backend
-        let journal = WALJournal::new(wal_backend)
-            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-        let journal_arc = Arc::new(journal);
-
-        // Start checkpoint manager
-        let config = CheckpointConfig {
-            interval: std::time::Duration::from_secs(15), // WASM default: 15s
-            max_wal_size: 32 * 1024 * 1024,               // WASM default: 32 MB
-        };
+    /// The bytes are stored in the master header and are returned by
+    /// [`ColumnFamily::metadata`]. An empty slice clears them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::NotFound`] if the column family does not exist. Returns an
+    /// [`io::ErrorKind::InvalidInput`] error, leaving the header unchanged, if `user_metadata`
+    /// is longer than [`MAX_USER_METADATA_SIZE`] bytes.
+    pub fn set_cf_metadata(
+        &self,
+        name: &str,
+        user_metadata: &[u8],
+    ) -> Result<(), ColumnFamilyError> {
+        if user_metadata.len() > MAX_USER_METADATA_SIZE {
+            return Err(ColumnFamilyError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "user metadata of {} bytes exceeds the maximum of {MAX_USER_METADATA_SIZE} bytes",
+                    user_metadata.len()
+                ),
+            )));
+        }
 
-        // We need Arc<Self> for checkpoint manager, but we have &mut self
-        // Store journal first, then create manager
-        self.wal_journal = Some(Arc::clone(&journal_arc));
+        let mut header = self.header.write().unwrap();
+        let mut updated = header.clone();
+        let cf_meta = updated
+            .column_families
+            .iter_mut()
+            .find(|cf| cf.name == name)
+            .ok_or_else(|| ColumnFamilyError::NotFound(name.to_string()))?;
+        cf_meta.user_metadata = user_metadata.to_vec();
 
-        // Create a temporary Arc to Self for checkpoint manager
-        // This is safe because checkpoint manager only needs read access
-        let db_arc = unsafe { Arc::from_raw(self as *const Self) };
+        updated.write_to(self.header_backend.as_ref())?;
+        *header = updated;
 
-        let checkpoint_mgr =
-            CheckpointManager::start(Arc::clone(&journal_arc), Arc::clone(&db_arc), config);
+        Ok(())
+    }
 
-        // Don't drop the Arc - it's just a reference
-        std::mem::forget(db_arc);
+    /// Enable WAL with the given backend (WASM only).
+    ///
+    /// This must be called immediately after creation to initialize WAL support, and
+    /// starts a checkpoint manager with the default configuration (15s interval, 32 MB).
+    #[cfg(target_arch = "wasm32")]
+    pub fn enable_wal(
+        &mut self,
+        wal_backend: Arc<dyn StorageBackend>,
+    ) -> Result<(), DatabaseError> {
+        use crate::column_family::wal::config::WALConfig;
 
-        self.checkpoint_manager = Some(Arc::new(checkpoint_mgr));
+        let journal = WALJournal::new(wal_backend)
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+        self.start_checkpoint_manager(
+            Arc::new(journal),
+            CheckpointConfig::from(WALConfig::default()),
+        );
 
         Ok(())
     }
 
     /// Manually triggers a checkpoint to flush WAL to main database.
     ///
-    /// This ensures all pending WAL entries are applied to the database and persisted.
-    /// If WAL is disabled (`pool_size` = 0), this is a no-op.
+    /// This ensures all pending WAL entries are applied to the database and persisted, and
+    /// returns what was applied. If WAL is disabled (`pool_size` = 0), this is a no-op that
+    /// returns an empty report.
     ///
     /// # Errors
     ///
     /// Returns an error if the checkpoint operation fails.
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn checkpoint(&self) -> Result<(), DatabaseError> {
-        if let Some(checkpoint_mgr) = self.checkpoint_manager.as_ref() {
-            checkpoint_mgr
-                .checkpoint_now()
-                .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
-        }
-        Ok(())
+    pub fn checkpoint(&self) -> Result<CheckpointReport, DatabaseError> {
+        let Some(checkpoint_mgr) = self.checkpoint_manager.as_ref() else {
+            return Ok(CheckpointReport::default());
+        };
+        checkpoint_mgr
+            .checkpoint_now()
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))
+    }
+
+    /// Returns the checkpoint manager, if the WAL is enabled.
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    pub(crate) fn checkpoint_manager(&self) -> Option<&Arc<CheckpointManager>> {
+        self.checkpoint_manager.as_ref()
     }
 
     /// Manually triggers a checkpoint to flush WAL to main database (WASM version).
@@ -859,173 +1386,951 @@ impl ColumnFamilyDatabase {
     ///
     /// Returns an error if the checkpoint operation fails.
     #[cfg(target_arch = "wasm32")]
-    pub fn checkpoint(&self) -> Result<(), DatabaseError> {
+    pub fn checkpoint(&self) -> Result<CheckpointReport, DatabaseError> {
         // WASM checkpoint manager placeholder - will be implemented when we have proper async support
-        Ok(())
+        Ok(CheckpointReport::default())
     }
 
-    /// Returns the path to the database file (native platforms).
+    /// Checkpoints the pending WAL entries of column family `name` only.
+    ///
+    /// The column family is made durable at its latest commit, without flushing any other
+    /// column family, which bounds the time recovery spends on it after a burst of writes.
+    /// Its entries are no longer applied by later checkpoints or by recovery. They are only
+    /// removed from the WAL file if no other column family has entries pending; otherwise
+    /// their space is reclaimed by the next checkpoint that empties the WAL.
+    ///
+    /// If WAL is disabled (`pool_size` = 0), this is a no-op that returns an empty report.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::NotFound`] if the column family does not exist, or a
+    /// storage error if the checkpoint fails. WAL files written by an older version that
+    /// still hold entries for other column families can only be checkpointed as a whole,
+    /// until they have been emptied once.
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn path(&self) -> &Path {
-        &self.path
+    pub fn checkpoint_cf(&self, name: &str) -> Result<CheckpointReport, ColumnFamilyError> {
+        if !self.column_families.read().unwrap().contains_key(name) {
+            return Err(ColumnFamilyError::NotFound(name.to_string()));
+        }
+        let Some(checkpoint_mgr) = self.checkpoint_manager.as_ref() else {
+            return Ok(CheckpointReport::default());
+        };
+        checkpoint_mgr
+            .checkpoint_cf_now(name)
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)).into())
     }
 
-    /// Returns the file name (WASM).
+    /// Checkpoints the pending WAL entries of column family `name` only (WASM version).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::NotFound`] if the column family does not exist.
     #[cfg(target_arch = "wasm32")]
-    pub fn file_name(&self) -> &str {
-        &self.file_name
+    pub fn checkpoint_cf(&self, name: &str) -> Result<CheckpointReport, ColumnFamilyError> {
+        if !self.column_families.read().unwrap().contains_key(name) {
+            return Err(ColumnFamilyError::NotFound(name.to_string()));
+        }
+        // WASM checkpoint manager placeholder, like `checkpoint`
+        Ok(CheckpointReport::default())
     }
 
-    /// Deletes a column family and adds its segments to the free list for reuse.
+    /// Makes every transaction committed so far durable, in every column family, without
+    /// checkpointing.
+    ///
+    /// The WAL is shared by all column families, so this does the same as
+    /// [`ColumnFamily::flush`] on any of them. Returns the highest durable WAL sequence
+    /// number, or 0 if WAL is disabled (`pool_size` = 0).
     ///
     /// # Errors
     ///
-    /// Returns an error if the column family does not exist or the header
-    /// cannot be updated.
-    pub fn delete_column_family(&self, name: &str) -> Result<(), ColumnFamilyError> {
-        let mut cfs = self.column_families.write().unwrap();
+    /// Returns an error if the WAL cannot be synced.
+    pub fn flush_all(&self) -> Result<u64, DatabaseError> {
+        flush_journal(self.wal_journal.as_deref())
+    }
 
-        if !cfs.contains_key(name) {
-            return Err(ColumnFamilyError::NotFound(name.to_string()));
+    /// Closes the database, making everything committed so far durable in the main file.
+    ///
+    /// Dropping the database does the same on a best-effort basis; this reports failures
+    /// instead. The checkpoint thread is stopped, a final checkpoint applies the whole WAL and
+    /// empties it, and the master header is written and synced. The locks on the file and
+    /// the WAL are then released, so the database can be reopened straight away, even from the
+    /// same process, without replaying the WAL.
+    ///
+    /// [`ColumnFamily`] handles that are still held fail with
+    /// [`StorageError::DatabaseClosed`] from then on. Transactions in progress should be
+    /// committed or dropped first: a commit logged after the final checkpoint is left in the
+    /// WAL and replayed on the next open.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the final checkpoint fails, or if the header cannot be written or
+    /// synced. The locks are released in either case.
+    pub fn close(mut self) -> Result<(), ColumnFamilyError> {
+        self.closed = true;
+        let states: Vec<_> = self
+            .column_families
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        for state in &states {
+            state.mark_closed();
         }
 
-        cfs.remove(name);
+        let result = self.persist_for_close(&states);
 
-        let mut header = self.header.write().unwrap();
+        for state in &states {
+            #[cfg(not(target_arch = "wasm32"))]
+            self.storage.release(&state.name);
+            state.evict_database();
+        }
+        let journal_unlocked = self
+            .wal_journal
+            .as_ref()
+            .map_or(Ok(()), |journal| journal.close());
+        let unlocked = self.header_backend.close();
+        result?;
+        journal_unlocked?;
+        unlocked.map_err(ColumnFamilyError::from)
+    }
 
-        let cf_idx = header
-            .column_families
-            .iter()
-            .position(|cf| cf.name == name)
-            .ok_or_else(|| ColumnFamilyError::NotFound(name.to_string()))?;
+    /// Checkpoints the WAL, commits the open column families durably and writes the header,
+    /// for [`close`](Self::close).
+    fn persist_for_close(
+        &self,
+        states: &[Arc<ColumnFamilyState>],
+    ) -> Result<(), ColumnFamilyError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(checkpoint_mgr) = &self.checkpoint_manager {
+            checkpoint_mgr.shutdown()?;
+            checkpoint_mgr.checkpoint_now()?;
+        }
 
-        let cf_meta = header.column_families.remove(cf_idx);
-        for segment in cf_meta.segments {
-            header
-                .free_segments
-                .push(FreeSegment::new(segment.offset, segment.size));
+        if let Some(journal) = &self.wal_journal {
+            // Commits that were never logged, such as those with `Durability::None`, are only
+            // made durable here
+            self.commit_non_durable(states)?;
+            journal.shutdown()?;
         }
 
-        let header_bytes = header.to_bytes()?;
-        self.header_backend.write(0, &header_bytes)?;
-        self.header_backend.sync_data()?;
+        // Writing the header syncs the file
+        self.header
+            .write()
+            .unwrap()
+            .write_to(self.header_backend.as_ref())?;
+        Ok(())
+    }
 
+    /// Durably commits the latest state of every open column family in `states` that has
+    /// commits newer than its last durable one.
+    ///
+    /// Checkpoints are held off meanwhile, so a manual or backpressure checkpoint started from
+    /// another handle does not apply WAL entries to a column family while it is committed.
+    fn commit_non_durable(&self, states: &[Arc<ColumnFamilyState>]) -> Result<(), DatabaseError> {
+        let _checkpoint_guard = self
+            .checkpoint_manager
+            .as_ref()
+            .map(|checkpoint_mgr| checkpoint_mgr.exclusive());
+        for state in states {
+            let Some(db) = state.db.read().unwrap().clone() else {
+                continue;
+            };
+            let mem = db.get_memory();
+            if let Some((data_root, system_root, txn_id)) = mem.get_non_durable_state()? {
+                mem.checkpoint_commit(data_root, system_root, txn_id)?;
+            }
+        }
         Ok(())
     }
 
-    /// Internal segment allocation function used by expansion callbacks (native platforms).
+    /// Begins write transactions on all of `cfs` that commit atomically as a [`WriteGroup`].
+    ///
+    /// The write locks are taken in order of column family name, so groups over overlapping
+    /// sets of column families cannot deadlock each other. A group may therefore block until
+    /// write transactions already in progress on any of its column families have finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] storage error if the WAL is disabled, if a
+    /// column family is listed twice or does not belong to this database, or an error from
+    /// [`ColumnFamily::begin_write`].
     #[cfg(not(target_arch = "wasm32"))]
-    fn allocate_segment_internal(
-        cf_name: &str,
-        size: u64,
-        header: &Arc<RwLock<MasterHeader>>,
-        _header_backend: &Arc<FileBackend>,
-        state: &Arc<ColumnFamilyState>,
-    ) -> io::Result<Segment> {
-        // Allocate segment from free list or end of file - keep lock minimal
-        let allocated_segment = {
-            let mut hdr = header.write().unwrap();
+    pub fn write_group(&self, cfs: &[&ColumnFamily]) -> Result<WriteGroup, TransactionError> {
+        let invalid = |message: String| {
+            TransactionError::Storage(StorageError::from(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                message,
+            )))
+        };
 
-            let mut best_fit_idx = None;
-            let mut best_fit_size = u64::MAX;
+        let Some(journal) = &self.wal_journal else {
+            return Err(invalid(
+                "write groups require the WAL to be enabled".to_string(),
+            ));
+        };
 
-            for (idx, free_seg) in hdr.free_segments.iter().enumerate() {
-                if free_seg.size >= size && free_seg.size < best_fit_size {
-                    best_fit_idx = Some(idx);
-                    best_fit_size = free_seg.size;
-                }
+        let mut cfs = cfs.to_vec();
+        cfs.sort_by(|a, b| a.name.cmp(&b.name));
+        for pair in cfs.windows(2) {
+            if pair[0].name == pair[1].name {
+                return Err(invalid(format!(
+                    "column family '{}' is listed twice in the write group",
+                    pair[0].name
+                )));
+            }
+        }
+        if let Some(cf) = cfs.iter().find(|cf| {
+            !cf.wal_journal
+                .as_ref()
+                .is_some_and(|cf_journal| Arc::ptr_eq(cf_journal, journal))
+        }) {
+            return Err(invalid(format!(
+                "column family '{}' does not belong to this database",
+                cf.name
+            )));
+        }
+
+        let mut members = Vec::with_capacity(cfs.len());
+        for cf in cfs {
+            members.push((cf.name.clone(), cf.begin_write()?));
+        }
+
+        Ok(WriteGroup::new(
+            members,
+            Arc::clone(journal),
+            self.checkpoint_manager.clone(),
+            self.metrics.clone(),
+        ))
+    }
+
+    /// Returns WAL size, pending entry counts and checkpoint statistics.
+    ///
+    /// Returns `None` if the WAL is disabled (`pool_size` = 0).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WAL file size cannot be read.
+    pub fn wal_stats(&self) -> Result<Option<WALStats>, DatabaseError> {
+        let Some(checkpoint_mgr) = self.checkpoint_manager.as_ref() else {
+            return Ok(None);
+        };
+        checkpoint_mgr
+            .stats()
+            .map(Some)
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))
+    }
+
+    /// Returns file handle usage and acquisition statistics.
+    ///
+    /// Returns `None` for an in-memory database, which has no file handle pool.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn file_handle_stats(&self) -> Option<FileHandlePoolStats> {
+        self.storage.stats()
+    }
+
+    /// Writes a consistent, compacted copy of the whole database to a new file at `path`.
+    ///
+    /// Commits to every column family are paused while the copy is taken: this waits for
+    /// in-flight write transactions to finish, checkpoints the WAL, and then copies the master
+    /// header and each column family's live data. The backup therefore contains every
+    /// transaction committed before this call returns. Free segments left by deleted column
+    /// families and unused space at the end of each partition are not copied, and each column
+    /// family occupies a single contiguous segment in the backup.
+    ///
+    /// Each copied column family is compacted and closed cleanly before this returns, so the
+    /// backup opens with [`ColumnFamilyDatabase::open`] without needing repair.
+    ///
+    /// Must not be called while the calling thread holds a write transaction, since this
+    /// waits for the write lock of every column family.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` already exists, the WAL cannot be checkpointed, or the
+    /// backup cannot be written. A partially written backup may be left at `path`.
+    /// Databases with encrypted column families cannot be backed up yet, and return an
+    /// [`io::ErrorKind::Unsupported`] error.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn backup_to(&self, path: impl AsRef<Path>) -> Result<BackupSummary, DatabaseError> {
+        const COMPACTION_HEADROOM: u64 = 1024 * 1024;
+
+        if let Some(cf) = self
+            .header
+            .read()
+            .unwrap()
+            .column_families
+            .iter()
+            .find(|cf| cf.encrypted)
+        {
+            return Err(StorageError::from(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "column family '{}' is encrypted, and encrypted column families cannot be backed up",
+                    cf.name
+                ),
+            ))
+            .into());
+        }
+
+        let path = path.as_ref();
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| DatabaseError::Storage(StorageError::from(e)))?;
+        let backup: Arc<dyn StorageBackend> = Arc::new(FileBackend::new(file)?);
+
+        // Pause commits by holding a write transaction on every column family. This waits for
+        // in-flight transactions to finish and blocks new ones until the copy is complete.
+        // Write locks are taken in name order so that concurrent backups cannot deadlock.
+        let mut states: Vec<Arc<ColumnFamilyState>> = self
+            .column_families
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut paused = Vec::with_capacity(states.len());
+        for state in states {
+            // Skip column families deleted since the snapshot; their segments may be reused
+            let current = self
+                .column_families
+                .read()
+                .unwrap()
+                .get(&state.name)
+                .cloned();
+            if !current.is_some_and(|current| Arc::ptr_eq(&current, &state)) {
+                continue;
             }
 
-            let allocated_segment = if let Some(idx) = best_fit_idx {
-                let free_seg = hdr.free_segments.remove(idx);
+            let cf = ColumnFamily {
+                name: state.name.clone(),
+                state: Arc::clone(&state),
+                pool: self.storage.clone(),
+                header: self.header.clone(),
+                header_backend: self.header_backend.clone(),
+                wal_journal: self.wal_journal.clone(),
+                checkpoint_manager: self.checkpoint_manager.clone(),
+                metrics: self.metrics.clone(),
+            };
+            let db = cf.ensure_database()?;
+            let txn = db.begin_write().map_err(|e| e.into_storage_error())?;
+            paused.push((state, db, txn));
+        }
 
-                if free_seg.size == size {
-                    Segment::new(free_seg.offset, free_seg.size)
-                } else {
-                    let allocated = Segment::new(free_seg.offset, size);
-                    let remaining = FreeSegment::new(free_seg.offset + size, free_seg.size - size);
-                    hdr.free_segments.push(remaining);
-                    allocated
+        // With commits paused, a checkpoint empties the WAL and leaves every column family
+        // durable at its latest commit. Without a WAL, only commits made with
+        // `Durability::None` still need to be persisted.
+        if self.checkpoint_manager.is_some() {
+            self.checkpoint()?;
+        } else {
+            for (_, db, _) in &paused {
+                let mem = db.get_memory();
+                if let Some((data_root, system_root, txn_id)) = mem.get_non_durable_state()? {
+                    mem.checkpoint_commit(data_root, system_root, txn_id)?;
                 }
-            } else {
-                let offset = hdr.end_of_file();
-                let aligned_offset = offset.div_ceil(PAGE_SIZE as u64) * PAGE_SIZE as u64;
-                Segment::new(aligned_offset, size)
+            }
+        }
+
+        // Holding the header keeps column families from being created, deleted or grown
+        // while their segments are read
+        let header = self.header.read().unwrap();
+
+        // Give each column family a single partition as large as its source partition, with
+        // headroom for the pages compaction allocates. Column families deleted since the pause
+        // began are left out.
+        let mut backup_header = MasterHeader::new();
+        backup_header.next_generation = header.next_generation;
+        let mut copies = Vec::with_capacity(paused.len());
+        let data_start = backup_header.end_of_file();
+        let mut offset = data_start;
+        for cf_meta in &header.column_families {
+            let Some((state, db, _)) = paused.iter().find(|(state, ..)| {
+                state.name == cf_meta.name && state.generation == cf_meta.generation
+            }) else {
+                continue;
             };
 
-            if let Some(cf_meta) = hdr.column_families.iter_mut().find(|cf| cf.name == cf_name) {
-                cf_meta.segments.push(allocated_segment.clone());
+            let size = round_up_to_page(cf_meta.total_size()) + COMPACTION_HEADROOM;
+            backup_header.column_families.push(
+                ColumnFamilyMetadata::new(cf_meta.name.clone(), offset, size)
+                    .with_generation(cf_meta.generation)
+                    .with_created_at(cf_meta.created_at_ms)
+                    .with_user_metadata(cf_meta.user_metadata.clone()),
+            );
+            copies.push((state, db.get_memory().allocated_ranges(), offset));
+            offset += size;
+        }
+        backup.set_len(offset)?;
+
+        // Only allocated pages are copied; the rest of each partition is left as a hole
+        let mut buffer = Vec::new();
+        for (state, ranges, backup_offset) in &copies {
+            let source = PartitionedStorageBackend::with_segments(
+                self.storage.acquire(&state.name)?,
+                state.segments.read().unwrap().clone(),
+                None,
+                self.storage.file_growth_lock(),
+            )
+            .with_label(&state.name);
+            for range in ranges {
+                copy_storage_range(
+                    &source,
+                    range.start,
+                    backup.as_ref(),
+                    backup_offset + range.start,
+                    range.end - range.start,
+                    &mut buffer,
+                )?;
             }
+        }
 
-            allocated_segment
-        }; // Header lock released here - no disk I/O while holding lock
+        // Resume commits
+        drop(header);
+        for (_, _, txn) in paused {
+            txn.abort()?;
+        }
 
-        // Update state outside of header lock
-        let mut state_segments = state.segments.write().unwrap();
-        state_segments.push(allocated_segment.clone());
+        // Compact each column family within its partition, then move the partitions back
+        // to back so that the free space left behind is truncated from the file
+        let file_growth_lock = Arc::new(Mutex::new(()));
+        let mut compacted_end = data_start;
+        for cf in &mut backup_header.column_families {
+            let partition = PartitionedStorageBackend::with_segments(
+                Arc::clone(&backup),
+                cf.segments.clone(),
+                None,
+                Arc::clone(&file_growth_lock),
+            );
+            let mut db = Database::builder().create_with_backend(partition)?;
+            match db.compact() {
+                // Persistent savepoints pin their pages, so the column family is kept as is
+                Ok(_) | Err(CompactionError::PersistentSavepointExists) => {}
+                Err(CompactionError::Storage(err)) => return Err(err.into()),
+                Err(err) => return Err(StorageError::from(io::Error::other(err)).into()),
+            }
+            // Closing runs a final commit that may still allocate, so the compacted length is
+            // read back from the closed database's header
+            drop(db);
+            let partition = PartitionedStorageBackend::with_segments(
+                Arc::clone(&backup),
+                cf.segments.clone(),
+                None,
+                Arc::clone(&file_growth_lock),
+            );
+            let size = round_up_to_page(TransactionalMemory::stored_storage_len(&partition)?);
+
+            let old_offset = cf.segments[0].offset;
+            if old_offset != compacted_end {
+                copy_storage_range(
+                    backup.as_ref(),
+                    old_offset,
+                    backup.as_ref(),
+                    compacted_end,
+                    size,
+                    &mut buffer,
+                )?;
+            }
+            cf.segments = vec![Segment::new(compacted_end, size)];
+            compacted_end += size;
+        }
 
-        // Don't write/fsync header on every allocation - eliminates serialization bottleneck
-        // Header persisted on clean shutdown or periodically
-        // Trade-off: crash may lose segment allocations (wasted space, not data loss)
+        // A header extension, if needed, is allocated after the compacted column families
+        backup.set_len(compacted_end)?;
+        backup_header.write_to(backup.as_ref())?;
+        let bytes_written = backup_header.end_of_file();
+        backup.close()?;
+        drop(backup);
 
-        Ok(allocated_segment)
+        let column_families = backup_header
+            .column_families
+            .into_iter()
+            .map(|cf| cf.name)
+            .collect();
+
+        Ok(BackupSummary {
+            bytes_written,
+            column_families,
+        })
+    }
+
+    /// Writes the tables of the column family `name` to `writer` in a portable format.
+    ///
+    /// The export is taken from a single read transaction, so it is a consistent snapshot of
+    /// the column family and does not block concurrent writers. Keys and values are written as
+    /// their stored bytes, together with each table's name and key and value types, so the
+    /// stream can be loaded into any database with
+    /// [`import_column_family`](Self::import_column_family).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column family does not exist, contains a multimap table, or the
+    /// export cannot be written.
+    pub fn export_column_family(
+        &self,
+        name: &str,
+        mut writer: impl Write,
+    ) -> Result<(), ColumnFamilyError> {
+        let cf = self.column_family(name)?;
+        let txn = cf
+            .begin_read()
+            .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+        export::export_tables(&txn, &mut writer)
+    }
+
+    /// Creates the column family `name` and loads the tables of an export read from `reader`.
+    ///
+    /// `reader` must produce a stream written by
+    /// [`export_column_family`](Self::export_column_family). The tables are loaded in one write
+    /// transaction per table. If the import fails, the partially imported column family is
+    /// deleted again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::AlreadyExists`] if a column family named `name` exists; to
+    /// replace it, delete it first. Returns an [`io::ErrorKind::InvalidData`] error if the
+    /// stream is not a valid export, including one that fails its checksums or whose keys are
+    /// out of order, and an error if the data cannot be written.
+    pub fn import_column_family(
+        &self,
+        name: &str,
+        mut reader: impl Read,
+    ) -> Result<(), ColumnFamilyError> {
+        let cf = self.create_column_family(name, None)?;
+        export::import_tables(&cf, &mut reader).inspect_err(|_| {
+            drop(cf);
+            let _ = self.delete_column_family(name);
+        })
+    }
+
+    /// Creates the column family `cf_name` from the tables of a standalone redb database.
+    ///
+    /// The source is opened read-only and must have been shut down cleanly. Every table is
+    /// copied under its own name as raw key and value bytes, so it can be read back through
+    /// the column family with the [`TableDefinition`]s used to write the source. Tables are
+    /// committed in batches of [`RedbImportOptions::batch_entries`] entries; if the import
+    /// fails, the partially imported column family is deleted again, and a column family it
+    /// was to overwrite is left as it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::AlreadyExists`] if `cf_name` exists and
+    /// [`RedbImportOptions::overwrite`] is not set. Returns an error if the source cannot be
+    /// opened, contains a multimap table, or the data cannot be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn import_redb(
+        &self,
+        source: impl Into<RedbSource>,
+        cf_name: &str,
+        mut options: RedbImportOptions<'_>,
+    ) -> Result<RedbImportProgress, ColumnFamilyError> {
+        let source = source.into().open()?;
+        let source_txn = source
+            .begin_read()
+            .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+
+        if !options.overwrites() {
+            let cf = self.create_column_family(cf_name, None)?;
+            return redb_import::copy_tables(&source_txn, &cf, &mut options).inspect_err(|_| {
+                drop(cf);
+                let _ = self.delete_column_family(cf_name);
+            });
+        }
+
+        // The import is staged in a column family of its own, which only replaces `cf_name`
+        // once it is complete, so a failed import leaves the existing column family as it was.
+        // Keys are looked up by column family name, so an encrypted staging column family
+        // could not be read under the name it takes.
+        let staging = redb_import::staging_name(cf_name);
+        if self.keyring.has_key(cf_name) || self.keyring.has_key(&staging) {
+            return Err(ColumnFamilyError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot overwrite encrypted column family '{cf_name}', delete it first"),
+            )));
+        }
+        // Left behind by an import that was interrupted by a crash
+        match self.delete_column_family(&staging) {
+            Ok(()) | Err(ColumnFamilyError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+        let cf = self.create_column_family(&staging, None)?;
+        let result = redb_import::copy_tables(&source_txn, &cf, &mut options);
+        drop(cf);
+        result
+            .and_then(|progress| {
+                self.replace_column_family(cf_name, &staging)?;
+                Ok(progress)
+            })
+            .inspect_err(|_| {
+                let _ = self.delete_column_family(&staging);
+            })
     }
 
-    /// Internal segment allocation function used by expansion callbacks (WASM).
+    /// Replaces the column family `target`, if it exists, with the column family `staging`,
+    /// which takes its name.
+    ///
+    /// The header is updated with a single write, so after a crash the database holds either
+    /// the old `target` and `staging`, or only the new `target`. Existing handles to either
+    /// column family are left as after [`delete_column_family`](Self::delete_column_family).
+    #[cfg(not(target_arch = "wasm32"))]
+    fn replace_column_family(&self, target: &str, staging: &str) -> Result<(), ColumnFamilyError> {
+        // Drain the WAL, which refers to both column families by name. As in
+        // delete_column_family, this must happen without holding the column family lock.
+        self.checkpoint()?;
+
+        let mut cfs = self.column_families.write().unwrap();
+        let Some(staged) = cfs.get(staging).cloned() else {
+            return Err(ColumnFamilyError::NotFound(staging.to_string()));
+        };
+
+        let mut header = self.header.write().unwrap();
+        let mut updated = header.clone();
+        if let Some(cf_idx) = updated
+            .column_families
+            .iter()
+            .position(|cf| cf.name == target)
+        {
+            let cf_meta = updated.column_families.remove(cf_idx);
+            for segment in &cf_meta.segments {
+                updated.free_segment(segment);
+            }
+        }
+        let staged_meta = updated
+            .column_families
+            .iter_mut()
+            .find(|cf| cf.name == staging)
+            .ok_or_else(|| ColumnFamilyError::NotFound(staging.to_string()))?;
+        staged_meta.name = target.to_string();
+        let segments = staged_meta.segments.clone();
+        updated.write_to(self.header_backend.as_ref())?;
+        *header = updated;
+
+        // The freed segments cannot be allocated before the header lock is released, so the
+        // cached Database of the old column family is dropped before anything can reuse them
+        let old = cfs.remove(target);
+        cfs.remove(staging);
+        self.storage.release(target);
+        self.storage.release(staging);
+        if let Some(old) = old {
+            old.evict_database();
+        }
+        staged.evict_database();
+        let state = ColumnFamilyState::new(target.to_string(), staged.generation, segments)
+            .with_cache_size(self.cf_defaults.cache_size_for(target));
+        cfs.insert(target.to_string(), Arc::new(state));
+        self.storage.set_column_families(cfs.len());
+
+        // The replacement is durable at this point, so failing to give back the space at the
+        // end of the file only leaves it unused until the file grows into it again
+        let end_of_file = header.end_of_file();
+        if self.header_backend.len().is_ok_and(|len| len > end_of_file) {
+            let _ = self.header_backend.set_len(end_of_file);
+        }
+
+        Ok(())
+    }
+
+    /// Creates the column family `dst` as a copy of the column family `src`.
+    ///
+    /// The tables are copied from a single read transaction on `src`, so the copy is a
+    /// consistent snapshot of it, and writers on `src` are not blocked while it is made. Tables
+    /// are copied as their stored bytes under their own names and committed in batches of
+    /// about 100,000 entries; `progress` is called after each commit. `size` is the initial
+    /// size of `dst`, as for [`create_column_family`](Self::create_column_family). If the copy
+    /// fails, the partially copied column family is deleted again.
+    ///
+    /// Returns the number of entries copied into each table.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::NotFound`] if `src` does not exist, and
+    /// [`ColumnFamilyError::AlreadyExists`] if `dst` exists. Returns an error if `src` contains
+    /// a multimap table or the data cannot be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn copy_column_family(
+        &self,
+        src: &str,
+        dst: &str,
+        size: Option<u64>,
+        mut progress: Option<&mut dyn FnMut(CopyProgress)>,
+    ) -> Result<BTreeMap<String, u64>, ColumnFamilyError> {
+        let source = self.column_family(src)?;
+        let cf = self.create_column_family(dst, size)?;
+        let mut copy = || {
+            let snapshot = source
+                .begin_read()
+                .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+            let mut tables = BTreeMap::new();
+            for handle in snapshot.list_tables().map_err(export::storage_error)? {
+                let name = handle.name().to_string();
+                let table = snapshot
+                    .open_untyped_table(handle)
+                    .map_err(export::table_error)?;
+                tables.insert(name, table.len().map_err(export::storage_error)?);
+            }
+            redb_import::copy_raw_tables(
+                &snapshot,
+                &cf,
+                redb_import::DEFAULT_BATCH_ENTRIES,
+                |copied| {
+                    if let Some(callback) = &mut progress {
+                        callback(copied);
+                    }
+                },
+            )?;
+            Ok(tables)
+        };
+        copy().inspect_err(|_| {
+            drop(cf);
+            let _ = self.delete_column_family(dst);
+        })
+    }
+
+    /// Returns the path to the database file, or `None` for an in-memory database
+    /// (native platforms).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns true if this database is held in memory rather than backed by a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_in_memory(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Returns true if this database is held in memory rather than backed by a file.
     #[cfg(target_arch = "wasm32")]
+    fn is_in_memory(&self) -> bool {
+        false
+    }
+
+    /// Returns the file name (WASM).
+    #[cfg(target_arch = "wasm32")]
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Deletes a column family and adds its segments to the free list for reuse.
+    ///
+    /// Freed segments are merged with adjacent free space. If the column family was at the
+    /// end of the file, the file is truncated instead.
+    ///
+    /// If the WAL is enabled, pending WAL entries are checkpointed first so that the WAL no
+    /// longer references the column family by the time its segments become reusable. Any
+    /// entry for the deleted column family that is appended concurrently is skipped by
+    /// checkpointing and recovery, since the column family is no longer in the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column family does not exist, the WAL cannot be drained,
+    /// or the header cannot be updated.
+    pub fn delete_column_family(&self, name: &str) -> Result<(), ColumnFamilyError> {
+        if !self.column_families.read().unwrap().contains_key(name) {
+            return Err(ColumnFamilyError::NotFound(name.to_string()));
+        }
+
+        // Drain the WAL before freeing any space. This must happen without holding the
+        // column family lock, since checkpointing looks column families up by name.
+        self.checkpoint()?;
+
+        let mut cfs = self.column_families.write().unwrap();
+
+        let Some(state) = cfs.remove(name) else {
+            return Err(ColumnFamilyError::NotFound(name.to_string()));
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        self.storage.set_column_families(cfs.len());
+
+        // Drop the cached Database before its segments are freed, so nothing it does on
+        // close can land in space that another column family may be allocated from.
+        #[cfg(not(target_arch = "wasm32"))]
+        self.storage.release(name);
+        state.evict_database();
+
+        let mut header = self.header.write().unwrap();
+        let mut updated = header.clone();
+
+        let cf_idx = updated
+            .column_families
+            .iter()
+            .position(|cf| cf.name == name)
+            .ok_or_else(|| ColumnFamilyError::NotFound(name.to_string()))?;
+
+        let cf_meta = updated.column_families.remove(cf_idx);
+        for segment in &cf_meta.segments {
+            updated.free_segment(segment);
+        }
+
+        updated.write_to(self.header_backend.as_ref())?;
+        *header = updated;
+
+        // Free space at the end of the file is no longer tracked by the header, so the file
+        // is truncated to the last segment still in use. The header lock keeps segments from
+        // being allocated past that point in the meantime.
+        let end_of_file = header.end_of_file();
+        if self.header_backend.len()? > end_of_file {
+            self.header_backend.set_len(end_of_file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reports how fragmented the free space inside the database file is.
+    ///
+    /// Free space is left behind by deleted column families and is reused by later
+    /// allocations before the file grows. Free space at the end of the file is truncated
+    /// rather than tracked, so it is not counted.
+    pub fn fragmentation_stats(&self) -> FragmentationStats {
+        let header = self.header.read().unwrap();
+        FragmentationStats {
+            free_bytes: header.free_segments.iter().map(|free| free.size).sum(),
+            largest_free_block: header
+                .free_segments
+                .iter()
+                .map(|free| free.size)
+                .max()
+                .unwrap_or(0),
+            fragments: header.free_segments.len(),
+        }
+    }
+
+    /// Internal segment allocation function used by expansion callbacks.
     fn allocate_segment_internal(
         cf_name: &str,
         size: u64,
         header: &Arc<RwLock<MasterHeader>>,
-        _header_backend: &Arc<dyn StorageBackend>,
+        header_backend: &Arc<dyn StorageBackend>,
         state: &Arc<ColumnFamilyState>,
+        metrics: Option<&dyn DatabaseMetrics>,
     ) -> io::Result<Segment> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("segment_allocation", cf = cf_name, size).entered();
+
         // Allocate segment from free list or end of file - keep lock minimal
         let allocated_segment = {
             let mut hdr = header.write().unwrap();
 
-            let mut best_fit_idx = None;
-            let mut best_fit_size = u64::MAX;
-
-            for (idx, free_seg) in hdr.free_segments.iter().enumerate() {
-                if free_seg.size >= size && free_seg.size < best_fit_size {
-                    best_fit_idx = Some(idx);
-                    best_fit_size = free_seg.size;
-                }
-            }
-
-            let allocated_segment = if let Some(idx) = best_fit_idx {
-                let free_seg = hdr.free_segments.remove(idx);
-
-                if free_seg.size == size {
-                    Segment::new(free_seg.offset, free_seg.size)
-                } else {
-                    let allocated = Segment::new(free_seg.offset, size);
-                    let remaining = FreeSegment::new(free_seg.offset + size, free_seg.size - size);
-                    hdr.free_segments.push(remaining);
-                    allocated
-                }
-            } else {
-                let offset = hdr.end_of_file();
-                let aligned_offset = offset.div_ceil(PAGE_SIZE as u64) * PAGE_SIZE as u64;
-                Segment::new(aligned_offset, size)
-            };
+            let allocated_segment = hdr.allocate_segment(size);
 
             if let Some(cf_meta) = hdr.column_families.iter_mut().find(|cf| cf.name == cf_name) {
                 cf_meta.segments.push(allocated_segment.clone());
             }
 
+            // Persist the allocation before any data lands in the segment. Otherwise a crash
+            // would leave it on the free list (or unowned) in the on-disk header while WAL
+            // entries already reference pages inside it. Expansions are rare, so the fsync
+            // under the header lock is cheap overall.
+            hdr.write_to(header_backend.as_ref())?;
+
             allocated_segment
         }; // Header lock released here
 
-        // Update segments in state
-        let mut segments = state.segments.write().unwrap();
-        segments.push(allocated_segment.clone());
+        // Update state outside of header lock
+        let mut state_segments = state.segments.write().unwrap();
+        state_segments.push(allocated_segment.clone());
+
+        if let Some(metrics) = metrics {
+            metrics.on_segment_allocated(cf_name, allocated_segment.size);
+        }
 
         Ok(allocated_segment)
     }
 }
 
+/// Copies `len` bytes from one storage range to another in fixed-size chunks.
+///
+/// The ranges may overlap only if `dest_offset` is below `source_offset`.
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_storage_range(
+    source: &dyn StorageBackend,
+    source_offset: u64,
+    dest: &dyn StorageBackend,
+    dest_offset: u64,
+    len: u64,
+    buffer: &mut Vec<u8>,
+) -> Result<(), StorageError> {
+    const CHUNK_SIZE: u64 = 1024 * 1024;
+
+    let mut copied = 0;
+    while copied < len {
+        let chunk_len = (len - copied).min(CHUNK_SIZE);
+        buffer.resize(usize::try_from(chunk_len).unwrap(), 0);
+        source.read(source_offset + copied, buffer)?;
+        dest.write(dest_offset + copied, buffer)?;
+        copied += chunk_len;
+    }
+
+    Ok(())
+}
+
+/// Syncs everything committed to `journal` so far, returning the durable sequence number.
+fn flush_journal(journal: Option<&WALJournal>) -> Result<u64, DatabaseError> {
+    let Some(journal) = journal else {
+        return Ok(0);
+    };
+    journal
+        .flush()
+        .map_err(|e| DatabaseError::Storage(StorageError::from(e)))
+}
+
+/// Returns the current time in milliseconds since the Unix epoch, or 0 if the clock is set
+/// before it.
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+#[cfg(target_arch = "wasm32")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn now_ms() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+/// Describes the process holding the lock on `path`, if it can be determined.
+///
+/// On Linux the holder is looked up in `/proc/locks` by inode. Other platforms don't expose
+/// the lock owner, so no hint is given.
+#[cfg(target_os = "linux")]
+fn lock_holder_hint(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let inode = std::fs::metadata(path).ok()?.ino();
+    let locks = std::fs::read_to_string("/proc/locks").ok()?;
+
+    // Lines look like: "1: FLOCK  ADVISORY  WRITE 1234 fe:00:5678 0 EOF"
+    let pid = locks.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let pid = fields.get(4)?;
+        let file_id = fields.get(5)?;
+        let (_, lock_inode) = file_id.rsplit_once(':')?;
+        if lock_inode.parse::<u64>().ok()? == inode {
+            pid.parse::<u32>().ok()
+        } else {
+            None
+        }
+    })?;
+
+    if pid == std::process::id() {
+        Some(format!("this process (pid {pid})"))
+    } else {
+        Some(format!("pid {pid}"))
+    }
+}
+
+#[cfg(all(not(target_os = "linux"), not(target_arch = "wasm32")))]
+fn lock_holder_hint(_path: &Path) -> Option<String> {
+    None
+}
+
 /// A handle to a column family within a [`ColumnFamilyDatabase`].
 ///
 /// This is a lightweight structure that can be cheaply cloned and passed between threads.
@@ -1036,12 +2341,7 @@ pub struct ColumnFamily {
     name: String,
     state: Arc<ColumnFamilyState>,
     #[cfg(not(target_arch = "wasm32"))]
-    pool: Arc<FileHandlePool>,
-    #[cfg(not(target_arch = "wasm32"))]
-    path: PathBuf,
-    #[cfg(not(target_arch = "wasm32"))]
-    header_backend: Arc<FileBackend>,
-    #[cfg(target_arch = "wasm32")]
+    pool: BackendSource,
     header_backend: Arc<dyn StorageBackend>,
     #[cfg(target_arch = "wasm32")]
     backend: Arc<dyn StorageBackend>,
@@ -1050,6 +2350,7 @@ pub struct ColumnFamily {
     header: Arc<RwLock<MasterHeader>>,
     wal_journal: Option<Arc<WALJournal>>,
     checkpoint_manager: Option<Arc<CheckpointManager>>,
+    metrics: Option<Arc<dyn DatabaseMetrics>>,
 }
 
 impl ColumnFamily {
@@ -1058,6 +2359,28 @@ impl ColumnFamily {
         &self.name
     }
 
+    /// Returns the generation of this column family, assigned when it was created.
+    pub(crate) fn generation(&self) -> u64 {
+        self.state.generation
+    }
+
+    /// Returns the metadata of this column family from the master header, including its
+    /// creation time and user metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ColumnFamilyError::NotFound`] if the column family has been deleted.
+    pub fn metadata(&self) -> Result<ColumnFamilyMetadata, ColumnFamilyError> {
+        self.header
+            .read()
+            .unwrap()
+            .column_families
+            .iter()
+            .find(|cf| cf.name == self.name && cf.generation == self.state.generation)
+            .cloned()
+            .ok_or_else(|| ColumnFamilyError::NotFound(self.name.clone()))
+    }
+
     /// Begins a write transaction for this column family.
     ///
     /// On first call, this acquires a file handle from the pool and initializes
@@ -1066,8 +2389,10 @@ impl ColumnFamily {
     /// # Errors
     ///
     /// Returns an error if a write transaction is already in progress for this
-    /// column family or if the Database cannot be initialized.
+    /// column family or if the Database cannot be initialized, and
+    /// [`StorageError::DatabaseClosed`] if the database has been closed.
     pub fn begin_write(&self) -> Result<WriteTransaction, TransactionError> {
+        self.check_open()?;
         let db = self.ensure_database().map_err(|e| match e {
             DatabaseError::Storage(s) => TransactionError::Storage(s),
             _ => TransactionError::Storage(StorageError::from(io::Error::other(format!(
@@ -1082,25 +2407,264 @@ impl ColumnFamily {
         if let Some(wal_journal) = &self.wal_journal {
             txn.set_wal_context(
                 self.name.clone(),
+                self.state.generation,
                 Arc::clone(wal_journal),
                 self.checkpoint_manager.as_ref().map(Arc::clone),
             );
         }
 
+        if let Some(metrics) = &self.metrics {
+            txn.set_metrics(self.name.clone(), Arc::clone(metrics));
+        }
+
+        #[cfg(feature = "tracing")]
+        txn.set_span(tracing::debug_span!(
+            "write_transaction",
+            cf = self.name.as_str()
+        ));
+
+        Ok(txn)
+    }
+
+    /// Makes every transaction committed to this column family durable, without
+    /// checkpointing.
+    ///
+    /// Waits for commits that are logged but not yet visible, including write groups, to
+    /// finish, and then syncs the WAL, joining a group commit sync if one is in progress.
+    /// Afterwards every committed transaction is durable in either the WAL or the main file,
+    /// including those made with [`Durability::Eventual`](crate::Durability::Eventual). The
+    /// WAL is not applied to the B-tree, so this is much cheaper than
+    /// [`ColumnFamilyDatabase::checkpoint_cf`], and suits a barrier before taking a
+    /// filesystem snapshot of the database and WAL files.
+    ///
+    /// Returns the highest durable WAL sequence number, which covers the latest commit of
+    /// this column family. Returns 0 if WAL is disabled, in which case commits are written
+    /// directly to the main file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the WAL cannot be synced, or [`StorageError::DatabaseClosed`] if the
+    /// database has been closed.
+    pub fn flush(&self) -> Result<u64, DatabaseError> {
+        self.check_open()?;
+        flush_journal(self.wal_journal.as_deref())
+    }
+
+    /// Reports whether this column family would gain from compaction or a checkpoint.
+    ///
+    /// Counts the free pages left between the column family's allocated pages and reads its
+    /// pending WAL entries and last checkpoint from the checkpoint manager, so schedulers can
+    /// pick the column families that need maintenance and leave the others alone. Freed pages
+    /// are only counted once no transaction can still read them, which is usually after the
+    /// next commit. Opens the column family's database if it is not open yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column family's database cannot be opened, or
+    /// [`StorageError::DatabaseClosed`] if the database has been closed.
+    pub fn maintenance_hint(&self) -> Result<MaintenanceHint, DatabaseError> {
+        self.check_open()?;
+        let mem = self.ensure_database()?.get_memory();
+        let reclaimable_bytes = mem.count_reclaimable_pages()? * mem.get_page_size() as u64;
+
+        let (pending_wal_entries, last_checkpoint) = self
+            .checkpoint_manager
+            .as_ref()
+            .map_or((0, None), |mgr| mgr.cf_progress(&self.name));
+        #[cfg(not(target_arch = "wasm32"))]
+        let since_checkpoint =
+            last_checkpoint.and_then(|at| SystemTime::now().duration_since(at).ok());
+        #[cfg(target_arch = "wasm32")]
+        let since_checkpoint = last_checkpoint.map(|_| Duration::ZERO);
+
+        Ok(MaintenanceHint {
+            reclaimable_bytes,
+            pending_wal_entries,
+            since_checkpoint,
+        })
+    }
+
+    /// Returns the hits, misses and evictions of this column family's page cache.
+    ///
+    /// Each column family has its own cache, sized with
+    /// [`ColumnFamilyDatabaseBuilder::cf_cache_size`]. The counts start from zero whenever the
+    /// column family's database is opened, including after its handle has been released or
+    /// evicted from the pool. Opens the column family's database if it is not open yet.
+    ///
+    /// Hits, misses and evictions are only counted with the `cache_metrics` feature; without
+    /// it, every count is zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the column family's database cannot be opened, or
+    /// [`StorageError::DatabaseClosed`] if the database has been closed.
+    pub fn cache_stats(&self) -> Result<CacheStats, DatabaseError> {
+        self.check_open()?;
+        Ok(self.ensure_database()?.cache_stats())
+    }
+
+    /// Begins a write transaction for this column family that bypasses the WAL.
+    ///
+    /// The transaction commits directly with a full durable B-tree commit instead of
+    /// appending to the WAL and waiting for group commit. This is slower per commit but
+    /// avoids WAL overhead for bulk loads, and is crash-safe on its own.
+    ///
+    /// Logged and unlogged transactions may be freely interleaved on the same column
+    /// family. Because WAL entries carry whole B-tree roots, replaying a logged commit made
+    /// before an unlogged one would roll the column family back past it. To prevent that,
+    /// the pending WAL entries of this column family are checkpointed before this returns,
+    /// while its write lock is already held, as by
+    /// [`checkpoint_cf`](ColumnFamilyDatabase::checkpoint_cf). The WAL therefore only ever
+    /// contains logged commits of this column family newer than the last unlogged one.
+    ///
+    /// That checkpoint is not free. It reads the whole WAL while holding the commit gate
+    /// exclusively, so logged commits to every column family block until it finishes, and
+    /// if this column family has entries pending it also makes it durable with an fsync.
+    /// Other column families are not flushed. A WAL written by an older version that still
+    /// holds entries for other column families is checkpointed as a whole instead, which
+    /// flushes every column family.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`begin_write`](Self::begin_write), or a storage error if the checkpoint fails.
+    pub fn begin_write_unlogged(&self) -> Result<WriteTransaction, TransactionError> {
+        let mut txn = self.begin_write()?;
+        txn.disable_wal();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(checkpoint_mgr) = &self.checkpoint_manager {
+            checkpoint_mgr
+                .checkpoint_cf_now(&self.name)
+                .or_else(|e| {
+                    // Older WALs cannot hold checkpoint markers until they are emptied once
+                    if e.kind() == io::ErrorKind::Unsupported {
+                        checkpoint_mgr.checkpoint_now()
+                    } else {
+                        Err(e)
+                    }
+                })
+                .map_err(|e| TransactionError::Storage(StorageError::from(e)))?;
+        }
+
         Ok(txn)
     }
 
+    /// Creates a [`BulkLoader`] that loads rows into `table` in chunked, unlogged transactions.
+    ///
+    /// This is the fastest way to fill a table with a large number of rows, such as on an
+    /// initial import. See [`BulkLoader`] for the durability it provides during the load.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn bulk_loader<'a, K: Key + 'static, V: Value + 'static>(
+        &'a self,
+        table: TableDefinition<'a, K, V>,
+    ) -> BulkLoader<'a, K, V> {
+        BulkLoader::new(self, table)
+    }
+
+    /// Returns the checkpoint manager of the database, if the WAL is enabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn checkpoint_manager(&self) -> Option<&Arc<CheckpointManager>> {
+        self.checkpoint_manager.as_ref()
+    }
+
     /// Begins a read transaction for this column family.
     ///
     /// Multiple read transactions may be active concurrently.
     pub fn begin_read(&self) -> Result<ReadTransaction, TransactionError> {
-        let db = self.ensure_database().map_err(|e| match e {
+        self.database_for_read()?.begin_read()
+    }
+
+    /// Pins the most recently committed state of this column family.
+    ///
+    /// Read transactions opened from the returned [`Snapshot`] with
+    /// [`begin_read_at`](Self::begin_read_at) all see that state, even if writes are committed
+    /// in between. The state is retained until the snapshot is dropped, so snapshots should
+    /// not be held longer than needed.
+    pub fn snapshot(&self) -> Result<Snapshot, TransactionError> {
+        self.database_for_read()?.snapshot()
+    }
+
+    /// Begins a read transaction that sees the state pinned by `snapshot`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::SnapshotReclaimed`] if the snapshot was not taken from this
+    /// column family, or if the column family's database has been released since (for example
+    /// by [`release_handle`](Self::release_handle) or file handle eviction), which does not
+    /// retain earlier snapshots.
+    pub fn begin_read_at(&self, snapshot: &Snapshot) -> Result<ReadTransaction, TransactionError> {
+        self.database_for_read()?.begin_read_at(snapshot)
+    }
+
+    /// Lists the tables of this column family with their entry counts, sorted by name.
+    ///
+    /// This includes the internal tables that the domain crates create, such as the
+    /// downsampled tables of a time series or the reverse index of a graph.
+    pub fn list_tables(&self) -> Result<Vec<TableInfo>, Error> {
+        let txn = self.begin_read()?;
+        let mut tables = vec![];
+        for handle in txn.list_tables()? {
+            let entries = txn.open_untyped_table(handle.clone())?.len()?;
+            tables.push(TableInfo {
+                name: handle.name().to_string(),
+                entries,
+                multimap: false,
+            });
+        }
+        for handle in txn.list_multimap_tables()? {
+            let entries = txn.open_untyped_multimap_table(handle.clone())?.len()?;
+            tables.push(TableInfo {
+                name: handle.name().to_string(),
+                entries,
+                multimap: true,
+            });
+        }
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tables)
+    }
+
+    /// Deletes the table `name` from this column family in its own write transaction.
+    ///
+    /// Both normal and multimap tables can be deleted. Returns whether the table existed.
+    ///
+    /// # Errors
+    ///
+    /// Besides storage and commit errors, returns the same errors as
+    /// [`begin_write`](Self::begin_write), so this fails while another write transaction is
+    /// in progress on the same thread.
+    pub fn delete_table(&self, name: &str) -> Result<bool, Error> {
+        let txn = self.begin_write()?;
+        let existed = match txn.delete_table(UntypedTableHandle::new(name.to_string())) {
+            Ok(existed) => existed,
+            Err(TableError::TableIsMultimap(_)) => {
+                txn.delete_multimap_table(UntypedMultimapTableHandle::new(name.to_string()))?
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if existed {
+            txn.commit()?;
+        } else {
+            txn.abort()?;
+        }
+        Ok(existed)
+    }
+
+    fn database_for_read(&self) -> Result<Arc<Database>, TransactionError> {
+        self.check_open()?;
+        self.ensure_database().map_err(|e| match e {
             DatabaseError::Storage(s) => TransactionError::Storage(s),
             _ => TransactionError::Storage(StorageError::from(io::Error::other(format!(
                 "database initialization error: {e}"
             )))),
-        })?;
-        db.begin_read()
+        })
+    }
+
+    /// Returns [`StorageError::DatabaseClosed`] if the database has been closed.
+    fn check_open(&self) -> Result<(), StorageError> {
+        if self.state.is_closed() {
+            return Err(StorageError::DatabaseClosed);
+        }
+        Ok(())
     }
 
     /// Releases this column family's file handle back to the pool.
@@ -1131,6 +2695,7 @@ impl ColumnFamily {
         let header_backend = self.header_backend.clone();
 
         let state = self.state.clone();
+        let metrics = self.metrics.clone();
 
         let expansion_callback = Arc::new(move |requested_size: u64| -> io::Result<Segment> {
             ColumnFamilyDatabase::allocate_segment_internal(
@@ -1139,11 +2704,11 @@ impl ColumnFamily {
                 &header,
                 &header_backend,
                 &state,
+                metrics.as_deref(),
             )
         });
 
-        self.state
-            .ensure_database(&self.pool, &self.path, expansion_callback)
+        self.state.ensure_database(&self.pool, expansion_callback)
     }
 
     /// Ensures the Database instance exists, creating it if necessary (WASM).
@@ -1153,6 +2718,7 @@ impl ColumnFamily {
         let header = self.header.clone();
         let header_backend = self.header_backend.clone();
         let state = self.state.clone();
+        let metrics = self.metrics.clone();
 
         let expansion_callback = Arc::new(move |requested_size: u64| -> io::Result<Segment> {
             ColumnFamilyDatabase::allocate_segment_internal(
@@ -1161,6 +2727,7 @@ impl ColumnFamily {
                 &header,
                 &header_backend,
                 &state,
+                metrics.as_deref(),
             )
         });
 
@@ -1172,37 +2739,580 @@ impl ColumnFamily {
     }
 }
 
+/// The state a checkpoint manager shares with its column family database.
+///
+/// It shares the column family map and master header with the database, so creates and
+/// deletes are visible to checkpoints, but it has none of the database's `Drop` cleanup:
+/// the final checkpoint and closing the file are left to the database itself.
+pub(crate) struct CheckpointView {
+    #[cfg(not(target_arch = "wasm32"))]
+    storage: BackendSource,
+    header_backend: Arc<dyn StorageBackend>,
+    #[cfg(target_arch = "wasm32")]
+    file_growth_lock: Arc<std::sync::Mutex<()>>,
+    column_families: Arc<RwLock<HashMap<String, Arc<ColumnFamilyState>>>>,
+    header: Arc<RwLock<MasterHeader>>,
+    metrics: Option<Arc<dyn DatabaseMetrics>>,
+}
+
+impl CheckpointTarget for CheckpointView {
+    fn list_column_families(&self) -> Vec<String> {
+        let header = self.header.read().unwrap();
+        header
+            .column_families
+            .iter()
+            .map(|cf| cf.name.clone())
+            .collect()
+    }
+
+    fn column_family(&self, name: &str) -> Option<ColumnFamily> {
+        let state = self.column_families.read().unwrap().get(name).cloned()?;
+
+        Some(ColumnFamily {
+            name: name.to_string(),
+            state,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool: self.storage.clone(),
+            header_backend: self.header_backend.clone(),
+            #[cfg(target_arch = "wasm32")]
+            backend: self.header_backend.clone(),
+            #[cfg(target_arch = "wasm32")]
+            file_growth_lock: self.file_growth_lock.clone(),
+            header: self.header.clone(),
+            wal_journal: None,
+            checkpoint_manager: None,
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    fn metrics(&self) -> Option<&dyn DatabaseMetrics> {
+        self.metrics.as_deref()
+    }
+}
+
 impl Drop for ColumnFamilyDatabase {
     fn drop(&mut self) {
-        // Run final checkpoint to flush dirty data if WAL is enabled
+        if self.closed {
+            return;
+        }
+
+        // Stop the checkpoint thread before committing anything here. Stopping it waits for
+        // a checkpoint in progress, then runs a final one that applies the whole WAL. The
+        // manager may outlive the database through column family handles, so it is shut
+        // down through the shared reference rather than on its own drop.
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(checkpoint_mgr) = &self.checkpoint_manager {
+            let _ = checkpoint_mgr.shutdown();
+        }
+
+        // Persist what the final checkpoint did not cover, such as commits with
+        // `Durability::None`
         #[cfg(not(target_arch = "wasm32"))]
         if self.wal_journal.is_some() {
-            // Checkpoint all column families to persist dirty data
-            for cf_name in self.list_column_families() {
-                if let Ok(cf) = self.column_family(&cf_name)
-                    && let Ok(db) = cf.ensure_database()
-                {
-                    let mem = db.get_memory();
-                    if let Ok((data_root, system_root, txn_id)) = mem.get_current_secondary_state()
-                    {
-                        let _ = mem.checkpoint_commit(data_root, system_root, txn_id);
-                    }
+            let states: Vec<_> = self
+                .column_families
+                .read()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect();
+            let _ = self.commit_non_durable(&states);
+        }
+        self.checkpoint_manager = None;
+
+        // Close the header backend to release the file lock (or OPFS handle)
+        let _ = self.header_backend.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_family::wal::entry::entries_to_apply;
+    use crate::column_family::wal::entry::{WALEntry, WALTransactionPayload};
+    use crate::{Durability, TableDefinition};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recovery_skips_entries_for_unknown_column_family() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        {
+            let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+            db.create_column_family("live", None).unwrap();
+        }
+
+        // Leave an entry in the WAL for a column family that isn't in the header
+        {
+            let journal = WALJournal::open(db_path.with_extension("wal"), None).unwrap();
+            let payload = WALTransactionPayload {
+                user_root: None,
+                system_root: None,
+                freed_pages: vec![],
+                allocated_pages: vec![],
+                durability: Durability::Immediate,
+            };
+            let mut entry = WALEntry::new("ghost".to_string(), 1, 7, payload);
+            journal.append(&mut entry).unwrap();
+            journal.sync().unwrap();
+        }
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        assert_eq!(db.list_column_families(), vec!["live".to_string()]);
+        assert!(
+            db.wal_journal
+                .as_ref()
+                .unwrap()
+                .read_from(0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unlogged_write_drains_earlier_wal_entries() {
+        const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = ColumnFamilyDatabase::open(temp_dir.path().join("test.db")).unwrap();
+        let cf = db.create_column_family("bulk", None).unwrap();
+        let journal = db.wal_journal.as_ref().unwrap();
+
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(TABLE).unwrap().insert(&1, &1).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(journal.read_from(0).unwrap().len(), 1);
+
+        // The logged entry must be gone before the unlogged commit can supersede it
+        let txn = cf.begin_write_unlogged().unwrap();
+        assert!(journal.read_from(0).unwrap().is_empty());
+        txn.open_table(TABLE).unwrap().insert(&2, &2).unwrap();
+        txn.commit().unwrap();
+        assert!(journal.read_from(0).unwrap().is_empty());
+
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        assert_eq!(table.get(&1).unwrap().unwrap().value(), 1);
+        assert_eq!(table.get(&2).unwrap().unwrap().value(), 2);
+    }
+
+    #[test]
+    fn test_unlogged_write_leaves_other_wal_entries_pending() {
+        const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = ColumnFamilyDatabase::open(temp_dir.path().join("test.db")).unwrap();
+        let bulk = db.create_column_family("bulk", None).unwrap();
+        let other = db.create_column_family("other", None).unwrap();
+        let journal = db.wal_journal.as_ref().unwrap();
+
+        for cf in [&bulk, &other] {
+            let txn = cf.begin_write().unwrap();
+            txn.open_table(TABLE).unwrap().insert(&1, &1).unwrap();
+            txn.commit().unwrap();
+        }
+
+        // Only the entry of the column family being written is retired
+        let txn = bulk.begin_write_unlogged().unwrap();
+        let applied = entries_to_apply(journal.read_from(0).unwrap());
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].cf_name, "other");
+        txn.open_table(TABLE).unwrap().insert(&2, &2).unwrap();
+        txn.commit().unwrap();
+
+        for (cf, second) in [(&bulk, Some(2)), (&other, None)] {
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(TABLE).unwrap();
+            assert_eq!(table.get(&1).unwrap().unwrap().value(), 1);
+            assert_eq!(table.get(&2).unwrap().map(|v| v.value()), second);
+        }
+    }
+
+    #[test]
+    fn test_recovery_skips_entries_outside_segments() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let generation = {
+            let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+            let cf = db.create_column_family("small", Some(64 * 1024)).unwrap();
+            // Dropping the database leaves a column family that was never written to
+            // uninitialized, unlike one with entries in the WAL
+            cf.begin_write().unwrap().commit().unwrap();
+            cf.generation()
+        };
+
+        // A root far beyond the column family's segments
+        {
+            let journal = WALJournal::open(db_path.with_extension("wal"), None).unwrap();
+            let payload = WALTransactionPayload {
+                user_root: Some((crate::tree_store::PageNumber::new(0, 100_000, 0), 0, 5)),
+                system_root: None,
+                freed_pages: vec![],
+                allocated_pages: vec![],
+                durability: Durability::Immediate,
+            };
+            let mut entry = WALEntry::new("small".to_string(), generation, 5, payload);
+            journal.append(&mut entry).unwrap();
+            journal.sync().unwrap();
+        }
+
+        let result = ColumnFamilyDatabase::builder()
+            .strict_recovery(true)
+            .open(&db_path);
+        assert!(result.is_err());
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        let txn = db.column_family("small").unwrap().begin_read().unwrap();
+        assert_eq!(txn.list_tables().unwrap().count(), 0);
+        assert!(
+            db.wal_journal
+                .as_ref()
+                .unwrap()
+                .read_from(0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_manager_sees_column_families_created_after_open() {
+        const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        {
+            let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+            let cf = db.create_column_family("late", None).unwrap();
+            let txn = cf.begin_write().unwrap();
+            txn.open_table(TABLE).unwrap().insert(&1, &10).unwrap();
+            txn.commit().unwrap();
+
+            db.checkpoint().unwrap();
+            let stats = db.wal_stats().unwrap().unwrap();
+            assert_eq!(stats.checkpoints, 1);
+            assert_eq!(stats.pending_entries, 0);
+            assert!(
+                db.wal_journal
+                    .as_ref()
+                    .unwrap()
+                    .read_from(0)
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        let txn = db.column_family("late").unwrap().begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        assert_eq!(table.get(&1).unwrap().unwrap().value(), 10);
+    }
+
+    #[test]
+    fn test_checkpoint_cf_retires_only_its_entries() {
+        const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        for (name, commits) in [("hot", 3), ("cold", 2)] {
+            let cf = db.create_column_family(name, None).unwrap();
+            for i in 0..commits {
+                let txn = cf.begin_write().unwrap();
+                txn.open_table(TABLE).unwrap().insert(&i, &i).unwrap();
+                txn.commit().unwrap();
+            }
+        }
+        let pending = || {
+            let journal = db.wal_journal.as_ref().unwrap();
+            entries_to_apply(journal.read_from(0).unwrap())
+                .into_iter()
+                .map(|entry| entry.cf_name)
+                .collect::<Vec<_>>()
+        };
+
+        // "cold" still has entries pending, so "hot" is retired without shrinking the WAL
+        let report = db.checkpoint_cf("hot").unwrap();
+        assert_eq!(
+            report.entries_by_cf,
+            HashMap::from([("hot".to_string(), 3)])
+        );
+        assert_eq!(report.wal_bytes_truncated, 0);
+        assert_eq!(pending(), ["cold", "cold"]);
+        let stats = db.wal_stats().unwrap().unwrap();
+        assert_eq!(stats.pending_entries, 2);
+        assert_eq!(
+            stats.pending_entries_by_cf,
+            HashMap::from([("cold".to_string(), 2)])
+        );
+        assert_eq!(
+            db.checkpoint_cf("hot").unwrap(),
+            CheckpointReport::default()
+        );
+
+        // Once nothing else is pending, the WAL is emptied
+        let report = db.checkpoint_cf("cold").unwrap();
+        assert_eq!(report.entries_applied(), 2);
+        assert!(report.wal_bytes_truncated > 0);
+        assert!(
+            db.wal_journal
+                .as_ref()
+                .unwrap()
+                .read_from(0)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(db.wal_stats().unwrap().unwrap().checkpoints, 2);
+
+        assert!(matches!(
+            db.checkpoint_cf("missing"),
+            Err(ColumnFamilyError::NotFound(_))
+        ));
+        assert_eq!(db.checkpoint().unwrap(), CheckpointReport::default());
+    }
+
+    #[test]
+    fn test_drop_stops_checkpoint_manager() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        db.create_column_family("data", None).unwrap();
+        let manager = Arc::downgrade(db.checkpoint_manager.as_ref().unwrap());
+
+        // Dropping the database shuts down and joins the checkpoint thread, and releases the
+        // file lock so the database can be reopened straight away
+        drop(db);
+        assert!(manager.upgrade().is_none());
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        assert_eq!(db.list_column_families(), vec!["data".to_string()]);
+    }
+
+    #[test]
+    fn test_close_checkpoints_and_releases_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let table: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        let cf = db.create_column_family("data", None).unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(table).unwrap().insert(1, 10).unwrap();
+        txn.commit().unwrap();
+        db.close().unwrap();
+
+        // Handles that outlive the database fail instead of reaching the released file
+        assert!(matches!(
+            cf.begin_write(),
+            Err(TransactionError::Storage(StorageError::DatabaseClosed))
+        ));
+        assert!(matches!(
+            cf.begin_read(),
+            Err(TransactionError::Storage(StorageError::DatabaseClosed))
+        ));
+        assert!(matches!(
+            cf.flush(),
+            Err(DatabaseError::Storage(StorageError::DatabaseClosed))
+        ));
+
+        // The final checkpoint emptied the WAL, so there is nothing to replay on reopen
+        let journal = WALJournal::open(db_path.with_extension("wal"), None).unwrap();
+        assert!(journal.read_from(0).unwrap().is_empty());
+        drop(journal);
+
+        // The lock is released, so the database reopens at once while the old handle is held
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        let txn = db.column_family("data").unwrap().begin_read().unwrap();
+        let value = txn
+            .open_table(table)
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .unwrap()
+            .value();
+        assert_eq!(value, 10);
+        drop(cf);
+    }
+
+    #[test]
+    fn test_close_without_wal() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let table: TableDefinition<&str, &str> = TableDefinition::new("data");
+
+        let db = ColumnFamilyDatabase::builder()
+            .without_wal()
+            .open(&db_path)
+            .unwrap();
+        let cf = db.create_column_family("data", None).unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(table)
+            .unwrap()
+            .insert("key", "value")
+            .unwrap();
+        txn.commit().unwrap();
+        db.close().unwrap();
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        let txn = db.column_family("data").unwrap().begin_read().unwrap();
+        let table = txn.open_table(table).unwrap();
+        assert_eq!(table.get("key").unwrap().unwrap().value(), "value");
+    }
+
+    #[test]
+    fn test_recovery_discards_entries_from_previous_generation() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let old_generation = {
+            let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+            let old_generation = db.create_column_family("jobs", None).unwrap().generation();
+            db.delete_column_family("jobs").unwrap();
+            let recreated = db.create_column_family("jobs", None).unwrap();
+            assert_ne!(recreated.generation(), old_generation);
+            old_generation
+        };
+
+        // Leave an entry from the old incarnation in the WAL. Its root points at a page the
+        // new column family never wrote, so replaying it would corrupt the table tree.
+        {
+            let journal = WALJournal::open(db_path.with_extension("wal"), None).unwrap();
+            let payload = WALTransactionPayload {
+                user_root: Some((crate::tree_store::PageNumber::new(0, 1000, 0), 0, 5)),
+                system_root: None,
+                freed_pages: vec![],
+                allocated_pages: vec![],
+                durability: Durability::Immediate,
+            };
+            let mut entry = WALEntry::new("jobs".to_string(), old_generation, 5, payload);
+            journal.append(&mut entry).unwrap();
+            journal.sync().unwrap();
+        }
+
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        let cf = db.column_family("jobs").unwrap();
+        let txn = cf.begin_read().unwrap();
+        assert_eq!(txn.list_tables().unwrap().count(), 0);
+        assert!(
+            db.wal_journal
+                .as_ref()
+                .unwrap()
+                .read_from(0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_commit_rolls_back_when_wal_append_fails() {
+        use crate::{CommitError, ReadableTable};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+        /// WAL storage that writes half of each write and then fails while `full` is set, as
+        /// on a full disk
+        #[derive(Debug, Default)]
+        struct FullDisk {
+            inner: InMemoryBackend,
+            full: AtomicBool,
+        }
+
+        impl StorageBackend for FullDisk {
+            fn len(&self) -> io::Result<u64> {
+                self.inner.len()
+            }
+
+            fn read(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+                self.inner.read(offset, out)
+            }
+
+            fn set_len(&self, len: u64) -> io::Result<()> {
+                self.inner.set_len(len)
+            }
+
+            fn sync_data(&self) -> io::Result<()> {
+                self.inner.sync_data()
+            }
+
+            fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+                if self.full.load(Ordering::SeqCst) {
+                    self.inner.write(offset, &data[..data.len() / 2])?;
+                    return Err(io::ErrorKind::StorageFull.into());
                 }
+                self.inner.write(offset, data)
             }
         }
 
-        // Shutdown checkpoint manager if it exists (native platforms only)
-        #[cfg(not(target_arch = "wasm32"))]
-        if let Some(checkpoint_mgr) = self.checkpoint_manager.take() {
-            // Try to unwrap the Arc - if we're the last owner, we can shutdown gracefully
-            if let Ok(manager) = Arc::try_unwrap(checkpoint_mgr) {
-                let _ = manager.shutdown();
+        let storage: Arc<dyn StorageBackend> = Arc::new(InMemoryBackend::new());
+        let wal = Arc::new(FullDisk::default());
+        let open = || {
+            ColumnFamilyDatabase::open_with_storage(
+                None,
+                Arc::clone(&storage),
+                BackendSource::shared(Arc::clone(&storage)),
+                Some(WALJournal::new(Arc::clone(&wal) as Arc<dyn StorageBackend>).unwrap()),
+                WALConfig::default(),
+                ColumnFamilyDefaults::default(),
+                RecoveryOptions::default(),
+                Keyring::default(),
+                None,
+            )
+            .unwrap()
+        };
+        let read_all = |cf: &ColumnFamily| -> Vec<(u64, u64)> {
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(TABLE).unwrap();
+            table
+                .iter()
+                .unwrap()
+                .map(|entry| {
+                    let (key, value) = entry.unwrap();
+                    (key.value(), value.value())
+                })
+                .collect()
+        };
+
+        let db = open();
+        let cf = db.create_column_family("data", None).unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(TABLE).unwrap().insert(&1, &1).unwrap();
+        txn.commit().unwrap();
+
+        // A commit that allocates and frees pages fails without panicking
+        wal.full.store(true, Ordering::SeqCst);
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TABLE).unwrap();
+            for i in 2..2000 {
+                table.insert(&i, &i).unwrap();
+            }
+            table.remove(&1).unwrap();
+        }
+        match txn.commit() {
+            Err(CommitError::Storage(StorageError::Io(err))) => {
+                assert_eq!(err.kind(), io::ErrorKind::StorageFull);
             }
-            // If Arc::try_unwrap fails, other references exist and Drop on CheckpointManager
-            // will handle shutdown when they're dropped
+            other => panic!("expected a storage error, got {other:?}"),
+        }
+        assert_eq!(read_all(&cf), [(1, 1)]);
+
+        // Once space is freed, later transactions commit on top of the last good one
+        wal.full.store(false, Ordering::SeqCst);
+        for i in 10..13 {
+            let txn = cf.begin_write().unwrap();
+            txn.open_table(TABLE).unwrap().insert(&i, &i).unwrap();
+            txn.commit().unwrap();
         }
+        let expected = [(1, 1), (10, 10), (11, 11), (12, 12)];
+        assert_eq!(read_all(&cf), expected);
+        drop(cf);
+        drop(db);
 
-        // Close the header backend to release the file lock (or OPFS handle)
-        let _ = self.header_backend.close();
+        let db = open();
+        assert_eq!(read_all(&db.column_family("data").unwrap()), expected);
     }
 }
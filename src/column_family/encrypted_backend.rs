@@ -0,0 +1,574 @@
+use crate::StorageBackend;
+use crate::column_family::encryption::{EncryptionKey, KeyProvider, Keyring};
+use crate::column_family::wal::entry::PayloadCipher;
+use chacha20poly1305::aead::{AeadCore, AeadInPlace, KeyInit, OsRng};
+use chacha20poly1305::{Key, Tag, XChaCha20Poly1305, XNonce};
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// Size of the plaintext blocks that data is encrypted in. Matches the page size, so that
+/// a page is read and written as one block.
+pub(crate) const BLOCK_SIZE: u64 = 4096;
+
+/// Bytes stored after the ciphertext of each block:
+/// tag (16) | nonce (24) | `key_id` (u32) | reserved (u32, zero)
+const TRAILER_SIZE: u64 = 48;
+
+const TAG_SIZE: usize = 16;
+
+const NONCE_SIZE: usize = 24;
+
+/// Size a block takes in the underlying storage.
+const STORED_BLOCK_SIZE: u64 = BLOCK_SIZE + TRAILER_SIZE;
+
+/// Number of blocks sealed at a time when the storage grows.
+const GROWTH_CHUNK_BLOCKS: u64 = 256;
+
+/// Returns a new random nonce. At 192 bits, random nonces never repeat in practice, however
+/// many blocks are written and whatever the clock does between runs.
+fn random_nonce() -> XNonce {
+    XChaCha20Poly1305::generate_nonce(&mut OsRng)
+}
+
+/// Returns the associated data of a block: the id of its column family and its index, which
+/// bind the ciphertext to its position, followed by the rest of its trailer.
+fn block_aad(cf_id: u64, block_index: u64, trailer: &[u8]) -> Vec<u8> {
+    [&cf_id.to_le_bytes(), &block_index.to_le_bytes(), trailer].concat()
+}
+
+fn cipher(key: &EncryptionKey) -> XChaCha20Poly1305 {
+    XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()))
+}
+
+/// Returns how many bytes of data fit in `stored_len` bytes of encrypted storage.
+pub(crate) fn encrypted_capacity(stored_len: u64) -> u64 {
+    stored_len / STORED_BLOCK_SIZE * BLOCK_SIZE
+}
+
+/// A storage backend that encrypts the data of a column family before it reaches the
+/// underlying storage.
+///
+/// Data is encrypted with XChaCha20-Poly1305 in blocks of 4 KiB, each stored with a 48 byte
+/// trailer holding its authentication tag, its nonce and the id of the key it was sealed
+/// with. Every write seals a block with a new random nonce, and the column family id and the
+/// block index are authenticated with it, so a block cannot be moved to another position.
+/// Growing the storage seals the new blocks as zeros, so every block is authenticated and
+/// unwritten storage still reads as zeros, like that of any backend.
+///
+/// Reading a block fails with [`io::ErrorKind::InvalidData`] if it was not sealed with the
+/// key the [`KeyProvider`] returns for its key id, so a wrong key is detected on the first
+/// read rather than returning garbage.
+///
+/// # Example
+///
+/// ```ignore
+/// use manifold::column_family::{EncryptedStorageBackend, EncryptionKey};
+///
+/// let backend = EncryptedStorageBackend::new(inner, "secrets", 1, Arc::new(keys));
+/// let db = Database::builder().create_with_backend(backend)?;
+/// ```
+pub struct EncryptedStorageBackend {
+    inner: Box<dyn StorageBackend>,
+    cf_name: String,
+    cf_id: u64,
+    keys: Arc<dyn KeyProvider>,
+    /// Serializes writes, which read and reseal the blocks they only partly cover
+    write_lock: Mutex<()>,
+}
+
+impl EncryptedStorageBackend {
+    /// Creates a backend that encrypts the data of column family `cf_name` into `inner`.
+    ///
+    /// `cf_id` and the index of each block are authenticated as associated data, which binds
+    /// every block to its column family and its position, so `cf_id` must differ between all
+    /// column families encrypted with the same key.
+    /// [`ColumnFamilyDatabase`](crate::column_family::ColumnFamilyDatabase) uses the column
+    /// family's generation.
+    pub fn new(
+        inner: impl StorageBackend,
+        cf_name: impl Into<String>,
+        cf_id: u64,
+        keys: Arc<dyn KeyProvider>,
+    ) -> Self {
+        Self {
+            inner: Box::new(inner),
+            cf_name: cf_name.into(),
+            cf_id,
+            keys,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Encrypts the plaintext in the first [`BLOCK_SIZE`] bytes of `block` in place, and
+    /// fills in its trailer.
+    fn seal_block(&self, block_index: u64, block: &mut [u8]) -> io::Result<()> {
+        let (key_id, key) = self.keys.current_key(&self.cf_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("no key for encrypted column family '{}'", self.cf_name),
+            )
+        })?;
+        let nonce = random_nonce();
+
+        let (data, trailer) = block.split_at_mut(BLOCK_SIZE as usize);
+        let (tag, rest) = trailer.split_at_mut(TAG_SIZE);
+        rest[..NONCE_SIZE].copy_from_slice(&nonce);
+        rest[NONCE_SIZE..NONCE_SIZE + 4].copy_from_slice(&key_id.to_le_bytes());
+        rest[NONCE_SIZE + 4..].fill(0);
+
+        let aad = block_aad(self.cf_id, block_index, rest);
+        let sealed_tag = cipher(&key)
+            .encrypt_in_place_detached(&nonce, &aad, data)
+            .map_err(|_| io::Error::other("block encryption failed"))?;
+        tag.copy_from_slice(&sealed_tag);
+        Ok(())
+    }
+
+    /// Decrypts `block` in place, leaving the plaintext in its first [`BLOCK_SIZE`] bytes.
+    ///
+    /// Every block within the storage is sealed, so a block that fails authentication, such
+    /// as one of zeros, is an error.
+    fn open_block(&self, block_index: u64, block: &mut [u8]) -> io::Result<()> {
+        let (data, trailer) = block.split_at_mut(BLOCK_SIZE as usize);
+        let (tag, rest) = trailer.split_at(TAG_SIZE);
+        let nonce = XNonce::from_slice(&rest[..NONCE_SIZE]);
+        let key_id = u32::from_le_bytes(rest[NONCE_SIZE..NONCE_SIZE + 4].try_into().unwrap());
+        let key = self.keys.key(&self.cf_name, key_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "no key with id {key_id} for encrypted column family '{}'",
+                    self.cf_name
+                ),
+            )
+        })?;
+
+        cipher(&key)
+            .decrypt_in_place_detached(
+                nonce,
+                &block_aad(self.cf_id, block_index, rest),
+                data,
+                Tag::from_slice(tag),
+            )
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "block {block_index} of column family '{}' could not be decrypted: wrong key or corrupted data",
+                        self.cf_name
+                    ),
+                )
+            })
+    }
+
+    /// Returns the range of blocks covering `len` bytes from `offset`.
+    fn blocks(offset: u64, len: usize) -> std::ops::Range<u64> {
+        let first = offset / BLOCK_SIZE;
+        let end = (offset + len as u64).div_ceil(BLOCK_SIZE);
+        first..end
+    }
+}
+
+impl Debug for EncryptedStorageBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedStorageBackend")
+            .field("inner", &self.inner)
+            .field("cf_name", &self.cf_name)
+            .field("cf_id", &self.cf_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StorageBackend for EncryptedStorageBackend {
+    fn len(&self) -> io::Result<u64> {
+        Ok(encrypted_capacity(self.inner.len()?))
+    }
+
+    fn read(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        if out.is_empty() {
+            return Ok(());
+        }
+
+        let blocks = Self::blocks(offset, out.len());
+        let mut stored = vec![
+            0u8;
+            usize::try_from(blocks.end - blocks.start).unwrap()
+                * STORED_BLOCK_SIZE as usize
+        ];
+        self.inner
+            .read(blocks.start * STORED_BLOCK_SIZE, &mut stored)?;
+
+        let mut position = offset;
+        let mut copied = 0;
+        for (block_index, block) in blocks.zip(stored.chunks_exact_mut(STORED_BLOCK_SIZE as usize))
+        {
+            self.open_block(block_index, block)?;
+
+            #[allow(clippy::cast_possible_truncation)]
+            let within = (position % BLOCK_SIZE) as usize;
+            let len = (BLOCK_SIZE as usize - within).min(out.len() - copied);
+            out[copied..copied + len].copy_from_slice(&block[within..within + len]);
+            copied += len;
+            position += len as u64;
+        }
+
+        Ok(())
+    }
+
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+
+        // New blocks are sealed as zeros, so that no block of the storage is left
+        // unauthenticated
+        let blocks = len.div_ceil(BLOCK_SIZE);
+        let mut sealed = self.inner.len()? / STORED_BLOCK_SIZE;
+        self.inner.set_len(blocks * STORED_BLOCK_SIZE)?;
+        while sealed < blocks {
+            let chunk = (blocks - sealed).min(GROWTH_CHUNK_BLOCKS);
+            let mut stored =
+                vec![0u8; usize::try_from(chunk).unwrap() * STORED_BLOCK_SIZE as usize];
+            for (block_index, block) in
+                (sealed..).zip(stored.chunks_exact_mut(STORED_BLOCK_SIZE as usize))
+            {
+                self.seal_block(block_index, block)?;
+            }
+            self.inner.write(sealed * STORED_BLOCK_SIZE, &stored)?;
+            sealed += chunk;
+        }
+        Ok(())
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        self.inner.sync_data()
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let _guard = self.write_lock.lock().unwrap();
+
+        let blocks = Self::blocks(offset, data.len());
+        let stored_start = blocks.start * STORED_BLOCK_SIZE;
+        let mut stored = vec![
+            0u8;
+            usize::try_from(blocks.end - blocks.start).unwrap()
+                * STORED_BLOCK_SIZE as usize
+        ];
+
+        // The parts of the first and last blocks that are not overwritten keep their contents
+        let stored_len = self.inner.len()?;
+        let last_index = blocks.end - 1;
+        for block_index in [blocks.start, last_index] {
+            let start = block_index * STORED_BLOCK_SIZE;
+            let partial = (block_index == blocks.start && offset % BLOCK_SIZE != 0)
+                || (block_index == last_index && (offset + data.len() as u64) % BLOCK_SIZE != 0);
+            if partial && start + STORED_BLOCK_SIZE <= stored_len {
+                #[allow(clippy::cast_possible_truncation)]
+                let block =
+                    &mut stored[(start - stored_start) as usize..][..STORED_BLOCK_SIZE as usize];
+                self.inner.read(start, block)?;
+                self.open_block(block_index, block)?;
+            }
+        }
+
+        let mut position = offset;
+        let mut copied = 0;
+        for (block_index, block) in blocks.zip(stored.chunks_exact_mut(STORED_BLOCK_SIZE as usize))
+        {
+            #[allow(clippy::cast_possible_truncation)]
+            let within = (position % BLOCK_SIZE) as usize;
+            let len = (BLOCK_SIZE as usize - within).min(data.len() - copied);
+            block[within..within + len].copy_from_slice(&data[copied..copied + len]);
+            copied += len;
+            position += len as u64;
+
+            self.seal_block(block_index, block)?;
+        }
+
+        self.inner.write(stored_start, &stored)
+    }
+
+    fn prefetch(&self, offset: u64, len: u64) -> io::Result<()> {
+        let first = offset / BLOCK_SIZE;
+        let end = offset.saturating_add(len).div_ceil(BLOCK_SIZE);
+        self.inner
+            .prefetch(first * STORED_BLOCK_SIZE, (end - first) * STORED_BLOCK_SIZE)
+    }
+
+    fn close(&self) -> io::Result<()> {
+        self.inner.close()
+    }
+}
+
+/// Bytes stored before the ciphertext of a sealed WAL payload: its nonce and key id.
+const WAL_PREFIX_SIZE: usize = NONCE_SIZE + 4;
+
+fn wal_aad(header: &[u8], cf_generation: u64, prefix: &[u8]) -> Vec<u8> {
+    [header, &cf_generation.to_le_bytes(), prefix].concat()
+}
+
+/// WAL payloads are sealed like blocks, with a random nonce, and with the entry header and
+/// the column family generation authenticated with them.
+///
+/// Format: nonce (24) | `key_id` (u32) | ciphertext | tag (16)
+impl PayloadCipher for Keyring {
+    fn seal(
+        &self,
+        cf_name: &str,
+        cf_generation: u64,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Option<Vec<u8>> {
+        let (key_id, key) = self.current_key(cf_name)?;
+        let nonce = random_nonce();
+
+        let mut sealed = Vec::with_capacity(WAL_PREFIX_SIZE + payload.len() + TAG_SIZE);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&key_id.to_le_bytes());
+        sealed.extend_from_slice(payload);
+
+        let aad = wal_aad(header, cf_generation, &sealed[..WAL_PREFIX_SIZE]);
+        let tag = cipher(&key)
+            .encrypt_in_place_detached(&nonce, &aad, &mut sealed[WAL_PREFIX_SIZE..])
+            .expect("WAL payload too large to encrypt");
+        sealed.extend_from_slice(&tag);
+        Some(sealed)
+    }
+
+    fn open(
+        &self,
+        cf_name: &str,
+        cf_generation: u64,
+        header: &[u8],
+        sealed: &[u8],
+    ) -> io::Result<Vec<u8>> {
+        if sealed.len() < WAL_PREFIX_SIZE + TAG_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "sealed WAL payload too short",
+            ));
+        }
+        let nonce = XNonce::from_slice(&sealed[..NONCE_SIZE]);
+        let key_id = u32::from_le_bytes(sealed[NONCE_SIZE..WAL_PREFIX_SIZE].try_into().unwrap());
+        let key = self.key(cf_name, key_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("no key with id {key_id} for encrypted column family '{cf_name}'"),
+            )
+        })?;
+
+        let aad = wal_aad(header, cf_generation, &sealed[..WAL_PREFIX_SIZE]);
+        let (ciphertext, tag) =
+            sealed[WAL_PREFIX_SIZE..].split_at(sealed.len() - WAL_PREFIX_SIZE - TAG_SIZE);
+        let mut payload = ciphertext.to_vec();
+        cipher(&key)
+            .decrypt_in_place_detached(nonce, &aad, &mut payload, Tag::from_slice(tag))
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "WAL entry for column family '{cf_name}' could not be decrypted: wrong key or corrupted data"
+                    ),
+                )
+            })?;
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::InMemoryBackend;
+
+    /// Rotates keys by handing out a new current key, keeping the old ones readable
+    struct RotatingKeys(Mutex<Vec<EncryptionKey>>);
+
+    impl KeyProvider for RotatingKeys {
+        fn current_key(&self, _: &str) -> Option<(u32, EncryptionKey)> {
+            let keys = self.0.lock().unwrap();
+            Some((u32::try_from(keys.len() - 1).unwrap(), keys.last()?.clone()))
+        }
+
+        fn key(&self, _: &str, key_id: u32) -> Option<EncryptionKey> {
+            self.0.lock().unwrap().get(key_id as usize).cloned()
+        }
+    }
+
+    fn keys(key: u8) -> Arc<dyn KeyProvider> {
+        let mut keyring = Keyring::default();
+        keyring.insert("secrets".to_string(), EncryptionKey::new([key; 32]));
+        Arc::new(keyring)
+    }
+
+    /// Lets the tests look at the bytes an encrypted backend stores
+    #[derive(Debug)]
+    struct Shared(Arc<InMemoryBackend>);
+
+    impl StorageBackend for Shared {
+        fn len(&self) -> io::Result<u64> {
+            self.0.len()
+        }
+
+        fn read(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+            self.0.read(offset, out)
+        }
+
+        fn set_len(&self, len: u64) -> io::Result<()> {
+            self.0.set_len(len)
+        }
+
+        fn sync_data(&self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+            self.0.write(offset, data)
+        }
+    }
+
+    fn backend(
+        inner: &Arc<InMemoryBackend>,
+        keys: Arc<dyn KeyProvider>,
+    ) -> EncryptedStorageBackend {
+        EncryptedStorageBackend::new(Shared(Arc::clone(inner)), "secrets", 1, keys)
+    }
+
+    fn raw_bytes(inner: &InMemoryBackend) -> Vec<u8> {
+        let mut bytes = vec![0; usize::try_from(inner.len().unwrap()).unwrap()];
+        inner.read(0, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip_unaligned() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let backend = backend(&inner, keys(1));
+        backend.set_len(3 * BLOCK_SIZE).unwrap();
+        assert_eq!(backend.len().unwrap(), 3 * BLOCK_SIZE);
+        assert_eq!(inner.len().unwrap(), 3 * STORED_BLOCK_SIZE);
+
+        // Unwritten storage reads as zeros
+        let mut out = vec![1u8; 100];
+        backend.read(BLOCK_SIZE - 50, &mut out).unwrap();
+        assert!(out.iter().all(|&byte| byte == 0));
+
+        // A write spanning a block boundary keeps the rest of both blocks
+        backend.write(0, &[7; 4096]).unwrap();
+        let data: Vec<u8> = (0..=255).cycle().take(5000).collect();
+        backend.write(100, &data).unwrap();
+
+        let mut out = vec![0u8; 3 * BLOCK_SIZE as usize];
+        backend.read(0, &mut out).unwrap();
+        assert!(out[..100].iter().all(|&byte| byte == 7));
+        assert_eq!(&out[100..5100], &data[..]);
+        assert!(out[5100..].iter().all(|&byte| byte == 0));
+
+        // Another handle with the same key reads the same data
+        let mut reopened = vec![0u8; 5000];
+        self::backend(&inner, keys(1))
+            .read(100, &mut reopened)
+            .unwrap();
+        assert_eq!(reopened, data);
+    }
+
+    #[test]
+    fn test_stored_bytes_are_encrypted() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let backend = backend(&inner, keys(1));
+        backend.set_len(BLOCK_SIZE).unwrap();
+
+        let plaintext = b"attack at dawn, attack at dawn";
+        backend.write(64, plaintext).unwrap();
+        let first = raw_bytes(&inner);
+        assert!(
+            !first
+                .windows(plaintext.len())
+                .any(|window| window == plaintext)
+        );
+
+        // Rewriting the same data uses a new nonce
+        backend.write(64, plaintext).unwrap();
+        assert_ne!(raw_bytes(&inner), first);
+    }
+
+    #[test]
+    fn test_moved_or_zeroed_blocks_fail() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let backend = backend(&inner, keys(1));
+        backend.set_len(2 * BLOCK_SIZE).unwrap();
+        backend.write(0, b"first").unwrap();
+        let mut out = [0u8; 5];
+
+        // A block copied to another index does not authenticate there
+        let mut stored = vec![0u8; STORED_BLOCK_SIZE as usize];
+        inner.read(0, &mut stored).unwrap();
+        inner.write(STORED_BLOCK_SIZE, &stored).unwrap();
+        let err = backend.read(BLOCK_SIZE, &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Neither does a block of zeros, since every block of the storage is sealed
+        inner
+            .write(0, &vec![0u8; STORED_BLOCK_SIZE as usize])
+            .unwrap();
+        let err = backend.read(0, &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let backend = backend(&inner, keys(1));
+        backend.set_len(BLOCK_SIZE).unwrap();
+        backend.write(0, b"header").unwrap();
+
+        let mut out = [0u8; 6];
+        let err = self::backend(&inner, keys(2))
+            .read(0, &mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = self::backend(&inner, Arc::new(Keyring::default()))
+            .read(0, &mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_rotated_key_reads_old_blocks() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let keys = Arc::new(RotatingKeys(Mutex::new(vec![EncryptionKey::new([1; 32])])));
+        let backend = backend(&inner, keys.clone());
+        backend.set_len(2 * BLOCK_SIZE).unwrap();
+        backend.write(0, b"old").unwrap();
+
+        keys.0.lock().unwrap().push(EncryptionKey::new([2; 32]));
+        backend.write(BLOCK_SIZE, b"new").unwrap();
+
+        let mut out = [0u8; 3];
+        backend.read(0, &mut out).unwrap();
+        assert_eq!(&out, b"old");
+        backend.read(BLOCK_SIZE, &mut out).unwrap();
+        assert_eq!(&out, b"new");
+    }
+
+    #[test]
+    fn test_wal_payload_round_trip() {
+        let mut keyring = Keyring::default();
+        keyring.insert("secrets".to_string(), EncryptionKey::new([3; 32]));
+
+        assert!(keyring.seal("public", 1, b"header", b"payload").is_none());
+        let sealed = keyring.seal("secrets", 1, b"header", b"payload").unwrap();
+        assert!(!sealed.windows(7).any(|window| window == b"payload"));
+        assert_eq!(
+            keyring.open("secrets", 1, b"header", &sealed).unwrap(),
+            b"payload"
+        );
+
+        // The payload is bound to the entry header and the column family generation
+        assert!(keyring.open("secrets", 1, b"HEADER", &sealed).is_err());
+        assert!(keyring.open("secrets", 2, b"header", &sealed).is_err());
+    }
+}
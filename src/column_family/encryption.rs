@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// Size of an [`EncryptionKey`] in bytes.
+pub const ENCRYPTION_KEY_SIZE: usize = 32;
+
+/// A 256-bit key that the pages and WAL entries of a column family are encrypted with.
+///
+/// The key bytes are never printed by its `Debug` implementation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; ENCRYPTION_KEY_SIZE]);
+
+impl EncryptionKey {
+    /// Creates a key from its raw bytes.
+    pub fn new(bytes: [u8; ENCRYPTION_KEY_SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the raw bytes of the key.
+    pub fn as_bytes(&self) -> &[u8; ENCRYPTION_KEY_SIZE] {
+        &self.0
+    }
+}
+
+impl From<[u8; ENCRYPTION_KEY_SIZE]> for EncryptionKey {
+    fn from(bytes: [u8; ENCRYPTION_KEY_SIZE]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Supplies the keys of encrypted column families.
+///
+/// Keys are identified by a `key_id` that is stored next to every page and WAL entry they
+/// encrypt. Rotating a key means returning a new key, under a new id, from
+/// [`current_key`](Self::current_key): data written from then on uses the new key, while
+/// data written earlier is still decrypted with the key [`key`](Self::key) returns for its
+/// id. Old keys must stay available until the data they encrypt has been rewritten.
+///
+/// Set with
+/// [`ColumnFamilyDatabaseBuilder::key_provider()`](crate::column_family::ColumnFamilyDatabaseBuilder::key_provider).
+pub trait KeyProvider: Send + Sync {
+    /// Returns the id and key that new data of column family `cf_name` is encrypted with,
+    /// or `None` if the column family is not encrypted.
+    fn current_key(&self, cf_name: &str) -> Option<(u32, EncryptionKey)>;
+
+    /// Returns the key with id `key_id` of column family `cf_name`, or `None` if it is not
+    /// known.
+    fn key(&self, cf_name: &str, key_id: u32) -> Option<EncryptionKey>;
+}
+
+/// The keys a database was opened with: the keys set for single column families, which
+/// have id 0, and then the key provider.
+#[derive(Clone, Default)]
+pub(crate) struct Keyring {
+    keys: HashMap<String, EncryptionKey>,
+    provider: Option<Arc<dyn KeyProvider>>,
+}
+
+impl Keyring {
+    #[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+    pub(crate) fn insert(&mut self, cf_name: String, key: EncryptionKey) {
+        self.keys.insert(cf_name, key);
+    }
+
+    #[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+    pub(crate) fn set_provider(&mut self, provider: Arc<dyn KeyProvider>) {
+        self.provider = Some(provider);
+    }
+
+    /// Returns whether no keys were configured.
+    #[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.provider.is_none()
+    }
+
+    /// Returns whether a key was set for column family `cf_name` itself, rather than coming
+    /// from the provider.
+    pub(crate) fn has_cf_key(&self, cf_name: &str) -> bool {
+        self.keys.contains_key(cf_name)
+    }
+
+    /// Returns whether column family `cf_name` has a key, and so is encrypted when created.
+    pub(crate) fn has_key(&self, cf_name: &str) -> bool {
+        self.current_key(cf_name).is_some()
+    }
+}
+
+impl KeyProvider for Keyring {
+    fn current_key(&self, cf_name: &str) -> Option<(u32, EncryptionKey)> {
+        if let Some(key) = self.keys.get(cf_name) {
+            return Some((0, key.clone()));
+        }
+        self.provider.as_ref()?.current_key(cf_name)
+    }
+
+    fn key(&self, cf_name: &str, key_id: u32) -> Option<EncryptionKey> {
+        if key_id == 0
+            && let Some(key) = self.keys.get(cf_name)
+        {
+            return Some(key.clone());
+        }
+        self.provider.as_ref()?.key(cf_name, key_id)
+    }
+}
+
+impl Debug for Keyring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keyring")
+            .field("column_families", &self.keys.keys().collect::<Vec<_>>())
+            .field("provider", &self.provider.is_some())
+            .finish()
+    }
+}
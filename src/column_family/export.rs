@@ -0,0 +1,673 @@
+//! Portable export format for a single column family.
+//!
+//! An export is a self-describing stream that can be imported into any column family
+//! database, independent of the layout of the file it came from:
+//!
+//! ```text
+//! magic              8 bytes    b"MFLDCFX\0"
+//! version            u32
+//! table count        u32
+//! table list, one record per table:
+//!   name             u32 length, then UTF-8 bytes
+//!   key type         u32 length, then type name bytes
+//!   value type       u32 length, then type name bytes
+//!   key width        u32, or u32::MAX if variable width
+//!   value width      u32, or u32::MAX if variable width
+//!   entry count      u64
+//! checksum           u32, the CRC32 of everything above
+//! entries of each table, in table list order and then key order:
+//!   key              u32 length, then bytes
+//!   value            u32 length, then bytes
+//!   checksum         u32 after the last entry, the CRC32 of the entries of the table
+//! ```
+//!
+//! All integers are little endian. Keys and values are written as their stored bytes, so an
+//! imported table is bit-for-bit identical to the exported one.
+
+use std::cmp::Ordering;
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::transactions::ReadTransaction;
+use crate::tree_store::{InternalTableDefinition, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH};
+use crate::types::TypeName;
+use crate::{DatabaseError, StorageError, TableError};
+
+use super::database::{ColumnFamily, ColumnFamilyError};
+
+const MAGIC: [u8; 8] = *b"MFLDCFX\0";
+const VERSION: u32 = 1;
+const VARIABLE_WIDTH: u32 = u32::MAX;
+/// Longest table or type name an import accepts, in bytes
+const MAX_NAME_LENGTH: usize = 64 * 1024;
+
+/// A table record from the table list of an export.
+struct TableRecord {
+    name: String,
+    key_type: TypeName,
+    value_type: TypeName,
+    fixed_key_size: Option<usize>,
+    fixed_value_size: Option<usize>,
+    entries: u64,
+}
+
+/// Writes every table of the column family, as seen by `txn`, to `writer`.
+///
+/// Multimap tables store nested trees that cannot be described as flat key/value pairs, so
+/// a column family containing one is rejected before anything is written.
+pub(crate) fn export_tables(
+    txn: &ReadTransaction,
+    writer: &mut impl Write,
+) -> Result<(), ColumnFamilyError> {
+    if let Some(table) = txn.list_multimap_tables().map_err(storage_error)?.next() {
+        return Err(ColumnFamilyError::Io(io::Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "multimap table '{}' cannot be exported",
+                crate::MultimapTableHandle::name(&table)
+            ),
+        )));
+    }
+
+    let mut tables = vec![];
+    for handle in txn.list_tables().map_err(storage_error)? {
+        let name = crate::TableHandle::name(&handle).to_string();
+        let (definition, tree) = txn.open_raw_table(&name).map_err(table_error)?;
+        let InternalTableDefinition::Normal {
+            table_length,
+            fixed_key_size,
+            fixed_value_size,
+            key_type,
+            value_type,
+            ..
+        } = definition
+        else {
+            unreachable!();
+        };
+        tables.push((
+            TableRecord {
+                name,
+                key_type,
+                value_type,
+                fixed_key_size,
+                fixed_value_size,
+                entries: table_length,
+            },
+            tree,
+        ));
+    }
+
+    let mut writer = CrcWriter::new(writer);
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    write_u32(&mut writer, tables.len())?;
+    for (record, _) in &tables {
+        write_bytes(&mut writer, record.name.as_bytes())?;
+        write_bytes(&mut writer, &record.key_type.to_bytes())?;
+        write_bytes(&mut writer, &record.value_type.to_bytes())?;
+        write_width(&mut writer, record.fixed_key_size)?;
+        write_width(&mut writer, record.fixed_value_size)?;
+        writer.write_all(&record.entries.to_le_bytes())?;
+    }
+    writer.write_checksum()?;
+
+    for (_, tree) in &tables {
+        tree.for_each_entry(|key, value| {
+            write_bytes(&mut writer, key)?;
+            write_bytes(&mut writer, value)?;
+            Ok(())
+        })
+        .map_err(storage_error)?;
+        writer.write_checksum()?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads an export from `reader` and creates its tables in `cf`, one write transaction per
+/// table.
+///
+/// The table list and the entries of each table are checked against their checksums, every
+/// entry against the fixed widths of its table, and, for key types defined by this crate and
+/// tuples of them, the keys of each table against their order. A table whose entries fail a
+/// check is never committed; the error is returned once the check fails. The entries of each
+/// table are committed in a single transaction, whose pages are written as they fill.
+pub(crate) fn import_tables(
+    cf: &ColumnFamily,
+    reader: &mut impl Read,
+) -> Result<(), ColumnFamilyError> {
+    let mut reader = CrcReader::new(reader);
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(invalid_data("not a column family export").into());
+    }
+    let version = read_u32(&mut reader)?;
+    if version != VERSION {
+        return Err(invalid_data(&format!(
+            "unsupported column family export version {version}"
+        ))
+        .into());
+    }
+
+    let table_count = read_u32(&mut reader)?;
+    let mut tables = vec![];
+    for _ in 0..table_count {
+        let name = String::from_utf8(read_bytes(&mut reader, MAX_NAME_LENGTH)?)
+            .map_err(|_| invalid_data("table name is not valid UTF-8"))?;
+        let key_type = read_type_name(&mut reader)?;
+        let value_type = read_type_name(&mut reader)?;
+        let fixed_key_size = read_width(&mut reader)?;
+        let fixed_value_size = read_width(&mut reader)?;
+        let entries = read_u64(&mut reader)?;
+        tables.push(TableRecord {
+            name,
+            key_type,
+            value_type,
+            fixed_key_size,
+            fixed_value_size,
+            entries,
+        });
+    }
+    reader.verify_checksum("table list")?;
+
+    for record in tables {
+        let order = KeyOrder::of(&record.key_type);
+        if let Some(order) = &order
+            && order.fixed_width() != record.fixed_key_size
+        {
+            return Err(invalid_data(&format!(
+                "table '{}' has a key width that does not match its key type",
+                record.name
+            ))
+            .into());
+        }
+
+        let txn = cf
+            .begin_write()
+            .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+        txn.create_raw_table(
+            &record.name,
+            record.key_type.clone(),
+            record.value_type.clone(),
+            record.fixed_key_size,
+            record.fixed_value_size,
+            |builder| {
+                let mut previous: Option<Vec<u8>> = None;
+                for _ in 0..record.entries {
+                    let (key, value) = read_entry(&mut reader, &record)?;
+                    if let Some(previous) = &previous {
+                        check_key_order(&record.name, order.as_ref(), previous, &key)?;
+                    }
+                    builder.push(&key, &value)?;
+                    previous = Some(key);
+                }
+                reader.verify_checksum(&record.name)?;
+                Ok(())
+            },
+        )
+        .map_err(table_error)?;
+        txn.commit()
+            .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+    }
+
+    Ok(())
+}
+
+/// Reads one entry of the table described by `record`, and checks it against the fixed widths
+/// of the table.
+fn read_entry(reader: &mut impl Read, record: &TableRecord) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let key = read_bytes(reader, record.fixed_key_size.unwrap_or(MAX_VALUE_LENGTH))?;
+    let value = read_bytes(reader, record.fixed_value_size.unwrap_or(MAX_VALUE_LENGTH))?;
+    let widths_match = record.fixed_key_size.is_none_or(|width| width == key.len())
+        && record
+            .fixed_value_size
+            .is_none_or(|width| width == value.len());
+    if !widths_match {
+        return Err(invalid_data(&format!(
+            "entry of {} key bytes and {} value bytes in table '{}' does not match the \
+             table's fixed widths",
+            key.len(),
+            value.len(),
+            record.name
+        )));
+    }
+    if key.len() > MAX_PAIR_LENGTH - value.len() {
+        return Err(invalid_data(&format!(
+            "entry in table '{}' is too large",
+            record.name
+        )));
+    }
+    Ok((key, value))
+}
+
+/// Fails unless `key` follows `previous` in the table `table`.
+///
+/// Without a known order, only checks that the keys differ.
+fn check_key_order(
+    table: &str,
+    order: Option<&KeyOrder>,
+    previous: &[u8],
+    key: &[u8],
+) -> io::Result<()> {
+    let ordering = match order {
+        Some(order) => order
+            .compare(previous, key)
+            .ok_or_else(|| invalid_data(&format!("malformed key in table '{table}'")))?,
+        None if previous == key => Ordering::Equal,
+        None => Ordering::Less,
+    };
+    if ordering != Ordering::Less {
+        return Err(invalid_data(&format!(
+            "keys of table '{table}' are not in strictly ascending order"
+        )));
+    }
+    Ok(())
+}
+
+/// An element of a key whose order is known without its Rust type
+#[derive(Clone, Copy)]
+enum KeyElement {
+    /// Compared bytewise: byte slices and arrays, strings and UUIDs
+    Bytes(Option<usize>),
+    /// A little endian unsigned integer of the given width
+    Unsigned(usize),
+    /// A little endian two's complement integer of the given width
+    Signed(usize),
+}
+
+impl KeyElement {
+    fn of(name: &str) -> Option<Self> {
+        let element = match name {
+            "&[u8]" | "&str" | "String" => Self::Bytes(None),
+            "uuid::Uuid" => Self::Bytes(Some(16)),
+            "u8" => Self::Unsigned(1),
+            "u16" => Self::Unsigned(2),
+            "u32" => Self::Unsigned(4),
+            "u64" => Self::Unsigned(8),
+            "u128" => Self::Unsigned(16),
+            "i8" => Self::Signed(1),
+            "i16" => Self::Signed(2),
+            "i32" => Self::Signed(4),
+            "i64" => Self::Signed(8),
+            "i128" => Self::Signed(16),
+            _ => {
+                let width = name.strip_prefix("[u8;")?.strip_suffix(']')?.parse().ok()?;
+                Self::Bytes(Some(width))
+            }
+        };
+        Some(element)
+    }
+
+    fn fixed_width(self) -> Option<usize> {
+        match self {
+            Self::Bytes(width) => width,
+            Self::Unsigned(width) | Self::Signed(width) => Some(width),
+        }
+    }
+
+    // Both slices must have the fixed width of the element, if it has one
+    fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            Self::Bytes(_) => a.cmp(b),
+            Self::Unsigned(_) => a.iter().rev().cmp(b.iter().rev()),
+            Self::Signed(_) => {
+                // Flipping the sign bit orders two's complement values as unsigned ones
+                let (a_high, a_low) = a.split_last().unwrap();
+                let (b_high, b_low) = b.split_last().unwrap();
+                (a_high ^ 0x80)
+                    .cmp(&(b_high ^ 0x80))
+                    .then_with(|| a_low.iter().rev().cmp(b_low.iter().rev()))
+            }
+        }
+    }
+}
+
+/// The order of the stored keys of a table, for the key types defined by this crate and
+/// tuples of them, the same as the `Key::compare` of the type.
+struct KeyOrder {
+    // The elements of a tuple key, or the key itself
+    elements: Vec<KeyElement>,
+    tuple: bool,
+}
+
+impl KeyOrder {
+    /// Returns the order of keys of type `key_type`, or `None` if it is not known.
+    fn of(key_type: &TypeName) -> Option<Self> {
+        let name = key_type.name();
+        let Some(inner) = name
+            .strip_prefix('(')
+            .and_then(|name| name.strip_suffix(')'))
+        else {
+            let element = KeyElement::of(name)?;
+            let expected = if name == "uuid::Uuid" {
+                TypeName::new(name)
+            } else {
+                TypeName::internal(name)
+            };
+            return (*key_type == expected).then(|| Self {
+                elements: vec![element],
+                tuple: false,
+            });
+        };
+
+        // A one element tuple is stored as its element
+        if let Some(element) = inner.strip_suffix(',') {
+            let element = KeyElement::of(element)?;
+            return (*key_type == TypeName::internal(name)).then(|| Self {
+                elements: vec![element],
+                tuple: false,
+            });
+        }
+
+        let elements = inner
+            .split(',')
+            .map(KeyElement::of)
+            .collect::<Option<Vec<_>>>()?;
+        let order = Self {
+            elements,
+            tuple: true,
+        };
+        // Variable width tuples of the legacy encoding are named without `internal2`
+        let expected = if order.fixed_width().is_some() {
+            TypeName::internal(name)
+        } else {
+            TypeName::internal2(name)
+        };
+        (*key_type == expected).then_some(order)
+    }
+
+    fn fixed_width(&self) -> Option<usize> {
+        self.elements
+            .iter()
+            .map(|element| element.fixed_width())
+            .sum()
+    }
+
+    /// Compares two stored keys, or returns `None` if either is malformed.
+    fn compare(&self, a: &[u8], b: &[u8]) -> Option<Ordering> {
+        if !self.tuple {
+            return Some(self.elements[0].compare(a, b));
+        }
+        let a = self.split(a)?;
+        let b = self.split(b)?;
+        let ordering = self
+            .elements
+            .iter()
+            .zip(a.iter().zip(&b))
+            .map(|(element, (a, b))| element.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal);
+        Some(ordering)
+    }
+
+    // Splits a stored tuple into its elements: the lengths of the variable width elements
+    // other than the last come first, as varints, then the elements themselves
+    fn split<'a>(&self, mut data: &'a [u8]) -> Option<Vec<&'a [u8]>> {
+        let (last, rest) = self.elements.split_last().unwrap();
+        let mut lens = vec![];
+        for element in rest {
+            let len = if let Some(width) = element.fixed_width() {
+                width
+            } else {
+                let (&first, tail) = data.split_first()?;
+                let (len, tail) = match first {
+                    0..=253 => (usize::from(first), tail),
+                    254 => (
+                        usize::from(u16::from_le_bytes(tail.get(..2)?.try_into().ok()?)),
+                        &tail[2..],
+                    ),
+                    255 => (
+                        u32::from_le_bytes(tail.get(..4)?.try_into().ok()?) as usize,
+                        &tail[4..],
+                    ),
+                };
+                data = tail;
+                len
+            };
+            lens.push(len);
+        }
+
+        let mut elements = vec![];
+        for len in lens {
+            let (element, tail) = data.split_at_checked(len)?;
+            elements.push(element);
+            data = tail;
+        }
+        if last.fixed_width().is_some_and(|width| width != data.len()) {
+            return None;
+        }
+        elements.push(data);
+        Some(elements)
+    }
+}
+
+pub(super) fn storage_error(err: StorageError) -> ColumnFamilyError {
+    match err {
+        StorageError::Io(err) => ColumnFamilyError::Io(err),
+        err => ColumnFamilyError::Database(err.into()),
+    }
+}
+
+pub(super) fn table_error(err: TableError) -> ColumnFamilyError {
+    match err {
+        TableError::Storage(err) => storage_error(err),
+        err => ColumnFamilyError::Database(DatabaseError::Storage(
+            err.into_storage_error_or_corrupted("Unexpected table error"),
+        )),
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Passes writes through to `inner`, keeping the CRC32 of the bytes written since the last
+/// checksum.
+struct CrcWriter<W> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> CrcWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    /// Writes the checksum of the bytes written since the last checksum.
+    fn write_checksum(&mut self) -> io::Result<()> {
+        let hasher = std::mem::take(&mut self.hasher);
+        self.inner.write_all(&hasher.finalize().to_le_bytes())
+    }
+}
+
+impl<W: Write> Write for CrcWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Passes reads through to `inner`, keeping the CRC32 of the bytes read since the last
+/// checksum.
+struct CrcReader<R> {
+    inner: R,
+    hasher: crc32fast::Hasher,
+}
+
+impl<R: Read> CrcReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    /// Reads a checksum and fails unless it matches the bytes read since the last checksum.
+    fn verify_checksum(&mut self, what: &str) -> io::Result<()> {
+        let hasher = std::mem::take(&mut self.hasher);
+        if read_u32(&mut self.inner)? != hasher.finalize() {
+            return Err(invalid_data(&format!("checksum mismatch in {what}")));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+fn write_u32(writer: &mut impl Write, value: usize) -> io::Result<()> {
+    let value = u32::try_from(value)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "length does not fit in u32"))?;
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_u32(writer, bytes.len())?;
+    writer.write_all(bytes)
+}
+
+fn write_width(writer: &mut impl Write, width: Option<usize>) -> io::Result<()> {
+    match width {
+        Some(width) => write_u32(writer, width),
+        None => writer.write_all(&VARIABLE_WIDTH.to_le_bytes()),
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// Reads a length-prefixed byte string of at most `max_len` bytes. The buffer grows with the
+// data actually read, so a corrupt length cannot trigger a huge allocation up front
+fn read_bytes(reader: &mut impl Read, max_len: usize) -> io::Result<Vec<u8>> {
+    let len = read_u32(reader)?;
+    if len as usize > max_len {
+        return Err(invalid_data(&format!(
+            "length {len} exceeds the limit of {max_len} bytes"
+        )));
+    }
+    let mut bytes = vec![];
+    reader.take(u64::from(len)).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+fn read_width(reader: &mut impl Read) -> io::Result<Option<usize>> {
+    let width = read_u32(reader)?;
+    Ok((width != VARIABLE_WIDTH).then_some(width as usize))
+}
+
+fn read_type_name(reader: &mut impl Read) -> io::Result<TypeName> {
+    TypeName::try_from_bytes(&read_bytes(reader, MAX_NAME_LENGTH)?)
+        .ok_or_else(|| invalid_data("invalid type name"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_family::ColumnFamilyDatabase;
+    use crate::{Key, ReadableTableMetadata, TableDefinition, Value};
+    use tempfile::TempDir;
+
+    fn assert_same_order<K: Key + 'static>(values: &[K::SelfType<'_>]) {
+        let order = KeyOrder::of(&K::type_name()).unwrap();
+        assert_eq!(order.fixed_width(), K::fixed_width());
+        for a in values {
+            for b in values {
+                let (a, b) = (K::as_bytes(a), K::as_bytes(b));
+                let (a, b) = (a.as_ref(), b.as_ref());
+                assert_eq!(order.compare(a, b), Some(K::compare(a, b)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_order_matches_key_compare() {
+        assert_same_order::<i64>(&[i64::MIN, -300, -1, 0, 1, 255, 256, i64::MAX]);
+        assert_same_order::<u32>(&[0, 1, 255, 256, 65_536, u32::MAX]);
+        assert_same_order::<&str>(&["", "a", "ab", "b"]);
+        assert_same_order::<(u64, &str)>(&[(0, "b"), (1, ""), (1, "a"), (256, "a")]);
+        let bytes: [&[u8]; 2] = [b"", b"z"];
+        assert_same_order::<(&str, i8, &[u8])>(&[
+            ("", -1, bytes[1]),
+            ("a", -1, bytes[0]),
+            ("a", 1, bytes[0]),
+        ]);
+        assert_same_order::<(u16, u8)>(&[(0, 9), (1, 0), (256, 0)]);
+
+        assert!(KeyOrder::of(&<(u64, f32)>::type_name()).is_none());
+        assert!(KeyOrder::of(&TypeName::new("u64")).is_none());
+    }
+
+    // Writes an export of one `u64` to `u64` table holding `keys`, in the given order
+    fn export_u64_table(keys: &[u64]) -> Vec<u8> {
+        let mut export = vec![];
+        let mut writer = CrcWriter::new(&mut export);
+        writer.write_all(&MAGIC).unwrap();
+        writer.write_all(&VERSION.to_le_bytes()).unwrap();
+        write_u32(&mut writer, 1).unwrap();
+        write_bytes(&mut writer, b"values").unwrap();
+        write_bytes(&mut writer, &<u64>::type_name().to_bytes()).unwrap();
+        write_bytes(&mut writer, &<u64>::type_name().to_bytes()).unwrap();
+        write_width(&mut writer, Some(8)).unwrap();
+        write_width(&mut writer, Some(8)).unwrap();
+        writer
+            .write_all(&(keys.len() as u64).to_le_bytes())
+            .unwrap();
+        writer.write_checksum().unwrap();
+        for key in keys {
+            write_bytes(&mut writer, &key.to_le_bytes()).unwrap();
+            write_bytes(&mut writer, &key.to_le_bytes()).unwrap();
+        }
+        writer.write_checksum().unwrap();
+        export
+    }
+
+    #[test]
+    fn test_import_rejects_unordered_keys() {
+        const TABLE: TableDefinition<u64, u64> = TableDefinition::new("values");
+
+        let dir = TempDir::new().unwrap();
+        let db = ColumnFamilyDatabase::open(dir.path().join("test.db")).unwrap();
+
+        db.import_column_family("ordered", export_u64_table(&[1, 2, 256]).as_slice())
+            .unwrap();
+        let cf = db.column_family("ordered").unwrap();
+        let txn = cf.begin_read().unwrap();
+        assert_eq!(txn.open_table(TABLE).unwrap().len().unwrap(), 3);
+
+        for keys in [&[1, 256, 2][..], &[1, 1]] {
+            let Err(ColumnFamilyError::Io(err)) =
+                db.import_column_family("unordered", export_u64_table(keys).as_slice())
+            else {
+                panic!("import of keys {keys:?} did not fail");
+            };
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+        assert_eq!(db.list_column_families(), vec!["ordered".to_string()]);
+    }
+}
@@ -1,9 +1,11 @@
-use crate::DatabaseError;
 use crate::StorageBackend;
+use crate::{DatabaseError, StorageError};
 use std::collections::HashMap;
+use std::io;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use super::unlocked_backend::UnlockedFileBackend;
 
@@ -26,6 +28,35 @@ impl PoolEntry {
     }
 }
 
+/// Usage statistics of a [`FileHandlePool`], as returned by [`FileHandlePool::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileHandlePoolStats {
+    /// Number of handles currently held by the pool.
+    pub in_use: usize,
+    /// Highest number of handles held at once.
+    pub peak: usize,
+    /// Maximum number of handles the pool keeps before evicting.
+    pub max_size: usize,
+    /// Number of column families the pool serves.
+    pub column_families: usize,
+    /// Number of handles evicted to stay within `max_size`.
+    pub evictions: u64,
+    /// Number of `acquire` calls.
+    pub acquires: u64,
+    /// Total time callers spent in `acquire`, waiting for the pool lock or for a new file
+    /// handle to open.
+    pub acquire_wait: Duration,
+}
+
+/// Counters behind [`FileHandlePoolStats`], updated under the entries lock.
+#[derive(Default)]
+struct PoolCounters {
+    peak: usize,
+    evictions: u64,
+    acquires: u64,
+    acquire_wait: Duration,
+}
+
 /// Manages a pool of file handles for column families.
 ///
 /// The pool maintains a fixed maximum number of open file descriptors and implements
@@ -34,10 +65,16 @@ impl PoolEntry {
 ///
 /// Each column family can acquire its own `FileBackend` to the same physical file,
 /// enabling true concurrent writes through independent file descriptors.
+///
+/// With auto sizing, the maximum grows to one handle per column family as column families
+/// are opened and created, so that they do not evict each other.
 pub struct FileHandlePool {
     path: PathBuf,
-    max_size: usize,
+    max_size: AtomicUsize,
+    auto_size: bool,
+    column_families: AtomicUsize,
     entries: Mutex<HashMap<String, PoolEntry>>,
+    counters: Mutex<PoolCounters>,
     /// Global lock for file growth operations to prevent race conditions
     /// when multiple column families try to grow the same underlying file concurrently.
     /// Serializes `set_len()` calls across all file handles to the same file.
@@ -55,12 +92,33 @@ impl FileHandlePool {
     pub fn new(path: PathBuf, max_size: usize) -> Self {
         Self {
             path,
-            max_size,
+            max_size: AtomicUsize::new(max_size),
+            auto_size: false,
+            column_families: AtomicUsize::new(0),
             entries: Mutex::new(HashMap::new()),
+            counters: Mutex::new(PoolCounters::default()),
             file_growth_lock: Arc::new(Mutex::new(())),
         }
     }
 
+    /// Grows the maximum size to the number of column families whenever it is set with
+    /// [`set_column_families`](Self::set_column_families). The pool never shrinks below
+    /// the size it was created with.
+    #[must_use]
+    pub fn with_auto_size(mut self) -> Self {
+        self.auto_size = true;
+        self
+    }
+
+    /// Records the number of column families the pool serves, growing the maximum size to
+    /// match if the pool is auto sized.
+    pub fn set_column_families(&self, count: usize) {
+        self.column_families.store(count, Ordering::Relaxed);
+        if self.auto_size {
+            self.max_size.fetch_max(count, Ordering::Relaxed);
+        }
+    }
+
     /// Acquires a file handle for the specified column family.
     ///
     /// If the column family already has an open handle, it is reused and its
@@ -74,7 +132,21 @@ impl FileHandlePool {
     /// # Returns
     ///
     /// An Arc-wrapped `StorageBackend` that the column family can use for I/O operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::FileHandlesExhausted`] if the process is out of file
+    /// descriptors, even after closing the handles no column family is using.
     pub fn acquire(&self, cf_name: &str) -> Result<Arc<dyn StorageBackend>, DatabaseError> {
+        let started = Instant::now();
+        let result = self.acquire_inner(cf_name);
+        let mut counters = self.counters.lock().unwrap();
+        counters.acquires += 1;
+        counters.acquire_wait += started.elapsed();
+        result
+    }
+
+    fn acquire_inner(&self, cf_name: &str) -> Result<Arc<dyn StorageBackend>, DatabaseError> {
         // Fast path: check if already exists (read-only, no eviction needed)
         {
             let mut entries = self.entries.lock().unwrap();
@@ -86,10 +158,28 @@ impl FileHandlePool {
 
         // Slow path: need to open a new file
         // Open file WITHOUT holding the lock to avoid serializing all threads
-        let file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&self.path)?;
+        let file = match self.open_file() {
+            Err(err) if is_out_of_handles(&err) => {
+                // Handles held only by the pool belong to column families that are no longer
+                // open, so closing them frees descriptors for this one
+                self.entries
+                    .lock()
+                    .unwrap()
+                    .retain(|_, entry| Arc::strong_count(&entry.backend) > 1);
+                self.open_file().map_err(|source| {
+                    if is_out_of_handles(&source) {
+                        StorageError::FileHandlesExhausted {
+                            pool_size: self.max_size(),
+                            column_families: self.column_families.load(Ordering::Relaxed),
+                            source,
+                        }
+                    } else {
+                        StorageError::Io(source)
+                    }
+                })?
+            }
+            result => result?,
+        };
 
         let backend: Arc<dyn StorageBackend> = Arc::new(UnlockedFileBackend::new(file)?);
 
@@ -102,14 +192,23 @@ impl FileHandlePool {
             return Ok(entry.backend.clone());
         }
 
-        if entries.len() >= self.max_size {
-            Self::evict_lru(&mut entries, cf_name);
+        let mut counters = self.counters.lock().unwrap();
+        if entries.len() >= self.max_size() && Self::evict_lru(&mut entries, cf_name) {
+            counters.evictions += 1;
         }
 
         entries.insert(cf_name.to_string(), PoolEntry::new(backend.clone()));
+        counters.peak = counters.peak.max(entries.len());
         Ok(backend)
     }
 
+    fn open_file(&self) -> io::Result<std::fs::File> {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.path)
+    }
+
     /// Updates the `last_used` timestamp for a column family's handle.
     ///
     /// This should be called when a column family's `Database` is reused to prevent
@@ -145,14 +244,29 @@ impl FileHandlePool {
 
     /// Returns the maximum pool size.
     pub fn max_size(&self) -> usize {
-        self.max_size
+        self.max_size.load(Ordering::Relaxed)
     }
 
-    /// Evicts the least recently used entry from the pool.
+    /// Returns handle usage and `acquire` timing statistics.
+    pub fn stats(&self) -> FileHandlePoolStats {
+        let in_use = self.len();
+        let counters = self.counters.lock().unwrap();
+        FileHandlePoolStats {
+            in_use,
+            peak: counters.peak,
+            max_size: self.max_size(),
+            column_families: self.column_families.load(Ordering::Relaxed),
+            evictions: counters.evictions,
+            acquires: counters.acquires,
+            acquire_wait: counters.acquire_wait,
+        }
+    }
+
+    /// Evicts the least recently used entry from the pool, returning whether one was.
     ///
     /// The entry being acquired (`cf_name`) is never evicted, even if it would be
     /// the LRU candidate.
-    fn evict_lru(entries: &mut HashMap<String, PoolEntry>, exclude: &str) {
+    fn evict_lru(entries: &mut HashMap<String, PoolEntry>, exclude: &str) -> bool {
         let mut lru_name: Option<String> = None;
         let mut lru_time = Instant::now();
 
@@ -169,7 +283,9 @@ impl FileHandlePool {
 
         if let Some(name) = lru_name {
             entries.remove(&name);
+            return true;
         }
+        false
     }
 
     /// Returns a clone of the Arc wrapping the file growth lock.
@@ -185,6 +301,89 @@ impl FileHandlePool {
     }
 }
 
+/// Returns true if `err` reports that the process or system has no file descriptors left.
+fn is_out_of_handles(err: &io::Error) -> bool {
+    // ENFILE and EMFILE
+    #[cfg(unix)]
+    const CODES: &[i32] = &[23, 24];
+    // ERROR_TOO_MANY_OPEN_FILES
+    #[cfg(windows)]
+    const CODES: &[i32] = &[4];
+    #[cfg(not(any(unix, windows)))]
+    const CODES: &[i32] = &[];
+
+    err.raw_os_error().is_some_and(|code| CODES.contains(&code))
+}
+
+/// Source of the storage backends that column families are partitioned over.
+#[derive(Clone)]
+pub(crate) enum BackendSource {
+    /// Each column family acquires its own handle to the database file from a pool.
+    Pool(Arc<FileHandlePool>),
+    /// All column families share a single backend, such as an in-memory one.
+    Shared {
+        backend: Arc<dyn StorageBackend>,
+        file_growth_lock: Arc<Mutex<()>>,
+    },
+}
+
+impl BackendSource {
+    /// Creates a source that hands every column family the same backend.
+    pub(crate) fn shared(backend: Arc<dyn StorageBackend>) -> Self {
+        Self::Shared {
+            backend,
+            file_growth_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns the backend for the specified column family.
+    pub(crate) fn acquire(&self, cf_name: &str) -> Result<Arc<dyn StorageBackend>, DatabaseError> {
+        match self {
+            Self::Pool(pool) => pool.acquire(cf_name),
+            Self::Shared { backend, .. } => Ok(Arc::clone(backend)),
+        }
+    }
+
+    /// Marks the column family's handle as recently used. No-op for a shared backend.
+    pub(crate) fn touch(&self, cf_name: &str) {
+        if let Self::Pool(pool) = self {
+            pool.touch(cf_name);
+        }
+    }
+
+    /// Releases the column family's handle. No-op for a shared backend.
+    pub(crate) fn release(&self, cf_name: &str) {
+        if let Self::Pool(pool) = self {
+            pool.release(cf_name);
+        }
+    }
+
+    /// Records the number of column families served. No-op for a shared backend.
+    pub(crate) fn set_column_families(&self, count: usize) {
+        if let Self::Pool(pool) = self {
+            pool.set_column_families(count);
+        }
+    }
+
+    /// Returns the pool's statistics, or None for a shared backend.
+    pub(crate) fn stats(&self) -> Option<FileHandlePoolStats> {
+        match self {
+            Self::Pool(pool) => Some(pool.stats()),
+            Self::Shared { .. } => None,
+        }
+    }
+
+    /// Returns the lock serializing growth of the underlying storage.
+    pub(crate) fn file_growth_lock(&self) -> Arc<Mutex<()>> {
+        match self {
+            Self::Pool(pool) => pool.file_growth_lock(),
+            Self::Shared {
+                file_growth_lock, ..
+            } => Arc::clone(file_growth_lock),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -347,4 +546,50 @@ mod tests {
 
         assert_eq!(pool.len(), 5);
     }
+
+    #[test]
+    fn test_stats() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        std::fs::write(tmpfile.path(), b"test").unwrap();
+
+        let pool = FileHandlePool::new(tmpfile.path().to_path_buf(), 2);
+        pool.set_column_families(3);
+        for name in ["cf1", "cf2", "cf1", "cf3"] {
+            pool.acquire(name).unwrap();
+        }
+        pool.release("cf3");
+
+        let stats = pool.stats();
+        assert_eq!(stats.in_use, 1);
+        assert_eq!(stats.peak, 2);
+        assert_eq!(stats.max_size, 2);
+        assert_eq!(stats.column_families, 3);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.acquires, 4);
+    }
+
+    #[test]
+    fn test_auto_size() {
+        let tmpfile = NamedTempFile::new().unwrap();
+        std::fs::write(tmpfile.path(), b"test").unwrap();
+
+        let pool = FileHandlePool::new(tmpfile.path().to_path_buf(), 2).with_auto_size();
+        pool.set_column_families(4);
+        assert_eq!(pool.max_size(), 4);
+        pool.set_column_families(1);
+        assert_eq!(pool.max_size(), 4);
+
+        let pool = FileHandlePool::new(tmpfile.path().to_path_buf(), 2);
+        pool.set_column_families(4);
+        assert_eq!(pool.max_size(), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_out_of_handles_detection() {
+        assert!(is_out_of_handles(&io::Error::from_raw_os_error(24)));
+        assert!(is_out_of_handles(&io::Error::from_raw_os_error(23)));
+        assert!(!is_out_of_handles(&io::Error::from_raw_os_error(2)));
+        assert!(!is_out_of_handles(&io::Error::other("too many open files")));
+    }
 }
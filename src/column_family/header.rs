@@ -1,5 +1,7 @@
 use std::io;
 
+use crate::StorageBackend;
+
 /// Magic number identifying a column family database file.
 ///
 /// The sequence includes DOS/Unix line ending detection bytes (0x1A, 0x0A) to help
@@ -8,13 +10,43 @@ pub const MAGIC_NUMBER: [u8; 9] = *b"mnfd-cf\x1A\x0A";
 
 /// Current format version for the master header.
 /// Version 2 introduces segmented column families with free space tracking.
-pub const FORMAT_VERSION: u8 = 2;
+/// Version 3 adds a per-column-family generation counter.
+/// Version 4 adds a creation timestamp and user metadata to each column family.
+/// Version 5 lets the header continue past the first page in header extension segments.
+/// Version 6 records whether each column family is encrypted.
+/// Version 7 keeps two generations of the header in the first two pages of the file.
+pub const FORMAT_VERSION: u8 = 7;
+
+/// Oldest master header format version that can still be read.
+///
+/// Older headers are upgraded on read: version 2 column families get generation 0, column
+/// families from before version 4 get a creation timestamp of 0 and no user metadata, and
+/// column families from before version 6 are unencrypted. Headers from before version 7 have
+/// a single slot, and are read as header generation 0.
+const MIN_READABLE_FORMAT_VERSION: u8 = 2;
+
+/// Maximum size of the user metadata of a column family, in bytes.
+///
+/// All column families share the single page of the master header, so each one may only
+/// use a small part of it.
+pub const MAX_USER_METADATA_SIZE: usize = 256;
 
 /// Size of one page in bytes (4KB).
 ///
 /// The master header must fit within a single page.
 pub(crate) const PAGE_SIZE: usize = 4096;
 
+/// Number of header slots at the start of the file, written alternately.
+const HEADER_SLOTS: usize = 2;
+
+/// Size of the header slots at the start of the file, in bytes. Segments start after them.
+pub(crate) const HEADER_SIZE: u64 = (HEADER_SLOTS * PAGE_SIZE) as u64;
+
+/// Rounds `len` up to a whole number of pages.
+pub(crate) fn round_up_to_page(len: u64) -> u64 {
+    len.div_ceil(PAGE_SIZE as u64) * PAGE_SIZE as u64
+}
+
 /// A contiguous segment of storage within the database file.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Segment {
@@ -111,6 +143,23 @@ impl FreeSegment {
 pub struct ColumnFamilyMetadata {
     /// Name of the column family.
     pub name: String,
+    /// Generation of this column family, assigned when it is created.
+    ///
+    /// A column family that is deleted and recreated under the same name gets a new
+    /// generation, which lets WAL replay tell its entries apart from the old ones.
+    pub generation: u64,
+    /// Time the column family was created, in milliseconds since the Unix epoch.
+    ///
+    /// Column families created before format version 4 have a creation time of 0.
+    pub created_at_ms: u64,
+    /// Application-defined bytes attached to the column family, at most
+    /// [`MAX_USER_METADATA_SIZE`] long. Empty if none were set.
+    pub user_metadata: Vec<u8>,
+    /// Whether the pages of this column family are encrypted.
+    ///
+    /// An encrypted column family can only be opened with its key. Column families from
+    /// before format version 6 are unencrypted.
+    pub encrypted: bool,
     /// Segments that make up this column family.
     /// Multiple segments enable non-contiguous growth without data movement.
     pub segments: Vec<Segment>,
@@ -121,13 +170,52 @@ impl ColumnFamilyMetadata {
     pub fn new(name: String, offset: u64, size: u64) -> Self {
         Self {
             name,
+            generation: 0,
+            created_at_ms: 0,
+            user_metadata: Vec::new(),
+            encrypted: false,
             segments: vec![Segment::new(offset, size)],
         }
     }
 
     /// Creates a new column family metadata entry with multiple segments.
     pub fn with_segments(name: String, segments: Vec<Segment>) -> Self {
-        Self { name, segments }
+        Self {
+            name,
+            generation: 0,
+            created_at_ms: 0,
+            user_metadata: Vec::new(),
+            encrypted: false,
+            segments,
+        }
+    }
+
+    /// Sets the generation of this column family.
+    #[must_use]
+    pub fn with_generation(mut self, generation: u64) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    /// Sets the creation time of this column family, in milliseconds since the Unix epoch.
+    #[must_use]
+    pub fn with_created_at(mut self, created_at_ms: u64) -> Self {
+        self.created_at_ms = created_at_ms;
+        self
+    }
+
+    /// Sets the user metadata of this column family.
+    #[must_use]
+    pub fn with_user_metadata(mut self, user_metadata: Vec<u8>) -> Self {
+        self.user_metadata = user_metadata;
+        self
+    }
+
+    /// Sets whether the pages of this column family are encrypted.
+    #[must_use]
+    pub fn with_encrypted(mut self, encrypted: bool) -> Self {
+        self.encrypted = encrypted;
+        self
     }
 
     /// Returns the total size of all segments.
@@ -135,19 +223,32 @@ impl ColumnFamilyMetadata {
         self.segments.iter().map(|s| s.size).sum()
     }
 
-    /// Serializes this metadata entry to bytes.
+    /// Serializes this metadata entry to bytes in the given header format version (4 or later).
     ///
-    /// Format: `name_len` (u32) | `name_bytes` | `segment_count` (u32) | segments
-    fn to_bytes(&self) -> Vec<u8> {
+    /// Format: `name_len` (u32) | `name_bytes` | `generation` (u64) | `created_at_ms` (u64) |
+    /// `user_metadata_len` (u16) | `user_metadata` | `flags` (u8, bit 0 = encrypted, version
+    /// 6+) | `segment_count` (u32) | segments
+    fn to_bytes(&self, version: u8) -> Vec<u8> {
         let name_bytes = self.name.as_bytes();
         let name_len =
             u32::try_from(name_bytes.len()).expect("column family name exceeds maximum length");
+        let user_metadata_len =
+            u16::try_from(self.user_metadata.len()).expect("user metadata exceeds maximum length");
         let segment_count =
             u32::try_from(self.segments.len()).expect("too many segments in column family");
 
-        let mut bytes = Vec::with_capacity(4 + name_bytes.len() + 4 + self.segments.len() * 16);
+        let mut bytes = Vec::with_capacity(
+            4 + name_bytes.len() + 19 + self.user_metadata.len() + 4 + self.segments.len() * 16,
+        );
         bytes.extend_from_slice(&name_len.to_le_bytes());
         bytes.extend_from_slice(name_bytes);
+        bytes.extend_from_slice(&self.generation.to_le_bytes());
+        bytes.extend_from_slice(&self.created_at_ms.to_le_bytes());
+        bytes.extend_from_slice(&user_metadata_len.to_le_bytes());
+        bytes.extend_from_slice(&self.user_metadata);
+        if version >= 6 {
+            bytes.push(u8::from(self.encrypted));
+        }
         bytes.extend_from_slice(&segment_count.to_le_bytes());
 
         for segment in &self.segments {
@@ -157,10 +258,14 @@ impl ColumnFamilyMetadata {
         bytes
     }
 
-    /// Deserializes metadata from bytes.
+    /// Deserializes metadata from bytes written with the given header format version.
+    ///
+    /// Version 2 entries have no generation field and decode as generation 0. Entries from
+    /// before version 4 have no creation time or user metadata and decode with 0 and none,
+    /// and entries from before version 6 decode as unencrypted.
     ///
     /// Returns (`metadata`, `bytes_consumed`) on success.
-    fn from_bytes(data: &[u8]) -> io::Result<(Self, usize)> {
+    fn from_bytes(data: &[u8], version: u8) -> io::Result<(Self, usize)> {
         if data.len() < 4 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -169,13 +274,14 @@ impl ColumnFamilyMetadata {
         }
 
         let name_len = u32::from_le_bytes(data[0..4].try_into().unwrap()) as usize;
+        let generation_len = if version >= 3 { 8 } else { 0 };
 
-        if data.len() < 4 + name_len + 4 {
+        if data.len() < 4 + name_len + generation_len + 4 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!(
                     "insufficient data for metadata entry: need at least {}, have {}",
-                    4 + name_len + 4,
+                    4 + name_len + generation_len + 4,
                     data.len()
                 ),
             ));
@@ -188,7 +294,63 @@ impl ColumnFamilyMetadata {
             )
         })?;
 
-        let segment_count_start = 4 + name_len;
+        let generation = if generation_len > 0 {
+            u64::from_le_bytes(data[4 + name_len..4 + name_len + 8].try_into().unwrap())
+        } else {
+            0
+        };
+
+        let mut offset = 4 + name_len + generation_len;
+        let (created_at_ms, user_metadata) = if version >= 4 {
+            if data.len() < offset + 10 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "insufficient data for column family creation time",
+                ));
+            }
+            let created_at_ms = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            let user_metadata_len =
+                u16::from_le_bytes(data[offset + 8..offset + 10].try_into().unwrap()) as usize;
+            offset += 10;
+            if data.len() < offset + user_metadata_len + 4 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "insufficient data for column family user metadata",
+                ));
+            }
+            let user_metadata = data[offset..offset + user_metadata_len].to_vec();
+            offset += user_metadata_len;
+            (created_at_ms, user_metadata)
+        } else {
+            (0, Vec::new())
+        };
+
+        let encrypted = if version >= 6 {
+            let flags = *data.get(offset).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "insufficient data for column family flags",
+                )
+            })?;
+            if flags > 1 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid column family flags: {flags:#x}"),
+                ));
+            }
+            offset += 1;
+            flags == 1
+        } else {
+            false
+        };
+        if data.len() < offset + 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "insufficient data for segment count",
+            ));
+        }
+
+        let segment_count_start = offset;
         let segment_count = u32::from_le_bytes(
             data[segment_count_start..segment_count_start + 4]
                 .try_into()
@@ -206,15 +368,27 @@ impl ColumnFamilyMetadata {
 
         let bytes_consumed = offset;
 
-        Ok((Self { name, segments }, bytes_consumed))
+        Ok((
+            Self {
+                name,
+                generation,
+                created_at_ms,
+                user_metadata,
+                encrypted,
+                segments,
+            },
+            bytes_consumed,
+        ))
     }
 }
 
 /// Master header describing the layout of all column families within a database file.
 ///
-/// The master header occupies the first page (4KB) of the file and contains metadata
-/// about all column families including their names and segments, plus a free list
-/// for deleted/reclaimed space.
+/// The master header starts in one of the first two pages (4KB each) of the file and contains
+/// metadata about all column families including their names and segments, plus a free list
+/// for deleted/reclaimed space. A header too large for its page continues in
+/// [extension segments](Self::extension_segments) allocated from the file like any other
+/// segment.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MasterHeader {
     /// Version of the header format.
@@ -223,6 +397,57 @@ pub struct MasterHeader {
     pub column_families: Vec<ColumnFamilyMetadata>,
     /// Free segments available for reuse.
     pub free_segments: Vec<FreeSegment>,
+    /// Generation to assign to the next column family that is created.
+    pub next_generation: u64,
+    /// Segments reserved for the part of the header that does not fit in the first page.
+    ///
+    /// There are either none or two. Each write of an overflowing header goes to the
+    /// extension that does not hold the current one, and only then is the first page updated
+    /// to point at it, so a crash during the write leaves the previous header intact.
+    pub extension_segments: Vec<Segment>,
+    /// Index into [`extension_segments`](Self::extension_segments) of the extension holding
+    /// the header, when it does not fit in the first page.
+    pub active_extension: usize,
+    /// Number of times the header has been written in the current format.
+    ///
+    /// Odd generations are written to the first page of the file and even ones to the second,
+    /// so each write leaves the previous generation intact. A header that was never written,
+    /// or was read from a file from before version 7, has generation 0.
+    pub header_generation: u64,
+}
+
+/// Number of header extensions that are written alternately.
+const EXTENSION_COUNT: usize = 2;
+
+/// Location of the header body, as recorded in the first page.
+struct FirstPage {
+    version: u8,
+    header_generation: u64,
+    extension_segments: Vec<Segment>,
+    active_extension: usize,
+    body_start: usize,
+    body_len: usize,
+    body_crc: u32,
+}
+
+impl FirstPage {
+    /// Returns the extension the body is stored in, or `None` if it is in the first page.
+    fn extension(&self) -> io::Result<Option<&Segment>> {
+        if self.body_start + self.body_len <= PAGE_SIZE - 4 {
+            return Ok(None);
+        }
+
+        match self.extension_segments.get(self.active_extension) {
+            Some(extension) if extension.size >= self.body_len as u64 => Ok(Some(extension)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "header body of {} bytes has no header extension large enough to hold it",
+                    self.body_len
+                ),
+            )),
+        }
+    }
 }
 
 impl MasterHeader {
@@ -232,22 +457,44 @@ impl MasterHeader {
             version: FORMAT_VERSION,
             column_families: Vec::new(),
             free_segments: Vec::new(),
+            next_generation: 1,
+            extension_segments: Vec::new(),
+            active_extension: 0,
+            header_generation: 0,
         }
     }
 
     /// Creates a master header with the given column families.
     pub fn with_column_families(column_families: Vec<ColumnFamilyMetadata>) -> Self {
+        let next_generation = column_families
+            .iter()
+            .map(|cf| cf.generation + 1)
+            .max()
+            .unwrap_or(1);
+
         Self {
             version: FORMAT_VERSION,
             column_families,
             free_segments: Vec::new(),
+            next_generation,
+            extension_segments: Vec::new(),
+            active_extension: 0,
+            header_generation: 0,
         }
     }
 
+    /// Returns a fresh column family generation and advances the counter.
+    pub fn allocate_generation(&mut self) -> u64 {
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        generation
+    }
+
     /// Finds the end of the last allocated segment in the file.
-    /// Returns `PAGE_SIZE` if no segments exist (first allocation starts after header).
+    /// Returns the size of the two header slots if no segments exist (first allocation starts
+    /// after them).
     pub fn end_of_file(&self) -> u64 {
-        let mut max_end = PAGE_SIZE as u64;
+        let mut max_end = HEADER_SIZE;
 
         for cf in &self.column_families {
             for segment in &cf.segments {
@@ -259,30 +506,81 @@ impl MasterHeader {
             max_end = max_end.max(free_seg.offset + free_seg.size);
         }
 
+        for extension in &self.extension_segments {
+            max_end = max_end.max(extension.end());
+        }
+
         max_end
     }
 
-    /// Serializes the master header to bytes that fit within one page.
+    /// Allocates a page-aligned segment of `size` bytes.
     ///
-    /// Format:
-    /// - magic (9 bytes)
-    /// - version (1 byte)
-    /// - `cf_count` (u32)
-    /// - metadata entries (variable)
-    /// - `free_count` (u32)
-    /// - free segment entries (variable)
-    /// - CRC32 checksum (4 bytes) at `PAGE_SIZE - 4`
-    /// - padding to page size
+    /// The smallest free segment that fits is reused, and whatever it has left over stays on
+    /// the free list. The file only grows when no free segment is large enough.
+    pub fn allocate_segment(&mut self, size: u64) -> Segment {
+        let reserved = round_up_to_page(size);
+        let best_fit = self
+            .free_segments
+            .iter()
+            .enumerate()
+            .filter(|(_, free)| free.size >= reserved)
+            .min_by_key(|(_, free)| free.size)
+            .map(|(idx, _)| idx);
+
+        let Some(idx) = best_fit else {
+            return Segment::new(round_up_to_page(self.end_of_file()), size);
+        };
+
+        let free = &mut self.free_segments[idx];
+        let offset = free.offset;
+        if free.size == reserved {
+            self.free_segments.remove(idx);
+        } else {
+            free.offset += reserved;
+            free.size -= reserved;
+        }
+        Segment::new(offset, size)
+    }
+
+    /// Returns the space of a segment that is no longer used to the free list.
     ///
-    /// Returns error if serialized size exceeds `PAGE_SIZE - 4` (need space for CRC).
-    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
-        let mut bytes = Vec::with_capacity(PAGE_SIZE);
+    /// The free list is kept sorted by offset, with adjacent free segments merged into one.
+    /// Free space at the end of the file is dropped from the list instead, so that
+    /// [`end_of_file`](Self::end_of_file) shrinks and the file can be truncated to it.
+    pub fn free_segment(&mut self, segment: &Segment) {
+        // Segments start on page boundaries, so the rest of a segment's last page is unused
+        self.free_segments.push(FreeSegment::new(
+            segment.offset,
+            round_up_to_page(segment.size),
+        ));
+        self.free_segments.sort_by_key(|free| free.offset);
 
-        // Magic number
-        bytes.extend_from_slice(&MAGIC_NUMBER);
+        let mut merged: Vec<FreeSegment> = Vec::with_capacity(self.free_segments.len());
+        for free in self.free_segments.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.offset + last.size >= free.offset => {
+                    last.size = last.size.max(free.offset + free.size - last.offset);
+                }
+                _ => merged.push(free),
+            }
+        }
 
-        // Version
-        bytes.push(self.version);
+        let data_end = self
+            .column_families
+            .iter()
+            .flat_map(|cf| &cf.segments)
+            .chain(&self.extension_segments)
+            .map(|segment| round_up_to_page(segment.end()))
+            .max()
+            .unwrap_or(0);
+        merged.retain(|free| free.offset < data_end);
+        self.free_segments = merged;
+    }
+
+    /// Serializes everything but the first page's fixed fields: column families, free
+    /// segments and the generation counter.
+    fn body_to_bytes(&self, version: u8) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PAGE_SIZE);
 
         // Column family count
         let cf_count = u32::try_from(self.column_families.len()).expect("too many column families");
@@ -290,7 +588,7 @@ impl MasterHeader {
 
         // Serialize each column family metadata
         for cf in &self.column_families {
-            bytes.extend_from_slice(&cf.to_bytes());
+            bytes.extend_from_slice(&cf.to_bytes(version));
         }
 
         // Free segment count
@@ -302,16 +600,89 @@ impl MasterHeader {
             bytes.extend_from_slice(&free_seg.to_bytes());
         }
 
-        // Check size constraint (reserve 4 bytes for CRC at the end)
-        if bytes.len() > PAGE_SIZE - 4 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!(
-                    "master header size ({} bytes) exceeds page size minus CRC ({} bytes)",
-                    bytes.len(),
-                    PAGE_SIZE - 4
-                ),
-            ));
+        // Generation counter
+        bytes.extend_from_slice(&self.next_generation.to_le_bytes());
+
+        bytes
+    }
+
+    /// Returns the offset of the body in the first page, after the fixed fields.
+    fn body_start(&self) -> usize {
+        // magic | version | header generation | extension count | extensions |
+        // active extension | body length | body checksum
+        MAGIC_NUMBER.len() + 1 + 8 + 4 + self.extension_segments.len() * 16 + 1 + 4 + 4
+    }
+
+    /// Serializes the master header, as stored in its header slot.
+    ///
+    /// The first page has this format:
+    /// - magic (9 bytes)
+    /// - version (1 byte)
+    /// - `header_generation` (u64)
+    /// - `extension_count` (u32)
+    /// - extension segments (`offset` (u64) | `size` (u64) each)
+    /// - `active_extension` (u8)
+    /// - `body_len` (u32)
+    /// - CRC32 checksum of the body (u32)
+    /// - the body, if it fits in the first page
+    /// - padding, then a CRC32 checksum of the page (4 bytes) at `PAGE_SIZE - 4`
+    ///
+    /// The body holds `cf_count` (u32), the metadata entries, `free_count` (u32), the free
+    /// segment entries and `next_generation` (u64). If it does not fit in the first page, it
+    /// follows the first page in the returned bytes, padded to whole pages, and is stored in
+    /// the active extension segment.
+    ///
+    /// Returns an error if the body neither fits in the first page nor in the active extension.
+    /// [`write_to`](Self::write_to) reserves extensions as needed.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let body = self.body_to_bytes(FORMAT_VERSION);
+        let body_start = self.body_start();
+        let inline = body_start + body.len() <= PAGE_SIZE - 4;
+        if !inline {
+            let capacity = self
+                .extension_segments
+                .get(self.active_extension)
+                .map_or(0, |extension| extension.size);
+            if (body.len() as u64) > capacity {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "master header body ({} bytes) exceeds both the first page ({} bytes) and the header extension ({capacity} bytes)",
+                        body.len(),
+                        PAGE_SIZE - 4 - body_start
+                    ),
+                ));
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(PAGE_SIZE);
+
+        // Magic number
+        bytes.extend_from_slice(&MAGIC_NUMBER);
+
+        // Version
+        bytes.push(self.version);
+
+        // Header generation
+        bytes.extend_from_slice(&self.header_generation.to_le_bytes());
+
+        // Header extensions
+        let extension_count =
+            u32::try_from(self.extension_segments.len()).expect("too many header extensions");
+        bytes.extend_from_slice(&extension_count.to_le_bytes());
+        for extension in &self.extension_segments {
+            bytes.extend_from_slice(&extension.to_bytes());
+        }
+        bytes.push(u8::try_from(self.active_extension).expect("invalid active header extension"));
+
+        // Body length and checksum
+        let body_len = u32::try_from(body.len()).expect("master header body too large");
+        bytes.extend_from_slice(&body_len.to_le_bytes());
+        bytes.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+        debug_assert_eq!(bytes.len(), body_start);
+
+        if inline {
+            bytes.extend_from_slice(&body);
         }
 
         // Pad to PAGE_SIZE - 4 with zeros (leaving space for CRC)
@@ -319,19 +690,22 @@ impl MasterHeader {
 
         // Compute CRC32 over all data before the checksum
         let crc = crc32fast::hash(&bytes);
-        
+
         // Append CRC32 at the end
         bytes.extend_from_slice(&crc.to_le_bytes());
 
         assert_eq!(bytes.len(), PAGE_SIZE);
 
+        if !inline {
+            bytes.extend_from_slice(&body);
+            bytes.resize(PAGE_SIZE + body.len().div_ceil(PAGE_SIZE) * PAGE_SIZE, 0);
+        }
+
         Ok(bytes)
     }
 
-    /// Deserializes a master header from bytes.
-    ///
-    /// Validates magic number, CRC32 checksum, version, and metadata integrity.
-    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+    /// Validates the first page of a serialized header and reads its fixed fields.
+    fn parse_first_page(data: &[u8]) -> io::Result<FirstPage> {
         if data.len() < PAGE_SIZE {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -355,54 +729,173 @@ impl MasterHeader {
         if stored_crc != computed_crc {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
-                format!("header checksum mismatch: expected {stored_crc:#x}, got {computed_crc:#x}"),
+                format!(
+                    "header checksum mismatch: expected {stored_crc:#x}, got {computed_crc:#x}"
+                ),
             ));
         }
 
         // Check version
         let version = data[9];
-        if version != FORMAT_VERSION {
+        if !(MIN_READABLE_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("unsupported format version: {version}"),
             ));
         }
 
+        // Before version 5 the body fills the rest of the first page
+        if version < 5 {
+            return Ok(FirstPage {
+                version,
+                header_generation: 0,
+                extension_segments: Vec::new(),
+                active_extension: 0,
+                body_start: 10,
+                body_len: PAGE_SIZE - 4 - 10,
+                body_crc: crc32fast::hash(&data[10..PAGE_SIZE - 4]),
+            });
+        }
+
+        // Before version 7 there is a single header slot
+        let (header_generation, mut offset) = if version >= 7 {
+            (u64::from_le_bytes(data[10..18].try_into().unwrap()), 18)
+        } else {
+            (0, 10)
+        };
+
+        let extension_count =
+            u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        if extension_count > EXTENSION_COUNT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid header extension count: {extension_count}"),
+            ));
+        }
+
+        offset += 4;
+        let mut extension_segments = Vec::with_capacity(extension_count);
+        for _ in 0..extension_count {
+            let (extension, consumed) = Segment::from_bytes(&data[offset..])?;
+            extension_segments.push(extension);
+            offset += consumed;
+        }
+
+        let active_extension = usize::from(data[offset]);
+        let body_len =
+            u32::from_le_bytes(data[offset + 1..offset + 5].try_into().unwrap()) as usize;
+        let body_crc = u32::from_le_bytes(data[offset + 5..offset + 9].try_into().unwrap());
+
+        Ok(FirstPage {
+            version,
+            header_generation,
+            extension_segments,
+            active_extension,
+            body_start: offset + 9,
+            body_len,
+            body_crc,
+        })
+    }
+
+    /// Deserializes a master header from bytes.
+    ///
+    /// `data` is the output of [`to_bytes`](Self::to_bytes): the page of one header slot,
+    /// followed by the contents of the active header extension if the header does not fit in
+    /// that page. [`read_from`](Self::read_from) reads a header directly from storage, picking
+    /// the newest valid generation of the two slots.
+    ///
+    /// Validates magic number, CRC32 checksums, version, and metadata integrity.
+    /// Headers from version 2 onwards are accepted and upgraded to the current format in memory.
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let first_page = Self::parse_first_page(data)?;
+
+        let body = if first_page.extension()?.is_some() {
+            data.get(PAGE_SIZE..PAGE_SIZE + first_page.body_len)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "insufficient data for header extension: need {}, have {}",
+                            first_page.body_len,
+                            data.len() - PAGE_SIZE
+                        ),
+                    )
+                })?
+        } else {
+            &data[first_page.body_start..first_page.body_start + first_page.body_len]
+        };
+
+        let computed_crc = crc32fast::hash(body);
+        if computed_crc != first_page.body_crc {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "header body checksum mismatch: expected {:#x}, got {computed_crc:#x}",
+                    first_page.body_crc
+                ),
+            ));
+        }
+
+        let version = first_page.version;
+
         // Read column family count
-        let cf_count = u32::from_le_bytes(data[10..14].try_into().unwrap()) as usize;
+        if body.len() < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "insufficient data for column family count",
+            ));
+        }
+        let cf_count = u32::from_le_bytes(body[0..4].try_into().unwrap()) as usize;
 
         // Deserialize column family metadata entries
-        let mut column_families = Vec::with_capacity(cf_count);
-        let mut offset = 14;
+        let mut column_families = Vec::with_capacity(cf_count.min(body.len()));
+        let mut offset = 4;
 
         for _ in 0..cf_count {
-            let (cf_meta, consumed) = ColumnFamilyMetadata::from_bytes(&data[offset..])?;
+            let (cf_meta, consumed) = ColumnFamilyMetadata::from_bytes(&body[offset..], version)?;
             column_families.push(cf_meta);
             offset += consumed;
         }
 
         // Read free segment count
-        if offset + 4 > data.len() {
+        if offset + 4 > body.len() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "insufficient data for free segment count",
             ));
         }
-        let free_count = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        let free_count = u32::from_le_bytes(body[offset..offset + 4].try_into().unwrap()) as usize;
         offset += 4;
 
         // Deserialize free segments
-        let mut free_segments = Vec::with_capacity(free_count);
+        let mut free_segments = Vec::with_capacity(free_count.min(body.len()));
         for _ in 0..free_count {
-            let (free_seg, consumed) = FreeSegment::from_bytes(&data[offset..])?;
+            let (free_seg, consumed) = FreeSegment::from_bytes(&body[offset..])?;
             free_segments.push(free_seg);
             offset += consumed;
         }
 
+        // Read generation counter (absent before version 3)
+        let next_generation = if version >= 3 {
+            if offset + 8 > body.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "insufficient data for generation counter",
+                ));
+            }
+            u64::from_le_bytes(body[offset..offset + 8].try_into().unwrap())
+        } else {
+            1
+        };
+
         let header = Self {
-            version,
+            version: FORMAT_VERSION,
             column_families,
             free_segments,
+            next_generation,
+            extension_segments: first_page.extension_segments,
+            active_extension: first_page.active_extension,
+            header_generation: first_page.header_generation,
         };
 
         // Validate the header
@@ -411,10 +904,251 @@ impl MasterHeader {
         Ok(header)
     }
 
+    /// Reads the master header from the start of `backend`, including its extension.
+    ///
+    /// Both header slots are read, and the valid one with the highest generation is returned.
+    /// If the newest slot was torn by a crash while it was written, or is otherwise corrupted,
+    /// the previous generation is returned and a warning is logged. In a file from before
+    /// version 7 the second page is not a header slot, and is only read as one once the first
+    /// write of the upgraded header has reached it.
+    pub fn read_from(backend: &dyn StorageBackend) -> io::Result<Self> {
+        let first = Self::read_slot(backend, 0);
+        if backend.len()? < HEADER_SIZE {
+            return first;
+        }
+
+        // The second slot is blank if the first write of the header did not get to fill it
+        let mut page = vec![0u8; PAGE_SIZE];
+        backend.read(PAGE_SIZE as u64, &mut page)?;
+        if page.iter().all(|&byte| byte == 0) {
+            return first;
+        }
+        let second = Self::read_slot(backend, 1).and_then(|header| {
+            if header.header_generation == 0 {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "second header slot holds a header from before version 7",
+                ))
+            } else {
+                Ok(header)
+            }
+        });
+
+        let (header, invalid_slot, err) = match (first, second) {
+            (Ok(first), Ok(second)) => {
+                return Ok(if second.header_generation > first.header_generation {
+                    second
+                } else {
+                    first
+                });
+            }
+            // Until a file from before version 7 is upgraded, the second page holds other data
+            (Ok(first), Err(_)) if first.header_generation == 0 => return Ok(first),
+            (Err(err), Err(_)) => return Err(err),
+            (Ok(header), Err(err)) => (header, 1, err),
+            (Err(err), Ok(header)) => (header, 0, err),
+        };
+
+        #[cfg(feature = "logging")]
+        log::warn!(
+            "Master header slot {invalid_slot} is invalid ({err}); falling back to header generation {}",
+            header.header_generation
+        );
+        #[cfg(not(feature = "logging"))]
+        let _ = (invalid_slot, err);
+
+        Ok(header)
+    }
+
+    /// Reads the header in slot `slot`, including its extension.
+    fn read_slot(backend: &dyn StorageBackend, slot: usize) -> io::Result<Self> {
+        let mut data = vec![0u8; PAGE_SIZE];
+        backend.read((slot * PAGE_SIZE) as u64, &mut data)?;
+
+        let first_page = Self::parse_first_page(&data)?;
+        if let Some(extension) = first_page.extension()? {
+            let mut body = vec![0u8; first_page.body_len];
+            backend.read(extension.offset, &mut body)?;
+            data.extend_from_slice(&body);
+        }
+
+        Self::from_bytes(&data)
+    }
+
+    /// Writes the next generation of the master header to `backend` and syncs it.
+    ///
+    /// The header is written to the slot that does not hold the current generation, so a
+    /// crash during the write leaves the current generation to be read. A header that does
+    /// not fit in the first page is written to the header extension that is not in use first,
+    /// allocating or growing the extensions from the file as needed, and the slot is only
+    /// written once the extension is synced. The allocations are recorded in `self`, so
+    /// callers that write a modified copy of the header must publish the copy.
+    ///
+    /// The first write of a header fills both slots, starting with the second one. For a
+    /// header read from a file from before version 7, whatever occupies the page of the
+    /// second slot is moved first, and the single slot of that file is only overwritten once
+    /// the second slot holds the upgraded header. A crash before then leaves the header of
+    /// the older version to be read. Since the upgrade moves column family data, it must
+    /// happen before any column family is opened.
+    pub fn write_to(&mut self, backend: &dyn StorageBackend) -> io::Result<()> {
+        if self.header_generation > 0 {
+            return self.write_next_generation(backend);
+        }
+
+        self.vacate_second_slot(backend)?;
+        self.write_next_generation(backend)?;
+        self.write_next_generation(backend)
+    }
+
+    /// Writes the header as the next generation, leaving `self` at the current one if that
+    /// fails.
+    fn write_next_generation(&mut self, backend: &dyn StorageBackend) -> io::Result<()> {
+        let active_extension = self.active_extension;
+        self.header_generation += 1;
+        let result = self.write_slot(backend);
+        if result.is_err() {
+            // The write may have reached the slot and extension it was meant for, so the next
+            // attempt must target them again rather than those of the current generation
+            self.header_generation -= 1;
+            self.active_extension = active_extension;
+        }
+        result
+    }
+
+    /// Writes the header to the slot of its generation, preceded by its extension.
+    fn write_slot(&mut self, backend: &dyn StorageBackend) -> io::Result<()> {
+        self.reserve_extension();
+        let bytes = self.to_bytes()?;
+        let (first_page, extension_bytes) = bytes.split_at(PAGE_SIZE);
+
+        if backend.len()? < HEADER_SIZE {
+            // Only a file without any segments is this short
+            backend.set_len(HEADER_SIZE)?;
+        }
+
+        if !extension_bytes.is_empty() {
+            let extension = &self.extension_segments[self.active_extension];
+            // Column families only ever grow the file to the end of their own segments, all of
+            // which lie before the end of the file recorded in the header. Growing the file
+            // to that point can therefore not truncate one that grows concurrently.
+            if backend.len()? < extension.offset + extension_bytes.len() as u64 {
+                backend.set_len(self.end_of_file())?;
+            }
+            backend.write(extension.offset, extension_bytes)?;
+            backend.sync_data()?;
+        }
+
+        let slot = self.header_generation % HEADER_SLOTS as u64;
+        backend.write(slot * PAGE_SIZE as u64, first_page)?;
+        backend.sync_data()
+    }
+
+    /// Moves whatever occupies the page of the second header slot elsewhere in the file.
+    ///
+    /// Files from before version 7 allocate that page like any other. Column family data in
+    /// it is copied to a newly allocated page, which takes its place in the column family's
+    /// segments, and is synced before the header that refers to the copy is written. A free
+    /// segment or header extension that starts there is shortened by a page instead. The page
+    /// is then zeroed, so that it reads as a blank slot until a header is written to it.
+    fn vacate_second_slot(&mut self, backend: &dyn StorageBackend) -> io::Result<()> {
+        let slot = PAGE_SIZE as u64;
+
+        for free in &mut self.free_segments {
+            if free.offset == slot {
+                free.offset += slot;
+                free.size -= slot;
+            }
+        }
+        self.free_segments.retain(|free| free.size > 0);
+
+        for i in 0..self.extension_segments.len() {
+            let extension = &mut self.extension_segments[i];
+            if extension.offset != slot {
+                continue;
+            }
+            if extension.size > slot {
+                extension.offset += slot;
+                extension.size -= slot;
+            } else {
+                self.extension_segments[i] = self.allocate_segment(slot);
+            }
+        }
+
+        for cf_index in 0..self.column_families.len() {
+            let segments = &self.column_families[cf_index].segments;
+            let Some(index) = segments.iter().position(|segment| segment.offset == slot) else {
+                continue;
+            };
+            let segment = segments[index].clone();
+
+            let len = segment.size.min(slot);
+            let moved = self.allocate_segment(len);
+            let mut replacement = vec![moved.clone()];
+            if segment.size > slot {
+                replacement.push(Segment::new(HEADER_SIZE, segment.size - slot));
+            }
+            self.column_families[cf_index]
+                .segments
+                .splice(index..=index, replacement);
+
+            // The column family may not have written all of its first page
+            let mut data = vec![0u8; usize::try_from(len).unwrap()];
+            let available = backend.len()?.saturating_sub(slot).min(len);
+            backend.read(slot, &mut data[..usize::try_from(available).unwrap()])?;
+            if backend.len()? < moved.end() {
+                backend.set_len(self.end_of_file())?;
+            }
+            backend.write(moved.offset, &data)?;
+            backend.sync_data()?;
+        }
+
+        if backend.len()? < HEADER_SIZE {
+            // Only a file without any segments is this short
+            backend.set_len(HEADER_SIZE)?;
+        }
+        backend.write(slot, &[0u8; PAGE_SIZE])?;
+        backend.sync_data()
+    }
+
+    /// Makes room for the header body in a header extension if it does not fit in the first
+    /// page, and makes the extension that is not in use the active one.
+    fn reserve_extension(&mut self) {
+        if self.body_start() + self.body_to_bytes(FORMAT_VERSION).len() <= PAGE_SIZE - 4 {
+            return;
+        }
+
+        let target = if self.extension_segments.len() == EXTENSION_COUNT {
+            (self.active_extension + 1) % EXTENSION_COUNT
+        } else {
+            0
+        };
+
+        // Allocations change the free list and so the size of the body; extensions are
+        // allocated with room to spare, so this settles after a round or two
+        loop {
+            let body_len = self.body_to_bytes(FORMAT_VERSION).len() as u64;
+            let extension_size = round_up_to_page(body_len * 2);
+            if self.extension_segments.len() < EXTENSION_COUNT {
+                let extension = self.allocate_segment(extension_size);
+                self.extension_segments.push(extension);
+            } else if self.extension_segments[target].size < body_len {
+                let extension = self.allocate_segment(extension_size);
+                let old = std::mem::replace(&mut self.extension_segments[target], extension);
+                self.free_segment(&old);
+            } else {
+                break;
+            }
+        }
+
+        self.active_extension = target;
+    }
+
     /// Validates the master header for consistency.
     ///
     /// Checks:
     /// - Column family names are non-empty and unique
+    /// - User metadata is at most [`MAX_USER_METADATA_SIZE`] bytes
+    /// - There are no or two header extensions, and the active one exists
     /// - Segment offsets are page-aligned
     /// - Segment sizes are positive
     /// - No overlapping segments (CF or free)
@@ -436,6 +1170,17 @@ impl MasterHeader {
                 ));
             }
 
+            if cf.user_metadata.len() > MAX_USER_METADATA_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "column family '{}' has {} bytes of user metadata, more than the maximum of {MAX_USER_METADATA_SIZE}",
+                        cf.name,
+                        cf.user_metadata.len()
+                    ),
+                ));
+            }
+
             // Validate each segment
             for segment in &cf.segments {
                 // Offset should be page-aligned
@@ -497,7 +1242,43 @@ impl MasterHeader {
             })?;
         }
 
-        // Collect all segments (CF and free) and check for overlaps
+        // Validate header extensions
+        if !(self.extension_segments.is_empty() || self.extension_segments.len() == EXTENSION_COUNT)
+            || self.active_extension >= EXTENSION_COUNT
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid header extensions: {} extensions, extension {} active",
+                    self.extension_segments.len(),
+                    self.active_extension
+                ),
+            ));
+        }
+
+        for extension in &self.extension_segments {
+            if extension.offset % PAGE_SIZE as u64 != 0 || extension.size == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "header extension at offset {} with size {} is not a valid segment",
+                        extension.offset, extension.size
+                    ),
+                ));
+            }
+
+            extension
+                .offset
+                .checked_add(extension.size)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "header extension offset + size overflows",
+                    )
+                })?;
+        }
+
+        // Collect all segments (CF, free and header extensions) and check for overlaps
         let mut all_segments: Vec<(u64, u64, String)> = Vec::new();
 
         for cf in &self.column_families {
@@ -514,6 +1295,10 @@ impl MasterHeader {
             ));
         }
 
+        for (i, extension) in self.extension_segments.iter().enumerate() {
+            all_segments.push((extension.offset, extension.end(), format!("extension#{i}")));
+        }
+
         all_segments.sort_by_key(|(start, _, _)| *start);
 
         for i in 0..all_segments.len() {
@@ -544,6 +1329,7 @@ impl Default for MasterHeader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backends::InMemoryBackend;
 
     #[test]
     fn test_empty_header_round_trip() {
@@ -628,6 +1414,145 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_generation_round_trip() {
+        let cf = ColumnFamilyMetadata::new("jobs".to_string(), PAGE_SIZE as u64, 1024 * 1024)
+            .with_generation(7);
+        let mut header = MasterHeader::with_column_families(vec![cf]);
+        assert_eq!(header.next_generation, 8);
+        assert_eq!(header.allocate_generation(), 8);
+
+        let bytes = header.to_bytes().unwrap();
+        let decoded = MasterHeader::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.column_families[0].generation, 7);
+        assert_eq!(decoded.next_generation, 9);
+    }
+
+    #[test]
+    fn test_reads_version_2_header() {
+        // magic | version | cf_count | name_len | name | segment_count | segments | free_count
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC_NUMBER);
+        bytes.push(2);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(b"jobs");
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(PAGE_SIZE as u64).to_le_bytes());
+        bytes.extend_from_slice(&(1024 * 1024u64).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.resize(PAGE_SIZE - 4, 0);
+        let crc = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+
+        let decoded = MasterHeader::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.version, FORMAT_VERSION);
+        assert_eq!(decoded.column_families.len(), 1);
+        assert_eq!(decoded.column_families[0].name, "jobs");
+        assert_eq!(decoded.column_families[0].generation, 0);
+        assert_eq!(decoded.column_families[0].created_at_ms, 0);
+        assert!(decoded.column_families[0].user_metadata.is_empty());
+        assert_eq!(
+            decoded.column_families[0].segments,
+            vec![Segment::new(PAGE_SIZE as u64, 1024 * 1024)]
+        );
+        assert_eq!(decoded.next_generation, 1);
+    }
+
+    #[test]
+    fn test_reads_version_3_header() {
+        // magic | version | cf_count | name_len | name | generation | segment_count | segments
+        // | free_count | next_generation
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC_NUMBER);
+        bytes.push(3);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(b"jobs");
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(PAGE_SIZE as u64).to_le_bytes());
+        bytes.extend_from_slice(&(1024 * 1024u64).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&6u64.to_le_bytes());
+        bytes.resize(PAGE_SIZE - 4, 0);
+        let crc = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+
+        let decoded = MasterHeader::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.version, FORMAT_VERSION);
+        assert_eq!(decoded.column_families[0].generation, 5);
+        assert_eq!(decoded.column_families[0].created_at_ms, 0);
+        assert!(decoded.column_families[0].user_metadata.is_empty());
+        assert_eq!(
+            decoded.column_families[0].segments,
+            vec![Segment::new(PAGE_SIZE as u64, 1024 * 1024)]
+        );
+        assert_eq!(decoded.next_generation, 6);
+    }
+
+    #[test]
+    fn test_user_metadata_round_trip() {
+        let cf = ColumnFamilyMetadata::new("jobs".to_string(), PAGE_SIZE as u64, 1024 * 1024)
+            .with_created_at(1_700_000_000_000)
+            .with_user_metadata(b"schema=2".to_vec());
+        let empty = ColumnFamilyMetadata::new(
+            "logs".to_string(),
+            PAGE_SIZE as u64 + 1024 * 1024,
+            1024 * 1024,
+        )
+        .with_created_at(1);
+        let header = MasterHeader::with_column_families(vec![cf.clone(), empty.clone()]);
+
+        let decoded = MasterHeader::from_bytes(&header.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(decoded.column_families, vec![cf, empty]);
+    }
+
+    #[test]
+    fn test_encrypted_flag_round_trip() {
+        let encrypted =
+            ColumnFamilyMetadata::new("vault".to_string(), PAGE_SIZE as u64, 1024 * 1024)
+                .with_encrypted(true);
+        let plain = ColumnFamilyMetadata::new(
+            "logs".to_string(),
+            PAGE_SIZE as u64 + 1024 * 1024,
+            1024 * 1024,
+        );
+        let header = MasterHeader::with_column_families(vec![encrypted.clone(), plain.clone()]);
+
+        let decoded = MasterHeader::from_bytes(&header.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.column_families, vec![encrypted.clone(), plain]);
+
+        // Before version 6 there is no flags byte, and nothing is encrypted
+        let bytes = encrypted.to_bytes(5);
+        assert_eq!(bytes.len() + 1, encrypted.to_bytes(FORMAT_VERSION).len());
+        let (decoded, consumed) = ColumnFamilyMetadata::from_bytes(&bytes, 5).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert!(!decoded.encrypted);
+    }
+
+    #[test]
+    fn test_validate_user_metadata_size() {
+        let cf = ColumnFamilyMetadata::new("jobs".to_string(), PAGE_SIZE as u64, 1024)
+            .with_user_metadata(vec![0; MAX_USER_METADATA_SIZE]);
+        assert!(
+            MasterHeader::with_column_families(vec![cf.clone()])
+                .validate()
+                .is_ok()
+        );
+
+        let cf = cf.with_user_metadata(vec![0; MAX_USER_METADATA_SIZE + 1]);
+        assert!(
+            MasterHeader::with_column_families(vec![cf])
+                .validate()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_too_short_data() {
         let bytes = vec![0u8; 100];
@@ -712,22 +1637,299 @@ mod tests {
         let mut cfs = Vec::new();
         for i in 0..100_u64 {
             let name = format!("column_family_with_very_long_name_{i}");
-            let offset = PAGE_SIZE as u64 + (i * 1024 * 1024);
-            cfs.push(ColumnFamilyMetadata::new(name, offset, 1024 * 1024));
+            let offset = HEADER_SIZE + (i * 64 * 1024);
+            cfs.push(ColumnFamilyMetadata::new(name, offset, 64 * 1024));
         }
 
         let header = MasterHeader::with_column_families(cfs);
-        let result = header.to_bytes();
 
-        assert!(result.is_err());
+        // Without a header extension to continue in, the header cannot be serialized
+        assert!(header.to_bytes().is_err());
+
+        let backend = InMemoryBackend::new();
+        backend.set_len(PAGE_SIZE as u64).unwrap();
+        let mut written = header.clone();
+        written.write_to(&backend).unwrap();
+
+        assert_eq!(written.extension_segments.len(), EXTENSION_COUNT);
+        assert!(written.to_bytes().unwrap().len() > PAGE_SIZE);
+        assert!(backend.len().unwrap() >= written.end_of_file());
+        written.validate().unwrap();
+
+        let decoded = MasterHeader::read_from(&backend).unwrap();
+        assert_eq!(decoded, written);
+        assert_eq!(decoded.column_families, header.column_families);
+    }
+
+    #[test]
+    fn test_reads_version_4_header() {
+        let header = MasterHeader::with_column_families(vec![
+            ColumnFamilyMetadata::new("jobs".to_string(), PAGE_SIZE as u64, 1024 * 1024)
+                .with_user_metadata(b"v4".to_vec()),
+        ]);
+
+        // A version 4 header is a single page: magic | version | body | CRC
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC_NUMBER);
+        bytes.push(4);
+        bytes.extend_from_slice(&header.body_to_bytes(4));
+        bytes.resize(PAGE_SIZE - 4, 0);
+        let crc = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+
+        let decoded = MasterHeader::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.version, FORMAT_VERSION);
+        assert_eq!(decoded.column_families, header.column_families);
+        assert!(decoded.extension_segments.is_empty());
+
+        let backend = InMemoryBackend::new();
+        backend.set_len(PAGE_SIZE as u64).unwrap();
+        backend.write(0, &bytes).unwrap();
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), decoded);
+    }
+
+    #[test]
+    fn test_torn_extension_write_keeps_previous_header() {
+        let cfs = (0..100_u64)
+            .map(|i| {
+                ColumnFamilyMetadata::new(
+                    format!("column_family_with_very_long_name_{i}"),
+                    HEADER_SIZE + i * 64 * 1024,
+                    64 * 1024,
+                )
+            })
+            .collect();
+        let mut header = MasterHeader::with_column_families(cfs);
+
+        let backend = InMemoryBackend::new();
+        backend.set_len(PAGE_SIZE as u64).unwrap();
+        header.write_to(&backend).unwrap();
+
+        // Write only the extension of the next header, as if the process died before the
+        // first page was written
+        let mut next = header.clone();
+        next.column_families[0].user_metadata = b"never published".to_vec();
+        next.reserve_extension();
+        assert_ne!(next.active_extension, header.active_extension);
+        let bytes = next.to_bytes().unwrap();
+        let extension = &next.extension_segments[next.active_extension];
+        backend
+            .write(extension.offset, &bytes[PAGE_SIZE..])
+            .unwrap();
+
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), header);
+
+        next.write_to(&backend).unwrap();
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), next);
+    }
+
+    #[test]
+    fn test_torn_slot_falls_back_to_previous_generation() {
+        let backend = InMemoryBackend::new();
+        let mut header = MasterHeader::new();
+        header.write_to(&backend).unwrap();
+        // The first write fills both slots
+        assert_eq!(header.header_generation, 2);
+        assert_eq!(backend.len().unwrap(), HEADER_SIZE);
+
+        let mut next = header.clone();
+        next.column_families.push(ColumnFamilyMetadata::new(
+            "jobs".to_string(),
+            HEADER_SIZE,
+            1024 * 1024,
+        ));
+        next.write_to(&backend).unwrap();
+        assert_eq!(next.header_generation, 3);
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), next);
+
+        // Generation 3 went to the second slot; tear it as a crash during the write would
+        let torn = vec![0u8; PAGE_SIZE / 2];
+        backend
+            .write((PAGE_SIZE + PAGE_SIZE / 2) as u64, &torn)
+            .unwrap();
+        let recovered = MasterHeader::read_from(&backend).unwrap();
+        assert_eq!(recovered, header);
+
+        // Writing from the recovered header replaces the torn slot rather than the intact one
+        let mut rewritten = recovered.clone();
+        rewritten.next_generation = 5;
+        rewritten.write_to(&backend).unwrap();
+        assert_eq!(rewritten.header_generation, 3);
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), rewritten);
+
+        // Generation 4 goes to the first slot, and tearing it falls back to generation 3
+        let mut fourth = rewritten.clone();
+        fourth.next_generation = 6;
+        fourth.write_to(&backend).unwrap();
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), fourth);
+        backend.write((PAGE_SIZE / 2) as u64, &torn).unwrap();
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), rewritten);
+
+        // With both slots corrupted there is nothing to fall back to
+        backend
+            .write((PAGE_SIZE + PAGE_SIZE / 2) as u64, &torn)
+            .unwrap();
+        assert!(MasterHeader::read_from(&backend).is_err());
+    }
+
+    /// Serializes `header` as the single header slot of a version 6 file
+    fn version_6_page(header: &MasterHeader) -> Vec<u8> {
+        let body = header.body_to_bytes(6);
+        let mut page = Vec::new();
+        page.extend_from_slice(&MAGIC_NUMBER);
+        page.push(6);
+        page.extend_from_slice(&0u32.to_le_bytes());
+        page.push(0);
+        page.extend_from_slice(&u32::try_from(body.len()).unwrap().to_le_bytes());
+        page.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+        page.extend_from_slice(&body);
+        page.resize(PAGE_SIZE - 4, 0);
+        let crc = crc32fast::hash(&page);
+        page.extend_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    #[test]
+    fn test_upgrade_moves_data_out_of_second_slot() {
+        // A version 6 file with a column family in the page after its single header slot
+        let cf = ColumnFamilyMetadata::new("jobs".to_string(), PAGE_SIZE as u64, 3 * 4096);
+        let legacy = MasterHeader {
+            free_segments: vec![FreeSegment::new(4 * PAGE_SIZE as u64, 2 * PAGE_SIZE as u64)],
+            ..MasterHeader::with_column_families(vec![cf])
+        };
+        let page = version_6_page(&legacy);
+
+        let data: Vec<u8> = (0..3 * PAGE_SIZE)
+            .map(|i| u8::try_from(i % 251).unwrap())
+            .collect();
+        let backend = InMemoryBackend::new();
+        backend.set_len(6 * PAGE_SIZE as u64).unwrap();
+        backend.write(0, &page).unwrap();
+        backend.write(PAGE_SIZE as u64, &data).unwrap();
+
+        let mut header = MasterHeader::read_from(&backend).unwrap();
+        assert_eq!(header.header_generation, 0);
+        assert_eq!(header.column_families, legacy.column_families);
+
+        // The first page of the column family moves into the free segment, which keeps the
+        // rest of its space, and both slots are written
+        header.write_to(&backend).unwrap();
+        assert_eq!(header.header_generation, 2);
+        assert_eq!(
+            header.column_families[0].segments,
+            [
+                Segment::new(4 * PAGE_SIZE as u64, PAGE_SIZE as u64),
+                Segment::new(HEADER_SIZE, 2 * PAGE_SIZE as u64),
+            ]
+        );
+        assert_eq!(
+            header.free_segments,
+            [FreeSegment::new(5 * PAGE_SIZE as u64, PAGE_SIZE as u64)]
+        );
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), header);
+
+        // Later generations alternate between the slots without touching the column family
+        let mut next = header.clone();
+        next.column_families[0].user_metadata = b"upgraded".to_vec();
+        next.write_to(&backend).unwrap();
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), next);
+
+        let mut read = Vec::new();
+        for segment in &next.column_families[0].segments {
+            let mut bytes = vec![0u8; usize::try_from(segment.size).unwrap()];
+            backend.read(segment.offset, &mut bytes).unwrap();
+            read.extend_from_slice(&bytes);
+        }
+        assert_eq!(read, data);
+    }
+
+    #[test]
+    fn test_torn_upgrade_still_opens() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        /// Storage that writes half of the first header written to the slot at `tear_at` and
+        /// then fails, as a crash during the write would
+        #[derive(Debug)]
+        struct TornSlot {
+            inner: InMemoryBackend,
+            tear_at: u64,
+            torn: AtomicBool,
+        }
+
+        impl StorageBackend for TornSlot {
+            fn len(&self) -> io::Result<u64> {
+                self.inner.len()
+            }
+
+            fn read(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+                self.inner.read(offset, out)
+            }
+
+            fn set_len(&self, len: u64) -> io::Result<()> {
+                self.inner.set_len(len)
+            }
+
+            fn sync_data(&self) -> io::Result<()> {
+                self.inner.sync_data()
+            }
+
+            fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+                // The second slot is zeroed before the header is written to it
+                if offset == self.tear_at
+                    && data.iter().any(|&byte| byte != 0)
+                    && !self.torn.swap(true, Ordering::SeqCst)
+                {
+                    self.inner.write(offset, &data[..data.len() / 2])?;
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                self.inner.write(offset, data)
+            }
+        }
+
+        // A version 6 file with free space in the page after its single header slot
+        let cf = ColumnFamilyMetadata::new("jobs".to_string(), HEADER_SIZE, 2 * 4096);
+        let legacy = MasterHeader {
+            free_segments: vec![FreeSegment::new(PAGE_SIZE as u64, PAGE_SIZE as u64)],
+            ..MasterHeader::with_column_families(vec![cf])
+        };
+        let open = |tear_at: u64| {
+            let backend = TornSlot {
+                inner: InMemoryBackend::new(),
+                tear_at,
+                torn: AtomicBool::new(false),
+            };
+            backend.set_len(4 * PAGE_SIZE as u64).unwrap();
+            backend.inner.write(0, &version_6_page(&legacy)).unwrap();
+            let mut header = MasterHeader::read_from(&backend).unwrap();
+            assert!(header.write_to(&backend).is_err());
+            backend
+        };
+
+        // Torn while writing the first generation, the header of version 6 is still read
+        let backend = open(PAGE_SIZE as u64);
+        let mut header = MasterHeader::read_from(&backend).unwrap();
+        assert_eq!(header.header_generation, 0);
+        assert_eq!(header.column_families, legacy.column_families);
+
+        // and the upgrade can be retried
+        header.write_to(&backend).unwrap();
+        assert_eq!(header.header_generation, 2);
+        assert_eq!(MasterHeader::read_from(&backend).unwrap(), header);
+
+        // Torn while overwriting the single slot of version 6, the first generation is read
+        let backend = open(0);
+        let header = MasterHeader::read_from(&backend).unwrap();
+        assert_eq!(header.header_generation, 1);
+        assert_eq!(header.column_families, legacy.column_families);
+        assert!(header.free_segments.is_empty());
     }
 
     #[test]
     fn test_metadata_serialization() {
         let cf = ColumnFamilyMetadata::new("test".to_string(), 4096, 1024);
-        let bytes = cf.to_bytes();
+        let bytes = cf.to_bytes(FORMAT_VERSION);
 
-        let (decoded, consumed) = ColumnFamilyMetadata::from_bytes(&bytes).unwrap();
+        let (decoded, consumed) = ColumnFamilyMetadata::from_bytes(&bytes, FORMAT_VERSION).unwrap();
         assert_eq!(decoded, cf);
         assert_eq!(consumed, bytes.len());
     }
@@ -744,8 +1946,8 @@ mod tests {
         assert_eq!(cf.segments.len(), 3);
         assert_eq!(cf.total_size(), 1024 * 1024 + 512 * 1024 + 256 * 1024);
 
-        let bytes = cf.to_bytes();
-        let (decoded, _) = ColumnFamilyMetadata::from_bytes(&bytes).unwrap();
+        let bytes = cf.to_bytes(FORMAT_VERSION);
+        let (decoded, _) = ColumnFamilyMetadata::from_bytes(&bytes, FORMAT_VERSION).unwrap();
         assert_eq!(decoded.segments, segments);
     }
 
@@ -779,8 +1981,8 @@ mod tests {
     fn test_end_of_file_calculation() {
         let mut header = MasterHeader::new();
 
-        // Empty header should return PAGE_SIZE
-        assert_eq!(header.end_of_file(), PAGE_SIZE as u64);
+        // Empty header should return the end of both header slots
+        assert_eq!(header.end_of_file(), HEADER_SIZE);
 
         // Add a column family
         let cf1 = ColumnFamilyMetadata::new("users".to_string(), PAGE_SIZE as u64, 1024 * 1024);
@@ -847,6 +2049,73 @@ mod tests {
         assert!(header.validate().is_err());
     }
 
+    #[test]
+    fn test_free_segment_merges_adjacent_space() {
+        const MB: u64 = 1024 * 1024;
+        let start = HEADER_SIZE;
+        let mut header = MasterHeader::with_column_families(
+            (0..4)
+                .map(|i| ColumnFamilyMetadata::new(format!("cf{i}"), start + i * MB, MB))
+                .collect(),
+        );
+
+        let cf0 = header.column_families.remove(0);
+        header.free_segment(&cf0.segments[0]);
+        let cf2 = header.column_families.remove(1);
+        header.free_segment(&cf2.segments[0]);
+        assert_eq!(
+            header.free_segments,
+            vec![
+                FreeSegment::new(start, MB),
+                FreeSegment::new(start + 2 * MB, MB)
+            ]
+        );
+
+        // Freeing the space in between merges all three
+        let cf1 = header.column_families.remove(0);
+        header.free_segment(&cf1.segments[0]);
+        assert_eq!(header.free_segments, vec![FreeSegment::new(start, 3 * MB)]);
+        header.validate().unwrap();
+
+        // Free space at the end of the file is dropped
+        let cf3 = header.column_families.remove(0);
+        header.free_segment(&cf3.segments[0]);
+        assert!(header.free_segments.is_empty());
+        assert_eq!(header.end_of_file(), start);
+    }
+
+    #[test]
+    fn test_allocate_segment_reuses_free_space() {
+        const MB: u64 = 1024 * 1024;
+        let page = PAGE_SIZE as u64;
+        let mut header = MasterHeader::with_column_families(vec![ColumnFamilyMetadata::new(
+            "last".to_string(),
+            page + 3 * MB,
+            MB,
+        )]);
+        header.free_segments = vec![
+            FreeSegment::new(page, 2 * MB),
+            FreeSegment::new(page + 2 * MB, MB),
+        ];
+
+        // Best fit takes the smaller free segment whole
+        assert_eq!(header.allocate_segment(MB), Segment::new(page + 2 * MB, MB));
+        assert_eq!(header.free_segments, vec![FreeSegment::new(page, 2 * MB)]);
+
+        // A partial fit leaves a page-aligned remainder
+        assert_eq!(header.allocate_segment(100), Segment::new(page, 100));
+        assert_eq!(
+            header.free_segments,
+            vec![FreeSegment::new(2 * page, 2 * MB - page)]
+        );
+
+        // Nothing fits, so the segment goes at the end of the file
+        assert_eq!(
+            header.allocate_segment(4 * MB),
+            Segment::new(page + 4 * MB, 4 * MB)
+        );
+    }
+
     #[test]
     fn test_segment_end() {
         let segment = Segment::new(4096, 1024);
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+/// Receives events from a column family database, for export to a metrics system.
+///
+/// Set with [`ColumnFamilyDatabaseBuilder::metrics`](super::ColumnFamilyDatabaseBuilder::metrics).
+/// Every method has an empty default, so an implementation only overrides the events it
+/// records. Methods are called synchronously on the thread that caused the event, in the
+/// middle of commits and checkpoints, so they should return quickly and must not call back
+/// into the database. When no metrics are set, nothing is timed.
+///
+/// WAL fsync counts and checkpoint totals are also available by polling
+/// [`ColumnFamilyDatabase::wal_stats`](super::ColumnFamilyDatabase::wal_stats).
+pub trait DatabaseMetrics: Send + Sync {
+    /// Called when a write transaction on column family `cf` has committed.
+    ///
+    /// `wal_wait` is the time spent appending the transaction to the WAL and waiting for it
+    /// to be synced. It is zero for commits that bypass the WAL, and only covers the append
+    /// for commits with [`Durability::Eventual`](crate::Durability::Eventual). `total` is the
+    /// time taken by the whole call to `commit`, including `wal_wait` and any backpressure.
+    ///
+    /// Each member of a [`WriteGroup`](super::WriteGroup) reports the group's timings.
+    fn on_commit(&self, cf: &str, wal_wait: Duration, total: Duration) {
+        let _ = (cf, wal_wait, total);
+    }
+
+    /// Called when a checkpoint has applied `entries` WAL entries to their column families
+    /// and truncated the WAL, or retired the entries in it for a checkpoint of a single
+    /// column family, taking `duration` in total.
+    ///
+    /// Checkpoints that find nothing to apply are not reported.
+    fn on_checkpoint(&self, duration: Duration, entries: usize) {
+        let _ = (duration, entries);
+    }
+
+    /// Called when a segment of `size` bytes has been allocated to column family `cf`, either
+    /// when it is created or when it grows.
+    fn on_segment_allocated(&self, cf: &str, size: u64) {
+        let _ = (cf, size);
+    }
+}
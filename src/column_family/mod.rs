@@ -47,22 +47,54 @@
 //! });
 //! ```
 
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub(crate) mod async_api;
 pub(crate) mod builder;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod bulk_load;
 pub(crate) mod database;
+#[cfg(feature = "encryption")]
+pub(crate) mod encrypted_backend;
+pub(crate) mod encryption;
+pub(crate) mod export;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod file_handle_pool;
 pub(crate) mod header;
+pub(crate) mod metrics;
 pub(crate) mod partitioned_backend;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod redb_import;
 pub(crate) mod state;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod unlocked_backend;
 pub(crate) mod wal;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod write_group;
 
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub use async_api::AsyncWriteTransaction;
 #[cfg(not(target_arch = "wasm32"))]
 pub use builder::ColumnFamilyDatabaseBuilder;
-pub use database::{ColumnFamily, ColumnFamilyDatabase, ColumnFamilyError};
 #[cfg(not(target_arch = "wasm32"))]
-pub use file_handle_pool::FileHandlePool;
-pub use header::{ColumnFamilyMetadata, FORMAT_VERSION, MAGIC_NUMBER, MasterHeader};
+pub use bulk_load::{BulkLoadProgress, BulkLoader};
+#[cfg(not(target_arch = "wasm32"))]
+pub use database::BackupSummary;
+pub use database::{
+    ColumnFamily, ColumnFamilyDatabase, ColumnFamilyError, FragmentationStats, MaintenanceHint,
+    MaintenanceThresholds, TableInfo,
+};
+#[cfg(feature = "encryption")]
+pub use encrypted_backend::EncryptedStorageBackend;
+pub use encryption::{ENCRYPTION_KEY_SIZE, EncryptionKey, KeyProvider};
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_handle_pool::{FileHandlePool, FileHandlePoolStats};
+pub use header::{
+    ColumnFamilyMetadata, FORMAT_VERSION, MAGIC_NUMBER, MAX_USER_METADATA_SIZE, MasterHeader,
+};
+pub use metrics::DatabaseMetrics;
 pub use partitioned_backend::PartitionedStorageBackend;
-pub use wal::WALConfig;
+#[cfg(not(target_arch = "wasm32"))]
+pub use redb_import::{CopyProgress, RedbImportOptions, RedbImportProgress, RedbSource};
+pub use wal::{CheckpointReport, RecoveryProgress, WALConfig, WALStats};
+#[cfg(not(target_arch = "wasm32"))]
+pub use write_group::WriteGroup;
@@ -21,7 +21,8 @@ use std::sync::{Arc, Mutex, RwLock};
 ///
 /// When a write would exceed the total capacity of all segments, the backend can automatically
 /// request a new segment via the expansion callback. This makes growth transparent to the
-/// Database instance.
+/// Database instance. Reads never expand: reading past the end of the segments is an error
+/// naming the offset, the capacity and the [label](Self::with_label) of the partition.
 ///
 /// # Example
 ///
@@ -51,6 +52,8 @@ pub struct PartitionedStorageBackend {
     /// instances using the same underlying file to prevent race conditions during concurrent
     /// `set_len()` calls.
     file_growth_lock: Arc<Mutex<()>>,
+    /// Name of the partition, usually its column family, used in error messages.
+    label: Option<String>,
 }
 
 impl PartitionedStorageBackend {
@@ -79,6 +82,7 @@ impl PartitionedStorageBackend {
             )])),
             expansion_callback: None,
             file_growth_lock: Arc::new(Mutex::new(())),
+            label: None,
         }
     }
 
@@ -100,9 +104,30 @@ impl PartitionedStorageBackend {
             segments: Arc::new(RwLock::new(segments)),
             expansion_callback,
             file_growth_lock,
+            label: None,
         }
     }
 
+    /// Names the partition, usually after its column family, in the errors it returns.
+    #[must_use]
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Returns the error for an `access` that does not fit in the `total_size` bytes of the
+    /// segments.
+    fn out_of_bounds(&self, access: &str, total_size: u64) -> io::Error {
+        let partition = match &self.label {
+            Some(label) => format!("column family '{label}'"),
+            None => "partition".to_string(),
+        };
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{access} exceeds total size {total_size} of {partition}"),
+        )
+    }
+
     /// Returns the total size of all segments (virtual address space size).
     fn total_size(&self) -> u64 {
         let segments = self.segments.read().unwrap();
@@ -130,10 +155,20 @@ impl PartitionedStorageBackend {
             current_virtual = segment_end;
         }
 
-        Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!("virtual offset {virtual_offset} exceeds total size {current_virtual}"),
-        ))
+        Err(self.out_of_bounds(&format!("virtual offset {virtual_offset}"), current_virtual))
+    }
+
+    /// Adds a segment if the segments hold fewer than `len` bytes.
+    ///
+    /// The new segment is 10% larger than needed, and at least 1MB larger, to reduce frequent
+    /// small expansions.
+    fn ensure_capacity(&self, len: u64) -> io::Result<()> {
+        let current_total = self.total_size();
+        if len > current_total {
+            let needed = len - current_total;
+            self.try_expand(needed + (needed / 10).max(1024 * 1024))?;
+        }
+        Ok(())
     }
 
     /// Attempts to expand the partition by requesting a new segment.
@@ -189,6 +224,12 @@ impl StorageBackend for PartitionedStorageBackend {
     }
 
     fn read(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        let total_size = self.total_size();
+        if offset.saturating_add(out.len() as u64) > total_size {
+            let access = format!("read of {} bytes at virtual offset {offset}", out.len());
+            return Err(self.out_of_bounds(&access, total_size));
+        }
+
         let mut bytes_read = 0;
         let mut current_offset = offset;
 
@@ -212,17 +253,28 @@ impl StorageBackend for PartitionedStorageBackend {
         Ok(())
     }
 
-    fn set_len(&self, len: u64) -> io::Result<()> {
-        let current_total = self.total_size();
+    fn prefetch(&self, offset: u64, len: u64) -> io::Result<()> {
+        let end = offset.saturating_add(len);
+        let mut current_offset = offset;
 
-        // If requested length exceeds current capacity, try to expand
-        if len > current_total {
-            let needed = len - current_total;
-            // Add 10% buffer to reduce frequent small expansions
-            let allocation_size = needed + (needed / 10).max(1024 * 1024); // At least 1MB buffer
-            self.try_expand(allocation_size)?;
+        // A virtual range may span several segments, each prefetched at its physical location
+        while current_offset < end {
+            let (physical_offset, remaining_in_segment) =
+                self.virtual_to_physical(current_offset)?;
+            let bytes_to_prefetch = (end - current_offset).min(remaining_in_segment);
+
+            self.inner.prefetch(physical_offset, bytes_to_prefetch)?;
+
+            current_offset += bytes_to_prefetch;
         }
 
+        Ok(())
+    }
+
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        // If requested length exceeds current capacity, try to expand
+        self.ensure_capacity(len)?;
+
         // CRITICAL SECTION: Calculate physical size and grow file atomically
         // Calculate the maximum physical end we need to allocate
         let max_physical_end = {
@@ -266,6 +318,16 @@ impl StorageBackend for PartitionedStorageBackend {
     }
 
     fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+        // A write past the end of the segments grows the partition as `set_len` would
+        let total_size = self.total_size();
+        if offset.saturating_add(data.len() as u64) > total_size {
+            if self.expansion_callback.is_none() {
+                let access = format!("write of {} bytes at virtual offset {offset}", data.len());
+                return Err(self.out_of_bounds(&access, total_size));
+            }
+            self.set_len(offset.saturating_add(data.len() as u64))?;
+        }
+
         let mut bytes_written = 0;
         let mut current_offset = offset;
 
@@ -398,6 +460,75 @@ mod tests {
         assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
     }
 
+    #[test]
+    fn test_read_error_names_offset_size_and_label() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let backend = PartitionedStorageBackend::new(inner, 1000, 5000).with_label("users");
+        backend.set_len(5000).unwrap();
+
+        let mut buf = vec![0u8; 100];
+        let err = backend.read(4950, &mut buf).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let message = err.to_string();
+        assert!(message.contains("virtual offset 4950"), "{message}");
+        assert!(message.contains("total size 5000"), "{message}");
+        assert!(message.contains("column family 'users'"), "{message}");
+    }
+
+    #[test]
+    fn test_write_expands_through_callback() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let callback_requested = Arc::clone(&requested);
+        let callback: Arc<dyn Fn(u64) -> io::Result<Segment> + Send + Sync> =
+            Arc::new(move |size| {
+                callback_requested.lock().unwrap().push(size);
+                Ok(Segment::new(10_000, size))
+            });
+        let backend = PartitionedStorageBackend::with_segments(
+            inner.clone(),
+            vec![Segment::new(1000, 1000)],
+            Some(callback),
+            Arc::new(Mutex::new(())),
+        );
+
+        // The write spans the end of the only segment, 10 bytes past its capacity
+        let data = [7u8; 20];
+        backend.write(990, &data).unwrap();
+
+        assert_eq!(*requested.lock().unwrap(), vec![10 + 1024 * 1024]);
+        assert_eq!(backend.total_size(), 1000 + 10 + 1024 * 1024);
+
+        let mut read_buf = [0u8; 20];
+        backend.read(990, &mut read_buf).unwrap();
+        assert_eq!(read_buf, data);
+
+        // The bytes past the first segment land at the start of the new one
+        let mut verify = [0u8; 10];
+        inner.read(10_000, &mut verify).unwrap();
+        assert_eq!(verify, [7u8; 10]);
+    }
+
+    #[test]
+    fn test_write_expansion_failure_propagates() {
+        let inner = Arc::new(InMemoryBackend::new());
+        let callback: Arc<dyn Fn(u64) -> io::Result<Segment> + Send + Sync> =
+            Arc::new(|_| Err(io::Error::new(io::ErrorKind::StorageFull, "no space left")));
+        let backend = PartitionedStorageBackend::with_segments(
+            inner,
+            vec![Segment::new(1000, 1000)],
+            Some(callback),
+            Arc::new(Mutex::new(())),
+        );
+
+        let err = backend.write(990, &[0u8; 20]).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        assert_eq!(err.to_string(), "no space left");
+        assert_eq!(backend.total_size(), 1000);
+    }
+
     #[test]
     fn test_set_len_within_partition() {
         let inner = Arc::new(InMemoryBackend::new());
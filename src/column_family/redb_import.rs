@@ -0,0 +1,295 @@
+//! Importing a standalone redb database into a column family.
+//!
+//! The tables of the source database are copied as their stored key and value bytes, so the
+//! imported tables can be opened with the same [`TableDefinition`](crate::TableDefinition)s
+//! that were used to write the source.
+
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::transactions::ReadTransaction;
+use crate::tree_store::InternalTableDefinition;
+use crate::{Builder, ReadOnlyDatabase, StorageBackend};
+
+use super::database::{ColumnFamily, ColumnFamilyError};
+use super::export::{storage_error, table_error};
+
+/// Default number of entries committed together by an import.
+pub(crate) const DEFAULT_BATCH_ENTRIES: u64 = 100_000;
+
+/// The redb database read by [`ColumnFamilyDatabase::import_redb`].
+///
+/// [`ColumnFamilyDatabase::import_redb`]: super::ColumnFamilyDatabase::import_redb
+pub enum RedbSource {
+    /// A database file, opened read-only.
+    Path(PathBuf),
+    /// A database stored in a custom backend. Nothing is written to the backend.
+    Backend(Box<dyn StorageBackend>),
+}
+
+impl RedbSource {
+    pub(crate) fn open(self) -> Result<ReadOnlyDatabase, ColumnFamilyError> {
+        let database = match self {
+            RedbSource::Path(path) => Builder::new().open_read_only(path),
+            RedbSource::Backend(backend) => {
+                Builder::new().open_read_only_with_boxed_backend(backend)
+            }
+        };
+        database.map_err(ColumnFamilyError::Database)
+    }
+}
+
+impl fmt::Debug for RedbSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedbSource::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            RedbSource::Backend(backend) => f.debug_tuple("Backend").field(backend).finish(),
+        }
+    }
+}
+
+impl From<&Path> for RedbSource {
+    fn from(path: &Path) -> Self {
+        RedbSource::Path(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for RedbSource {
+    fn from(path: PathBuf) -> Self {
+        RedbSource::Path(path)
+    }
+}
+
+impl From<&PathBuf> for RedbSource {
+    fn from(path: &PathBuf) -> Self {
+        RedbSource::Path(path.clone())
+    }
+}
+
+impl From<&str> for RedbSource {
+    fn from(path: &str) -> Self {
+        RedbSource::Path(path.into())
+    }
+}
+
+impl From<Box<dyn StorageBackend>> for RedbSource {
+    fn from(backend: Box<dyn StorageBackend>) -> Self {
+        RedbSource::Backend(backend)
+    }
+}
+
+/// Progress of a copy of tables into a column family, as reported after each committed
+/// transaction of [`ColumnFamilyDatabase::import_redb`] and
+/// [`ColumnFamilyDatabase::copy_column_family`].
+///
+/// [`ColumnFamilyDatabase::import_redb`]: super::ColumnFamilyDatabase::import_redb
+/// [`ColumnFamilyDatabase::copy_column_family`]: super::ColumnFamilyDatabase::copy_column_family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyProgress {
+    /// Number of tables committed so far.
+    pub tables: usize,
+    /// Number of tables in the source.
+    pub total_tables: usize,
+    /// Number of entries committed so far.
+    pub entries: u64,
+    /// Number of entries in the source.
+    pub total_entries: u64,
+    /// Time since the copy started.
+    pub elapsed: Duration,
+}
+
+/// Progress of a redb import, as reported by [`RedbImportOptions::on_progress`] and returned
+/// by [`ColumnFamilyDatabase::import_redb`].
+///
+/// [`ColumnFamilyDatabase::import_redb`]: super::ColumnFamilyDatabase::import_redb
+pub type RedbImportProgress = CopyProgress;
+
+/// Options for [`ColumnFamilyDatabase::import_redb`].
+///
+/// [`ColumnFamilyDatabase::import_redb`]: super::ColumnFamilyDatabase::import_redb
+pub struct RedbImportOptions<'a> {
+    overwrite: bool,
+    batch_entries: u64,
+    progress: Option<Box<dyn FnMut(RedbImportProgress) + 'a>>,
+}
+
+impl<'a> RedbImportOptions<'a> {
+    /// Creates the default options: fail if the column family exists, and commit about
+    /// 100,000 entries per transaction.
+    pub fn new() -> Self {
+        Self {
+            overwrite: false,
+            batch_entries: DEFAULT_BATCH_ENTRIES,
+            progress: None,
+        }
+    }
+
+    /// Sets whether an existing column family with the target name is replaced.
+    ///
+    /// The source is imported into a staging column family first, which replaces the existing
+    /// column family only once the import has succeeded, so a failed import leaves it as it
+    /// was. Encrypted column families cannot be overwritten this way and must be deleted
+    /// first.
+    ///
+    /// Default: false
+    #[must_use]
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Sets the number of entries after which the current transaction is committed.
+    ///
+    /// Tables are copied whole, so consecutive small tables share a transaction while a
+    /// table larger than `entries` gets one of its own.
+    ///
+    /// Default: 100,000
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entries` is zero.
+    #[must_use]
+    pub fn batch_entries(mut self, entries: u64) -> Self {
+        assert!(entries > 0, "batch_entries must be at least 1");
+        self.batch_entries = entries;
+        self
+    }
+
+    /// Sets a callback invoked after each committed transaction.
+    #[must_use]
+    pub fn on_progress(mut self, callback: impl FnMut(RedbImportProgress) + 'a) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    pub(crate) fn overwrites(&self) -> bool {
+        self.overwrite
+    }
+}
+
+impl Default for RedbImportOptions<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RedbImportOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedbImportOptions")
+            .field("overwrite", &self.overwrite)
+            .field("batch_entries", &self.batch_entries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the name of the column family an overwriting import into `cf_name` is staged in.
+pub(crate) fn staging_name(cf_name: &str) -> String {
+    format!("__manifold_import__/{cf_name}")
+}
+
+/// Copies every table of `source` into `cf` as configured by `options`.
+pub(crate) fn copy_tables(
+    source: &ReadTransaction,
+    cf: &ColumnFamily,
+    options: &mut RedbImportOptions<'_>,
+) -> Result<RedbImportProgress, ColumnFamilyError> {
+    copy_raw_tables(source, cf, options.batch_entries, |progress| {
+        if let Some(callback) = &mut options.progress {
+            callback(progress);
+        }
+    })
+}
+
+/// Copies every table of `source` into `cf`, preserving table names and stored bytes.
+///
+/// Tables are copied whole, committing once at least `max_batch_entries` entries were written,
+/// and `on_commit` is called after each commit.
+///
+/// Multimap tables store nested trees that cannot be copied as flat key/value pairs, so a
+/// source containing one is rejected before anything is written.
+pub(crate) fn copy_raw_tables(
+    source: &ReadTransaction,
+    cf: &ColumnFamily,
+    max_batch_entries: u64,
+    mut on_commit: impl FnMut(CopyProgress),
+) -> Result<CopyProgress, ColumnFamilyError> {
+    if let Some(table) = source.list_multimap_tables().map_err(storage_error)?.next() {
+        return Err(ColumnFamilyError::Io(io::Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "multimap table '{}' cannot be copied",
+                crate::MultimapTableHandle::name(&table)
+            ),
+        )));
+    }
+
+    let mut tables = vec![];
+    for handle in source.list_tables().map_err(storage_error)? {
+        let name = crate::TableHandle::name(&handle).to_string();
+        let (definition, tree) = source.open_raw_table(&name).map_err(table_error)?;
+        tables.push((name, definition, tree));
+    }
+
+    let started = Instant::now();
+    let mut progress = CopyProgress {
+        tables: 0,
+        total_tables: tables.len(),
+        entries: 0,
+        total_entries: tables
+            .iter()
+            .map(|(_, definition, _)| match definition {
+                InternalTableDefinition::Normal { table_length, .. } => *table_length,
+                InternalTableDefinition::Multimap { .. } => unreachable!(),
+            })
+            .sum(),
+        elapsed: Duration::ZERO,
+    };
+
+    let mut tables = tables.into_iter().peekable();
+    while tables.peek().is_some() {
+        let txn = cf
+            .begin_write()
+            .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+        let mut batch_tables = 0;
+        let mut batch_entries = 0;
+        while batch_entries < max_batch_entries {
+            let Some((name, definition, tree)) = tables.next() else {
+                break;
+            };
+            let InternalTableDefinition::Normal {
+                table_length,
+                fixed_key_size,
+                fixed_value_size,
+                key_type,
+                value_type,
+                ..
+            } = definition
+            else {
+                unreachable!();
+            };
+            txn.create_raw_table(
+                &name,
+                key_type,
+                value_type,
+                fixed_key_size,
+                fixed_value_size,
+                |builder| tree.for_each_entry(|key, value| builder.push(key, value)),
+            )
+            .map_err(table_error)?;
+            batch_tables += 1;
+            batch_entries += table_length;
+        }
+        txn.commit()
+            .map_err(|e| ColumnFamilyError::Database(e.into_storage_error().into()))?;
+
+        progress.tables += batch_tables;
+        progress.entries += batch_entries;
+        progress.elapsed = started.elapsed();
+        on_commit(progress);
+    }
+
+    progress.elapsed = started.elapsed();
+    Ok(progress)
+}
@@ -1,16 +1,16 @@
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(any(target_arch = "wasm32", feature = "encryption"))]
 use crate::StorageBackend;
 use crate::{Database, DatabaseError};
 
+use super::encryption::Keyring;
 #[cfg(not(target_arch = "wasm32"))]
-use super::file_handle_pool::FileHandlePool;
+use super::file_handle_pool::BackendSource;
 use super::header::Segment;
 use super::partitioned_backend::PartitionedStorageBackend;
-#[cfg(not(target_arch = "wasm32"))]
-use std::path::Path;
 
 /// Internal state for a column family, supporting lazy Database initialization.
 ///
@@ -20,32 +20,112 @@ use std::path::Path;
 pub(crate) struct ColumnFamilyState {
     /// Name of this column family.
     pub name: String,
+    /// Generation of this column family, as recorded in the master header.
+    pub generation: u64,
     /// Segments that make up this column family's storage.
     pub segments: Arc<RwLock<Vec<Segment>>>,
     /// Lazily initialized Database instance.
     pub db: Arc<RwLock<Option<Arc<Database>>>>,
+    /// Keys of this column family, if it is encrypted.
+    pub keys: Option<Arc<Keyring>>,
+    /// Cache size of the Database instance, or `None` for the default.
+    pub cache_size: Option<usize>,
+    /// Whether the database has been closed, which is seen by every handle of this column
+    /// family.
+    pub closed: AtomicBool,
 }
 
 impl ColumnFamilyState {
     /// Creates a new column family state with no Database instance.
-    pub fn new(name: String, segments: Vec<Segment>) -> Self {
+    pub fn new(name: String, generation: u64, segments: Vec<Segment>) -> Self {
         Self {
             name,
+            generation,
             segments: Arc::new(RwLock::new(segments)),
             db: Arc::new(RwLock::new(None)),
+            keys: None,
+            cache_size: None,
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Marks this column family as encrypted with the keys in `keys`.
+    #[must_use]
+    pub fn with_keys(mut self, keys: Option<Arc<Keyring>>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Sets the cache size of the Database instance, in bytes.
+    #[must_use]
+    pub fn with_cache_size(mut self, cache_size: Option<usize>) -> Self {
+        self.cache_size = cache_size;
+        self
+    }
+
+    /// Returns how many bytes of data fit in `segments`, which takes the space used by
+    /// encryption into account.
+    #[cfg_attr(not(feature = "encryption"), expect(clippy::unused_self))]
+    pub fn capacity(&self, segments: &[Segment]) -> u64 {
+        let size: u64 = segments.iter().map(|segment| segment.size).sum();
+        #[cfg(feature = "encryption")]
+        if self.keys.is_some() {
+            return super::encrypted_backend::encrypted_capacity(size);
+        }
+        size
+    }
+
+    /// Checks that the keys of this encrypted column family decrypt its first block, which
+    /// holds the database header.
+    #[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+    pub fn verify_key(&self, backend: PartitionedStorageBackend) -> io::Result<()> {
+        let Some(keys) = &self.keys else {
+            return Ok(());
+        };
+        let backend = super::encrypted_backend::EncryptedStorageBackend::new(
+            backend,
+            self.name.clone(),
+            self.generation,
+            Arc::clone(keys) as Arc<dyn super::encryption::KeyProvider>,
+        );
+        if backend.len()? == 0 {
+            return Ok(());
+        }
+        backend.read(0, &mut [0; 1])
+    }
+
+    /// Opens the Database stored in `backend` with the cache size of this column family,
+    /// decrypting it if this column family is encrypted.
+    pub fn create_database(
+        &self,
+        backend: PartitionedStorageBackend,
+    ) -> Result<Database, DatabaseError> {
+        let mut builder = Database::builder();
+        if let Some(cache_size) = self.cache_size {
+            builder.set_cache_size(cache_size);
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(keys) = &self.keys {
+            let backend = super::encrypted_backend::EncryptedStorageBackend::new(
+                backend,
+                self.name.clone(),
+                self.generation,
+                Arc::clone(keys) as Arc<dyn super::encryption::KeyProvider>,
+            );
+            return builder.create_with_backend(backend);
         }
+        builder.create_with_backend(backend)
     }
 
     /// Ensures the Database instance exists, creating it if necessary (native platforms).
     ///
-    /// This acquires a file handle from the pool and initializes the Database
+    /// This acquires a backend from the source and initializes the Database
     /// on first call. Subsequent calls reuse the cached instance and touch the
-    /// pool to prevent eviction.
+    /// source to prevent eviction from the pool.
     ///
     /// # Arguments
     ///
-    /// * `pool` - File handle pool to acquire backend from
-    /// * `path` - Path to database file
+    /// * `source` - Source to acquire the backend from
     /// * `expansion_callback` - Callback to request new segments when needed
     ///
     /// # Returns
@@ -54,14 +134,13 @@ impl ColumnFamilyState {
     #[cfg(not(target_arch = "wasm32"))]
     pub fn ensure_database(
         &self,
-        pool: &FileHandlePool,
-        _path: &Path,
+        source: &BackendSource,
         expansion_callback: Arc<dyn Fn(u64) -> io::Result<Segment> + Send + Sync>,
     ) -> Result<Arc<Database>, DatabaseError> {
         {
             let db_guard = self.db.read().unwrap();
             if let Some(db) = db_guard.as_ref() {
-                pool.touch(&self.name);
+                source.touch(&self.name);
                 return Ok(db.clone());
             }
         }
@@ -69,22 +148,23 @@ impl ColumnFamilyState {
         let mut db_guard = self.db.write().unwrap();
 
         if let Some(db) = db_guard.as_ref() {
-            pool.touch(&self.name);
+            source.touch(&self.name);
             return Ok(db.clone());
         }
 
-        let backend = pool.acquire(&self.name)?;
+        let backend = source.acquire(&self.name)?;
         let segments = self.segments.read().unwrap().clone();
-        let file_growth_lock = pool.file_growth_lock();
+        let file_growth_lock = source.file_growth_lock();
 
         let partition_backend = PartitionedStorageBackend::with_segments(
             backend,
             segments,
             Some(expansion_callback),
             file_growth_lock,
-        );
+        )
+        .with_label(&self.name);
 
-        let db = Arc::new(Database::builder().create_with_backend(partition_backend)?);
+        let db = Arc::new(self.create_database(partition_backend)?);
         *db_guard = Some(db.clone());
 
         Ok(db)
@@ -130,9 +210,10 @@ impl ColumnFamilyState {
             segments,
             Some(expansion_callback),
             file_growth_lock,
-        );
+        )
+        .with_label(&self.name);
 
-        let db = Arc::new(Database::builder().create_with_backend(partition_backend)?);
+        let db = Arc::new(self.create_database(partition_backend)?);
         *db_guard = Some(db.clone());
 
         Ok(db)
@@ -147,6 +228,16 @@ impl ColumnFamilyState {
         let mut db_guard = self.db.write().unwrap();
         *db_guard = None;
     }
+
+    /// Marks this column family as belonging to a closed database.
+    pub fn mark_closed(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the database of this column family has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
 }
 
 #[cfg(test)]
@@ -156,7 +247,7 @@ mod tests {
     #[test]
     fn test_state_creation() {
         let segments = vec![Segment::new(4096, 1024 * 1024)];
-        let state = ColumnFamilyState::new("test_cf".to_string(), segments.clone());
+        let state = ColumnFamilyState::new("test_cf".to_string(), 1, segments.clone());
 
         assert_eq!(state.name, "test_cf");
         assert_eq!(state.segments.read().unwrap().len(), 1);
@@ -166,7 +257,7 @@ mod tests {
     #[test]
     fn test_eviction() {
         let segments = vec![Segment::new(4096, 1024 * 1024)];
-        let state = ColumnFamilyState::new("test_cf".to_string(), segments);
+        let state = ColumnFamilyState::new("test_cf".to_string(), 1, segments);
 
         assert!(state.db.read().unwrap().is_none());
 
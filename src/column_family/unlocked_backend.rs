@@ -88,6 +88,29 @@ impl StorageBackend for UnlockedFileBackend {
         Ok(())
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn prefetch(&self, offset: u64, len: u64) -> Result<(), io::Error> {
+        use std::os::fd::AsRawFd;
+
+        let (Ok(offset), Ok(len)) = (libc::off_t::try_from(offset), libc::off_t::try_from(len))
+        else {
+            return Ok(());
+        };
+        let result = unsafe {
+            libc::posix_fadvise(
+                self.file.as_raw_fd(),
+                offset,
+                len,
+                libc::POSIX_FADV_WILLNEED,
+            )
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::from_raw_os_error(result))
+        }
+    }
+
     fn close(&self) -> Result<(), io::Error> {
         Ok(())
     }
@@ -29,7 +29,7 @@
 #![allow(dead_code)] // Phase 1 core implementation - will be used in integration
 
 use super::entry::WALEntry;
-use super::journal::{WALHeader, WAL_HEADER_SIZE};
+use super::journal::{WAL_HEADER_SIZE, WAL_VERSION, WALHeader};
 use crate::StorageBackend;
 use std::collections::BTreeSet;
 use std::io;
@@ -190,7 +190,7 @@ impl AsyncWALJournal {
         entry.sequence = seq;
 
         // Serialize entry using zero-cost manual serialization
-        let entry_data = entry.to_bytes();
+        let entry_data = entry.to_bytes(WAL_VERSION);
 
         // Compute CRC32 of entry data
         let crc = crc32fast::hash(&entry_data);
@@ -382,7 +382,7 @@ impl AsyncWALJournal {
     pub fn read_from(&self, start_sequence: u64) -> io::Result<Vec<WALEntry>> {
         // This is the same implementation as the synchronous WAL journal
         // We can reuse the logic by reading directly from the backend
-        super::journal::WALJournal::read_entries_from_backend(&self.backend, start_sequence)
+        super::journal::WALJournal::read_entries_from_backend(&self.backend, start_sequence, None)
     }
 
     /// Truncates the WAL file to remove entries before the given sequence.
@@ -456,7 +456,7 @@ mod tests {
             durability: Durability::Immediate,
         };
 
-        let mut entry = WALEntry::new("test_cf".to_string(), 1, payload);
+        let mut entry = WALEntry::new("test_cf".to_string(), 1, 1, payload);
         let seq = journal.append(&mut entry).unwrap();
 
         assert_eq!(seq, 1);
@@ -487,7 +487,7 @@ mod tests {
                 durability: Durability::Immediate,
             };
 
-            let mut entry = WALEntry::new(format!("cf_{i}"), i as u64, payload);
+            let mut entry = WALEntry::new(format!("cf_{i}"), 1, i as u64, payload);
             let seq = journal.append(&mut entry).unwrap();
             sequences.push(seq);
         }
@@ -1,17 +1,39 @@
 use super::config::CheckpointConfig;
 use super::journal::WALJournal;
-use crate::column_family::database::ColumnFamilyDatabase;
-use crate::column_family::wal::entry::WALEntry;
+use super::stats::{CheckpointMetrics, CheckpointReport, WALStats};
+use crate::column_family::database::ColumnFamily;
+use crate::column_family::metrics::DatabaseMetrics;
+use crate::column_family::wal::entry::{WALEntry, entries_to_apply};
 use crate::tree_store::BtreeHeader;
 use std::collections::BTreeSet;
 use std::io;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::{Duration, SystemTime};
 
 #[cfg(not(target_arch = "wasm32"))]
 use std::thread::{self, JoinHandle};
 
+/// The parts of a column family database that checkpointing needs.
+///
+/// The checkpoint manager holds this rather than the database itself, so the database is
+/// constructed once and never referenced from its own background thread.
+pub(crate) trait CheckpointTarget: Send + Sync {
+    /// Returns the names of all column families.
+    fn list_column_families(&self) -> Vec<String>;
+
+    /// Returns a handle to the column family `name`, or `None` if it does not exist.
+    ///
+    /// The handle is only used to reach the column family's `Database`, so it does not need
+    /// to write through the WAL.
+    fn column_family(&self, name: &str) -> Option<ColumnFamily>;
+
+    /// Returns the hooks that checkpoints are reported to, if any.
+    fn metrics(&self) -> Option<&dyn DatabaseMetrics> {
+        None
+    }
+}
+
 /// Manages background checkpointing of WAL entries to the main database.
 ///
 /// The `CheckpointManager` runs a background thread that periodically:
@@ -24,18 +46,23 @@ use std::thread::{self, JoinHandle};
 /// - Time interval (default: 60 seconds)
 /// - WAL size threshold (default: 64 MB)
 /// - Manual checkpoint requests
+/// - Commits that find the WAL above the soft size limit (backpressure)
+///
+/// Only one checkpoint runs at a time: background, manual and backpressure checkpoints take
+/// the same lock, which [`Self::exclusive`] also hands to other writers of durable state.
 pub(crate) struct CheckpointManager {
     // These fields are accessed via &self references in methods like checkpoint_now()
     #[allow(dead_code)]
     journal: Arc<WALJournal>,
     #[allow(dead_code)]
-    database: Arc<ColumnFamilyDatabase>,
-    #[allow(dead_code)]
+    database: Arc<dyn CheckpointTarget>,
     config: CheckpointConfig,
     pending_sequences: Arc<RwLock<BTreeSet<u64>>>,
+    metrics: Arc<CheckpointMetrics>,
+    checkpoint_lock: Arc<Mutex<()>>,
     shutdown_signal: Arc<AtomicBool>,
     #[cfg(not(target_arch = "wasm32"))]
-    checkpoint_thread: Option<JoinHandle<()>>,
+    checkpoint_thread: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl CheckpointManager {
@@ -43,16 +70,20 @@ impl CheckpointManager {
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) fn start(
         journal: Arc<WALJournal>,
-        database: Arc<ColumnFamilyDatabase>,
+        database: Arc<dyn CheckpointTarget>,
         config: CheckpointConfig,
     ) -> Self {
         let pending_sequences = Arc::new(RwLock::new(BTreeSet::new()));
+        let metrics = Arc::new(CheckpointMetrics::default());
+        let checkpoint_lock = Arc::new(Mutex::new(()));
         let shutdown_signal = Arc::new(AtomicBool::new(false));
 
         let thread_journal = Arc::clone(&journal);
         let thread_database = Arc::clone(&database);
         let thread_config = config.clone();
         let thread_pending = Arc::clone(&pending_sequences);
+        let thread_metrics = Arc::clone(&metrics);
+        let thread_lock = Arc::clone(&checkpoint_lock);
         let thread_shutdown = Arc::clone(&shutdown_signal);
 
         let checkpoint_thread = thread::spawn(move || {
@@ -61,6 +92,8 @@ impl CheckpointManager {
                 thread_database,
                 thread_config,
                 thread_pending,
+                thread_metrics,
+                thread_lock,
                 thread_shutdown,
             );
         });
@@ -70,8 +103,10 @@ impl CheckpointManager {
             database,
             config,
             pending_sequences,
+            metrics,
+            checkpoint_lock,
             shutdown_signal,
-            checkpoint_thread: Some(checkpoint_thread),
+            checkpoint_thread: Mutex::new(Some(checkpoint_thread)),
         }
     }
 
@@ -79,16 +114,20 @@ impl CheckpointManager {
     #[cfg(target_arch = "wasm32")]
     pub(crate) fn start(
         journal: Arc<WALJournal>,
-        database: Arc<ColumnFamilyDatabase>,
+        database: Arc<dyn CheckpointTarget>,
         config: CheckpointConfig,
     ) -> Self {
         let pending_sequences = Arc::new(RwLock::new(BTreeSet::new()));
+        let metrics = Arc::new(CheckpointMetrics::default());
+        let checkpoint_lock = Arc::new(Mutex::new(()));
         let shutdown_signal = Arc::new(AtomicBool::new(false));
 
         let task_journal = Arc::clone(&journal);
         let task_database = Arc::clone(&database);
         let task_config = config.clone();
         let task_pending = Arc::clone(&pending_sequences);
+        let task_metrics = Arc::clone(&metrics);
+        let task_lock = Arc::clone(&checkpoint_lock);
         let task_shutdown = Arc::clone(&shutdown_signal);
 
         wasm_bindgen_futures::spawn_local(async move {
@@ -97,6 +136,8 @@ impl CheckpointManager {
                 task_database,
                 task_config,
                 task_pending,
+                task_metrics,
+                task_lock,
                 task_shutdown,
             )
             .await;
@@ -107,14 +148,35 @@ impl CheckpointManager {
             database,
             config,
             pending_sequences,
+            metrics,
+            checkpoint_lock,
             shutdown_signal,
         }
     }
 
-    /// Registers a transaction sequence number as pending checkpoint.
-    pub(crate) fn register_pending(&self, sequence: u64) {
+    /// Registers a transaction sequence number for `cf_name` as pending checkpoint.
+    pub(crate) fn register_pending(&self, sequence: u64, cf_name: &str) {
         let mut pending = self.pending_sequences.write().unwrap();
         pending.insert(sequence);
+        self.metrics.record_pending(cf_name);
+    }
+
+    /// Returns a snapshot of WAL size, pending entries and checkpoint counters.
+    pub(crate) fn stats(&self) -> io::Result<WALStats> {
+        let wal_size = self.journal.file_size()?;
+        let pending_entries = self.pending_sequences.read().unwrap().len() as u64;
+        Ok(self
+            .metrics
+            .snapshot(wal_size, pending_entries, self.journal.sync_count()))
+    }
+
+    /// Returns the number of WAL entries of `cf_name` pending checkpoint, and when its entries
+    /// were last checkpointed since the database was opened.
+    pub(crate) fn cf_progress(&self, cf_name: &str) -> (u64, Option<SystemTime>) {
+        (
+            self.metrics.pending_of(cf_name),
+            self.metrics.last_checkpoint_of(cf_name),
+        )
     }
 
     /// Manually triggers a checkpoint (blocks until complete).
@@ -122,16 +184,165 @@ impl CheckpointManager {
     /// This is used in tests and can be used by applications that need
     /// explicit control over checkpoint timing.
     #[allow(dead_code)]
-    pub(crate) fn checkpoint_now(&self) -> io::Result<()> {
-        Self::checkpoint_internal(&self.journal, &self.database, &self.pending_sequences)
+    pub(crate) fn checkpoint_now(&self) -> io::Result<CheckpointReport> {
+        Self::checkpoint_internal(
+            &self.journal,
+            self.database.as_ref(),
+            &self.pending_sequences,
+            &self.metrics,
+            &self.checkpoint_lock,
+        )
+    }
+
+    /// Checkpoints the WAL entries of column family `cf_name` only (blocks until complete).
+    ///
+    /// The entries are applied and the column family is made durable. If no other column
+    /// family has entries pending, the WAL is truncated as by a full checkpoint. Otherwise a
+    /// marker is appended that retires the entries, so that neither later checkpoints nor
+    /// recovery apply them again, and the WAL keeps its size until the next checkpoint that
+    /// empties it.
+    pub(crate) fn checkpoint_cf_now(&self, cf_name: &str) -> io::Result<CheckpointReport> {
+        let _guard = self.checkpoint_lock.lock().unwrap();
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("checkpoint", cf = cf_name, entries = tracing::field::Empty)
+            .entered();
+        let journal = &self.journal;
+        let database = self.database.as_ref();
+        #[cfg(not(target_arch = "wasm32"))]
+        let started = std::time::Instant::now();
+
+        let _commit_gate = journal.exclusive_guard();
+
+        let entries = entries_to_apply(journal.read_from(0)?);
+        let (cf_entries, others): (Vec<_>, Vec<_>) =
+            entries.iter().partition(|entry| entry.cf_name == cf_name);
+        let Some(last) = cf_entries.last() else {
+            return Ok(CheckpointReport::default());
+        };
+        let through = last.sequence;
+        #[cfg(feature = "tracing")]
+        span.record("entries", cf_entries.len());
+
+        let mut report = CheckpointReport::default();
+        for entry in &cf_entries {
+            if Self::apply_wal_entry_to_database(database, entry)? {
+                *report.entries_by_cf.entry(cf_name.to_string()).or_default() += 1;
+            }
+        }
+        Self::commit_durable(database, cf_name)?;
+
+        if others.is_empty() {
+            let latest_seq = self
+                .pending_sequences
+                .read()
+                .unwrap()
+                .last()
+                .map_or(through, |&latest| latest.max(through));
+            report.wal_bytes_truncated = Self::truncate(journal, latest_seq)?;
+            self.pending_sequences.write().unwrap().clear();
+            self.metrics.clear_pending();
+        } else {
+            // The entries stay in the WAL until it is emptied; the marker keeps them from being
+            // applied again, over later commits to the column family
+            let marker = journal.append_checkpointed(cf_name, last.cf_generation, through)?;
+            journal.wait_for_sync(marker)?;
+            let mut pending = self.pending_sequences.write().unwrap();
+            for entry in &cf_entries {
+                pending.remove(&entry.sequence);
+            }
+            self.metrics.clear_pending_cf(cf_name);
+        }
+        self.metrics.record_checkpointed(Some(cf_name));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            report.duration = started.elapsed();
+        }
+        Self::record(database, &self.metrics, cf_entries.len(), report.duration);
+
+        Ok(report)
+    }
+
+    /// Waits for any checkpoint in progress and keeps new ones from starting until the guard
+    /// is dropped.
+    ///
+    /// Held while the database commits column families durably outside of a checkpoint, on
+    /// close and on drop, so that those commits never interleave with a checkpoint applying
+    /// WAL entries to the same column family.
+    pub(crate) fn exclusive(&self) -> MutexGuard<'_, ()> {
+        // The lock guards no data, so a checkpoint that panicked leaves nothing inconsistent
+        self.checkpoint_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns `true` if the WAL has grown past the soft size limit, so that
+    /// [`Self::apply_backpressure`] would throttle the committer.
+    #[cfg(feature = "async")]
+    pub(crate) fn needs_backpressure(&self) -> bool {
+        self.journal
+            .file_size()
+            .is_ok_and(|size| size >= self.config.soft_wal_size_limit)
+    }
+
+    /// Throttles a committer when the WAL has grown past the configured size limits.
+    ///
+    /// Called after a WAL-backed commit has completed. Above the soft limit this runs a
+    /// checkpoint synchronously (or waits for one already in progress); above the hard
+    /// limit it keeps checkpointing until the WAL is back below the soft limit. Failures
+    /// are not reported to the caller, whose commit is already durable in the WAL; the
+    /// background thread will retry.
+    pub(crate) fn apply_backpressure(&self) {
+        let soft_limit = self.config.soft_wal_size_limit;
+        let hard_limit = self.config.hard_wal_size_limit;
+
+        let Ok(size) = self.journal.file_size() else {
+            return;
+        };
+        if size < soft_limit {
+            return;
+        }
+        self.metrics.record_throttled();
+
+        loop {
+            let result = {
+                let _guard = self.checkpoint_lock.lock().unwrap();
+                // Another committer may have checkpointed while we waited for the lock
+                match self.journal.file_size() {
+                    Ok(size) if size < soft_limit => return,
+                    Ok(_) => Self::checkpoint_locked(
+                        &self.journal,
+                        self.database.as_ref(),
+                        &self.pending_sequences,
+                        &self.metrics,
+                    ),
+                    Err(e) => Err(e),
+                }
+            };
+
+            if let Err(_e) = result {
+                #[cfg(feature = "logging")]
+                log::warn!("Backpressure checkpoint failed: {_e}");
+                return;
+            }
+
+            // Above the hard limit, stay blocked until the WAL is below the soft limit. If
+            // nothing is pending the WAL cannot shrink further, so stop waiting.
+            if size < hard_limit || self.pending_sequences.read().unwrap().is_empty() {
+                return;
+            }
+        }
     }
 
     /// Shuts down the checkpoint thread gracefully.
+    ///
+    /// Checkpoints can still be run with [`Self::checkpoint_now`] afterwards.
     #[cfg(not(target_arch = "wasm32"))]
-    pub(crate) fn shutdown(mut self) -> io::Result<()> {
+    pub(crate) fn shutdown(&self) -> io::Result<()> {
         self.shutdown_signal.store(true, Ordering::Release);
 
-        if let Some(handle) = self.checkpoint_thread.take() {
+        let handle = self.checkpoint_thread.lock().unwrap().take();
+        if let Some(handle) = handle {
             handle
                 .join()
                 .map_err(|_| io::Error::other("checkpoint thread panicked"))?;
@@ -142,7 +353,7 @@ impl CheckpointManager {
 
     /// Shuts down the checkpoint task gracefully (WASM version).
     #[cfg(target_arch = "wasm32")]
-    pub(crate) fn shutdown(self) -> io::Result<()> {
+    pub(crate) fn shutdown(&self) -> io::Result<()> {
         self.shutdown_signal.store(true, Ordering::Release);
         // WASM async task will see shutdown signal and exit
         // No join needed - task cleanup handled by JavaScript runtime
@@ -153,9 +364,11 @@ impl CheckpointManager {
     #[cfg(not(target_arch = "wasm32"))]
     fn checkpoint_loop(
         journal: Arc<WALJournal>,
-        database: Arc<ColumnFamilyDatabase>,
+        database: Arc<dyn CheckpointTarget>,
         config: CheckpointConfig,
         pending_sequences: Arc<RwLock<BTreeSet<u64>>>,
+        metrics: Arc<CheckpointMetrics>,
+        checkpoint_lock: Arc<Mutex<()>>,
         shutdown_signal: Arc<AtomicBool>,
     ) {
         let mut last_checkpoint = std::time::Instant::now();
@@ -167,7 +380,13 @@ impl CheckpointManager {
 
             if shutdown_signal.load(Ordering::Acquire) {
                 // Perform final checkpoint before shutdown
-                let _ = Self::checkpoint_internal(&journal, &database, &pending_sequences);
+                let _ = Self::checkpoint_internal(
+                    &journal,
+                    database.as_ref(),
+                    &pending_sequences,
+                    &metrics,
+                    &checkpoint_lock,
+                );
                 break;
             }
 
@@ -184,8 +403,14 @@ impl CheckpointManager {
             };
 
             if should_checkpoint {
-                match Self::checkpoint_internal(&journal, &database, &pending_sequences) {
-                    Ok(()) => {
+                match Self::checkpoint_internal(
+                    &journal,
+                    database.as_ref(),
+                    &pending_sequences,
+                    &metrics,
+                    &checkpoint_lock,
+                ) {
+                    Ok(_) => {
                         last_checkpoint = std::time::Instant::now();
                     }
                     #[cfg(feature = "logging")]
@@ -206,9 +431,11 @@ impl CheckpointManager {
     #[cfg(target_arch = "wasm32")]
     async fn checkpoint_loop_async(
         journal: Arc<WALJournal>,
-        database: Arc<ColumnFamilyDatabase>,
+        database: Arc<dyn CheckpointTarget>,
         config: CheckpointConfig,
         pending_sequences: Arc<RwLock<BTreeSet<u64>>>,
+        metrics: Arc<CheckpointMetrics>,
+        checkpoint_lock: Arc<Mutex<()>>,
         shutdown_signal: Arc<AtomicBool>,
     ) {
         // Track last checkpoint time using a counter (Instant not available in WASM)
@@ -223,7 +450,13 @@ impl CheckpointManager {
 
             if shutdown_signal.load(Ordering::Acquire) {
                 // Perform final checkpoint before shutdown
-                let _ = Self::checkpoint_internal(&journal, &database, &pending_sequences);
+                let _ = Self::checkpoint_internal(
+                    &journal,
+                    database.as_ref(),
+                    &pending_sequences,
+                    &metrics,
+                    &checkpoint_lock,
+                );
                 break;
             }
 
@@ -242,8 +475,14 @@ impl CheckpointManager {
             };
 
             if should_checkpoint {
-                match Self::checkpoint_internal(&journal, &database, &pending_sequences) {
-                    Ok(()) => {
+                match Self::checkpoint_internal(
+                    &journal,
+                    database.as_ref(),
+                    &pending_sequences,
+                    &metrics,
+                    &checkpoint_lock,
+                ) {
+                    Ok(_) => {
                         iterations_since_checkpoint = 0;
                     }
                     Err(_e) => {
@@ -258,75 +497,171 @@ impl CheckpointManager {
     /// Performs a checkpoint operation.
     fn checkpoint_internal(
         journal: &Arc<WALJournal>,
-        database: &Arc<ColumnFamilyDatabase>,
+        database: &dyn CheckpointTarget,
         pending_sequences: &Arc<RwLock<BTreeSet<u64>>>,
-    ) -> io::Result<()> {
-        // Get snapshot of pending sequences
-        let sequences = {
-            let pending = pending_sequences.read().unwrap();
-            if pending.is_empty() {
-                return Ok(()); // Nothing to checkpoint
-            }
-            pending.clone()
-        };
+        metrics: &CheckpointMetrics,
+        checkpoint_lock: &Mutex<()>,
+    ) -> io::Result<CheckpointReport> {
+        let _guard = checkpoint_lock.lock().unwrap();
+        Self::checkpoint_locked(journal, database, pending_sequences, metrics)
+    }
 
-        let oldest_seq = *sequences.first().unwrap();
-        let latest_seq = *sequences.last().unwrap();
+    /// Performs a checkpoint while the caller holds the checkpoint lock.
+    fn checkpoint_locked(
+        journal: &Arc<WALJournal>,
+        database: &dyn CheckpointTarget,
+        pending_sequences: &Arc<RwLock<BTreeSet<u64>>>,
+        metrics: &CheckpointMetrics,
+    ) -> io::Result<CheckpointReport> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("checkpoint", entries = tracing::field::Empty).entered();
+        #[cfg(not(target_arch = "wasm32"))]
+        let started = std::time::Instant::now();
+
+        // Every appended entry must be applied in memory before the WAL is truncated
+        let _commit_gate = journal.exclusive_guard();
+
+        // Get the latest pending sequence
+        let Some(latest_seq) = pending_sequences.read().unwrap().last().copied() else {
+            return Ok(CheckpointReport::default()); // Nothing to checkpoint
+        };
 
-        // Read all pending WAL entries
-        let entries = journal.read_from(oldest_seq)?;
+        // Read the whole WAL, leaving out write groups that were never completed and entries
+        // already checkpointed on their own. Those entries are no longer pending, but may
+        // belong to the same write group as pending ones.
+        let entries = entries_to_apply(journal.read_from(0)?);
+        #[cfg(feature = "tracing")]
+        span.record("entries", entries.len());
 
         if entries.is_empty() {
             // No entries found - clear pending and return
             pending_sequences.write().unwrap().clear();
-            return Ok(());
+            metrics.clear_pending();
+            return Ok(CheckpointReport::default());
         }
 
         // Apply each entry to the database
+        let mut report = CheckpointReport::default();
         for entry in &entries {
-            Self::apply_wal_entry_to_database(database, entry)?;
+            if Self::apply_wal_entry_to_database(database, entry)? {
+                *report
+                    .entries_by_cf
+                    .entry(entry.cf_name.clone())
+                    .or_default() += 1;
+            }
         }
 
         // Flush and durably commit all column families to persist changes
         // This ensures the main database is durable before we truncate the WAL
         for cf_name in database.list_column_families() {
-            if let Ok(cf) = database.column_family(&cf_name)
-                && let Ok(db) = cf.ensure_database()
-            {
-                let mem = db.get_memory();
-
-                // Get current state from secondary slot
-                let (data_root, system_root, txn_id) = mem
-                    .get_current_secondary_state()
-                    .map_err(|e| io::Error::other(format!("get state failed: {e}")))?;
-
-                // Perform checkpoint commit: flush all pending writes and do durable commit
-                mem.checkpoint_commit(data_root, system_root, txn_id)
-                    .map_err(|e| io::Error::other(format!("checkpoint commit failed: {e}")))?;
-            }
+            Self::commit_durable(database, &cf_name)?;
         }
 
         // Truncate WAL and reset sequence counter
-        journal.truncate(latest_seq + 1)?;
+        report.wal_bytes_truncated = Self::truncate(journal, latest_seq)?;
 
         // Clear pending sequences
         pending_sequences.write().unwrap().clear();
+        metrics.clear_pending();
+        metrics.record_checkpointed(None);
 
-        Ok(())
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            report.duration = started.elapsed();
+        }
+        Self::record(database, metrics, entries.len(), report.duration);
+
+        #[cfg(feature = "logging")]
+        {
+            let stats = metrics.snapshot(journal.file_size().unwrap_or(0), 0, journal.sync_count());
+            log::debug!(
+                "Checkpoint applied {} WAL entries in {:?} ({} checkpoints, {} entries total, WAL size {} bytes)",
+                stats.last_checkpoint_entries,
+                report.duration,
+                stats.checkpoints,
+                stats.entries_checkpointed,
+                stats.wal_size
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Durably commits the latest state of column family `cf_name`, flushing its pending
+    /// writes. Column families with nothing newer than their last durable commit, or without
+    /// a database yet, are skipped.
+    fn commit_durable(database: &dyn CheckpointTarget, cf_name: &str) -> io::Result<()> {
+        let Some(cf) = database.column_family(cf_name) else {
+            return Ok(());
+        };
+        let Ok(db) = cf.ensure_database() else {
+            return Ok(());
+        };
+        let mem = db.get_memory();
+
+        let Some((data_root, system_root, txn_id)) = mem
+            .get_non_durable_state()
+            .map_err(|e| io::Error::other(format!("get state failed: {e}")))?
+        else {
+            return Ok(());
+        };
+
+        mem.checkpoint_commit(data_root, system_root, txn_id)
+            .map_err(|e| io::Error::other(format!("checkpoint commit failed: {e}")))
+    }
+
+    /// Truncates the WAL past `latest_seq`, returning the number of bytes removed.
+    fn truncate(journal: &WALJournal, latest_seq: u64) -> io::Result<u64> {
+        let size = journal.file_size()?;
+        journal.truncate(latest_seq + 1)?;
+        Ok(size.saturating_sub(journal.file_size()?))
+    }
+
+    /// Reports a checkpoint that applied `entries` entries to the counters and hooks.
+    fn record(
+        database: &dyn CheckpointTarget,
+        metrics: &CheckpointMetrics,
+        entries: usize,
+        duration: Duration,
+    ) {
+        metrics.record_checkpoint(entries as u64, duration);
+        if let Some(hooks) = database.metrics() {
+            hooks.on_checkpoint(duration, entries);
+        }
     }
 
     /// Applies a single WAL entry to the database.
+    ///
+    /// Returns `false` if the entry was skipped because its column family no longer exists
+    /// or has been recreated since.
     fn apply_wal_entry_to_database(
-        database: &Arc<ColumnFamilyDatabase>,
+        database: &dyn CheckpointTarget,
         entry: &WALEntry,
-    ) -> io::Result<()> {
-        // Get the column family
-        let cf = database.column_family(&entry.cf_name).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("column family '{}' not found: {e}", entry.cf_name),
-            )
-        })?;
+    ) -> io::Result<bool> {
+        // Get the column family. Entries for a deleted column family are skipped: its
+        // segments may already have been handed to another column family.
+        let Some(cf) = database.column_family(&entry.cf_name) else {
+            #[cfg(feature = "logging")]
+            log::warn!(
+                "Skipping WAL entry {} for deleted column family '{}'",
+                entry.sequence,
+                entry.cf_name
+            );
+            return Ok(false);
+        };
+
+        // Entries from an earlier incarnation of a recreated column family are stale
+        if cf.generation() != entry.cf_generation {
+            #[cfg(feature = "logging")]
+            log::warn!(
+                "Skipping WAL entry {} for column family '{}': generation {} does not match current generation {}",
+                entry.sequence,
+                entry.cf_name,
+                entry.cf_generation,
+                cf.generation()
+            );
+            return Ok(false);
+        }
 
         // Get the underlying Database instance
         let db = cf
@@ -363,7 +698,7 @@ impl CheckpointManager {
         )
         .map_err(|e| io::Error::other(format!("apply_wal_transaction failed: {e}")))?;
 
-        Ok(())
+        Ok(true)
     }
 }
 
@@ -374,7 +709,9 @@ impl Drop for CheckpointManager {
         self.shutdown_signal.store(true, Ordering::Release);
 
         // Wait for thread to finish
-        if let Some(handle) = self.checkpoint_thread.take() {
+        if let Ok(thread) = self.checkpoint_thread.get_mut()
+            && let Some(handle) = thread.take()
+        {
             let _ = handle.join();
         }
     }
@@ -402,22 +739,22 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
 
-        let db = Arc::new(
-            ColumnFamilyDatabase::builder()
-                .pool_size(0) // Disable automatic WAL so we can create our own for testing
-                .open(&db_path)
-                .unwrap(),
-        );
+        let db = ColumnFamilyDatabase::builder()
+            .pool_size(0) // Disable automatic WAL so we can create our own for testing
+            .open(&db_path)
+            .unwrap();
 
         let wal_path = db_path.with_extension("wal");
-        let journal = Arc::new(WALJournal::open(&wal_path).unwrap());
+        let journal = Arc::new(WALJournal::open(&wal_path, None).unwrap());
 
         let config = CheckpointConfig {
             interval: Duration::from_secs(60),
             max_wal_size: 64 * 1024 * 1024,
+            soft_wal_size_limit: 256 * 1024 * 1024,
+            hard_wal_size_limit: 1024 * 1024 * 1024,
         };
 
-        let manager = CheckpointManager::start(journal, db, config);
+        let manager = CheckpointManager::start(journal, Arc::new(db.checkpoint_view()), config);
         manager.shutdown().unwrap();
     }
 
@@ -426,27 +763,27 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
 
-        let db = Arc::new(
-            ColumnFamilyDatabase::builder()
-                .pool_size(0) // Disable automatic WAL so we can create our own for testing
-                .open(&db_path)
-                .unwrap(),
-        );
+        let db = ColumnFamilyDatabase::builder()
+            .pool_size(0) // Disable automatic WAL so we can create our own for testing
+            .open(&db_path)
+            .unwrap();
 
         let wal_path = db_path.with_extension("wal");
-        let journal = Arc::new(WALJournal::open(&wal_path).unwrap());
+        let journal = Arc::new(WALJournal::open(&wal_path, None).unwrap());
 
         let config = CheckpointConfig {
             interval: Duration::from_secs(3600), // Long interval
             max_wal_size: 1024 * 1024 * 1024,    // Large threshold
+            soft_wal_size_limit: 1024 * 1024 * 1024,
+            hard_wal_size_limit: 1024 * 1024 * 1024,
         };
 
-        let manager = CheckpointManager::start(journal, db, config);
+        let manager = CheckpointManager::start(journal, Arc::new(db.checkpoint_view()), config);
 
         // Register some sequences
-        manager.register_pending(1);
-        manager.register_pending(2);
-        manager.register_pending(3);
+        manager.register_pending(1, "test_cf");
+        manager.register_pending(2, "test_cf");
+        manager.register_pending(3, "test_cf");
 
         let pending = manager.pending_sequences.read().unwrap();
         assert_eq!(pending.len(), 3);
@@ -463,18 +800,16 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
 
-        let db = Arc::new(
-            ColumnFamilyDatabase::builder()
-                .pool_size(0) // Disable automatic WAL so we can create our own for testing
-                .open(&db_path)
-                .unwrap(),
-        );
+        let db = ColumnFamilyDatabase::builder()
+            .pool_size(0) // Disable automatic WAL so we can create our own for testing
+            .open(&db_path)
+            .unwrap();
 
         // Create a column family
         db.create_column_family("test_cf", None).unwrap();
 
         let wal_path = db_path.with_extension("wal");
-        let journal = Arc::new(WALJournal::open(&wal_path).unwrap());
+        let journal = Arc::new(WALJournal::open(&wal_path, None).unwrap());
 
         // Write some WAL entries
         for i in 0..3 {
@@ -486,7 +821,7 @@ mod tests {
                 durability: Durability::Immediate,
             };
 
-            let mut entry = WALEntry::new("test_cf".to_string(), i, payload);
+            let mut entry = WALEntry::new("test_cf".to_string(), 1, i, payload);
             journal.append(&mut entry).unwrap();
         }
         journal.sync().unwrap();
@@ -494,14 +829,17 @@ mod tests {
         let config = CheckpointConfig {
             interval: Duration::from_secs(3600),
             max_wal_size: 1024 * 1024 * 1024,
+            soft_wal_size_limit: 1024 * 1024 * 1024,
+            hard_wal_size_limit: 1024 * 1024 * 1024,
         };
 
-        let manager = CheckpointManager::start(Arc::clone(&journal), db, config);
+        let manager =
+            CheckpointManager::start(Arc::clone(&journal), Arc::new(db.checkpoint_view()), config);
 
         // Register sequences
-        manager.register_pending(1);
-        manager.register_pending(2);
-        manager.register_pending(3);
+        manager.register_pending(1, "test_cf");
+        manager.register_pending(2, "test_cf");
+        manager.register_pending(3, "test_cf");
 
         // Trigger manual checkpoint
         manager.checkpoint_now().unwrap();
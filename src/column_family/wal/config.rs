@@ -12,6 +12,35 @@ pub struct WALConfig {
     ///
     /// Default: 64 MB (native), 32 MB (WASM)
     pub max_wal_size: u64,
+
+    /// WAL size above which a commit synchronously runs a checkpoint before returning.
+    ///
+    /// This applies backpressure when writers outpace the background checkpoint.
+    ///
+    /// Default: 256 MB (native), 64 MB (WASM)
+    pub soft_wal_size_limit: u64,
+
+    /// WAL size above which commits block until checkpointing has brought the WAL back
+    /// below [`soft_wal_size_limit`](Self::soft_wal_size_limit).
+    ///
+    /// Values below the soft limit are treated as equal to it.
+    ///
+    /// Default: 1 GB (native), 128 MB (WASM)
+    pub hard_wal_size_limit: u64,
+
+    /// Size at which the WAL is split into a new segment file.
+    ///
+    /// With a segment size, entries are written to numbered files next to the database
+    /// (`db.wal.000001`, `db.wal.000002`, ...), and a new one is started when an append would
+    /// grow the current one past this size. A checkpoint deletes the segments it has made
+    /// obsolete instead of truncating a single file, and recovery reads the segments in order.
+    /// An existing single-file WAL becomes the first segment. Once segmented, a WAL stays
+    /// segmented; opening it without a segment size keeps appending to its last segment.
+    ///
+    /// Has no effect on in-memory databases and WASM.
+    ///
+    /// Default: `None` (a single `db.wal` file)
+    pub segment_size: Option<u64>,
 }
 
 impl Default for WALConfig {
@@ -20,7 +49,10 @@ impl Default for WALConfig {
         {
             Self {
                 checkpoint_interval: Duration::from_secs(60),
-                max_wal_size: 64 * 1024 * 1024, // 64 MB
+                max_wal_size: 64 * 1024 * 1024,          // 64 MB
+                soft_wal_size_limit: 256 * 1024 * 1024,  // 256 MB
+                hard_wal_size_limit: 1024 * 1024 * 1024, // 1 GB
+                segment_size: None,
             }
         }
 
@@ -29,6 +61,9 @@ impl Default for WALConfig {
             Self {
                 checkpoint_interval: Duration::from_secs(15), // Shorter for browser context
                 max_wal_size: 32 * 1024 * 1024, // 32 MB (browser storage quota awareness)
+                soft_wal_size_limit: 64 * 1024 * 1024, // 64 MB
+                hard_wal_size_limit: 128 * 1024 * 1024, // 128 MB
+                segment_size: None,
             }
         }
     }
@@ -42,6 +77,12 @@ pub struct CheckpointConfig {
 
     /// Size-based checkpoint trigger.
     pub max_wal_size: u64,
+
+    /// WAL size at which commits checkpoint synchronously.
+    pub soft_wal_size_limit: u64,
+
+    /// WAL size at which commits block until the WAL is below the soft limit.
+    pub hard_wal_size_limit: u64,
 }
 
 impl From<WALConfig> for CheckpointConfig {
@@ -49,6 +90,8 @@ impl From<WALConfig> for CheckpointConfig {
         Self {
             interval: config.checkpoint_interval,
             max_wal_size: config.max_wal_size,
+            soft_wal_size_limit: config.soft_wal_size_limit,
+            hard_wal_size_limit: config.hard_wal_size_limit.max(config.soft_wal_size_limit),
         }
     }
 }
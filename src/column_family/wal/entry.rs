@@ -1,5 +1,6 @@
 use crate::Durability;
 use crate::tree_store::{Checksum, PageNumber};
+use std::collections::{HashMap, HashSet};
 use std::io;
 
 /// A single entry in the Write-Ahead Log.
@@ -14,13 +15,44 @@ pub(crate) struct WALEntry {
     /// Name of the column family this transaction belongs to.
     pub(crate) cf_name: String,
 
+    /// Generation of the column family when the transaction committed.
+    ///
+    /// Entries from WAL format version 1 carry no generation and decode as 0.
+    pub(crate) cf_generation: u64,
+
     /// Transaction ID from the underlying redb `TransactionalMemory`.
     pub(crate) transaction_id: u64,
 
+    /// The write group this entry belongs to.
+    ///
+    /// Entries from WAL format versions before 3 decode as [`WALGroup::None`].
+    pub(crate) group: WALGroup,
+
     /// The serialized transaction payload.
     pub(crate) payload: WALTransactionPayload,
 }
 
+/// Membership of a WAL entry in a write group.
+///
+/// The transactions of a write group are appended together, followed by a
+/// [`WALGroup::Commit`] entry that marks the group as complete. Recovery and checkpoints
+/// only apply members of groups whose commit entry was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WALGroup {
+    /// The transaction was committed on its own.
+    None,
+    /// The transaction is one of the members of write group `id`.
+    Member { id: u64 },
+    /// Marks write group `id`, of `members` transactions, as committed. The entry carries
+    /// no transaction of its own.
+    Commit { id: u64, members: u32 },
+    /// Not part of a write group: marks the entries of the entry's column family and
+    /// generation up to sequence `through` as checkpointed, so they are no longer applied.
+    /// Written by a checkpoint of a single column family. The entry carries no transaction
+    /// of its own.
+    Checkpointed { through: u64 },
+}
+
 /// The payload of a WAL entry containing all information needed to replay a transaction.
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct WALTransactionPayload {
@@ -42,32 +74,104 @@ pub(crate) struct WALTransactionPayload {
     pub(crate) durability: Durability,
 }
 
+/// Encrypts and decrypts the payloads of WAL entries for encrypted column families.
+pub(crate) trait PayloadCipher: Send + Sync {
+    /// Returns `payload` sealed with the key of column family `cf_name`, or `None` if the
+    /// column family is not encrypted. `header` is the serialized entry before the payload,
+    /// which the sealed payload is bound to.
+    fn seal(
+        &self,
+        cf_name: &str,
+        cf_generation: u64,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Option<Vec<u8>>;
+
+    /// Returns the payload that [`seal`](Self::seal) sealed into `sealed`.
+    ///
+    /// Fails if the key is not available or the payload was not sealed with it.
+    fn open(
+        &self,
+        cf_name: &str,
+        cf_generation: u64,
+        header: &[u8],
+        sealed: &[u8],
+    ) -> io::Result<Vec<u8>>;
+}
+
 impl WALEntry {
     /// Creates a new WAL entry.
     ///
     /// The sequence number will be assigned by the journal during append.
     pub(crate) fn new(
         cf_name: String,
+        cf_generation: u64,
         transaction_id: u64,
         payload: WALTransactionPayload,
     ) -> Self {
         Self {
             sequence: 0, // Will be assigned by journal
             cf_name,
+            cf_generation,
             transaction_id,
+            group: WALGroup::None,
             payload,
         }
     }
 
-    /// Serializes the entry to bytes using zero-cost manual serialization.
+    /// Creates the entry that marks write group `id`, of `members` transactions, as
+    /// committed.
+    pub(crate) fn group_commit(id: u64, members: u32) -> Self {
+        Self {
+            sequence: 0,
+            cf_name: String::new(),
+            cf_generation: 0,
+            transaction_id: 0,
+            group: WALGroup::Commit { id, members },
+            payload: WALTransactionPayload {
+                user_root: None,
+                system_root: None,
+                freed_pages: Vec::new(),
+                allocated_pages: Vec::new(),
+                durability: Durability::Immediate,
+            },
+        }
+    }
+
+    /// Creates the entry that marks the entries of column family `cf_name`, in generation
+    /// `cf_generation`, up to sequence `through` as checkpointed.
+    pub(crate) fn checkpointed(cf_name: String, cf_generation: u64, through: u64) -> Self {
+        Self {
+            cf_name,
+            cf_generation,
+            group: WALGroup::Checkpointed { through },
+            ..Self::group_commit(0, 0)
+        }
+    }
+
+    /// Serializes the entry to bytes in the given WAL format version using zero-cost
+    /// manual serialization.
     ///
     /// Format:
     /// - sequence: u64 (8 bytes)
     /// - `cf_name_len`: u32 (4 bytes)
     /// - `cf_name`: [u8; `cf_name_len`] (variable)
+    /// - `cf_generation`: u64 (8 bytes, version 2+)
     /// - `transaction_id`: u64 (8 bytes)
-    /// - payload: serialized `WALTransactionPayload` (variable)
-    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+    /// - group: u8 tag (0 = none, 1 = member, 2 = commit, 3 = checkpointed), then the group
+    ///   id: u64 for members and commits, the member count: u32 for commits, and the last
+    ///   checkpointed sequence: u64 for checkpoint markers (version 3+, checkpoint markers
+    ///   version 4+)
+    /// - payload encoding: u8 (0 = plain, 1 = sealed, version 5+)
+    /// - payload: serialized `WALTransactionPayload` (variable), or for a sealed payload its
+    ///   length: u32 followed by the payload sealed by a [`PayloadCipher`]
+    pub(crate) fn to_bytes(&self, version: u8) -> Vec<u8> {
+        self.to_bytes_with(version, None)
+    }
+
+    /// Serializes the entry like [`to_bytes`](Self::to_bytes), sealing the payload with
+    /// `cipher` if its column family is encrypted.
+    pub(crate) fn to_bytes_with(&self, version: u8, cipher: Option<&dyn PayloadCipher>) -> Vec<u8> {
         let mut buf = Vec::new();
 
         // Sequence number
@@ -79,19 +183,73 @@ impl WALEntry {
         buf.extend_from_slice(&(cf_name_bytes.len() as u32).to_le_bytes());
         buf.extend_from_slice(cf_name_bytes);
 
+        // CF generation
+        if version >= 2 {
+            buf.extend_from_slice(&self.cf_generation.to_le_bytes());
+        }
+
         // Transaction ID
         buf.extend_from_slice(&self.transaction_id.to_le_bytes());
 
+        // Write group
+        if version >= 3 {
+            match self.group {
+                WALGroup::None => buf.push(0),
+                WALGroup::Member { id } => {
+                    buf.push(1);
+                    buf.extend_from_slice(&id.to_le_bytes());
+                }
+                WALGroup::Commit { id, members } => {
+                    buf.push(2);
+                    buf.extend_from_slice(&id.to_le_bytes());
+                    buf.extend_from_slice(&members.to_le_bytes());
+                }
+                WALGroup::Checkpointed { through } => {
+                    buf.push(3);
+                    buf.extend_from_slice(&through.to_le_bytes());
+                }
+            }
+        }
+
         // Payload
-        self.payload.serialize_into(&mut buf);
+        if version < 5 {
+            self.payload.serialize_into(&mut buf);
+            return buf;
+        }
+        let mut payload = Vec::new();
+        self.payload.serialize_into(&mut payload);
+        if let Some(sealed) =
+            cipher.and_then(|cipher| cipher.seal(&self.cf_name, self.cf_generation, &buf, &payload))
+        {
+            buf.push(1);
+            #[allow(clippy::cast_possible_truncation)]
+            buf.extend_from_slice(&(sealed.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&sealed);
+        } else {
+            buf.push(0);
+            buf.extend_from_slice(&payload);
+        }
 
         buf
     }
 
-    /// Deserializes an entry from bytes.
+    /// Deserializes an entry written in the given WAL format version.
     ///
     /// Returns the entry and the number of bytes consumed.
-    pub(crate) fn from_bytes(data: &[u8]) -> io::Result<(Self, usize)> {
+    #[cfg(test)]
+    pub(crate) fn from_bytes(data: &[u8], version: u8) -> io::Result<(Self, usize)> {
+        Self::from_bytes_with(data, version, None)
+    }
+
+    /// Deserializes an entry like [`from_bytes`](Self::from_bytes), opening a sealed payload
+    /// with `cipher`.
+    ///
+    /// Fails if the payload is sealed and `cipher` is `None` or cannot open it.
+    pub(crate) fn from_bytes_with(
+        data: &[u8],
+        version: u8,
+        cipher: Option<&dyn PayloadCipher>,
+    ) -> io::Result<(Self, usize)> {
         let mut offset = 0;
 
         // Read sequence
@@ -127,6 +285,21 @@ impl WALEntry {
             })?;
         offset += cf_name_len;
 
+        // Read CF generation
+        let cf_generation = if version >= 2 {
+            if data.len() < offset + 8 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated cf_generation",
+                ));
+            }
+            let cf_generation = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            offset += 8;
+            cf_generation
+        } else {
+            0
+        };
+
         // Read transaction ID
         if data.len() < offset + 8 {
             return Err(io::Error::new(
@@ -137,20 +310,185 @@ impl WALEntry {
         let transaction_id = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
         offset += 8;
 
+        // Read write group
+        let group = if version >= 3 {
+            let (group, group_len) = Self::group_from_bytes(&data[offset..])?;
+            offset += group_len;
+            group
+        } else {
+            WALGroup::None
+        };
+
         // Read payload
-        let (payload, payload_len) = WALTransactionPayload::deserialize_from(&data[offset..])?;
-        offset += payload_len;
+        let sealed = if version >= 5 {
+            let encoding = *data.get(offset).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "truncated payload encoding")
+            })?;
+            offset += 1;
+            match encoding {
+                0 => false,
+                1 => true,
+                other => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid payload encoding: {other}"),
+                    ));
+                }
+            }
+        } else {
+            false
+        };
+        let payload = if sealed {
+            let sealed_len = data
+                .get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "truncated sealed payload length",
+                    )
+                })?;
+            let sealed_payload =
+                data.get(offset + 4..offset + 4 + sealed_len)
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::UnexpectedEof, "truncated sealed payload")
+                    })?;
+            let cipher = cipher.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "WAL entry {sequence} for column family '{cf_name}' is encrypted, but no key was configured"
+                    ),
+                )
+            })?;
+            // The header is everything before the encoding byte
+            let payload_bytes =
+                cipher.open(&cf_name, cf_generation, &data[..offset - 1], sealed_payload)?;
+            offset += 4 + sealed_len;
+            WALTransactionPayload::deserialize_from(&payload_bytes)?.0
+        } else {
+            let (payload, payload_len) = WALTransactionPayload::deserialize_from(&data[offset..])?;
+            offset += payload_len;
+            payload
+        };
 
         Ok((
             Self {
                 sequence,
                 cf_name,
+                cf_generation,
                 transaction_id,
+                group,
                 payload,
             },
             offset,
         ))
     }
+
+    /// Deserializes the write group of an entry.
+    ///
+    /// Returns the group and the number of bytes consumed.
+    fn group_from_bytes(data: &[u8]) -> io::Result<(WALGroup, usize)> {
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated write group");
+
+        let tag = *data.first().ok_or_else(truncated)?;
+        let id = || -> io::Result<u64> {
+            data.get(1..9)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or_else(truncated)
+        };
+
+        match tag {
+            0 => Ok((WALGroup::None, 1)),
+            1 => Ok((WALGroup::Member { id: id()? }, 9)),
+            2 => {
+                let members = data
+                    .get(9..13)
+                    .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+                    .ok_or_else(truncated)?;
+                Ok((WALGroup::Commit { id: id()?, members }, 13))
+            }
+            3 => Ok((WALGroup::Checkpointed { through: id()? }, 9)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid write group tag: {other}"),
+            )),
+        }
+    }
+}
+
+/// Returns the transactions of `entries` that still need to be applied, in order.
+///
+/// Members of write groups that were not completely written are removed, as are the
+/// entries retired by checkpoint markers, the markers themselves and group commit entries.
+pub(crate) fn entries_to_apply(entries: Vec<WALEntry>) -> Vec<WALEntry> {
+    // Groups are checked first: a group stays complete when some of its members have since
+    // been retired by a checkpoint of their column family
+    discard_checkpointed(discard_incomplete_groups(entries))
+}
+
+/// Removes the entries of write groups that were not completely written, along with the
+/// group commit entries. Checkpoint markers are kept.
+///
+/// A group is complete when its commit entry follows all of its members.
+fn discard_incomplete_groups(entries: Vec<WALEntry>) -> Vec<WALEntry> {
+    let mut members_seen: HashMap<u64, u32> = HashMap::new();
+    let mut complete = HashSet::new();
+    for entry in &entries {
+        match entry.group {
+            WALGroup::None | WALGroup::Checkpointed { .. } => {}
+            WALGroup::Member { id } => *members_seen.entry(id).or_default() += 1,
+            WALGroup::Commit { id, members } => {
+                if members_seen.get(&id) == Some(&members) {
+                    complete.insert(id);
+                }
+            }
+        }
+    }
+
+    entries
+        .into_iter()
+        .filter(|entry| match entry.group {
+            WALGroup::None | WALGroup::Checkpointed { .. } => true,
+            WALGroup::Member { id } => {
+                #[cfg(feature = "logging")]
+                if !complete.contains(&id) {
+                    log::warn!(
+                        "Discarding WAL entry {} for column family '{}': write group {id} is incomplete",
+                        entry.sequence,
+                        entry.cf_name
+                    );
+                }
+                complete.contains(&id)
+            }
+            WALGroup::Commit { .. } => false,
+        })
+        .collect()
+}
+
+/// Removes checkpoint markers along with the entries they retire.
+fn discard_checkpointed(entries: Vec<WALEntry>) -> Vec<WALEntry> {
+    let markers: Vec<(String, u64, u64)> = entries
+        .iter()
+        .filter_map(|entry| match entry.group {
+            WALGroup::Checkpointed { through } => {
+                Some((entry.cf_name.clone(), entry.cf_generation, through))
+            }
+            _ => None,
+        })
+        .collect();
+
+    entries
+        .into_iter()
+        .filter(|entry| match entry.group {
+            WALGroup::Checkpointed { .. } => false,
+            _ => !markers.iter().any(|(cf_name, generation, through)| {
+                *cf_name == entry.cf_name
+                    && *generation == entry.cf_generation
+                    && entry.sequence <= *through
+            }),
+        })
+        .collect()
 }
 
 impl WALTransactionPayload {
@@ -205,6 +543,7 @@ impl WALTransactionPayload {
         let durability_byte = match self.durability {
             Durability::None => 0,
             Durability::Immediate => 1,
+            Durability::Eventual => 2,
         };
         buf.push(durability_byte);
     }
@@ -327,6 +666,7 @@ impl WALTransactionPayload {
         let durability = match data[offset] {
             0 => Durability::None,
             1 => Durability::Immediate,
+            2 => Durability::Eventual,
             other => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -352,6 +692,7 @@ impl WALTransactionPayload {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::column_family::wal::journal::WAL_VERSION;
 
     #[test]
     fn test_entry_serialization_round_trip() {
@@ -366,20 +707,188 @@ mod tests {
         let entry = WALEntry {
             sequence: 42,
             cf_name: "test_cf".to_string(),
+            cf_generation: 3,
             transaction_id: 100,
+            group: WALGroup::Member { id: 7 },
             payload,
         };
 
-        let bytes = entry.to_bytes();
-        let (decoded, len) = WALEntry::from_bytes(&bytes).unwrap();
+        let bytes = entry.to_bytes(WAL_VERSION);
+        let (decoded, len) = WALEntry::from_bytes(&bytes, WAL_VERSION).unwrap();
 
         assert_eq!(len, bytes.len());
         assert_eq!(decoded.sequence, entry.sequence);
         assert_eq!(decoded.cf_name, entry.cf_name);
+        assert_eq!(decoded.cf_generation, entry.cf_generation);
         assert_eq!(decoded.transaction_id, entry.transaction_id);
+        assert_eq!(decoded.group, entry.group);
         assert_eq!(decoded.payload, entry.payload);
     }
 
+    #[test]
+    fn test_sealed_payload_round_trip() {
+        /// Seals the payloads of "vault" by flipping their bits
+        struct FlipCipher;
+
+        impl PayloadCipher for FlipCipher {
+            fn seal(&self, cf_name: &str, _: u64, _: &[u8], payload: &[u8]) -> Option<Vec<u8>> {
+                (cf_name == "vault").then(|| payload.iter().map(|byte| !byte).collect())
+            }
+
+            fn open(&self, _: &str, _: u64, _: &[u8], sealed: &[u8]) -> io::Result<Vec<u8>> {
+                Ok(sealed.iter().map(|byte| !byte).collect())
+            }
+        }
+
+        let mut entry = WALEntry::new(
+            "vault".to_string(),
+            2,
+            7,
+            WALTransactionPayload {
+                user_root: Some((PageNumber::new(0, 1, 0), 0xfeed, 100)),
+                system_root: None,
+                freed_pages: vec![PageNumber::new(0, 2, 0)],
+                allocated_pages: vec![],
+                durability: Durability::Immediate,
+            },
+        );
+        entry.sequence = 3;
+
+        let plain = entry.to_bytes(WAL_VERSION);
+        let sealed = entry.to_bytes_with(WAL_VERSION, Some(&FlipCipher));
+        // The sealed payload is preceded by its length
+        assert_eq!(sealed.len(), plain.len() + 4);
+        assert_ne!(sealed[plain.len() - 8..], plain[plain.len() - 8..]);
+
+        let (decoded, len) =
+            WALEntry::from_bytes_with(&sealed, WAL_VERSION, Some(&FlipCipher)).unwrap();
+        assert_eq!(len, sealed.len());
+        assert_eq!(decoded, entry);
+
+        // A sealed payload cannot be read without a cipher
+        assert!(WALEntry::from_bytes(&sealed, WAL_VERSION).is_err());
+
+        // Entries of column families the cipher does not encrypt are left plain
+        entry.cf_name = "logs".to_string();
+        assert_eq!(
+            entry.to_bytes_with(WAL_VERSION, Some(&FlipCipher)),
+            entry.to_bytes(WAL_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_version_1_entry_decodes_as_generation_zero() {
+        let payload = WALTransactionPayload {
+            user_root: None,
+            system_root: None,
+            freed_pages: vec![],
+            allocated_pages: vec![],
+            durability: Durability::Immediate,
+        };
+
+        let entry = WALEntry {
+            sequence: 5,
+            cf_name: "jobs".to_string(),
+            cf_generation: 9,
+            transaction_id: 12,
+            group: WALGroup::None,
+            payload,
+        };
+
+        let bytes = entry.to_bytes(1);
+        assert_eq!(bytes.len() + 8, entry.to_bytes(2).len());
+
+        let (decoded, len) = WALEntry::from_bytes(&bytes, 1).unwrap();
+        assert_eq!(len, bytes.len());
+        assert_eq!(decoded.cf_name, "jobs");
+        assert_eq!(decoded.cf_generation, 0);
+        assert_eq!(decoded.transaction_id, 12);
+    }
+
+    #[test]
+    fn test_version_2_entry_decodes_without_group() {
+        let mut entry = WALEntry::group_commit(4, 2);
+        entry.sequence = 8;
+
+        let bytes = entry.to_bytes(2);
+        assert_eq!(bytes.len() + 13, entry.to_bytes(4).len());
+
+        let (decoded, len) = WALEntry::from_bytes(&bytes, 2).unwrap();
+        assert_eq!(len, bytes.len());
+        assert_eq!(decoded.group, WALGroup::None);
+
+        let bytes = entry.to_bytes(WAL_VERSION);
+        let (decoded, _) = WALEntry::from_bytes(&bytes, WAL_VERSION).unwrap();
+        assert_eq!(decoded, entry);
+    }
+
+    #[test]
+    fn test_discard_incomplete_groups() {
+        let entry = |sequence, group| {
+            let mut entry = WALEntry::group_commit(0, 0);
+            entry.sequence = sequence;
+            entry.cf_name = format!("cf_{sequence}");
+            entry.group = group;
+            entry
+        };
+
+        let entries = vec![
+            entry(1, WALGroup::None),
+            entry(2, WALGroup::Member { id: 1 }),
+            entry(3, WALGroup::Member { id: 1 }),
+            entry(4, WALGroup::Commit { id: 1, members: 2 }),
+            // Only one of its two members was written before the commit entry
+            entry(5, WALGroup::Member { id: 2 }),
+            entry(6, WALGroup::Commit { id: 2, members: 2 }),
+            entry(7, WALGroup::None),
+            // The commit entry was never written
+            entry(8, WALGroup::Member { id: 3 }),
+        ];
+
+        let sequences: Vec<u64> = discard_incomplete_groups(entries)
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect();
+        assert_eq!(sequences, vec![1, 2, 3, 7]);
+    }
+
+    #[test]
+    fn test_checkpoint_markers_retire_entries() {
+        let entry = |sequence, cf_name: &str, generation, group| {
+            let mut entry = WALEntry::group_commit(0, 0);
+            entry.sequence = sequence;
+            entry.cf_name = cf_name.to_string();
+            entry.cf_generation = generation;
+            entry.group = group;
+            entry
+        };
+
+        let mut marker = WALEntry::checkpointed("hot".to_string(), 2, 5);
+        marker.sequence = 7;
+        let bytes = marker.to_bytes(WAL_VERSION);
+        let (decoded, _) = WALEntry::from_bytes(&bytes, WAL_VERSION).unwrap();
+        assert_eq!(decoded, marker);
+
+        let entries = vec![
+            entry(1, "hot", 2, WALGroup::None),
+            entry(2, "cold", 1, WALGroup::None),
+            // A group spanning both column families stays complete once "hot" is retired
+            entry(3, "hot", 2, WALGroup::Member { id: 3 }),
+            entry(4, "cold", 1, WALGroup::Member { id: 3 }),
+            entry(5, "", 0, WALGroup::Commit { id: 3, members: 2 }),
+            // An earlier incarnation of "hot" is not retired by the marker
+            entry(6, "hot", 1, WALGroup::None),
+            marker,
+            entry(8, "hot", 2, WALGroup::None),
+        ];
+
+        let sequences: Vec<u64> = entries_to_apply(entries)
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect();
+        assert_eq!(sequences, vec![2, 4, 6, 8]);
+    }
+
     #[test]
     fn test_payload_serialization_round_trip() {
         let payload = WALTransactionPayload {
@@ -1,25 +1,34 @@
-use super::entry::WALEntry;
+use super::entry::{PayloadCipher, WALEntry, WALGroup};
+use crate::StorageBackend;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::tree_store::file_backend::FileBackend;
-use crate::StorageBackend;
 #[cfg(not(target_arch = "wasm32"))]
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
 use std::io;
-#[cfg(not(target_arch = "wasm32"))]
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::{Arc, Condvar, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::{Duration, Instant};
 
 /// Magic number for WAL file identification.
 pub(crate) const WAL_MAGIC: &[u8; 8] = b"REDB-WAL";
 
 /// Current WAL format version.
-pub(crate) const WAL_VERSION: u8 = 1;
+///
+/// Version 2 adds the column family generation to each entry, version 3 the write group it
+/// belongs to, version 4 the markers written by checkpoints of a single column family, and
+/// version 5 payloads sealed with the key of an encrypted column family.
+pub(crate) const WAL_VERSION: u8 = 5;
+
+/// Oldest WAL format version that can still be read and replayed.
+const MIN_READABLE_WAL_VERSION: u8 = 1;
 
 /// Size of the WAL file header in bytes.
 pub(crate) const WAL_HEADER_SIZE: usize = 512;
 
+/// Number of digits in the number of a segment file, as in `db.wal.000001`.
+const SEGMENT_NUMBER_DIGITS: usize = 6;
+
 /// Batching window for leader-based group commit (microseconds)
 /// Leader spins for this duration to collect additional transactions before fsync
 /// Tuned for balance: 100μs = low latency, 300μs = balanced, 500μs+ = max batching
@@ -40,15 +49,65 @@ const GROUP_COMMIT_WINDOW_MICROS: u64 = 0;
 ///
 /// Single transaction: ~200-300 ops/sec (limited by fsync ~3-5ms)
 /// Concurrent transactions: 30-50K+ ops/sec (20-200 txns batched per fsync)
+///
+/// **Segments:** A segmented WAL writes its entries into numbered files next to the base
+/// path (`db.wal.000001`, `db.wal.000002`, ...), each with its own header. Appends go to the
+/// newest segment, and a new one is started when an append would grow it past the segment
+/// size. Sequence numbers run on across segments, and truncating the WAL deletes whole
+/// segments instead of rewriting a file.
 pub(crate) struct WALJournal {
-    backend: Arc<dyn StorageBackend>,
+    /// The files holding the entries, oldest first. Appends go to the last one, the active
+    /// segment. A WAL that is not segmented has a single one.
+    segments: RwLock<Vec<Segment>>,
+    /// Where segment files are created and when a new one is started, for a segmented WAL
+    rotation: Option<Rotation>,
     sequence_counter: Arc<AtomicU64>,
     /// Tracks the last sequence number that has been fsynced
     last_synced: Arc<(Mutex<u64>, Condvar)>,
     /// Leader election flag - true when a transaction is performing group sync
     sync_in_progress: AtomicBool,
-    /// Mutex to ensure atomic append operations (len + write)
+    /// Number of successful fsyncs made to commit entries
+    syncs: AtomicU64,
+    /// Mutex to ensure atomic append operations (sequence assignment + len + write)
     append_lock: Mutex<()>,
+    /// Held shared by commits from WAL append until the transaction is visible in memory,
+    /// and exclusively by checkpoints so they never truncate an entry whose commit is
+    /// still in flight
+    commit_gate: RwLock<()>,
+    /// Format version of the entries in the backend. Stays at the on-disk version of an
+    /// existing WAL until the next truncate, so appended entries match the ones before them.
+    format_version: AtomicU8,
+    /// Seals the payloads of entries for encrypted column families, and opens them again
+    cipher: Option<Arc<dyn PayloadCipher>>,
+    /// Wakes async waiters whenever `last_synced` changes or a sync leader steps down
+    #[cfg(feature = "async")]
+    synced_notify: tokio::sync::Notify,
+}
+
+/// The leader flag of an async sync, handed to the blocking task that performs the sync.
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+struct SyncLeader(Option<Arc<WALJournal>>);
+
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+impl SyncLeader {
+    fn sync(mut self) -> io::Result<()> {
+        self.0.take().unwrap().perform_group_sync()
+    }
+}
+
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+impl Drop for SyncLeader {
+    fn drop(&mut self) {
+        if let Some(journal) = self.0.take() {
+            {
+                let (lock, cvar) = &*journal.last_synced;
+                let _synced = lock.lock().unwrap();
+                journal.sync_in_progress.store(false, Ordering::Release);
+                cvar.notify_all();
+            }
+            journal.synced_notify.notify_waiters();
+        }
+    }
 }
 
 /// Header structure for the WAL file.
@@ -98,7 +157,7 @@ impl WALHeader {
         }
 
         let version = buf[8];
-        if version != WAL_VERSION {
+        if !(MIN_READABLE_WAL_VERSION..=WAL_VERSION).contains(&version) {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("unsupported WAL version: {version}"),
@@ -127,6 +186,139 @@ impl WALHeader {
     }
 }
 
+/// Where the valid entries found by scanning a WAL end.
+struct WALScan {
+    /// Offset just past the last valid entry, within its segment.
+    valid_len: u64,
+    /// Number of valid entries.
+    valid_entries: usize,
+}
+
+/// A file of the WAL.
+struct Segment {
+    /// Number in the file name, or 0 for a WAL that is not segmented.
+    number: u64,
+    backend: Arc<dyn StorageBackend>,
+}
+
+/// Naming and size of the segment files of a segmented WAL.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+struct Rotation {
+    /// Path the segment numbers are appended to.
+    base: PathBuf,
+    /// Size past which appends start a new segment.
+    segment_size: u64,
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+impl Rotation {
+    /// Returns the path of segment `number`.
+    fn path(&self, number: u64) -> PathBuf {
+        let mut path = self.base.clone().into_os_string();
+        path.push(format!(".{number:0SEGMENT_NUMBER_DIGITS$}"));
+        PathBuf::from(path)
+    }
+
+    /// Returns the numbers of the existing segment files, in ascending order.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn discover(&self) -> io::Result<Vec<u64>> {
+        let Some(prefix) = self.base.file_name().and_then(|name| name.to_str()) else {
+            return Ok(vec![]);
+        };
+        let dir = match self.base.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let mut numbers = vec![];
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let number = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(prefix)?.strip_prefix('.'))
+                .filter(|digits| {
+                    digits.len() >= SEGMENT_NUMBER_DIGITS
+                        && digits.bytes().all(|byte| byte.is_ascii_digit())
+                })
+                .and_then(|digits| digits.parse::<u64>().ok());
+            numbers.extend(number);
+        }
+        numbers.sort_unstable();
+        Ok(numbers)
+    }
+
+    /// Creates segment `number` holding only `header`, durably.
+    fn create(&self, number: u64, header: &WALHeader) -> io::Result<Arc<dyn StorageBackend>> {
+        let path = self.path(number);
+        let backend = open_file(&path)?;
+        backend.set_len(WAL_HEADER_SIZE as u64)?;
+        backend.write(0, &header.to_bytes())?;
+        backend.sync_data()?;
+        // The new file must survive a crash along with the entries synced into it
+        sync_parent_dir(&path)?;
+        Ok(backend)
+    }
+
+    /// Deletes the files of `segments`, in order.
+    fn remove(&self, segments: Vec<Segment>) -> io::Result<()> {
+        for segment in segments {
+            drop(segment.backend);
+            remove_file(&self.path(segment.number))?;
+        }
+        Ok(())
+    }
+}
+
+/// Opens the file at `path` as a backend, creating it if needed.
+#[cfg(not(target_arch = "wasm32"))]
+fn open_file(path: &Path) -> io::Result<Arc<dyn StorageBackend>> {
+    #[allow(clippy::suspicious_open_options)]
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(path)?;
+
+    Ok(Arc::new(FileBackend::new(file).map_err(|e| {
+        io::Error::other(format!("Failed to create FileBackend: {e}"))
+    })?))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn open_file(_path: &Path) -> io::Result<Arc<dyn StorageBackend>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "segmented WAL files are not supported on this platform",
+    ))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_file(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remove_file(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Makes the creation of the file at `path` durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::File::open(dir)?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
 impl WALJournal {
     /// Creates a new WAL journal with the given storage backend.
     ///
@@ -148,11 +340,22 @@ impl WALJournal {
     /// let journal = WALJournal::new(Arc::new(backend))?;
     /// ```
     pub(crate) fn new(backend: Arc<dyn StorageBackend>) -> io::Result<Self> {
+        let header = Self::init_header(&backend)?;
+        Ok(Self::with_segments(
+            vec![Segment { number: 0, backend }],
+            None,
+            &header,
+        ))
+    }
+
+    /// Writes the initial header to an empty backend, or reads the header of an existing one.
+    fn init_header(backend: &Arc<dyn StorageBackend>) -> io::Result<WALHeader> {
         // Check if backend is new (empty)
         let backend_len = backend.len()?;
         let header = if backend_len == 0 {
             // New backend - write initial header
             let header = WALHeader::new();
+            backend.set_len(WAL_HEADER_SIZE as u64)?;
             backend.write(0, &header.to_bytes())?;
             backend.sync_data()?;
             header
@@ -160,37 +363,96 @@ impl WALJournal {
             // Existing backend - read and validate header
             let mut header_buf = [0u8; WAL_HEADER_SIZE];
             backend.read(0, &mut header_buf)?;
-            WALHeader::from_bytes(&header_buf)?
+            let mut header = WALHeader::from_bytes(&header_buf)?;
+
+            // An older WAL with no entries can be upgraded in place
+            if header.version < WAL_VERSION && backend_len <= WAL_HEADER_SIZE as u64 {
+                header.version = WAL_VERSION;
+                backend.write(0, &header.to_bytes())?;
+                backend.sync_data()?;
+            }
+            header
         };
+        Ok(header)
+    }
 
-        Ok(Self {
-            backend,
+    /// Creates a journal over `segments`, continuing from the header of the last one.
+    fn with_segments(
+        segments: Vec<Segment>,
+        rotation: Option<Rotation>,
+        header: &WALHeader,
+    ) -> Self {
+        Self {
+            segments: RwLock::new(segments),
+            rotation,
             sequence_counter: Arc::new(AtomicU64::new(header.latest_seq)),
             last_synced: Arc::new((Mutex::new(header.latest_seq), Condvar::new())),
             sync_in_progress: AtomicBool::new(false),
+            syncs: AtomicU64::new(0),
             append_lock: Mutex::new(()),
-        })
+            commit_gate: RwLock::new(()),
+            format_version: AtomicU8::new(header.version),
+            cipher: None,
+            #[cfg(feature = "async")]
+            synced_notify: tokio::sync::Notify::new(),
+        }
     }
 
-    /// Opens an existing WAL file or creates a new one (native platforms only).
+    /// Opens an existing WAL or creates a new one at `path` (native platforms only).
+    ///
+    /// Segment files next to `path` are discovered and opened in order. Without them, a
+    /// `segment_size` starts a segmented WAL, taking over the file at `path` as its first
+    /// segment if one exists; otherwise the WAL is the single file at `path`. A WAL that has
+    /// been segmented stays segmented, and with no `segment_size` its active segment is never
+    /// rolled over.
     ///
-    /// This is a convenience method for native platforms that wraps a `FileBackend`.
     /// For WASM or custom backends, use `WALJournal::new()` directly.
     #[cfg(not(target_arch = "wasm32"))]
-    pub(crate) fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let path = path.as_ref();
-        #[allow(clippy::suspicious_open_options)]
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(path)?;
+    pub(crate) fn open<P: AsRef<Path>>(path: P, segment_size: Option<u64>) -> io::Result<Self> {
+        let rotation = Rotation {
+            base: path.as_ref().to_path_buf(),
+            segment_size: segment_size.unwrap_or(u64::MAX),
+        };
+        let mut numbers = rotation.discover()?;
+        if numbers.is_empty() {
+            if segment_size.is_none() {
+                return Self::new(open_file(&rotation.base)?);
+            }
+            if rotation.base.exists() {
+                fs::rename(&rotation.base, rotation.path(1))?;
+            }
+            numbers.push(1);
+        }
 
-        let backend = Arc::new(
-            FileBackend::new(file)
-                .map_err(|e| io::Error::other(format!("Failed to create FileBackend: {e}")))?,
-        );
-        Self::new(backend)
+        let mut segments = numbers
+            .into_iter()
+            .map(|number| {
+                let backend = open_file(&rotation.path(number))?;
+                Ok(Segment { number, backend })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // A crash while starting a segment can leave it without a complete header. It holds
+        // no entries yet, since its header is synced before anything is written to it.
+        while segments.len() > 1
+            && Self::read_backend_header(&segments.last().unwrap().backend).is_err()
+        {
+            let incomplete = segments.pop().unwrap();
+            rotation.remove(vec![incomplete])?;
+        }
+
+        let header = Self::init_header(&segments.last().unwrap().backend)?;
+        Ok(Self::with_segments(segments, Some(rotation), &header))
+    }
+
+    /// Sets the cipher that seals the payloads of entries for encrypted column families.
+    ///
+    /// Must be set before the WAL is recovered, so that the sealed payloads can be read.
+    #[cfg(all(feature = "encryption", not(target_arch = "wasm32")))]
+    #[must_use]
+    pub(crate) fn with_cipher(mut self, cipher: Arc<dyn PayloadCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
     }
 
     /// Appends a transaction entry to the WAL (without fsync).
@@ -198,33 +460,180 @@ impl WALJournal {
     /// Returns the assigned sequence number.
     /// Call `wait_for_sync(sequence)` to wait until this entry is durable.
     pub(crate) fn append(&self, entry: &mut WALEntry) -> io::Result<u64> {
-        // Assign sequence number
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "wal_append",
+            cf = entry.cf_name.as_str(),
+            wal_sequence = tracing::field::Empty
+        )
+        .entered();
+
+        // Assign the sequence number under the append lock, so that every sequence number
+        // visible in the counter belongs to an entry that has already been written
+        let _guard = self.append_lock.lock().unwrap();
         let seq = self.sequence_counter.fetch_add(1, Ordering::SeqCst) + 1;
         entry.sequence = seq;
+        #[cfg(feature = "tracing")]
+        span.record("wal_sequence", seq);
+
+        let wire_data = self.encode(entry);
+        if let Err(err) = self.write_at_end(seq, &wire_data) {
+            // Give the sequence number back, so the next entry follows the last one written
+            self.sequence_counter.store(seq - 1, Ordering::SeqCst);
+            return Err(err);
+        }
+
+        Ok(seq)
+    }
+
+    /// Appends the entries of a write group, followed by the entry that commits the group,
+    /// in a single write (without fsync).
+    ///
+    /// The group is identified by the sequence number of its first member, which is unique
+    /// within the WAL. Returns the sequence number of the commit entry; once it is synced,
+    /// the whole group is durable.
+    pub(crate) fn append_group(&self, entries: &mut [WALEntry]) -> io::Result<u64> {
+        let _guard = self.append_lock.lock().unwrap();
+
+        let version = self.format_version.load(Ordering::Acquire);
+        if version < 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "write groups need WAL format version 3, but the WAL holds version {version} entries until it is checkpointed"
+                ),
+            ));
+        }
+        let members = u32::try_from(entries.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "too many write group members")
+        })?;
+
+        let first = self.sequence_counter.load(Ordering::Acquire) + 1;
+        let mut commit = WALEntry::group_commit(first, members);
+        let mut wire_data = Vec::new();
+        for (sequence, entry) in (first..).zip(entries.iter_mut().chain([&mut commit])) {
+            entry.sequence = sequence;
+            if entry.group == WALGroup::None {
+                entry.group = WALGroup::Member { id: first };
+            }
+            wire_data.extend_from_slice(&self.encode(entry));
+        }
+
+        self.write_at_end(first, &wire_data)?;
+        self.sequence_counter
+            .store(commit.sequence, Ordering::SeqCst);
+
+        Ok(commit.sequence)
+    }
+
+    /// Appends a marker retiring the entries of `cf_name`, in generation `cf_generation`, up to
+    /// sequence `through` (without fsync).
+    ///
+    /// Returns the sequence number of the marker.
+    pub(crate) fn append_checkpointed(
+        &self,
+        cf_name: &str,
+        cf_generation: u64,
+        through: u64,
+    ) -> io::Result<u64> {
+        let version = self.format_version.load(Ordering::Acquire);
+        if version < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "checkpoint markers need WAL format version 4, but the WAL holds version {version} entries until it is checkpointed"
+                ),
+            ));
+        }
+
+        self.append(&mut WALEntry::checkpointed(
+            cf_name.to_string(),
+            cf_generation,
+            through,
+        ))
+    }
 
+    /// Serializes an entry into its wire format: length (4) + data (variable) + crc (4).
+    fn encode(&self, entry: &WALEntry) -> Vec<u8> {
         // Serialize entry using zero-cost manual serialization
-        let entry_data = entry.to_bytes();
+        let entry_data = entry.to_bytes_with(
+            self.format_version.load(Ordering::Acquire),
+            self.cipher.as_deref(),
+        );
 
         // Compute CRC32 of entry data
         let crc = crc32fast::hash(&entry_data);
 
-        // Build wire format: length (4) + data (variable) + crc (4)
         let total_len = 4 + entry_data.len() + 4;
         let mut wire_data = Vec::with_capacity(total_len);
         #[allow(clippy::cast_possible_truncation)]
         wire_data.extend_from_slice(&(total_len as u32).to_le_bytes());
         wire_data.extend_from_slice(&entry_data);
         wire_data.extend_from_slice(&crc.to_le_bytes());
+        wire_data
+    }
 
+    /// Appends encoded entries, the first with sequence number `first_seq`, to the active
+    /// segment. Must be called with the append lock held.
+    ///
+    /// The entries are always written to a single segment, so a write group never spans two.
+    /// If the write fails, for example because the disk is full, the segment is truncated back
+    /// to its previous end, so that the next append does not follow a partial entry. Should
+    /// that fail as well, recovery still stops at the partial entry, since its CRC does not
+    /// match.
+    fn write_at_end(&self, first_seq: u64, wire_data: &[u8]) -> io::Result<()> {
         // Append to backend (buffered write, no fsync yet)
         // Note: We don't update the header here to allow concurrent appends.
         // The header will be updated during checkpoint/truncate operations.
-        // Use append_lock to make len() + write() atomic
-        let _guard = self.append_lock.lock().unwrap();
-        let offset = self.backend.len()?;
-        self.backend.write(offset, &wire_data)?;
+        let mut backend = self.active_backend();
+        let mut offset = backend.len()?;
+        if let Some(rotation) = &self.rotation
+            && offset > WAL_HEADER_SIZE as u64
+            && offset + wire_data.len() as u64 > rotation.segment_size
+        {
+            backend = self.roll(rotation, first_seq)?;
+            offset = WAL_HEADER_SIZE as u64;
+        }
+        let result = backend.write(offset, wire_data);
+        if result.is_err() {
+            let _ = backend.set_len(offset);
+        }
+        result
+    }
 
-        Ok(seq)
+    /// Starts a new active segment whose first entry is `first_seq`. Must be called with the
+    /// append lock held.
+    ///
+    /// The previous segment is synced first, so syncing the new one makes every entry
+    /// written so far durable.
+    fn roll(&self, rotation: &Rotation, first_seq: u64) -> io::Result<Arc<dyn StorageBackend>> {
+        self.active_backend().sync_data()?;
+
+        let mut header = WALHeader::new();
+        header.version = self.format_version.load(Ordering::Acquire);
+        header.oldest_seq = first_seq;
+        header.latest_seq = first_seq - 1;
+
+        let mut segments = self.segments.write().unwrap();
+        let number = segments.last().unwrap().number + 1;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            segment = number,
+            wal_sequence = first_seq,
+            "starting WAL segment"
+        );
+        let backend = rotation.create(number, &header)?;
+        segments.push(Segment {
+            number,
+            backend: Arc::clone(&backend),
+        });
+        Ok(backend)
+    }
+
+    /// Returns the backend of the segment appends go to.
+    fn active_backend(&self) -> Arc<dyn StorageBackend> {
+        let segments = self.segments.read().unwrap();
+        Arc::clone(&segments.last().unwrap().backend)
     }
 
     /// Waits until the specified sequence number has been synced to disk.
@@ -266,14 +675,16 @@ impl WALJournal {
                 let (lock, cvar) = &*self.last_synced;
                 let mut synced = lock.lock().unwrap();
 
-                // Wait for leader to notify us
-                // The leader will wake all waiters when it updates last_synced
-                while *synced < sequence {
+                // Wait for leader to notify us. The leader clears its flag under this lock
+                // before waking waiters, so if it finished without covering our sequence we
+                // see the flag cleared and go back to try to lead the next sync ourselves.
+                while *synced < sequence && self.sync_in_progress.load(Ordering::Acquire) {
                     synced = cvar.wait(synced).unwrap();
                 }
 
-                // We're synced - done
-                return Ok(());
+                if *synced >= sequence {
+                    return Ok(());
+                }
             }
         }
     }
@@ -296,56 +707,258 @@ impl WALJournal {
             }
         }
 
-        // Fsync all pending writes
-        self.backend.sync_data()?;
-
-        // Update last_synced and wake all waiting followers
-        let current_seq = self.sequence_counter.load(Ordering::Acquire);
+        // Everything up to this sequence has been written, so the fsync covers it. Segments
+        // before the active one were synced when it was started.
+        let current_seq = self.written_sequence();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::enabled!(tracing::Level::DEBUG)
+            .then(|| {
+                // Takes the sync lock, so only when someone is listening
+                let batch_size = current_seq.saturating_sub(self.synced_sequence());
+                tracing::debug_span!("wal_fsync", wal_sequence = current_seq, batch_size)
+            })
+            .map(tracing::Span::entered);
+        let result = self.active_backend().sync_data();
+
+        // Update last_synced, release the leader flag so the next transaction can become
+        // leader, and wake all waiting followers. The flag is released under the lock so a
+        // follower can't miss the wakeup, including when the fsync failed.
         {
             let (lock, cvar) = &*self.last_synced;
             let mut synced = lock.lock().unwrap();
-            *synced = current_seq;
+            if result.is_ok() {
+                *synced = (*synced).max(current_seq);
+                self.syncs.fetch_add(1, Ordering::Relaxed);
+            }
+            self.sync_in_progress.store(false, Ordering::Release);
             cvar.notify_all();
         }
+        #[cfg(feature = "async")]
+        self.synced_notify.notify_waiters();
 
-        // Release leader flag so next transaction can become leader
-        self.sync_in_progress.store(false, Ordering::Release);
+        result
+    }
 
-        Ok(())
+    /// Waits until the specified sequence number has been synced to disk, without blocking
+    /// the calling thread.
+    ///
+    /// Takes part in the same leader-based group commit as [`Self::wait_for_sync`]: a waiter
+    /// that finds no sync in progress becomes the leader and runs the fsync on tokio's
+    /// blocking thread pool, and every other waiter, sync or async, is woken when it
+    /// completes. Must be called from within a tokio runtime.
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    pub(crate) async fn wait_for_sync_async(self: Arc<Self>, sequence: u64) -> io::Result<()> {
+        loop {
+            // Register for the wakeup before checking, so a sync finishing in between is seen
+            let notified = self.synced_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.synced_sequence() >= sequence {
+                return Ok(());
+            }
+
+            if self
+                .sync_in_progress
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                // Step down if the runtime drops the task without running it
+                let leader = SyncLeader(Some(Arc::clone(&self)));
+                tokio::task::spawn_blocking(move || leader.sync())
+                    .await
+                    .map_err(io::Error::other)??;
+            } else {
+                notified.await;
+            }
+        }
+    }
+
+    /// Returns the highest sequence number whose entry has been fully written.
+    fn written_sequence(&self) -> u64 {
+        let _guard = self.append_lock.lock().unwrap();
+        self.sequence_counter.load(Ordering::Acquire)
+    }
+
+    /// Blocks checkpoints from truncating the WAL while a commit is between appending its
+    /// entry and making the transaction visible in memory.
+    pub(crate) fn commit_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.commit_gate.read().unwrap()
+    }
+
+    /// Waits for in-flight commits to finish and blocks new ones from appending, so a
+    /// checkpoint sees every WAL entry applied in memory before it truncates.
+    pub(crate) fn exclusive_guard(&self) -> RwLockWriteGuard<'_, ()> {
+        self.commit_gate.write().unwrap()
     }
 
     /// Syncs all pending writes to disk immediately (bypasses group commit).
     pub(crate) fn sync(&self) -> io::Result<()> {
-        self.backend.sync_data()?;
+        let current_seq = self.written_sequence();
+        self.active_backend().sync_data()?;
+        self.syncs.fetch_add(1, Ordering::Relaxed);
 
         // Update last_synced to current sequence
-        let current_seq = self.sequence_counter.load(Ordering::Acquire);
-        let (lock, cvar) = &*self.last_synced;
-        let mut synced = lock.lock().unwrap();
-        *synced = current_seq;
-        cvar.notify_all();
+        {
+            let (lock, cvar) = &*self.last_synced;
+            let mut synced = lock.lock().unwrap();
+            *synced = (*synced).max(current_seq);
+            cvar.notify_all();
+        }
+        #[cfg(feature = "async")]
+        self.synced_notify.notify_waiters();
 
         Ok(())
     }
 
+    /// Waits for in-flight commits to become visible, then makes every entry written so far
+    /// durable, joining a group sync if one is in progress.
+    ///
+    /// Returns the highest sequence number known to be durable.
+    pub(crate) fn flush(&self) -> io::Result<u64> {
+        // Commits hold the commit gate from appending their entry until they are visible, and
+        // write groups hold it for the whole group, so taking it once is a barrier for them
+        drop(self.exclusive_guard());
+        self.wait_for_sync(self.written_sequence())?;
+        Ok(self.synced_sequence())
+    }
+
     /// Reads all entries with sequence numbers >= `start_seq`.
     pub(crate) fn read_from(&self, start_seq: u64) -> io::Result<Vec<WALEntry>> {
-        Self::read_entries_from_backend(&self.backend, start_seq)
+        let segments = self.segments.read().unwrap();
+        let mut entries = Vec::new();
+        Self::scan_segments(&segments, self.cipher.as_deref(), |entry, _| {
+            if entry.sequence >= start_seq {
+                entries.push(entry);
+            }
+            Ok::<_, io::Error>(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Reads all entries for recovery, discarding an incomplete or corrupt tail.
+    ///
+    /// Entries are passed to `visit` one at a time, in sequence order, together with the
+    /// offset just past them, so the WAL is read once and only one entry is held in memory
+    /// at a time. Offsets count the bytes of all segments before the entry's own. An error
+    /// from `visit` stops the scan and is returned.
+    ///
+    /// A crash during an append can leave a partially written entry at the end of the WAL.
+    /// Reading stops at the first entry that is truncated or fails its CRC, and the WAL is
+    /// truncated to the last valid entry so later appends are not written after the damaged
+    /// data; any later segments are deleted. With `strict` set, a damaged tail is reported
+    /// as an error instead and the WAL is left untouched; the valid entries before it have
+    /// been visited by then.
+    pub(crate) fn recover<E: From<io::Error>>(
+        &self,
+        strict: bool,
+        visit: impl FnMut(WALEntry, u64) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut segments = self.segments.write().unwrap();
+        let (index, scan) = Self::scan_segments(&segments, self.cipher.as_deref(), visit)?;
+        let backend = &segments[index].backend;
+        let mut discarded_bytes = backend.len()? - scan.valid_len;
+        for later in &segments[index + 1..] {
+            discarded_bytes += later.backend.len()?;
+        }
+        if discarded_bytes == 0 {
+            return Ok(());
+        }
+
+        if strict {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "WAL has {discarded_bytes} bytes of incomplete or corrupt data after {} valid entries",
+                    scan.valid_entries
+                ),
+            )
+            .into());
+        }
+
+        #[cfg(feature = "logging")]
+        log::warn!(
+            "Recovered {} valid WAL entries; discarding {discarded_bytes} bytes of incomplete or corrupt data",
+            scan.valid_entries
+        );
+
+        backend.set_len(scan.valid_len)?;
+        backend.sync_data()?;
+        let later = segments.split_off(index + 1);
+        if let Some(rotation) = &self.rotation {
+            rotation.remove(later)?;
+        }
+        Ok(())
     }
 
     /// Helper method to read entries from a backend (used by both sync and async journals).
     ///
-    /// This is a static method so it can be called by `AsyncWALJournal`.
+    /// This is a static method so it can be called by `AsyncWALJournal`. Sealed payloads
+    /// are opened with `cipher`.
     pub(crate) fn read_entries_from_backend(
         backend: &Arc<dyn StorageBackend>,
         start_seq: u64,
+        cipher: Option<&dyn PayloadCipher>,
     ) -> io::Result<Vec<WALEntry>> {
+        let mut entries = Vec::new();
+        Self::scan_backend(backend, cipher, |entry, _| {
+            if entry.sequence >= start_seq {
+                entries.push(entry);
+            }
+            Ok::<_, io::Error>(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Scans the segments in order, stopping at the first truncated or corrupt entry.
+    ///
+    /// Each valid entry is passed to `visit` with the offset just past it, counting the
+    /// bytes of the segments before its own. Returns the index of the segment the scan
+    /// stopped in, which is the last one unless it found a damaged tail, and where the
+    /// valid entries end in it.
+    fn scan_segments<E: From<io::Error>>(
+        segments: &[Segment],
+        cipher: Option<&dyn PayloadCipher>,
+        mut visit: impl FnMut(WALEntry, u64) -> Result<(), E>,
+    ) -> Result<(usize, WALScan), E> {
+        let mut preceding_bytes = 0;
+        let mut valid_entries = 0;
+        for (index, segment) in segments.iter().enumerate() {
+            let mut scan = Self::scan_backend(&segment.backend, cipher, |entry, offset| {
+                visit(entry, preceding_bytes + offset)
+            })?;
+            valid_entries += scan.valid_entries;
+
+            let len = segment.backend.len()?;
+            if scan.valid_len < len || index + 1 == segments.len() {
+                scan.valid_entries = valid_entries;
+                return Ok((index, scan));
+            }
+            preceding_bytes += len;
+        }
+        unreachable!("a WAL has at least one segment")
+    }
+
+    /// Scans the backend for entries, stopping at the first truncated or corrupt one.
+    ///
+    /// Each valid entry is passed to `visit` with the offset just past it. The buffer
+    /// entries are read into is reused, so memory use is bounded by the largest entry.
+    /// An entry whose sealed payload `cipher` cannot open is an error, not a corrupt tail.
+    fn scan_backend<E: From<io::Error>>(
+        backend: &Arc<dyn StorageBackend>,
+        cipher: Option<&dyn PayloadCipher>,
+        mut visit: impl FnMut(WALEntry, u64) -> Result<(), E>,
+    ) -> Result<WALScan, E> {
         // Note: We don't check header.latest_seq here because append() doesn't update
         // the header (for performance). Instead, we scan the backend until EOF.
 
+        let version = Self::read_backend_header(backend)?.version;
+
         let backend_len = backend.len()?;
         let mut offset = WAL_HEADER_SIZE as u64;
-        let mut entries = Vec::new();
+        let mut valid_len = offset;
+        let mut valid_entries = 0;
+        let mut entry_data = Vec::new();
 
         while offset < backend_len {
             // Read entry length header
@@ -367,7 +980,7 @@ impl WALJournal {
             }
 
             // Read entry data
-            let mut entry_data = vec![0u8; data_len];
+            entry_data.resize(data_len, 0);
             backend.read(offset, &mut entry_data)?;
             offset += data_len as u64;
 
@@ -384,53 +997,98 @@ impl WALJournal {
                 break;
             }
 
-            let (entry, _) = WALEntry::from_bytes(&entry_data)?;
+            let (entry, _) = WALEntry::from_bytes_with(&entry_data, version, cipher)?;
+            valid_len = offset;
+            valid_entries += 1;
 
-            if entry.sequence >= start_seq {
-                entries.push(entry);
-            }
+            visit(entry, offset)?;
         }
 
-        Ok(entries)
+        Ok(WALScan {
+            valid_len,
+            valid_entries,
+        })
     }
 
     /// Truncates the WAL and resets the sequence counter.
+    ///
+    /// A segmented WAL starts a new segment and deletes all the others instead.
     pub(crate) fn truncate(&self, new_oldest_seq: u64) -> io::Result<()> {
-        // Truncate backend to just the header size
-        self.backend.set_len(WAL_HEADER_SIZE as u64)?;
-
-        // Write new header
+        // Write new header (always in the current format, now that no entries remain)
         let mut header = WALHeader::new();
         header.oldest_seq = new_oldest_seq;
         header.latest_seq = new_oldest_seq - 1;
-        self.backend.write(0, &header.to_bytes())?;
-        self.backend.sync_data()?;
+
+        {
+            let mut segments = self.segments.write().unwrap();
+            if let Some(rotation) = &self.rotation {
+                let number = segments.last().unwrap().number + 1;
+                let backend = rotation.create(number, &header)?;
+                // Oldest first, so a crash part way through leaves the newest entries of the
+                // remaining segments to be replayed, which are already checkpointed
+                let obsolete = std::mem::replace(&mut *segments, vec![Segment { number, backend }]);
+                rotation.remove(obsolete)?;
+            } else {
+                // Truncate backend to just the header size
+                let backend = &segments[0].backend;
+                backend.set_len(WAL_HEADER_SIZE as u64)?;
+                backend.write(0, &header.to_bytes())?;
+                backend.sync_data()?;
+            }
+        }
+        self.format_version.store(WAL_VERSION, Ordering::Release);
 
         // Update internal state
         self.sequence_counter
             .store(new_oldest_seq - 1, Ordering::SeqCst);
 
-        let (lock, cvar) = &*self.last_synced;
-        let mut synced = lock.lock().unwrap();
-        *synced = new_oldest_seq - 1;
-        cvar.notify_all();
+        {
+            let (lock, cvar) = &*self.last_synced;
+            let mut synced = lock.lock().unwrap();
+            *synced = new_oldest_seq - 1;
+            cvar.notify_all();
+        }
+        #[cfg(feature = "async")]
+        self.synced_notify.notify_waiters();
 
         Ok(())
     }
 
     /// Reads the WAL header.
     ///
-    /// Used in tests and diagnostics to inspect WAL state.
+    /// Used in tests and diagnostics to inspect WAL state. A segmented WAL returns the header
+    /// of its oldest segment.
     #[allow(dead_code)]
     pub(crate) fn read_header(&self) -> io::Result<WALHeader> {
+        Self::read_backend_header(&self.segments.read().unwrap()[0].backend)
+    }
+
+    fn read_backend_header(backend: &Arc<dyn StorageBackend>) -> io::Result<WALHeader> {
         let mut header_buf = [0u8; WAL_HEADER_SIZE];
-        self.backend.read(0, &mut header_buf)?;
+        backend.read(0, &mut header_buf)?;
         WALHeader::from_bytes(&header_buf)
     }
 
-    /// Returns the current WAL backend size in bytes.
+    /// Returns the number of fsyncs made to commit entries since the journal was opened.
+    pub(crate) fn sync_count(&self) -> u64 {
+        self.syncs.load(Ordering::Relaxed)
+    }
+
+    /// Returns the highest sequence number known to be durable, either synced or checkpointed.
+    pub(crate) fn synced_sequence(&self) -> u64 {
+        *self.last_synced.0.lock().unwrap()
+    }
+
+    /// Returns the current WAL size in bytes, across all segments.
     pub(crate) fn file_size(&self) -> io::Result<u64> {
-        self.backend.len()
+        let segments = self.segments.read().unwrap();
+        segments.iter().map(|segment| segment.backend.len()).sum()
+    }
+
+    /// Returns the number of segment files, or 1 for a WAL that is not segmented.
+    #[allow(dead_code)]
+    pub(crate) fn segment_count(&self) -> usize {
+        self.segments.read().unwrap().len()
     }
 
     /// Shuts down the WAL journal gracefully.
@@ -445,6 +1103,15 @@ impl WALJournal {
         self.sync()
     }
 
+    /// Releases the lock on the segment files, so the WAL can be opened again while handles
+    /// holding this journal are still alive. The journal must not be used afterwards.
+    pub(crate) fn close(&self) -> io::Result<()> {
+        for segment in self.segments.read().unwrap().iter() {
+            segment.backend.close()?;
+        }
+        Ok(())
+    }
+
     /// Updates the header's `latest_seq` field.
     ///
     /// This is intentionally not called during `append()` for performance reasons.
@@ -452,13 +1119,11 @@ impl WALJournal {
     /// Kept for potential future use in checkpoint optimizations.
     #[allow(dead_code)]
     fn update_header_latest_seq(&self, latest_seq: u64) -> io::Result<()> {
-        let mut header_buf = [0u8; WAL_HEADER_SIZE];
-        self.backend.read(0, &mut header_buf)?;
-
-        let mut header = WALHeader::from_bytes(&header_buf)?;
+        let backend = self.active_backend();
+        let mut header = Self::read_backend_header(&backend)?;
         header.latest_seq = latest_seq;
 
-        self.backend.write(0, &header.to_bytes())?;
+        backend.write(0, &header.to_bytes())?;
 
         Ok(())
     }
@@ -474,6 +1139,7 @@ impl Drop for WALJournal {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backends::InMemoryBackend;
     use crate::column_family::wal::entry::WALTransactionPayload;
     use tempfile::NamedTempFile;
 
@@ -482,7 +1148,7 @@ mod tests {
         let temp_file = NamedTempFile::new().unwrap();
         let path = temp_file.path();
 
-        let wal = WALJournal::open(path).unwrap();
+        let wal = WALJournal::open(path, None).unwrap();
         let header = wal.read_header().unwrap();
         assert_eq!(header.magic, *WAL_MAGIC);
         assert_eq!(header.version, WAL_VERSION);
@@ -497,7 +1163,7 @@ mod tests {
         let temp_file = NamedTempFile::new().unwrap();
         let path = temp_file.path();
 
-        let wal = Arc::new(WALJournal::open(path).unwrap());
+        let wal = Arc::new(WALJournal::open(path, None).unwrap());
 
         // Simulate concurrent writes from different column families
         let mut handles = vec![];
@@ -512,7 +1178,7 @@ mod tests {
                     durability: crate::Durability::Immediate,
                 };
 
-                let mut entry = WALEntry::new(format!("cf_{i}"), i, payload);
+                let mut entry = WALEntry::new(format!("cf_{i}"), 1, i, payload);
                 let seq = wal_clone.append(&mut entry).unwrap();
                 wal_clone.wait_for_sync(seq).unwrap();
                 seq
@@ -534,7 +1200,7 @@ mod tests {
         let temp_file = NamedTempFile::new().unwrap();
         let path = temp_file.path();
 
-        let wal = WALJournal::open(path).unwrap();
+        let wal = WALJournal::open(path, None).unwrap();
 
         for i in 0..5 {
             let payload = WALTransactionPayload {
@@ -545,7 +1211,7 @@ mod tests {
                 durability: crate::Durability::Immediate,
             };
 
-            let mut entry = WALEntry::new(format!("cf_{i}"), i, payload);
+            let mut entry = WALEntry::new(format!("cf_{i}"), 1, i, payload);
             let seq = wal.append(&mut entry).unwrap();
             wal.wait_for_sync(seq).unwrap();
         }
@@ -563,10 +1229,205 @@ mod tests {
             durability: crate::Durability::Immediate,
         };
 
-        let mut entry = WALEntry::new("cf_new".to_string(), 100, payload);
+        let mut entry = WALEntry::new("cf_new".to_string(), 1, 100, payload);
         let seq = wal.append(&mut entry).unwrap();
         assert_eq!(seq, 6);
 
         wal.shutdown().unwrap();
     }
+
+    /// Returns an entry with no roots
+    fn empty_entry(i: u64) -> WALEntry {
+        let payload = WALTransactionPayload {
+            user_root: None,
+            system_root: None,
+            freed_pages: vec![],
+            allocated_pages: vec![],
+            durability: crate::Durability::Immediate,
+        };
+        WALEntry::new(format!("cf_{i}"), 1, i, payload)
+    }
+
+    /// Appends an entry with no roots, returning its sequence number
+    fn append_empty(wal: &WALJournal, i: u64) -> u64 {
+        wal.append(&mut empty_entry(i)).unwrap()
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_wal_segments_roll_over_and_truncate() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("db.wal");
+
+        let wal = WALJournal::open(&path, Some(1024)).unwrap();
+        for i in 1..=20 {
+            assert_eq!(append_empty(&wal, i), i);
+        }
+        wal.sync().unwrap();
+        let segments = wal.segment_count();
+        assert!(segments >= 3, "{segments} segments");
+        assert_eq!(file_names(temp_dir.path())[0], "db.wal.000001");
+        assert!(!path.exists());
+        drop(wal);
+
+        // Reopening discovers the segments, and reads their entries in order
+        let wal = WALJournal::open(&path, Some(1024)).unwrap();
+        assert_eq!(wal.segment_count(), segments);
+        let sequences: Vec<u64> = wal
+            .read_from(0)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect();
+        assert_eq!(sequences, (1..=20).collect::<Vec<_>>());
+
+        // Truncating starts a new segment and deletes the others
+        wal.truncate(21).unwrap();
+        assert_eq!(wal.segment_count(), 1);
+        assert_eq!(
+            file_names(temp_dir.path()),
+            [format!("db.wal.{:06}", segments + 1)]
+        );
+        assert_eq!(wal.read_header().unwrap().oldest_seq, 21);
+        assert_eq!(append_empty(&wal, 21), 21);
+        assert_eq!(wal.read_from(0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_wal_segment_recovery_stops_at_damaged_tail() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("db.wal");
+
+        {
+            let wal = WALJournal::open(&path, Some(1024)).unwrap();
+            for i in 1..=20 {
+                append_empty(&wal, i);
+            }
+            wal.sync().unwrap();
+            assert!(wal.segment_count() >= 3);
+        }
+
+        // Tear the last entry of the first segment
+        let first = path.with_extension("wal.000001");
+        let len = fs::metadata(&first).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&first)
+            .unwrap()
+            .set_len(len - 1)
+            .unwrap();
+
+        let wal = WALJournal::open(&path, Some(1024)).unwrap();
+        let mut recovered = vec![];
+        wal.recover(false, |entry, _| {
+            recovered.push(entry.sequence);
+            Ok::<_, io::Error>(())
+        })
+        .unwrap();
+
+        // The valid entries of the first segment are kept and the later segments discarded
+        assert!(!recovered.is_empty() && recovered.len() < 20);
+        assert_eq!(recovered, (1..=recovered.len() as u64).collect::<Vec<_>>());
+        assert_eq!(wal.segment_count(), 1);
+        assert_eq!(file_names(temp_dir.path()), ["db.wal.000001"]);
+        assert_eq!(wal.read_from(0).unwrap().len(), recovered.len());
+    }
+
+    #[test]
+    fn test_wal_append_failure_leaves_no_valid_partial_entry() {
+        /// Storage that runs out of space once `budget` bytes have been written, writing what
+        /// fits before failing, and that fails to shrink while `fail_shrink` is set
+        #[derive(Debug)]
+        struct FullDisk {
+            inner: Arc<InMemoryBackend>,
+            budget: AtomicU64,
+            fail_shrink: AtomicBool,
+        }
+
+        impl StorageBackend for FullDisk {
+            fn len(&self) -> io::Result<u64> {
+                self.inner.len()
+            }
+
+            fn read(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+                self.inner.read(offset, out)
+            }
+
+            fn set_len(&self, len: u64) -> io::Result<()> {
+                if self.fail_shrink.load(Ordering::SeqCst) && len < self.inner.len()? {
+                    return Err(io::Error::other("shrink failed"));
+                }
+                self.inner.set_len(len)
+            }
+
+            fn sync_data(&self) -> io::Result<()> {
+                self.inner.sync_data()
+            }
+
+            fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+                let budget = self.budget.load(Ordering::SeqCst);
+                let fits = data
+                    .len()
+                    .min(usize::try_from(budget).unwrap_or(usize::MAX));
+                self.budget.store(budget - fits as u64, Ordering::SeqCst);
+                self.inner.write(offset, &data[..fits])?;
+                if fits < data.len() {
+                    return Err(io::ErrorKind::StorageFull.into());
+                }
+                Ok(())
+            }
+        }
+
+        let inner = Arc::new(InMemoryBackend::new());
+        let disk = Arc::new(FullDisk {
+            inner: Arc::clone(&inner),
+            budget: AtomicU64::new(u64::MAX),
+            fail_shrink: AtomicBool::new(false),
+        });
+        let wal = WALJournal::new(Arc::clone(&disk) as Arc<dyn StorageBackend>).unwrap();
+        assert_eq!(append_empty(&wal, 1), 1);
+
+        // The partial entry is cut off and its sequence number reused
+        let len = inner.len().unwrap();
+        disk.budget.store(10, Ordering::SeqCst);
+        let err = wal.append(&mut empty_entry(2)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        assert_eq!(inner.len().unwrap(), len);
+
+        disk.budget.store(u64::MAX, Ordering::SeqCst);
+        assert_eq!(append_empty(&wal, 3), 2);
+        wal.sync().unwrap();
+        let names: Vec<String> = wal
+            .read_from(0)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.cf_name)
+            .collect();
+        assert_eq!(names, ["cf_1", "cf_3"]);
+
+        // A partial entry that can't be cut off is ignored by recovery
+        let len = inner.len().unwrap();
+        disk.fail_shrink.store(true, Ordering::SeqCst);
+        disk.budget.store(10, Ordering::SeqCst);
+        assert!(wal.append(&mut empty_entry(4)).is_err());
+        assert!(inner.len().unwrap() > len);
+        drop(wal);
+
+        let wal = WALJournal::new(inner).unwrap();
+        let mut recovered = vec![];
+        wal.recover(false, |entry, _| {
+            recovered.push(entry.sequence);
+            Ok::<_, io::Error>(())
+        })
+        .unwrap();
+        assert_eq!(recovered, [1, 2]);
+    }
 }
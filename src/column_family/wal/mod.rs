@@ -11,5 +11,9 @@ pub mod checkpoint;
 pub mod config;
 pub mod entry;
 pub mod journal;
+pub mod recovery;
+pub mod stats;
 
 pub use self::config::WALConfig;
+pub use self::recovery::RecoveryProgress;
+pub use self::stats::{CheckpointReport, WALStats};
@@ -0,0 +1,274 @@
+use crate::column_family::wal::entry::{WALEntry, WALGroup};
+use crate::tree_store::{Checksum, PageNumber};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Progress is reported after this many entries have been read since the last report.
+pub(crate) const PROGRESS_INTERVAL_ENTRIES: u64 = 10_000;
+
+/// Progress is reported after this many bytes have been read since the last report.
+pub(crate) const PROGRESS_INTERVAL_BYTES: u64 = 64 * 1024 * 1024;
+
+/// How far WAL recovery has read through the journal.
+///
+/// Passed to the callback set with
+/// [`ColumnFamilyDatabaseBuilder::on_recovery_progress()`](crate::column_family::ColumnFamilyDatabaseBuilder::on_recovery_progress)
+/// every 10,000 entries or 64 MiB read, and once more when the whole journal has been read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryProgress {
+    /// Entries read so far, including those that are not replayed.
+    pub entries: u64,
+    /// Bytes of the journal read so far, including its header.
+    pub bytes: u64,
+    /// Size of the journal when recovery started.
+    ///
+    /// `bytes` reaches it on the final report, unless the journal ends with a damaged entry.
+    pub total_bytes: u64,
+    /// Time since recovery started.
+    pub elapsed: Duration,
+}
+
+/// Callback receiving [`RecoveryProgress`] reports.
+pub(crate) type RecoveryProgressFn = Box<dyn FnMut(RecoveryProgress) + Send>;
+
+/// How the WAL is recovered when a database is opened.
+#[derive(Default)]
+pub(crate) struct RecoveryOptions {
+    /// Fail instead of repairing a damaged journal or skipping entries with invalid roots.
+    pub(crate) strict: bool,
+    pub(crate) progress: Option<RecoveryProgressFn>,
+}
+
+/// The parts of a [`WALEntry`] needed to replay it. The page lists are dropped so that
+/// buffered entries stay small.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ReplayEntry {
+    pub(crate) sequence: u64,
+    pub(crate) cf_name: String,
+    pub(crate) cf_generation: u64,
+    pub(crate) transaction_id: u64,
+    pub(crate) user_root: Option<(PageNumber, Checksum, u64)>,
+    pub(crate) system_root: Option<(PageNumber, Checksum, u64)>,
+}
+
+impl From<WALEntry> for ReplayEntry {
+    fn from(entry: WALEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            cf_name: entry.cf_name,
+            cf_generation: entry.cf_generation,
+            transaction_id: entry.transaction_id,
+            user_root: entry.payload.user_root,
+            system_root: entry.payload.system_root,
+        }
+    }
+}
+
+/// What to do with an entry that is ready to be replayed.
+pub(crate) enum Replay {
+    /// Replay the entry.
+    Apply,
+    /// Leave the entry out, and keep replaying later entries for its column family.
+    Skip,
+    /// Leave the entry and every later entry for its column family out, since they build on
+    /// it.
+    Stop,
+}
+
+/// Works out, one entry at a time, which entry each column family is recovered to.
+///
+/// Replaying an entry sets its column family's roots, so only the latest entry that is
+/// applied for each column family matters. Entries are folded in as they are read: write
+/// group members are held until their commit entry is seen, and checkpoint markers retire
+/// the entries before them. This picks the same entries as
+/// [`entries_to_apply()`](super::entry::entries_to_apply) without holding the whole
+/// journal in memory.
+#[derive(Default)]
+pub(crate) struct ReplayPlan {
+    /// Members of write groups whose commit entry has not been read yet.
+    open_groups: HashMap<u64, Vec<ReplayEntry>>,
+    /// The latest entry to replay for each column family.
+    latest: HashMap<String, ReplayEntry>,
+    /// Column families whose replay was stopped, with the generation and sequence of the
+    /// entry it was stopped at.
+    stopped: HashMap<String, (u64, u64)>,
+    latest_sequence: Option<u64>,
+}
+
+impl ReplayPlan {
+    /// Folds in the next entry read from the journal.
+    ///
+    /// `accept` is called for each entry once it is known to be part of a complete
+    /// transaction, in sequence order. Its error is returned.
+    pub(crate) fn push<E>(
+        &mut self,
+        entry: WALEntry,
+        mut accept: impl FnMut(&ReplayEntry) -> Result<Replay, E>,
+    ) -> Result<(), E> {
+        self.latest_sequence = Some(entry.sequence);
+        match entry.group {
+            WALGroup::None => self.offer(entry.into(), &mut accept)?,
+            WALGroup::Member { id } => self.open_groups.entry(id).or_default().push(entry.into()),
+            WALGroup::Commit { id, members } => {
+                let group = self.open_groups.remove(&id).unwrap_or_default();
+                if group.len() == members as usize {
+                    for member in group {
+                        self.offer(member, &mut accept)?;
+                    }
+                } else {
+                    Self::discard_group(id, &group);
+                }
+            }
+            WALGroup::Checkpointed { through } => {
+                let retired = |candidate: &ReplayEntry| {
+                    candidate.cf_name == entry.cf_name
+                        && candidate.cf_generation == entry.cf_generation
+                        && candidate.sequence <= through
+                };
+                if self.latest.get(&entry.cf_name).is_some_and(retired) {
+                    self.latest.remove(&entry.cf_name);
+                }
+                if self
+                    .stopped
+                    .get(&entry.cf_name)
+                    .is_some_and(|&(generation, sequence)| {
+                        generation == entry.cf_generation && sequence <= through
+                    })
+                {
+                    self.stopped.remove(&entry.cf_name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn offer<E>(
+        &mut self,
+        entry: ReplayEntry,
+        accept: &mut impl FnMut(&ReplayEntry) -> Result<Replay, E>,
+    ) -> Result<(), E> {
+        if self
+            .stopped
+            .get(&entry.cf_name)
+            .is_some_and(|&(generation, _)| generation == entry.cf_generation)
+        {
+            return Ok(());
+        }
+        match accept(&entry)? {
+            Replay::Apply => {
+                self.latest.insert(entry.cf_name.clone(), entry);
+            }
+            Replay::Skip => {}
+            Replay::Stop => {
+                self.stopped
+                    .insert(entry.cf_name, (entry.cf_generation, entry.sequence));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "logging"), allow(unused_variables))]
+    fn discard_group(id: u64, members: &[ReplayEntry]) {
+        #[cfg(feature = "logging")]
+        for member in members {
+            log::warn!(
+                "Discarding WAL entry {} for column family '{}': write group {id} is incomplete",
+                member.sequence,
+                member.cf_name
+            );
+        }
+    }
+
+    /// Returns the entry to recover each column family to, and the sequence of the last
+    /// entry read, if any. Write groups whose commit entry was never read are discarded.
+    pub(crate) fn finish(mut self) -> (HashMap<String, ReplayEntry>, Option<u64>) {
+        for (id, group) in self.open_groups.drain() {
+            Self::discard_group(id, &group);
+        }
+        (self.latest, self.latest_sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_family::wal::entry::entries_to_apply;
+
+    #[test]
+    fn test_replay_plan_matches_entries_to_apply() {
+        let entry = |sequence, cf_name: &str, generation, group| {
+            let mut entry = WALEntry::group_commit(0, 0);
+            entry.sequence = sequence;
+            entry.cf_name = cf_name.to_string();
+            entry.cf_generation = generation;
+            entry.transaction_id = sequence * 10;
+            entry.group = group;
+            entry
+        };
+        let marker = |sequence, cf_name: &str, generation, through| {
+            let mut marker = WALEntry::checkpointed(cf_name.to_string(), generation, through);
+            marker.sequence = sequence;
+            marker
+        };
+
+        let entries = vec![
+            entry(1, "hot", 2, WALGroup::None),
+            entry(2, "cold", 1, WALGroup::None),
+            entry(3, "hot", 2, WALGroup::Member { id: 3 }),
+            entry(4, "cold", 1, WALGroup::Member { id: 3 }),
+            entry(5, "", 0, WALGroup::Commit { id: 3, members: 2 }),
+            entry(6, "warm", 1, WALGroup::None),
+            marker(7, "hot", 2, 5),
+            // Only one of its two members was written before the commit entry
+            entry(8, "warm", 1, WALGroup::Member { id: 9 }),
+            entry(9, "", 0, WALGroup::Commit { id: 9, members: 2 }),
+            entry(10, "cold", 1, WALGroup::None),
+            marker(11, "cold", 1, 10),
+            // The commit entry was never written
+            entry(12, "warm", 1, WALGroup::Member { id: 12 }),
+        ];
+
+        let mut expected = HashMap::new();
+        for entry in entries_to_apply(entries.clone()) {
+            expected.insert(entry.cf_name.clone(), ReplayEntry::from(entry));
+        }
+
+        let mut plan = ReplayPlan::default();
+        for entry in entries {
+            plan.push(entry, |_| Ok::<_, ()>(Replay::Apply)).unwrap();
+        }
+        let (latest, latest_sequence) = plan.finish();
+
+        assert_eq!(latest, expected);
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest["warm"].sequence, 6);
+        assert_eq!(latest_sequence, Some(12));
+    }
+
+    #[test]
+    fn test_replay_plan_stops_column_family() {
+        let entry = |sequence, cf_name: &str| {
+            let mut entry = WALEntry::group_commit(0, 0);
+            entry.sequence = sequence;
+            entry.cf_name = cf_name.to_string();
+            entry.group = WALGroup::None;
+            entry
+        };
+
+        let mut plan = ReplayPlan::default();
+        for entry in [entry(1, "a"), entry(2, "b"), entry(3, "a"), entry(4, "a")] {
+            plan.push(entry, |entry| {
+                Ok::<_, ()>(match (entry.cf_name.as_str(), entry.sequence) {
+                    ("a", 3) => Replay::Stop,
+                    ("b", _) => Replay::Skip,
+                    _ => Replay::Apply,
+                })
+            })
+            .unwrap();
+        }
+        let (latest, _) = plan.finish();
+
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest["a"].sequence, 1);
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Point-in-time view of WAL and checkpoint activity.
+///
+/// Returned by [`ColumnFamilyDatabase::wal_stats()`](crate::column_family::ColumnFamilyDatabase::wal_stats).
+/// Counters are read individually, so a snapshot taken while a checkpoint is running may mix
+/// values from before and after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WALStats {
+    /// Current size of the WAL file in bytes, including its header.
+    pub wal_size: u64,
+    /// Entries appended since the WAL was last truncated.
+    pub pending_entries: u64,
+    /// Fsyncs of the WAL made to commit entries since the database was opened.
+    ///
+    /// Concurrent commits share an fsync, and commits with
+    /// [`Durability::Eventual`](crate::Durability::Eventual) do not wait for one.
+    pub wal_syncs: u64,
+    /// Pending entries broken down by column family name.
+    pub pending_entries_by_cf: HashMap<String, u64>,
+    /// Checkpoints performed that applied at least one entry.
+    pub checkpoints: u64,
+    /// Total entries applied by all checkpoints.
+    pub entries_checkpointed: u64,
+    /// Entries applied by the most recent checkpoint.
+    pub last_checkpoint_entries: u64,
+    /// Wall-clock time at which the most recent checkpoint finished.
+    ///
+    /// Always `None` on WASM, where no system clock is available.
+    pub last_checkpoint_at: Option<SystemTime>,
+    /// How long the most recent checkpoint took.
+    ///
+    /// Always `None` on WASM, where no monotonic clock is available.
+    pub last_checkpoint_duration: Option<Duration>,
+    /// Commits that found the WAL above its soft size limit and had to checkpoint or wait
+    /// before returning.
+    pub throttled_commits: u64,
+}
+
+/// What a checkpoint did.
+///
+/// Returned by [`ColumnFamilyDatabase::checkpoint()`](crate::column_family::ColumnFamilyDatabase::checkpoint)
+/// and [`ColumnFamilyDatabase::checkpoint_cf()`](crate::column_family::ColumnFamilyDatabase::checkpoint_cf).
+/// A checkpoint that found nothing to apply returns an empty report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckpointReport {
+    /// WAL entries applied, broken down by column family name.
+    pub entries_by_cf: HashMap<String, u64>,
+    /// Bytes removed from the WAL file.
+    ///
+    /// Zero for a checkpoint of a single column family while other column families still
+    /// have entries pending: its entries are retired in place, and the space they take is
+    /// reclaimed by the next checkpoint that empties the WAL.
+    pub wal_bytes_truncated: u64,
+    /// How long the checkpoint took.
+    ///
+    /// Always zero on WASM, where no monotonic clock is available.
+    pub duration: Duration,
+}
+
+impl CheckpointReport {
+    /// Returns the total number of WAL entries applied.
+    pub fn entries_applied(&self) -> u64 {
+        self.entries_by_cf.values().sum()
+    }
+}
+
+/// Counters maintained by the checkpoint manager and shared with its background thread.
+#[derive(Debug, Default)]
+pub(crate) struct CheckpointMetrics {
+    checkpoints: AtomicU64,
+    entries_checkpointed: AtomicU64,
+    last_checkpoint_entries: AtomicU64,
+    /// Microseconds since the Unix epoch, or 0 if no checkpoint has completed.
+    last_checkpoint_at_micros: AtomicU64,
+    last_checkpoint_duration_micros: AtomicU64,
+    throttled_commits: AtomicU64,
+    pending_by_cf: Mutex<HashMap<String, u64>>,
+    /// Microseconds since the Unix epoch at which every column family was last checkpointed,
+    /// or 0 if no full checkpoint has completed.
+    full_checkpoint_at_micros: AtomicU64,
+    /// Microseconds since the Unix epoch at which each column family was last checkpointed
+    /// on its own.
+    cf_checkpoint_at_micros: Mutex<HashMap<String, u64>>,
+}
+
+impl CheckpointMetrics {
+    /// Counts a WAL entry for `cf_name` as pending checkpoint.
+    pub(crate) fn record_pending(&self, cf_name: &str) {
+        let mut pending = self.pending_by_cf.lock().unwrap();
+        *pending.entry(cf_name.to_string()).or_default() += 1;
+    }
+
+    /// Resets per-column-family pending counts after the WAL has been truncated.
+    pub(crate) fn clear_pending(&self) {
+        self.pending_by_cf.lock().unwrap().clear();
+    }
+
+    /// Resets the pending count of `cf_name` after its entries have been checkpointed.
+    pub(crate) fn clear_pending_cf(&self, cf_name: &str) {
+        self.pending_by_cf.lock().unwrap().remove(cf_name);
+    }
+
+    /// Counts a commit that was throttled by WAL backpressure.
+    pub(crate) fn record_throttled(&self) {
+        self.throttled_commits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a completed checkpoint that applied `entries` entries.
+    pub(crate) fn record_checkpoint(&self, entries: u64, duration: Duration) {
+        self.checkpoints.fetch_add(1, Ordering::Relaxed);
+        self.entries_checkpointed
+            .fetch_add(entries, Ordering::Relaxed);
+        self.last_checkpoint_entries
+            .store(entries, Ordering::Relaxed);
+        self.last_checkpoint_duration_micros.store(
+            u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(since_epoch) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            self.last_checkpoint_at_micros.store(
+                u64::try_from(since_epoch.as_micros()).unwrap_or(u64::MAX),
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    /// Records that the entries of `cf_name` have been checkpointed, or those of every column
+    /// family if `cf_name` is `None`.
+    pub(crate) fn record_checkpointed(&self, cf_name: Option<&str>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(since_epoch) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            let micros = u64::try_from(since_epoch.as_micros()).unwrap_or(u64::MAX);
+            match cf_name {
+                Some(cf_name) => {
+                    self.cf_checkpoint_at_micros
+                        .lock()
+                        .unwrap()
+                        .insert(cf_name.to_string(), micros);
+                }
+                None => self
+                    .full_checkpoint_at_micros
+                    .store(micros, Ordering::Relaxed),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = cf_name;
+    }
+
+    /// Returns the number of WAL entries of `cf_name` pending checkpoint.
+    pub(crate) fn pending_of(&self, cf_name: &str) -> u64 {
+        self.pending_by_cf
+            .lock()
+            .unwrap()
+            .get(cf_name)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns when the entries of `cf_name` were last checkpointed, by a full checkpoint or
+    /// one of the column family alone, or `None` if they have not been since the database
+    /// was opened. Always `None` on WASM, where no system clock is available.
+    pub(crate) fn last_checkpoint_of(&self, cf_name: &str) -> Option<SystemTime> {
+        let full = self.full_checkpoint_at_micros.load(Ordering::Relaxed);
+        let cf = self
+            .cf_checkpoint_at_micros
+            .lock()
+            .unwrap()
+            .get(cf_name)
+            .copied()
+            .unwrap_or(0);
+        let micros = full.max(cf);
+        (micros != 0).then(|| UNIX_EPOCH + Duration::from_micros(micros))
+    }
+
+    /// Builds a [`WALStats`] snapshot from the counters.
+    pub(crate) fn snapshot(&self, wal_size: u64, pending_entries: u64, wal_syncs: u64) -> WALStats {
+        let checkpoints = self.checkpoints.load(Ordering::Relaxed);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let (last_checkpoint_at, last_checkpoint_duration) = {
+            let at_micros = self.last_checkpoint_at_micros.load(Ordering::Relaxed);
+            let duration_micros = self.last_checkpoint_duration_micros.load(Ordering::Relaxed);
+            (
+                (at_micros != 0).then(|| UNIX_EPOCH + Duration::from_micros(at_micros)),
+                (checkpoints > 0).then(|| Duration::from_micros(duration_micros)),
+            )
+        };
+        #[cfg(target_arch = "wasm32")]
+        let (last_checkpoint_at, last_checkpoint_duration) = (None, None);
+
+        WALStats {
+            wal_size,
+            pending_entries,
+            wal_syncs,
+            pending_entries_by_cf: self.pending_by_cf.lock().unwrap().clone(),
+            checkpoints,
+            entries_checkpointed: self.entries_checkpointed.load(Ordering::Relaxed),
+            last_checkpoint_entries: self.last_checkpoint_entries.load(Ordering::Relaxed),
+            last_checkpoint_at,
+            last_checkpoint_duration,
+            throttled_commits: self.throttled_commits.load(Ordering::Relaxed),
+        }
+    }
+}
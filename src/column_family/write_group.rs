@@ -0,0 +1,269 @@
+use crate::error::CommitError;
+use crate::{StorageError, WriteTransaction};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::metrics::DatabaseMetrics;
+use super::wal::checkpoint::CheckpointManager;
+use super::wal::journal::WALJournal;
+
+/// Write transactions on several column families that commit atomically.
+///
+/// Created by [`ColumnFamilyDatabase::write_group`](super::ColumnFamilyDatabase::write_group).
+/// Each member is an ordinary [`WriteTransaction`], holding the write lock of its column
+/// family until the group is committed or dropped. On commit, the WAL entries of all members
+/// are appended together with an entry that completes the group, and are made durable by a
+/// single fsync. Recovery replays a group only if its final entry was written, so after a
+/// crash either every member of the group is visible or none is.
+///
+/// Dropping a group without committing it aborts every member.
+pub struct WriteGroup {
+    // Sorted by column family name, the order in which the write locks were taken
+    members: Vec<(String, WriteTransaction)>,
+    journal: Arc<WALJournal>,
+    checkpoint_manager: Option<Arc<CheckpointManager>>,
+    metrics: Option<Arc<dyn DatabaseMetrics>>,
+    // Index of a member whose prepare is made to fail
+    #[cfg(test)]
+    fail_prepare_at: Option<usize>,
+}
+
+impl WriteGroup {
+    pub(crate) fn new(
+        members: Vec<(String, WriteTransaction)>,
+        journal: Arc<WALJournal>,
+        checkpoint_manager: Option<Arc<CheckpointManager>>,
+        metrics: Option<Arc<dyn DatabaseMetrics>>,
+    ) -> Self {
+        Self {
+            members,
+            journal,
+            checkpoint_manager,
+            metrics,
+            #[cfg(test)]
+            fail_prepare_at: None,
+        }
+    }
+
+    /// Returns the write transaction of the column family named `cf_name`, or `None` if it
+    /// is not a member of this group.
+    pub fn transaction(&self, cf_name: &str) -> Option<&WriteTransaction> {
+        self.members
+            .iter()
+            .find(|(name, _)| name == cf_name)
+            .map(|(_, txn)| txn)
+    }
+
+    /// Returns the names of the column families in this group, in the order their write
+    /// locks were taken.
+    pub fn column_families(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Commits every member of the group.
+    ///
+    /// Returns once the whole group is durable in the WAL and visible to new transactions.
+    /// The durability level set on the individual transactions is ignored: the group always
+    /// waits for its fsync.
+    ///
+    /// # Errors
+    ///
+    /// If a member cannot be prepared, or the group's entries cannot be written to the WAL,
+    /// such as when the disk is full, every member is rolled back and the error is returned,
+    /// as with [`WriteTransaction::commit`]. If the entries were written but could not be
+    /// synced, every member is still made visible before the error is returned.
+    ///
+    /// Once the entries are written, the group is committed even if a member cannot be made
+    /// visible in memory: the other members still are, and the group is then applied from the
+    /// WAL by a checkpoint. The error is only returned if that checkpoint fails too, in which
+    /// case the group is applied by recovery the next time the database is opened.
+    pub fn commit(mut self) -> Result<(), CommitError> {
+        let started = self.metrics.as_ref().map(|_| Instant::now());
+
+        let mut prepared = Vec::with_capacity(self.members.len());
+        let mut entries = Vec::with_capacity(self.members.len());
+        let mut failed = None;
+        for (index, (_, txn)) in self.members.iter_mut().enumerate() {
+            let result = txn.prepare_group_commit();
+            #[cfg(test)]
+            let result = result.and_then(|prepared| match self.fail_prepare_at {
+                Some(at) if at == index => Err(CommitError::Storage(StorageError::from(
+                    std::io::Error::other("injected prepare failure"),
+                ))),
+                _ => Ok(prepared),
+            });
+            match result {
+                Ok((commit, entry)) => {
+                    prepared.push(commit);
+                    entries.push(entry);
+                }
+                Err(err) => {
+                    failed = Some((index, err));
+                    break;
+                }
+            }
+        }
+        if let Some((index, err)) = failed {
+            // The failed member may have been partly prepared, so it is rolled back along with
+            // the members before it
+            self.rollback(index + 1)?;
+            return Err(err);
+        }
+
+        // Keep checkpoints from truncating the group until every member is visible in memory
+        let journal = Arc::clone(&self.journal);
+        let commit_guard = journal.commit_guard();
+
+        let wal_started = started.map(|_| Instant::now());
+        let sequence = match journal.append_group(&mut entries) {
+            Ok(sequence) => sequence,
+            Err(err) => {
+                let len = self.members.len();
+                self.rollback(len)?;
+                return Err(CommitError::Storage(StorageError::from(err)));
+            }
+        };
+        // The entries are in the WAL even if the sync fails, so the members are made visible
+        // either way, and become durable with the next successful sync
+        let synced = self.journal.wait_for_sync(sequence);
+        if synced.is_err() {
+            for (_, txn) in &self.members {
+                txn.register_unsynced_group_commit(sequence);
+            }
+        }
+        let wal_wait = wal_started.map_or(Duration::ZERO, |wal_started| wal_started.elapsed());
+
+        if let Some(checkpoint_mgr) = &self.checkpoint_manager {
+            for entry in &entries {
+                checkpoint_mgr.register_pending(entry.sequence, &entry.cf_name);
+            }
+        }
+
+        // The group is committed once its entries are in the WAL, so every member is made
+        // visible even if an earlier one fails
+        let mut finished = Ok(());
+        for ((_, txn), commit) in self.members.iter_mut().zip(prepared) {
+            let result = txn.finish_group_commit(commit);
+            if finished.is_ok() {
+                finished = result;
+            }
+        }
+        drop(commit_guard);
+        if let Err(err) = finished {
+            // Checkpointing applies the group's entries from the WAL, including those of the
+            // members that could not be made visible. The commit guard must be released first.
+            match &self.checkpoint_manager {
+                Some(checkpoint_mgr) if checkpoint_mgr.checkpoint_now().is_ok() => {}
+                _ => return Err(err),
+            }
+        }
+        synced.map_err(|e| CommitError::Storage(StorageError::from(e)))?;
+
+        // Throttle the writer if the WAL is growing faster than it is checkpointed
+        if let Some(checkpoint_mgr) = &self.checkpoint_manager {
+            checkpoint_mgr.apply_backpressure();
+        }
+
+        if let (Some(metrics), Some(started)) = (&self.metrics, started) {
+            let total = started.elapsed();
+            for (name, _) in &self.members {
+                metrics.on_commit(name, wal_wait, total);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rolls back the first `count` members, which have been prepared, returning the first
+    /// error. The remaining members are still rolled back.
+    fn rollback(&mut self, count: usize) -> Result<(), CommitError> {
+        let mut result = Ok(());
+        for (_, txn) in &mut self.members[..count] {
+            let rolled_back = txn.rollback_group_commit();
+            if result.is_ok() {
+                result = rolled_back;
+            }
+        }
+        result.map_err(CommitError::Storage)
+    }
+
+    /// Aborts every member of the group.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while aborting the members. The remaining members
+    /// are still aborted.
+    pub fn abort(self) -> crate::Result {
+        let mut result = Ok(());
+        for (_, txn) in self.members {
+            let aborted = txn.abort();
+            if result.is_ok() {
+                result = aborted;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TableDefinition;
+    use crate::column_family::ColumnFamilyDatabase;
+    use tempfile::TempDir;
+
+    const TABLE: TableDefinition<u64, u64> = TableDefinition::new("values");
+
+    #[test]
+    fn test_failed_prepare_rolls_back_prepared_members() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.db");
+
+        {
+            let db = ColumnFamilyDatabase::builder()
+                .pool_size(64)
+                .open(&path)
+                .unwrap();
+            let cfs = ["a", "b", "c"].map(|name| db.create_column_family(name, None).unwrap());
+            for cf in &cfs {
+                let txn = cf.begin_write().unwrap();
+                txn.open_table(TABLE).unwrap().insert(0, 1).unwrap();
+                txn.commit().unwrap();
+            }
+
+            // The first member is prepared when the second one fails
+            let mut group = db.write_group(&[&cfs[0], &cfs[1], &cfs[2]]).unwrap();
+            for name in ["a", "b", "c"] {
+                let txn = group.transaction(name).unwrap();
+                let mut table = txn.open_table(TABLE).unwrap();
+                table.insert(0, 2).unwrap();
+                table.insert(1, 2).unwrap();
+            }
+            group.fail_prepare_at = Some(1);
+            assert!(group.commit().is_err());
+
+            // Every column family is back at its last commit, and accepts new commits
+            for cf in &cfs {
+                let txn = cf.begin_read().unwrap();
+                let table = txn.open_table(TABLE).unwrap();
+                assert_eq!(table.get(0).unwrap().unwrap().value(), 1);
+                assert!(table.get(1).unwrap().is_none());
+                drop(table);
+                drop(txn);
+
+                let txn = cf.begin_write().unwrap();
+                txn.open_table(TABLE).unwrap().insert(2, 3).unwrap();
+                txn.commit().unwrap();
+            }
+        }
+
+        let db = ColumnFamilyDatabase::open(&path).unwrap();
+        for name in ["a", "b", "c"] {
+            let cf = db.column_family(name).unwrap();
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(TABLE).unwrap();
+            assert_eq!(table.get(0).unwrap().unwrap().value(), 1);
+            assert!(table.get(1).unwrap().is_none());
+            assert_eq!(table.get(2).unwrap().unwrap().value(), 3);
+        }
+    }
+}
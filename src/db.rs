@@ -1,13 +1,13 @@
 use crate::transaction_tracker::{TransactionId, TransactionTracker};
 use crate::tree_store::{
-    BtreeHeader, InternalTableDefinition, PageHint, PageNumber, ReadOnlyBackend, ShrinkPolicy,
-    TableTree, TableType, TransactionalMemory, PAGE_SIZE,
+    BtreeHeader, InternalTableDefinition, PAGE_SIZE, PageHint, PageNumber, ReadOnlyBackend,
+    ShrinkPolicy, TableTree, TableType, TransactionalMemory,
 };
 use crate::types::{Key, Value};
 use crate::{
     CompactionError, DatabaseError, Error, ReadOnlyTable, SavepointError, StorageError, TableError,
 };
-use crate::{ReadTransaction, Result, WriteTransaction};
+use crate::{ReadTransaction, Result, Snapshot, WriteTransaction};
 use std::fmt::{Debug, Display, Formatter};
 
 use std::fs::{File, OpenOptions};
@@ -19,9 +19,9 @@ use std::{io, thread};
 use crate::error::TransactionError;
 use crate::sealed::Sealed;
 use crate::transactions::{
-    AllocatorStateKey, AllocatorStateTree, PageList, SystemTableDefinition,
-    TransactionIdWithPagination, ALLOCATOR_STATE_TABLE_NAME, DATA_ALLOCATED_TABLE,
-    DATA_FREED_TABLE, SYSTEM_FREED_TABLE,
+    ALLOCATOR_STATE_TABLE_NAME, AllocatorStateKey, AllocatorStateTree, DATA_ALLOCATED_TABLE,
+    DATA_FREED_TABLE, PageList, SYSTEM_FREED_TABLE, SystemTableDefinition,
+    TransactionIdWithPagination,
 };
 use crate::tree_store::file_backend::FileBackend;
 #[cfg(feature = "logging")]
@@ -49,6 +49,15 @@ pub trait StorageBackend: 'static + Debug + Send + Sync {
     /// Writes the specified array to the storage.
     fn write(&self, offset: u64, data: &[u8]) -> std::result::Result<(), io::Error>;
 
+    /// Hints that the specified range of the storage will be read soon.
+    ///
+    /// Backends may start loading the range in the background. This is advisory only: the
+    /// default implementation does nothing, and errors are ignored by the caller.
+    fn prefetch(&self, offset: u64, len: u64) -> std::result::Result<(), io::Error> {
+        let _ = (offset, len);
+        Ok(())
+    }
+
     /// Release any resources held by the backend
     ///
     /// Note: redb will not access the backend after calling this method and will call it exactly
@@ -304,6 +313,12 @@ impl TransactionGuard {
         self.transaction_id.unwrap()
     }
 
+    pub(crate) fn is_tracked_by(&self, tracker: &Arc<TransactionTracker>) -> bool {
+        self.transaction_tracker
+            .as_ref()
+            .is_some_and(|x| Arc::ptr_eq(x, tracker))
+    }
+
     pub(crate) fn leak(mut self) -> TransactionId {
         self.transaction_id.take().unwrap()
     }
@@ -1030,6 +1045,33 @@ impl Database {
         ))
     }
 
+    /// Pins the most recently committed state of the database
+    ///
+    /// The returned [`Snapshot`] keeps that state readable until it is dropped, so that
+    /// [`Database::begin_read_at`] can open any number of read transactions that all see the
+    /// same data, regardless of commits made in between.
+    pub fn snapshot(&self) -> Result<Snapshot, TransactionError> {
+        let guard = self.allocate_read_transaction()?;
+        Ok(Snapshot::new(guard, self.mem.get_data_root()))
+    }
+
+    /// Begins a read transaction that sees the state pinned by `snapshot`
+    ///
+    /// Returns [`TransactionError::SnapshotReclaimed`] if the snapshot was taken from another
+    /// [`Database`] instance, whose state is not retained by this one.
+    pub fn begin_read_at(&self, snapshot: &Snapshot) -> Result<ReadTransaction, TransactionError> {
+        if !snapshot.guard().is_tracked_by(&self.transaction_tracker) {
+            return Err(TransactionError::SnapshotReclaimed(snapshot.id()));
+        }
+        let id = snapshot.guard().id();
+        self.transaction_tracker.register_read_transaction_at(id);
+        #[cfg(feature = "logging")]
+        debug!("Beginning read transaction id={id:?} at snapshot");
+
+        let guard = TransactionGuard::new_read(id, self.transaction_tracker.clone());
+        ReadTransaction::new_at(self.mem.clone(), guard, snapshot.root())
+    }
+
     /// Convenience method for [`Builder::new`]
     pub fn builder() -> Builder {
         Builder::new()
@@ -1251,6 +1293,24 @@ impl Builder {
         )
     }
 
+    /// Opens an existing redb database stored in `backend`, without writing to it.
+    ///
+    /// The database must have been shut down cleanly; otherwise
+    /// [`DatabaseError::RepairAborted`] is returned.
+    pub fn open_read_only_with_backend(
+        &self,
+        backend: impl StorageBackend,
+    ) -> Result<ReadOnlyDatabase, DatabaseError> {
+        self.open_read_only_with_boxed_backend(Box::new(backend))
+    }
+
+    pub(crate) fn open_read_only_with_boxed_backend(
+        &self,
+        backend: Box<dyn StorageBackend>,
+    ) -> Result<ReadOnlyDatabase, DatabaseError> {
+        ReadOnlyDatabase::new(backend, self.page_size, None, self.read_cache_size_bytes)
+    }
+
     /// Open an existing or create a new database in the given `file`.
     ///
     /// The file must be empty or contain a valid database.
@@ -1298,8 +1358,8 @@ mod test {
     };
     use std::fs::File;
     use std::io::{ErrorKind, Read, Seek, SeekFrom};
-    use std::sync::atomic::{AtomicU64, Ordering};
     use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
 
     #[derive(Debug)]
     struct FailingBackend {
@@ -1327,11 +1387,7 @@ mod test {
             if self
                 .countdown
                 .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| {
-                    if x > 0 {
-                        Some(x - 1)
-                    } else {
-                        None
-                    }
+                    if x > 0 { Some(x - 1) } else { None }
                 })
                 .is_err()
             {
@@ -1,6 +1,7 @@
 use crate::tree_store::{FILE_FORMAT_VERSION3, MAX_VALUE_LENGTH};
 use crate::{ReadTransaction, TypeName};
 use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
 use std::sync::PoisonError;
 use std::{io, panic};
 
@@ -16,6 +17,14 @@ pub enum StorageError {
     PreviousIo,
     DatabaseClosed,
     LockPoisoned(&'static panic::Location<'static>),
+    /// A column family's file handle could not be opened because the process is out of file
+    /// descriptors. `pool_size` is the file handle pool's maximum size and `column_families`
+    /// the number of column families in the database.
+    FileHandlesExhausted {
+        pool_size: usize,
+        column_families: usize,
+        source: io::Error,
+    },
 }
 
 impl<T> From<PoisonError<T>> for StorageError {
@@ -39,6 +48,15 @@ impl From<StorageError> for Error {
             StorageError::PreviousIo => Error::PreviousIo,
             StorageError::DatabaseClosed => Error::DatabaseClosed,
             StorageError::LockPoisoned(location) => Error::LockPoisoned(location),
+            StorageError::FileHandlesExhausted {
+                pool_size,
+                column_families,
+                source,
+            } => Error::FileHandlesExhausted {
+                pool_size,
+                column_families,
+                source,
+            },
         }
     }
 }
@@ -71,6 +89,11 @@ impl Display for StorageError {
             StorageError::LockPoisoned(location) => {
                 write!(f, "Poisoned internal lock: {location}")
             }
+            StorageError::FileHandlesExhausted {
+                pool_size,
+                column_families,
+                source,
+            } => fmt_file_handles_exhausted(f, *pool_size, *column_families, source),
         }
     }
 }
@@ -207,6 +230,12 @@ impl std::error::Error for TableError {}
 pub enum DatabaseError {
     /// The Database is already open. Cannot acquire lock.
     DatabaseAlreadyOpen,
+    /// The database file at `path` is locked by another handle and the lock could not be
+    /// acquired. `holder_hint` describes the lock holder (e.g. its PID) when it can be determined.
+    FileLocked {
+        path: PathBuf,
+        holder_hint: Option<String>,
+    },
     /// [`crate::RepairSession::abort`] was called or repair was aborted for another reason (such as the database being read-only).
     RepairAborted,
     /// The database file is in an old file format and must be manually upgraded
@@ -219,6 +248,9 @@ impl From<DatabaseError> for Error {
     fn from(err: DatabaseError) -> Error {
         match err {
             DatabaseError::DatabaseAlreadyOpen => Error::DatabaseAlreadyOpen,
+            DatabaseError::FileLocked { path, holder_hint } => {
+                Error::FileLocked { path, holder_hint }
+            }
             DatabaseError::RepairAborted => Error::RepairAborted,
             DatabaseError::UpgradeRequired(x) => Error::UpgradeRequired(x),
             DatabaseError::Storage(storage) => storage.into(),
@@ -253,6 +285,9 @@ impl Display for DatabaseError {
             DatabaseError::DatabaseAlreadyOpen => {
                 write!(f, "Database already open. Cannot acquire lock.")
             }
+            DatabaseError::FileLocked { path, holder_hint } => {
+                fmt_file_locked(f, path, holder_hint.as_deref())
+            }
             DatabaseError::Storage(storage) => storage.fmt(f),
         }
     }
@@ -260,6 +295,35 @@ impl Display for DatabaseError {
 
 impl std::error::Error for DatabaseError {}
 
+fn fmt_file_locked(
+    f: &mut Formatter<'_>,
+    path: &std::path::Path,
+    holder_hint: Option<&str>,
+) -> std::fmt::Result {
+    write!(
+        f,
+        "Database file '{}' is locked by another handle. Cannot acquire lock.",
+        path.display()
+    )?;
+    if let Some(hint) = holder_hint {
+        write!(f, " Lock holder: {hint}.")?;
+    }
+    Ok(())
+}
+
+fn fmt_file_handles_exhausted(
+    f: &mut Formatter<'_>,
+    pool_size: usize,
+    column_families: usize,
+    source: &io::Error,
+) -> std::fmt::Result {
+    write!(
+        f,
+        "Out of file handles opening column family storage ({column_families} column families, \
+         pool size {pool_size}): {source}"
+    )
+}
+
 /// Errors related to savepoints
 #[derive(Debug)]
 #[non_exhaustive]
@@ -399,6 +463,8 @@ pub enum TransactionError {
     Storage(StorageError),
     /// The transaction is still referenced by a table or other object
     ReadTransactionStillInUse(Box<ReadTransaction>),
+    /// The snapshot with this transaction id is no longer retained by the database
+    SnapshotReclaimed(u64),
 }
 
 impl TransactionError {
@@ -417,6 +483,7 @@ impl From<TransactionError> for Error {
             TransactionError::ReadTransactionStillInUse(txn) => {
                 Error::ReadTransactionStillInUse(txn)
             }
+            TransactionError::SnapshotReclaimed(id) => Error::SnapshotReclaimed(id),
         }
     }
 }
@@ -434,6 +501,9 @@ impl Display for TransactionError {
             TransactionError::ReadTransactionStillInUse(_) => {
                 write!(f, "Transaction still in use")
             }
+            TransactionError::SnapshotReclaimed(id) => {
+                write!(f, "Snapshot {id} is no longer retained")
+            }
         }
     }
 }
@@ -486,6 +556,11 @@ impl std::error::Error for CommitError {}
 pub enum Error {
     /// The Database is already open. Cannot acquire lock.
     DatabaseAlreadyOpen,
+    /// The database file at `path` is locked by another handle.
+    FileLocked {
+        path: PathBuf,
+        holder_hint: Option<String>,
+    },
     /// This savepoint is invalid or cannot be created.
     ///
     /// Savepoints become invalid when an older savepoint is restored after it was created,
@@ -536,6 +611,15 @@ pub enum Error {
     LockPoisoned(&'static panic::Location<'static>),
     /// The transaction is still referenced by a table or other object
     ReadTransactionStillInUse(Box<ReadTransaction>),
+    /// The snapshot with this transaction id is no longer retained by the database
+    SnapshotReclaimed(u64),
+    /// A column family's file handle could not be opened because the process is out of file
+    /// descriptors.
+    FileHandlesExhausted {
+        pool_size: usize,
+        column_families: usize,
+        source: io::Error,
+    },
 }
 
 impl<T> From<PoisonError<T>> for Error {
@@ -623,6 +707,9 @@ impl Display for Error {
             Error::DatabaseAlreadyOpen => {
                 write!(f, "Database already open. Cannot acquire lock.")
             }
+            Error::FileLocked { path, holder_hint } => {
+                fmt_file_locked(f, path, holder_hint.as_deref())
+            }
             Error::RepairAborted => {
                 write!(f, "Database repair aborted.")
             }
@@ -656,6 +743,14 @@ impl Display for Error {
             Error::ReadTransactionStillInUse(_) => {
                 write!(f, "Transaction still in use")
             }
+            Error::SnapshotReclaimed(id) => {
+                write!(f, "Snapshot {id} is no longer retained")
+            }
+            Error::FileHandlesExhausted {
+                pool_size,
+                column_families,
+                source,
+            } => fmt_file_handles_exhausted(f, *pool_size, *column_families, source),
         }
     }
 }
@@ -77,10 +77,13 @@ pub use multimap_table::{
     ReadOnlyUntypedMultimapTable, ReadableMultimapTable,
 };
 pub use table::{
-    ExtractIf, Range, ReadOnlyTable, ReadOnlyUntypedTable, ReadableTable, ReadableTableMetadata,
-    Table, TableStats,
+    ExtractIf, Range, ReadHint, ReadOnlyTable, ReadOnlyUntypedTable, ReadableTable,
+    ReadableTableMetadata, Table, TableStats,
+};
+pub use transactions::{
+    DatabaseStats, Durability, OpOutcome, ReadTransaction, Snapshot, TransactionSavepoint,
+    WriteTransaction,
 };
-pub use transactions::{DatabaseStats, Durability, ReadTransaction, WriteTransaction};
 pub use tree_store::{AccessGuard, AccessGuardMut, AccessGuardMutInPlace, Savepoint};
 pub use types::{Key, MutInPlaceValue, TypeName, Value};
 
@@ -103,6 +106,8 @@ mod types;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+mod wasm_tables;
 
 #[cfg(test)]
 fn create_tempfile() -> tempfile::NamedTempFile {
@@ -887,6 +887,33 @@ impl<
     }
 }
 
+/// Describes how a [`Range`] will be read, so that storage can be prefetched ahead of it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadHint {
+    /// Pages are read when the scan reaches them
+    #[default]
+    Normal,
+    /// The range is read in order, so the pages ahead of the scan are prefetched, up to
+    /// [`ReadHint::DEFAULT_WINDOW`] bytes ahead of it
+    Sequential,
+    /// Like [`ReadHint::Sequential`], prefetching at most the given number of bytes ahead of the
+    /// scan
+    SequentialWindow(u64),
+}
+
+impl ReadHint {
+    /// Number of bytes prefetched ahead of a [`ReadHint::Sequential`] scan
+    pub const DEFAULT_WINDOW: u64 = 4 * 1024 * 1024;
+
+    fn window(self) -> Option<u64> {
+        match self {
+            Self::Normal => None,
+            Self::Sequential => Some(Self::DEFAULT_WINDOW),
+            Self::SequentialWindow(window) => Some(window),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Range<'a, K: Key + 'static, V: Value + 'static> {
     inner: BtreeRangeIter<K, V>,
@@ -903,6 +930,20 @@ impl<K: Key + 'static, V: Value + 'static> Range<'_, K, V> {
             _lifetime: Default::default(),
         }
     }
+
+    /// Hints how the range will be read.
+    ///
+    /// With [`ReadHint::Sequential`], the pages the scan will reach next are prefetched from the
+    /// file as it advances, from whichever end is iterated. This speeds up large scans on a cold
+    /// cache, and does not change the entries returned. Prefetching is a no-op on platforms and
+    /// backends that do not support it, such as in-memory databases.
+    #[must_use]
+    pub fn with_hint(mut self, hint: ReadHint) -> Self {
+        if let Some(window) = hint.window() {
+            self.inner.set_readahead(window);
+        }
+        self
+    }
 }
 
 impl<'a, K: Key + 'static, V: Value + 'static> Iterator for Range<'a, K, V> {
@@ -89,6 +89,10 @@ struct State {
     pending_non_durable_commits: HashMap<TransactionId, TransactionId>,
     // Non-durable commits which have NOT been processed in the freed table
     unprocessed_freed_non_durable_commits: BTreeSet<TransactionId>,
+    // Commits whose WAL entry may not have been synced yet, mapped to the entry's sequence number.
+    // Recovery can fall back to the last synced entry, so pages freed by these commits must not
+    // be reused until they are synced
+    unsynced_commits: BTreeMap<TransactionId, u64>,
 }
 
 pub(crate) struct TransactionTracker {
@@ -107,6 +111,7 @@ impl TransactionTracker {
                 valid_savepoints: Default::default(),
                 pending_non_durable_commits: Default::default(),
                 unprocessed_freed_non_durable_commits: Default::default(),
+                unsynced_commits: Default::default(),
             }),
             live_write_transaction_available: Condvar::new(),
         }
@@ -158,6 +163,11 @@ impl TransactionTracker {
         state.unprocessed_freed_non_durable_commits.remove(&id);
     }
 
+    pub(crate) fn restore_unprocessed_non_durable_commit(&self, id: TransactionId) {
+        let mut state = self.state.lock().unwrap();
+        state.unprocessed_freed_non_durable_commits.insert(id);
+    }
+
     pub(crate) fn oldest_unprocessed_non_durable_commit(&self) -> Option<TransactionId> {
         let state = self.state.lock().unwrap();
         state
@@ -184,6 +194,20 @@ impl TransactionTracker {
         state.unprocessed_freed_non_durable_commits.insert(id);
     }
 
+    pub(crate) fn register_unsynced_commit(&self, id: TransactionId, wal_sequence: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.unsynced_commits.insert(id, wal_sequence);
+    }
+
+    // Returns the oldest commit whose WAL entry is newer than `synced_sequence`
+    pub(crate) fn oldest_unsynced_commit(&self, synced_sequence: u64) -> Option<TransactionId> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .unsynced_commits
+            .retain(|_, sequence| *sequence > synced_sequence);
+        state.unsynced_commits.keys().next().copied()
+    }
+
     pub(crate) fn restore_savepoint_counter_state(&self, next_savepoint: SavepointId) {
         let mut state = self.state.lock().unwrap();
         assert!(state.valid_savepoints.is_empty());
@@ -217,6 +241,13 @@ impl TransactionTracker {
         Ok(id)
     }
 
+    // Registers another read transaction on a transaction id that already has a live reader
+    pub(crate) fn register_read_transaction_at(&self, id: TransactionId) {
+        let mut state = self.state.lock().unwrap();
+        let ref_count = state.live_read_transactions.get_mut(&id).unwrap();
+        *ref_count += 1;
+    }
+
     pub(crate) fn deallocate_read_transaction(&self, id: TransactionId) {
         let mut state = self.state.lock().unwrap();
         let ref_count = state.live_read_transactions.get_mut(&id).unwrap();
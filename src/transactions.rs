@@ -6,8 +6,8 @@ use crate::table::ReadOnlyUntypedTable;
 use crate::transaction_tracker::{SavepointId, TransactionId, TransactionTracker};
 use crate::tree_store::{
     Btree, BtreeHeader, BtreeMut, InternalTableDefinition, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, Page,
-    PageHint, PageListMut, PageNumber, PageTrackerPolicy, SerializedSavepoint, ShrinkPolicy,
-    TableTree, TableTreeMut, TableType, TransactionalMemory,
+    PageHint, PageListMut, PageNumber, PageTrackerPolicy, RawBtree, SerializedSavepoint,
+    ShrinkPolicy, TableTree, TableTreeMut, TableType, TransactionalMemory, UntypedBtreeBuilder,
 };
 use crate::types::{Key, Value};
 use crate::{
@@ -23,11 +23,13 @@ use std::borrow::Borrow;
 use std::cmp::min;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
+use std::io;
 use std::marker::PhantomData;
 use std::mem::size_of;
 use std::ops::RangeBounds;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{panic, thread};
 
 const MAX_PAGES_PER_COMPACTION: usize = 1_000_000;
@@ -35,6 +37,9 @@ const NEXT_SAVEPOINT_TABLE: SystemTableDefinition<(), SavepointId> =
     SystemTableDefinition::new("next_savepoint_id");
 pub(crate) const SAVEPOINT_TABLE: SystemTableDefinition<SavepointId, SerializedSavepoint> =
     SystemTableDefinition::new("persistent_savepoints");
+// Operation ids applied through WriteTransaction::idempotent(), mapped to the time they were
+// recorded in milliseconds since the Unix epoch
+const OP_ID_TABLE: SystemTableDefinition<u128, u64> = SystemTableDefinition::new("applied_op_ids");
 // Pages that were allocated in the data tree by a given transaction. Only updated when a savepoint
 // exists
 pub(crate) const DATA_ALLOCATED_TABLE: SystemTableDefinition<
@@ -301,6 +306,15 @@ impl<K: Key + 'static, V: Value + 'static> Display for SystemTableDefinition<'_,
     }
 }
 
+/// Outcome of [`WriteTransaction::idempotent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpOutcome<T> {
+    /// The operation had not been applied before, and the closure returned this value
+    Applied(T),
+    /// The operation id was already recorded, so the closure was not run
+    Replayed,
+}
+
 /// Informational storage stats about the database
 #[derive(Debug)]
 pub struct DatabaseStats {
@@ -363,8 +377,19 @@ pub enum Durability {
     /// Commits with this durability level will not be persisted to disk unless followed by a
     /// commit with [`Durability::Immediate`].
     None,
+    /// Commits with this durability level are appended to the column family WAL, but
+    /// [`WriteTransaction::commit`] returns without waiting for the WAL to be synced to disk.
+    ///
+    /// The commit becomes durable when the next [`Durability::Immediate`] commit syncs the WAL,
+    /// when the WAL is checkpointed, or when the database is closed. A crash before then may
+    /// lose the most recent eventual commits, but recovery always restores the state of some
+    /// earlier commit, never a partial one. Without a WAL this is the same as
+    /// [`Durability::Immediate`].
+    Eventual,
     /// Commits with this durability level are guaranteed to be persistent as soon as
     /// [`WriteTransaction::commit`] returns.
+    ///
+    /// With a WAL, this also makes every earlier [`Durability::Eventual`] commit durable.
     Immediate,
 }
 
@@ -373,6 +398,7 @@ pub enum Durability {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum InternalDurability {
     None,
+    Eventual,
     Immediate,
 }
 
@@ -595,9 +621,12 @@ impl TableNamespace<'_> {
 
     fn set_dirty(&mut self, transaction: &WriteTransaction) {
         transaction.dirty.store(true, Ordering::Release);
-        if !transaction.transaction_tracker.any_savepoint_exists() {
+        if !transaction.transaction_tracker.any_savepoint_exists()
+            && transaction.transaction_savepoints.is_empty()
+        {
             // No savepoints exist, and we don't allow savepoints to be created in a dirty transaction
-            // so we can disable allocation tracking now
+            // so we can disable allocation tracking now. Savepoints within the transaction start
+            // tracking again
             *self.allocated_pages.lock().unwrap() = PageTrackerPolicy::Ignore;
         }
     }
@@ -754,6 +783,35 @@ impl TableNamespace<'_> {
     }
 }
 
+/// A point within a [`WriteTransaction`] that it can be rolled back to, created by
+/// [`WriteTransaction::savepoint`]
+///
+/// Unlike a [`Savepoint`], it only lives as long as the transaction that created it.
+pub struct TransactionSavepoint {
+    transaction_tracker: Arc<TransactionTracker>,
+    transaction_id: TransactionId,
+    id: u64,
+}
+
+impl Debug for TransactionSavepoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransactionSavepoint")
+            .field("transaction_id", &self.transaction_id)
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+// State of the data tree when a TransactionSavepoint was created
+struct SavepointState {
+    id: u64,
+    user_root: Option<BtreeHeader>,
+    // Pages allocated by the transaction before the savepoint, which the savepoint may reference
+    frozen: HashSet<PageNumber>,
+    // Length of the data freed pages, whose later entries may be referenced by the savepoint
+    data_freed_len: usize,
+}
+
 /// A read/write transaction
 ///
 /// Only a single [`WriteTransaction`] may exist at a time
@@ -773,10 +831,48 @@ pub struct WriteTransaction {
     // Persistent savepoints created during this transaction
     created_persistent_savepoints: Mutex<HashSet<SavepointId>>,
     deleted_persistent_savepoints: Mutex<Vec<(SavepointId, TransactionId)>>,
+    // Savepoints created within this transaction, oldest first
+    transaction_savepoints: Vec<SavepointState>,
+    next_transaction_savepoint: u64,
+    // Pages of earlier non-durable commits freed while preparing to commit, and those commits,
+    // kept so that a commit which fails to reach the WAL can be rolled back
+    freed_unpersisted: Vec<PageNumber>,
+    processed_non_durable: Vec<TransactionId>,
     // WAL integration for column families
     wal_journal: Option<Arc<crate::column_family::wal::journal::WALJournal>>,
     cf_name: Option<String>,
+    cf_generation: u64,
     checkpoint_manager: Option<Arc<crate::column_family::wal::checkpoint::CheckpointManager>>,
+    metrics: Option<Arc<dyn crate::column_family::DatabaseMetrics>>,
+    // Lasts from `begin_write` until the transaction is dropped
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+/// A logged commit that is visible but may not be durable yet, returned by
+/// [`WriteTransaction::commit_unsynced`].
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub(crate) struct UnsyncedCommit {
+    /// The journal and the sequence number to wait for, unless the commit had
+    /// `Durability::Eventual`
+    pub(crate) wal: Option<(Arc<crate::column_family::wal::journal::WALJournal>, u64)>,
+    pub(crate) checkpoint_manager:
+        Option<Arc<crate::column_family::wal::checkpoint::CheckpointManager>>,
+    pub(crate) metrics: Option<(Arc<dyn crate::column_family::DatabaseMetrics>, String)>,
+    /// When the commit started, if someone is listening
+    pub(crate) started: Option<Instant>,
+    /// Time spent appending to the WAL so far
+    pub(crate) wal_wait: Duration,
+}
+
+/// A write transaction whose tables have been flushed and whose roots are ready to commit.
+pub(crate) struct PreparedCommit {
+    user_root: Option<BtreeHeader>,
+    system_root: Option<BtreeHeader>,
+    post_commit_frees: Option<Vec<PageNumber>>,
+    // Logged to the WAL, and taken when the entry is built
+    data_freed: Vec<PageNumber>,
+    allocated_pages: Vec<PageNumber>,
 }
 
 impl WriteTransaction {
@@ -809,9 +905,17 @@ impl WriteTransaction {
             shrink_policy: ShrinkPolicy::Default,
             created_persistent_savepoints: Mutex::new(Default::default()),
             deleted_persistent_savepoints: Mutex::new(vec![]),
+            transaction_savepoints: vec![],
+            next_transaction_savepoint: 0,
+            freed_unpersisted: vec![],
+            processed_non_durable: vec![],
             wal_journal: None,
             cf_name: None,
+            cf_generation: 0,
             checkpoint_manager: None,
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::none(),
         })
     }
 
@@ -823,14 +927,32 @@ impl WriteTransaction {
     pub(crate) fn set_wal_context(
         &mut self,
         cf_name: String,
+        cf_generation: u64,
         wal_journal: Arc<crate::column_family::wal::journal::WALJournal>,
         checkpoint_manager: Option<Arc<crate::column_family::wal::checkpoint::CheckpointManager>>,
     ) {
         self.cf_name = Some(cf_name);
+        self.cf_generation = cf_generation;
         self.wal_journal = Some(wal_journal);
         self.checkpoint_manager = checkpoint_manager;
     }
 
+    /// Sets the hooks that this transaction reports its commit to, as column family `cf_name`.
+    pub(crate) fn set_metrics(
+        &mut self,
+        cf_name: String,
+        metrics: Arc<dyn crate::column_family::DatabaseMetrics>,
+    ) {
+        self.cf_name = Some(cf_name);
+        self.metrics = Some(metrics);
+    }
+
+    /// Sets the span that the commit of this transaction is traced in.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn set_span(&mut self, span: tracing::Span) {
+        self.span = span;
+    }
+
     /// Disable WAL for this specific transaction.
     ///
     /// This is useful for bulk load operations where WAL overhead provides no benefit.
@@ -838,6 +960,9 @@ impl WriteTransaction {
     ///
     /// **Important**: Only use this for large bulk operations. For normal operations,
     /// WAL provides better performance through group commit batching.
+    ///
+    /// Prefer [`ColumnFamily::begin_write_unlogged`](crate::column_family::ColumnFamily::begin_write_unlogged),
+    /// which also checkpoints pending WAL entries so they cannot be replayed over this commit.
     pub fn disable_wal(&mut self) {
         self.wal_journal = None;
         self.checkpoint_manager = None;
@@ -1070,6 +1195,60 @@ impl WriteTransaction {
         Ok(savepoints.into_iter())
     }
 
+    /// Runs `f` unless an operation with id `op_id` was applied before
+    ///
+    /// The id is recorded in a system table of this transaction once `f` succeeds, so it commits
+    /// atomically with the writes of `f`: either both become durable, including through WAL
+    /// recovery, or neither does. If the id is already recorded, `f` is not run and
+    /// `[OpOutcome::Replayed]` is returned. If `f` fails, the id is not recorded, but writes `f`
+    /// made before failing are left in the transaction; abort it to discard them.
+    ///
+    /// Restoring a savepoint does not forget the ids recorded after it. Recorded ids are kept
+    /// until removed with `[prune_op_ids_before()]`.
+    pub fn idempotent<T, E: From<StorageError>>(
+        &self,
+        op_id: u128,
+        f: impl FnOnce(&WriteTransaction) -> Result<T, E>,
+    ) -> Result<OpOutcome<T>, E> {
+        {
+            let mut system_tables = self.system_tables.lock().unwrap();
+            let table = system_tables.open_system_table(self, OP_ID_TABLE)?;
+            if table.get(op_id)?.is_some() {
+                return Ok(OpOutcome::Replayed);
+            }
+        }
+
+        let value = f(self)?;
+
+        let recorded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| {
+                u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+            });
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut table = system_tables.open_system_table(self, OP_ID_TABLE)?;
+        table.insert(op_id, recorded_at)?;
+        Ok(OpOutcome::Applied(value))
+    }
+
+    /// Forgets the operation ids recorded by `[idempotent()]` before `timestamp_ms`, in
+    /// milliseconds since the Unix epoch
+    ///
+    /// Once pruned, an operation id is applied again if it is replayed. Returns the number of ids
+    /// removed.
+    pub fn prune_op_ids_before(&self, timestamp_ms: u64) -> Result<u64> {
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut table = system_tables.open_system_table(self, OP_ID_TABLE)?;
+        let mut pruned = 0;
+        for entry in
+            table.extract_from_if::<u128, _>(.., |_, recorded_at| recorded_at < timestamp_ms)?
+        {
+            entry?;
+            pruned += 1;
+        }
+        Ok(pruned)
+    }
+
     // TODO: deduplicate this with the one in Database
     fn allocate_read_transaction(&self) -> Result<TransactionGuard> {
         let id = self
@@ -1140,6 +1319,8 @@ impl WriteTransaction {
         // the database
         assert_eq!(self.mem.get_version(), savepoint.get_version());
         self.dirty.store(true, Ordering::Release);
+        // Savepoints within the transaction are on another timeline once this one is restored
+        self.release_transaction_savepoints();
 
         // Restoring a savepoint needs to accomplish the following:
         // 1) restore the table tree. This is trivial, since we have the old root
@@ -1203,6 +1384,119 @@ impl WriteTransaction {
         Ok(())
     }
 
+    /// Marks a point within this transaction that it can be rolled back to with
+    /// `[rollback_to_savepoint()]`
+    ///
+    /// Unlike `[ephemeral_savepoint()]`, this can be called after tables have been written, and
+    /// the savepoint only lives as long as the transaction. Tables must be closed first. Only
+    /// tables are rolled back: operation ids recorded by `[idempotent()]` and persistent
+    /// savepoints created since the savepoint are kept.
+    ///
+    /// Pages written before the savepoint are copied rather than modified in place by later
+    /// writes, so that the savepoint still sees them, which makes those writes more expensive.
+    /// The commit only logs the state the transaction ends in, so the WAL never sees a state
+    /// that was rolled back.
+    pub fn savepoint(&mut self) -> Result<TransactionSavepoint, SavepointError> {
+        let tables = self.tables.get_mut().unwrap();
+        let user_root = tables
+            .table_tree
+            .flush_table_root_updates()?
+            .finalize_dirty_checksums()?;
+        let frozen = self.mem.freeze_uncommitted();
+        // Pages of the data tree allocated from now on are freed when rolling back
+        tables.allocated_pages.lock().unwrap().track();
+        let data_freed_len = tables.freed_pages.lock().unwrap().len();
+
+        let id = self.next_transaction_savepoint;
+        self.next_transaction_savepoint += 1;
+        self.transaction_savepoints.push(SavepointState {
+            id,
+            user_root,
+            frozen,
+            data_freed_len,
+        });
+
+        Ok(TransactionSavepoint {
+            transaction_tracker: self.transaction_tracker.clone(),
+            transaction_id: self.transaction_id,
+            id,
+        })
+    }
+
+    /// Rolls the tables of this transaction back to the given [`TransactionSavepoint`]
+    ///
+    /// Calling this method invalidates all [`TransactionSavepoint`]s created after the savepoint,
+    /// which can itself be rolled back to again. Returns `[SavepointError::InvalidSavepoint]` if
+    /// the savepoint was created by another transaction, or was invalidated.
+    pub fn rollback_to_savepoint(
+        &mut self,
+        savepoint: &TransactionSavepoint,
+    ) -> Result<(), SavepointError> {
+        let position = self
+            .transaction_savepoints
+            .iter()
+            .position(|state| state.id == savepoint.id)
+            .filter(|_| {
+                Arc::ptr_eq(&savepoint.transaction_tracker, &self.transaction_tracker)
+                    && savepoint.transaction_id == self.transaction_id
+            })
+            .ok_or(SavepointError::InvalidSavepoint)?;
+        self.transaction_savepoints.truncate(position + 1);
+        let state = &self.transaction_savepoints[position];
+
+        let tables = self.tables.get_mut().unwrap();
+        tables.table_tree.restore_root(state.user_root);
+        // Pages freed since the savepoint are reachable from it again
+        tables
+            .freed_pages
+            .lock()
+            .unwrap()
+            .truncate(state.data_freed_len);
+        // Pages of the data tree allocated since the savepoint are not reachable from it. The
+        // pages it references stay frozen, so that it can be rolled back to again
+        self.mem.set_frozen(&state.frozen);
+        let mut allocated = tables.allocated_pages.lock().unwrap();
+        for page in allocated.tracked() {
+            if !state.frozen.contains(&page) {
+                self.mem.free(page, &mut allocated);
+            }
+        }
+        self.dirty.store(true, Ordering::Release);
+
+        Ok(())
+    }
+
+    // Releases the savepoints created within this transaction. Pages allocated by the transaction
+    // whose frees were deferred because a savepoint referenced them are freed now
+    fn release_transaction_savepoints(&mut self) {
+        if self.transaction_savepoints.is_empty() {
+            return;
+        }
+        self.transaction_savepoints.clear();
+        self.mem.thaw();
+
+        let mem = &self.mem;
+        let tables = self.tables.get_mut().unwrap();
+        let mut allocated = tables.allocated_pages.lock().unwrap();
+        tables.freed_pages.lock().unwrap().retain(|&page| {
+            if !mem.uncommitted(page) {
+                return true;
+            }
+            // The page may have been allocated before its allocation was tracked
+            mem.free(page, &mut PageTrackerPolicy::Ignore);
+            allocated.forget(page);
+            false
+        });
+        let system_freed_pages = self.system_tables.get_mut().unwrap().system_freed_pages();
+        system_freed_pages.lock().unwrap().retain(|&page| {
+            if !mem.uncommitted(page) {
+                return true;
+            }
+            mem.free(page, &mut PageTrackerPolicy::Ignore);
+            false
+        });
+    }
+
     /// Set the desired durability level for writes made in this transaction
     /// Defaults to [`Durability::Immediate`]
     ///
@@ -1225,6 +1519,7 @@ impl WriteTransaction {
 
         self.durability = match durability {
             Durability::None => InternalDurability::None,
+            Durability::Eventual => InternalDurability::Eventual,
             Durability::Immediate => InternalDurability::Immediate,
         };
 
@@ -1378,6 +1673,44 @@ impl WriteTransaction {
             .delete_multimap_table(self, &name)
     }
 
+    // Creates a normal table with the given key and value types, filled with the entries that
+    // `fill` pushes in ascending key order. Keys are never compared, so the table's Rust types
+    // are not needed
+    pub(crate) fn create_raw_table<F>(
+        &self,
+        name: &str,
+        key_type: TypeName,
+        value_type: TypeName,
+        fixed_key_size: Option<usize>,
+        fixed_value_size: Option<usize>,
+        fill: F,
+    ) -> Result<(), TableError>
+    where
+        F: FnOnce(&mut UntypedBtreeBuilder) -> Result,
+    {
+        let mut tables = self.tables.lock().unwrap();
+        tables.set_dirty(self);
+
+        let mut builder = UntypedBtreeBuilder::new(
+            self.mem.clone(),
+            tables.allocated_pages.clone(),
+            fixed_key_size,
+            fixed_value_size,
+        );
+        fill(&mut builder)?;
+        let root = builder.finish()?;
+
+        let definition = InternalTableDefinition::new_untyped(
+            root,
+            root.map_or(0, |header| header.length),
+            fixed_key_size,
+            fixed_value_size,
+            key_type,
+            value_type,
+        );
+        tables.table_tree.create_table_untyped(name, definition)
+    }
+
     /// List all the tables
     pub fn list_tables(&self) -> Result<impl Iterator<Item = UntypedTableHandle> + '_> {
         self.tables
@@ -1404,17 +1737,222 @@ impl WriteTransaction {
     ///
     /// All writes performed in this transaction will be visible to future transactions, and are
     /// durable as consistent with the [`Durability`] level set by [`Self::set_durability`]
+    ///
+    /// In a column family, an error writing the transaction's WAL entry, such as a full disk,
+    /// is returned and the transaction is rolled back, so later transactions can commit once
+    /// the error clears. If the entry was written but could not be synced, the error is
+    /// returned but the transaction stays visible, as with [`Durability::Eventual`], and
+    /// becomes durable with the next successful sync or checkpoint.
     pub fn commit(mut self) -> Result<(), CommitError> {
+        // Only time the commit if someone is listening
+        let started = self.metrics.as_ref().map(|_| Instant::now());
+
+        // Set completed flag first, so that we don't go through the abort() path on drop, if this fails
+        self.completed = true;
+        let (wal_wait, _) = self.commit_inner(started.is_some(), true)?;
+
+        // Throttle the writer if the WAL is growing faster than it is checkpointed
+        if self.wal_journal.is_some()
+            && let Some(checkpoint_mgr) = &self.checkpoint_manager
+        {
+            checkpoint_mgr.apply_backpressure();
+        }
+
+        if let (Some(metrics), Some(cf_name), Some(started)) =
+            (&self.metrics, &self.cf_name, started)
+        {
+            metrics.on_commit(cf_name, wal_wait, started.elapsed());
+        }
+
+        Ok(())
+    }
+
+    /// Commits without waiting for the WAL entry to be synced, returning what remains to be
+    /// waited for before the commit is durable.
+    ///
+    /// The transaction is visible to other transactions as soon as this returns, as with
+    /// [`Durability::Eventual`]. Only logged transactions may be committed this way.
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    pub(crate) fn commit_unsynced(mut self) -> Result<UnsyncedCommit, CommitError> {
+        let started = self.metrics.as_ref().map(|_| Instant::now());
+
+        // Set completed flag first, so that we don't go through the abort() path on drop, if this fails
+        self.completed = true;
+        let (wal_wait, sequence) = self.commit_inner(started.is_some(), false)?;
+
+        Ok(UnsyncedCommit {
+            wal: self.wal_journal.clone().zip(sequence),
+            checkpoint_manager: self.checkpoint_manager.clone(),
+            metrics: self.metrics.clone().zip(self.cf_name.clone()),
+            started,
+            wal_wait,
+        })
+    }
+
+    /// Returns `true` if this transaction is logged to the WAL.
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    pub(crate) fn is_logged(&self) -> bool {
+        self.wal_journal.is_some() && self.cf_name.is_some()
+    }
+
+    /// Commits, returning the time spent in the WAL if `timed`, and zero otherwise.
+    ///
+    /// Without `wait`, a logged commit is made visible before its WAL entry is synced, as
+    /// with [`Durability::Eventual`], and the entry's sequence number is returned for the
+    /// caller to wait on unless the transaction's durability is `Eventual` anyway.
+    fn commit_inner(
+        &mut self,
+        timed: bool,
+        wait: bool,
+    ) -> Result<(Duration, Option<u64>), CommitError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(parent: &self.span, "commit").entered();
+
+        let mut prepared = self.prepare_commit()?;
+
+        // Keep checkpoints from truncating our entry until the commit is visible in memory
+        let wal_journal = self.wal_journal.clone();
+        let _commit_guard = wal_journal.as_ref().map(|journal| journal.commit_guard());
+
+        // Append to WAL if enabled (AFTER system root is finalized)
+        let mut wal_wait = Duration::ZERO;
+        let mut unsynced = None;
+        if let (Some(wal_journal), Some(cf_name)) = (&self.wal_journal, &self.cf_name) {
+            let mut entry = self.wal_entry(cf_name.clone(), &mut prepared);
+            let wal_started = timed.then(Instant::now);
+
+            // Append to WAL and wait for group commit fsync
+            let sequence = match wal_journal.append(&mut entry) {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    self.rollback_prepared_commit()?;
+                    return Err(CommitError::Storage(StorageError::from(err)));
+                }
+            };
+
+            // Wait for background sync thread to fsync (group commit). Eventual commits are
+            // synced by the next commit that waits, or by a checkpoint. A commit whose sync
+            // fails is still in the WAL, so it is made visible like an eventual one.
+            let mut sync_error = None;
+            // The else block only holds the `if let` below when tracing is disabled
+            #[cfg_attr(not(feature = "tracing"), allow(clippy::collapsible_else_if))]
+            if self.durability == InternalDurability::Eventual || !wait {
+                self.transaction_tracker
+                    .register_unsynced_commit(self.transaction_id, sequence);
+                if self.durability != InternalDurability::Eventual {
+                    unsynced = Some(sequence);
+                }
+            } else {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!(
+                    "wal_sync",
+                    cf = cf_name.as_str(),
+                    wal_sequence = sequence
+                )
+                .entered();
+                if let Err(err) = wal_journal.wait_for_sync(sequence) {
+                    self.transaction_tracker
+                        .register_unsynced_commit(self.transaction_id, sequence);
+                    sync_error = Some(err);
+                }
+            }
+            if let Some(wal_started) = wal_started {
+                wal_wait = wal_started.elapsed();
+            }
+
+            // Register for checkpoint
+            if let Some(checkpoint_mgr) = &self.checkpoint_manager {
+                checkpoint_mgr.register_pending(sequence, cf_name);
+            }
+
+            if let Some(err) = sync_error {
+                self.finish_commit(prepared)?;
+                return Err(CommitError::Storage(StorageError::from(err)));
+            }
+        }
+
+        self.finish_commit(prepared)?;
+
+        Ok((wal_wait, unsynced))
+    }
+
+    /// Prepares this transaction as a member of a write group, returning its WAL entry.
+    ///
+    /// The caller appends the entries of all members together, waits for them to be synced,
+    /// and then makes each member visible with [`Self::finish_group_commit`]. The caller also
+    /// holds the journal's commit guard for the whole group, which is why it is not taken here.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn prepare_group_commit(
+        &mut self,
+    ) -> Result<(PreparedCommit, crate::column_family::wal::entry::WALEntry), CommitError> {
         // Set completed flag first, so that we don't go through the abort() path on drop, if this fails
         self.completed = true;
-        self.commit_inner()
+
+        let Some(cf_name) = self.cf_name.clone().filter(|_| self.wal_journal.is_some()) else {
+            return Err(CommitError::Storage(StorageError::from(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "write group members must be logged to the WAL",
+            ))));
+        };
+
+        let mut prepared = self.prepare_commit()?;
+        let entry = self.wal_entry(cf_name, &mut prepared);
+
+        Ok((prepared, entry))
+    }
+
+    /// Makes a write group member prepared by [`Self::prepare_group_commit`] visible, once
+    /// the group's entries are durable in the WAL.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn finish_group_commit(
+        &mut self,
+        prepared: PreparedCommit,
+    ) -> Result<(), CommitError> {
+        self.finish_commit(prepared)
+    }
+
+    /// Records that the WAL entries of this member's write group, up to `sequence`, could not
+    /// be synced, so that the pages of the last synced commit are kept until they are.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn register_unsynced_group_commit(&self, sequence: u64) {
+        self.transaction_tracker
+            .register_unsynced_commit(self.transaction_id, sequence);
+    }
+
+    /// Rolls back a write group member prepared by [`Self::prepare_group_commit`], when the
+    /// group's entries could not be appended to the WAL.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn rollback_group_commit(&mut self) -> Result {
+        self.rollback_prepared_commit()
+    }
+
+    /// Rolls back a commit prepared by [`Self::prepare_commit`] whose WAL entry could not be
+    /// written, so that the next transaction starts from the last commit.
+    ///
+    /// Besides discarding the pages this transaction allocated, this undoes the freeing of
+    /// the pages of earlier non-durable commits, since the freed tree that records them is
+    /// rolled back with the transaction. The tables were already closed when the commit was
+    /// prepared, so unlike [`Self::abort_inner`] this leaves them alone.
+    fn rollback_prepared_commit(&mut self) -> Result {
+        self.mem.rollback_uncommitted_writes()?;
+        for page in self.freed_unpersisted.drain(..) {
+            self.mem.restore_unpersisted(page);
+        }
+        for transaction_id in self.processed_non_durable.drain(..) {
+            self.transaction_tracker
+                .restore_unprocessed_non_durable_commit(transaction_id);
+        }
+        Ok(())
     }
 
-    fn commit_inner(&mut self) -> Result<(), CommitError> {
+    /// Flushes the transaction's tables and prepares the roots it will commit, up to the point
+    /// where the commit can be logged to the WAL.
+    fn prepare_commit(&mut self) -> Result<PreparedCommit, CommitError> {
         // Quick-repair requires 2-phase commit
         if self.quick_repair {
             self.two_phase_commit = true;
         }
+        self.release_transaction_savepoints();
 
         let (user_root, allocated_pages, data_freed) =
             self.tables.lock().unwrap().table_tree.flush_and_close()?;
@@ -1433,7 +1971,7 @@ impl WriteTransaction {
                 let (sys_root, frees) = self.prepare_system_root_for_non_durable_commit()?;
                 (sys_root, Some(frees))
             }
-            InternalDurability::Immediate => {
+            InternalDurability::Eventual | InternalDurability::Immediate => {
                 if self.wal_journal.is_some() {
                     // WAL path: use non-durable preparation
                     let (sys_root, frees) = self.prepare_system_root_for_non_durable_commit()?;
@@ -1446,38 +1984,52 @@ impl WriteTransaction {
             }
         };
 
-        // Append to WAL if enabled (AFTER system root is finalized)
-        if let (Some(wal_journal), Some(cf_name)) = (&self.wal_journal, &self.cf_name) {
-            use crate::column_family::wal::entry::{WALEntry, WALTransactionPayload};
-
-            let payload = WALTransactionPayload {
-                user_root: user_root.map(|h| (h.root, h.checksum, h.length)),
-                system_root: system_root.map(|h| (h.root, h.checksum, h.length)),
-                freed_pages: data_freed_clone,
-                allocated_pages: allocated_pages_vec,
-                durability: match self.durability {
-                    InternalDurability::None => Durability::None,
-                    InternalDurability::Immediate => Durability::Immediate,
-                },
-            };
-
-            let mut entry = WALEntry::new(cf_name.clone(), self.transaction_id.raw_id(), payload);
+        Ok(PreparedCommit {
+            user_root,
+            system_root,
+            post_commit_frees,
+            data_freed: data_freed_clone,
+            allocated_pages: allocated_pages_vec,
+        })
+    }
 
-            // Append to WAL and wait for group commit fsync
-            let sequence = wal_journal
-                .append(&mut entry)
-                .map_err(|e| CommitError::Storage(StorageError::from(e)))?;
+    /// Builds the WAL entry logging a prepared commit.
+    fn wal_entry(
+        &self,
+        cf_name: String,
+        prepared: &mut PreparedCommit,
+    ) -> crate::column_family::wal::entry::WALEntry {
+        use crate::column_family::wal::entry::{WALEntry, WALTransactionPayload};
+
+        let payload = WALTransactionPayload {
+            user_root: prepared.user_root.map(|h| (h.root, h.checksum, h.length)),
+            system_root: prepared.system_root.map(|h| (h.root, h.checksum, h.length)),
+            freed_pages: std::mem::take(&mut prepared.data_freed),
+            allocated_pages: std::mem::take(&mut prepared.allocated_pages),
+            durability: match self.durability {
+                InternalDurability::None => Durability::None,
+                InternalDurability::Eventual => Durability::Eventual,
+                InternalDurability::Immediate => Durability::Immediate,
+            },
+        };
 
-            // Wait for background sync thread to fsync (group commit)
-            wal_journal
-                .wait_for_sync(sequence)
-                .map_err(|e| CommitError::Storage(StorageError::from(e)))?;
+        WALEntry::new(
+            cf_name,
+            self.cf_generation,
+            self.transaction_id.raw_id(),
+            payload,
+        )
+    }
 
-            // Register for checkpoint
-            if let Some(checkpoint_mgr) = &self.checkpoint_manager {
-                checkpoint_mgr.register_pending(sequence);
-            }
-        }
+    /// Makes a prepared commit visible to future transactions, committing it durably first if
+    /// it is not logged to the WAL.
+    fn finish_commit(&mut self, prepared: PreparedCommit) -> Result<(), CommitError> {
+        let PreparedCommit {
+            user_root,
+            system_root,
+            post_commit_frees,
+            ..
+        } = prepared;
 
         #[cfg(feature = "logging")]
         debug!(
@@ -1501,9 +2053,9 @@ impl WriteTransaction {
                     }
                 }
             }
-            InternalDurability::Immediate => {
+            InternalDurability::Eventual | InternalDurability::Immediate => {
                 if self.wal_journal.is_some() {
-                    // WAL already fsynced, just make changes visible
+                    // WAL entry appended, just make changes visible
                     self.mem
                         .non_durable_commit(user_root, system_root, self.transaction_id)?;
                     self.transaction_tracker.register_non_durable_commit(
@@ -1762,27 +2314,41 @@ impl WriteTransaction {
         if let Some((_, oldest_savepoint)) = self.transaction_tracker.oldest_savepoint() {
             free_until_transaction = TransactionId::min(free_until_transaction, oldest_savepoint);
         }
+        // WAL recovery may fall back to the last synced entry, so the pages that entry references
+        // must not be reused until every later entry is synced
+        if let Some(wal_journal) = &self.wal_journal
+            && let Some(oldest_unsynced) = self
+                .transaction_tracker
+                .oldest_unsynced_commit(wal_journal.synced_sequence())
+        {
+            free_until_transaction = TransactionId::min(free_until_transaction, oldest_unsynced);
+        }
         self.process_freed_pages_nondurable(free_until_transaction)?;
 
+        // An eventual commit isn't synced when it returns, so it leaves its unpersisted pages in
+        // the freed table for a later commit to free
+        let free_after_commit = self.durability != InternalDurability::Eventual;
         let mut post_commit_frees = vec![];
 
         let system_root = {
             let mut system_tables = self.system_tables.lock().unwrap();
             let system_freed_pages = system_tables.system_freed_pages();
             system_tables.table_tree.flush_table_root_updates()?;
-            for page in system_freed_pages
-                .lock()
-                .unwrap()
-                .extract_if(.., |p| self.mem.unpersisted(*p))
-            {
-                post_commit_frees.push(page);
+            if free_after_commit {
+                for page in system_freed_pages
+                    .lock()
+                    .unwrap()
+                    .extract_if(.., |p| self.mem.unpersisted(*p))
+                {
+                    post_commit_frees.push(page);
+                }
             }
             // Store all freed pages for a future commit(), since we can't free pages during a
             // non-durable commit (it's non-durable, so could be rolled back anytime in the future)
             self.store_system_freed_pages(
                 &mut system_tables.table_tree,
                 system_freed_pages,
-                Some(&mut post_commit_frees),
+                free_after_commit.then_some(&mut post_commit_frees),
                 &mut 0,
             )?;
 
@@ -1920,6 +2486,7 @@ impl WriteTransaction {
         let mut data_freed = system_tables.open_system_table(self, definition)?;
 
         let mut candidate_transactions = vec![];
+        let mut freed = vec![];
         for entry in data_freed.range(first_key..last_key)? {
             let (key, _) = entry?;
             let transaction_id = TransactionId::new(key.value().transaction_id);
@@ -1943,10 +2510,12 @@ impl WriteTransaction {
                 let mut new_pages = vec![];
                 for i in 0..pages.len() {
                     let page = pages.get(i);
-                    if !self
+                    if self
                         .mem
                         .free_if_unpersisted(page, &mut PageTrackerPolicy::Ignore)
                     {
+                        freed.push(page);
+                    } else {
                         new_pages.push(page);
                     }
                 }
@@ -1966,6 +2535,9 @@ impl WriteTransaction {
             }
             processed.push(transaction_id);
         }
+        drop(data_freed);
+        drop(system_tables);
+        self.freed_unpersisted.extend(freed);
 
         Ok(processed)
     }
@@ -1988,10 +2560,11 @@ impl WriteTransaction {
         processed
             .extend(self.process_freed_pages_nondurable_helper(free_until, SYSTEM_FREED_TABLE)?);
 
-        for transaction_id in processed {
+        for transaction_id in &processed {
             self.transaction_tracker
-                .mark_unprocessed_non_durable_commit(transaction_id);
+                .mark_unprocessed_non_durable_commit(*transaction_id);
         }
+        self.processed_non_durable.extend(processed);
 
         Ok(())
     }
@@ -2145,6 +2718,14 @@ impl ReadTransaction {
         guard: TransactionGuard,
     ) -> Result<Self, TransactionError> {
         let root_page = mem.get_data_root();
+        Self::new_at(mem, guard, root_page)
+    }
+
+    pub(crate) fn new_at(
+        mem: Arc<TransactionalMemory>,
+        guard: TransactionGuard,
+        root_page: Option<BtreeHeader>,
+    ) -> Result<Self, TransactionError> {
         let guard = Arc::new(guard);
         Ok(Self {
             mem: mem.clone(),
@@ -2201,6 +2782,35 @@ impl ReadTransaction {
         }
     }
 
+    // Returns the stored definition of a normal table, along with an untyped view of its tree
+    pub(crate) fn open_raw_table(
+        &self,
+        name: &str,
+    ) -> Result<(InternalTableDefinition, RawBtree), TableError> {
+        let definition = self
+            .tree
+            .get_table_untyped(name, TableType::Normal)?
+            .ok_or_else(|| TableError::TableDoesNotExist(name.to_string()))?;
+
+        match definition {
+            InternalTableDefinition::Normal {
+                table_root,
+                fixed_key_size,
+                fixed_value_size,
+                ..
+            } => {
+                let tree = RawBtree::new(
+                    table_root,
+                    fixed_key_size,
+                    fixed_value_size,
+                    self.mem.clone(),
+                );
+                Ok((definition, tree))
+            }
+            InternalTableDefinition::Multimap { .. } => unreachable!(),
+        }
+    }
+
     /// Open the given table
     pub fn open_multimap_table<K: Key + 'static, V: Key + 'static>(
         &self,
@@ -2269,6 +2879,13 @@ impl ReadTransaction {
             .map(|x| x.into_iter().map(UntypedMultimapTableHandle::new))
     }
 
+    /// Returns the id of the committed transaction whose state this transaction sees
+    ///
+    /// Two read transactions with the same snapshot id see the same data.
+    pub fn snapshot_id(&self) -> u64 {
+        self.tree.transaction_guard().id().raw_id()
+    }
+
     /// Close the transaction
     ///
     /// Transactions are automatically closed when they and all objects referencing them have been dropped,
@@ -2291,6 +2908,43 @@ impl Debug for ReadTransaction {
     }
 }
 
+/// A pinned, committed state of a database
+///
+/// Created by [`Database::snapshot`](crate::Database::snapshot). While a snapshot is alive, the
+/// pages of its state are not reclaimed, and read transactions opened from it with
+/// [`Database::begin_read_at`](crate::Database::begin_read_at) all see the same data.
+pub struct Snapshot {
+    guard: TransactionGuard,
+    root: Option<BtreeHeader>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(guard: TransactionGuard, root: Option<BtreeHeader>) -> Self {
+        Self { guard, root }
+    }
+
+    /// Returns the id of the committed transaction this snapshot pins
+    ///
+    /// This is the [`ReadTransaction::snapshot_id`] of every read transaction opened from it.
+    pub fn id(&self) -> u64 {
+        self.guard.id().raw_id()
+    }
+
+    pub(crate) fn guard(&self) -> &TransactionGuard {
+        &self.guard
+    }
+
+    pub(crate) fn root(&self) -> Option<BtreeHeader> {
+        self.root
+    }
+}
+
+impl Debug for Snapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot").field("id", &self.id()).finish()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Database, TableDefinition};
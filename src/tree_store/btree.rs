@@ -1,7 +1,8 @@
 use crate::db::TransactionGuard;
 use crate::tree_store::btree_base::{
-    AccessGuardMut, BRANCH, BranchAccessor, BranchMutator, BtreeHeader, Checksum, DEFERRED, LEAF,
-    LeafAccessor, branch_checksum, leaf_checksum,
+    AccessGuardMut, BRANCH, BranchAccessor, BranchBuilder, BranchMutator, BtreeHeader, Checksum,
+    DEFERRED, LEAF, LeafAccessor, LeafBuilder, RawBranchBuilder, RawLeafBuilder, branch_checksum,
+    leaf_checksum,
 };
 use crate::tree_store::btree_iters::BtreeExtractIf;
 use crate::tree_store::btree_mutator::MutateHelper;
@@ -11,7 +12,7 @@ use crate::tree_store::{
     PageTrackerPolicy,
 };
 use crate::types::{Key, MutInPlaceValue, Value};
-use crate::{AccessGuard, Result};
+use crate::{AccessGuard, Result, StorageError};
 #[cfg(feature = "logging")]
 use log::trace;
 use std::borrow::Borrow;
@@ -792,6 +793,186 @@ impl RawBtree {
             _ => false,
         })
     }
+
+    // Calls `visitor` with every key and value, in key order
+    pub(crate) fn for_each_entry<F>(&self, mut visitor: F) -> Result
+    where
+        F: FnMut(&[u8], &[u8]) -> Result,
+    {
+        if let Some(header) = self.root {
+            self.for_each_entry_helper(header.root, &mut visitor)?;
+        }
+        Ok(())
+    }
+
+    fn for_each_entry_helper<F>(&self, page_number: PageNumber, visitor: &mut F) -> Result
+    where
+        F: FnMut(&[u8], &[u8]) -> Result,
+    {
+        let page = self.mem.get_page(page_number)?;
+        match page.memory()[0] {
+            LEAF => {
+                let accessor =
+                    LeafAccessor::new(page.memory(), self.fixed_key_size, self.fixed_value_size);
+                for i in 0..accessor.num_pairs() {
+                    let entry = accessor.entry(i).unwrap();
+                    visitor(entry.key(), entry.value())?;
+                }
+            }
+            BRANCH => {
+                let accessor = BranchAccessor::new(&page, self.fixed_key_size);
+                for i in 0..accessor.count_children() {
+                    self.for_each_entry_helper(accessor.child_page(i).unwrap(), visitor)?;
+                }
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}
+
+// Builds a new btree bottom up from entries pushed in ascending key order. Keys are never
+// compared, so a tree can be built for a table whose types are only known from its stored
+// definition. Checksums are left deferred, to be finalized when the root is flushed
+pub(crate) struct UntypedBtreeBuilder {
+    mem: Arc<TransactionalMemory>,
+    allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
+    fixed_key_size: Option<usize>,
+    fixed_value_size: Option<usize>,
+    pending: Vec<(Vec<u8>, Vec<u8>)>,
+    pending_bytes: usize,
+    // Completed pages of the leaf level, each with its last key
+    leaves: Vec<(PageNumber, Vec<u8>)>,
+    length: u64,
+}
+
+impl UntypedBtreeBuilder {
+    pub(crate) fn new(
+        mem: Arc<TransactionalMemory>,
+        allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
+        fixed_key_size: Option<usize>,
+        fixed_value_size: Option<usize>,
+    ) -> Self {
+        Self {
+            mem,
+            allocated_pages,
+            fixed_key_size,
+            fixed_value_size,
+            pending: vec![],
+            pending_bytes: 0,
+            leaves: vec![],
+            length: 0,
+        }
+    }
+
+    // The caller must push keys in strictly ascending order
+    pub(crate) fn push(&mut self, key: &[u8], value: &[u8]) -> Result {
+        if self.fixed_key_size.is_some_and(|width| width != key.len())
+            || self
+                .fixed_value_size
+                .is_some_and(|width| width != value.len())
+        {
+            return Err(StorageError::Corrupted(format!(
+                "Entry of {} key bytes and {} value bytes does not match the table's fixed widths",
+                key.len(),
+                value.len()
+            )));
+        }
+
+        let required = RawLeafBuilder::required_bytes(
+            self.pending.len() + 1,
+            self.pending_bytes + key.len() + value.len(),
+            self.fixed_key_size,
+            self.fixed_value_size,
+        );
+        if required > self.mem.get_page_size() && !self.pending.is_empty() {
+            self.flush_leaf()?;
+        }
+
+        self.pending_bytes += key.len() + value.len();
+        self.pending.push((key.to_vec(), value.to_vec()));
+        self.length += 1;
+        Ok(())
+    }
+
+    fn flush_leaf(&mut self) -> Result {
+        let mut builder = LeafBuilder::new(
+            &self.mem,
+            &self.allocated_pages,
+            self.pending.len(),
+            self.fixed_key_size,
+            self.fixed_value_size,
+        );
+        for (key, value) in &self.pending {
+            builder.push(key, value);
+        }
+        let page_number = builder.build()?.get_page_number();
+
+        let (last_key, _) = self.pending.pop().unwrap();
+        self.leaves.push((page_number, last_key));
+        self.pending.clear();
+        self.pending_bytes = 0;
+        Ok(())
+    }
+
+    // Builds the branch levels and returns the root, or None if no entries were pushed
+    pub(crate) fn finish(mut self) -> Result<Option<BtreeHeader>> {
+        if !self.pending.is_empty() {
+            self.flush_leaf()?;
+        }
+
+        let mut level = std::mem::take(&mut self.leaves);
+        while level.len() > 1 {
+            // Group children into pages. Every branch needs at least two children, so a
+            // single child left over at the end joins the previous group
+            let mut groups: Vec<Vec<(PageNumber, Vec<u8>)>> = vec![];
+            let mut group: Vec<(PageNumber, Vec<u8>)> = vec![];
+            let mut group_key_bytes = 0;
+            for child in level {
+                let required = RawBranchBuilder::required_bytes(
+                    group.len(),
+                    group_key_bytes + child.1.len(),
+                    self.fixed_key_size,
+                );
+                if required > self.mem.get_page_size() && group.len() >= 2 {
+                    groups.push(std::mem::take(&mut group));
+                    group_key_bytes = 0;
+                }
+                group_key_bytes += child.1.len();
+                group.push(child);
+            }
+            if group.len() == 1
+                && let Some(previous) = groups.last_mut()
+            {
+                previous.append(&mut group);
+            } else {
+                groups.push(group);
+            }
+
+            level = Vec::with_capacity(groups.len());
+            for mut group in groups {
+                let mut builder = BranchBuilder::new(
+                    &self.mem,
+                    &self.allocated_pages,
+                    group.len(),
+                    self.fixed_key_size,
+                );
+                for (child, _) in &group {
+                    builder.push_child(*child, DEFERRED);
+                }
+                for (_, key) in &group[..group.len() - 1] {
+                    builder.push_key(key);
+                }
+                let page_number = builder.build()?.get_page_number();
+                let (_, last_key) = group.pop().unwrap();
+                level.push((page_number, last_key));
+            }
+        }
+
+        Ok(level
+            .pop()
+            .map(|(root, _)| BtreeHeader::new(root, DEFERRED, self.length)))
+    }
 }
 
 pub(crate) struct Btree<K: Key + 'static, V: Value + 'static> {
@@ -230,7 +230,14 @@ impl<V: Value + 'static> AccessGuard<'_, V> {
 
     /// Access the stored value
     pub fn value(&self) -> V::SelfType<'_> {
-        V::from_bytes(&self.page.memory()[self.offset..(self.offset + self.len)])
+        V::from_bytes(self.as_bytes())
+    }
+
+    /// Access the serialized bytes of the stored value
+    ///
+    /// The bytes are borrowed from the page holding the value, and have no particular alignment
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.page.memory()[self.offset..(self.offset + self.len)]
     }
 }
 
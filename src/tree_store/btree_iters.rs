@@ -357,12 +357,122 @@ impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> boo
     }
 }
 
+#[derive(Clone)]
+struct Readahead {
+    window: u64,
+    forward: ReadaheadCursor,
+    backward: ReadaheadCursor,
+}
+
+// Tracks the pages prefetched ahead of one end of a range scan. When the scan enters a new leaf,
+// the following children of the leaf's parent are prefetched until `window` bytes are ahead of
+// it. This is only topped up once less than half the window remains, so that each hint covers
+// several pages.
+#[derive(Clone, Default)]
+struct ReadaheadCursor {
+    // The leaf the scan was in when the cursor was last checked
+    leaf: Option<PageNumber>,
+    // The branch whose children are being prefetched, and the index of the next child to
+    // prefetch, in scan order
+    branch: Option<(PageNumber, isize)>,
+    // Bytes prefetched ahead of the scan
+    ahead: u64,
+}
+
+impl ReadaheadCursor {
+    fn top_up(
+        &mut self,
+        state: Option<&RangeIterState>,
+        other_end: Option<&RangeIterState>,
+        reverse: bool,
+        window: u64,
+        manager: &TransactionalMemory,
+    ) {
+        let Some(Leaf {
+            page: leaf,
+            parent: Some(parent),
+            ..
+        }) = state
+        else {
+            return;
+        };
+        if self.leaf == Some(leaf.get_page_number()) {
+            return;
+        }
+        self.leaf = Some(leaf.get_page_number());
+        let Internal {
+            page: branch,
+            fixed_key_size,
+            child,
+            ..
+        } = parent.as_ref()
+        else {
+            return;
+        };
+
+        let accessor = BranchAccessor::new(branch, *fixed_key_size);
+        let page_size = u32::try_from(manager.get_page_size()).unwrap();
+        let children = isize::try_from(accessor.count_children()).unwrap();
+        let direction = if reverse { -1 } else { 1 };
+        let child_page = |index: isize| {
+            accessor
+                .child_page(usize::try_from(index).unwrap())
+                .unwrap()
+        };
+        // Nothing past the leaf at the other end of the range is read
+        let stop = match other_end {
+            Some(Leaf { page, .. }) => Some(page.get_page_number()),
+            _ => None,
+        };
+
+        let child = isize::try_from(*child).unwrap();
+        // Continue after the pages already prefetched, less the leaf just entered, unless the
+        // scan has moved past them
+        let mut next = match self.branch {
+            Some((page, next))
+                if page == branch.get_page_number() && (next - child) * direction >= 0 =>
+            {
+                self.ahead = self
+                    .ahead
+                    .saturating_sub(leaf.get_page_number().page_size_bytes(page_size));
+                next
+            }
+            _ => {
+                self.ahead = 0;
+                child
+            }
+        };
+        if self.ahead > window / 2 {
+            return;
+        }
+
+        let mut pages = vec![];
+        while (0..children).contains(&next) {
+            let page = child_page(next);
+            let size = page.page_size_bytes(page_size);
+            if self.ahead + size > window {
+                break;
+            }
+            self.ahead += size;
+            pages.push(page);
+            next += direction;
+            if Some(page) == stop {
+                next = if reverse { -1 } else { children };
+                break;
+            }
+        }
+        manager.prefetch_pages(pages);
+        self.branch = Some((branch.get_page_number(), next));
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct BtreeRangeIter<K: Key + 'static, V: Value + 'static> {
     left: Option<RangeIterState>, // Exclusive. The previous element returned
     right: Option<RangeIterState>, // Exclusive. The previous element returned
     include_left: bool,           // left is inclusive, instead of exclusive
     include_right: bool,          // right is inclusive, instead of exclusive
+    readahead: Option<Readahead>,
     manager: Arc<TransactionalMemory>,
     _key_type: PhantomData<K>,
     _value_type: PhantomData<V>,
@@ -402,6 +512,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
                 right: None,
                 include_left: false,
                 include_right: false,
+                readahead: None,
                 manager,
                 _key_type: Default::default(),
                 _value_type: Default::default(),
@@ -459,6 +570,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
                 right,
                 include_left,
                 include_right,
+                readahead: None,
                 manager,
                 _key_type: Default::default(),
                 _value_type: Default::default(),
@@ -469,6 +581,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
                 right: None,
                 include_left: false,
                 include_right: false,
+                readahead: None,
                 manager,
                 _key_type: Default::default(),
                 _value_type: Default::default(),
@@ -476,6 +589,15 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
         }
     }
 
+    // Prefetches up to `window` bytes of the pages ahead of the scan, in either direction
+    pub(crate) fn set_readahead(&mut self, window: u64) {
+        self.readahead = Some(Readahead {
+            window,
+            forward: ReadaheadCursor::default(),
+            backward: ReadaheadCursor::default(),
+        });
+    }
+
     fn close(&mut self) {
         self.left = None;
         self.right = None;
@@ -510,6 +632,15 @@ impl<K: Key, V: Value> Iterator for BtreeRangeIter<K, V> {
                 match self.left.take()?.next(false, &self.manager) {
                     Ok(left) => {
                         self.left = left;
+                        if let Some(readahead) = &mut self.readahead {
+                            readahead.forward.top_up(
+                                self.left.as_ref(),
+                                self.right.as_ref(),
+                                false,
+                                readahead.window,
+                                &self.manager,
+                            );
+                        }
                     }
                     Err(err) => {
                         return Some(Err(err));
@@ -571,6 +702,15 @@ impl<K: Key, V: Value> DoubleEndedIterator for BtreeRangeIter<K, V> {
                 match self.right.take()?.next(true, &self.manager) {
                     Ok(right) => {
                         self.right = right;
+                        if let Some(readahead) = &mut self.readahead {
+                            readahead.backward.top_up(
+                                self.right.as_ref(),
+                                self.left.as_ref(),
+                                true,
+                                readahead.window,
+                                &self.manager,
+                            );
+                        }
                     }
                     Err(err) => {
                         return Some(Err(err));
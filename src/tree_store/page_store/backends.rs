@@ -35,6 +35,10 @@ impl StorageBackend for ReadOnlyBackend {
         unreachable!()
     }
 
+    fn prefetch(&self, offset: u64, len: u64) -> Result<(), Error> {
+        self.inner.prefetch(offset, len)
+    }
+
     fn close(&self) -> Result<(), Error> {
         self.inner.close()
     }
@@ -86,15 +90,7 @@ impl StorageBackend for InMemoryBackend {
     fn set_len(&self, len: u64) -> Result<(), io::Error> {
         let mut guard = self.write();
         let len = usize::try_from(len).map_err(|_| Self::out_of_range())?;
-        if guard.len() < len {
-            let additional = len - guard.len();
-            guard.reserve(additional);
-            for _ in 0..additional {
-                guard.push(0);
-            }
-        } else {
-            guard.truncate(len);
-        }
+        guard.resize(len, 0);
 
         Ok(())
     }
@@ -106,11 +102,14 @@ impl StorageBackend for InMemoryBackend {
     fn write(&self, offset: u64, data: &[u8]) -> Result<(), io::Error> {
         let mut guard = self.write();
         let offset = usize::try_from(offset).map_err(|_| Self::out_of_range())?;
-        if offset + data.len() <= guard.len() {
-            guard[offset..offset + data.len()].copy_from_slice(data);
-            Ok(())
-        } else {
-            Err(Self::out_of_range())
+        let end = offset
+            .checked_add(data.len())
+            .ok_or_else(Self::out_of_range)?;
+        // Like a file, the buffer grows to hold a write past its end
+        if end > guard.len() {
+            guard.resize(end, 0);
         }
+        guard[offset..end].copy_from_slice(data);
+        Ok(())
     }
 }
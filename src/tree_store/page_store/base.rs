@@ -317,6 +317,28 @@ impl PageTrackerPolicy {
         }
     }
 
+    // Starts tracking pages allocated from now on, if they were ignored
+    pub(crate) fn track(&mut self) {
+        if matches!(self, PageTrackerPolicy::Ignore) {
+            *self = PageTrackerPolicy::new_tracking();
+        }
+    }
+
+    // Returns the pages tracked so far
+    pub(crate) fn tracked(&self) -> Vec<PageNumber> {
+        match self {
+            PageTrackerPolicy::Track(x) => x.iter().copied().collect(),
+            PageTrackerPolicy::Ignore | PageTrackerPolicy::Closed => vec![],
+        }
+    }
+
+    // Stops tracking the page, which may have been allocated before tracking started
+    pub(crate) fn forget(&mut self, page: PageNumber) {
+        if let PageTrackerPolicy::Track(x) = self {
+            x.remove(&page);
+        }
+    }
+
     pub(crate) fn close(&mut self) -> HashSet<PageNumber> {
         let old = mem::replace(self, PageTrackerPolicy::Closed);
         match old {
@@ -165,6 +165,10 @@ impl BuddyAllocator {
         None
     }
 
+    pub(crate) fn is_allocated(&self, page: u32) -> bool {
+        self.find_free_order(page).is_none()
+    }
+
     pub(crate) fn trailing_free_pages(&self) -> u32 {
         let mut free_pages = 0;
         let mut next_page = self.len() - 1;
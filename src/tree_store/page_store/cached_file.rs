@@ -212,6 +212,13 @@ impl CheckedBackend {
         }
         result.map_err(StorageError::from)
     }
+
+    // Prefetching is only a hint, so a failure does not poison the backend
+    fn prefetch(&self, offset: u64, len: u64) {
+        if self.check_failure().is_ok() {
+            let _ = self.file.prefetch(offset, len);
+        }
+    }
 }
 
 pub(super) struct PagedCachedFile {
@@ -424,6 +431,12 @@ impl PagedCachedFile {
         Ok(buffer)
     }
 
+    // Hint that the given range will be read soon. Ranges already in the read cache are not
+    // skipped, since the cache is checked when they are read.
+    pub(super) fn prefetch(&self, offset: u64, len: u64) {
+        self.file.prefetch(offset, len);
+    }
+
     // Read with caching. Caller must not read overlapping ranges without first calling invalidate_cache().
     // Doing so will not cause UB, but is a logic error.
     pub(super) fn read(&self, offset: u64, len: usize, hint: PageHint) -> Result<Arc<[u8]>> {
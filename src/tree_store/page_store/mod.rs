@@ -17,11 +17,11 @@ mod xxh3;
 pub use backends::InMemoryBackend;
 pub(crate) use backends::ReadOnlyBackend;
 pub(crate) use base::{
-    Page, PageHint, PageNumber, PageTrackerPolicy, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH,
+    MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, Page, PageHint, PageNumber, PageTrackerPolicy,
 };
 pub(crate) use header::PAGE_SIZE;
 pub(crate) use page_manager::{
-    xxh3_checksum, ShrinkPolicy, TransactionalMemory, FILE_FORMAT_VERSION3,
+    FILE_FORMAT_VERSION3, ShrinkPolicy, TransactionalMemory, xxh3_checksum,
 };
 pub use savepoint::Savepoint;
 pub(crate) use savepoint::SerializedSavepoint;
@@ -1,14 +1,14 @@
 use crate::transaction_tracker::TransactionId;
 use crate::transactions::{AllocatorStateKey, AllocatorStateTree, AllocatorStateTreeMut};
 use crate::tree_store::btree_base::{BtreeHeader, Checksum};
-use crate::tree_store::page_store::base::{PageHint, MAX_PAGE_INDEX};
+use crate::tree_store::page_store::base::{MAX_PAGE_INDEX, PageHint};
 use crate::tree_store::page_store::buddy_allocator::BuddyAllocator;
 use crate::tree_store::page_store::cached_file::PagedCachedFile;
 use crate::tree_store::page_store::fast_hash::PageNumberHashSet;
-use crate::tree_store::page_store::header::{DatabaseHeader, DB_HEADER_SIZE, MAGICNUMBER};
+use crate::tree_store::page_store::header::{DB_HEADER_SIZE, DatabaseHeader, MAGICNUMBER};
 use crate::tree_store::page_store::layout::DatabaseLayout;
 use crate::tree_store::page_store::region::{Allocators, RegionTracker};
-use crate::tree_store::page_store::{hash128_with_seed, PageImpl, PageMut};
+use crate::tree_store::page_store::{PageImpl, PageMut, hash128_with_seed};
 use crate::tree_store::{Page, PageNumber, PageTrackerPolicy};
 use crate::{CacheStats, StorageBackend};
 use crate::{DatabaseError, Result, StorageError};
@@ -16,13 +16,13 @@ use arc_swap::ArcSwap;
 use std::cmp::{max, min};
 #[cfg(debug_assertions)]
 use std::collections::HashMap;
-#[cfg(debug_assertions)]
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::io::ErrorKind;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::ops::Range;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
 // The region header is optional in the v3 file format
@@ -103,6 +103,9 @@ pub(crate) struct TransactionalMemory {
     // Pages allocated since the last commit
     // TODO: maybe this should be moved to WriteTransaction?
     allocated_since_commit: Mutex<PageNumberHashSet>,
+    // Pages allocated since the last commit that a savepoint within the write transaction still
+    // references. They are treated as committed: copied on write, and their frees are deferred
+    frozen: Mutex<PageNumberHashSet>,
     unpersisted: Mutex<PageNumberHashSet>,
     // True if the allocator state was corrupted when the file was opened
     // TODO: maybe we can remove this flag now that CheckedBackend exists?
@@ -280,6 +283,7 @@ impl TransactionalMemory {
 
         Ok(Self {
             allocated_since_commit: Mutex::new(Default::default()),
+            frozen: Mutex::new(Default::default()),
             unpersisted: Mutex::new(Default::default()),
             needs_recovery: AtomicBool::new(needs_recovery),
             storage,
@@ -639,6 +643,8 @@ impl TransactionalMemory {
         // to future read transactions
         #[cfg(debug_assertions)]
         debug_assert!(self.open_dirty_pages.lock().unwrap().is_empty());
+        // Savepoints within the transaction must have been released before it commits
+        debug_assert!(self.frozen.lock().unwrap().is_empty());
         assert!(!self.needs_recovery.load(Ordering::Acquire));
 
         let mut state = self.state.lock().unwrap();
@@ -719,6 +725,8 @@ impl TransactionalMemory {
         // to future read transactions
         #[cfg(debug_assertions)]
         debug_assert!(self.open_dirty_pages.lock().unwrap().is_empty());
+        // Savepoints within the transaction must have been released before it commits
+        debug_assert!(self.frozen.lock().unwrap().is_empty());
         assert!(!self.needs_recovery.load(Ordering::Acquire));
 
         let mut unpersisted = self.unpersisted.lock().unwrap();
@@ -821,6 +829,24 @@ impl TransactionalMemory {
         self.non_durable_commit(data_root, system_root, transaction_id)
     }
 
+    /// Returns whether `page` lies entirely within the first `len` bytes of storage.
+    ///
+    /// Used to reject roots read from the WAL that point outside a column family's segments.
+    pub(crate) fn page_within(&self, page: PageNumber, len: u64) -> bool {
+        if page.page_order > MAX_MAX_PAGE_ORDER {
+            return false;
+        }
+        let page_bytes = page.page_size_bytes(self.page_size);
+        let regional_end =
+            self.region_header_with_padding_size + (u64::from(page.page_index) + 1) * page_bytes;
+        if regional_end > self.region_size {
+            return false;
+        }
+        let end =
+            u64::from(self.page_size) + u64::from(page.region) * self.region_size + regional_end;
+        end <= len
+    }
+
     pub(crate) fn rollback_uncommitted_writes(&self) -> Result {
         let result = self.rollback_uncommitted_writes_inner();
         if result.is_err() {
@@ -841,6 +867,7 @@ impl TransactionalMemory {
         assert!(!self.needs_recovery.load(Ordering::Acquire));
         let mut state = self.state.lock().unwrap();
         let mut guard = self.allocated_since_commit.lock().unwrap();
+        self.frozen.lock().unwrap().clear();
         for page_number in guard.iter() {
             let region_index = page_number.region;
             state
@@ -868,6 +895,32 @@ impl TransactionalMemory {
         Ok(())
     }
 
+    // Hint that the given pages will be read soon. Pages that are adjacent in the file are
+    // prefetched together, so that sequentially allocated pages need a single hint.
+    pub(crate) fn prefetch_pages(&self, pages: impl IntoIterator<Item = PageNumber>) {
+        let mut pending: Option<Range<u64>> = None;
+        for page_number in pages {
+            let range = page_number.address_range(
+                self.page_size.into(),
+                self.region_size,
+                self.region_header_with_padding_size,
+                self.page_size,
+            );
+            match &mut pending {
+                Some(pending) if pending.end == range.start => pending.end = range.end,
+                _ => {
+                    if let Some(previous) = pending.replace(range) {
+                        self.storage
+                            .prefetch(previous.start, previous.end - previous.start);
+                    }
+                }
+            }
+        }
+        if let Some(last) = pending {
+            self.storage.prefetch(last.start, last.end - last.start);
+        }
+    }
+
     // TODO: make all callers explicitly provide a hint
     pub(crate) fn get_page(&self, page_number: PageNumber) -> Result<PageImpl> {
         self.get_page_extended(page_number, PageHint::None)
@@ -913,11 +966,13 @@ impl TransactionalMemory {
     pub(crate) fn get_page_mut(&self, page_number: PageNumber) -> Result<PageMut> {
         #[cfg(debug_assertions)]
         {
-            assert!(!self
-                .read_page_ref_counts
-                .lock()
-                .unwrap()
-                .contains_key(&page_number));
+            assert!(
+                !self
+                    .read_page_ref_counts
+                    .lock()
+                    .unwrap()
+                    .contains_key(&page_number)
+            );
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page_number));
         }
 
@@ -987,21 +1042,26 @@ impl TransactionalMemory {
         Ok(header.primary_slot().transaction_id)
     }
 
-    /// Gets the current secondary slot state for checkpoint.
+    /// Gets the state of the latest commit for checkpoint, if it is not yet durable.
     ///
-    /// Returns the current transaction ID and roots from the secondary slot.
-    /// This is used during WAL checkpoint to get the current state before
-    /// performing a durable commit.
-    pub(crate) fn get_current_secondary_state(
+    /// Non-durable commits are recorded in the secondary slot. Once a durable commit has made
+    /// that state primary, the secondary slot holds the commit before it instead, so `None` is
+    /// returned: there is nothing to checkpoint, and committing the secondary slot would roll
+    /// the database back.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn get_non_durable_state(
         &self,
-    ) -> Result<(Option<BtreeHeader>, Option<BtreeHeader>, TransactionId)> {
+    ) -> Result<Option<(Option<BtreeHeader>, Option<BtreeHeader>, TransactionId)>> {
+        if !self.read_from_secondary.load(Ordering::Acquire) {
+            return Ok(None);
+        }
         let header = self.header_snapshot.load();
         let secondary = header.secondary_slot();
-        Ok((
+        Ok(Some((
             secondary.user_root,
             secondary.system_root,
             secondary.transaction_id,
-        ))
+        )))
     }
 
     pub(crate) fn free(&self, page: PageNumber, allocated: &mut PageTrackerPolicy) {
@@ -1012,11 +1072,13 @@ impl TransactionalMemory {
     fn free_helper(&self, page: PageNumber, allocated: &mut PageTrackerPolicy) {
         #[cfg(debug_assertions)]
         {
-            assert!(!self
-                .read_page_ref_counts
-                .lock()
-                .unwrap()
-                .contains_key(&page));
+            assert!(
+                !self
+                    .read_page_ref_counts
+                    .lock()
+                    .unwrap()
+                    .contains_key(&page)
+            );
             assert!(self.allocated_pages.lock().unwrap().remove(&page));
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page));
         }
@@ -1059,23 +1121,54 @@ impl TransactionalMemory {
         }
     }
 
+    // Undoes free_if_unpersisted(), for a page freed by a commit that was then rolled back
+    pub(crate) fn restore_unpersisted(&self, page: PageNumber) {
+        self.mark_page_allocated(page);
+        self.unpersisted.lock().unwrap().insert(page);
+    }
+
     // Frees the page if it was allocated since the last commit. Returns true, if the page was freed
     pub(crate) fn free_if_uncommitted(
         &self,
         page: PageNumber,
         allocated: &mut PageTrackerPolicy,
     ) -> bool {
-        if self.allocated_since_commit.lock().unwrap().remove(&page) {
-            self.free_helper(page, allocated);
-            true
-        } else {
-            false
+        let mut allocated_since_commit = self.allocated_since_commit.lock().unwrap();
+        if self.frozen.lock().unwrap().contains(&page) || !allocated_since_commit.remove(&page) {
+            return false;
         }
+        drop(allocated_since_commit);
+        self.free_helper(page, allocated);
+        true
     }
 
-    // Page has not been committed
+    // Page has not been committed, and is not referenced by a savepoint within the transaction
     pub(crate) fn uncommitted(&self, page: PageNumber) -> bool {
         self.allocated_since_commit.lock().unwrap().contains(&page)
+            && !self.frozen.lock().unwrap().contains(&page)
+    }
+
+    // Freezes all the pages allocated since the last commit, so that they are no longer modified
+    // or freed until thawed. Returns all the frozen pages
+    pub(crate) fn freeze_uncommitted(&self) -> HashSet<PageNumber> {
+        let allocated_since_commit = self.allocated_since_commit.lock().unwrap();
+        let mut frozen = self.frozen.lock().unwrap();
+        frozen.extend(allocated_since_commit.iter().copied());
+        frozen.iter().copied().collect()
+    }
+
+    // Replaces the frozen pages with the ones returned by an earlier freeze_uncommitted()
+    pub(crate) fn set_frozen(&self, pages: &HashSet<PageNumber>) {
+        let mut frozen = self.frozen.lock().unwrap();
+        frozen.clear();
+        frozen.extend(pages.iter().copied());
+    }
+
+    // Treats all the pages allocated since the last commit as uncommitted again
+    pub(crate) fn thaw(&self) {
+        let mut frozen = self.frozen.lock().unwrap();
+        frozen.clear();
+        frozen.shrink_to_fit();
     }
 
     pub(crate) fn unpersisted(&self, page: PageNumber) -> bool {
@@ -1305,6 +1398,69 @@ impl TransactionalMemory {
         Ok(count)
     }
 
+    // Free pages between the first and last allocated page of each region, left behind by
+    // freed pages, which compaction can reclaim. Space the allocator has not reached yet is not
+    // counted
+    pub(crate) fn count_reclaimable_pages(&self) -> Result<u64> {
+        let state = self.state.lock().unwrap();
+        let mut count = 0u64;
+        for i in 0..state.header.layout().num_regions() {
+            let region = state.get_region(i);
+            let Some(first) = (0..region.len()).find(|&page| region.is_allocated(page)) else {
+                continue;
+            };
+            let last = (first..region.len())
+                .rev()
+                .find(|&page| region.is_allocated(page))
+                .unwrap();
+            count += u64::from(last - first + 1 - region.count_allocated_pages());
+        }
+
+        Ok(count)
+    }
+
+    // Byte ranges of the storage holding the database header, region headers and allocated
+    // pages, with adjacent ranges merged. A copy of only these ranges can be repaired into the
+    // same database
+    pub(crate) fn allocated_ranges(&self) -> Vec<Range<u64>> {
+        let state = self.state.lock().unwrap();
+        let layout = state.header.layout();
+        let page_size = u64::from(self.page_size);
+
+        let mut ranges: Vec<Range<u64>> = vec![];
+        let mut push = |range: Range<u64>| match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => ranges.push(range),
+        };
+        push(0..layout.region_base_address(0));
+        for region in 0..layout.num_regions() {
+            let base = layout.region_base_address(region);
+            let data_start = base + layout.region_layout(region).data_section().start;
+            push(base..data_start);
+            let allocator = state.get_region(region);
+            for page in 0..allocator.len() {
+                if allocator.is_allocated(page) {
+                    let start = data_start + u64::from(page) * page_size;
+                    push(start..start + page_size);
+                }
+            }
+        }
+
+        ranges
+    }
+
+    // Length of the storage used by the layout recorded in the header of a closed database
+    pub(crate) fn stored_storage_len(
+        storage: &dyn StorageBackend,
+    ) -> std::result::Result<u64, DatabaseError> {
+        let mut header_bytes = vec![0; DB_HEADER_SIZE];
+        storage
+            .read(0, &mut header_bytes)
+            .map_err(StorageError::from)?;
+        let (header, _) = DatabaseHeader::from_bytes(&header_bytes)?;
+        Ok(header.layout().len())
+    }
+
     pub(crate) fn get_page_size(&self) -> usize {
         self.page_size.try_into().unwrap()
     }
@@ -245,6 +245,12 @@ impl TableTreeMut<'_> {
         self.tree.set_root(root);
     }
 
+    // Replaces the root, discarding the roots of tables closed since it was current
+    pub(crate) fn restore_root(&mut self, root: Option<BtreeHeader>) {
+        self.pending_table_updates.clear();
+        self.tree.set_root(root);
+    }
+
     /// Returns the current root of the table tree.
     #[cfg_attr(not(debug_assertions), expect(dead_code))]
     pub(crate) fn visit_all_pages<F>(&self, mut visitor: F) -> Result
@@ -574,6 +580,38 @@ impl TableTreeMut<'_> {
         Ok(false)
     }
 
+    // Creates a normal table from a definition whose root was built outside of a typed table,
+    // finalizing the root's checksums
+    pub(crate) fn create_table_untyped(
+        &mut self,
+        name: &str,
+        mut definition: InternalTableDefinition,
+    ) -> Result<(), TableError> {
+        if self.tree.get(&name)?.is_some() {
+            return Err(TableError::TableExists(name.to_string()));
+        }
+
+        if let InternalTableDefinition::Normal {
+            ref mut table_root,
+            fixed_key_size,
+            fixed_value_size,
+            ..
+        } = definition
+        {
+            let mut tree = UntypedBtreeMut::new(
+                *table_root,
+                self.mem.clone(),
+                self.freed_pages.clone(),
+                fixed_key_size,
+                fixed_value_size,
+            );
+            *table_root = tree.finalize_dirty_checksums()?;
+        }
+        self.tree.insert(&name, &definition)?;
+
+        Ok(())
+    }
+
     pub(crate) fn get_or_create_table<K: Key, V: Value>(
         &mut self,
         name: &str,
@@ -100,6 +100,27 @@ impl InternalTableDefinition {
         }
     }
 
+    // Definition of a normal table whose types are only known by name and width
+    pub(crate) fn new_untyped(
+        table_root: Option<BtreeHeader>,
+        table_length: u64,
+        fixed_key_size: Option<usize>,
+        fixed_value_size: Option<usize>,
+        key_type: TypeName,
+        value_type: TypeName,
+    ) -> Self {
+        InternalTableDefinition::Normal {
+            table_root,
+            table_length,
+            fixed_key_size,
+            fixed_value_size,
+            key_alignment: ALIGNMENT,
+            value_alignment: ALIGNMENT,
+            key_type,
+            value_type,
+        }
+    }
+
     pub(super) fn set_header(&mut self, root: Option<BtreeHeader>, length: u64) {
         match self {
             InternalTableDefinition::Normal {
@@ -26,11 +26,15 @@ impl TypeClassification {
     }
 
     fn from_byte(value: u8) -> Self {
+        Self::try_from_byte(value).unwrap()
+    }
+
+    fn try_from_byte(value: u8) -> Option<Self> {
         match value {
-            1 => TypeClassification::Internal,
-            2 => TypeClassification::UserDefined,
-            3 => TypeClassification::Internal2,
-            _ => unreachable!(),
+            1 => Some(TypeClassification::Internal),
+            2 => Some(TypeClassification::UserDefined),
+            3 => Some(TypeClassification::Internal2),
+            _ => None,
         }
     }
 }
@@ -82,6 +86,16 @@ impl TypeName {
         }
     }
 
+    // Like from_bytes(), but returns None instead of panicking on malformed input
+    pub(crate) fn try_from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&classification, name) = bytes.split_first()?;
+
+        Some(Self {
+            classification: TypeClassification::try_from_byte(classification)?,
+            name: std::str::from_utf8(name).ok()?.to_string(),
+        })
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
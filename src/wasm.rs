@@ -440,64 +440,23 @@ impl WasmDatabase {
     /// * `pool_size` - Number of file handles for WAL (0 = disabled, 4-8 recommended for WASM)
     #[wasm_bindgen(constructor)]
     pub async fn new(file_name: String, pool_size: usize) -> Result<WasmDatabase, JsValue> {
-        use crate::column_family::wal::checkpoint::CheckpointManager;
-        use crate::column_family::wal::config::CheckpointConfig;
-        use crate::column_family::wal::journal::WALJournal;
-
         // Create main database backend
         let backend = WasmStorageBackend::new(&file_name).await?;
         let backend_arc: Arc<dyn StorageBackend> = Arc::new(backend);
 
-        let db = if pool_size > 0 {
-            // WAL enabled - create WAL backend, journal, and checkpoint manager
+        let mut db = crate::column_family::ColumnFamilyDatabase::open_with_backend_internal(
+            file_name.clone(),
+            backend_arc,
+        )
+        .map_err(|e| JsValue::from_str(&format!("Failed to open database: {}", e)))?;
 
-            // Create WAL backend with .wal extension
+        if pool_size > 0 {
+            // WAL enabled - create WAL backend with .wal extension and start checkpointing
             let wal_file_name = format!("{}.wal", file_name);
             let wal_backend = WasmStorageBackend::new(&wal_file_name).await?;
-            let wal_backend_arc: Arc<dyn StorageBackend> = Arc::new(wal_backend);
-
-            // Create WAL journal
-            let journal = WALJournal::new(wal_backend_arc)
-                .map_err(|e| JsValue::from_str(&format!("Failed to create WAL journal: {}", e)))?;
-            let journal_arc = Arc::new(journal);
-
-            // Open database without checkpoint manager first
-            let db_temp = crate::column_family::ColumnFamilyDatabase::open_with_backend_internal(
-                file_name.clone(),
-                Arc::clone(&backend_arc),
-                Some(Arc::clone(&journal_arc)),
-                None,
-            )
-            .map_err(|e| JsValue::from_str(&format!("Failed to open database: {}", e)))?;
-
-            // Create temporary Arc for checkpoint manager initialization
-            let db_arc = Arc::new(db_temp);
-
-            // Start checkpoint manager with WASM config (15s interval, 32MB max)
-            let config =
-                CheckpointConfig::from(crate::column_family::wal::config::WALConfig::default());
-            let checkpoint_mgr =
-                CheckpointManager::start(Arc::clone(&journal_arc), Arc::clone(&db_arc), config);
-
-            // Create final database with checkpoint manager
-            // Note: We can't easily extract the database from the Arc, so we create a new one
-            crate::column_family::ColumnFamilyDatabase::open_with_backend_internal(
-                file_name,
-                backend_arc,
-                Some(journal_arc),
-                Some(Arc::new(checkpoint_mgr)),
-            )
-            .map_err(|e| JsValue::from_str(&format!("Failed to reinitialize database: {}", e)))?
-        } else {
-            // WAL disabled - simple initialization
-            crate::column_family::ColumnFamilyDatabase::open_with_backend_internal(
-                file_name,
-                backend_arc,
-                None,
-                None,
-            )
-            .map_err(|e| JsValue::from_str(&format!("Failed to open database: {}", e)))?
-        };
+            db.enable_wal(Arc::new(wal_backend))
+                .map_err(|e| JsValue::from_str(&format!("Failed to enable WAL: {}", e)))?;
+        }
 
         Ok(WasmDatabase { db })
     }
@@ -508,12 +467,20 @@ impl WasmDatabase {
         self.db.list_column_families()
     }
 
+    /// Returns whether a column family with this name exists
+    #[wasm_bindgen(js_name = hasColumnFamily)]
+    pub fn has_column_family(&self, name: String) -> bool {
+        self.db.column_family(&name).is_ok()
+    }
+
     /// Creates a new column family
+    ///
+    /// Throws an error with `code` set to `"AlreadyExists"` if the column family exists.
     #[wasm_bindgen(js_name = createColumnFamily)]
     pub fn create_column_family(&self, name: String) -> Result<(), JsValue> {
         self.db
             .create_column_family(name, None)
-            .map_err(|e| JsValue::from_str(&format!("Failed to create column family: {}", e)))?;
+            .map_err(|e| column_family_error("Failed to create column family", &e))?;
         Ok(())
     }
 
@@ -523,20 +490,32 @@ impl WasmDatabase {
         let cf = self
             .db
             .column_family_or_create(&name)
-            .map_err(|e| JsValue::from_str(&format!("Failed to get column family: {}", e)))?;
+            .map_err(|e| column_family_error("Failed to get column family", &e))?;
         Ok(WasmColumnFamily { cf })
     }
 
     /// Gets an existing column family
+    ///
+    /// Throws an error with `code` set to `"NotFound"` if the column family does not exist.
     #[wasm_bindgen(js_name = columnFamily)]
     pub fn column_family(&self, name: String) -> Result<WasmColumnFamily, JsValue> {
         let cf = self
             .db
             .column_family(&name)
-            .map_err(|e| JsValue::from_str(&format!("Column family not found: {}", e)))?;
+            .map_err(|e| column_family_error("Failed to get column family", &e))?;
         Ok(WasmColumnFamily { cf })
     }
 
+    /// Deletes a column family and frees its space for reuse
+    ///
+    /// Throws an error with `code` set to `"NotFound"` if the column family does not exist.
+    #[wasm_bindgen(js_name = deleteColumnFamily)]
+    pub fn delete_column_family(&self, name: String) -> Result<(), JsValue> {
+        self.db
+            .delete_column_family(&name)
+            .map_err(|e| column_family_error("Failed to delete column family", &e))
+    }
+
     /// Manually triggers a checkpoint to flush WAL to main database
     ///
     /// This ensures all pending WAL entries are applied to the database and persisted.
@@ -554,6 +533,68 @@ impl WasmDatabase {
     }
 }
 
+/// Converts a column family error into a JavaScript `Error`
+///
+/// The error's `code` property names the `ColumnFamilyError` variant (`"NotFound"`,
+/// `"AlreadyExists"`, `"Io"` or `"Database"`), so callers can tell a missing column family
+/// apart from a failure without parsing the message.
+fn column_family_error(context: &str, error: &crate::column_family::ColumnFamilyError) -> JsValue {
+    use crate::column_family::ColumnFamilyError;
+
+    let code = match error {
+        ColumnFamilyError::NotFound(_) => "NotFound",
+        ColumnFamilyError::AlreadyExists(_) => "AlreadyExists",
+        ColumnFamilyError::Io(_) => "Io",
+        ColumnFamilyError::Database(_) => "Database",
+    };
+    let js_error = js_sys::Error::new(&format!("{}: {}", context, error));
+    // Setting a property on a freshly created Error object cannot fail
+    let _ = js_sys::Reflect::set(
+        &js_error,
+        &JsValue::from_str("code"),
+        &JsValue::from_str(code),
+    );
+    js_error.into()
+}
+
+/// Converts the argument of writeBatch() into key-value pairs
+///
+/// The array is treated as a list of `[key, value]` pairs if its first element is an array,
+/// and as a flat interleaved list otherwise.
+fn string_pairs(
+    entries: &js_sys::Array,
+) -> Result<Box<dyn Iterator<Item = Result<(String, String), String>>>, String> {
+    use js_sys::Array;
+
+    let entries = entries.clone();
+    if Array::is_array(&entries.get(0)) {
+        let pairs = (0..entries.length()).map(move |i| {
+            let entry = entries.get(i);
+            let pair = entry
+                .dyn_into::<Array>()
+                .ok()
+                .filter(|pair| pair.length() == 2);
+            pair.and_then(|pair| Some((pair.get(0).as_string()?, pair.get(1).as_string()?)))
+                .ok_or_else(|| "expected a [string, string] pair".to_string())
+        });
+        Ok(Box::new(pairs))
+    } else {
+        if entries.length() % 2 != 0 {
+            return Err(format!(
+                "Failed to write batch: flat array has odd length {}",
+                entries.length()
+            ));
+        }
+        let pairs = (0..entries.length() / 2).map(move |i| {
+            let key = entries.get(2 * i).as_string();
+            let value = entries.get(2 * i + 1).as_string();
+            key.zip(value)
+                .ok_or_else(|| "expected a string key and value".to_string())
+        });
+        Ok(Box::new(pairs))
+    }
+}
+
 /// WASM-specific wrapper for ColumnFamily
 #[wasm_bindgen]
 pub struct WasmColumnFamily {
@@ -564,58 +605,90 @@ pub struct WasmColumnFamily {
 impl WasmColumnFamily {
     /// Writes a key-value pair atomically
     pub fn write(&self, key: String, value: String) -> Result<(), JsValue> {
-        use crate::TableDefinition;
-
-        let txn = self.cf.begin_write().map_err(|e| {
-            error(&format!("begin_write error: {}", e));
-            JsValue::from_str(&format!("Failed to begin write: {}", e))
-        })?;
-
-        {
-            let table_def: TableDefinition<String, String> = TableDefinition::new("data");
-            let mut table = txn.open_table(table_def).map_err(|e| {
-                error(&format!("open_table error: {}", e));
-                JsValue::from_str(&format!("Failed to open table: {}", e))
-            })?;
-
-            table.insert(&key, &value).map_err(|e| {
-                error(&format!(
-                    "insert error: {} (key={}, value={})",
-                    e, key, value
-                ));
-                JsValue::from_str(&format!("Failed to insert: {}", e))
-            })?;
-        }
-
-        txn.commit().map_err(|e| {
-            error(&format!("commit error: {}", e));
-            JsValue::from_str(&format!("Failed to commit: {}", e))
-        })?;
-
-        Ok(())
+        crate::wasm_tables::write_string(&self.cf, key, value).map_err(|e| {
+            error(&format!("write error: {}", e));
+            JsValue::from_str(&format!("Failed to write: {}", e))
+        })
     }
 
     /// Reads a value by key
     pub fn read(&self, key: String) -> Result<Option<String>, JsValue> {
-        use crate::{ReadableTable, TableDefinition};
+        crate::wasm_tables::read_string(&self.cf, key).map_err(|e| {
+            error(&format!("read error: {}", e));
+            JsValue::from_str(&format!("Failed to read: {}", e))
+        })
+    }
 
-        let txn = self.cf.begin_read().map_err(|e| {
-            error(&format!("begin_read error: {}", e));
-            JsValue::from_str(&format!("Failed to begin read: {}", e))
-        })?;
+    /// Writes many key-value pairs in a single transaction
+    ///
+    /// Accepts either an array of `[key, value]` pairs or a flat `[key, value, key, value, ...]`
+    /// array, and returns the number of pairs written. The batch is all-or-nothing: if any
+    /// entry is malformed or fails to write, nothing is committed and the error names the
+    /// index of the offending pair.
+    #[wasm_bindgen(js_name = writeBatch)]
+    pub fn write_batch(&self, entries: js_sys::Array) -> Result<usize, JsValue> {
+        let pairs = string_pairs(&entries).map_err(|e| JsValue::from_str(&e))?;
+        crate::wasm_tables::write_string_batch(&self.cf, pairs).map_err(|e| {
+            error(&format!("writeBatch error: {}", e));
+            JsValue::from_str(&format!("Failed to write batch: {}", e))
+        })
+    }
 
-        let table_def: TableDefinition<String, String> = TableDefinition::new("data");
-        let table = txn.open_table(table_def).map_err(|e| {
-            error(&format!("open_table error: {}", e));
-            JsValue::from_str(&format!("Failed to open table: {}", e))
-        })?;
+    /// Deletes many keys in a single transaction
+    ///
+    /// Returns the number of keys that were present. Like writeBatch(), the batch is
+    /// all-or-nothing and errors name the index of the offending key.
+    #[wasm_bindgen(js_name = deleteBatch)]
+    pub fn delete_batch(&self, keys: js_sys::Array) -> Result<usize, JsValue> {
+        let keys = keys.iter().map(|key| {
+            key.as_string()
+                .ok_or_else(|| "expected a string key".to_string())
+        });
+        crate::wasm_tables::delete_string_batch(&self.cf, keys).map_err(|e| {
+            error(&format!("deleteBatch error: {}", e));
+            JsValue::from_str(&format!("Failed to delete batch: {}", e))
+        })
+    }
 
-        let value = table.get(&key).map_err(|e| {
-            error(&format!("get error: {} (key={})", e, key));
-            JsValue::from_str(&format!("Failed to get: {}", e))
-        })?;
+    /// Writes a binary key-value pair atomically
+    ///
+    /// Binary entries are stored in their own table, separate from the string entries
+    /// written by write().
+    #[wasm_bindgen(js_name = writeBytes)]
+    pub fn write_bytes(&self, key: &[u8], value: &[u8]) -> Result<(), JsValue> {
+        crate::wasm_tables::write_bytes(&self.cf, key, value).map_err(|e| {
+            error(&format!("writeBytes error: {}", e));
+            JsValue::from_str(&format!("Failed to write: {}", e))
+        })
+    }
+
+    /// Reads a binary value by key
+    #[wasm_bindgen(js_name = readBytes)]
+    pub fn read_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, JsValue> {
+        crate::wasm_tables::read_bytes(&self.cf, key).map_err(|e| {
+            error(&format!("readBytes error: {}", e));
+            JsValue::from_str(&format!("Failed to read: {}", e))
+        })
+    }
+
+    /// Writes a binary value under a numeric key atomically
+    ///
+    /// Keys are passed as BigInt. Numeric entries are stored in their own table.
+    #[wasm_bindgen(js_name = writeU64)]
+    pub fn write_u64(&self, key: u64, value: &[u8]) -> Result<(), JsValue> {
+        crate::wasm_tables::write_u64(&self.cf, key, value).map_err(|e| {
+            error(&format!("writeU64 error: {}", e));
+            JsValue::from_str(&format!("Failed to write: {}", e))
+        })
+    }
 
-        Ok(value.map(|v| v.value().clone()))
+    /// Reads a binary value by numeric key
+    #[wasm_bindgen(js_name = readU64)]
+    pub fn read_u64(&self, key: u64) -> Result<Option<Vec<u8>>, JsValue> {
+        crate::wasm_tables::read_u64(&self.cf, key).map_err(|e| {
+            error(&format!("readU64 error: {}", e));
+            JsValue::from_str(&format!("Failed to read: {}", e))
+        })
     }
 
     /// Creates an iterator over all entries in the table
@@ -636,6 +709,44 @@ impl WasmColumnFamily {
     ) -> Result<WasmIterator, JsValue> {
         WasmIterator::new(&self.cf, start_key, end_key)
     }
+
+    /// Creates an iterator over the entries whose keys start with `prefix`
+    ///
+    /// The end of the range is computed from the prefix, so entries come in key order and
+    /// iteration stops at the first key past the prefix. An empty prefix iterates over all
+    /// entries.
+    #[wasm_bindgen(js_name = iterPrefix)]
+    pub fn iter_prefix(&self, prefix: String) -> Result<WasmIterator, JsValue> {
+        let end_key = crate::wasm_tables::string_prefix_end(&prefix);
+        WasmIterator::new(&self.cf, Some(prefix), end_key)
+    }
+
+    /// Creates an iterator over all binary entries, in byte order of their keys
+    #[wasm_bindgen(js_name = iterBytes)]
+    pub fn iter_bytes(&self) -> Result<WasmBytesIterator, JsValue> {
+        WasmBytesIterator::new(&self.cf, None, None)
+    }
+
+    /// Creates an iterator over a range of binary entries
+    ///
+    /// If start_key is provided, iteration begins at that key (inclusive)
+    /// If end_key is provided, iteration ends at that key (exclusive)
+    #[wasm_bindgen(js_name = iterRangeBytes)]
+    pub fn iter_range_bytes(
+        &self,
+        start_key: Option<Vec<u8>>,
+        end_key: Option<Vec<u8>>,
+    ) -> Result<WasmBytesIterator, JsValue> {
+        WasmBytesIterator::new(&self.cf, start_key.as_deref(), end_key.as_deref())
+    }
+}
+
+impl WasmColumnFamily {
+    /// Returns the wrapped column family, so the WASM bindings of the domain crates, such as
+    /// `manifold-timeseries`, can open their tables in it
+    pub fn column_family(&self) -> &crate::column_family::ColumnFamily {
+        &self.cf
+    }
 }
 
 /// High-performance batch iterator for WASM
@@ -656,40 +767,19 @@ impl WasmIterator {
         start_key: Option<String>,
         end_key: Option<String>,
     ) -> Result<WasmIterator, JsValue> {
-        use crate::{ReadableTable, TableDefinition};
-        use std::ops::Bound;
-
         let txn = cf.begin_read().map_err(|e| {
             error(&format!("begin_read error: {}", e));
             JsValue::from_str(&format!("Failed to begin read: {}", e))
         })?;
 
-        let table_def: TableDefinition<String, String> = TableDefinition::new("data");
-        let table = txn.open_table(table_def).map_err(|e| {
-            error(&format!("open_table error: {}", e));
-            JsValue::from_str(&format!("Failed to open table: {}", e))
-        })?;
+        let table = txn
+            .open_table(crate::wasm_tables::STRING_TABLE)
+            .map_err(|e| {
+                error(&format!("open_table error: {}", e));
+                JsValue::from_str(&format!("Failed to open table: {}", e))
+            })?;
 
-        // Build range bounds based on start_key and end_key
-        let range = match (start_key, end_key) {
-            (None, None) => {
-                // Full range
-                table.range::<String>(..)
-            }
-            (Some(start), None) => {
-                // Start to end
-                table.range::<String>((Bound::Included(start), Bound::Unbounded))
-            }
-            (None, Some(end)) => {
-                // Beginning to end
-                table.range::<String>((Bound::Unbounded, Bound::Excluded(end)))
-            }
-            (Some(start), Some(end)) => {
-                // Start to end (inclusive start, exclusive end)
-                table.range::<String>((Bound::Included(start), Bound::Excluded(end)))
-            }
-        }
-        .map_err(|e| {
+        let range = crate::wasm_tables::string_range(&table, start_key, end_key).map_err(|e| {
             error(&format!("range error: {}", e));
             JsValue::from_str(&format!("Failed to create range: {}", e))
         })?;
@@ -714,31 +804,56 @@ impl WasmIterator {
 
         let batch = Array::new();
 
-        if let Some(range) = &mut self.range {
-            for _ in 0..batch_size {
-                match range.next() {
-                    Some(Ok((key_guard, value_guard))) => {
-                        let key = key_guard.value().clone();
-                        let value = value_guard.value().clone();
+        self.for_each_next(batch_size, |key, value| {
+            let pair = Array::new();
+            pair.push(&JsValue::from_str(&key));
+            pair.push(&JsValue::from_str(&value));
+            batch.push(&pair);
+        });
 
-                        let pair = Array::new();
-                        pair.push(&JsValue::from_str(&key));
-                        pair.push(&JsValue::from_str(&value));
-                        batch.push(&pair);
-                    }
-                    Some(Err(e)) => {
-                        error(&format!("iterator error: {}", e));
-                        break;
-                    }
-                    None => {
-                        self.range = None;
-                        break;
-                    }
+        batch.into()
+    }
+
+    /// Returns the next batch of entries (up to `batch_size`) as `{key, value}` objects
+    ///
+    /// Like next_batch(), but each entry is a plain object that can be destructured
+    /// directly, without mapping the pairs first. Empty array indicates end of iteration.
+    #[wasm_bindgen(js_name = nextBatchObjects)]
+    pub fn next_batch_objects(&mut self, batch_size: usize) -> JsValue {
+        use js_sys::{Array, Object, Reflect};
+
+        let batch = Array::new();
+        let key_name = JsValue::from_str("key");
+        let value_name = JsValue::from_str("value");
+
+        self.for_each_next(batch_size, |key, value| {
+            let entry = Object::new();
+            // Setting a property on a fresh plain object cannot fail
+            let _ = Reflect::set(&entry, &key_name, &JsValue::from_str(&key));
+            let _ = Reflect::set(&entry, &value_name, &JsValue::from_str(&value));
+            batch.push(&entry);
+        });
+
+        batch.into()
+    }
+
+    /// Drains the iterator and returns the number of entries it had left
+    ///
+    /// Entries are counted without being converted to JS values, so this is much cheaper
+    /// than collecting them, for diagnostics such as the size of a prefix.
+    pub fn count(&mut self) -> usize {
+        let mut count = 0;
+        if let Some(range) = &mut self.range {
+            for entry in range.by_ref() {
+                if let Err(e) = entry {
+                    error(&format!("iterator error: {}", e));
+                    return count;
                 }
+                count += 1;
             }
         }
-
-        batch.into()
+        self.range = None;
+        count
     }
 
     /// Returns the next single entry
@@ -796,9 +911,156 @@ impl WasmIterator {
     }
 }
 
+impl WasmIterator {
+    /// Passes up to `batch_size` entries to `push`, in key order
+    ///
+    /// The range is released once it is exhausted. A read error is logged and ends the
+    /// batch early.
+    fn for_each_next(&mut self, batch_size: usize, mut push: impl FnMut(String, String)) {
+        if let Some(range) = &mut self.range {
+            for _ in 0..batch_size {
+                match range.next() {
+                    Some(Ok((key_guard, value_guard))) => {
+                        push(key_guard.value(), value_guard.value());
+                    }
+                    Some(Err(e)) => {
+                        error(&format!("iterator error: {}", e));
+                        break;
+                    }
+                    None => {
+                        self.range = None;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Batch iterator over binary entries, returning [Uint8Array, Uint8Array] pairs
+///
+/// Works like WasmIterator, for the table written by writeBytes().
+#[wasm_bindgen]
+pub struct WasmBytesIterator {
+    txn: crate::ReadTransaction,
+    table: crate::table::ReadOnlyTable<&'static [u8], &'static [u8]>,
+    range: Option<crate::table::Range<'static, &'static [u8], &'static [u8]>>,
+}
+
+#[wasm_bindgen]
+impl WasmBytesIterator {
+    fn new(
+        cf: &crate::column_family::ColumnFamily,
+        start_key: Option<&[u8]>,
+        end_key: Option<&[u8]>,
+    ) -> Result<WasmBytesIterator, JsValue> {
+        let txn = cf.begin_read().map_err(|e| {
+            error(&format!("begin_read error: {}", e));
+            JsValue::from_str(&format!("Failed to begin read: {}", e))
+        })?;
+
+        let table = txn
+            .open_table(crate::wasm_tables::BYTES_TABLE)
+            .map_err(|e| {
+                error(&format!("open_table error: {}", e));
+                JsValue::from_str(&format!("Failed to open table: {}", e))
+            })?;
+
+        let range = crate::wasm_tables::bytes_range(&table, start_key, end_key).map_err(|e| {
+            error(&format!("range error: {}", e));
+            JsValue::from_str(&format!("Failed to create range: {}", e))
+        })?;
+
+        Ok(WasmBytesIterator {
+            txn,
+            table,
+            range: Some(range),
+        })
+    }
+
+    /// Returns the next batch of entries (up to `batch_size`)
+    ///
+    /// Returns an array of [key, value] pairs of Uint8Arrays.
+    /// Empty array indicates end of iteration.
+    #[wasm_bindgen(js_name = nextBatch)]
+    pub fn next_batch(&mut self, batch_size: usize) -> JsValue {
+        use js_sys::{Array, Uint8Array};
+
+        let batch = Array::new();
+
+        if let Some(range) = &mut self.range {
+            for _ in 0..batch_size {
+                match range.next() {
+                    Some(Ok((key_guard, value_guard))) => {
+                        let pair = Array::new();
+                        pair.push(&Uint8Array::from(key_guard.value()));
+                        pair.push(&Uint8Array::from(value_guard.value()));
+                        batch.push(&pair);
+                    }
+                    Some(Err(e)) => {
+                        error(&format!("iterator error: {}", e));
+                        break;
+                    }
+                    None => {
+                        self.range = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        batch.into()
+    }
+
+    /// Returns the next single entry
+    ///
+    /// Returns [key, value] array or undefined if done.
+    pub fn next(&mut self) -> JsValue {
+        use js_sys::Array;
+
+        let batch = Array::from(&self.next_batch(1));
+
+        if batch.length() > 0 {
+            batch.get(0)
+        } else {
+            JsValue::UNDEFINED
+        }
+    }
+
+    /// Collects all remaining entries into an array
+    #[wasm_bindgen(js_name = collectAll)]
+    pub fn collect_all(&mut self) -> JsValue {
+        use js_sys::Array;
+
+        let all = Array::new();
+
+        loop {
+            let batch = Array::from(&self.next_batch(100));
+
+            if batch.length() == 0 {
+                break;
+            }
+
+            for i in 0..batch.length() {
+                all.push(&batch.get(i));
+            }
+        }
+
+        all.into()
+    }
+
+    /// Explicitly closes the iterator and releases the transaction
+    pub fn close(self) {
+        // Drop self, cleaning up transaction
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::column_family::ColumnFamilyDatabase;
+    use js_sys::{Array, Reflect};
+    use wasm_bindgen_test::wasm_bindgen_test;
 
     // Note: These tests require wasm-bindgen-test to run in a browser environment
     // Run with: wasm-pack test --headless --chrome
@@ -810,4 +1072,95 @@ mod tests {
         let _supported = is_opfs_supported();
         // We can't assert true/false here since it depends on the environment
     }
+
+    /// Opens an in-memory column family holding `keys`, each with a value derived from it
+    fn in_memory_cf(keys: &[&str]) -> (ColumnFamilyDatabase, WasmColumnFamily) {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = WasmColumnFamily {
+            cf: db.column_family_or_create("js").unwrap(),
+        };
+        for key in keys {
+            cf.write(key.to_string(), format!("value of {key}"))
+                .unwrap();
+        }
+        (db, cf)
+    }
+
+    /// Drains an iterator with nextBatchObjects(), returning the keys of the entries
+    fn object_keys(iter: &mut WasmIterator, batch_size: usize) -> Vec<String> {
+        let mut keys = Vec::new();
+        loop {
+            let batch = Array::from(&iter.next_batch_objects(batch_size));
+            if batch.length() == 0 {
+                return keys;
+            }
+            assert!(batch.length() as usize <= batch_size);
+            for entry in batch.iter() {
+                let key = Reflect::get(&entry, &"key".into())
+                    .unwrap()
+                    .as_string()
+                    .unwrap();
+                let value = Reflect::get(&entry, &"value".into()).unwrap();
+                assert_eq!(value.as_string().unwrap(), format!("value of {key}"));
+                keys.push(key);
+            }
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_iter_prefix_boundaries() {
+        let (_db, cf) = in_memory_cf(&["", "a", "a/", "a/b", "a0", "ab", "b", "b/c"]);
+
+        let cases: [(&str, &[&str]); 5] = [
+            ("a", &["a", "a/", "a/b", "a0", "ab"]),
+            ("a/", &["a/", "a/b"]),
+            ("a0", &["a0"]),
+            ("b/", &["b/c"]),
+            ("c", &[]),
+        ];
+        for (prefix, expected) in cases {
+            for batch_size in [1, 1000] {
+                let mut iter = cf.iter_prefix(prefix.to_string()).unwrap();
+                assert_eq!(object_keys(&mut iter, batch_size), expected, "{prefix:?}");
+            }
+            let mut iter = cf.iter_prefix(prefix.to_string()).unwrap();
+            assert_eq!(iter.count(), expected.len());
+        }
+
+        let mut iter = cf.iter_prefix(String::new()).unwrap();
+        assert_eq!(iter.count(), 8);
+        assert_eq!(iter.count(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_next_batch_objects_matches_next_batch() {
+        let keys: Vec<String> = (0..2500).map(|i| format!("key{i:04}")).collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let (_db, cf) = in_memory_cf(&keys);
+
+        for batch_size in [1, 1000] {
+            let mut iter = cf.iter().unwrap();
+            assert_eq!(object_keys(&mut iter, batch_size), keys);
+
+            let mut iter = cf.iter().unwrap();
+            let mut pairs = 0;
+            loop {
+                let batch = Array::from(&iter.next_batch(batch_size));
+                if batch.length() == 0 {
+                    break;
+                }
+                for pair in batch.iter() {
+                    let key = Array::from(&pair).get(0).as_string().unwrap();
+                    assert_eq!(key, keys[pairs]);
+                    pairs += 1;
+                }
+            }
+            assert_eq!(pairs, keys.len());
+        }
+
+        // Counting part way through counts what is left
+        let mut iter = cf.iter().unwrap();
+        assert_eq!(Array::from(&iter.next_batch_objects(1000)).length(), 1000);
+        assert_eq!(iter.count(), 1500);
+    }
 }
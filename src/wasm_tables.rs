@@ -0,0 +1,410 @@
+//! Tables behind the key/value methods of [`WasmColumnFamily`](crate::wasm::WasmColumnFamily).
+//!
+//! Each key type has its own table in the column family, so string, binary and numeric
+//! entries never collide. These functions are plain Rust and build on every target, which
+//! lets native tests cover the same code the WASM bindings call.
+
+use crate::column_family::ColumnFamily;
+use crate::{Error, Range, ReadOnlyTable, StorageError, TableDefinition};
+use std::fmt;
+use std::ops::Bound;
+
+/// String keys and values, used by `write`/`read`.
+pub(crate) const STRING_TABLE: TableDefinition<String, String> = TableDefinition::new("data");
+
+/// Binary keys and values, used by `writeBytes`/`readBytes`.
+pub(crate) const BYTES_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("data_bytes");
+
+/// Numeric keys with binary values, used by `writeU64`/`readU64`.
+pub(crate) const U64_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("data_u64");
+
+pub(crate) fn write_string(cf: &ColumnFamily, key: String, value: String) -> Result<(), Error> {
+    let txn = cf.begin_write()?;
+    txn.open_table(STRING_TABLE)?.insert(&key, &value)?;
+    txn.commit()?;
+    Ok(())
+}
+
+pub(crate) fn read_string(cf: &ColumnFamily, key: String) -> Result<Option<String>, Error> {
+    let txn = cf.begin_read()?;
+    let table = txn.open_table(STRING_TABLE)?;
+    Ok(table.get(&key)?.map(|guard| guard.value()))
+}
+
+/// Error from a batch operation. Nothing in the batch was written.
+#[derive(Debug)]
+pub(crate) enum BatchError {
+    /// Entry `index` of the batch was malformed or could not be written.
+    Entry { index: usize, message: String },
+    /// The transaction could not be started or committed.
+    Transaction(Error),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Entry { index, message } => write!(f, "entry {index}: {message}"),
+            Self::Transaction(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl<E: Into<Error>> From<E> for BatchError {
+    fn from(err: E) -> Self {
+        Self::Transaction(err.into())
+    }
+}
+
+/// Writes all `entries` in one transaction and returns how many were written.
+///
+/// Entries are `Err` with a description if they could not be converted from their JS
+/// representation. The first malformed or failing entry aborts the transaction.
+pub(crate) fn write_string_batch(
+    cf: &ColumnFamily,
+    entries: impl IntoIterator<Item = Result<(String, String), String>>,
+) -> Result<usize, BatchError> {
+    let txn = cf.begin_write()?;
+    let mut count = 0;
+    {
+        let mut table = txn.open_table(STRING_TABLE)?;
+        for (index, entry) in entries.into_iter().enumerate() {
+            let (key, value) = entry.map_err(|message| BatchError::Entry { index, message })?;
+            table
+                .insert(&key, &value)
+                .map_err(|err| BatchError::Entry {
+                    index,
+                    message: err.to_string(),
+                })?;
+            count += 1;
+        }
+    }
+    txn.commit()?;
+    Ok(count)
+}
+
+/// Removes all `keys` in one transaction and returns how many were present.
+///
+/// Like [`write_string_batch`], the first malformed key aborts the transaction.
+pub(crate) fn delete_string_batch(
+    cf: &ColumnFamily,
+    keys: impl IntoIterator<Item = Result<String, String>>,
+) -> Result<usize, BatchError> {
+    let txn = cf.begin_write()?;
+    let mut count = 0;
+    {
+        let mut table = txn.open_table(STRING_TABLE)?;
+        for (index, key) in keys.into_iter().enumerate() {
+            let key = key.map_err(|message| BatchError::Entry { index, message })?;
+            let removed = table.remove(&key).map_err(|err| BatchError::Entry {
+                index,
+                message: err.to_string(),
+            })?;
+            if removed.is_some() {
+                count += 1;
+            }
+        }
+    }
+    txn.commit()?;
+    Ok(count)
+}
+
+pub(crate) fn write_bytes(cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    let txn = cf.begin_write()?;
+    txn.open_table(BYTES_TABLE)?.insert(key, value)?;
+    txn.commit()?;
+    Ok(())
+}
+
+pub(crate) fn read_bytes(cf: &ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let txn = cf.begin_read()?;
+    let table = txn.open_table(BYTES_TABLE)?;
+    Ok(table.get(key)?.map(|guard| guard.value().to_vec()))
+}
+
+pub(crate) fn write_u64(cf: &ColumnFamily, key: u64, value: &[u8]) -> Result<(), Error> {
+    let txn = cf.begin_write()?;
+    txn.open_table(U64_TABLE)?.insert(key, value)?;
+    txn.commit()?;
+    Ok(())
+}
+
+pub(crate) fn read_u64(cf: &ColumnFamily, key: u64) -> Result<Option<Vec<u8>>, Error> {
+    let txn = cf.begin_read()?;
+    let table = txn.open_table(U64_TABLE)?;
+    Ok(table.get(key)?.map(|guard| guard.value().to_vec()))
+}
+
+/// Returns the entries of the bytes table from `start` (inclusive) to `end` (exclusive).
+///
+/// A missing bound leaves that side of the range open.
+pub(crate) fn bytes_range(
+    table: &ReadOnlyTable<&'static [u8], &'static [u8]>,
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+) -> Result<Range<'static, &'static [u8], &'static [u8]>, StorageError> {
+    let start = start.map_or(Bound::Unbounded, Bound::Included);
+    let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+    table.range::<&[u8]>((start, end))
+}
+
+/// Returns the entries of the string table from `start` (inclusive) to `end` (exclusive).
+///
+/// A missing bound leaves that side of the range open.
+pub(crate) fn string_range(
+    table: &ReadOnlyTable<String, String>,
+    start: Option<String>,
+    end: Option<String>,
+) -> Result<Range<'static, String, String>, StorageError> {
+    let start = start.map_or(Bound::Unbounded, Bound::Included);
+    let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+    table.range::<String>((start, end))
+}
+
+/// Returns the smallest string greater than every string starting with `prefix`, to use as
+/// the exclusive end of a prefix scan.
+///
+/// Strings compare by their UTF-8 bytes, which is the order of their chars, so this is the
+/// prefix with its last char incremented. Trailing chars that cannot be incremented are
+/// dropped first, and `None` means no string is greater, so the scan runs to the end.
+pub(crate) fn string_prefix_end(prefix: &str) -> Option<String> {
+    let mut end = prefix.to_string();
+    while let Some(last) = end.pop() {
+        let next = match last {
+            // Surrogates are not chars
+            '\u{D7FF}' => Some('\u{E000}'),
+            _ => char::from_u32(u32::from(last) + 1),
+        };
+        if let Some(next) = next {
+            end.push(next);
+            return Some(end);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_family::ColumnFamilyDatabase;
+
+    fn range_keys(cf: &ColumnFamily, start: Option<&[u8]>, end: Option<&[u8]>) -> Vec<Vec<u8>> {
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(BYTES_TABLE).unwrap();
+        bytes_range(&table, start, end)
+            .unwrap()
+            .map(|entry| entry.unwrap().0.value().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("js").unwrap();
+
+        let entries: [(&[u8], &[u8]); 4] = [
+            (b"", b"empty key"),
+            (b"\0", b"\0\0\0"),
+            (b"\xff\xfe\x00invalid utf8", b"\xc3\x28\x00\xa0\xa1"),
+            (b"key", b""),
+        ];
+        for (key, value) in entries {
+            write_bytes(&cf, key, value).unwrap();
+        }
+        for (key, value) in entries {
+            assert_eq!(read_bytes(&cf, key).unwrap().as_deref(), Some(value));
+        }
+        assert_eq!(read_bytes(&cf, b"\0\0").unwrap(), None);
+
+        // Keys iterate in byte order
+        assert_eq!(range_keys(&cf, None, None).len(), 4);
+        assert_eq!(
+            range_keys(&cf, Some(b"\0"), None),
+            vec![
+                b"\0".to_vec(),
+                b"key".to_vec(),
+                b"\xff\xfe\x00invalid utf8".to_vec()
+            ]
+        );
+        assert_eq!(
+            range_keys(&cf, None, Some(b"key")),
+            vec![b"".to_vec(), b"\0".to_vec()]
+        );
+        assert_eq!(
+            range_keys(&cf, Some(b"\0"), Some(b"\xff")),
+            vec![b"\0".to_vec(), b"key".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_string_prefix_range() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("js").unwrap();
+        let keys = [
+            "",
+            "a",
+            "a/",
+            "a/b",
+            "a0",
+            "ab",
+            "a\u{10FFFF}",
+            "b",
+            "\u{D7FF}x",
+            "\u{E000}",
+        ];
+        for key in keys {
+            write_string(&cf, key.to_string(), String::new()).unwrap();
+        }
+
+        let prefix_keys = |prefix: &str| -> Vec<String> {
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(STRING_TABLE).unwrap();
+            string_range(&table, Some(prefix.to_string()), string_prefix_end(prefix))
+                .unwrap()
+                .map(|entry| entry.unwrap().0.value())
+                .collect()
+        };
+        for prefix in [
+            "",
+            "a",
+            "a/",
+            "a0",
+            "ab",
+            "a\u{10FFFF}",
+            "b",
+            "c",
+            "\u{D7FF}",
+        ] {
+            let expected: Vec<&str> = keys
+                .iter()
+                .copied()
+                .filter(|key| key.starts_with(prefix))
+                .collect();
+            assert_eq!(prefix_keys(prefix), expected, "prefix {prefix:?}");
+        }
+
+        assert_eq!(string_prefix_end("a").as_deref(), Some("b"));
+        assert_eq!(string_prefix_end("a/").as_deref(), Some("a0"));
+        assert_eq!(string_prefix_end("a\u{10FFFF}").as_deref(), Some("b"));
+        assert_eq!(string_prefix_end("\u{D7FF}").as_deref(), Some("\u{E000}"));
+        assert_eq!(string_prefix_end(""), None);
+        assert_eq!(string_prefix_end("\u{10FFFF}\u{10FFFF}"), None);
+    }
+
+    #[test]
+    fn test_string_batch() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("js").unwrap();
+        write_string(&cf, "existing".to_string(), "kept".to_string()).unwrap();
+
+        let entries = (0..10_000).map(|i| Ok((format!("key{i:05}"), format!("value{i}"))));
+        let start = std::time::Instant::now();
+        assert_eq!(write_string_batch(&cf, entries).unwrap(), 10_000);
+        let elapsed = start.elapsed();
+        assert!(elapsed.as_secs() < 10, "batch write took {elapsed:?}");
+
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(STRING_TABLE).unwrap();
+        let keys: Vec<String> = table
+            .range::<String>((
+                Bound::Included("key".to_string()),
+                Bound::Excluded("kez".to_string()),
+            ))
+            .unwrap()
+            .map(|entry| entry.unwrap().0.value())
+            .collect();
+        assert_eq!(keys.len(), 10_000);
+        assert_eq!(keys[1234], "key01234");
+        assert_eq!(
+            read_string(&cf, "key09999".to_string()).unwrap().as_deref(),
+            Some("value9999")
+        );
+        drop(table);
+        drop(txn);
+
+        let keys = ["key00000", "missing", "key09999", "existing"];
+        let deleted = delete_string_batch(&cf, keys.map(|key| Ok(key.to_string()))).unwrap();
+        assert_eq!(deleted, 3);
+        assert_eq!(read_string(&cf, "key00000".to_string()).unwrap(), None);
+        assert_eq!(read_string(&cf, "existing".to_string()).unwrap(), None);
+        assert!(read_string(&cf, "key00001".to_string()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_string_batch_is_all_or_nothing() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("js").unwrap();
+        write_string(&cf, "existing".to_string(), "kept".to_string()).unwrap();
+
+        let entries = (0..100).map(|i| {
+            if i == 57 {
+                Err("expected a [string, string] pair".to_string())
+            } else {
+                Ok((format!("key{i}"), "value".to_string()))
+            }
+        });
+        match write_string_batch(&cf, entries) {
+            Err(err @ BatchError::Entry { index: 57, .. }) => {
+                assert_eq!(
+                    err.to_string(),
+                    "entry 57: expected a [string, string] pair"
+                );
+            }
+            other => panic!("expected an error for entry 57, got {other:?}"),
+        }
+        assert_eq!(read_string(&cf, "key0".to_string()).unwrap(), None);
+
+        write_string(&cf, "key0".to_string(), "value".to_string()).unwrap();
+        let keys = [Ok("key0".to_string()), Err("not a string".to_string())];
+        assert!(matches!(
+            delete_string_batch(&cf, keys),
+            Err(BatchError::Entry { index: 1, .. })
+        ));
+        assert!(read_string(&cf, "key0".to_string()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_u64_round_trip() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("js").unwrap();
+
+        for key in [0, 1, u64::from(u32::MAX) + 1, u64::MAX] {
+            write_u64(&cf, key, &key.to_le_bytes()).unwrap();
+        }
+        write_u64(&cf, 1, b"\0\xff").unwrap();
+
+        assert_eq!(read_u64(&cf, 0).unwrap(), Some(vec![0; 8]));
+        assert_eq!(read_u64(&cf, 1).unwrap(), Some(b"\0\xff".to_vec()));
+        assert_eq!(
+            read_u64(&cf, u64::MAX).unwrap(),
+            Some(u64::MAX.to_le_bytes().to_vec())
+        );
+        assert_eq!(read_u64(&cf, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_key_types_use_separate_tables() {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        let cf = db.column_family_or_create("js").unwrap();
+
+        write_string(&cf, "1".to_string(), "string".to_string()).unwrap();
+        write_bytes(&cf, b"1", b"bytes").unwrap();
+        write_u64(&cf, 1, b"u64").unwrap();
+
+        assert_eq!(
+            read_string(&cf, "1".to_string()).unwrap().as_deref(),
+            Some("string")
+        );
+        assert_eq!(read_bytes(&cf, b"1").unwrap(), Some(b"bytes".to_vec()));
+        assert_eq!(read_u64(&cf, 1).unwrap(), Some(b"u64".to_vec()));
+
+        // The string table is the one earlier versions wrote to
+        let txn = cf.begin_read().unwrap();
+        let names: Vec<String> = txn
+            .list_tables()
+            .unwrap()
+            .map(|handle| crate::TableHandle::name(&handle).to_string())
+            .collect();
+        assert!(names.contains(&"data".to_string()));
+        assert_eq!(names.len(), 3);
+    }
+}
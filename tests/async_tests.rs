@@ -0,0 +1,105 @@
+//! Tests of async commits and checkpoints, which need the `async` feature.
+
+#![cfg(feature = "async")]
+
+use manifold::column_family::{ColumnFamilyDatabase, WALConfig};
+use manifold::{Durability, ReadableTableMetadata, TableDefinition};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+/// Opens a database whose WAL is only checkpointed on request, so that committed data is
+/// durable through the WAL alone.
+fn open(path: &Path) -> ColumnFamilyDatabase {
+    ColumnFamilyDatabase::builder()
+        .wal_config(WALConfig {
+            checkpoint_interval: Duration::from_secs(3600),
+            ..WALConfig::default()
+        })
+        .open(path)
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_concurrent_async_commits_are_durable() {
+    let dir = TempDir::new().unwrap();
+    let db_path = dir.path().join("async.db");
+    let db = open(&db_path);
+    let cf = db.create_column_family("async_cf", Some(1 << 20)).unwrap();
+
+    let tasks: Vec<_> = (0..100u64)
+        .map(|i| {
+            let cf = cf.clone();
+            tokio::spawn(async move {
+                let txn = cf.begin_write_async().await.unwrap();
+                txn.open_table(TABLE).unwrap().insert(i, i * 10).unwrap();
+                txn.commit().await.unwrap();
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let stats = db.wal_stats().unwrap().unwrap();
+    assert!(stats.pending_entries > 0);
+
+    // Every resolved commit is in the synced WAL: a copy of the files as they are now
+    // recovers all of them, as it would after a crash
+    let copy_path = dir.path().join("copy.db");
+    std::fs::copy(&db_path, &copy_path).unwrap();
+    std::fs::copy(
+        db_path.with_extension("wal"),
+        copy_path.with_extension("wal"),
+    )
+    .unwrap();
+
+    let copy = open(&copy_path);
+    let txn = copy
+        .column_family("async_cf")
+        .unwrap()
+        .begin_read()
+        .unwrap();
+    let table = txn.open_table(TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 100);
+    for i in 0..100u64 {
+        assert_eq!(table.get(i).unwrap().unwrap().value(), i * 10);
+    }
+}
+
+#[tokio::test]
+async fn test_async_commit_on_current_thread_runtime() {
+    let dir = TempDir::new().unwrap();
+    let db = open(&dir.path().join("async.db"));
+    let cf = db.create_column_family("async_cf", None).unwrap();
+
+    let txn = cf.begin_write_async().await.unwrap();
+    txn.open_table(TABLE).unwrap().insert(1, 1).unwrap();
+    txn.commit().await.unwrap();
+
+    let mut txn = cf.begin_write_async().await.unwrap();
+    txn.set_durability(Durability::Eventual).unwrap();
+    txn.open_table(TABLE).unwrap().insert(2, 2).unwrap();
+    txn.commit().await.unwrap();
+
+    let txn = cf.begin_write_async().await.unwrap();
+    txn.open_table(TABLE).unwrap().insert(3, 3).unwrap();
+    txn.abort().unwrap();
+
+    let report = db.checkpoint_async().await.unwrap();
+    assert_eq!(report.entries_applied(), 2);
+    assert_eq!(db.wal_stats().unwrap().unwrap().pending_entries, 0);
+
+    // Transactions that bypass the WAL commit too
+    let mut txn = cf.begin_write_async().await.unwrap();
+    txn.disable_wal();
+    txn.open_table(TABLE).unwrap().insert(4, 4).unwrap();
+    txn.commit().await.unwrap();
+
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 3);
+    assert!(table.get(3).unwrap().is_none());
+}
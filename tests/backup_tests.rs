@@ -0,0 +1,182 @@
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold::{ReadableTable, ReadableTableMetadata, TableDefinition};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use tempfile::TempDir;
+
+const DATA_TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+fn insert_range(db: &ColumnFamilyDatabase, cf_name: &str, range: std::ops::Range<u64>) {
+    let cf = db.column_family_or_create(cf_name).unwrap();
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(DATA_TABLE).unwrap();
+        for i in range {
+            table.insert(i, i * 10).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+}
+
+fn read_all(db: &ColumnFamilyDatabase, cf_name: &str) -> Vec<(u64, u64)> {
+    let cf = db.column_family(cf_name).unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(DATA_TABLE).unwrap();
+    table
+        .iter()
+        .unwrap()
+        .map(|entry| {
+            let (key, value) = entry.unwrap();
+            (key.value(), value.value())
+        })
+        .collect()
+}
+
+#[test]
+fn test_backup_contents_match_source() {
+    let dir = TempDir::new().unwrap();
+    let backup_path = dir.path().join("backup.manifold");
+    let db = ColumnFamilyDatabase::open(dir.path().join("source.manifold")).unwrap();
+
+    insert_range(&db, "users", 0..500);
+    insert_range(&db, "events", 0..2000);
+    insert_range(&db, "scratch", 0..10);
+    db.delete_column_family("scratch").unwrap();
+
+    let summary = db.backup_to(&backup_path).unwrap();
+    let mut copied = summary.column_families.clone();
+    copied.sort();
+    assert_eq!(copied, vec!["events".to_string(), "users".to_string()]);
+    assert_eq!(
+        summary.bytes_written,
+        std::fs::metadata(&backup_path).unwrap().len()
+    );
+
+    // Deleted column families and unused partition space are not copied
+    assert!(summary.bytes_written < 1024 * 1024);
+
+    let backup = ColumnFamilyDatabase::open(&backup_path).unwrap();
+    let mut families = backup.list_column_families();
+    families.sort();
+    assert_eq!(families, copied);
+    for name in ["users", "events"] {
+        assert_eq!(read_all(&backup, name), read_all(&db, name));
+    }
+
+    // The backup is a normal database that accepts new writes
+    insert_range(&backup, "users", 500..600);
+    insert_range(&backup, "archive", 0..10);
+    drop(backup);
+
+    let backup = ColumnFamilyDatabase::open(&backup_path).unwrap();
+    assert_eq!(read_all(&backup, "users").len(), 600);
+    assert_eq!(read_all(&backup, "archive").len(), 10);
+}
+
+#[test]
+fn test_backup_with_concurrent_writers() {
+    let dir = TempDir::new().unwrap();
+    let backup_path = dir.path().join("backup.manifold");
+    let db = Arc::new(ColumnFamilyDatabase::open(dir.path().join("source.manifold")).unwrap());
+
+    let cf_names = ["cf_a", "cf_b", "cf_c"];
+    for name in cf_names {
+        db.create_column_family(name, None).unwrap();
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let committed: Vec<Arc<AtomicU64>> = cf_names
+        .iter()
+        .map(|_| Arc::new(AtomicU64::new(0)))
+        .collect();
+
+    // Each writer commits keys 0, 1, 2, ... one per transaction and publishes how many it
+    // has committed
+    let writers: Vec<_> = cf_names
+        .iter()
+        .zip(&committed)
+        .map(|(&name, count)| {
+            let db = Arc::clone(&db);
+            let stop = Arc::clone(&stop);
+            let count = Arc::clone(count);
+            thread::spawn(move || {
+                let cf = db.column_family(name).unwrap();
+                let mut next = 0u64;
+                while !stop.load(Ordering::Acquire) {
+                    let txn = cf.begin_write().unwrap();
+                    {
+                        let mut table = txn.open_table(DATA_TABLE).unwrap();
+                        table.insert(next, next * 10).unwrap();
+                    }
+                    txn.commit().unwrap();
+                    next += 1;
+                    count.store(next, Ordering::Release);
+                }
+            })
+        })
+        .collect();
+
+    while committed.iter().any(|c| c.load(Ordering::Acquire) < 50) {
+        thread::yield_now();
+    }
+
+    let before: Vec<u64> = committed
+        .iter()
+        .map(|c| c.load(Ordering::Acquire))
+        .collect();
+    db.backup_to(&backup_path).unwrap();
+
+    stop.store(true, Ordering::Release);
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let backup = ColumnFamilyDatabase::open(&backup_path).unwrap();
+    for (name, &before) in cf_names.iter().zip(&before) {
+        let entries = read_all(&backup, name);
+
+        // Every key committed before the backup started is present, and the backup holds a
+        // consistent prefix of each writer's commits
+        assert!(entries.len() as u64 >= before, "{name} is missing commits");
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            assert_eq!(key, i as u64);
+            assert_eq!(value, key * 10);
+        }
+    }
+}
+
+#[test]
+fn test_backup_refuses_existing_destination() {
+    let dir = TempDir::new().unwrap();
+    let backup_path = dir.path().join("backup.manifold");
+    std::fs::write(&backup_path, b"keep me").unwrap();
+
+    let db = ColumnFamilyDatabase::open(dir.path().join("source.manifold")).unwrap();
+    insert_range(&db, "users", 0..10);
+
+    assert!(db.backup_to(&backup_path).is_err());
+    assert_eq!(std::fs::read(&backup_path).unwrap(), b"keep me");
+}
+
+#[test]
+fn test_backup_of_in_memory_database() {
+    let dir = TempDir::new().unwrap();
+    let backup_path = dir.path().join("backup.manifold");
+
+    let db = ColumnFamilyDatabase::builder()
+        .without_wal()
+        .open_in_memory()
+        .unwrap();
+    insert_range(&db, "users", 0..1000);
+
+    let summary = db.backup_to(&backup_path).unwrap();
+    assert_eq!(summary.column_families, vec!["users".to_string()]);
+
+    let backup = ColumnFamilyDatabase::open(&backup_path).unwrap();
+    let cf = backup.column_family("users").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(DATA_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 1000);
+    assert_eq!(table.get(999).unwrap().unwrap().value(), 9990);
+}
@@ -1,10 +1,10 @@
-use manifold::backends::InMemoryBackend;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 use manifold::DatabaseError;
+use manifold::backends::InMemoryBackend;
 use manifold::{
-    Database, Key, Legacy, MultimapTableDefinition, MultimapTableHandle, Range, ReadOnlyDatabase,
-    ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition, TableError,
-    TableHandle, TypeName, Value,
+    AccessGuard, Database, Key, Legacy, MultimapTableDefinition, MultimapTableHandle, Range,
+    ReadHint, ReadOnlyDatabase, ReadableDatabase, ReadableTable, ReadableTableMetadata,
+    StorageBackend, TableDefinition, TableError, TableHandle, TypeName, Value,
 };
 use rand::random;
 use std::cmp::Ordering;
@@ -123,6 +123,85 @@ fn in_memory() {
     assert_eq!(table.len().unwrap(), 3);
 }
 
+#[test]
+fn range_with_read_hint() {
+    #[derive(Debug)]
+    struct PrefetchRecorder {
+        inner: InMemoryBackend,
+        prefetched: sync::Arc<sync::Mutex<Vec<(u64, u64)>>>,
+    }
+
+    impl StorageBackend for PrefetchRecorder {
+        fn len(&self) -> Result<u64, std::io::Error> {
+            self.inner.len()
+        }
+
+        fn read(&self, offset: u64, out: &mut [u8]) -> Result<(), std::io::Error> {
+            self.inner.read(offset, out)
+        }
+
+        fn set_len(&self, len: u64) -> Result<(), std::io::Error> {
+            self.inner.set_len(len)
+        }
+
+        fn sync_data(&self) -> Result<(), std::io::Error> {
+            self.inner.sync_data()
+        }
+
+        fn write(&self, offset: u64, data: &[u8]) -> Result<(), std::io::Error> {
+            self.inner.write(offset, data)
+        }
+
+        fn prefetch(&self, offset: u64, len: u64) -> Result<(), std::io::Error> {
+            self.prefetched.lock().unwrap().push((offset, len));
+            Ok(())
+        }
+    }
+
+    let prefetched = sync::Arc::new(sync::Mutex::new(vec![]));
+    let db = Database::builder()
+        .create_with_backend(PrefetchRecorder {
+            inner: InMemoryBackend::new(),
+            prefetched: prefetched.clone(),
+        })
+        .unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..50_000 {
+            table.insert(&i, &(i * 2)).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let scan = |range: std::ops::Range<u64>, hint: ReadHint, reverse: bool| -> Vec<u64> {
+        let iter = table.range(range).unwrap().with_hint(hint);
+        let keys = |(k, _): (AccessGuard<u64>, AccessGuard<u64>)| k.value();
+        if reverse {
+            iter.rev().map(|x| keys(x.unwrap())).collect()
+        } else {
+            iter.map(|x| keys(x.unwrap())).collect()
+        }
+    };
+
+    for (range, reverse) in [(0..50_000, false), (0..50_000, true), (1_000..2_000, false)] {
+        let expected = scan(range.clone(), ReadHint::Normal, reverse);
+        assert_eq!(expected.len(), range.clone().count());
+        assert!(prefetched.lock().unwrap().is_empty());
+
+        for hint in [ReadHint::Sequential, ReadHint::SequentialWindow(16 * 1024)] {
+            assert_eq!(scan(range.clone(), hint, reverse), expected, "{hint:?}");
+            let calls = std::mem::take(&mut *prefetched.lock().unwrap());
+            assert!(!calls.is_empty(), "{hint:?}");
+            if hint == ReadHint::SequentialWindow(16 * 1024) {
+                assert!(calls.iter().all(|&(_, len)| len <= 16 * 1024));
+            }
+        }
+    }
+}
+
 #[test]
 fn first_last() {
     let tmpfile = create_tempfile();
@@ -338,6 +417,28 @@ fn stored_size() {
     write_txn.abort().unwrap();
 }
 
+#[test]
+fn access_guard_bytes() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        table.insert(&1, &0x0102_0304).unwrap();
+        assert_eq!(
+            table.get(&1).unwrap().unwrap().as_bytes(),
+            0x0102_0304u64.to_le_bytes()
+        );
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let (key, value) = table.first().unwrap().unwrap();
+    assert_eq!(key.as_bytes(), 1u64.to_le_bytes());
+    assert_eq!(value.as_bytes(), 0x0102_0304u64.to_le_bytes());
+}
+
 #[test]
 fn create_open() {
     let tmpfile = create_tempfile();
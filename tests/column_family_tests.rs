@@ -1,15 +1,34 @@
-use manifold::column_family::{ColumnFamilyDatabase, ColumnFamilyError};
-use manifold::{ReadableTableMetadata, TableDefinition};
-use std::sync::Arc;
+use manifold::backends::FileBackend;
+use manifold::column_family::{
+    ColumnFamilyDatabase, ColumnFamilyError, DatabaseMetrics, FORMAT_VERSION, MAGIC_NUMBER,
+    MAX_USER_METADATA_SIZE, MaintenanceThresholds, MasterHeader,
+};
+use manifold::{
+    DatabaseError, MultimapTableDefinition, OpOutcome, ReadableTable, ReadableTableMetadata,
+    SavepointError, TableDefinition, TableError, TransactionError, WriteTransaction,
+};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tempfile::NamedTempFile;
 
 const TEST_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("test");
 const META_TABLE: TableDefinition<u64, &str> = TableDefinition::new("metadata");
 const DATA_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("data");
 
+/// Returns the newest generation of the master header in `bytes`, the start of a database
+/// file, from the first page of each header slot.
+fn newest_header(bytes: &[u8]) -> MasterHeader {
+    bytes
+        .chunks(4096)
+        .take(2)
+        .filter_map(|slot| MasterHeader::from_bytes(slot).ok())
+        .max_by_key(|header| header.header_generation)
+        .unwrap()
+}
+
 // Integration Tests
 
 #[test]
@@ -567,3 +586,1117 @@ fn stress_test_data_integrity_verification() {
         }
     }
 }
+
+#[test]
+fn test_open_locked_file_reports_path() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let _db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+
+    let result = ColumnFamilyDatabase::open(tmpfile.path());
+    match result {
+        Err(DatabaseError::FileLocked { path, holder_hint }) => {
+            assert_eq!(path, tmpfile.path());
+            if cfg!(target_os = "linux") {
+                let hint = holder_hint.expect("lock holder should be known on linux");
+                assert!(hint.contains(&std::process::id().to_string()));
+            }
+        }
+        Err(e) => panic!("expected FileLocked, got {e}"),
+        Ok(_) => panic!("expected FileLocked, got Ok"),
+    }
+}
+
+#[test]
+fn test_open_timeout_gives_up_on_held_lock() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let _db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+
+    let timeout = Duration::from_millis(200);
+    let start = Instant::now();
+    let result = ColumnFamilyDatabase::builder()
+        .open_timeout(timeout)
+        .open(tmpfile.path());
+    let elapsed = start.elapsed();
+
+    assert!(matches!(result, Err(DatabaseError::FileLocked { .. })));
+    assert!(elapsed >= timeout);
+    assert!(elapsed < timeout + Duration::from_secs(5));
+}
+
+#[test]
+fn test_open_timeout_acquires_released_lock() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let path = tmpfile.path().to_path_buf();
+    let db = ColumnFamilyDatabase::open(&path).unwrap();
+
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        drop(db);
+    });
+
+    let reopened = ColumnFamilyDatabase::builder()
+        .open_timeout(Duration::from_secs(10))
+        .open(&path);
+    releaser.join().unwrap();
+
+    assert!(reopened.is_ok());
+}
+
+#[test]
+fn test_small_default_cf_size_expands_past_first_segment() {
+    const INITIAL_SIZE: u64 = 16 * 1024 * 1024;
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .default_cf_size(INITIAL_SIZE)
+        .open(tmpfile.path())
+        .unwrap();
+
+    // Three times the initial size
+    let cf = db.column_family_or_create("tenant").unwrap();
+    let data = vec![0x5a; 256 * 1024];
+    for batch in 0..12u64 {
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TEST_TABLE).unwrap();
+            for i in 0..16 {
+                table.insert(&(batch * 16 + i), data.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+    drop(cf);
+    drop(db);
+
+    let bytes = std::fs::read(tmpfile.path()).unwrap();
+    let header = newest_header(&bytes);
+    let segments = &header.column_families[0].segments;
+    assert_eq!(segments[0].size, INITIAL_SIZE);
+    assert!(segments.len() > 1, "expected expansion, got {segments:?}");
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family("tenant").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 192);
+    for i in 0..192 {
+        assert_eq!(table.get(&i).unwrap().unwrap().value(), data.as_slice());
+    }
+}
+
+#[test]
+fn test_column_family_or_create_with_size() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+
+    let cf = db
+        .column_family_or_create_with("small", 16 * 1024 * 1024)
+        .unwrap();
+    let txn = cf.begin_write().unwrap();
+    txn.open_table(META_TABLE)
+        .unwrap()
+        .insert(&1, "first")
+        .unwrap();
+    txn.commit().unwrap();
+
+    // An existing column family is returned as is, whatever the size
+    let cf = db.column_family_or_create_with("small", 1024).unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(META_TABLE).unwrap();
+    assert_eq!(table.get(&1).unwrap().unwrap().value(), "first");
+
+    for result in [
+        db.column_family_or_create_with("tiny", 8192),
+        db.create_column_family("tiny", Some(0)),
+    ] {
+        match result {
+            Err(ColumnFamilyError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+            other => panic!(
+                "expected an invalid size error, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+    assert_eq!(db.list_column_families(), vec!["small".to_string()]);
+
+    // The minimum size is usable
+    let cf = db
+        .column_family_or_create_with("minimal", 16 * 1024)
+        .unwrap();
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(TEST_TABLE).unwrap();
+        for i in 0..100 {
+            table.insert(&i, [0xab; 1024].as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn test_preallocate_disabled() {
+    const SIZE: u64 = 64 * 1024 * 1024;
+
+    let preallocated = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .open(preallocated.path())
+        .unwrap();
+    db.create_column_family("a", Some(SIZE)).unwrap();
+    db.create_column_family("b", Some(SIZE)).unwrap();
+    assert!(std::fs::metadata(preallocated.path()).unwrap().len() >= 2 * SIZE);
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .preallocate(false)
+        .open(tmpfile.path())
+        .unwrap();
+    db.create_column_family("a", Some(SIZE)).unwrap();
+    db.create_column_family("b", Some(SIZE)).unwrap();
+    assert!(std::fs::metadata(tmpfile.path()).unwrap().len() < SIZE);
+
+    // Both partitions grow the file as they are written, in either order
+    let data = vec![0x11; 64 * 1024];
+    for name in ["b", "a", "b"] {
+        let cf = db.column_family(name).unwrap();
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TEST_TABLE).unwrap();
+            let start = table.len().unwrap();
+            for i in start..start + 32 {
+                table.insert(&i, data.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+    drop(db);
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    for (name, count) in [("a", 32), ("b", 64)] {
+        let cf = db.column_family(name).unwrap();
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), count);
+        assert_eq!(table.get(&0).unwrap().unwrap().value(), data.as_slice());
+    }
+}
+
+#[test]
+fn test_concurrent_column_family_creation() {
+    const THREADS: u64 = 16;
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = Arc::new(ColumnFamilyDatabase::open(tmpfile.path()).unwrap());
+    let barrier = Arc::new(std::sync::Barrier::new(THREADS as usize));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|i| {
+            let db = Arc::clone(&db);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                // Sizes that are not page multiples, so each offset must be realigned
+                let name = format!("cf_{i}");
+                let size = (i + 1) * 64 * 1024 + 100;
+                barrier.wait();
+                let cf = if i % 2 == 0 {
+                    db.create_column_family(name, Some(size)).unwrap()
+                } else {
+                    db.column_family_or_create_with(&name, size).unwrap()
+                };
+
+                let txn = cf.begin_write().unwrap();
+                txn.open_table(TEST_TABLE)
+                    .unwrap()
+                    .insert(&i, [i as u8; 100].as_slice())
+                    .unwrap();
+                txn.commit().unwrap();
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let bytes = std::fs::read(tmpfile.path()).unwrap();
+    let header = newest_header(&bytes);
+    header.validate().unwrap();
+    assert_eq!(header.column_families.len(), THREADS as usize);
+
+    let mut segments: Vec<_> = header
+        .column_families
+        .iter()
+        .flat_map(|cf| cf.segments.iter())
+        .collect();
+    segments.sort_by_key(|segment| segment.offset);
+    for pair in segments.windows(2) {
+        assert!(
+            pair[0].offset + pair[0].size <= pair[1].offset,
+            "overlapping segments {pair:?}"
+        );
+    }
+
+    drop(db);
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    for i in 0..THREADS {
+        let cf = db.column_family(&format!("cf_{i}")).unwrap();
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(
+            table.get(&i).unwrap().unwrap().value(),
+            [i as u8; 100].as_slice()
+        );
+    }
+}
+
+#[test]
+fn test_deleted_segments_coalesce_and_truncate() {
+    const SIZE: u64 = 4 * 1024 * 1024;
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    for i in 0..10u64 {
+        let cf = db
+            .create_column_family(format!("cf_{i}"), Some(SIZE))
+            .unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(TEST_TABLE)
+            .unwrap()
+            .insert(&i, [i as u8; 100].as_slice())
+            .unwrap();
+        txn.commit().unwrap();
+    }
+    // Keeps the freed space away from the end of the file
+    db.create_column_family("last", Some(SIZE)).unwrap();
+    let file_len = std::fs::metadata(tmpfile.path()).unwrap().len();
+    assert_eq!(db.fragmentation_stats().fragments, 0);
+
+    for i in (0..10).step_by(2) {
+        db.delete_column_family(&format!("cf_{i}")).unwrap();
+    }
+    let stats = db.fragmentation_stats();
+    assert_eq!(stats.fragments, 5);
+    assert_eq!(stats.free_bytes, 5 * SIZE);
+    assert_eq!(stats.largest_free_block, SIZE);
+
+    // A freed segment is reused before the file grows
+    db.create_column_family("reused", Some(SIZE)).unwrap();
+    assert_eq!(db.fragmentation_stats().fragments, 4);
+    assert_eq!(std::fs::metadata(tmpfile.path()).unwrap().len(), file_len);
+    db.delete_column_family("reused").unwrap();
+
+    for i in (1..10).step_by(2) {
+        db.delete_column_family(&format!("cf_{i}")).unwrap();
+    }
+    let stats = db.fragmentation_stats();
+    assert_eq!(stats.fragments, 1);
+    assert_eq!(stats.free_bytes, 10 * SIZE);
+    assert_eq!(stats.largest_free_block, 10 * SIZE);
+
+    db.delete_column_family("last").unwrap();
+    assert_eq!(db.fragmentation_stats().fragments, 0);
+    // Only the two header slots are left
+    assert_eq!(std::fs::metadata(tmpfile.path()).unwrap().len(), 2 * 4096);
+
+    drop(db);
+    let bytes = std::fs::read(tmpfile.path()).unwrap();
+    let header = newest_header(&bytes);
+    header.validate().unwrap();
+    assert!(header.column_families.is_empty());
+    assert!(header.free_segments.is_empty());
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    assert!(db.list_column_families().is_empty());
+}
+
+#[test]
+fn test_maintenance_hint_tells_churned_cf_apart() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let quiet = db.column_family_or_create("quiet").unwrap();
+    let churned = db.column_family_or_create("churned").unwrap();
+
+    insert_values(quiet.begin_write().unwrap(), 0..10);
+    db.checkpoint().unwrap();
+
+    // Fill and empty the table a few times, then commit twice more so that the freed pages
+    // are released once no transaction can read them
+    for _ in 0..3 {
+        insert_values(churned.begin_write_unlogged().unwrap(), 0..4000);
+        let txn = churned.begin_write_unlogged().unwrap();
+        txn.open_table(TEST_TABLE)
+            .unwrap()
+            .retain(|_, _| false)
+            .unwrap();
+        txn.commit().unwrap();
+    }
+    insert_values(churned.begin_write_unlogged().unwrap(), 0..1);
+    insert_values(churned.begin_write_unlogged().unwrap(), 1..2);
+    // Left pending in the WAL
+    insert_values(churned.begin_write().unwrap(), 2..3);
+
+    let quiet_hint = quiet.maintenance_hint().unwrap();
+    let churned_hint = churned.maintenance_hint().unwrap();
+    assert!(
+        churned_hint.reclaimable_bytes >= 4 * 1024 * 1024,
+        "{churned_hint:?}"
+    );
+    assert!(
+        churned_hint.reclaimable_bytes > 2 * quiet_hint.reclaimable_bytes,
+        "{quiet_hint:?} {churned_hint:?}"
+    );
+    assert_eq!(quiet_hint.pending_wal_entries, 0);
+    assert_eq!(churned_hint.pending_wal_entries, 1);
+    assert!(quiet_hint.since_checkpoint.is_some());
+
+    let thresholds = MaintenanceThresholds {
+        reclaimable_bytes: 4 * 1024 * 1024,
+        pending_wal_entries: 1000,
+        since_checkpoint: Duration::from_secs(3600),
+    };
+    assert!(churned_hint.needs_maintenance(&thresholds));
+    assert!(!quiet_hint.needs_maintenance(&thresholds));
+    assert!(!quiet_hint.needs_maintenance(&MaintenanceThresholds::default()));
+
+    // A pending entry past the age threshold needs a checkpoint on its own
+    let stale = MaintenanceThresholds {
+        reclaimable_bytes: u64::MAX,
+        since_checkpoint: Duration::ZERO,
+        ..thresholds
+    };
+    assert!(churned_hint.needs_maintenance(&stale));
+    assert!(!quiet_hint.needs_maintenance(&stale));
+
+    db.checkpoint_cf("churned").unwrap();
+    let hint = churned.maintenance_hint().unwrap();
+    assert_eq!(hint.pending_wal_entries, 0);
+    assert!(hint.since_checkpoint.unwrap() < Duration::from_secs(60));
+}
+
+#[test]
+#[cfg(feature = "cache_metrics")]
+fn test_cache_size_per_column_family() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .default_cf_cache_size(64 * 1024 * 1024)
+        .cf_cache_size("scan", 16 * 1024)
+        .open(tmpfile.path())
+        .unwrap();
+
+    // The same workload on both: write 1 MiB of rows, then read them back three times
+    let data = vec![7u8; 1024];
+    let mut stats = Vec::new();
+    for name in ["hot", "scan"] {
+        let cf = db.column_family_or_create(name).unwrap();
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TEST_TABLE).unwrap();
+            for i in 0..1024u64 {
+                table.insert(&i, data.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+
+        for _ in 0..3 {
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(TEST_TABLE).unwrap();
+            for i in 0..1024u64 {
+                assert_eq!(table.get(&i).unwrap().unwrap().value(), data.as_slice());
+            }
+        }
+        stats.push(cf.cache_stats().unwrap());
+    }
+
+    // The large cache holds every page, the 16 KiB one keeps evicting them
+    let (hot, scan) = (&stats[0], &stats[1]);
+    assert_eq!(hot.evictions(), 0);
+    assert!(scan.evictions() > 0);
+    assert!(hot.read_hits() > scan.read_hits());
+    assert!(hot.read_misses() < scan.read_misses());
+}
+
+#[test]
+fn test_read_at_pinned_snapshot() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.create_column_family("data", None).unwrap();
+    let other = db.create_column_family("other", None).unwrap();
+
+    let write = |key: u64, value: &[u8]| {
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(TEST_TABLE)
+            .unwrap()
+            .insert(&key, value)
+            .unwrap();
+        txn.commit().unwrap();
+    };
+
+    write(1, b"before");
+    let snapshot = cf.snapshot().unwrap();
+    write(1, b"after");
+    write(2, b"after");
+
+    let read_at = cf.begin_read_at(&snapshot).unwrap();
+    assert_eq!(read_at.snapshot_id(), snapshot.id());
+    let table = read_at.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.get(&1).unwrap().unwrap().value(), b"before");
+    assert!(table.get(&2).unwrap().is_none());
+    drop(table);
+    read_at.close().unwrap();
+
+    let latest = cf.begin_read().unwrap();
+    assert!(latest.snapshot_id() > snapshot.id());
+    let table = latest.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.get(&1).unwrap().unwrap().value(), b"after");
+    drop(table);
+    drop(latest);
+
+    // The snapshot stays readable while later commits free its pages
+    for i in 0..500 {
+        write(1, &[0; 1024]);
+        write(100 + i, &[1; 1024]);
+    }
+    let read_at = cf.begin_read_at(&snapshot).unwrap();
+    let table = read_at.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.get(&1).unwrap().unwrap().value(), b"before");
+    assert_eq!(table.len().unwrap(), 1);
+    drop(table);
+    drop(read_at);
+
+    assert!(matches!(
+        other.begin_read_at(&snapshot),
+        Err(TransactionError::SnapshotReclaimed(id)) if id == snapshot.id()
+    ));
+    cf.release_handle();
+    assert!(matches!(
+        cf.begin_read_at(&snapshot),
+        Err(TransactionError::SnapshotReclaimed(_))
+    ));
+}
+
+#[test]
+fn test_list_and_delete_tables() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.create_column_family("data", None).unwrap();
+    let tags: MultimapTableDefinition<u64, &str> = MultimapTableDefinition::new("tags");
+
+    assert!(cf.list_tables().unwrap().is_empty());
+
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(TEST_TABLE).unwrap();
+        for i in 0..25 {
+            table.insert(&i, b"value".as_slice()).unwrap();
+        }
+        txn.open_table(META_TABLE).unwrap();
+        let mut table = txn.open_multimap_table(tags).unwrap();
+        table.insert(&1, "a").unwrap();
+        table.insert(&1, "b").unwrap();
+        table.insert(&2, "a").unwrap();
+    }
+    txn.commit().unwrap();
+
+    let listed = cf.list_tables().unwrap();
+    let summary: Vec<(&str, u64, bool)> = listed
+        .iter()
+        .map(|table| (table.name.as_str(), table.entries, table.multimap))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("metadata", 0, false),
+            ("tags", 3, true),
+            ("test", 25, false)
+        ]
+    );
+
+    assert!(cf.delete_table("test").unwrap());
+    assert!(cf.delete_table("tags").unwrap());
+    assert!(!cf.delete_table("test").unwrap());
+    assert!(!cf.delete_table("missing").unwrap());
+
+    let names: Vec<String> = cf
+        .list_tables()
+        .unwrap()
+        .into_iter()
+        .map(|table| table.name)
+        .collect();
+    assert_eq!(names, vec!["metadata"]);
+    let txn = cf.begin_read().unwrap();
+    assert!(txn.open_table(TEST_TABLE).is_err());
+}
+
+#[test]
+fn test_column_family_metadata_persists() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let before_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+    {
+        let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+        let cf = db.create_column_family("users", None).unwrap();
+        let metadata = cf.metadata().unwrap();
+        assert_eq!(metadata.name, "users");
+        assert!(u128::from(metadata.created_at_ms) >= before_ms);
+        assert!(metadata.user_metadata.is_empty());
+
+        db.set_cf_metadata("users", b"schema=3;owner=billing")
+            .unwrap();
+        assert_eq!(
+            cf.metadata().unwrap().user_metadata,
+            b"schema=3;owner=billing"
+        );
+
+        let err = db
+            .set_cf_metadata("users", &[0; MAX_USER_METADATA_SIZE + 1])
+            .unwrap_err();
+        assert!(matches!(err, ColumnFamilyError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+        assert!(matches!(
+            db.set_cf_metadata("missing", b""),
+            Err(ColumnFamilyError::NotFound(_))
+        ));
+    }
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family("users").unwrap();
+    let metadata = cf.metadata().unwrap();
+    assert_eq!(metadata.user_metadata, b"schema=3;owner=billing");
+    assert!(u128::from(metadata.created_at_ms) >= before_ms);
+
+    // Metadata of many column families spills into the header extension
+    for i in 0..20 {
+        let name = format!("cf_{i}");
+        db.create_column_family(&name, Some(1024 * 1024)).unwrap();
+        db.set_cf_metadata(&name, &[i; MAX_USER_METADATA_SIZE])
+            .unwrap();
+    }
+    drop(cf);
+    drop(db);
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    for i in 0..20 {
+        let cf = db.column_family(&format!("cf_{i}")).unwrap();
+        assert_eq!(
+            cf.metadata().unwrap().user_metadata,
+            [i; MAX_USER_METADATA_SIZE]
+        );
+    }
+}
+
+#[test]
+fn test_upgrades_version_2_header() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    {
+        let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+        let cf = db.create_column_family("legacy", None).unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(META_TABLE)
+            .unwrap()
+            .insert(&1, "kept")
+            .unwrap();
+        txn.commit().unwrap();
+        db.checkpoint().unwrap();
+    }
+
+    // Rewrite the header in the version 2 format, which has no generations, creation times
+    // or user metadata, and move the column family to directly after its single header page,
+    // where files from before version 7 place the first segment
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(tmpfile.path())
+        .unwrap();
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).unwrap();
+    let header = newest_header(&contents);
+    let segment = header.column_families[0].segments[0].clone();
+    assert_eq!(header.column_families[0].segments.len(), 1);
+    assert_eq!(segment.offset, 2 * 4096);
+    let data_end = contents.len().min((segment.offset + segment.size) as usize);
+    let data = contents[segment.offset as usize..data_end].to_vec();
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&MAGIC_NUMBER);
+    bytes.push(2);
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    let cf_meta = &header.column_families[0];
+    bytes.extend_from_slice(&u32::try_from(cf_meta.name.len()).unwrap().to_le_bytes());
+    bytes.extend_from_slice(cf_meta.name.as_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&4096u64.to_le_bytes());
+    bytes.extend_from_slice(&segment.size.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.resize(4092, 0);
+    let crc = crc32fast::hash(&bytes);
+    bytes.extend_from_slice(&crc.to_le_bytes());
+    bytes.extend_from_slice(&data);
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.write_all(&bytes).unwrap();
+    file.sync_all().unwrap();
+    drop(file);
+
+    {
+        let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+        let cf = db.column_family("legacy").unwrap();
+        let metadata = cf.metadata().unwrap();
+        assert_eq!(metadata.generation, 0);
+        assert_eq!(metadata.created_at_ms, 0);
+        assert!(metadata.user_metadata.is_empty());
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(META_TABLE).unwrap();
+        assert_eq!(table.get(&1).unwrap().unwrap().value(), "kept");
+        drop(table);
+        drop(txn);
+
+        db.set_cf_metadata("legacy", b"v2").unwrap();
+    }
+
+    // The header is written back in the current format, in both slots, with the first page
+    // of the column family moved out of the second slot
+    let bytes = std::fs::read(tmpfile.path()).unwrap();
+    for slot in 0..2 {
+        assert_eq!(bytes[slot * 4096 + 9], FORMAT_VERSION);
+        MasterHeader::from_bytes(&bytes[slot * 4096..(slot + 1) * 4096]).unwrap();
+    }
+    let header = newest_header(&bytes);
+    assert_eq!(header.column_families[0].user_metadata, b"v2");
+    let segments = &header.column_families[0].segments;
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].size, 4096);
+    assert!(segments[0].offset >= 2 * 4096);
+    assert_eq!(segments[1].offset, 2 * 4096);
+    assert_eq!(segments[1].size, segment.size - 4096);
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let txn = db.column_family("legacy").unwrap().begin_read().unwrap();
+    let table = txn.open_table(META_TABLE).unwrap();
+    assert_eq!(table.get(&1).unwrap().unwrap().value(), "kept");
+}
+
+#[test]
+fn test_header_grows_past_first_page() {
+    const COLUMN_FAMILIES: usize = 500;
+    let tmpfile = NamedTempFile::new().unwrap();
+    let name = |i: usize| format!("tenant_{i:04}_with_a_moderately_long_descriptive_name");
+    // Without the WAL, dropping the database does not open every column family to checkpoint it
+    let open = || {
+        ColumnFamilyDatabase::builder()
+            .without_wal()
+            .open(tmpfile.path())
+            .unwrap()
+    };
+
+    {
+        let db = open();
+        for i in 0..COLUMN_FAMILIES {
+            let cf = db.create_column_family(name(i), Some(64 * 1024)).unwrap();
+            if i % 50 == 1 {
+                let txn = cf.begin_write().unwrap();
+                txn.open_table(META_TABLE)
+                    .unwrap()
+                    .insert(&(i as u64), "tenant")
+                    .unwrap();
+                txn.commit().unwrap();
+            }
+        }
+    }
+
+    let read_header = || {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(tmpfile.path())
+            .unwrap();
+        MasterHeader::read_from(&FileBackend::new(file).unwrap()).unwrap()
+    };
+    let header = read_header();
+    assert_eq!(header.column_families.len(), COLUMN_FAMILIES);
+    assert_eq!(header.extension_segments.len(), 2);
+
+    {
+        let db = open();
+        let expected: Vec<String> = (0..COLUMN_FAMILIES).map(name).collect();
+        assert_eq!(db.list_column_families(), expected);
+
+        for i in (0..COLUMN_FAMILIES).step_by(5) {
+            db.delete_column_family(&name(i)).unwrap();
+        }
+    }
+
+    let db = open();
+    let expected: Vec<String> = (0..COLUMN_FAMILIES)
+        .filter(|i| i % 5 != 0)
+        .map(name)
+        .collect();
+    assert_eq!(db.list_column_families(), expected);
+    for i in (0..COLUMN_FAMILIES).step_by(50).map(|i| i + 26) {
+        let cf = db.column_family(&name(i)).unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(META_TABLE)
+            .unwrap()
+            .insert(&(i as u64), "tenant")
+            .unwrap();
+        txn.commit().unwrap();
+    }
+    drop(db);
+
+    let header = read_header();
+    header.validate().unwrap();
+    assert_eq!(header.column_families.len(), expected.len());
+    let bytes = header.to_bytes().unwrap();
+    assert!(bytes.len() > 4096);
+    assert_eq!(MasterHeader::from_bytes(&bytes).unwrap(), header);
+
+    // Every page, including those of the extension, is covered by a checksum
+    for page in 0..bytes.len() / 4096 {
+        let mut corrupted = bytes.clone();
+        corrupted[page * 4096 + 20] ^= 0xFF;
+        assert!(
+            MasterHeader::from_bytes(&corrupted).is_err(),
+            "corruption of page {page} went undetected"
+        );
+    }
+
+    let db = open();
+    for i in (0..COLUMN_FAMILIES).filter(|i| i % 50 == 1 || i % 50 == 26) {
+        let txn = db.column_family(&name(i)).unwrap().begin_read().unwrap();
+        let table = txn.open_table(META_TABLE).unwrap();
+        assert_eq!(table.get(&(i as u64)).unwrap().unwrap().value(), "tenant");
+    }
+}
+
+#[derive(Default)]
+struct CountingMetrics {
+    commits: Mutex<Vec<(String, Duration, Duration)>>,
+    checkpoints: Mutex<Vec<usize>>,
+    segments: Mutex<Vec<(String, u64)>>,
+}
+
+impl DatabaseMetrics for CountingMetrics {
+    fn on_commit(&self, cf: &str, wal_wait: Duration, total: Duration) {
+        self.commits
+            .lock()
+            .unwrap()
+            .push((cf.to_string(), wal_wait, total));
+    }
+
+    fn on_checkpoint(&self, _duration: Duration, entries: usize) {
+        self.checkpoints.lock().unwrap().push(entries);
+    }
+
+    fn on_segment_allocated(&self, cf: &str, size: u64) {
+        self.segments.lock().unwrap().push((cf.to_string(), size));
+    }
+}
+
+fn insert_values(txn: WriteTransaction, keys: std::ops::Range<u64>) {
+    {
+        let mut table = txn.open_table(TEST_TABLE).unwrap();
+        for key in keys {
+            table.insert(&key, [7u8; 1024].as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn test_metrics_hooks_with_wal() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let metrics = Arc::new(CountingMetrics::default());
+    let db = ColumnFamilyDatabase::builder()
+        .metrics(metrics.clone())
+        .open(tmpfile.path())
+        .unwrap();
+
+    let cf = db.create_column_family("a", Some(64 * 1024)).unwrap();
+    let other = db.create_column_family("b", Some(64 * 1024)).unwrap();
+    assert_eq!(
+        *metrics.segments.lock().unwrap(),
+        [("a".to_string(), 64 * 1024), ("b".to_string(), 64 * 1024)]
+    );
+
+    // Logged commits wait for the WAL
+    for i in 0..3 {
+        insert_values(cf.begin_write().unwrap(), i * 10..(i + 1) * 10);
+    }
+    {
+        let commits = metrics.commits.lock().unwrap();
+        assert_eq!(commits.len(), 3);
+        for (name, wal_wait, total) in commits.iter() {
+            assert_eq!(name, "a");
+            assert!(*wal_wait > Duration::ZERO);
+            assert!(wal_wait <= total);
+        }
+    }
+
+    // Writing far more than the first segment holds grows the column family
+    insert_values(cf.begin_write().unwrap(), 100..400);
+    let grown = metrics.segments.lock().unwrap().len();
+    assert!(grown > 2);
+    assert!(
+        metrics.segments.lock().unwrap()[2..]
+            .iter()
+            .all(|(name, size)| name == "a" && *size > 0)
+    );
+
+    // An unlogged commit checkpoints the pending entries first, and skips the WAL itself
+    insert_values(cf.begin_write_unlogged().unwrap(), 400..410);
+    assert_eq!(*metrics.checkpoints.lock().unwrap(), [4]);
+    let (name, wal_wait, _) = metrics.commits.lock().unwrap().last().cloned().unwrap();
+    assert_eq!(name, "a");
+    assert_eq!(wal_wait, Duration::ZERO);
+
+    // Each member of a write group reports the group commit
+    let group = db.write_group(&[&other, &cf]).unwrap();
+    for name in ["a", "b"] {
+        let mut table = group
+            .transaction(name)
+            .unwrap()
+            .open_table(TEST_TABLE)
+            .unwrap();
+        table.insert(&1000, [1u8; 16].as_slice()).unwrap();
+    }
+    group.commit().unwrap();
+    let commits = metrics.commits.lock().unwrap().clone();
+    assert_eq!(commits.len(), 7);
+    let names: Vec<&str> = commits[5..]
+        .iter()
+        .map(|(name, _, _)| name.as_str())
+        .collect();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(commits[5].1, commits[6].1);
+    assert!(commits[5].1 > Duration::ZERO);
+
+    // Only the members' entries are applied, not the entry that completes the group
+    db.checkpoint().unwrap();
+    assert_eq!(*metrics.checkpoints.lock().unwrap(), [4, 2]);
+}
+
+#[test]
+fn test_metrics_hooks_without_wal() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let metrics = Arc::new(CountingMetrics::default());
+    let db = ColumnFamilyDatabase::builder()
+        .without_wal()
+        .metrics(metrics.clone())
+        .open(tmpfile.path())
+        .unwrap();
+
+    let cf = db.create_column_family("a", Some(64 * 1024)).unwrap();
+    for i in 0..2 {
+        insert_values(cf.begin_write().unwrap(), i * 10..(i + 1) * 10);
+    }
+
+    let commits = metrics.commits.lock().unwrap();
+    assert_eq!(commits.len(), 2);
+    for (name, wal_wait, total) in commits.iter() {
+        assert_eq!(name, "a");
+        assert_eq!(*wal_wait, Duration::ZERO);
+        assert!(*total > Duration::ZERO);
+    }
+    assert!(metrics.checkpoints.lock().unwrap().is_empty());
+    let segments = metrics.segments.lock().unwrap();
+    assert_eq!(segments[0], ("a".to_string(), 64 * 1024));
+    assert!(segments.iter().all(|(name, _)| name == "a"));
+}
+
+#[test]
+fn test_bulk_load() {
+    const ROWS: u64 = 1_000_000;
+    const BULK_TABLE: TableDefinition<u64, u64> = TableDefinition::new("bulk");
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    {
+        let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+        let cf = db.column_family_or_create("bulk").unwrap();
+
+        let mut reports = Vec::new();
+        let progress = cf
+            .bulk_loader(BULK_TABLE)
+            .chunk_rows(100_000)
+            .sync_every(3)
+            .checkpoint_on_finish(true)
+            .on_progress(|progress| reports.push(progress))
+            .load((0..ROWS).map(|key| (key, key * 3)))
+            .unwrap();
+
+        assert_eq!(progress.rows, ROWS);
+        assert_eq!(progress.bytes, ROWS * 16);
+        assert_eq!(progress.chunks, 10);
+        assert_eq!(reports.len(), 10);
+        for (chunk, report) in (1..).zip(&reports) {
+            assert_eq!(report.chunks, chunk);
+            assert_eq!(report.rows, chunk * 100_000);
+        }
+        assert!(reports.windows(2).all(|w| w[0].elapsed <= w[1].elapsed));
+
+        // The table can be written normally once the load has finished
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(BULK_TABLE)
+            .unwrap()
+            .insert(&ROWS, &0)
+            .unwrap();
+        txn.commit().unwrap();
+    }
+
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.column_family("bulk").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(BULK_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), ROWS + 1);
+    for key in (0..ROWS).step_by(7919).chain([ROWS - 1]) {
+        assert_eq!(
+            table.get(&key).unwrap().unwrap().value(),
+            key * 3,
+            "key {key}"
+        );
+    }
+}
+
+#[test]
+fn test_idempotent_skips_applied_op_ids() {
+    const COUNTER: TableDefinition<&str, u64> = TableDefinition::new("counter");
+
+    fn increment(txn: &WriteTransaction, op_id: u128) -> OpOutcome<u64> {
+        txn.idempotent(op_id, |txn| {
+            let mut table = txn.open_table(COUNTER)?;
+            let count = table.get("hits")?.map_or(0, |guard| guard.value()) + 1;
+            table.insert("hits", count)?;
+            Ok::<_, TableError>(count)
+        })
+        .unwrap()
+    }
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::open(tmpfile.path()).unwrap();
+    let cf = db.create_column_family("ingest", None).unwrap();
+
+    let txn = cf.begin_write().unwrap();
+    assert_eq!(increment(&txn, 1), OpOutcome::Applied(1));
+    txn.commit().unwrap();
+
+    // A retry in a later transaction is skipped, as is one in the same transaction
+    let txn = cf.begin_write().unwrap();
+    assert_eq!(increment(&txn, 1), OpOutcome::Replayed);
+    assert_eq!(increment(&txn, 2), OpOutcome::Applied(2));
+    assert_eq!(increment(&txn, 2), OpOutcome::Replayed);
+    txn.commit().unwrap();
+
+    // An aborted transaction records neither the id nor the writes
+    let txn = cf.begin_write().unwrap();
+    assert_eq!(increment(&txn, 3), OpOutcome::Applied(3));
+    txn.abort().unwrap();
+
+    // A failed operation is not recorded
+    let txn = cf.begin_write().unwrap();
+    let failed = txn.idempotent(4, |_| {
+        Err::<(), _>(TableError::TableDoesNotExist("x".into()))
+    });
+    assert!(failed.is_err());
+    assert_eq!(increment(&txn, 4), OpOutcome::Applied(3));
+    txn.commit().unwrap();
+
+    // Pruned ids are applied again
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let txn = cf.begin_write().unwrap();
+    assert_eq!(txn.prune_op_ids_before(0).unwrap(), 0);
+    assert_eq!(txn.prune_op_ids_before(now + 1).unwrap(), 3);
+    assert_eq!(increment(&txn, 1), OpOutcome::Applied(4));
+    txn.commit().unwrap();
+}
+
+#[test]
+fn test_transaction_savepoint_rollback() {
+    const EDGES: TableDefinition<u64, u64> = TableDefinition::new("edges");
+    const EVENTS: TableDefinition<u64, u64> = TableDefinition::new("events");
+
+    for wal in [true, false] {
+        let tmpfile = NamedTempFile::new().unwrap();
+        let builder = ColumnFamilyDatabase::builder();
+        let builder = if wal { builder } else { builder.without_wal() };
+        let db = builder.open(tmpfile.path()).unwrap();
+        let cf = db.create_column_family("graph", None).unwrap();
+
+        let mut txn = cf.begin_write().unwrap();
+        // Enough entries for the table to span several pages
+        {
+            let mut edges = txn.open_table(EDGES).unwrap();
+            for key in 0..1000 {
+                edges.insert(key, key).unwrap();
+            }
+        }
+        let savepoint = txn.savepoint().unwrap();
+        {
+            let mut edges = txn.open_table(EDGES).unwrap();
+            for key in 0..500 {
+                edges.insert(key, key + 1).unwrap();
+            }
+            for key in 500..600 {
+                edges.remove(key).unwrap();
+            }
+            for key in 1000..2000 {
+                edges.insert(key, key).unwrap();
+            }
+            let mut events = txn.open_table(EVENTS).unwrap();
+            events.insert(1, 1).unwrap();
+        }
+
+        // A savepoint created after the one rolled back to is invalidated
+        let later = txn.savepoint().unwrap();
+        txn.rollback_to_savepoint(&savepoint).unwrap();
+        assert!(matches!(
+            txn.rollback_to_savepoint(&later),
+            Err(SavepointError::InvalidSavepoint)
+        ));
+
+        // The savepoint can be rolled back to again
+        txn.open_table(EDGES).unwrap().insert(0, 7).unwrap();
+        txn.rollback_to_savepoint(&savepoint).unwrap();
+        txn.commit().unwrap();
+
+        // Savepoints do not outlive their transaction
+        let mut txn = cf.begin_write().unwrap();
+        assert!(matches!(
+            txn.rollback_to_savepoint(&savepoint),
+            Err(SavepointError::InvalidSavepoint)
+        ));
+        txn.abort().unwrap();
+
+        drop(cf);
+        drop(db);
+        let builder = ColumnFamilyDatabase::builder();
+        let builder = if wal { builder } else { builder.without_wal() };
+        let db = builder.open(tmpfile.path()).unwrap();
+        let cf = db.column_family("graph").unwrap();
+
+        let txn = cf.begin_read().unwrap();
+        let edges = txn.open_table(EDGES).unwrap();
+        assert_eq!(edges.len().unwrap(), 1000, "wal: {wal}");
+        for entry in edges.iter().unwrap() {
+            let (key, value) = entry.unwrap();
+            assert_eq!(key.value(), value.value(), "wal: {wal}");
+        }
+        assert!(matches!(
+            txn.open_table(EVENTS),
+            Err(TableError::TableDoesNotExist(_))
+        ));
+        drop(edges);
+        drop(txn);
+
+        // Pages released by the rollback can be reused
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut edges = txn.open_table(EDGES).unwrap();
+            for key in 1000..3000 {
+                edges.insert(key, key).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+        let txn = cf.begin_read().unwrap();
+        assert_eq!(txn.open_table(EDGES).unwrap().len().unwrap(), 3000);
+    }
+}
@@ -0,0 +1,152 @@
+//! Read compatibility with column family databases written by earlier versions
+//!
+//! `tests/golden/` holds a database written by `write_golden` at each released version, named
+//! after that version, with the WAL it was left with. Every golden file must pass
+//! `check_golden` with the current code, so a change that stops the master header, the WAL or
+//! the tables of an earlier version from being read fails this suite. See
+//! `docs/on_disk_format.md` for the formats and for when to add a golden file.
+
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold::{ReadableTable, ReadableTableMetadata, TableDefinition};
+use std::path::Path;
+
+#[path = "support/golden.rs"]
+mod golden;
+
+const GOLDEN_PREFIX: &str = "cf-v";
+
+/// Column families start small and grow as they are written, which keeps the golden file small
+const CF_SIZE: Option<u64> = Some(64 * 1024);
+
+const VALUES: TableDefinition<u64, &str> = TableDefinition::new("values");
+const BLOBS: TableDefinition<&str, &[u8]> = TableDefinition::new("blobs");
+
+/// Writes the fixture to `path`: two column families, one with user metadata, with the last
+/// commits left in the WAL as if the process had crashed before checkpointing them
+///
+/// Must not change once a golden file has been written with it.
+fn write_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+    let alpha = db.create_column_family("alpha", CF_SIZE).unwrap();
+    let beta = db.create_column_family("beta", CF_SIZE).unwrap();
+    db.set_cf_metadata("alpha", b"golden").unwrap();
+
+    let write_txn = alpha.begin_write().unwrap();
+    {
+        let mut values = write_txn.open_table(VALUES).unwrap();
+        for i in 0..100u64 {
+            values.insert(i, format!("value {i}").as_str()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = beta.begin_write().unwrap();
+    {
+        let mut blobs = write_txn.open_table(BLOBS).unwrap();
+        blobs.insert("empty", [0u8; 0].as_slice()).unwrap();
+        blobs.insert("bytes", [0u8, 1, 2, 255].as_slice()).unwrap();
+        blobs.insert("removed", [7u8].as_slice()).unwrap();
+    }
+    write_txn.commit().unwrap();
+    db.checkpoint().unwrap();
+
+    // Logged but not checkpointed, so only the WAL holds these commits
+    let write_txn = alpha.begin_write().unwrap();
+    {
+        let mut values = write_txn.open_table(VALUES).unwrap();
+        for i in 100..110u64 {
+            values.insert(i, format!("logged {i}").as_str()).unwrap();
+        }
+        values.remove(0).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let group = db.write_group(&[&alpha, &beta]).unwrap();
+    {
+        let mut values = group
+            .transaction("alpha")
+            .unwrap()
+            .open_table(VALUES)
+            .unwrap();
+        values.insert(1, "grouped").unwrap();
+        let mut blobs = group
+            .transaction("beta")
+            .unwrap()
+            .open_table(BLOBS)
+            .unwrap();
+        blobs.remove("removed").unwrap();
+    }
+    group.commit().unwrap();
+
+    // Skip the checkpoint that closing the database would run
+    std::mem::forget(alpha);
+    std::mem::forget(beta);
+    std::mem::forget(db);
+}
+
+/// Asserts that the database at `path` holds exactly what `write_golden` wrote
+fn check_golden(path: &Path) {
+    let db = ColumnFamilyDatabase::open(path).unwrap();
+
+    let mut names = db.list_column_families();
+    names.sort();
+    assert_eq!(names, ["alpha", "beta"]);
+
+    let alpha = db.column_family("alpha").unwrap();
+    let metadata = alpha.metadata().unwrap();
+    assert_eq!(metadata.user_metadata, b"golden");
+    assert!(metadata.created_at_ms > 0);
+    assert!(!metadata.encrypted);
+    let beta = db.column_family("beta").unwrap();
+    assert!(beta.metadata().unwrap().user_metadata.is_empty());
+    assert!(beta.metadata().unwrap().generation > metadata.generation);
+
+    let read_txn = alpha.begin_read().unwrap();
+    let values = read_txn.open_table(VALUES).unwrap();
+    assert_eq!(values.len().unwrap(), 109);
+    for entry in values.iter().unwrap() {
+        let (key, value) = entry.unwrap();
+        let expected = match key.value() {
+            1 => "grouped".to_string(),
+            i @ 2..100 => format!("value {i}"),
+            i @ 100..110 => format!("logged {i}"),
+            i => panic!("unexpected key {i}"),
+        };
+        assert_eq!(value.value(), expected);
+    }
+
+    let read_txn = beta.begin_read().unwrap();
+    let blobs = read_txn.open_table(BLOBS).unwrap();
+    let entries: Vec<(String, Vec<u8>)> = blobs
+        .iter()
+        .unwrap()
+        .map(|entry| {
+            let (key, value) = entry.unwrap();
+            (key.value().to_string(), value.value().to_vec())
+        })
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("bytes".to_string(), vec![0, 1, 2, 255]),
+            ("empty".to_string(), vec![]),
+        ]
+    );
+}
+
+#[test]
+fn test_golden_files_read_back() {
+    golden::check_golden_files(GOLDEN_PREFIX, check_golden);
+}
+
+#[test]
+fn test_fixture_round_trips() {
+    golden::check_fixture_round_trips(write_golden, check_golden);
+}
+
+/// Writes the golden file of the current version
+#[test]
+#[ignore = "run once per release to add its golden file"]
+fn bless_golden_file() {
+    golden::bless_golden_file(GOLDEN_PREFIX, write_golden);
+}
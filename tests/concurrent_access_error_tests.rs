@@ -617,7 +617,140 @@ fn test_mixed_read_write_contention() {
     let read_txn = cf.begin_read().unwrap();
     let table = read_txn.open_table(TEST_TABLE).unwrap();
     // Verify initial data exists
-    assert!(table.get(&0).unwrap().is_some(), "Initial data should exist");
+    assert!(
+        table.get(&0).unwrap().is_some(),
+        "Initial data should exist"
+    );
     // Verify some written data exists
-    assert!(table.get(&1000).unwrap().is_some(), "Written data should exist");
+    assert!(
+        table.get(&1000).unwrap().is_some(),
+        "Written data should exist"
+    );
+}
+
+// ============================================================================
+// File Handle Pool Tests
+// ============================================================================
+
+/// Test that a pool much smaller than the number of CFs recycles handles, failing only with
+/// the typed exhaustion error
+#[test]
+fn test_small_pool_many_cfs() {
+    use manifold::{StorageError, TransactionError};
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = Arc::new(
+        ColumnFamilyDatabase::builder()
+            .pool_size(2)
+            .open(temp_file.path())
+            .unwrap(),
+    );
+
+    let num_cfs = 10;
+    for i in 0..num_cfs {
+        db.create_column_family(format!("cf_{i}"), None).unwrap();
+    }
+
+    let barrier = Arc::new(Barrier::new(num_cfs));
+    let mut handles = vec![];
+    for i in 0..num_cfs {
+        let db_clone = Arc::clone(&db);
+        let barrier_clone = Arc::clone(&barrier);
+
+        handles.push(thread::spawn(move || {
+            barrier_clone.wait();
+            let cf = db_clone.column_family(&format!("cf_{i}")).unwrap();
+            for key in 0..20u64 {
+                let write_txn = match cf.begin_write() {
+                    Ok(txn) => txn,
+                    Err(TransactionError::Storage(StorageError::FileHandlesExhausted {
+                        pool_size,
+                        column_families,
+                        ..
+                    })) => {
+                        assert_eq!(pool_size, 2);
+                        assert_eq!(column_families, num_cfs);
+                        return;
+                    }
+                    Err(e) => panic!("unexpected error: {e}"),
+                };
+                let mut table = write_txn.open_table(TEST_TABLE).unwrap();
+                table.insert(&key, &"pooled").unwrap();
+                drop(table);
+                write_txn.commit().unwrap();
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let stats = db.file_handle_stats().unwrap();
+    assert_eq!(stats.max_size, 2);
+    assert_eq!(stats.column_families, num_cfs);
+    assert!(stats.in_use <= 2);
+    assert!(stats.peak <= 2);
+    assert!(stats.acquires >= num_cfs as u64);
+    assert!(stats.evictions >= (num_cfs - 2) as u64);
+
+    for i in 0..num_cfs {
+        let cf = db.column_family(&format!("cf_{i}")).unwrap();
+        let read_txn = cf.begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.get(&19).unwrap().unwrap().value(), "pooled");
+    }
+}
+
+/// Test that an auto-sized pool grows to one handle per CF, on open and on creation
+#[test]
+fn test_pool_size_auto() {
+    let temp_file = NamedTempFile::new().unwrap();
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .pool_size(2)
+            .pool_size_auto(true)
+            .open(temp_file.path())
+            .unwrap();
+        for i in 0..5 {
+            let cf = db.create_column_family(format!("cf_{i}"), None).unwrap();
+            let write_txn = cf.begin_write().unwrap();
+            write_txn.open_table(TEST_TABLE).unwrap();
+            write_txn.commit().unwrap();
+        }
+
+        let stats = db.file_handle_stats().unwrap();
+        assert_eq!(stats.max_size, 5);
+        assert_eq!(stats.in_use, 5);
+        assert_eq!(stats.evictions, 0);
+
+        // Deleting a column family does not shrink the pool
+        db.delete_column_family("cf_0").unwrap();
+        let stats = db.file_handle_stats().unwrap();
+        assert_eq!(stats.max_size, 5);
+        assert_eq!(stats.column_families, 4);
+    }
+
+    let db = ColumnFamilyDatabase::builder()
+        .pool_size(2)
+        .pool_size_auto(true)
+        .open(temp_file.path())
+        .unwrap();
+    assert_eq!(db.file_handle_stats().unwrap().max_size, 4);
+
+    // Without auto sizing the pool keeps its configured size
+    drop(db);
+    let db = ColumnFamilyDatabase::builder()
+        .pool_size(2)
+        .open(temp_file.path())
+        .unwrap();
+    assert_eq!(db.file_handle_stats().unwrap().max_size, 2);
+    assert!(
+        ColumnFamilyDatabase::builder()
+            .open_in_memory()
+            .unwrap()
+            .file_handle_stats()
+            .is_none()
+    );
 }
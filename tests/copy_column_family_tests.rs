@@ -0,0 +1,94 @@
+//! Tests of copying a column family
+
+use manifold::column_family::{ColumnFamily, ColumnFamilyDatabase, ColumnFamilyError};
+use manifold::{ReadableTable, TableDefinition};
+
+// Laid out like the raw table of a time series and the forward table of a graph
+const POINTS: TableDefinition<(u64, &str), f32> = TableDefinition::new("metrics_raw");
+const EDGES: TableDefinition<(&str, &str, &str), f32> = TableDefinition::new("edges_forward");
+
+/// Returns the points of `series_id` and the outgoing edges of `source` stored in `cf`.
+fn contents(cf: &ColumnFamily, series_id: &str, source: &str) -> (Vec<(u64, f32)>, Vec<String>) {
+    let txn = cf.begin_read().unwrap();
+    let points = txn
+        .open_table(POINTS)
+        .unwrap()
+        .iter()
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|(key, _)| key.value().1 == series_id)
+        .map(|(key, value)| (key.value().0, value.value()))
+        .collect();
+    let edges = txn
+        .open_table(EDGES)
+        .unwrap()
+        .iter()
+        .unwrap()
+        .map(Result::unwrap)
+        .filter(|(key, _)| key.value().0 == source)
+        .map(|(key, _)| key.value().2.to_string())
+        .collect();
+    (points, edges)
+}
+
+#[test]
+fn test_copy_is_a_frozen_snapshot() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = ColumnFamilyDatabase::open(temp_dir.path().join("copy.db")).unwrap();
+    let original = db.create_column_family("search_v1", None).unwrap();
+
+    {
+        let txn = original.begin_write().unwrap();
+        let mut points = txn.open_table(POINTS).unwrap();
+        for i in 0..1_000u16 {
+            points
+                .insert((u64::from(i) * 1_000, "cpu"), f32::from(i))
+                .unwrap();
+        }
+        drop(points);
+        let mut edges = txn.open_table(EDGES).unwrap();
+        edges.insert(("alice", "follows", "bob"), 1.0).unwrap();
+        drop(edges);
+        txn.commit().unwrap();
+    }
+    let before = contents(&original, "cpu", "alice");
+
+    let mut commits = 0;
+    let tables = db
+        .copy_column_family("search_v1", "search_v2", None, Some(&mut |_| commits += 1))
+        .unwrap();
+    assert!(commits >= 1);
+    assert_eq!(tables["metrics_raw"], 1_000);
+    assert_eq!(tables["edges_forward"], 1);
+
+    // Mutate the original after the copy
+    {
+        let txn = original.begin_write().unwrap();
+        let mut points = txn.open_table(POINTS).unwrap();
+        points.insert((5_000_000, "cpu"), -1.0).unwrap();
+        points
+            .retain(|(timestamp, _), _| timestamp >= 500_000)
+            .unwrap();
+        drop(points);
+        let mut edges = txn.open_table(EDGES).unwrap();
+        edges.insert(("alice", "follows", "carol"), 1.0).unwrap();
+        edges.remove(("alice", "follows", "bob")).unwrap();
+        drop(edges);
+        txn.commit().unwrap();
+    }
+
+    let copy = db.column_family("search_v2").unwrap();
+    assert_eq!(contents(&copy, "cpu", "alice"), before);
+    assert_ne!(contents(&original, "cpu", "alice"), before);
+
+    // The destination must not exist yet, and nothing is created for a missing source
+    assert!(matches!(
+        db.copy_column_family("search_v1", "search_v2", None, None),
+        Err(ColumnFamilyError::AlreadyExists(_))
+    ));
+    assert!(matches!(
+        db.copy_column_family("missing", "search_v3", None, None),
+        Err(ColumnFamilyError::NotFound(_))
+    ));
+    assert!(db.column_family("search_v3").is_err());
+}
@@ -13,8 +13,8 @@
 //! - Unix/Linux/macOS: Full crash injection via fork()
 //! - Windows/WASM: Tests compile but skip crash injection (graceful degradation)
 
-use manifold::column_family::ColumnFamilyDatabase;
-use manifold::{ReadableTable, ReadableTableMetadata, TableDefinition};
+use manifold::column_family::{ColumnFamilyDatabase, MasterHeader};
+use manifold::{Durability, ReadableTable, ReadableTableMetadata, TableDefinition};
 use std::collections::HashSet;
 use tempfile::NamedTempFile;
 
@@ -365,6 +365,281 @@ fn test_crash_recovery_interleaved_cf_writes() {
     }
 }
 
+/// Test that deleting a CF with un-checkpointed WAL entries, then reusing its space for a
+/// new CF, recovers cleanly after a crash
+#[test]
+#[cfg(unix)]
+fn test_crash_after_delete_and_space_reuse() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        db.create_column_family("doomed", Some(4 * 1024 * 1024))
+            .unwrap();
+
+        let doomed = db.column_family("doomed").unwrap();
+        for i in 0..20u64 {
+            let txn = doomed.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(TEST_TABLE).unwrap();
+                table.insert(&i, &"doomed_value").unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        db.delete_column_family("doomed").unwrap();
+
+        // A small CF that has to grow, picking up the freed segment
+        db.create_column_family("fresh", Some(64 * 1024)).unwrap();
+        let fresh = db.column_family("fresh").unwrap();
+        let value = "x".repeat(1024);
+        for i in 0..500u64 {
+            let txn = fresh.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(TEST_TABLE).unwrap();
+                table.insert(&i, value.as_str()).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        // Crash without running Drop
+        std::mem::forget(fresh);
+        std::mem::forget(doomed);
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    // "doomed" was the first CF, so its segment started right after the two header slots
+    let header_pages = std::fs::read(&db_path).unwrap();
+    let header = header_pages
+        .chunks(4096)
+        .take(2)
+        .filter_map(|slot| MasterHeader::from_bytes(slot).ok())
+        .max_by_key(|header| header.header_generation)
+        .unwrap();
+    assert!(
+        header.column_families[0]
+            .segments
+            .iter()
+            .any(|segment| segment.offset == 2 * 4096),
+        "fresh CF should have grown into the freed segment"
+    );
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    assert_eq!(db.list_column_families(), vec!["fresh".to_string()]);
+
+    let cf = db.column_family("fresh").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+
+    assert_eq!(table.len().unwrap(), 500);
+    for i in 0..500u64 {
+        assert_eq!(table.get(&i).unwrap().unwrap().value().len(), 1024);
+    }
+}
+
+/// Inserts `keys` in a single transaction, through the WAL or as a direct durable commit
+#[cfg(unix)]
+fn insert_range(
+    cf: &manifold::column_family::ColumnFamily,
+    keys: std::ops::Range<u64>,
+    value: &str,
+    logged: bool,
+) {
+    let txn = if logged {
+        cf.begin_write().unwrap()
+    } else {
+        cf.begin_write_unlogged().unwrap()
+    };
+    {
+        let mut table = txn.open_table(TEST_TABLE).unwrap();
+        for key in keys {
+            table.insert(&key, value).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+}
+
+/// Checks that every key in `keys` maps to `value`
+#[cfg(unix)]
+fn assert_range(
+    table: &impl ReadableTable<u64, &'static str>,
+    keys: std::ops::Range<u64>,
+    value: &str,
+) {
+    for key in keys {
+        assert_eq!(
+            table.get(&key).unwrap().unwrap().value(),
+            value,
+            "key {key}"
+        );
+    }
+}
+
+/// Test an unlogged commit landing between two logged ones, then a crash before checkpoint
+#[test]
+#[cfg(unix)]
+fn test_crash_after_interleaved_logged_and_unlogged_commits() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        db.create_column_family("test_cf", None).unwrap();
+        let cf = db.column_family("test_cf").unwrap();
+
+        insert_range(&cf, 0..200, "logged_before", true);
+        insert_range(&cf, 200..2000, "unlogged", false);
+        insert_range(&cf, 2000..2200, "logged_after", true);
+
+        // Crash without running Drop
+        std::mem::forget(cf);
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let cf = db.column_family("test_cf").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+
+    assert_eq!(table.len().unwrap(), 2200);
+    assert_range(&table, 0..200, "logged_before");
+    assert_range(&table, 200..2000, "unlogged");
+    assert_range(&table, 2000..2200, "logged_after");
+}
+
+/// Test that a logged commit followed by an unlogged one recovers both
+#[test]
+#[cfg(unix)]
+fn test_crash_after_unlogged_commit() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        db.create_column_family("test_cf", None).unwrap();
+        let cf = db.column_family("test_cf").unwrap();
+
+        insert_range(&cf, 0..200, "logged", true);
+        insert_range(&cf, 200..2000, "unlogged", false);
+
+        // Crash without running Drop
+        std::mem::forget(cf);
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let cf = db.column_family("test_cf").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+
+    assert_eq!(table.len().unwrap(), 2000);
+    assert_range(&table, 0..200, "logged");
+    assert_range(&table, 200..2000, "unlogged");
+}
+
+/// Test that a crash during a bulk load keeps whole chunks, up to at least the last synced one
+#[test]
+#[cfg(unix)]
+fn test_crash_during_bulk_load() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        db.create_column_family("test_cf", None).unwrap();
+        let cf = db.column_family("test_cf").unwrap();
+
+        // Chunk 3 is synced, chunks 4 and 5 are not; crash before chunk 6 is written
+        cf.bulk_loader(TEST_TABLE)
+            .chunk_rows(1000)
+            .sync_every(3)
+            .on_progress(|progress| {
+                if progress.chunks == 5 {
+                    process::exit(0);
+                }
+            })
+            .load((0..10_000u64).map(|key| (key, "bulk")))
+            .unwrap();
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let cf = db.column_family("test_cf").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+
+    let len = table.len().unwrap();
+    assert!((3000..=5000).contains(&len), "recovered {len} rows");
+    assert_eq!(len % 1000, 0, "recovered a partial chunk");
+    assert_range(&table, 0..len, "bulk");
+}
+
+/// Test that after checkpointing one column family, a crash recovers the other from the WAL
+/// and replays only the later commits of the checkpointed one
+#[test]
+#[cfg(unix)]
+fn test_crash_after_single_cf_checkpoint() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        let hot = db.create_column_family("hot", None).unwrap();
+        let cold = db.create_column_family("cold", None).unwrap();
+
+        for batch in 0..5 {
+            insert_range(&hot, batch * 100..(batch + 1) * 100, "hot", true);
+        }
+        insert_range(&cold, 0..100, "cold", true);
+
+        // Only the entries of "hot" are retired; "cold" stays in the WAL
+        db.checkpoint_cf("hot").unwrap();
+        insert_range(&cold, 100..200, "cold_after", true);
+        insert_range(&hot, 500..600, "hot_after", true);
+
+        // Crash without running Drop
+        std::mem::forget(hot);
+        std::mem::forget(cold);
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+
+    let hot = db.column_family("hot").unwrap();
+    let txn = hot.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 600);
+    assert_range(&table, 0..500, "hot");
+    assert_range(&table, 500..600, "hot_after");
+
+    let cold = db.column_family("cold").unwrap();
+    let txn = cold.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 200);
+    assert_range(&table, 0..100, "cold");
+    assert_range(&table, 100..200, "cold_after");
+}
+
 // ============================================================================
 // Data Integrity Verification Tests
 // ============================================================================
@@ -532,6 +807,544 @@ fn test_crash_recovery_large_transaction_count() {
     }
 }
 
+/// Test that a WAL of tens of thousands of entries is recovered in a single pass, with
+/// progress reported along the way
+#[test]
+#[cfg(unix)]
+fn test_crash_recovery_streams_large_wal() {
+    use manifold::column_family::{RecoveryProgress, WALConfig};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const COMMITS_PER_CF: u64 = 12_000;
+    const CFS: [&str; 2] = ["first", "second"];
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("large_wal.db");
+
+    // Keep every commit in the WAL until the crash
+    let wal_config = WALConfig {
+        checkpoint_interval: Duration::from_secs(3600),
+        max_wal_size: u64::MAX,
+        soft_wal_size_limit: u64::MAX,
+        hard_wal_size_limit: u64::MAX,
+        segment_size: None,
+    };
+
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder()
+            .wal_config(wal_config)
+            .open(&db_path)
+            .unwrap();
+        let cfs: Vec<_> = CFS
+            .iter()
+            .map(|name| db.create_column_family(*name, None).unwrap())
+            .collect();
+
+        for key in 0..COMMITS_PER_CF {
+            for cf in &cfs {
+                let mut txn = cf.begin_write().unwrap();
+                if key + 1 < COMMITS_PER_CF {
+                    txn.set_durability(Durability::Eventual).unwrap();
+                }
+                txn.open_table(TEST_TABLE)
+                    .unwrap()
+                    .insert(&key, "streamed")
+                    .unwrap();
+                txn.commit().unwrap();
+            }
+        }
+
+        // Crash without running Drop
+        std::mem::forget(cfs);
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    let wal_len = std::fs::metadata(db_path.with_extension("wal"))
+        .unwrap()
+        .len();
+    let reports: Arc<Mutex<Vec<RecoveryProgress>>> = Arc::default();
+    let db = {
+        let reports = Arc::clone(&reports);
+        ColumnFamilyDatabase::builder()
+            .on_recovery_progress(move |progress| reports.lock().unwrap().push(progress))
+            .open(&db_path)
+            .unwrap()
+    };
+
+    // Every entry and every byte is read exactly once, in order
+    let reports = reports.lock().unwrap();
+    assert!(reports.len() >= 3, "{} reports", reports.len());
+    for pair in reports.windows(2) {
+        assert!(pair[1].entries > pair[0].entries);
+        assert!(pair[1].bytes > pair[0].bytes);
+        assert!(pair[1].elapsed >= pair[0].elapsed);
+    }
+    let last = reports.last().unwrap();
+    assert_eq!(last.entries, COMMITS_PER_CF * CFS.len() as u64);
+    assert_eq!(last.bytes, wal_len);
+    assert!(reports.iter().all(|report| report.total_bytes == wal_len));
+
+    for name in CFS {
+        let txn = db.column_family(name).unwrap().begin_read().unwrap();
+        let table = txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), COMMITS_PER_CF);
+        assert_range(&table, 0..COMMITS_PER_CF, "streamed");
+    }
+    assert_eq!(db.wal_stats().unwrap().unwrap().pending_entries, 0);
+}
+
+/// Test that a segmented WAL rolls over into new files, that a checkpoint deletes the
+/// segments it made obsolete, and that recovery replays entries across segment boundaries
+#[test]
+#[cfg(unix)]
+fn test_crash_recovery_across_wal_segments() {
+    use manifold::column_family::WALConfig;
+    use std::time::Duration;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("segmented.db");
+    let segments = || {
+        let mut names: Vec<String> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.starts_with("segmented.wal."))
+            .collect();
+        names.sort();
+        names
+    };
+    let wal_config = WALConfig {
+        checkpoint_interval: Duration::from_secs(3600),
+        max_wal_size: u64::MAX,
+        soft_wal_size_limit: u64::MAX,
+        hard_wal_size_limit: u64::MAX,
+        segment_size: Some(4096),
+    };
+    let open = || {
+        ColumnFamilyDatabase::builder()
+            .wal_config(wal_config.clone())
+            .open(&db_path)
+            .unwrap()
+    };
+    let commit = |db: &ColumnFamilyDatabase, keys: std::ops::Range<u64>, value: &str| {
+        let cf = db.column_family_or_create("test_cf").unwrap();
+        for key in keys {
+            let txn = cf.begin_write().unwrap();
+            txn.open_table(TEST_TABLE)
+                .unwrap()
+                .insert(&key, value)
+                .unwrap();
+            txn.commit().unwrap();
+        }
+    };
+
+    {
+        let db = open();
+        commit(&db, 0..200, "checkpointed");
+        let before = segments();
+        assert!(before.len() >= 3, "{before:?}");
+
+        db.checkpoint().unwrap();
+        let after = segments();
+        assert_eq!(after.len(), 1);
+        assert!(!before.contains(&after[0]));
+    }
+
+    let is_parent = fork_and_crash(|| {
+        let db = open();
+        commit(&db, 200..400, "replayed");
+
+        // Crash without running Drop
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    assert!(segments().len() >= 3, "{:?}", segments());
+    let db = open();
+    let txn = db.column_family("test_cf").unwrap().begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 400);
+    assert_range(&table, 0..200, "checkpointed");
+    assert_range(&table, 200..400, "replayed");
+    assert_eq!(segments().len(), 1);
+}
+
+// ============================================================================
+// Damaged WAL Tail Tests
+// ============================================================================
+
+/// Makes `commits` logged commits to `test_cf`, inserting keys `1..=commits`, then crashes
+/// before checkpointing. Returns the WAL length before the last commit was appended.
+#[cfg(unix)]
+fn crash_after_logged_commits(db_path: &std::path::Path, commits: u64) -> u64 {
+    let len_path = db_path.with_extension("len");
+
+    fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(db_path).unwrap();
+        let cf = db.create_column_family("test_cf", None).unwrap();
+
+        for key in 1..=commits {
+            if key == commits {
+                let len = std::fs::metadata(db_path.with_extension("wal"))
+                    .unwrap()
+                    .len();
+                std::fs::write(&len_path, len.to_string()).unwrap();
+            }
+            insert_range(&cf, key..key + 1, "logged", true);
+        }
+
+        // Crash without running Drop
+        std::mem::forget(cf);
+        std::mem::forget(db);
+    });
+
+    std::fs::read_to_string(&len_path).unwrap().parse().unwrap()
+}
+
+/// Copies the crashed database at `source` to `dest`, with its WAL cut to `wal_len` bytes
+#[cfg(unix)]
+fn copy_with_wal_len(source: &std::path::Path, dest: &std::path::Path, wal_len: u64) {
+    std::fs::copy(source, dest).unwrap();
+    std::fs::copy(source.with_extension("wal"), dest.with_extension("wal")).unwrap();
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(dest.with_extension("wal"))
+        .unwrap()
+        .set_len(wal_len)
+        .unwrap();
+}
+
+/// Test that a WAL cut off inside its last entry recovers every entry before it
+#[test]
+#[cfg(unix)]
+fn test_recovery_discards_torn_wal_tail() {
+    const COMMITS: u64 = 5;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let crashed_path = temp_dir.path().join("crashed.db");
+    let last_entry_start = crash_after_logged_commits(&crashed_path, COMMITS);
+    let wal_len = std::fs::metadata(crashed_path.with_extension("wal"))
+        .unwrap()
+        .len();
+    let last_entry_len = wal_len - last_entry_start;
+
+    // Cut inside the length prefix, the entry data and the trailing CRC
+    for cut in [
+        1,
+        3,
+        4,
+        last_entry_len / 2,
+        last_entry_len - 4,
+        last_entry_len - 1,
+    ] {
+        let db_path = temp_dir.path().join(format!("cut_{cut}.db"));
+        copy_with_wal_len(&crashed_path, &db_path, last_entry_start + cut);
+
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        let stats = db.wal_stats().unwrap().unwrap();
+        assert!(stats.wal_size <= last_entry_start, "cut at {cut}");
+
+        let cf = db.column_family("test_cf").unwrap();
+        {
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(TEST_TABLE).unwrap();
+            assert_eq!(table.len().unwrap(), COMMITS - 1, "cut at {cut}");
+            assert_range(&table, 1..COMMITS, "logged");
+            assert!(table.get(&COMMITS).unwrap().is_none(), "cut at {cut}");
+        }
+
+        // The database keeps working after the repair
+        insert_range(&cf, 100..101, "after", true);
+        drop(cf);
+        drop(db);
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        let txn = db.column_family("test_cf").unwrap().begin_read().unwrap();
+        assert_eq!(txn.open_table(TEST_TABLE).unwrap().len().unwrap(), COMMITS);
+    }
+}
+
+/// Test that an entry failing its CRC ends recovery, and that a torn first entry leaves an
+/// empty WAL
+#[test]
+#[cfg(unix)]
+fn test_recovery_discards_corrupt_wal_entries() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+
+    let crashed_path = temp_dir.path().join("crashed.db");
+    let last_entry_start = crash_after_logged_commits(&crashed_path, 3);
+    let db_path = temp_dir.path().join("corrupt.db");
+    let wal_len = std::fs::metadata(crashed_path.with_extension("wal"))
+        .unwrap()
+        .len();
+    copy_with_wal_len(&crashed_path, &db_path, wal_len);
+    let mut wal = std::fs::read(db_path.with_extension("wal")).unwrap();
+    let middle = usize::try_from(last_entry_start + (wal_len - last_entry_start) / 2).unwrap();
+    wal[middle] ^= 0xff;
+    std::fs::write(db_path.with_extension("wal"), wal).unwrap();
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let txn = db.column_family("test_cf").unwrap().begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 2);
+    assert_range(&table, 1..3, "logged");
+
+    let crashed_path = temp_dir.path().join("single.db");
+    let header_len = crash_after_logged_commits(&crashed_path, 1);
+    let db_path = temp_dir.path().join("torn_first.db");
+    copy_with_wal_len(&crashed_path, &db_path, header_len + 10);
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    assert_eq!(db.wal_stats().unwrap().unwrap().wal_size, header_len);
+    let txn = db.column_family("test_cf").unwrap().begin_read().unwrap();
+    assert!(txn.open_table(TEST_TABLE).is_err());
+}
+
+/// Test that strict recovery refuses to open a database with a torn WAL tail
+#[test]
+#[cfg(unix)]
+fn test_strict_recovery_rejects_torn_wal_tail() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let crashed_path = temp_dir.path().join("crashed.db");
+    let last_entry_start = crash_after_logged_commits(&crashed_path, 3);
+    let db_path = temp_dir.path().join("torn.db");
+    let torn_len = last_entry_start + 10;
+    copy_with_wal_len(&crashed_path, &db_path, torn_len);
+
+    let result = ColumnFamilyDatabase::builder()
+        .strict_recovery(true)
+        .open(&db_path);
+    assert!(result.is_err());
+    let wal_len = std::fs::metadata(db_path.with_extension("wal"))
+        .unwrap()
+        .len();
+    assert_eq!(wal_len, torn_len);
+
+    // The default, lenient recovery still opens it
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let txn = db.column_family("test_cf").unwrap().begin_read().unwrap();
+    assert_eq!(txn.open_table(TEST_TABLE).unwrap().len().unwrap(), 2);
+}
+
+/// Test that a crash losing the unsynced tail of the WAL drops only eventual commits, and
+/// always recovers the state of a whole commit
+#[test]
+#[cfg(unix)]
+fn test_crash_loses_only_unsynced_eventual_commits() {
+    const IMMEDIATE: u64 = 5;
+    const EVENTUAL: u64 = 10;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let crashed_path = temp_dir.path().join("crashed.db");
+    let len_path = crashed_path.with_extension("len");
+
+    // Commit `n` inserts key `n` and records `n` under key 0
+    fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&crashed_path).unwrap();
+        let cf = db.create_column_family("test_cf", None).unwrap();
+
+        for n in 1..=IMMEDIATE + EVENTUAL {
+            if n == IMMEDIATE + 1 {
+                let len = std::fs::metadata(crashed_path.with_extension("wal"))
+                    .unwrap()
+                    .len();
+                std::fs::write(&len_path, len.to_string()).unwrap();
+            }
+            let mut txn = cf.begin_write().unwrap();
+            if n > IMMEDIATE {
+                txn.set_durability(Durability::Eventual).unwrap();
+            }
+            {
+                let mut table = txn.open_table(TEST_TABLE).unwrap();
+                table.insert(&0, n.to_string().as_str()).unwrap();
+                table.insert(&n, "committed").unwrap();
+            }
+            txn.commit().unwrap();
+        }
+
+        // Crash without running Drop
+        std::mem::forget(cf);
+        std::mem::forget(db);
+    });
+
+    let synced_len: u64 = std::fs::read_to_string(&len_path).unwrap().parse().unwrap();
+    let wal_len = std::fs::metadata(crashed_path.with_extension("wal"))
+        .unwrap()
+        .len();
+    assert!(wal_len > synced_len);
+
+    // Lose any part of the unsynced tail
+    let step = (wal_len - synced_len) / 16;
+    for lost in (0..16).map(|i| i * step).chain([1, wal_len - synced_len]) {
+        let db_path = temp_dir.path().join(format!("lost_{lost}.db"));
+        copy_with_wal_len(&crashed_path, &db_path, wal_len - lost);
+
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        let txn = db.column_family("test_cf").unwrap().begin_read().unwrap();
+        let table = txn.open_table(TEST_TABLE).unwrap();
+        let recovered: u64 = table.get(&0).unwrap().unwrap().value().parse().unwrap();
+
+        assert!(recovered >= IMMEDIATE, "lost {lost} bytes");
+        if lost == 0 {
+            assert_eq!(recovered, IMMEDIATE + EVENTUAL);
+        }
+        if lost == wal_len - synced_len {
+            assert_eq!(recovered, IMMEDIATE);
+        }
+        assert_eq!(table.len().unwrap(), recovered + 1, "lost {lost} bytes");
+        assert_range(&table, 1..recovered + 1, "committed");
+    }
+}
+
+/// Test that flushing makes eventual commits durable without a checkpoint
+#[test]
+#[cfg(unix)]
+fn test_crash_after_flush_recovers_eventual_commits() {
+    const FLUSHED: u64 = 10;
+    const UNFLUSHED: u64 = 5;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let crashed_path = temp_dir.path().join("crashed.db");
+    let len_path = crashed_path.with_extension("len");
+
+    fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&crashed_path).unwrap();
+        let cf = db.create_column_family("test_cf", None).unwrap();
+
+        for n in 1..=FLUSHED + UNFLUSHED {
+            let mut txn = cf.begin_write().unwrap();
+            txn.set_durability(Durability::Eventual).unwrap();
+            {
+                let mut table = txn.open_table(TEST_TABLE).unwrap();
+                table.insert(&n, "committed").unwrap();
+            }
+            txn.commit().unwrap();
+
+            if n == FLUSHED {
+                let sequence = cf.flush().unwrap();
+                let len = std::fs::metadata(crashed_path.with_extension("wal"))
+                    .unwrap()
+                    .len();
+                std::fs::write(&len_path, format!("{len} {sequence}")).unwrap();
+            }
+        }
+
+        // Crash without running Drop
+        std::mem::forget(cf);
+        std::mem::forget(db);
+    });
+
+    let recorded = std::fs::read_to_string(&len_path).unwrap();
+    let (flushed_len, sequence) = recorded.split_once(' ').unwrap();
+    let flushed_len: u64 = flushed_len.parse().unwrap();
+    assert!(sequence.parse::<u64>().unwrap() >= FLUSHED);
+
+    // Lose everything written after the flush
+    let db_path = temp_dir.path().join("flushed.db");
+    copy_with_wal_len(&crashed_path, &db_path, flushed_len);
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let cf = db.column_family("test_cf").unwrap();
+    {
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), FLUSHED);
+        assert_range(&table, 1..FLUSHED + 1, "committed");
+    }
+
+    // Flushing again covers the new commit, from either the column family or the database
+    insert_range(&cf, 100..101, "after", true);
+    let sequence = cf.flush().unwrap();
+    assert!(sequence > 0);
+    assert_eq!(db.flush_all().unwrap(), sequence);
+}
+
+// ============================================================================
+// Write Group Tests
+// ============================================================================
+
+/// Test that recovery replays a write group only if the entry completing it was written
+#[test]
+#[cfg(unix)]
+fn test_recovery_discards_incomplete_write_group() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let crashed_path = temp_dir.path().join("crashed.db");
+    let len_path = crashed_path.with_extension("len");
+
+    fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder().open(&crashed_path).unwrap();
+        let graph = db.create_column_family("graph", None).unwrap();
+        let properties = db.create_column_family("properties", None).unwrap();
+        insert_range(&graph, 0..10, "base", true);
+        insert_range(&properties, 0..10, "base", true);
+
+        let len = std::fs::metadata(crashed_path.with_extension("wal"))
+            .unwrap()
+            .len();
+        std::fs::write(&len_path, len.to_string()).unwrap();
+
+        let group = db.write_group(&[&properties, &graph]).unwrap();
+        for name in ["graph", "properties"] {
+            let mut table = group
+                .transaction(name)
+                .unwrap()
+                .open_table(TEST_TABLE)
+                .unwrap();
+            for key in 5..20 {
+                table.insert(&key, "grouped").unwrap();
+            }
+        }
+        group.commit().unwrap();
+
+        // Crash without running Drop
+        std::mem::forget(graph);
+        std::mem::forget(properties);
+        std::mem::forget(db);
+    });
+
+    // Follow the length prefixes of the group's entries: one per member, then the entry
+    // completing the group
+    let group_start: u64 = std::fs::read_to_string(&len_path).unwrap().parse().unwrap();
+    let wal = std::fs::read(crashed_path.with_extension("wal")).unwrap();
+    let mut entry_ends = vec![group_start];
+    while *entry_ends.last().unwrap() < wal.len() as u64 {
+        let start = *entry_ends.last().unwrap() as usize;
+        let len = u32::from_le_bytes(wal[start..start + 4].try_into().unwrap());
+        entry_ends.push(start as u64 + u64::from(len));
+    }
+    assert_eq!(entry_ends.len(), 4);
+
+    for (wal_len, complete) in [
+        (entry_ends[1], false),
+        (entry_ends[2], false),
+        (entry_ends[3] - 1, false),
+        (entry_ends[3], true),
+    ] {
+        let db_path = temp_dir.path().join(format!("cut_{wal_len}.db"));
+        copy_with_wal_len(&crashed_path, &db_path, wal_len);
+
+        let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+        for name in ["graph", "properties"] {
+            let txn = db.column_family(name).unwrap().begin_read().unwrap();
+            let table = txn.open_table(TEST_TABLE).unwrap();
+            assert_range(&table, 0..5, "base");
+            if complete {
+                assert_eq!(table.len().unwrap(), 20, "{name}");
+                assert_range(&table, 5..20, "grouped");
+            } else {
+                assert_eq!(table.len().unwrap(), 10, "{name} with {wal_len} WAL bytes");
+                assert_range(&table, 5..10, "base");
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Platform-Agnostic Verification Tests
 // ============================================================================
@@ -571,3 +1384,64 @@ fn test_recovery_clean_wal_state() {
         assert!(table.get(&i).unwrap().is_some());
     }
 }
+
+/// Test that an operation applied before a crash is not applied again after WAL recovery
+#[test]
+#[cfg(unix)]
+fn test_crash_recovery_keeps_applied_op_ids() {
+    use manifold::column_family::WALConfig;
+    use manifold::{OpOutcome, TableError, WriteTransaction};
+    use std::time::Duration;
+
+    const COUNTER: TableDefinition<&str, u64> = TableDefinition::new("counter");
+
+    fn increment(txn: &WriteTransaction, op_id: u128) -> OpOutcome<()> {
+        txn.idempotent(op_id, |txn| {
+            let mut table = txn.open_table(COUNTER)?;
+            let count = table.get("hits")?.map_or(0, |guard| guard.value());
+            table.insert("hits", count + 1)?;
+            Ok::<_, TableError>(())
+        })
+        .unwrap()
+    }
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("idempotent.db");
+
+    // Crash after the commit is in the WAL but before it is checkpointed
+    let is_parent = fork_and_crash(|| {
+        let db = ColumnFamilyDatabase::builder()
+            .wal_config(WALConfig {
+                checkpoint_interval: Duration::from_secs(3600),
+                ..WALConfig::default()
+            })
+            .open(&db_path)
+            .unwrap();
+        let cf = db.create_column_family("test_cf", None).unwrap();
+
+        let txn = cf.begin_write().unwrap();
+        assert_eq!(increment(&txn, 7), OpOutcome::Applied(()));
+        txn.commit().unwrap();
+
+        // Crash without running Drop
+        std::mem::forget(cf);
+        std::mem::forget(db);
+    });
+
+    if !is_parent {
+        return;
+    }
+
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    let cf = db.column_family("test_cf").unwrap();
+
+    // The retried operation is skipped, while a new one is applied
+    let txn = cf.begin_write().unwrap();
+    assert_eq!(increment(&txn, 7), OpOutcome::Replayed);
+    assert_eq!(increment(&txn, 8), OpOutcome::Applied(()));
+    txn.commit().unwrap();
+
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(COUNTER).unwrap();
+    assert_eq!(table.get("hits").unwrap().unwrap().value(), 2);
+}
@@ -0,0 +1,232 @@
+//! Encryption at rest tests
+//!
+//! Validates that column families created with a key are stored encrypted, both in the
+//! database file and in the WAL, that they read back after reopening with the same key,
+//! and that opening with a missing or wrong key fails cleanly.
+
+#![cfg(feature = "encryption")]
+
+use manifold::column_family::{ColumnFamilyDatabase, EncryptionKey, KeyProvider};
+use manifold::{ReadableTable, TableDefinition};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+const TABLE: TableDefinition<&str, &str> = TableDefinition::new("data");
+
+const SECRET: &str = "the launch codes are 0000-0000-0000";
+const PUBLIC: &str = "the cafeteria serves soup on tuesdays";
+
+fn key(byte: u8) -> EncryptionKey {
+    EncryptionKey::new([byte; 32])
+}
+
+fn write(db: &ColumnFamilyDatabase, cf: &str, key: &str, value: &str) {
+    let cf = db.column_family_or_create(cf).unwrap();
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(TABLE).unwrap();
+        table.insert(key, value).unwrap();
+    }
+    txn.commit().unwrap();
+}
+
+fn read(db: &ColumnFamilyDatabase, cf: &str, key: &str) -> Option<String> {
+    let cf = db.column_family(cf).unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TABLE).unwrap();
+    table
+        .get(key)
+        .unwrap()
+        .map(|value| value.value().to_string())
+}
+
+fn contains(path: &Path, needle: &str) -> bool {
+    let bytes = std::fs::read(path).unwrap_or_default();
+    bytes
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+#[test]
+fn test_encrypted_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.manifold");
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .cf_key("secrets", key(1))
+            .open(&path)
+            .unwrap();
+        for i in 0..200 {
+            write(
+                &db,
+                "secrets",
+                &format!("key-{i}"),
+                &format!("{SECRET} #{i}"),
+            );
+        }
+        write(&db, "public", "key", PUBLIC);
+        assert_eq!(
+            read(&db, "secrets", "key-7").unwrap(),
+            format!("{SECRET} #7")
+        );
+    }
+
+    let db = ColumnFamilyDatabase::builder()
+        .cf_key("secrets", key(1))
+        .open(&path)
+        .unwrap();
+    for i in 0..200 {
+        assert_eq!(
+            read(&db, "secrets", &format!("key-{i}")).unwrap(),
+            format!("{SECRET} #{i}")
+        );
+    }
+    assert_eq!(read(&db, "public", "key").unwrap(), PUBLIC);
+}
+
+#[test]
+fn test_raw_files_do_not_contain_plaintext() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.manifold");
+    let wal_path = path.with_extension("wal");
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .cf_key("secrets", key(1))
+            .open(&path)
+            .unwrap();
+        write(&db, "secrets", "key", SECRET);
+        write(&db, "public", "key", PUBLIC);
+
+        // Before a checkpoint the commits are only durable in the WAL
+        assert!(!contains(&path, SECRET));
+        assert!(!contains(&wal_path, SECRET));
+    }
+
+    assert!(!contains(&path, SECRET));
+    assert!(!contains(&wal_path, SECRET));
+    // The unencrypted column family is stored as plaintext
+    assert!(contains(&path, PUBLIC) || contains(&wal_path, PUBLIC));
+}
+
+#[test]
+fn test_wrong_key_fails() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.manifold");
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .cf_key("secrets", key(1))
+            .open(&path)
+            .unwrap();
+        write(&db, "secrets", "key", SECRET);
+    }
+
+    assert!(
+        ColumnFamilyDatabase::builder()
+            .cf_key("secrets", key(2))
+            .open(&path)
+            .is_err()
+    );
+
+    // The failed opens leave the data intact
+    let db = ColumnFamilyDatabase::builder()
+        .cf_key("secrets", key(1))
+        .open(&path)
+        .unwrap();
+    assert_eq!(read(&db, "secrets", "key").unwrap(), SECRET);
+}
+
+#[test]
+fn test_missing_key_fails() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.manifold");
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .cf_key("secrets", key(1))
+            .open(&path)
+            .unwrap();
+        write(&db, "secrets", "key", SECRET);
+        write(&db, "public", "key", PUBLIC);
+    }
+
+    assert!(ColumnFamilyDatabase::open(&path).is_err());
+
+    // A key for a column family created without one is rejected too
+    assert!(
+        ColumnFamilyDatabase::builder()
+            .cf_key("secrets", key(1))
+            .cf_key("public", key(3))
+            .open(&path)
+            .is_err()
+    );
+}
+
+/// Hands out keys by id, with the last one current
+struct RotatingKeys(Mutex<Vec<EncryptionKey>>);
+
+impl KeyProvider for RotatingKeys {
+    fn current_key(&self, cf_name: &str) -> Option<(u32, EncryptionKey)> {
+        if cf_name != "secrets" {
+            return None;
+        }
+        let keys = self.0.lock().unwrap();
+        Some((u32::try_from(keys.len() - 1).unwrap(), keys.last()?.clone()))
+    }
+
+    fn key(&self, cf_name: &str, key_id: u32) -> Option<EncryptionKey> {
+        if cf_name != "secrets" {
+            return None;
+        }
+        self.0.lock().unwrap().get(key_id as usize).cloned()
+    }
+}
+
+#[test]
+fn test_key_rotation() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.manifold");
+    let keys = Arc::new(RotatingKeys(Mutex::new(vec![key(1)])));
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .key_provider(keys.clone())
+            .open(&path)
+            .unwrap();
+        write(&db, "secrets", "old", SECRET);
+    }
+
+    keys.0.lock().unwrap().push(key(2));
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .key_provider(keys.clone())
+            .open(&path)
+            .unwrap();
+        write(&db, "secrets", "new", SECRET);
+        assert_eq!(read(&db, "secrets", "old").unwrap(), SECRET);
+    }
+
+    let db = ColumnFamilyDatabase::builder()
+        .key_provider(keys)
+        .open(&path)
+        .unwrap();
+    assert_eq!(read(&db, "secrets", "old").unwrap(), SECRET);
+    assert_eq!(read(&db, "secrets", "new").unwrap(), SECRET);
+}
+
+#[test]
+fn test_backup_of_encrypted_database_is_unsupported() {
+    let dir = TempDir::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .cf_key("secrets", key(1))
+        .open(dir.path().join("test.manifold"))
+        .unwrap();
+    write(&db, "secrets", "key", SECRET);
+
+    let backup = dir.path().join("backup.manifold");
+    assert!(db.backup_to(&backup).is_err());
+    assert!(!backup.exists());
+}
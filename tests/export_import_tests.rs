@@ -0,0 +1,426 @@
+use manifold::column_family::{ColumnFamilyDatabase, ColumnFamilyError, RedbImportOptions};
+use manifold::{
+    Database, Key, MultimapTableDefinition, ReadableTable, ReadableTableMetadata, TableDefinition,
+    Value,
+};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use tempfile::TempDir;
+
+// The same tables `TimeSeriesTable` creates for a series named "cpu"
+const RAW_TABLE: TableDefinition<(u64, &str), f32> = TableDefinition::new("cpu_raw");
+const MINUTE_TABLE: TableDefinition<(u64, &str), [u8; 24]> = TableDefinition::new("cpu_minute");
+const HOUR_TABLE: TableDefinition<(u64, &str), [u8; 24]> = TableDefinition::new("cpu_hour");
+const DAY_TABLE: TableDefinition<(u64, &str), [u8; 24]> = TableDefinition::new("cpu_day");
+
+const BLOB_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("blobs");
+const EMPTY_TABLE: TableDefinition<u64, u64> = TableDefinition::new("empty");
+
+fn aggregate(i: u64) -> [u8; 24] {
+    let mut bytes = [0; 24];
+    for (j, byte) in bytes.iter_mut().enumerate() {
+        *byte = (i as u8).wrapping_mul(31).wrapping_add(j as u8);
+    }
+    bytes
+}
+
+fn populate_timeseries(db: &ColumnFamilyDatabase, cf_name: &str) {
+    let cf = db.column_family_or_create(cf_name).unwrap();
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut raw = txn.open_table(RAW_TABLE).unwrap();
+        let mut minute = txn.open_table(MINUTE_TABLE).unwrap();
+        let mut hour = txn.open_table(HOUR_TABLE).unwrap();
+        let mut day = txn.open_table(DAY_TABLE).unwrap();
+        for series in ["host-a", "host-b", "a-much-longer-series-identifier"] {
+            for i in 0..20_000u64 {
+                let ts = 1_700_000_000_000 + i * 1000;
+                raw.insert((ts, series), i as f32 * 0.25).unwrap();
+                if i % 60 == 0 {
+                    minute.insert((ts, series), aggregate(i)).unwrap();
+                }
+                if i % 3600 == 0 {
+                    hour.insert((ts, series), aggregate(i + 1)).unwrap();
+                }
+                if i % 86_400 == 0 {
+                    day.insert((ts, series), aggregate(i + 2)).unwrap();
+                }
+            }
+        }
+        // Includes a NaN payload, which must survive the round trip bit for bit
+        raw.insert((0, "special"), f32::from_bits(0x7fc0_1234))
+            .unwrap();
+        txn.open_table(EMPTY_TABLE).unwrap();
+    }
+    txn.commit().unwrap();
+}
+
+fn table_bytes<K: Key + 'static, V: Value + 'static>(
+    db: &ColumnFamilyDatabase,
+    cf_name: &str,
+    definition: TableDefinition<K, V>,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let cf = db.column_family(cf_name).unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(definition).unwrap();
+    table
+        .iter()
+        .unwrap()
+        .map(|entry| {
+            let (key, value) = entry.unwrap();
+            (
+                K::as_bytes(&key.value()).as_ref().to_vec(),
+                V::as_bytes(&value.value()).as_ref().to_vec(),
+            )
+        })
+        .collect()
+}
+
+fn assert_same_timeseries(a: &ColumnFamilyDatabase, b: &ColumnFamilyDatabase, cf_name: &str) {
+    assert_eq!(
+        table_bytes(a, cf_name, RAW_TABLE),
+        table_bytes(b, cf_name, RAW_TABLE)
+    );
+    assert_eq!(
+        table_bytes(a, cf_name, MINUTE_TABLE),
+        table_bytes(b, cf_name, MINUTE_TABLE)
+    );
+    assert_eq!(
+        table_bytes(a, cf_name, HOUR_TABLE),
+        table_bytes(b, cf_name, HOUR_TABLE)
+    );
+    assert_eq!(
+        table_bytes(a, cf_name, DAY_TABLE),
+        table_bytes(b, cf_name, DAY_TABLE)
+    );
+    assert_eq!(
+        table_bytes(a, cf_name, EMPTY_TABLE),
+        table_bytes(b, cf_name, EMPTY_TABLE)
+    );
+}
+
+fn export_to_file(db: &ColumnFamilyDatabase, cf_name: &str, path: &std::path::Path) {
+    let writer = BufWriter::new(File::create(path).unwrap());
+    db.export_column_family(cf_name, writer).unwrap();
+}
+
+#[test]
+fn test_export_import_timeseries_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let export_path = dir.path().join("metrics.cfx");
+
+    let source = ColumnFamilyDatabase::open(dir.path().join("source.manifold")).unwrap();
+    populate_timeseries(&source, "metrics");
+    export_to_file(&source, "metrics", &export_path);
+
+    let dest_path = dir.path().join("dest.manifold");
+    let dest = ColumnFamilyDatabase::open(&dest_path).unwrap();
+    dest.create_column_family("other", None).unwrap();
+    dest.import_column_family("metrics", BufReader::new(File::open(&export_path).unwrap()))
+        .unwrap();
+    assert_same_timeseries(&source, &dest, "metrics");
+
+    let cf = dest.column_family("metrics").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let raw = txn.open_table(RAW_TABLE).unwrap();
+    assert_eq!(raw.len().unwrap(), 60_001);
+    assert_eq!(
+        raw.get((0, "special")).unwrap().unwrap().value().to_bits(),
+        0x7fc0_1234
+    );
+    assert_eq!(
+        raw.get((1_700_000_000_000 + 5000, "host-b"))
+            .unwrap()
+            .unwrap()
+            .value(),
+        1.25
+    );
+    drop(raw);
+    drop(txn);
+
+    // The imported tables accept further writes and survive a reopen
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut raw = txn.open_table(RAW_TABLE).unwrap();
+        raw.insert((1, "special"), 2.5).unwrap();
+        raw.remove((1_700_000_000_000, "host-a")).unwrap();
+    }
+    txn.commit().unwrap();
+    drop(cf);
+    drop(dest);
+
+    let dest = ColumnFamilyDatabase::open(&dest_path).unwrap();
+    let cf = dest.column_family("metrics").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let raw = txn.open_table(RAW_TABLE).unwrap();
+    assert_eq!(raw.len().unwrap(), 60_001);
+    assert_eq!(raw.get((1, "special")).unwrap().unwrap().value(), 2.5);
+    assert!(raw.get((1_700_000_000_000, "host-a")).unwrap().is_none());
+    assert_eq!(
+        table_bytes(&dest, "metrics", MINUTE_TABLE),
+        table_bytes(&source, "metrics", MINUTE_TABLE)
+    );
+}
+
+#[test]
+fn test_export_import_variable_width_tables() {
+    let dir = TempDir::new().unwrap();
+    let export_path = dir.path().join("blobs.cfx");
+
+    let source = ColumnFamilyDatabase::open(dir.path().join("source.manifold")).unwrap();
+    let cf = source.create_column_family("blobs", None).unwrap();
+    let txn = cf.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(BLOB_TABLE).unwrap();
+        for i in 0..5000u32 {
+            let key = format!("key-{i:08}").into_bytes();
+            let value = vec![(i % 251) as u8; (i as usize * 7) % 3000];
+            table.insert(key.as_slice(), value.as_slice()).unwrap();
+        }
+        table
+            .insert(b"".as_slice(), b"empty key".as_slice())
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    export_to_file(&source, "blobs", &export_path);
+
+    let dest = ColumnFamilyDatabase::open(dir.path().join("dest.manifold")).unwrap();
+    dest.import_column_family("copy", File::open(&export_path).unwrap())
+        .unwrap();
+
+    let expected = table_bytes(&source, "blobs", BLOB_TABLE);
+    assert_eq!(expected.len(), 5001);
+    assert_eq!(table_bytes(&dest, "copy", BLOB_TABLE), expected);
+
+    let cf = dest.column_family("copy").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(BLOB_TABLE).unwrap();
+    let range: Vec<_> = table
+        .range(b"key-00001000".as_slice()..b"key-00001010".as_slice())
+        .unwrap()
+        .map(|entry| entry.unwrap().0.value().to_vec())
+        .collect();
+    assert_eq!(range.len(), 10);
+    assert_eq!(range[0], b"key-00001000");
+}
+
+#[test]
+fn test_import_into_existing_column_family_fails() {
+    let dir = TempDir::new().unwrap();
+    let db = ColumnFamilyDatabase::open(dir.path().join("test.manifold")).unwrap();
+    populate_timeseries(&db, "metrics");
+
+    let mut export = vec![];
+    db.export_column_family("metrics", &mut export).unwrap();
+
+    let result = db.import_column_family("metrics", export.as_slice());
+    assert!(matches!(result, Err(ColumnFamilyError::AlreadyExists(_))));
+
+    // Deleting the column family first replaces it
+    db.delete_column_family("metrics").unwrap();
+    db.import_column_family("metrics", export.as_slice())
+        .unwrap();
+    let cf = db.column_family("metrics").unwrap();
+    let txn = cf.begin_read().unwrap();
+    assert_eq!(txn.open_table(RAW_TABLE).unwrap().len().unwrap(), 60_001);
+}
+
+#[test]
+fn test_import_rejects_invalid_streams() {
+    let dir = TempDir::new().unwrap();
+    let db = ColumnFamilyDatabase::open(dir.path().join("test.manifold")).unwrap();
+    populate_timeseries(&db, "metrics");
+
+    let mut export = vec![];
+    db.export_column_family("metrics", &mut export).unwrap();
+
+    let mut bad_magic = export.clone();
+    bad_magic[0] ^= 0xff;
+    assert!(matches!(
+        db.import_column_family("bad_magic", bad_magic.as_slice()),
+        Err(ColumnFamilyError::Io(_))
+    ));
+
+    let truncated = &export[..export.len() / 2];
+    assert!(matches!(
+        db.import_column_family("truncated", truncated),
+        Err(ColumnFamilyError::Io(_))
+    ));
+
+    // Only the checksum covers a changed value: the last byte of the last value, before the
+    // checksum of its table
+    let mut corrupted = export.clone();
+    let last_value_byte = corrupted.len() - 5;
+    corrupted[last_value_byte] ^= 0x01;
+    assert!(matches!(
+        db.import_column_family("corrupted", corrupted.as_slice()),
+        Err(ColumnFamilyError::Io(err)) if err.kind() == std::io::ErrorKind::InvalidData
+    ));
+
+    // Failed imports leave no column family behind
+    assert_eq!(db.list_column_families(), vec!["metrics".to_string()]);
+}
+
+#[test]
+fn test_export_rejects_multimap_tables() {
+    const TAGS: MultimapTableDefinition<u64, &str> = MultimapTableDefinition::new("tags");
+
+    let dir = TempDir::new().unwrap();
+    let db = ColumnFamilyDatabase::open(dir.path().join("test.manifold")).unwrap();
+    let cf = db.create_column_family("tagged", None).unwrap();
+    let txn = cf.begin_write().unwrap();
+    txn.open_multimap_table(TAGS)
+        .unwrap()
+        .insert(1, "a")
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut export = vec![];
+    assert!(db.export_column_family("tagged", &mut export).is_err());
+    assert!(db.export_column_family("missing", &mut export).is_err());
+}
+
+const USERS_TABLE: TableDefinition<u64, &str> = TableDefinition::new("users");
+const SCORES_TABLE: TableDefinition<&str, (u32, f64)> = TableDefinition::new("scores");
+
+fn create_redb_file(path: &std::path::Path) {
+    let db = Database::create(path).unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut users = txn.open_table(USERS_TABLE).unwrap();
+        for i in 0..1_000u64 {
+            users.insert(i, format!("user-{i}").as_str()).unwrap();
+        }
+        let mut scores = txn.open_table(SCORES_TABLE).unwrap();
+        for i in 0..50u32 {
+            scores
+                .insert(format!("player-{i:02}").as_str(), (i, f64::from(i) / 2.0))
+                .unwrap();
+        }
+    }
+    txn.commit().unwrap();
+}
+
+#[test]
+fn test_import_redb_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("source.redb");
+    create_redb_file(&source);
+
+    let db = ColumnFamilyDatabase::open(dir.path().join("test.manifold")).unwrap();
+    let mut reports = vec![];
+    let progress = db
+        .import_redb(
+            &source,
+            "imported",
+            RedbImportOptions::new()
+                .batch_entries(10)
+                .on_progress(|progress| reports.push(progress)),
+        )
+        .unwrap();
+    assert_eq!(progress.tables, 2);
+    assert_eq!(progress.total_tables, 2);
+    assert_eq!(progress.entries, 1_050);
+    assert_eq!(progress.total_entries, 1_050);
+    // Each table exceeds the batch size, so each is committed on its own
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports.last().unwrap().entries, 1_050);
+
+    let cf = db.column_family("imported").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let users = txn.open_table(USERS_TABLE).unwrap();
+    assert_eq!(users.len().unwrap(), 1_000);
+    for i in [0, 1, 500, 999] {
+        assert_eq!(users.get(i).unwrap().unwrap().value(), format!("user-{i}"));
+    }
+    let scores = txn.open_table(SCORES_TABLE).unwrap();
+    assert_eq!(scores.len().unwrap(), 50);
+    assert_eq!(scores.get("player-07").unwrap().unwrap().value(), (7, 3.5));
+    let (first, _) = scores.first().unwrap().unwrap();
+    assert_eq!(first.value(), "player-00");
+
+    // The imported tables accept further writes
+    drop(txn);
+    let txn = cf.begin_write().unwrap();
+    txn.open_table(USERS_TABLE)
+        .unwrap()
+        .insert(1_000, "user-1000")
+        .unwrap();
+    txn.commit().unwrap();
+    let txn = cf.begin_read().unwrap();
+    assert_eq!(txn.open_table(USERS_TABLE).unwrap().len().unwrap(), 1_001);
+}
+
+#[test]
+fn test_import_redb_overwrite() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("source.redb");
+    create_redb_file(&source);
+
+    let db = ColumnFamilyDatabase::open(dir.path().join("test.manifold")).unwrap();
+    populate_timeseries(&db, "imported");
+
+    let result = db.import_redb(&source, "imported", RedbImportOptions::new());
+    assert!(matches!(result, Err(ColumnFamilyError::AlreadyExists(_))));
+
+    db.import_redb(
+        &source,
+        "imported",
+        RedbImportOptions::new().overwrite(true),
+    )
+    .unwrap();
+    let cf = db.column_family("imported").unwrap();
+    let txn = cf.begin_read().unwrap();
+    assert_eq!(txn.open_table(USERS_TABLE).unwrap().len().unwrap(), 1_000);
+    assert!(txn.open_table(RAW_TABLE).is_err());
+}
+
+#[test]
+fn test_failed_import_redb_overwrite_keeps_existing_data() {
+    const TAGS: MultimapTableDefinition<u64, &str> = MultimapTableDefinition::new("tags");
+
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("source.redb");
+    create_redb_file(&source);
+    {
+        // Multimap tables cannot be imported, so the import fails
+        let source_db = Database::open(&source).unwrap();
+        let txn = source_db.begin_write().unwrap();
+        txn.open_multimap_table(TAGS)
+            .unwrap()
+            .insert(1, "tag")
+            .unwrap();
+        txn.commit().unwrap();
+    }
+
+    let path = dir.path().join("test.manifold");
+    let db = ColumnFamilyDatabase::open(&path).unwrap();
+    populate_timeseries(&db, "imported");
+    let expected = db.column_family("imported").unwrap().list_tables().unwrap();
+
+    let result = db.import_redb(
+        &source,
+        "imported",
+        RedbImportOptions::new().overwrite(true),
+    );
+    assert!(result.is_err());
+    assert_eq!(db.list_column_families(), ["imported"]);
+    drop(db);
+
+    // The existing column family is intact, also after reopening
+    let db = ColumnFamilyDatabase::open(&path).unwrap();
+    assert_eq!(db.list_column_families(), ["imported"]);
+    let cf = db.column_family("imported").unwrap();
+    assert_eq!(cf.list_tables().unwrap(), expected);
+    let txn = cf.begin_read().unwrap();
+    let raw = txn.open_table(RAW_TABLE).unwrap();
+    assert_eq!(raw.len().unwrap(), 60_001);
+    assert_eq!(
+        raw.get((1_700_000_000_000, "host-a"))
+            .unwrap()
+            .unwrap()
+            .value(),
+        0.0
+    );
+}
@@ -8,7 +8,7 @@
 //! - Recovery from abnormal shutdown
 
 use manifold::TableDefinition;
-use manifold::column_family::ColumnFamilyDatabase;
+use manifold::column_family::{ColumnFamilyDatabase, WALConfig};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
@@ -352,8 +352,6 @@ fn test_recovery_from_incomplete_checkpoint() {
 }
 
 /// Test that multiple rapid shutdown/reopen cycles don't corrupt data
-/// DISABLED: This test reveals a bug with rapid reopen causing page allocation errors
-/// or database corruption. Need to investigate checkpoint/recovery race condition.
 #[test]
 fn test_rapid_shutdown_reopen_cycles() {
     let temp_file = NamedTempFile::new().unwrap();
@@ -508,10 +506,7 @@ fn test_concurrent_shutdown() {
 // ============================================================================
 
 /// Test that manual checkpoint before shutdown works correctly
-/// DISABLED: This test reveals an issue where checkpoint doesn't properly preserve state
-/// After checkpoint + reopen, column family metadata appears to be lost
 #[test]
-#[ignore]
 fn test_manual_checkpoint_before_shutdown() {
     let temp_file = NamedTempFile::new().unwrap();
     let db_path = temp_file.path().to_path_buf();
@@ -560,6 +555,80 @@ fn test_manual_checkpoint_before_shutdown() {
     }
 }
 
+/// Test that manual checkpoints, the background checkpoint thread and the final checkpoint
+/// on drop never interleave: repeated write bursts, each followed by a manual checkpoint and
+/// a drop with the background thread checkpointing as often as it can, lose no committed data
+#[test]
+fn test_checkpoint_drop_reopen_stress() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    for cycle in 0..50u64 {
+        let db = ColumnFamilyDatabase::builder()
+            .pool_size(64)
+            .wal_config(WALConfig {
+                checkpoint_interval: Duration::from_millis(1),
+                ..WALConfig::default()
+            })
+            .open(&db_path)
+            .unwrap();
+        if cycle == 0 {
+            db.create_column_family("test_cf", None).unwrap();
+        }
+        let cf = db.column_family("test_cf").unwrap();
+
+        // Every key of the earlier cycles survived their drop and reopen
+        if cycle > 0 {
+            let txn = cf.begin_read().unwrap();
+            let table = txn.open_table(TEST_TABLE).unwrap();
+            for key in 0..cycle * 20 {
+                assert!(
+                    table.get(&key).unwrap().is_some(),
+                    "Key {key} lost before cycle {cycle}"
+                );
+            }
+        }
+
+        for i in 0..20 {
+            let txn = cf.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(TEST_TABLE).unwrap();
+                table.insert(&(cycle * 20 + i), &"burst").unwrap();
+            }
+            txn.commit().unwrap();
+        }
+        db.checkpoint().unwrap();
+
+        // A last commit after the manual checkpoint is left to the checkpoint on drop, while
+        // the column family handle still holds the checkpoint manager
+        let txn = cf.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TEST_TABLE).unwrap();
+            table
+                .insert(&(cycle * 20 + 19), &"after checkpoint")
+                .unwrap();
+        }
+        txn.commit().unwrap();
+        drop(db);
+        drop(cf);
+    }
+
+    let db = ColumnFamilyDatabase::builder()
+        .pool_size(64)
+        .open(&db_path)
+        .unwrap();
+    let cf = db.column_family("test_cf").unwrap();
+    let txn = cf.begin_read().unwrap();
+    let table = txn.open_table(TEST_TABLE).unwrap();
+    for key in 0..50 * 20 {
+        assert!(table.get(&key).unwrap().is_some(), "Key {key} lost");
+    }
+    assert_eq!(
+        table.get(&(49 * 20 + 19)).unwrap().unwrap().value(),
+        "after checkpoint"
+    );
+}
+
 // ============================================================================
 // Minimal Debugging Tests
 // ============================================================================
@@ -600,7 +669,6 @@ fn test_single_reopen_with_wal() {
 }
 
 /// Minimal test: Two rapid shutdown/reopen cycles with WAL
-/// DISABLED: Demonstrates the WAL replay bug - see test_rapid_shutdown_reopen_cycles
 #[test]
 fn test_two_reopen_cycles_with_wal() {
     let temp_file = NamedTempFile::new().unwrap();
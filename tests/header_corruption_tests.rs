@@ -6,12 +6,30 @@
 //! - CRC validation on all critical structures
 //! - Clear error messages for corruption scenarios
 //! - Recovery procedures
+//!
+//! The header is kept in two slots, the first two pages of the file. Corrupting only one of
+//! them leaves the other generation to open, so tests of detection corrupt both.
 
-use manifold::column_family::ColumnFamilyDatabase;
+use manifold::TableDefinition;
+use manifold::column_family::{ColumnFamilyDatabase, MasterHeader};
 use std::fs::OpenOptions;
 use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
 use tempfile::NamedTempFile;
 
+const PAGE_SIZE: u64 = 4096;
+
+/// Writes `bytes` at `offset` within each of the two header slots.
+fn corrupt_both_slots(db_path: &Path, offset: u64, bytes: &[u8]) {
+    let mut file = OpenOptions::new().write(true).open(db_path).unwrap();
+    for slot in 0..2 {
+        file.seek(SeekFrom::Start(slot * PAGE_SIZE + offset))
+            .unwrap();
+        file.write_all(bytes).unwrap();
+    }
+    file.sync_all().unwrap();
+}
+
 // ============================================================================
 // Master Header Corruption Tests
 // ============================================================================
@@ -29,13 +47,7 @@ fn test_corrupted_magic_number() {
     }
 
     // Corrupt magic number
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        file.seek(SeekFrom::Start(0)).unwrap();
-        file.write_all(b"CORRUPTED").unwrap();
-        file.sync_all().unwrap();
-    }
+    corrupt_both_slots(&db_path, 0, b"CORRUPTED");
 
     // Try to reopen - should detect corruption
     let result = ColumnFamilyDatabase::open(&db_path);
@@ -61,15 +73,8 @@ fn test_corrupted_header_crc() {
         db.create_column_family("test_cf", None).unwrap();
     }
 
-    // Corrupt some bytes in the header (not magic number)
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        // Corrupt version byte
-        file.seek(SeekFrom::Start(9)).unwrap();
-        file.write_all(&[0xFF]).unwrap();
-        file.sync_all().unwrap();
-    }
+    // Corrupt some bytes in the header (not magic number): the version byte
+    corrupt_both_slots(&db_path, 9, &[0xFF]);
 
     // Try to reopen - should detect CRC mismatch
     let result = ColumnFamilyDatabase::open(&db_path);
@@ -130,13 +135,7 @@ fn test_random_byte_corruption() {
     }
 
     // Corrupt random bytes in the middle of the header
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        file.seek(SeekFrom::Start(500)).unwrap();
-        file.write_all(&[0xDE, 0xAD, 0xBE, 0xEF]).unwrap();
-        file.sync_all().unwrap();
-    }
+    corrupt_both_slots(&db_path, 500, &[0xDE, 0xAD, 0xBE, 0xEF]);
 
     // Should detect corruption
     let result = ColumnFamilyDatabase::open(&db_path);
@@ -161,15 +160,9 @@ fn test_corrupted_cf_name_invalid_utf8() {
     }
 
     // Corrupt CF name with invalid UTF-8
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        // Find approximate location of CF name and corrupt it
-        // This is after magic (9) + version (1) + CF count (4) + CF name length (4)
-        file.seek(SeekFrom::Start(18)).unwrap();
-        file.write_all(&[0xFF, 0xFE, 0xFD]).unwrap();
-        file.sync_all().unwrap();
-    }
+    // This is after magic (9) + version (1) + header generation (8) + extension count (4) +
+    // active extension (1) + body length and checksum (8) + CF count (4) + CF name length (4)
+    corrupt_both_slots(&db_path, 39, &[0xFF, 0xFE, 0xFD]);
 
     // Should detect corruption (either via CRC or UTF-8 validation)
     let result = ColumnFamilyDatabase::open(&db_path);
@@ -212,13 +205,7 @@ fn test_corruption_error_messages_are_clear() {
     }
 
     // Corrupt header
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        file.seek(SeekFrom::Start(100)).unwrap();
-        file.write_all(&[0xAA; 50]).unwrap();
-        file.sync_all().unwrap();
-    }
+    corrupt_both_slots(&db_path, 100, &[0xAA; 50]);
 
     // Error message should be informative
     let result = ColumnFamilyDatabase::open(&db_path);
@@ -255,14 +242,8 @@ fn test_unsupported_version_error() {
         db.create_column_family("test_cf", None).unwrap();
     }
 
-    // Change version to unsupported value
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        file.seek(SeekFrom::Start(9)).unwrap(); // After magic number
-        file.write_all(&[99]).unwrap(); // Future version
-        file.sync_all().unwrap();
-    }
+    // Change version, after the magic number, to a future value
+    corrupt_both_slots(&db_path, 9, &[99]);
 
     // Should fail with version-related error
     let result = ColumnFamilyDatabase::open(&db_path);
@@ -275,34 +256,71 @@ fn test_unsupported_version_error() {
 // Recovery Tests
 // ============================================================================
 
-/// Test that uncorrupted data can still be read after header is fixed
+/// Test that a torn write of the newest header falls back to the previous generation
 #[test]
 fn test_data_survives_header_corruption() {
+    const TABLE: TableDefinition<u64, &str> = TableDefinition::new("data");
+
     let temp_file = NamedTempFile::new().unwrap();
     let db_path = temp_file.path().to_path_buf();
 
     // Create database and write data
     {
         let db = ColumnFamilyDatabase::open(&db_path).unwrap();
-        db.create_column_family("test_cf", None).unwrap();
-        // Data is in the CF's segment, not in the master header
+        let cf = db.create_column_family("test_cf", None).unwrap();
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(TABLE).unwrap().insert(&1, "kept").unwrap();
+        txn.commit().unwrap();
+
+        // The newest generation of the header records the metadata, the previous one only
+        // the column family
+        db.set_cf_metadata("test_cf", b"lost").unwrap();
     }
 
-    // Corrupt header (not the data regions)
+    // Tear the slot holding the newest generation, as a crash while writing it would
+    let bytes = std::fs::read(&db_path).unwrap();
+    let generation = |slot: u64| {
+        let start = (slot * PAGE_SIZE) as usize;
+        MasterHeader::from_bytes(&bytes[start..start + PAGE_SIZE as usize])
+            .unwrap()
+            .header_generation
+    };
+    let (newest, previous) = if generation(0) > generation(1) {
+        (0, 1)
+    } else {
+        (1, 0)
+    };
+    assert_eq!(generation(newest), generation(previous) + 1);
     {
         let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        file.seek(SeekFrom::Start(200)).unwrap();
-        file.write_all(&[0xBB; 20]).unwrap();
+        file.seek(SeekFrom::Start(newest * PAGE_SIZE + PAGE_SIZE / 2))
+            .unwrap();
+        file.write_all(&[0; PAGE_SIZE as usize / 2]).unwrap();
         file.sync_all().unwrap();
     }
 
-    // Database should refuse to open with corrupted header
-    let result = ColumnFamilyDatabase::open(&db_path);
-    assert!(result.is_err(), "Should detect header corruption");
+    // Data is in the CF's segment, not in the master header, and the previous generation
+    // still locates it
+    {
+        let db = ColumnFamilyDatabase::open(&db_path).expect("should fall back to previous header");
+        assert_eq!(db.list_column_families(), vec!["test_cf".to_string()]);
+        let cf = db.column_family("test_cf").unwrap();
+        assert!(cf.metadata().unwrap().user_metadata.is_empty());
+        let txn = cf.begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        assert_eq!(table.get(&1).unwrap().unwrap().value(), "kept");
+        drop(table);
+        drop(txn);
+
+        // The next header is written over the torn slot
+        db.set_cf_metadata("test_cf", b"rewritten").unwrap();
+    }
 
-    // Note: In a production system with backup headers, we could recover here
-    // For now, we just verify that corruption is detected
+    let bytes = std::fs::read(&db_path).unwrap();
+    for slot in 0..2 {
+        let start = slot * PAGE_SIZE as usize;
+        assert!(MasterHeader::from_bytes(&bytes[start..start + PAGE_SIZE as usize]).is_ok());
+    }
 }
 
 /// Test that creating a new database over a corrupted file works
@@ -317,13 +335,8 @@ fn test_recover_by_recreating() {
         db.create_column_family("test_cf", None).unwrap();
     }
 
-    {
-        let mut file = OpenOptions::new().write(true).open(&db_path).unwrap();
-
-        file.seek(SeekFrom::Start(0)).unwrap();
-        file.write_all(&[0xFF; 4096]).unwrap(); // Corrupt entire header
-        file.sync_all().unwrap();
-    }
+    // Corrupt entire header
+    corrupt_both_slots(&db_path, 0, &[0xFF; 4096]);
 
     // Cannot open corrupted database
     assert!(ColumnFamilyDatabase::open(&db_path).is_err());
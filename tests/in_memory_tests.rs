@@ -0,0 +1,121 @@
+use manifold::column_family::ColumnFamilyDatabase;
+use manifold::{ReadableTableMetadata, TableDefinition};
+use tempfile::TempDir;
+
+const USERS_TABLE: TableDefinition<u64, &str> = TableDefinition::new("users");
+const EVENTS_TABLE: TableDefinition<u64, u64> = TableDefinition::new("events");
+const BLOBS_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("blobs");
+
+fn write_and_read_two_column_families(db: &ColumnFamilyDatabase) {
+    let users = db.column_family_or_create("users").unwrap();
+    let events = db.column_family_or_create("events").unwrap();
+
+    let txn = users.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(USERS_TABLE).unwrap();
+        table.insert(1, "alice").unwrap();
+        table.insert(2, "bob").unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = events.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(EVENTS_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i * 2).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let txn = users.begin_read().unwrap();
+    let table = txn.open_table(USERS_TABLE).unwrap();
+    assert_eq!(table.get(1).unwrap().unwrap().value(), "alice");
+    assert_eq!(table.get(2).unwrap().unwrap().value(), "bob");
+
+    let txn = events.begin_read().unwrap();
+    let table = txn.open_table(EVENTS_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 1000);
+    assert_eq!(table.get(999).unwrap().unwrap().value(), 1998);
+
+    // Each column family only sees its own tables
+    let txn = users.begin_read().unwrap();
+    assert!(txn.open_table(EVENTS_TABLE).is_err());
+}
+
+#[test]
+fn test_in_memory_write_read_across_column_families() {
+    let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+    assert!(db.path().is_none());
+
+    write_and_read_two_column_families(&db);
+
+    let mut families = db.list_column_families();
+    families.sort();
+    assert_eq!(families, vec!["events".to_string(), "users".to_string()]);
+
+    db.checkpoint().unwrap();
+    let stats = db.wal_stats().unwrap().unwrap();
+    assert_eq!(stats.pending_entries, 0);
+}
+
+#[test]
+fn test_in_memory_without_wal() {
+    let db = ColumnFamilyDatabase::builder()
+        .without_wal()
+        .open_in_memory()
+        .unwrap();
+    assert!(db.wal_stats().unwrap().is_none());
+
+    write_and_read_two_column_families(&db);
+
+    db.delete_column_family("events").unwrap();
+    assert_eq!(db.list_column_families(), vec!["users".to_string()]);
+}
+
+#[test]
+fn test_in_memory_column_family_grows_past_initial_partition() {
+    let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+    let blobs = db.column_family_or_create("blobs").unwrap();
+    write_and_read_two_column_families(&db);
+
+    // Well past the initial in-memory partition size, so new segments must be allocated
+    // after the partitions of the other column families
+    let value = vec![7u8; 4096];
+    for batch in 0..4u64 {
+        let txn = blobs.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(BLOBS_TABLE).unwrap();
+            for i in 0..1000 {
+                table.insert(batch * 1000 + i, value.as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+
+    let txn = blobs.begin_read().unwrap();
+    let table = txn.open_table(BLOBS_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 4000);
+    assert_eq!(table.get(3999).unwrap().unwrap().value(), value.as_slice());
+
+    let users = db.column_family("users").unwrap();
+    let txn = users.begin_read().unwrap();
+    let table = txn.open_table(USERS_TABLE).unwrap();
+    assert_eq!(table.get(1).unwrap().unwrap().value(), "alice");
+}
+
+#[test]
+fn test_in_memory_drop_does_not_touch_filesystem() {
+    // Run from an empty directory so that any file created relative to the working
+    // directory, including on drop, would show up in it.
+    let dir = TempDir::new().unwrap();
+    let original_dir = std::env::current_dir().unwrap();
+    std::env::set_current_dir(dir.path()).unwrap();
+
+    {
+        let db = ColumnFamilyDatabase::open_in_memory().unwrap();
+        write_and_read_two_column_families(&db);
+    }
+
+    std::env::set_current_dir(original_dir).unwrap();
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,79 @@
+//! Harness shared by the `compat_tests` suites of the workspace
+//!
+//! Each suite includes this file with `#[path]`, so `CARGO_MANIFEST_DIR` and
+//! `CARGO_PKG_VERSION` are those of the crate under test, and its golden files live in that
+//! crate's `tests/golden/`. See `docs/on_disk_format.md` at the root of the repository.
+
+use std::path::{Path, PathBuf};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+/// Copies the database at `source`, with its WAL if it has one, to `dir` and returns the path
+/// of the copy
+fn copy_database(source: &Path, dir: &Path) -> PathBuf {
+    let path = dir.join("golden.db");
+    std::fs::copy(source, &path).unwrap();
+    let wal = source.with_extension("wal");
+    if wal.exists() {
+        std::fs::copy(wal, path.with_extension("wal")).unwrap();
+    }
+    path
+}
+
+/// Returns the golden files whose names start with `prefix`, oldest first
+///
+/// Panics if there are none, so that a suite never passes without reading a file written by
+/// an earlier version.
+fn golden_files(prefix: &str) -> Vec<PathBuf> {
+    let entries = std::fs::read_dir(GOLDEN_DIR)
+        .unwrap_or_else(|e| panic!("cannot read golden files from {GOLDEN_DIR}: {e}"));
+    let mut files: Vec<PathBuf> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with(prefix) && name.ends_with(".db")
+        })
+        .collect();
+    assert!(
+        !files.is_empty(),
+        "no {prefix}*.db golden files in {GOLDEN_DIR}"
+    );
+    files.sort();
+    files
+}
+
+/// Checks a copy of every golden file with `check`
+pub fn check_golden_files(prefix: &str, check: impl Fn(&Path)) {
+    for golden in golden_files(prefix) {
+        let dir = tempfile::tempdir().unwrap();
+        check(&copy_database(&golden, dir.path()));
+    }
+}
+
+/// Writes the fixture with `write` and checks a copy of it with `check`, so that `check` is
+/// known to accept the file that will be blessed
+pub fn check_fixture_round_trips(write: impl FnOnce(&Path), check: impl FnOnce(&Path)) {
+    let written = tempfile::tempdir().unwrap();
+    let path = written.path().join("fixture.db");
+    write(&path);
+
+    let dir = tempfile::tempdir().unwrap();
+    check(&copy_database(&path, dir.path()));
+}
+
+/// Writes the golden file of the current version with `write`
+pub fn bless_golden_file(prefix: &str, write: impl FnOnce(&Path)) {
+    let golden = Path::new(GOLDEN_DIR).join(format!("{prefix}{}.db", env!("CARGO_PKG_VERSION")));
+    assert!(!golden.exists(), "{} already exists", golden.display());
+
+    let written = tempfile::tempdir().unwrap();
+    let path = written.path().join("fixture.db");
+    write(&path);
+
+    std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+    std::fs::copy(&path, &golden).unwrap();
+    let wal = path.with_extension("wal");
+    if wal.exists() {
+        std::fs::copy(wal, golden.with_extension("wal")).unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+//! Tests of the spans emitted with the `tracing` feature.
+
+#![cfg(feature = "tracing")]
+
+use manifold::TableDefinition;
+use manifold::column_family::{ColumnFamilyDatabase, WALConfig};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Subscriber, subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+const TABLE: TableDefinition<u64, u64> = TableDefinition::new("data");
+
+/// A span as seen by [`Capture`].
+#[derive(Debug, Clone)]
+struct CapturedSpan {
+    name: &'static str,
+    parent: Option<Id>,
+    fields: HashMap<&'static str, String>,
+}
+
+impl Visit for CapturedSpan {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields.insert(field.name(), format!("{value:?}"));
+    }
+}
+
+/// Records every span with its parent and fields.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<(Id, CapturedSpan)>>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut span = CapturedSpan {
+            name: attrs.metadata().name(),
+            parent: ctx.span(id).unwrap().parent().map(|parent| parent.id()),
+            fields: HashMap::new(),
+        };
+        attrs.record(&mut span);
+        self.0.lock().unwrap().push((id.clone(), span));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut spans = self.0.lock().unwrap();
+        let (_, span) = spans.iter_mut().find(|(span_id, _)| span_id == id).unwrap();
+        values.record(span);
+    }
+}
+
+impl Capture {
+    /// Returns the only span named `name`.
+    fn single(&self, name: &str) -> (Id, CapturedSpan) {
+        let spans = self.0.lock().unwrap();
+        let mut found = spans.iter().filter(|(_, span)| span.name == name);
+        let span = found.next().unwrap_or_else(|| panic!("no {name} span"));
+        assert!(found.next().is_none(), "more than one {name} span");
+        span.clone()
+    }
+}
+
+#[test]
+fn test_logged_commit_spans() {
+    let dir = TempDir::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .wal_config(WALConfig {
+            checkpoint_interval: Duration::from_secs(3600),
+            ..WALConfig::default()
+        })
+        .open(dir.path().join("traced.db"))
+        .unwrap();
+    let cf = db.create_column_family("events", Some(1 << 20)).unwrap();
+
+    let capture = Capture::default();
+    let subscriber = Registry::default().with(capture.clone());
+    subscriber::with_default(subscriber, || {
+        let txn = cf.begin_write().unwrap();
+        txn.open_table(TABLE).unwrap().insert(1, 10).unwrap();
+        txn.commit().unwrap();
+    });
+
+    let (txn_id, txn) = capture.single("write_transaction");
+    assert_eq!(txn.fields["cf"], "\"events\"");
+    assert_eq!(txn.parent, None);
+
+    let (commit_id, commit) = capture.single("commit");
+    assert_eq!(commit.parent, Some(txn_id));
+
+    let (_, append) = capture.single("wal_append");
+    assert_eq!(append.parent, Some(commit_id.clone()));
+    assert_eq!(append.fields["cf"], "\"events\"");
+    let sequence = &append.fields["wal_sequence"];
+
+    let (sync_id, sync) = capture.single("wal_sync");
+    assert_eq!(sync.parent, Some(commit_id));
+    assert_eq!(sync.fields["cf"], "\"events\"");
+    assert_eq!(&sync.fields["wal_sequence"], sequence);
+
+    // The commit is alone, so it leads the group commit and its fsync covers its own entry
+    let (_, fsync) = capture.single("wal_fsync");
+    assert_eq!(fsync.parent, Some(sync_id));
+    assert_eq!(&fsync.fields["wal_sequence"], sequence);
+    assert!(fsync.fields["batch_size"].parse::<u64>().unwrap() >= 1);
+}
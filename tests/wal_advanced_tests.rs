@@ -1,9 +1,12 @@
 // Advanced WAL tests covering error conditions, recovery, and edge cases
 
-use manifold::column_family::ColumnFamilyDatabase;
-use manifold::TableDefinition;
+use manifold::column_family::{ColumnFamilyDatabase, WALConfig};
+use manifold::{Durability, ReadableTableMetadata, TableDefinition};
 use std::fs::{self, OpenOptions};
 use std::io::{Seek, SeekFrom, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use tempfile::NamedTempFile;
 
 const TEST_TABLE: TableDefinition<u64, &str> = TableDefinition::new("test");
@@ -52,22 +55,21 @@ fn test_wal_invalid_magic_number() {
     // The system should either:
     // 1. Detect corruption and refuse to open (correct behavior)
     // 2. Detect corruption and recreate/ignore the WAL (also acceptable)
-    let db_result = ColumnFamilyDatabase::builder()
-        .pool_size(64)
-        .open(&db_path);
+    let db_result = ColumnFamilyDatabase::builder().pool_size(64).open(&db_path);
 
     // If the database opens, the previously committed data should still be accessible
     // (it was flushed before we corrupted the WAL)
     if let Ok(db) = db_result
-        && let Ok(cf) = db.column_family("test_cf") {
-            let read_txn = cf.begin_read().unwrap();
-            let table = read_txn.open_table(TEST_TABLE).unwrap();
-            // Data committed before corruption should be readable
-            assert_eq!(
-                table.get(&1).unwrap().unwrap().value(),
-                "persisted_before_corruption"
-            );
-        }
+        && let Ok(cf) = db.column_family("test_cf")
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        // Data committed before corruption should be readable
+        assert_eq!(
+            table.get(&1).unwrap().unwrap().value(),
+            "persisted_before_corruption"
+        );
+    }
     // If the database refuses to open due to corruption, that's also valid behavior
 }
 
@@ -114,20 +116,16 @@ fn test_wal_unsupported_version() {
     }
 
     // System should handle unsupported version
-    let db_result = ColumnFamilyDatabase::builder()
-        .pool_size(64)
-        .open(&db_path);
+    let db_result = ColumnFamilyDatabase::builder().pool_size(64).open(&db_path);
 
     // If opens, previously persisted data should be accessible
     if let Ok(db) = db_result
-        && let Ok(cf) = db.column_family("test_cf") {
-            let read_txn = cf.begin_read().unwrap();
-            let table = read_txn.open_table(TEST_TABLE).unwrap();
-            assert_eq!(
-                table.get(&2).unwrap().unwrap().value(),
-                "persisted_data"
-            );
-        }
+        && let Ok(cf) = db.column_family("test_cf")
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.get(&2).unwrap().unwrap().value(), "persisted_data");
+    }
     // Refusing to open is also acceptable
 }
 
@@ -177,20 +175,16 @@ fn test_wal_header_crc_mismatch() {
     }
 
     // System should detect CRC mismatch
-    let db_result = ColumnFamilyDatabase::builder()
-        .pool_size(64)
-        .open(&db_path);
+    let db_result = ColumnFamilyDatabase::builder().pool_size(64).open(&db_path);
 
     // If opens, verify data integrity
     if let Ok(db) = db_result
-        && let Ok(cf) = db.column_family("test_cf") {
-            let read_txn = cf.begin_read().unwrap();
-            let table = read_txn.open_table(TEST_TABLE).unwrap();
-            assert_eq!(
-                table.get(&3).unwrap().unwrap().value(),
-                "crc_test_data"
-            );
-        }
+        && let Ok(cf) = db.column_family("test_cf")
+    {
+        let read_txn = cf.begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.get(&3).unwrap().unwrap().value(), "crc_test_data");
+    }
     // Refusing to open due to CRC failure is also valid
 }
 
@@ -287,10 +281,7 @@ fn test_wal_manual_checkpoint() {
     let read_txn = cf.begin_read().unwrap();
     let table = read_txn.open_table(TEST_TABLE).unwrap();
     for i in 0..20 {
-        assert_eq!(
-            table.get(&i).unwrap().unwrap().value(),
-            "checkpoint_test"
-        );
+        assert_eq!(table.get(&i).unwrap().unwrap().value(), "checkpoint_test");
     }
 }
 
@@ -330,11 +321,7 @@ fn test_wal_crash_recovery() {
     let table = read_txn.open_table(TEST_TABLE).unwrap();
     for i in 0..50 {
         let value = table.get(&i).unwrap();
-        assert!(
-            value.is_some(),
-            "Key {} should exist after recovery",
-            i
-        );
+        assert!(value.is_some(), "Key {} should exist after recovery", i);
         assert_eq!(value.unwrap().value(), "crash_recovery_test");
     }
 }
@@ -449,6 +436,45 @@ fn test_wal_concurrent_small_transactions() {
     }
 }
 
+/// Test that a checkpoint does not roll back column families that are already durable
+#[test]
+fn test_checkpoint_keeps_durable_column_families() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let write = |db: &ColumnFamilyDatabase, cf_name: &str, key: u64| {
+        let cf = db.column_family_or_create(cf_name).unwrap();
+        let write_txn = cf.begin_write().unwrap();
+        write_txn
+            .open_table(TEST_TABLE)
+            .unwrap()
+            .insert(&key, &"durable")
+            .unwrap();
+        write_txn.commit().unwrap();
+    };
+    let assert_present = |db: &ColumnFamilyDatabase, cf_name: &str, key: u64| {
+        let cf = db.column_family(cf_name).unwrap();
+        let read_txn = cf.begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.get(&key).unwrap().unwrap().value(), "durable");
+    };
+
+    {
+        let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+        write(&db, "cf_a", 1);
+        db.checkpoint().unwrap();
+
+        // cf_a has nothing new, so this checkpoint must leave it at its durable state
+        write(&db, "cf_b", 2);
+        db.checkpoint().unwrap();
+        assert_present(&db, "cf_a", 1);
+    }
+
+    let db = ColumnFamilyDatabase::open(&db_path).unwrap();
+    assert_present(&db, "cf_a", 1);
+    assert_present(&db, "cf_b", 2);
+}
+
 /// Test disabled WAL (pool_size = 0)
 #[test]
 fn test_disabled_wal() {
@@ -480,6 +506,133 @@ fn test_disabled_wal() {
     let read_txn = cf.begin_read().unwrap();
     let table = read_txn.open_table(TEST_TABLE).unwrap();
     assert_eq!(table.get(&1).unwrap().unwrap().value(), "no_wal");
+
+    assert!(db.wal_stats().unwrap().is_none());
+}
+
+/// Test that WAL stats follow writes and checkpoints
+#[test]
+fn test_wal_stats_track_checkpoints() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    let db = ColumnFamilyDatabase::builder()
+        .pool_size(64)
+        .open(&db_path)
+        .unwrap();
+    db.create_column_family("cf_a", None).unwrap();
+    db.create_column_family("cf_b", None).unwrap();
+
+    let initial = db.wal_stats().unwrap().unwrap();
+    assert_eq!(initial.pending_entries, 0);
+    assert_eq!(initial.checkpoints, 0);
+    assert!(initial.last_checkpoint_at.is_none());
+    assert!(initial.last_checkpoint_duration.is_none());
+
+    for (name, count) in [("cf_a", 10u64), ("cf_b", 5)] {
+        let cf = db.column_family(name).unwrap();
+        for i in 0..count {
+            let write_txn = cf.begin_write().unwrap();
+            let mut table = write_txn.open_table(TEST_TABLE).unwrap();
+            table.insert(&i, &"stats").unwrap();
+            drop(table);
+            write_txn.commit().unwrap();
+        }
+    }
+
+    let before = db.wal_stats().unwrap().unwrap();
+    assert_eq!(before.pending_entries, 15);
+    assert_eq!(before.pending_entries_by_cf.get("cf_a"), Some(&10));
+    assert_eq!(before.pending_entries_by_cf.get("cf_b"), Some(&5));
+    assert!(before.wal_size > initial.wal_size);
+
+    db.checkpoint().unwrap();
+
+    let after = db.wal_stats().unwrap().unwrap();
+    assert_eq!(after.pending_entries, 0);
+    assert!(after.pending_entries_by_cf.is_empty());
+    assert_eq!(after.checkpoints, 1);
+    assert_eq!(after.last_checkpoint_entries, 15);
+    assert_eq!(after.entries_checkpointed, 15);
+    assert!(after.wal_size < before.wal_size);
+    assert!(after.last_checkpoint_at.is_some());
+    assert!(after.last_checkpoint_duration.is_some());
+
+    // A checkpoint with nothing pending is not counted
+    db.checkpoint().unwrap();
+    assert_eq!(db.wal_stats().unwrap().unwrap().checkpoints, 1);
+}
+
+/// Test that WAL backpressure keeps the WAL bounded when checkpoints can't keep up
+#[test]
+fn test_wal_backpressure_bounds_wal_size() {
+    const SOFT_LIMIT: u64 = 16 * 1024;
+    const HARD_LIMIT: u64 = 64 * 1024;
+    const THREADS: u64 = 4;
+    const COMMITS_PER_THREAD: u64 = 100;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+    let wal_path = db_path.with_extension("wal");
+
+    // The background checkpoint never fires, so only backpressure can shrink the WAL
+    let db = Arc::new(
+        ColumnFamilyDatabase::builder()
+            .wal_config(WALConfig {
+                checkpoint_interval: Duration::from_secs(3600),
+                max_wal_size: u64::MAX,
+                soft_wal_size_limit: SOFT_LIMIT,
+                hard_wal_size_limit: HARD_LIMIT,
+                segment_size: None,
+            })
+            .open(&db_path)
+            .unwrap(),
+    );
+    for t in 0..THREADS {
+        db.create_column_family(format!("cf_{t}"), None).unwrap();
+    }
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let db = Arc::clone(&db);
+            let wal_path = wal_path.clone();
+            thread::spawn(move || {
+                let cf = db.column_family(&format!("cf_{t}")).unwrap();
+                let mut max_size = 0;
+                for i in 0..COMMITS_PER_THREAD {
+                    let write_txn = cf.begin_write().unwrap();
+                    let mut table = write_txn.open_table(TEST_TABLE).unwrap();
+                    table.insert(&i, &"backpressure").unwrap();
+                    drop(table);
+                    write_txn.commit().unwrap();
+                    max_size = max_size.max(fs::metadata(&wal_path).unwrap().len());
+                }
+                max_size
+            })
+        })
+        .collect();
+    let max_size = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .max()
+        .unwrap();
+
+    // Commits racing the throttled one can each add an entry past the hard limit
+    assert!(
+        max_size < HARD_LIMIT + 16 * 1024,
+        "WAL grew to {max_size} bytes"
+    );
+
+    let stats = db.wal_stats().unwrap().unwrap();
+    assert!(stats.throttled_commits > 0);
+    assert!(stats.checkpoints > 0);
+
+    for t in 0..THREADS {
+        let cf = db.column_family(&format!("cf_{t}")).unwrap();
+        let read_txn = cf.begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), COMMITS_PER_THREAD);
+    }
 }
 
 /// Test WAL truncation after successful checkpoint
@@ -525,10 +678,7 @@ fn test_wal_truncation_after_checkpoint() {
         let read_txn = cf.begin_read().unwrap();
         let table = read_txn.open_table(TEST_TABLE).unwrap();
         for i in 0..100 {
-            assert_eq!(
-                table.get(&i).unwrap().unwrap().value(),
-                "truncation_test"
-            );
+            assert_eq!(table.get(&i).unwrap().unwrap().value(), "truncation_test");
         }
     }
 }
@@ -586,3 +736,144 @@ fn test_cf_recreate_with_existing_wal() {
         }
     }
 }
+
+/// Test that eventual commits are visible at once but leave syncing the WAL to the next
+/// immediate commit
+#[test]
+fn test_eventual_commits_skip_wal_sync() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .pool_size(64)
+        .open(temp_file.path())
+        .unwrap();
+    let cf = db.create_column_family("cache", None).unwrap();
+
+    let commit = |key: u64, durability: Durability| {
+        let mut write_txn = cf.begin_write().unwrap();
+        write_txn.set_durability(durability).unwrap();
+        let mut table = write_txn.open_table(TEST_TABLE).unwrap();
+        table.insert(&key, &"cached").unwrap();
+        drop(table);
+        write_txn.commit().unwrap();
+    };
+
+    commit(0, Durability::Immediate);
+    let before = db.wal_stats().unwrap().unwrap();
+    assert!(before.wal_syncs >= 1);
+
+    for key in 1..=50 {
+        commit(key, Durability::Eventual);
+    }
+    let after_eventual = db.wal_stats().unwrap().unwrap();
+    assert_eq!(after_eventual.wal_syncs, before.wal_syncs);
+    assert_eq!(after_eventual.pending_entries, before.pending_entries + 50);
+    let read_txn = cf.begin_read().unwrap();
+    assert_eq!(read_txn.open_table(TEST_TABLE).unwrap().len().unwrap(), 51);
+    drop(read_txn);
+
+    // One sync covers the immediate commit and every eventual commit before it
+    commit(51, Durability::Immediate);
+    let after_immediate = db.wal_stats().unwrap().unwrap();
+    assert_eq!(after_immediate.wal_syncs, before.wal_syncs + 1);
+}
+
+/// Test that a write group commits every member with a single WAL sync, and that dropping
+/// a group aborts it
+#[test]
+fn test_write_group_commits_all_column_families() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db_path = temp_file.path().to_path_buf();
+
+    {
+        let db = ColumnFamilyDatabase::builder()
+            .pool_size(64)
+            .open(&db_path)
+            .unwrap();
+        let graph = db.create_column_family("graph", None).unwrap();
+        let properties = db.create_column_family("properties", None).unwrap();
+
+        let dropped = db.write_group(&[&properties, &graph]).unwrap();
+        let mut table = dropped
+            .transaction("graph")
+            .unwrap()
+            .open_table(TEST_TABLE)
+            .unwrap();
+        table.insert(&0, &"dropped").unwrap();
+        drop(table);
+        drop(dropped);
+
+        let before = db.wal_stats().unwrap().unwrap();
+        let group = db.write_group(&[&properties, &graph]).unwrap();
+        assert_eq!(
+            group.column_families().collect::<Vec<_>>(),
+            ["graph", "properties"]
+        );
+        assert!(group.transaction("missing").is_none());
+        for name in ["graph", "properties"] {
+            let mut table = group
+                .transaction(name)
+                .unwrap()
+                .open_table(TEST_TABLE)
+                .unwrap();
+            table.insert(&1, &"grouped").unwrap();
+        }
+        group.commit().unwrap();
+
+        let after = db.wal_stats().unwrap().unwrap();
+        assert_eq!(after.wal_syncs, before.wal_syncs + 1);
+        assert_eq!(after.pending_entries, before.pending_entries + 2);
+        assert_eq!(after.pending_entries_by_cf.get("graph"), Some(&1));
+        assert_eq!(after.pending_entries_by_cf.get("properties"), Some(&1));
+
+        // The members are ordinary transactions once the group is committed
+        let write_txn = graph.begin_write().unwrap();
+        write_txn
+            .open_table(TEST_TABLE)
+            .unwrap()
+            .insert(&2, &"single")
+            .unwrap();
+        write_txn.commit().unwrap();
+    }
+
+    // Recovered from the WAL on reopen
+    let db = ColumnFamilyDatabase::builder().open(&db_path).unwrap();
+    for (name, len) in [("graph", 2), ("properties", 1)] {
+        let read_txn = db.column_family(name).unwrap().begin_read().unwrap();
+        let table = read_txn.open_table(TEST_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), len, "{name}");
+        assert!(table.get(&0).unwrap().is_none(), "{name}");
+        assert_eq!(table.get(&1).unwrap().unwrap().value(), "grouped", "{name}");
+    }
+}
+
+/// Test that write groups reject duplicate members and databases without a WAL
+#[test]
+fn test_write_group_rejects_invalid_members() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = ColumnFamilyDatabase::builder()
+        .pool_size(64)
+        .open(temp_file.path())
+        .unwrap();
+    let cf = db.create_column_family("test_cf", None).unwrap();
+    let same_cf = db.column_family("test_cf").unwrap();
+    assert!(db.write_group(&[&cf, &same_cf]).is_err());
+
+    let other_file = NamedTempFile::new().unwrap();
+    let other_db = ColumnFamilyDatabase::builder()
+        .pool_size(64)
+        .open(other_file.path())
+        .unwrap();
+    let other_cf = other_db.create_column_family("other_cf", None).unwrap();
+    assert!(db.write_group(&[&cf, &other_cf]).is_err());
+
+    // The failed groups left no write transaction behind
+    cf.begin_write().unwrap().abort().unwrap();
+
+    let unlogged_file = NamedTempFile::new().unwrap();
+    let unlogged_db = ColumnFamilyDatabase::builder()
+        .without_wal()
+        .open(unlogged_file.path())
+        .unwrap();
+    let unlogged_cf = unlogged_db.create_column_family("test_cf", None).unwrap();
+    assert!(unlogged_db.write_group(&[&unlogged_cf]).is_err());
+}
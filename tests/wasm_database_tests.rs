@@ -0,0 +1,59 @@
+//! WasmDatabase API tests
+//!
+//! OPFS synchronous access is only available in workers, so these run in a dedicated
+//! worker:
+//!
+//! ```bash
+//! wasm-pack test --headless --chrome -- --test wasm_database_tests
+//! ```
+
+#![cfg(target_arch = "wasm32")]
+
+use manifold::wasm::WasmDatabase;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+wasm_bindgen_test_configure!(run_in_dedicated_worker);
+
+fn error_code(error: &JsValue) -> Option<String> {
+    js_sys::Reflect::get(error, &JsValue::from_str("code"))
+        .ok()?
+        .as_string()
+}
+
+#[wasm_bindgen_test]
+async fn test_column_family_not_found() {
+    let db = WasmDatabase::new("not_found_test.db".to_string(), 0)
+        .await
+        .unwrap();
+
+    assert!(!db.has_column_family("missing".to_string()));
+    let error = db.column_family("missing".to_string()).err().unwrap();
+    assert_eq!(error_code(&error).as_deref(), Some("NotFound"));
+    assert!(error.is_instance_of::<js_sys::Error>());
+
+    let error = db.delete_column_family("missing".to_string()).unwrap_err();
+    assert_eq!(error_code(&error).as_deref(), Some("NotFound"));
+
+    // Looking the column family up does not create it
+    assert!(!db.has_column_family("missing".to_string()));
+}
+
+#[wasm_bindgen_test]
+async fn test_column_family_already_exists() {
+    let db = WasmDatabase::new("already_exists_test.db".to_string(), 0)
+        .await
+        .unwrap();
+    if db.has_column_family("users".to_string()) {
+        db.delete_column_family("users".to_string()).unwrap();
+    }
+
+    db.create_column_family("users".to_string()).unwrap();
+    assert!(db.has_column_family("users".to_string()));
+    let error = db.create_column_family("users".to_string()).unwrap_err();
+    assert_eq!(error_code(&error).as_deref(), Some("AlreadyExists"));
+
+    db.delete_column_family("users".to_string()).unwrap();
+    assert!(!db.has_column_family("users".to_string()));
+    assert!(db.column_family("users".to_string()).is_err());
+}